@@ -0,0 +1,82 @@
+//! Codec comparison on real chunks.
+//!
+//! `cargo bench --bench codecs` runs [`benchmark_codecs`] with a handful of
+//! candidate pipelines and prints the table.  Set
+//! `SIMPLEZARR_BENCH_ARRAY=/path/to/store:array` to sample an existing V2
+//! array, and `SIMPLEZARR_BENCH_CHUNKS` to change how many chunks are
+//! sampled (default 16); otherwise a generated 1024x1024 `f8` array is used.
+
+use std::sync::Arc;
+
+use simplezarr::benchmark::benchmark_codecs;
+use simplezarr::builder::{ArrayBuilder, CompressionGoal, gzip, lz4, suggest_codecs, zstd};
+use simplezarr::store::LocalBackend;
+use simplezarr::types::{DataType, ZarrVectorValue};
+use simplezarr::{UnifiedZarrArray, v2};
+
+async fn generated(dir: &std::path::Path) -> UnifiedZarrArray {
+    let store = Arc::new(LocalBackend::new(dir));
+    let array = ArrayBuilder::new([1024, 1024])
+        .chunks([256, 256])
+        .dtype(DataType::Float64)
+        .codec(zstd(3))
+        .build(store, "bench")
+        .await
+        .expect("create bench array");
+    let values = (0..1024 * 1024)
+        .map(|i| {
+            let (row, col) = ((i / 1024) as f64, (i % 1024) as f64);
+            (row * 0.013).sin() * (col * 0.007).cos() * 100.0
+        })
+        .collect();
+    array
+        .set_region(&array.full_region(), &ZarrVectorValue::VFloat64(values))
+        .await
+        .expect("write bench array");
+    array
+}
+
+#[tokio::main]
+async fn main() {
+    let scratch = std::env::temp_dir().join(format!("simplezarr-codecs-{}", std::process::id()));
+    let array = match std::env::var("SIMPLEZARR_BENCH_ARRAY") {
+        Ok(spec) => {
+            let (root, path) = spec.split_once(':').unwrap_or((&spec, ""));
+            v2::open(Arc::new(LocalBackend::new(root)), path)
+                .await
+                .expect("open SIMPLEZARR_BENCH_ARRAY")
+        }
+        Err(_) => generated(&scratch).await,
+    };
+    let sample = std::env::var("SIMPLEZARR_BENCH_CHUNKS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(16);
+
+    let md = &array.metadata;
+    let mut candidates = vec![Vec::new(), vec![lz4(1)], vec![zstd(3)], vec![gzip(5)]];
+    for goal in [
+        CompressionGoal::Speed,
+        CompressionGoal::Balanced,
+        CompressionGoal::Size,
+    ] {
+        let suggested = suggest_codecs(md.data_type, &md.chunk_shape, goal);
+        if !candidates
+            .iter()
+            .any(|c| format!("{c:?}") == format!("{suggested:?}"))
+        {
+            candidates.push(suggested);
+        }
+    }
+
+    let report = benchmark_codecs(&array, &candidates, sample)
+        .await
+        .expect("benchmark codecs");
+    println!(
+        "shape {:?}, chunks {:?}, {:?}",
+        md.shape, md.chunk_shape, md.data_type
+    );
+    print!("{report}");
+
+    let _ = std::fs::remove_dir_all(scratch);
+}
@@ -0,0 +1,95 @@
+//! Load throughput.
+//!
+//! `cargo bench --bench load` writes a 2048x2048 `f8` array with zstd-compressed
+//! 256x256 chunks to a temporary directory and times whole-array and windowed
+//! reads.  Set `SIMPLEZARR_BENCH_ARRAY=/path/to/store:array` to time an existing
+//! V2 array instead.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use simplezarr::builder::{ArrayBuilder, zstd};
+use simplezarr::store::LocalBackend;
+use simplezarr::types::{DataType, ZarrVectorValue};
+use simplezarr::{UnifiedZarrArray, v2};
+
+const ROUNDS: u32 = 5;
+
+async fn generated(dir: &std::path::Path) -> UnifiedZarrArray {
+    let store = Arc::new(LocalBackend::new(dir));
+    let array = ArrayBuilder::new([2048, 2048])
+        .chunks([256, 256])
+        .dtype(DataType::Float64)
+        .codec(zstd(3))
+        .build(store, "bench")
+        .await
+        .expect("create bench array");
+    let values = (0..2048 * 2048).map(|i| (i % 4096) as f64 * 0.25).collect();
+    array
+        .set_region(&array.full_region(), &ZarrVectorValue::VFloat64(values))
+        .await
+        .expect("write bench array");
+    array
+}
+
+/// Run `f` `ROUNDS` times and report the best time as MB/s of decoded data.
+async fn time<F, Fut, T>(name: &str, bytes: usize, mut f: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = T>,
+{
+    let mut best = Duration::MAX;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        std::hint::black_box(f().await);
+        best = best.min(start.elapsed());
+    }
+    let mb_per_s = bytes as f64 / 1e6 / best.as_secs_f64();
+    println!(
+        "{name:<24} {:>10.2} ms {mb_per_s:>10.1} MB/s",
+        best.as_secs_f64() * 1e3
+    );
+}
+
+#[tokio::main]
+async fn main() {
+    let scratch = std::env::temp_dir().join(format!("simplezarr-bench-{}", std::process::id()));
+    let array = match std::env::var("SIMPLEZARR_BENCH_ARRAY") {
+        Ok(spec) => {
+            let (root, path) = spec.split_once(':').unwrap_or((&spec, ""));
+            v2::open(Arc::new(LocalBackend::new(root)), path)
+                .await
+                .expect("open SIMPLEZARR_BENCH_ARRAY")
+        }
+        Err(_) => generated(&scratch).await,
+    };
+    let md = &array.metadata;
+    let item = md.data_type.byte_size().unwrap_or(8);
+    let total = md.shape.iter().product::<usize>() * item;
+    println!(
+        "shape {:?}, chunks {:?}, {:?}",
+        md.shape, md.chunk_shape, md.data_type
+    );
+
+    time("load_value", total, || array.load_value()).await;
+    time("load (f64)", total, || array.load()).await;
+    time("load_f32", total, || array.load_f32()).await;
+
+    // One chunk-sized window from the middle of the array.
+    let window: Vec<_> = md
+        .shape
+        .iter()
+        .zip(&md.chunk_shape)
+        .map(|(&s, &c)| {
+            let start = (s / 2).saturating_sub(c / 2);
+            start..(start + c).min(s)
+        })
+        .collect();
+    let window_bytes = window.iter().map(|r| r.len()).product::<usize>() * item;
+    time("read_region (window)", window_bytes, || {
+        array.read_region(&window)
+    })
+    .await;
+
+    let _ = std::fs::remove_dir_all(scratch);
+}
@@ -0,0 +1,40 @@
+//! Every codec's bounded decode on arbitrary input.
+//!
+//! The first byte picks the codec and the rest is the stored chunk.  Decodes
+//! must fail cleanly, and never produce (or allocate for) more than the
+//! limit, so a hostile chunk can't exhaust memory.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use serde_json::json;
+use simplezarr::codecs::parse_codec;
+
+/// Largest decoded chunk accepted, as a chunk read passes its expected size.
+const LIMIT: usize = 1 << 20;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&selector, chunk)) = data.split_first() else {
+        return;
+    };
+    let configs = [
+        json!({"name": "bytes", "configuration": {"endian": "little"}}),
+        json!({"name": "gzip", "configuration": {"level": 5}}),
+        json!({"name": "zlib", "configuration": {"level": 5}}),
+        json!({"name": "zstd", "configuration": {"level": 3}}),
+        json!({"name": "lz4", "configuration": {"acceleration": 1}}),
+        json!({"name": "numcodecs.snappy"}),
+        json!({"name": "numcodecs.brotli"}),
+        json!({"name": "blosc", "configuration": {"cname": "lz4", "clevel": 5, "shuffle": "shuffle"}}),
+        json!({"name": "blosc", "configuration": {"cname": "zstd", "clevel": 5, "shuffle": "bitshuffle"}}),
+        json!({
+            "name": "numcodecs.fixedscaleoffset",
+            "configuration": {"offset": 1.5, "scale": 10.0, "dtype": "<f8", "astype": "<i2"},
+        }),
+    ];
+    let codec = parse_codec(&configs[usize::from(selector) % configs.len()])
+        .expect("fuzz codec configuration");
+    if let Ok(decoded) = futures::executor::block_on(codec.decode_bounded(chunk, LIMIT)) {
+        assert!(decoded.len() <= LIMIT.max(chunk.len()));
+    }
+});
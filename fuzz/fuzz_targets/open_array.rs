@@ -0,0 +1,56 @@
+//! Opening a V2 array from an arbitrary `.zarray` and reading its first
+//! chunk from arbitrary bytes.
+//!
+//! Input up to the first NUL byte is the `.zarray`; the rest, if any, is
+//! stored as the first chunk.  Opening and reading must fail cleanly, and a
+//! hostile grid or chunk shape must be rejected rather than exhaust memory.
+
+#![no_main]
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use libfuzzer_sys::fuzz_target;
+use object_store::memory::InMemory;
+use simplezarr::config::SimplezarrConfig;
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+use simplezarr::v2;
+
+/// Largest chunk read, as a service opening untrusted stores would cap it
+/// before reading: missing chunks are filled at their full size.
+const MAX_CHUNK_BYTES: usize = 1 << 20;
+
+fuzz_target!(|data: &[u8]| {
+    let (zarray, chunk) = match data.iter().position(|&b| b == 0) {
+        Some(nul) => (&data[..nul], Some(&data[nul + 1..])),
+        None => (data, None),
+    };
+    futures::executor::block_on(async {
+        let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+        let zarray_path = store.join("a", ".zarray");
+        store
+            .put(&zarray_path, Bytes::copy_from_slice(zarray))
+            .await
+            .unwrap();
+        let config = Arc::new(SimplezarrConfig::builder().max_chunks(1 << 16).build());
+        let Ok(array) = v2::open_with_config(store.clone(), "a", config).await else {
+            return;
+        };
+        let md = &array.metadata;
+        let Some(key) = md.keys.first() else {
+            return;
+        };
+        let elements: usize = md.chunk_shape.iter().product();
+        if elements.saturating_mul(md.data_type.byte_size().unwrap_or(1)) > MAX_CHUNK_BYTES {
+            return;
+        }
+        if let Some(chunk) = chunk {
+            let chunk_path = store.join("a", key);
+            store
+                .put(&chunk_path, Bytes::copy_from_slice(chunk))
+                .await
+                .unwrap();
+        }
+        let _ = array.get_chunk(&vec![0; md.shape.len()]).await;
+    });
+});
@@ -0,0 +1,12 @@
+//! Codec configurations from arbitrary JSON.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use simplezarr::codecs::parse_codec;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(value) = serde_json::from_slice::<serde_json::Value>(data) {
+        let _ = parse_codec(&value);
+    }
+});
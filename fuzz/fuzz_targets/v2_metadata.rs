@@ -0,0 +1,15 @@
+//! `.zarray` and `.zmetadata` parsing must reject malformed documents with
+//! an error, never a panic.  Grids are capped as a service opening untrusted
+//! stores would cap them.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use simplezarr::metadata::v2::{ZarrConsolidatedMetadata, ZarrV2Metadata};
+
+const MAX_CHUNKS: usize = 1 << 20;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = ZarrV2Metadata::parse_with_max_chunks(data, MAX_CHUNKS);
+    let _ = ZarrConsolidatedMetadata::parse_with_max_chunks(data, MAX_CHUNKS);
+});
@@ -0,0 +1,278 @@
+//! Reading the same region of several arrays at once.
+//!
+//! Derived quantities such as wind speed from `u` and `v` need the same
+//! region of arrays that share a grid.  [`read_region_multi`] checks that
+//! the arrays have the same shape and chunk shape, then fetches their
+//! chunks together, chunk by chunk across the arrays, under one
+//! concurrency limit:
+//!
+//! ```ignore
+//! let data = aligned::read_region_multi(&[&u, &v], &region).await?;
+//! let speed: Vec<f64> = data["u"].iter().zip(data["v"].iter())
+//!     .map(|(u, v)| u.hypot(*v))
+//!     .collect();
+//! ```
+//!
+//! A group can also carry such a quantity as a derived variable, computed
+//! on each read from its member arrays:
+//!
+//! ```ignore
+//! let group = group.with_derived("speed", &["u", "v"], |uv| uv[0].hypot(uv[1]))?;
+//! let speed = group.read_variable("speed", &region).await?;
+//! ```
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use futures::StreamExt;
+
+use crate::array::{
+    ArrayData, UnifiedZarrArray, chunk_indices_for_region, collect_chunks, merge_chunks,
+    validate_region,
+};
+use crate::error::{ZarrError, ZarrResult};
+use crate::group::UnifiedZarrGroup;
+use crate::types::ZarrVectorValue;
+
+// ---------------------------------------------------------------------------
+// Aligned reads
+// ---------------------------------------------------------------------------
+
+/// Read `region` of each of `arrays` as `f64`, keyed by array name (the
+/// last component of its path).
+///
+/// The arrays must have the same shape and chunk shape, and distinct
+/// names.  Chunks are fetched at most the first array's `max_concurrency`
+/// at a time, with the arrays' chunks for each grid cell requested
+/// together.  A failed chunk fails the read as the first array's
+/// [`fail_fast`](crate::config::SimplezarrConfig::fail_fast) says.  Each
+/// result is laid out as
+/// [`read_region`](UnifiedZarrArray::read_region) would for its array.
+pub async fn read_region_multi(
+    arrays: &[&UnifiedZarrArray],
+    region: &[Range<usize>],
+) -> ZarrResult<BTreeMap<String, ArrayData<Vec<f64>>>> {
+    let mut named: Vec<(&str, &UnifiedZarrArray)> = Vec::with_capacity(arrays.len());
+    for &array in arrays {
+        let name = array.path.rsplit('/').next().unwrap_or_default();
+        if named.iter().any(|&(n, _)| n == name) {
+            return Err(ZarrError::Other(format!(
+                "Array name {name} given twice for an aligned read"
+            )));
+        }
+        named.push((name, array));
+    }
+    check_aligned(&named)?;
+    let data = read_aligned(arrays, region).await?;
+    Ok(named
+        .into_iter()
+        .map(|(name, _)| name.to_string())
+        .zip(data)
+        .collect())
+}
+
+/// Fail unless every array has the first one's shape and chunk shape.
+fn check_aligned(arrays: &[(&str, &UnifiedZarrArray)]) -> ZarrResult<()> {
+    let Some(&(first_name, first)) = arrays.first() else {
+        return Ok(());
+    };
+    let first = &first.metadata;
+    for (name, array) in &arrays[1..] {
+        let metadata = &array.metadata;
+        if metadata.shape != first.shape || metadata.chunk_shape != first.chunk_shape {
+            return Err(ZarrError::Metadata(format!(
+                "Array {name} has shape {:?} and chunks {:?}, expected {:?} and {:?} \
+                 to align with {first_name}",
+                metadata.shape, metadata.chunk_shape, first.shape, first.chunk_shape
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// `region` of each of `arrays`, which share a grid, in the same order.
+async fn read_aligned(
+    arrays: &[&UnifiedZarrArray],
+    region: &[Range<usize>],
+) -> ZarrResult<Vec<ArrayData<Vec<f64>>>> {
+    let Some(first) = arrays.first() else {
+        return Ok(Vec::new());
+    };
+    validate_region(&first.metadata.shape, region)?;
+
+    let keys = chunk_indices_for_region(&first.metadata.chunk_shape, region);
+    let fetches = keys
+        .iter()
+        .flat_map(|key| (0..arrays.len()).map(move |i| (i, key)));
+    let fetches = futures::stream::iter(fetches)
+        .map(|(i, key)| async move {
+            let chunk = arrays[i].get_chunk(key).await?;
+            Ok::<_, ZarrError>((i, key.clone(), chunk))
+        })
+        .buffer_unordered(first.config.max_concurrency.max(1));
+    let fetched: Vec<(usize, Vec<usize>, ZarrVectorValue)> =
+        collect_chunks(fetches, first.config.fail_fast).await?;
+
+    let mut chunks: Vec<Vec<(Vec<usize>, ZarrVectorValue)>> = vec![Vec::new(); arrays.len()];
+    for (i, key, chunk) in fetched {
+        chunks[i].push((key, chunk));
+    }
+    arrays
+        .iter()
+        .zip(&chunks)
+        .map(|(array, chunks)| {
+            let data = merge_chunks(&array.metadata, region, chunks)?;
+            Ok(array.region_data(region, data))
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Derived variables
+// ---------------------------------------------------------------------------
+
+/// An elementwise function of a derived variable's inputs, given in the
+/// order they were registered.
+pub type DerivedFn = Arc<dyn Fn(&[f64]) -> f64 + Send + Sync>;
+
+/// A variable of a group computed from its arrays; see
+/// [`UnifiedZarrGroup::with_derived`].
+#[derive(Clone)]
+pub struct DerivedVariable {
+    /// Names of the arrays it is computed from.
+    pub inputs: Vec<String>,
+    f: DerivedFn,
+}
+
+impl std::fmt::Debug for DerivedVariable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DerivedVariable")
+            .field("inputs", &self.inputs)
+            .finish_non_exhaustive()
+    }
+}
+
+impl UnifiedZarrGroup {
+    /// Register `name` as a variable computed by `f` from the arrays
+    /// `inputs`, element by element: `f` gets one value per input, in
+    /// order.  Nothing is read until the variable is.
+    ///
+    /// The inputs must be arrays of the group (not other derived
+    /// variables) sharing a grid and memory order, and `name` mustn't be
+    /// taken.
+    pub fn with_derived(
+        mut self,
+        name: impl Into<String>,
+        inputs: &[&str],
+        f: impl Fn(&[f64]) -> f64 + Send + Sync + 'static,
+    ) -> ZarrResult<Self> {
+        let name = name.into();
+        if self.arrays.contains_key(&name) || self.derived.contains_key(&name) {
+            return Err(ZarrError::Other(format!(
+                "Variable {name} already exists in group"
+            )));
+        }
+        let variable = DerivedVariable {
+            inputs: inputs.iter().map(|s| s.to_string()).collect(),
+            f: Arc::new(f),
+        };
+        self.derived_inputs(&variable)?;
+        self.derived.insert(name, variable);
+        Ok(self)
+    }
+
+    pub fn get_derived(&self, name: &str) -> Option<&DerivedVariable> {
+        self.derived.get(name)
+    }
+
+    /// Names of the stored arrays and derived variables, sorted.
+    pub fn variable_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .arrays
+            .keys()
+            .chain(self.derived.keys())
+            .map(String::as_str)
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// The shape of an array or derived variable.
+    pub fn variable_shape(&self, name: &str) -> Option<&[usize]> {
+        let array = match self.derived.get(name) {
+            Some(variable) => self.arrays.get(variable.inputs.first()?)?,
+            None => self.arrays.get(name)?,
+        };
+        Some(&array.metadata.shape)
+    }
+
+    /// Read `region` of an array or derived variable as `f64`.  Derived
+    /// variables read their inputs with [`read_region_multi`]'s aligned
+    /// fetch and take the first input's layout.
+    pub async fn read_variable(
+        &self,
+        name: &str,
+        region: &[Range<usize>],
+    ) -> ZarrResult<ArrayData<Vec<f64>>> {
+        let Some(variable) = self.derived.get(name) else {
+            let array = self
+                .arrays
+                .get(name)
+                .ok_or_else(|| ZarrError::NotFound(format!("No variable {name} in group")))?;
+            return array.read_region(region).await;
+        };
+        let arrays = self.derived_inputs(variable)?;
+        let inputs = read_aligned(&arrays, region).await?;
+        let mut row = vec![0.0; inputs.len()];
+        let values = (0..inputs[0].len())
+            .map(|i| {
+                for (value, input) in row.iter_mut().zip(&inputs) {
+                    *value = input[i];
+                }
+                (variable.f)(&row)
+            })
+            .collect();
+        let first = &inputs[0];
+        Ok(ArrayData::new(values, first.shape.clone(), first.order))
+    }
+
+    /// Load a whole array or derived variable as `f64`; see
+    /// [`read_variable`](Self::read_variable).
+    pub async fn load_variable(&self, name: &str) -> ZarrResult<ArrayData<Vec<f64>>> {
+        let shape = self
+            .variable_shape(name)
+            .ok_or_else(|| ZarrError::NotFound(format!("No variable {name} in group")))?;
+        let region: Vec<Range<usize>> = shape.iter().map(|&d| 0..d).collect();
+        self.read_variable(name, &region).await
+    }
+
+    /// The input arrays of `variable`, checked to be aligned element for
+    /// element.
+    fn derived_inputs(&self, variable: &DerivedVariable) -> ZarrResult<Vec<&UnifiedZarrArray>> {
+        let named = variable
+            .inputs
+            .iter()
+            .map(|input| {
+                let array = self
+                    .arrays
+                    .get(input)
+                    .ok_or_else(|| ZarrError::NotFound(format!("No array {input} in group")))?;
+                Ok((input.as_str(), array))
+            })
+            .collect::<ZarrResult<Vec<_>>>()?;
+        check_aligned(&named)?;
+        let Some(&(first_name, first)) = named.first() else {
+            return Err(ZarrError::Other("Derived variable has no inputs".into()));
+        };
+        if let Some((name, _)) = named
+            .iter()
+            .find(|(_, array)| array.metadata.order != first.metadata.order)
+        {
+            return Err(ZarrError::Metadata(format!(
+                "Array {name} has a different memory order from {first_name}"
+            )));
+        }
+        Ok(named.into_iter().map(|(_, array)| array).collect())
+    }
+}
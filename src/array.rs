@@ -1,12 +1,32 @@
+use std::collections::VecDeque;
 use std::future::Future;
+use std::ops::{Deref, Range};
+use std::panic::AssertUnwindSafe;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
 
-use crate::codecs::{AnyCodec, apply_codec_pipeline};
+use bytes::Bytes;
+use futures::future::MaybeDone;
+use futures::{FutureExt, Stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::attributes::Attributes;
+use crate::cache::ChunkCache;
+use crate::capabilities::FeatureId;
+use crate::codecs::{AnyCodec, CodecPipeline};
+use crate::config::SimplezarrConfig;
+use crate::diagnostics::{ChunkDiagnostics, ChunkRecord, codec_label};
 use crate::error::{ZarrError, ZarrResult};
-use crate::store::StorageBackend;
+use crate::metadata::v2::{ZarrV2Metadata, chunk_key_with_separator, list_keys_with_separator};
+use crate::options::{ArrayReadOptions, ArrayWriteOptions, BoolEncoding};
+use crate::runtime;
+use crate::store::{StorageBackend, get_with_etag_retry, get_with_retry, head_with_retry};
 use crate::types::{
-    ArrayOrder, DataType, Endian, FillValue, ZarrVectorValue, bytes_to_zarr_vector, fill_chunk,
+    ArrayOrder, DataType, Endian, FillValue, ZarrValue, ZarrVectorValue, fill_chunk,
+    saturating_f32, scatter, scatter_f64,
 };
 
 // ---------------------------------------------------------------------------
@@ -42,15 +62,148 @@ pub struct UnifiedMetadata {
     pub shape: Vec<usize>,
     pub chunk_shape: Vec<usize>,
     pub data_type: DataType,
+    /// Byte order of stored elements; `NotApplicable` for one-byte types.
+    pub byte_order: Endian,
     pub fill_value: FillValue,
     pub order: ArrayOrder,
     pub zarr_format: u32,
     pub compression_info: CompressionInfo,
-    pub attributes: Option<serde_json::Map<String, serde_json::Value>>,
+    pub attributes: Option<Attributes>,
     pub dimension_names: Option<Vec<Option<String>>>,
+    /// V3 extension fields flagged `must_understand: false`, kept as read so
+    /// they survive a rewrite.  Always empty for V2.
+    pub extensions: serde_json::Map<String, serde_json::Value>,
+    /// Separator between chunk indices in the storage keys, `.` or `/`.
+    pub dimension_separator: char,
     pub keys: Vec<String>,
 }
 
+impl UnifiedMetadata {
+    /// Dimensions of length 1, in order.
+    pub fn singleton_axes(&self) -> Vec<usize> {
+        (0..self.shape.len())
+            .filter(|&d| self.shape[d] == 1)
+            .collect()
+    }
+
+    /// The shape without its length-1 dimensions, like NumPy's `squeeze`.
+    /// Dropping them doesn't move any element, so flat data keeps its
+    /// layout.
+    pub fn squeeze(&self) -> Vec<usize> {
+        self.shape.iter().copied().filter(|&d| d != 1).collect()
+    }
+
+    /// The codec list chunks are decoded with, as `open` builds it.  Fails
+    /// for codecs this build can't run.
+    pub fn codecs(&self) -> ZarrResult<Vec<AnyCodec>> {
+        match &self.compression_info {
+            CompressionInfo::V2Compression {
+                compressor,
+                filters,
+            } => crate::v2::v2_codecs(compressor.as_ref(), filters.as_ref(), self.byte_order),
+            CompressionInfo::V3Codecs(codecs) => Ok(codecs.clone()),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ArrayDescriptor
+// ---------------------------------------------------------------------------
+
+/// Everything needed to reopen an array on another process: its metadata
+/// document (which includes the codec configuration), path and read options.
+///
+/// Obtain one with [`UnifiedZarrArray::descriptor`], ship it as JSON and
+/// rebuild the handle with [`UnifiedZarrArray::from_descriptor`].  The store
+/// and [`SimplezarrConfig`] are supplied by the receiver.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArrayDescriptor {
+    pub zarr_format: u32,
+    pub path: String,
+    pub metadata: serde_json::Value,
+    /// The array's `.zattrs`, if it had any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<Attributes>,
+    #[serde(default)]
+    pub read_options: ArrayReadOptions,
+    /// The ETag of the metadata document, if the store reported one; see
+    /// [`UnifiedZarrArray::refresh`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata_etag: Option<String>,
+}
+
+// ---------------------------------------------------------------------------
+// ArrayData
+// ---------------------------------------------------------------------------
+
+/// Values read from an array together with the layout needed to interpret
+/// them: the shape of the region read and the memory order the values are
+/// flattened in.
+///
+/// `ArrayData` dereferences to its values, so slices and vector methods
+/// apply directly, and compares equal to a bare value container holding
+/// the same values; [`into_inner`](Self::into_inner) drops the layout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrayData<T> {
+    /// The values, flattened in `order`.
+    pub data: T,
+    /// Extent of each dimension.
+    pub shape: Vec<usize>,
+    pub order: ArrayOrder,
+}
+
+impl<T> ArrayData<T> {
+    pub fn new(data: T, shape: Vec<usize>, order: ArrayOrder) -> Self {
+        Self { data, shape, order }
+    }
+
+    /// The flat values, without the layout.
+    pub fn into_inner(self) -> T {
+        self.data
+    }
+
+    /// Apply `f` to the values, keeping the layout.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> ArrayData<U> {
+        ArrayData {
+            data: f(self.data),
+            shape: self.shape,
+            order: self.order,
+        }
+    }
+}
+
+impl<E> ArrayData<Vec<E>> {
+    /// The element at `indices` (one per dimension, relative to the region
+    /// read), or `None` if they fall outside it.
+    pub fn get(&self, indices: &[usize]) -> Option<&E> {
+        if indices.len() != self.shape.len() || indices.iter().zip(&self.shape).any(|(i, s)| i >= s)
+        {
+            return None;
+        }
+        self.data.get(linear_index(&self.shape, self.order, indices))
+    }
+}
+
+/// Compares the values alone, so results can be checked against a plain
+/// vector.
+impl<T: PartialEq> PartialEq<T> for ArrayData<T> {
+    fn eq(&self, other: &T) -> bool {
+        self.data == *other
+    }
+}
+
+impl<T> Deref for ArrayData<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.data
+    }
+}
+
+/// A region and the values read from it, as yielded by
+/// [`UnifiedZarrArray::iter_windows`].
+pub type Window = (Vec<Range<usize>>, ArrayData<Vec<f64>>);
+
 // ---------------------------------------------------------------------------
 // UnifiedZarrArray
 // ---------------------------------------------------------------------------
@@ -59,7 +212,18 @@ pub struct UnifiedZarrArray {
     pub metadata: UnifiedMetadata,
     pub(crate) store: Arc<dyn StorageBackend>,
     pub(crate) path: String,
-    pub(crate) codecs: Vec<AnyCodec>,
+    pub(crate) document: serde_json::Value,
+    /// The codec list, or the codec this build can't run.  Opening doesn't
+    /// fail on it, so metadata and raw chunks stay readable.
+    pub(crate) codecs: Result<Vec<AnyCodec>, FeatureId>,
+    pub(crate) config: Arc<SimplezarrConfig>,
+    pub(crate) cache: Option<Arc<ChunkCache>>,
+    pub(crate) read_options: ArrayReadOptions,
+    pub(crate) write_options: ArrayWriteOptions,
+    pub(crate) diagnostics: Option<Arc<ChunkDiagnostics>>,
+    /// The ETag of `.zarray` when it was last read, if the store reported
+    /// one; see [`refresh`](Self::refresh).
+    pub(crate) metadata_etag: Option<String>,
 }
 
 impl Clone for UnifiedZarrArray {
@@ -68,7 +232,14 @@ impl Clone for UnifiedZarrArray {
             metadata: self.metadata.clone(),
             store: self.store.clone(),
             path: self.path.clone(),
+            document: self.document.clone(),
             codecs: self.codecs.clone(),
+            config: self.config.clone(),
+            cache: self.cache.clone(),
+            read_options: self.read_options.clone(),
+            write_options: self.write_options.clone(),
+            diagnostics: self.diagnostics.clone(),
+            metadata_etag: self.metadata_etag.clone(),
         }
     }
 }
@@ -82,39 +253,731 @@ impl std::fmt::Debug for UnifiedZarrArray {
 }
 
 impl UnifiedZarrArray {
-    /// Fetch a single chunk by its multi-dimensional indices.
-    pub async fn get_chunk(&self, key: &[usize]) -> ZarrResult<ZarrVectorValue> {
+    pub(crate) fn new(
+        metadata: UnifiedMetadata,
+        store: Arc<dyn StorageBackend>,
+        path: String,
+        document: serde_json::Value,
+        codecs: Result<Vec<AnyCodec>, FeatureId>,
+        config: Arc<SimplezarrConfig>,
+    ) -> Self {
+        let cache = (config.chunk_cache_bytes > 0)
+            .then(|| Arc::new(ChunkCache::new(config.chunk_cache_bytes)));
+        let codecs = codecs.map(|codecs| {
+            codecs
+                .into_iter()
+                .map(|codec| match codec {
+                    AnyCodec::Blosc(blosc) => AnyCodec::Blosc(blosc.with_config(&config)),
+                    other => other,
+                })
+                .collect()
+        });
+        Self {
+            metadata,
+            store,
+            path,
+            document,
+            codecs,
+            config,
+            cache,
+            read_options: ArrayReadOptions::default(),
+            write_options: ArrayWriteOptions::default(),
+            diagnostics: None,
+            metadata_etag: None,
+        }
+    }
+
+    /// Replace the read options used for subsequent chunk decodes.
+    ///
+    /// The chunk cache is reset, since cached chunks were decoded under the
+    /// previous options.
+    pub fn with_read_options(mut self, options: ArrayReadOptions) -> Self {
+        self.read_options = options;
+        if let Some(cache) = &self.cache {
+            self.cache = Some(Arc::new(ChunkCache::new(cache.capacity_bytes())));
+        }
+        self
+    }
+
+    pub fn read_options(&self) -> &ArrayReadOptions {
+        &self.read_options
+    }
+
+    /// Address chunks with `separator` (`.` or `/`) between their indices,
+    /// whatever the metadata declares.  The chunk cache is reset.
+    pub fn with_dimension_separator(mut self, separator: char) -> ZarrResult<Self> {
+        if separator != '.' && separator != '/' {
+            return Err(ZarrError::Metadata(format!(
+                "Invalid dimension_separator: {separator:?}"
+            )));
+        }
+        let md = &mut self.metadata;
+        md.dimension_separator = separator;
+        md.keys = list_keys_with_separator(&md.shape, &md.chunk_shape, separator);
+        // Descriptors and rewritten metadata keep the layout.
+        self.document["dimension_separator"] = separator.to_string().into();
+        if let Some(cache) = &self.cache {
+            self.cache = Some(Arc::new(ChunkCache::new(cache.capacity_bytes())));
+        }
+        Ok(self)
+    }
+
+    /// Switch to nested (`0/0`) chunk keys if the store uses them without
+    /// the metadata saying so, as zarr-python's `NestedDirectoryStore` did
+    /// before `dimension_separator` existed.
+    ///
+    /// Only arrays of two or more dimensions that don't declare a separator
+    /// are probed: the layout is nested if the first chunk is missing as
+    /// `0.0` but present as `0/0`.  That costs up to two requests, so opening
+    /// an array doesn't do it.
+    pub async fn detect_dimension_separator(self) -> ZarrResult<Self> {
+        let md = &self.metadata;
+        let undeclared = self.document.get("dimension_separator").is_none();
+        let Some(first) = md.keys.first().filter(|_| undeclared && md.shape.len() > 1) else {
+            return Ok(self);
+        };
+        if self
+            .store
+            .head(&self.store.join(&self.path, first))
+            .await?
+            .is_some()
+        {
+            return Ok(self);
+        }
+        let nested = chunk_key_with_separator(&vec![0; md.shape.len()], '/');
+        if self
+            .store
+            .head(&self.store.join(&self.path, &nested))
+            .await?
+            .is_none()
+        {
+            return Ok(self);
+        }
+        self.with_dimension_separator('/')
+    }
+
+    /// The configuration this array was opened with.
+    pub fn config(&self) -> &SimplezarrConfig {
+        &self.config
+    }
+
+    /// The codec this build can't run, if any.  Such arrays open normally,
+    /// but decoding or encoding chunks fails with
+    /// [`ZarrError::UnsupportedFeature`]; [`get_chunk_raw`](Self::get_chunk_raw)
+    /// still works.
+    pub fn unsupported_codec(&self) -> Option<&FeatureId> {
+        self.codecs.as_ref().err()
+    }
+
+    /// The codec list, or the error for the codec that can't be run.
+    pub(crate) fn codecs(&self) -> ZarrResult<&[AnyCodec]> {
+        self.codecs
+            .as_deref()
+            .map_err(|feature| ZarrError::UnsupportedFeature(feature.clone()))
+    }
+
+    /// Read the array's `.zattrs` into `metadata.attributes`, and xarray's
+    /// `_ARRAY_DIMENSIONS` into `metadata.dimension_names`.
+    ///
+    /// `open` skips `.zattrs` to save a request; arrays from a consolidated
+    /// group already have them.
+    pub async fn load_attributes(&mut self) -> ZarrResult<()> {
+        let attributes =
+            crate::v2::read_attributes(self.store.as_ref(), &self.path, &self.config).await?;
+        self.metadata.dimension_names = attributes.as_ref().and_then(|attrs| {
+            crate::v2::dimension_names_from_attributes(attrs, self.metadata.shape.len())
+        });
+        self.metadata.attributes = attributes;
+        Ok(())
+    }
+
+    /// The ETag of the `.zarray` this handle was built from, if the store
+    /// reported one.
+    pub fn metadata_etag(&self) -> Option<&str> {
+        self.metadata_etag.as_deref()
+    }
+
+    /// Re-read the array's `.zarray` if it was rewritten since it was read,
+    /// returning whether it changed.
+    ///
+    /// With an ETag from when it was read, an unchanged array costs one
+    /// `head` request; otherwise the document is fetched and compared.  A
+    /// changed array takes the new shape, chunks and codecs, starts with an
+    /// empty chunk cache and has its attributes read again if they had
+    /// been; read and write options stay.  So does a chunk layout set with
+    /// [`with_dimension_separator`](Self::with_dimension_separator), unless
+    /// the new document declares one.
+    pub async fn refresh(&mut self) -> ZarrResult<bool> {
+        if self.metadata.zarr_format != 2 {
+            return Err(ZarrError::UnsupportedFeature(FeatureId::ZarrFormat(
+                self.metadata.zarr_format,
+            )));
+        }
+        let zarray_path = self.store.join(&self.path, ".zarray");
+        if let Some(etag) = &self.metadata_etag {
+            let meta = head_with_retry(self.store.as_ref(), &zarray_path, &self.config).await?;
+            if meta.and_then(|meta| meta.etag).as_ref() == Some(etag) {
+                return Ok(false);
+            }
+        }
+        let (bytes, etag) = get_with_etag_retry(self.store.as_ref(), &zarray_path, &self.config)
+            .await?
+            .ok_or_else(|| ZarrError::NotFound(format!("No .zarray at {}", self.path)))?;
+        let md = ZarrV2Metadata::parse_with_max_chunks(&bytes, self.config.max_chunks)?;
+        let mut refreshed = crate::v2::array_from_metadata(
+            self.store.clone(),
+            self.path.clone(),
+            &md,
+            None,
+            self.config.clone(),
+        )?;
+        if md.document.get("dimension_separator").is_none()
+            && self.document.get("dimension_separator").is_some()
+        {
+            refreshed = refreshed.with_dimension_separator(self.metadata.dimension_separator)?;
+        }
+        if refreshed.document == self.document {
+            self.metadata_etag = etag;
+            return Ok(false);
+        }
+        refreshed.read_options = self.read_options.clone();
+        refreshed.write_options = self.write_options.clone();
+        refreshed.diagnostics = self.diagnostics.clone();
+        refreshed.metadata_etag = etag;
+        let reload_attributes = self.metadata.attributes.is_some();
+        *self = refreshed;
+        if reload_attributes {
+            self.load_attributes().await?;
+        }
+        Ok(true)
+    }
+
+    /// The decoded-chunk cache, if `chunk_cache_bytes` was non-zero at open.
+    pub fn chunk_cache(&self) -> Option<&ChunkCache> {
+        self.cache.as_deref()
+    }
+
+    /// A serialisable snapshot of this array; see [`ArrayDescriptor`].
+    pub fn descriptor(&self) -> ArrayDescriptor {
+        ArrayDescriptor {
+            zarr_format: self.metadata.zarr_format,
+            path: self.path.clone(),
+            metadata: self.document.clone(),
+            attributes: self.metadata.attributes.clone(),
+            read_options: self.read_options.clone(),
+            metadata_etag: self.metadata_etag.clone(),
+        }
+    }
+
+    /// Rebuild an array from a descriptor without reading metadata from
+    /// `store`.
+    ///
+    /// Uses the process-wide [`config::global`](crate::config::global)
+    /// configuration.
+    pub fn from_descriptor<S: StorageBackend + 'static>(
+        store: Arc<S>,
+        descriptor: &ArrayDescriptor,
+    ) -> ZarrResult<Self> {
+        Self::from_descriptor_with_config(store, descriptor, crate::config::global())
+    }
+
+    /// Rebuild an array from a descriptor with an explicit configuration.
+    pub fn from_descriptor_with_config<S: StorageBackend + 'static>(
+        store: Arc<S>,
+        descriptor: &ArrayDescriptor,
+        config: Arc<SimplezarrConfig>,
+    ) -> ZarrResult<Self> {
+        if descriptor.zarr_format != 2 {
+            return Err(ZarrError::UnsupportedFeature(FeatureId::ZarrFormat(
+                descriptor.zarr_format,
+            )));
+        }
+        let bytes = serde_json::to_vec(&descriptor.metadata)?;
+        let md = ZarrV2Metadata::parse_with_max_chunks(&bytes, config.max_chunks)?;
+        let array = crate::v2::array_from_metadata(
+            store,
+            descriptor.path.clone(),
+            &md,
+            descriptor.attributes.clone(),
+            config,
+        )?;
+        let mut array = array.with_read_options(descriptor.read_options.clone());
+        array.metadata_etag = descriptor.metadata_etag.clone();
+        Ok(array)
+    }
+
+    /// The storage key of chunk `key`, checked against the array's chunk grid.
+    pub(crate) fn chunk_key(&self, key: &[usize]) -> ZarrResult<String> {
         if key.len() != self.metadata.shape.len() {
             return Err(ZarrError::Other(
                 "Key dimensionality must match array shape".into(),
             ));
         }
 
-        let key_str: String = key
-            .iter()
-            .map(|i| i.to_string())
-            .collect::<Vec<_>>()
-            .join(".");
-
+        let key_str = chunk_key_with_separator(key, self.metadata.dimension_separator);
         if !self.metadata.keys.contains(&key_str) {
             return Err(ZarrError::NotFound(format!(
                 "Storage key {key_str} not found"
             )));
         }
+        Ok(key_str)
+    }
 
+    /// Fetch a chunk's stored bytes without decoding them; `None` if the
+    /// chunk was never written.  Works even when the array's codecs can't be
+    /// run (see [`unsupported_codec`](Self::unsupported_codec)).
+    pub async fn get_chunk_raw(&self, key: &[usize]) -> ZarrResult<Option<Bytes>> {
+        let key_str = self.chunk_key(key)?;
         let chunk_path = self.store.join(&self.path, &key_str);
-        let bytes = self.store.get(&chunk_path).await?;
+        get_with_retry(self.store.as_ref(), &chunk_path, &self.config).await
+    }
 
-        let raw: Option<&[u8]> = bytes.as_deref();
-        parse_chunk(
-            raw,
-            self.metadata.data_type,
-            &self.metadata.chunk_shape,
-            &self.metadata.fill_value,
-            &self.codecs,
+    /// Fetch a single chunk by its multi-dimensional indices.
+    pub async fn get_chunk(&self, key: &[usize]) -> ZarrResult<ZarrVectorValue> {
+        let key_str = self.chunk_key(key)?;
+        let codecs = self.codecs()?;
+
+        if let Some(cached) = self.cache.as_ref().and_then(|c| c.get(&key_str)) {
+            return Ok(cached);
+        }
+
+        // A panic while fetching or decoding fails this chunk alone; a read
+        // seeing the error drops its other chunks' futures straight away.
+        let fetch = runtime::named(
+            || format!("read {}", self.store.join(&self.path, &key_str)),
+            self.fetch_chunk(key, &key_str, codecs),
+        );
+        let chunk = AssertUnwindSafe(fetch)
+            .catch_unwind()
+            .await
+            .unwrap_or_else(|panic| {
+                Err(ZarrError::Decode(format!(
+                    "Chunk {key_str} panicked: {}",
+                    runtime::panic_message(panic.as_ref())
+                )))
+            })?;
+
+        if let Some(cache) = &self.cache {
+            cache.insert(&key_str, chunk.clone());
+        }
+        Ok(chunk)
+    }
+
+    async fn fetch_chunk(
+        &self,
+        key: &[usize],
+        key_str: &str,
+        codecs: &[AnyCodec],
+    ) -> ZarrResult<ZarrVectorValue> {
+        let chunk_path = self.store.join(&self.path, key_str);
+        let started = Instant::now();
+        let bytes = get_with_retry(self.store.as_ref(), &chunk_path, &self.config).await?;
+        let fetched = Instant::now();
+        let chunk = self
+            .decode_chunk(key, key_str, bytes.as_deref(), codecs)
+            .await?;
+        if let Some(diagnostics) = &self.diagnostics {
+            diagnostics.record(ChunkRecord {
+                key: key_str.to_string(),
+                fetch_time: fetched - started,
+                decode_time: fetched.elapsed(),
+                stored_bytes: bytes.as_ref().map(|b| b.len()),
+                decoded_bytes: chunk.nbytes(),
+                codec: codec_label(codecs),
+            });
+        }
+        Ok(chunk)
+    }
+
+    /// Decode the stored bytes of chunk `key` with this array's codecs and
+    /// read options; see [`decode_chunk_bytes`].
+    pub(crate) async fn decode_chunk(
+        &self,
+        key: &[usize],
+        key_str: &str,
+        data: Option<&[u8]>,
+        codecs: &[AnyCodec],
+    ) -> ZarrResult<ZarrVectorValue> {
+        decode_stored_chunk(
+            &self.metadata,
+            key,
+            key_str,
+            data,
+            codecs,
+            &self.read_options,
         )
         .await
     }
+
+    /// Split the array's chunks between `n_workers` workers; entry `i` is
+    /// [`chunks_for_worker(i, n_workers)`](Self::chunks_for_worker).
+    pub fn partition_chunks(&self, n_workers: usize) -> ZarrResult<Vec<Vec<Vec<usize>>>> {
+        if n_workers == 0 {
+            return Err(ZarrError::Other("n_workers must be at least 1".into()));
+        }
+        (0..n_workers)
+            .map(|worker| self.chunks_for_worker(worker, n_workers))
+            .collect()
+    }
+
+    /// Chunk indices assigned to `worker` out of `n_workers`.
+    ///
+    /// Chunks are taken in storage key order and dealt out as contiguous
+    /// runs whose lengths differ by at most one, so every worker computes
+    /// the same assignment from the same metadata.
+    pub fn chunks_for_worker(
+        &self,
+        worker: usize,
+        n_workers: usize,
+    ) -> ZarrResult<Vec<Vec<usize>>> {
+        if worker >= n_workers {
+            return Err(ZarrError::Other(format!(
+                "Worker {worker} out of range for {n_workers} workers"
+            )));
+        }
+        let keys = &self.metadata.keys;
+        Ok(keys[worker_range(keys.len(), worker, n_workers)]
+            .iter()
+            .map(|key| parse_chunk_key(key, self.metadata.shape.len()))
+            .collect())
+    }
+
+    /// The region covering the whole array.
+    pub fn full_region(&self) -> Vec<Range<usize>> {
+        self.metadata.shape.iter().map(|&d| 0..d).collect()
+    }
+
+    /// Load the whole array as `f64`, in the array's memory order.
+    pub async fn load(&self) -> ZarrResult<ArrayData<Vec<f64>>> {
+        self.read_region(&self.full_region()).await
+    }
+
+    /// Load the whole array as `f32`, in the array's memory order, without
+    /// an intermediate `f64` copy.  Finite values beyond the `f32` range
+    /// saturate.
+    pub async fn load_f32(&self) -> ZarrResult<ArrayData<Vec<f32>>> {
+        self.read_region_f32(&self.full_region()).await
+    }
+
+    /// Load the whole array, preserving the element type.
+    pub async fn load_value(&self) -> ZarrResult<ArrayData<ZarrVectorValue>> {
+        self.read_region_value(&self.full_region()).await
+    }
+
+    /// Read a hyper-rectangular region (one half-open range per dimension)
+    /// as `f64`, in the array's memory order.
+    pub async fn read_region(&self, region: &[Range<usize>]) -> ZarrResult<ArrayData<Vec<f64>>> {
+        let chunks = self.fetch_region_chunks(region).await?;
+        Ok(self.region_data(region, merge_chunks(&self.metadata, region, &chunks)?))
+    }
+
+    /// Read position `index` along `axis` as `f64`, e.g. one time step of a
+    /// `(time, y, x)` array.  The result has that axis dropped from its
+    /// shape; the values are laid out as for
+    /// [`read_region`](Self::read_region).
+    pub async fn read_slice(&self, axis: usize, index: usize) -> ZarrResult<ArrayData<Vec<f64>>> {
+        let ndim = self.metadata.shape.len();
+        if axis >= ndim {
+            return Err(ZarrError::Other(format!(
+                "Axis {axis} out of range for an array of {ndim} dimensions"
+            )));
+        }
+        let mut region = self.full_region();
+        region[axis] = index..index.saturating_add(1);
+        let mut data = self.read_region(&region).await?;
+        // Removing a length-1 axis doesn't move any values, in either order.
+        data.shape = region
+            .iter()
+            .zip(&self.metadata.shape)
+            .enumerate()
+            .filter(|&(dim, (_, &len))| dim != axis && !(self.read_options.squeeze && len == 1))
+            .map(|(_, (r, _))| r.end - r.start)
+            .collect();
+        Ok(data)
+    }
+
+    /// [`read_region`](Self::read_region) as `f32`; see
+    /// [`load_f32`](Self::load_f32).
+    pub async fn read_region_f32(
+        &self,
+        region: &[Range<usize>],
+    ) -> ZarrResult<ArrayData<Vec<f32>>> {
+        let chunks = self.fetch_region_chunks(region).await?;
+        Ok(self.region_data(region, merge_chunks_f32(&self.metadata, region, &chunks)?))
+    }
+
+    /// Read a hyper-rectangular region without converting to `f64`.  The
+    /// result is normally a typed vector of the array's element type; see
+    /// [`merge_chunks_value`].
+    pub async fn read_region_value(
+        &self,
+        region: &[Range<usize>],
+    ) -> ZarrResult<ArrayData<ZarrVectorValue>> {
+        let chunks = self.fetch_region_chunks(region).await?;
+        Ok(self.region_data(region, merge_chunks_value(&self.metadata, region, &chunks)?))
+    }
+
+    /// `data` read from `region`, with the region's shape and the array's
+    /// memory order.  With [`ArrayReadOptions::squeeze`], the array's
+    /// length-1 dimensions are left out of the shape.
+    pub(crate) fn region_data<T>(&self, region: &[Range<usize>], data: T) -> ArrayData<T> {
+        let shape = region
+            .iter()
+            .zip(&self.metadata.shape)
+            .filter(|&(_, &len)| !(self.read_options.squeeze && len == 1))
+            .map(|(r, _)| r.end - r.start)
+            .collect();
+        ArrayData::new(data, shape, self.metadata.order)
+    }
+
+    /// Load the whole array as `f64` on the calling thread.
+    ///
+    /// Chunks are fetched and decoded one at a time in key order, and no task
+    /// or thread is spawned: blocking codecs and local reads run inline (see
+    /// [`runtime::block_on_inline`]).  This blocks the caller, so call it
+    /// from synchronous code rather than from inside an async task.
+    pub fn load_sync(&self) -> ZarrResult<ArrayData<Vec<f64>>> {
+        let region = self.full_region();
+        let data = runtime::block_on_inline(async {
+            let keys = chunk_indices_for_region(&self.metadata.chunk_shape, &region);
+            let chunks = futures::stream::iter(keys).then(|key| async move {
+                let chunk = self.get_chunk(&key).await?;
+                Ok::<_, ZarrError>((key, chunk))
+            });
+            let chunks = collect_chunks(chunks, self.config.fail_fast).await?;
+            merge_chunks(&self.metadata, &region, &chunks)
+        })?;
+        Ok(self.region_data(&region, data))
+    }
+
+    /// Iterate over every chunk as `(chunk indices, chunk)`, in key order.
+    ///
+    /// Each time a block is handed out, fetches for the next
+    /// [`read_ahead`](SimplezarrConfig::read_ahead) chunks have already been
+    /// started, so store latency overlaps with processing on sequential
+    /// scans.  In-flight fetches advance whenever the stream is polled.
+    pub fn iter_blocks(
+        &self,
+    ) -> impl Stream<Item = ZarrResult<(Vec<usize>, ZarrVectorValue)>> + '_ {
+        let keys = chunk_indices_for_region(&self.metadata.chunk_shape, &self.full_region());
+        ReadAhead {
+            array: self,
+            keys: keys.into_iter(),
+            window: self.config.read_ahead.saturating_add(1),
+            in_flight: VecDeque::new(),
+        }
+    }
+
+    /// Iterate over `window`-shaped regions starting every `step` elements
+    /// along each axis, as `(region, values)`, with starts in row-major
+    /// order.
+    ///
+    /// Only whole windows are produced, like numpy's
+    /// `sliding_window_view(a, window)[::step]`.  Windows overlap when `step`
+    /// is smaller than `window`, so set
+    /// [`chunk_cache_bytes`](SimplezarrConfig::chunk_cache_bytes) to decode
+    /// each shared chunk once.  The next
+    /// [`read_ahead`](SimplezarrConfig::read_ahead) windows are read while
+    /// one is being consumed.
+    pub fn iter_windows(
+        &self,
+        window: &[usize],
+        step: &[usize],
+    ) -> ZarrResult<impl Stream<Item = ZarrResult<Window>> + '_> {
+        let shape = &self.metadata.shape;
+        if window.len() != shape.len() || window.iter().zip(shape).any(|(&w, &s)| w == 0 || w > s) {
+            return Err(ZarrError::Other(format!(
+                "Window {window:?} does not fit an array of shape {shape:?}"
+            )));
+        }
+        if step.len() != shape.len() || step.contains(&0) {
+            return Err(ZarrError::Other(format!(
+                "Step {step:?} needs one non-zero entry per dimension"
+            )));
+        }
+        // Windows along each axis.
+        let counts: Vec<usize> = shape
+            .iter()
+            .zip(window)
+            .zip(step)
+            .map(|((&s, &w), &st)| (s - w) / st + 1)
+            .collect();
+        let total: usize = counts.iter().product();
+        let (window, step) = (window.to_vec(), step.to_vec());
+        let regions = (0..total).map(move |mut n| {
+            let mut region = vec![0..0; counts.len()];
+            for dim in (0..counts.len()).rev() {
+                let start = n % counts[dim] * step[dim];
+                n /= counts[dim];
+                region[dim] = start..start + window[dim];
+            }
+            region
+        });
+        Ok(futures::stream::iter(regions)
+            .map(move |region| async move {
+                let data = self.read_region(&region).await?;
+                Ok((region, data))
+            })
+            .buffered(self.config.read_ahead.saturating_add(1)))
+    }
+
+    /// Send every chunk of the array to `tx` as `(chunk indices, chunk)`; see
+    /// [`stream_region_into`](Self::stream_region_into).
+    pub async fn stream_into(
+        &self,
+        tx: mpsc::Sender<(Vec<usize>, ZarrVectorValue)>,
+    ) -> ZarrResult<()> {
+        self.stream_region_into(&self.full_region(), tx).await
+    }
+
+    /// Send every chunk overlapping `region` to `tx` as they are decoded, in
+    /// completion order.
+    ///
+    /// No new fetch starts while a send is waiting for channel capacity, so a
+    /// slow consumer throttles the pipeline: at most `max_concurrency` chunks
+    /// are in flight beyond what the channel holds.  Returns early, without
+    /// error, if the receiver is dropped.  Without
+    /// [`fail_fast`](SimplezarrConfig::fail_fast), failed chunks are skipped
+    /// and their errors returned once the rest have been sent.
+    pub async fn stream_region_into(
+        &self,
+        region: &[Range<usize>],
+        tx: mpsc::Sender<(Vec<usize>, ZarrVectorValue)>,
+    ) -> ZarrResult<()> {
+        validate_region(&self.metadata.shape, region)?;
+        let keys = chunk_indices_for_region(&self.metadata.chunk_shape, region);
+        let mut chunks = futures::stream::iter(keys)
+            .map(|key| async move {
+                let chunk = self.get_chunk(&key).await?;
+                Ok::<_, ZarrError>((key, chunk))
+            })
+            .buffer_unordered(self.config.max_concurrency.max(1));
+        let mut errors = Vec::new();
+        while let Some(item) = chunks.next().await {
+            match item {
+                Ok(item) => {
+                    if tx.send(item).await.is_err() {
+                        break;
+                    }
+                }
+                Err(e) if self.config.fail_fast => return Err(e),
+                Err(e) => errors.push(e),
+            }
+        }
+        chunk_errors(errors)
+    }
+
+    /// Fetch every chunk overlapping `region`, at most `max_concurrency` at a time.
+    async fn fetch_region_chunks(
+        &self,
+        region: &[Range<usize>],
+    ) -> ZarrResult<Vec<(Vec<usize>, ZarrVectorValue)>> {
+        validate_region(&self.metadata.shape, region)?;
+        let keys = chunk_indices_for_region(&self.metadata.chunk_shape, region);
+        let chunks = futures::stream::iter(keys)
+            .map(|key| async move {
+                let chunk = self.get_chunk(&key).await?;
+                Ok::<_, ZarrError>((key, chunk))
+            })
+            .buffer_unordered(self.config.max_concurrency.max(1));
+        collect_chunks(chunks, self.config.fail_fast).await
+    }
+}
+
+/// Collect a read's chunks.  With `fail_fast`, the first error is returned
+/// at once and dropping `chunks` cancels the fetches still in flight;
+/// otherwise every chunk is awaited and a single error is returned as is,
+/// several as [`ZarrError::ChunkErrors`].
+pub(crate) async fn collect_chunks<T>(
+    chunks: impl Stream<Item = ZarrResult<T>>,
+    fail_fast: bool,
+) -> ZarrResult<Vec<T>> {
+    if fail_fast {
+        return chunks.try_collect().await;
+    }
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    let mut chunks = std::pin::pin!(chunks);
+    while let Some(chunk) = chunks.next().await {
+        match chunk {
+            Ok(value) => values.push(value),
+            Err(e) => errors.push(e),
+        }
+    }
+    chunk_errors(errors).map(|()| values)
+}
+
+/// `Ok` without errors, the error itself for one, and
+/// [`ZarrError::ChunkErrors`] for several.  Errors that are themselves
+/// several, from reads of whole arrays, are flattened.
+fn chunk_errors(errors: Vec<ZarrError>) -> ZarrResult<()> {
+    let mut errors: Vec<ZarrError> = errors
+        .into_iter()
+        .flat_map(|e| match e {
+            ZarrError::ChunkErrors(inner) => inner,
+            e => vec![e],
+        })
+        .collect();
+    match errors.len() {
+        0 => Ok(()),
+        1 => Err(errors.remove(0)),
+        _ => Err(ZarrError::ChunkErrors(errors)),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Read-ahead
+// ---------------------------------------------------------------------------
+
+type BlockFuture<'a> =
+    Pin<Box<dyn Future<Output = ZarrResult<(Vec<usize>, ZarrVectorValue)>> + Send + 'a>>;
+
+/// Ordered chunk stream behind [`UnifiedZarrArray::iter_blocks`].
+///
+/// Unlike `StreamExt::buffered`, which may return the head of its queue
+/// before polling the rest, every poll drives all in-flight fetches, so the
+/// ones queued behind a returned block have really been issued.
+struct ReadAhead<'a> {
+    array: &'a UnifiedZarrArray,
+    keys: std::vec::IntoIter<Vec<usize>>,
+    /// Fetches in flight, counting the one about to be returned.
+    window: usize,
+    in_flight: VecDeque<MaybeDone<BlockFuture<'a>>>,
+}
+
+impl ReadAhead<'_> {
+    fn fill_and_poll(&mut self, cx: &mut Context<'_>) {
+        while self.in_flight.len() < self.window {
+            let Some(key) = self.keys.next() else { break };
+            let array = self.array;
+            self.in_flight.push_back(MaybeDone::Future(Box::pin(async move {
+                let chunk = array.get_chunk(&key).await?;
+                Ok((key, chunk))
+            })));
+        }
+        for fetch in &mut self.in_flight {
+            let _ = Pin::new(fetch).poll(cx);
+        }
+    }
+}
+
+impl Stream for ReadAhead<'_> {
+    type Item = ZarrResult<(Vec<usize>, ZarrVectorValue)>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        this.fill_and_poll(cx);
+        let Some(front) = this.in_flight.front_mut() else {
+            return Poll::Ready(None);
+        };
+        match Pin::new(front).take_output() {
+            Some(block) => {
+                this.in_flight.pop_front();
+                Poll::Ready(Some(block))
+            }
+            None => Poll::Pending,
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -184,34 +1047,518 @@ pub fn parse_key_string(key: &str) -> Vec<usize> {
         .collect()
 }
 
+/// Parse the storage key of a chunk of an `ndim`-dimensional array.  A 0-d
+/// array's single chunk is stored as `"0"` but has no indices.
+pub fn parse_chunk_key(key: &str, ndim: usize) -> Vec<usize> {
+    if ndim == 0 {
+        return Vec::new();
+    }
+    parse_key_string(key)
+}
+
+/// The slice of `len` items worker `worker` of `n_workers` owns: the first
+/// `len % n_workers` workers take one extra item.
+fn worker_range(len: usize, worker: usize, n_workers: usize) -> Range<usize> {
+    let base = len / n_workers;
+    let extra = len % n_workers;
+    let start = worker * base + worker.min(extra);
+    start..start + base + usize::from(worker < extra)
+}
+
+/// Check that `region` has one in-bounds, non-inverted range per dimension.
+pub fn validate_region(shape: &[usize], region: &[Range<usize>]) -> ZarrResult<()> {
+    if region.len() != shape.len() {
+        return Err(ZarrError::Other(format!(
+            "Region has {} dimensions, array has {}",
+            region.len(),
+            shape.len()
+        )));
+    }
+    for (dim, (r, &size)) in region.iter().zip(shape).enumerate() {
+        if r.start > r.end || r.end > size {
+            return Err(ZarrError::Other(format!(
+                "Region {r:?} out of bounds for dimension {dim} of size {size}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Indices of every chunk that overlaps `region`.
+pub fn chunk_indices_for_region(chunk_shape: &[usize], region: &[Range<usize>]) -> Vec<Vec<usize>> {
+    if region.iter().any(|r| r.is_empty()) {
+        return Vec::new();
+    }
+    let first: Vec<usize> = region
+        .iter()
+        .zip(chunk_shape)
+        .map(|(r, &c)| r.start / c)
+        .collect();
+    let counts: Vec<usize> = region
+        .iter()
+        .zip(chunk_shape)
+        .zip(&first)
+        .map(|((r, &c), &f)| r.end.div_ceil(c) - f)
+        .collect();
+    cartesian_indices(&counts)
+        .into_iter()
+        .map(|idx| idx.iter().zip(&first).map(|(i, f)| i + f).collect())
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Chunk merging
+// ---------------------------------------------------------------------------
+
+/// Pairs of (index within the chunk, index within the region output) for
+/// every element of chunk `chunk_idx` that falls inside `region`.
+pub(crate) fn chunk_copy_pairs(
+    md: &UnifiedMetadata,
+    region: &[Range<usize>],
+    chunk_idx: &[usize],
+) -> Vec<(usize, usize)> {
+    let out_shape: Vec<usize> = region.iter().map(|r| r.end - r.start).collect();
+    let origin: Vec<usize> = chunk_idx
+        .iter()
+        .zip(&md.chunk_shape)
+        .map(|(i, c)| i * c)
+        .collect();
+
+    // Intersection of the chunk with the region, in global coordinates.
+    let lo: Vec<usize> = origin
+        .iter()
+        .zip(region)
+        .map(|(o, r)| (*o).max(r.start))
+        .collect();
+    let hi: Vec<usize> = origin
+        .iter()
+        .zip(&md.chunk_shape)
+        .zip(region)
+        .map(|((o, c), r)| (o + c).min(r.end))
+        .collect();
+    if lo.iter().zip(&hi).any(|(l, h)| l >= h) {
+        return Vec::new();
+    }
+    let extent: Vec<usize> = lo.iter().zip(&hi).map(|(l, h)| h - l).collect();
+
+    let chunk_strides = strides(&md.chunk_shape, md.order);
+    let out_strides = strides(&out_shape, md.order);
+
+    cartesian_indices(&extent)
+        .into_iter()
+        .map(|offset| {
+            let mut src = 0;
+            let mut dst = 0;
+            for d in 0..offset.len() {
+                let global = lo[d] + offset[d];
+                src += (global - origin[d]) * chunk_strides[d];
+                dst += (global - region[d].start) * out_strides[d];
+            }
+            (src, dst)
+        })
+        .collect()
+}
+
+/// Assemble decoded chunks into a flat `f64` buffer covering `region`.
+pub fn merge_chunks(
+    md: &UnifiedMetadata,
+    region: &[Range<usize>],
+    chunks: &[(Vec<usize>, ZarrVectorValue)],
+) -> ZarrResult<Vec<f64>> {
+    let total: usize = region.iter().map(|r| r.end - r.start).product();
+    let mut out = vec![md.fill_value.to_f64(); total];
+    for (idx, chunk) in chunks {
+        // Converted element by element straight into `out`, with no `f64`
+        // copy of the chunk.
+        let pairs = chunk_copy_pairs(md, region, idx);
+        if pairs.iter().any(|&(src, _)| src >= chunk.len()) {
+            return Err(ZarrError::Decode(format!(
+                "Chunk {idx:?} is shorter than its chunk shape"
+            )));
+        }
+        scatter_f64(&mut out, chunk, &pairs)?;
+    }
+    Ok(out)
+}
+
+/// [`merge_chunks`] into `f32`, converting one chunk at a time.
+pub fn merge_chunks_f32(
+    md: &UnifiedMetadata,
+    region: &[Range<usize>],
+    chunks: &[(Vec<usize>, ZarrVectorValue)],
+) -> ZarrResult<Vec<f32>> {
+    let total: usize = region.iter().map(|r| r.end - r.start).product();
+    let mut out = vec![saturating_f32(md.fill_value.to_f64()); total];
+    for (idx, chunk) in chunks {
+        let values = chunk.to_f32_vec()?;
+        for (src, dst) in chunk_copy_pairs(md, region, idx) {
+            out[dst] = *values.get(src).ok_or_else(|| {
+                ZarrError::Decode(format!("Chunk {idx:?} is shorter than its chunk shape"))
+            })?;
+        }
+    }
+    Ok(out)
+}
+
+/// Assemble decoded chunks into a single vector covering `region`.
+///
+/// When the chunks share one typed variant and together cover the region,
+/// the result is that variant.  Otherwise (mixed variants, chunks with
+/// nulls, or gaps) it is a [`ZarrVectorValue::VWithNulls`] tagged with
+/// `md.data_type`, with `None` where no chunk covers.
+pub fn merge_chunks_value(
+    md: &UnifiedMetadata,
+    region: &[Range<usize>],
+    chunks: &[(Vec<usize>, ZarrVectorValue)],
+) -> ZarrResult<ZarrVectorValue> {
+    let total: usize = region.iter().map(|r| r.end - r.start).product();
+    if let Some(merged) = merge_typed(md, region, chunks, total)? {
+        return Ok(merged);
+    }
+    let mut out: Vec<Option<ZarrValue>> = vec![None; total];
+    for (idx, chunk) in chunks {
+        let values = chunk.to_maybe_values();
+        for (src, dst) in chunk_copy_pairs(md, region, idx) {
+            out[dst] = values
+                .get(src)
+                .ok_or_else(|| {
+                    ZarrError::Decode(format!("Chunk {idx:?} is shorter than its chunk shape"))
+                })?
+                .clone();
+        }
+    }
+    Ok(ZarrVectorValue::VWithNulls(md.data_type, out))
+}
+
+/// The typed path of [`merge_chunks_value`]: scatter every chunk into one
+/// vector of their shared variant, or `None` when that isn't possible.
+fn merge_typed(
+    md: &UnifiedMetadata,
+    region: &[Range<usize>],
+    chunks: &[(Vec<usize>, ZarrVectorValue)],
+    total: usize,
+) -> ZarrResult<Option<ZarrVectorValue>> {
+    if chunks.is_empty()
+        || chunks
+            .iter()
+            .any(|(_, chunk)| matches!(chunk, ZarrVectorValue::VWithNulls(..)))
+    {
+        return Ok(None);
+    }
+    // Every position is overwritten when the chunks cover the region.
+    let mut out = fill_chunk(&md.fill_value.to_zarr_value(md.data_type), &[total]);
+    let mut covered = 0;
+    for (idx, chunk) in chunks {
+        let pairs: Vec<(usize, usize)> = chunk_copy_pairs(md, region, idx)
+            .into_iter()
+            .map(|(src, dst)| (dst, src))
+            .collect();
+        if pairs.iter().any(|&(_, src)| src >= chunk.len()) {
+            return Err(ZarrError::Decode(format!(
+                "Chunk {idx:?} is shorter than its chunk shape"
+            )));
+        }
+        covered += pairs.len();
+        if scatter(&mut out, chunk, pairs).is_err() {
+            return Ok(None);
+        }
+    }
+    Ok((covered == total).then_some(out))
+}
+
 // ---------------------------------------------------------------------------
 // Chunk parsing
 // ---------------------------------------------------------------------------
 
+/// Number of decoded bytes a chunk of `elements` elements must occupy, or
+/// `None` for variable-size types.  A chunk too large for its byte count to
+/// fit in a `usize` is a [`ZarrError::Metadata`].
+pub fn expected_chunk_bytes(
+    dtype: DataType,
+    elements: usize,
+    options: &ArrayReadOptions,
+) -> ZarrResult<Option<usize>> {
+    match (dtype, options.bool_encoding) {
+        (DataType::Bool, BoolEncoding::BitPacked) => Ok(Some(elements.div_ceil(8))),
+        _ => dtype
+            .byte_size()
+            .map(|size| {
+                size.checked_mul(elements).ok_or_else(|| {
+                    ZarrError::Metadata(format!(
+                        "A chunk of {elements} {dtype} elements is too large to address"
+                    ))
+                })
+            })
+            .transpose(),
+    }
+}
+
 /// Parse a single chunk: decompress via codec pipeline, then interpret bytes.
+///
+/// Uses the default [`ArrayReadOptions`]; see [`parse_chunk_with_options`].
 pub async fn parse_chunk(
     data: Option<&[u8]>,
     dtype: DataType,
     chunk_shape: &[usize],
     fill_value: &FillValue,
     codecs: &[AnyCodec],
+) -> ZarrResult<ZarrVectorValue> {
+    parse_chunk_with_options(
+        data,
+        "<chunk>",
+        dtype,
+        chunk_shape,
+        fill_value,
+        codecs,
+        &ArrayReadOptions::default(),
+    )
+    .await
+}
+
+/// Parse a single chunk with explicit read options.
+///
+/// `key` is the chunk's storage key, used to identify the chunk in errors.
+/// The decoded byte count is checked against the chunk shape before the
+/// bytes are interpreted.
+pub async fn parse_chunk_with_options(
+    data: Option<&[u8]>,
+    key: &str,
+    dtype: DataType,
+    chunk_shape: &[usize],
+    fill_value: &FillValue,
+    codecs: &[AnyCodec],
+    options: &ArrayReadOptions,
 ) -> ZarrResult<ZarrVectorValue> {
     match data {
         Some(raw) if !raw.is_empty() => {
-            let decompressed = apply_codec_pipeline(codecs, raw).await?;
-
-            // Determine endianness from the BytesCodec in the pipeline
-            let endian = codecs
-                .iter()
-                .find_map(|c| c.bytes_endian())
-                .unwrap_or(Endian::Little);
-
-            bytes_to_zarr_vector(endian, dtype, &decompressed)
+            let elements: usize = chunk_shape.iter().product();
+            CodecPipeline::new(codecs)?
+                .decode(raw, key, dtype, elements, options)
+                .await
         }
         _ => {
             // Missing or empty chunk -> fill with fill value
-            let scalar = fill_value.to_zarr_value(dtype);
+            let scalar = missing_chunk_scalar(dtype, fill_value, options)?;
             Ok(fill_chunk(&scalar, chunk_shape))
         }
     }
 }
+
+/// Decode chunk `key`'s stored bytes with nothing but the array's metadata,
+/// for chunks fetched over a transport of your own; `None` means the chunk
+/// was never written.
+///
+/// The result matches [`UnifiedZarrArray::get_chunk`]: the whole chunk in
+/// the chunk shape, including an edge chunk stored trimmed to the array.
+/// The codec list is rebuilt from `metadata` on every call, so decoding many
+/// chunks through an opened array is cheaper.
+pub async fn decode_chunk_bytes(
+    metadata: &UnifiedMetadata,
+    key: &[usize],
+    data: Option<&[u8]>,
+    options: &ArrayReadOptions,
+) -> ZarrResult<ZarrVectorValue> {
+    let key_str = chunk_key_with_separator(key, metadata.dimension_separator);
+    if key.len() != metadata.shape.len() || !metadata.keys.contains(&key_str) {
+        return Err(ZarrError::NotFound(format!(
+            "Chunk {key:?} is outside the chunk grid"
+        )));
+    }
+    let codecs = metadata.codecs()?;
+    decode_stored_chunk(metadata, key, &key_str, data, &codecs, options).await
+}
+
+/// Decode the stored bytes of chunk `key`, as [`parse_chunk_with_options`]
+/// does, but also accepting an edge chunk stored trimmed to the elements
+/// inside the array.  A trimmed chunk is laid out in the full chunk shape,
+/// padded with the fill value.
+async fn decode_stored_chunk(
+    md: &UnifiedMetadata,
+    key: &[usize],
+    key_str: &str,
+    data: Option<&[u8]>,
+    codecs: &[AnyCodec],
+    options: &ArrayReadOptions,
+) -> ZarrResult<ZarrVectorValue> {
+    let edge: Vec<usize> = key
+        .iter()
+        .zip(&md.chunk_shape)
+        .zip(&md.shape)
+        .map(|((k, c), s)| (*c).min(s.saturating_sub(k * c)))
+        .collect();
+    let Some(raw) = data.filter(|raw| !raw.is_empty() && edge != md.chunk_shape) else {
+        return parse_chunk_with_options(
+            data,
+            key_str,
+            md.data_type,
+            &md.chunk_shape,
+            &md.fill_value,
+            codecs,
+            options,
+        )
+        .await;
+    };
+    let elements = md.chunk_shape.iter().product();
+    let values = CodecPipeline::new(codecs)?
+        .decode_edge(
+            raw,
+            key_str,
+            md.data_type,
+            elements,
+            Some(edge.iter().product()),
+            options,
+        )
+        .await?;
+    if values.len() == elements {
+        return Ok(values);
+    }
+    let mut chunk = fill_chunk(&md.fill_value.to_zarr_value(md.data_type), &md.chunk_shape);
+    let edge_strides = strides(&edge, md.order);
+    let chunk_strides = strides(&md.chunk_shape, md.order);
+    let pairs = cartesian_indices(&edge)
+        .into_iter()
+        .map(|index| {
+            let dot = |strides: &[usize]| index.iter().zip(strides).map(|(i, s)| i * s).sum();
+            (dot(&chunk_strides), dot(&edge_strides))
+        })
+        .collect();
+    scatter(&mut chunk, &values, pairs).map_err(|_| {
+        ZarrError::Decode(format!("Chunk {key_str} decoded to the wrong element type"))
+    })?;
+    Ok(chunk)
+}
+
+/// The element of missing chunks: the fill value, or for integer arrays with
+/// a NaN fill, [`ArrayReadOptions::int_fill_sentinel`] when set.
+fn missing_chunk_scalar(
+    dtype: DataType,
+    fill_value: &FillValue,
+    options: &ArrayReadOptions,
+) -> ZarrResult<ZarrValue> {
+    let Some(sentinel) = options
+        .int_fill_sentinel
+        .filter(|_| *fill_value == FillValue::NaN)
+    else {
+        return Ok(fill_value.to_zarr_value(dtype));
+    };
+    let scalar = match dtype {
+        DataType::Int8 => i8::try_from(sentinel).map(ZarrValue::Int8),
+        DataType::Int16 => i16::try_from(sentinel).map(ZarrValue::Int16),
+        DataType::Int32 => i32::try_from(sentinel).map(ZarrValue::Int32),
+        DataType::Int64 => Ok(ZarrValue::Int64(sentinel)),
+        DataType::UInt8 => u8::try_from(sentinel).map(ZarrValue::UInt8),
+        DataType::UInt16 => u16::try_from(sentinel).map(ZarrValue::UInt16),
+        DataType::UInt32 => u32::try_from(sentinel).map(ZarrValue::UInt32),
+        DataType::UInt64 => u64::try_from(sentinel).map(ZarrValue::UInt64),
+        _ => return Ok(fill_value.to_zarr_value(dtype)),
+    };
+    scalar.map_err(|_| {
+        ZarrError::TypeConversion(format!("Fill sentinel {sentinel} doesn't fit {dtype}"))
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Table columns
+// ---------------------------------------------------------------------------
+
+/// The shape of the arrays of a table (a `what`, for errors), which must
+/// all have it.
+#[cfg(any(feature = "polars", feature = "datafusion"))]
+pub(crate) fn table_shape<'a>(
+    what: &str,
+    arrays: &[(&str, &'a UnifiedZarrArray)],
+) -> ZarrResult<&'a [usize]> {
+    let Some(&(first_name, first)) = arrays.first() else {
+        return Err(ZarrError::Other(format!(
+            "A {what} needs at least one array"
+        )));
+    };
+    let shape = &first.metadata.shape;
+    if let Some((name, array)) = arrays.iter().find(|(_, a)| a.metadata.shape != *shape) {
+        return Err(ZarrError::Other(format!(
+            "Array {name} has shape {:?}, not {shape:?} like {first_name}",
+            array.metadata.shape
+        )));
+    }
+    Ok(shape)
+}
+
+/// The index column of each of `array`'s dimensions: its dimension name,
+/// or `dim_{axis}` when it has none or it is also one of `columns`.
+#[cfg(any(feature = "polars", feature = "datafusion"))]
+pub(crate) fn index_column_names(array: &UnifiedZarrArray, columns: &[&str]) -> Vec<String> {
+    let dims = array
+        .metadata
+        .dimension_names
+        .as_deref()
+        .unwrap_or_default();
+    (0..array.metadata.shape.len())
+        .map(|axis| match dims.get(axis) {
+            Some(Some(dim)) if !columns.contains(&dim.as_str()) => dim.clone(),
+            _ => format!("dim_{axis}"),
+        })
+        .collect()
+}
+
+/// The error for a column of complex values, which tables can't hold.
+#[cfg(any(feature = "polars", feature = "datafusion"))]
+pub(crate) fn complex_column_error() -> ZarrError {
+    ZarrError::TypeConversion("Cannot build a column from complex values".into())
+}
+
+/// Nullable elements of one type, ready to become a table column.
+#[cfg(any(feature = "polars", feature = "datafusion"))]
+pub(crate) enum NullableColumn<'a> {
+    Bool(Vec<Option<bool>>),
+    Int8(Vec<Option<i8>>),
+    Int16(Vec<Option<i16>>),
+    Int32(Vec<Option<i32>>),
+    Int64(Vec<Option<i64>>),
+    UInt8(Vec<Option<u8>>),
+    UInt16(Vec<Option<u16>>),
+    UInt32(Vec<Option<u32>>),
+    UInt64(Vec<Option<u64>>),
+    Float16(Vec<Option<half::f16>>),
+    Float32(Vec<Option<f32>>),
+    Float64(Vec<Option<f64>>),
+    String(Vec<Option<&'a str>>),
+    Bytes(Vec<Option<&'a [u8]>>),
+}
+
+#[cfg(any(feature = "polars", feature = "datafusion"))]
+impl<'a> NullableColumn<'a> {
+    /// Split `values` of `dtype` into elements and nulls.  Complex values
+    /// are rejected.
+    pub(crate) fn new(dtype: DataType, values: &'a [Option<ZarrValue>]) -> ZarrResult<Self> {
+        macro_rules! column {
+            ($variant:ident, $convert:expr) => {
+                NullableColumn::$variant(
+                    values
+                        .iter()
+                        .map(|value| match value {
+                            Some(ZarrValue::$variant(x)) => Some($convert(x)),
+                            _ => None,
+                        })
+                        .collect(),
+                )
+            };
+        }
+        Ok(match dtype {
+            DataType::Bool => column!(Bool, |x: &bool| *x),
+            DataType::Int8 => column!(Int8, |x: &i8| *x),
+            DataType::Int16 => column!(Int16, |x: &i16| *x),
+            DataType::Int32 => column!(Int32, |x: &i32| *x),
+            DataType::Int64 => column!(Int64, |x: &i64| *x),
+            DataType::UInt8 => column!(UInt8, |x: &u8| *x),
+            DataType::UInt16 => column!(UInt16, |x: &u16| *x),
+            DataType::UInt32 => column!(UInt32, |x: &u32| *x),
+            DataType::UInt64 => column!(UInt64, |x: &u64| *x),
+            DataType::Float16 => column!(Float16, |x: &half::f16| *x),
+            DataType::Float32 => column!(Float32, |x: &f32| *x),
+            DataType::Float64 => column!(Float64, |x: &f64| *x),
+            DataType::String => column!(String, String::as_str),
+            DataType::Bytes | DataType::Raw(_) => column!(Bytes, Vec::as_slice),
+            DataType::Complex64 | DataType::Complex128 => return Err(complex_column_error()),
+        })
+    }
+}
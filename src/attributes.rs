@@ -0,0 +1,162 @@
+//! Typed access to `.zattrs` and V3 `attributes`.
+//!
+//! [`Attributes`] wraps the JSON object and dereferences to it, so map
+//! methods apply directly.  Its getters take a `/`-separated path into
+//! nested objects and arrays and return `None` when the value is missing or
+//! of another type:
+//!
+//! ```ignore
+//! let attrs = array.metadata.attributes.as_ref().unwrap();
+//! let units = attrs.get_str("units");
+//! let semi_major = attrs.get_f64("grid_mapping/semi_major_axis");
+//! let range = attrs.get_vec_f64("valid_range");
+//! let cf: CfAttrs = attrs.deserialize()?;
+//! ```
+
+use std::ops::{Deref, DerefMut};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::{ZarrError, ZarrResult};
+
+/// A node's attributes: a JSON object with typed getters.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Attributes(Map<String, Value>);
+
+impl Attributes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The value at `path`: a key, or keys and array indices joined by
+    /// `/`.  A key that itself contains `/` is found before the path is
+    /// split.
+    pub fn get_path(&self, path: &str) -> Option<&Value> {
+        if let Some(value) = self.0.get(path) {
+            return Some(value);
+        }
+        let mut parts = path.split('/');
+        let mut value = self.0.get(parts.next()?)?;
+        for part in parts {
+            value = match value {
+                Value::Object(map) => map.get(part)?,
+                Value::Array(items) => items.get(part.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    pub fn get_str(&self, path: &str) -> Option<&str> {
+        self.get_path(path)?.as_str()
+    }
+
+    /// A number at `path`.  `"NaN"`, `"Infinity"` and `"-Infinity"`, as
+    /// Zarr writes non-finite floats, count as numbers.
+    pub fn get_f64(&self, path: &str) -> Option<f64> {
+        value_f64(self.get_path(path)?)
+    }
+
+    /// An integer at `path`; floats with a fractional part don't count.
+    pub fn get_i64(&self, path: &str) -> Option<i64> {
+        let value = self.get_path(path)?;
+        value.as_i64().or_else(|| {
+            value
+                .as_f64()
+                .filter(|f| f.fract() == 0.0 && f.abs() < i64::MAX as f64)
+                .map(|f| f as i64)
+        })
+    }
+
+    pub fn get_bool(&self, path: &str) -> Option<bool> {
+        self.get_path(path)?.as_bool()
+    }
+
+    /// An array of numbers at `path`, or a single number as a vector of
+    /// one, as netCDF attributes such as `valid_range` may be either.
+    pub fn get_vec_f64(&self, path: &str) -> Option<Vec<f64>> {
+        match self.get_path(path)? {
+            Value::Array(items) => items.iter().map(value_f64).collect(),
+            value => Some(vec![value_f64(value)?]),
+        }
+    }
+
+    /// An array of strings at `path`, or a single string as a vector of
+    /// one.
+    pub fn get_vec_str(&self, path: &str) -> Option<Vec<&str>> {
+        match self.get_path(path)? {
+            Value::Array(items) => items.iter().map(Value::as_str).collect(),
+            value => Some(vec![value.as_str()?]),
+        }
+    }
+
+    /// Deserialize the value at `path` into `T`; `Ok(None)` if there is
+    /// none.
+    pub fn get_as<T: DeserializeOwned>(&self, path: &str) -> ZarrResult<Option<T>> {
+        self.get_path(path)
+            .map(|value| {
+                T::deserialize(value)
+                    .map_err(|e| ZarrError::Metadata(format!("Invalid attribute {path}: {e}")))
+            })
+            .transpose()
+    }
+
+    /// Deserialize all the attributes into `T`, typically a struct with a
+    /// field per attribute of interest.
+    pub fn deserialize<T: DeserializeOwned>(&self) -> ZarrResult<T> {
+        T::deserialize(&Value::Object(self.0.clone()))
+            .map_err(|e| ZarrError::Metadata(format!("Invalid attributes: {e}")))
+    }
+
+    /// The underlying JSON object.
+    pub fn into_inner(self) -> Map<String, Value> {
+        self.0
+    }
+}
+
+fn value_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::String(s) => match s.as_str() {
+            "NaN" => Some(f64::NAN),
+            "Infinity" => Some(f64::INFINITY),
+            "-Infinity" => Some(f64::NEG_INFINITY),
+            _ => None,
+        },
+        value => value.as_f64(),
+    }
+}
+
+impl Deref for Attributes {
+    type Target = Map<String, Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Attributes {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<Map<String, Value>> for Attributes {
+    fn from(map: Map<String, Value>) -> Self {
+        Self(map)
+    }
+}
+
+impl From<Attributes> for Value {
+    fn from(attributes: Attributes) -> Self {
+        Value::Object(attributes.0)
+    }
+}
+
+impl FromIterator<(String, Value)> for Attributes {
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
@@ -0,0 +1,216 @@
+//! Comparing compression pipelines on an array's own chunks.
+//!
+//! [`benchmark_codecs`] decodes a sample of an array's stored chunks, then
+//! re-encodes and decodes them with each candidate pipeline, timing both
+//! directions.  The resulting [`BenchmarkReport`] prints as a table, so the
+//! ratio and speed of, say, `zstd(3)` against Blosc with a shuffle can be
+//! judged on real data before re-encoding an array with
+//! [`ConvertOptions::reencode`](crate::convert::ConvertOptions::reencode).
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::array::{UnifiedZarrArray, parse_chunk_key};
+use crate::codecs::{AnyCodec, CodecPipeline};
+use crate::describe::numcodecs_repr;
+use crate::error::{ZarrError, ZarrResult};
+use crate::options::ArrayReadOptions;
+use crate::types::ZarrVectorValue;
+
+// ---------------------------------------------------------------------------
+// Results
+// ---------------------------------------------------------------------------
+
+/// How one candidate pipeline did on the sampled chunks.
+#[derive(Debug, Clone)]
+pub struct CodecBenchmark {
+    /// The pipeline as numcodecs prints it, e.g. `Zstd(level=3)`, or `none`.
+    pub label: String,
+    pub codecs: Vec<AnyCodec>,
+    /// Decoded size of the sampled chunks.
+    pub raw_bytes: usize,
+    /// Their size once encoded with this pipeline.
+    pub encoded_bytes: usize,
+    pub encode_time: Duration,
+    pub decode_time: Duration,
+}
+
+impl CodecBenchmark {
+    /// Decoded size over encoded size; higher is better.
+    pub fn ratio(&self) -> f64 {
+        self.raw_bytes as f64 / self.encoded_bytes.max(1) as f64
+    }
+
+    /// Encoding throughput in MB/s of decoded data.
+    pub fn encode_mb_per_s(&self) -> f64 {
+        mb_per_s(self.raw_bytes, self.encode_time)
+    }
+
+    /// Decoding throughput in MB/s of decoded data.
+    pub fn decode_mb_per_s(&self) -> f64 {
+        mb_per_s(self.raw_bytes, self.decode_time)
+    }
+}
+
+/// The outcome of [`benchmark_codecs`]: one row per candidate, in the order
+/// given.
+#[derive(Debug, Clone)]
+pub struct BenchmarkReport {
+    /// The sampled chunks' indices.
+    pub chunks: Vec<Vec<usize>>,
+    /// Decoded size of the sampled chunks.
+    pub raw_bytes: usize,
+    /// Their size as currently stored.
+    pub stored_bytes: usize,
+    pub results: Vec<CodecBenchmark>,
+}
+
+impl BenchmarkReport {
+    /// The candidate with the best ratio.
+    pub fn smallest(&self) -> Option<&CodecBenchmark> {
+        self.results
+            .iter()
+            .max_by(|a, b| a.ratio().total_cmp(&b.ratio()))
+    }
+
+    /// The candidate that decodes fastest.
+    pub fn fastest_decode(&self) -> Option<&CodecBenchmark> {
+        self.results.iter().min_by_key(|r| r.decode_time)
+    }
+}
+
+impl fmt::Display for BenchmarkReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} chunks, {} bytes decoded, {} stored (ratio {:.2})",
+            self.chunks.len(),
+            self.raw_bytes,
+            self.stored_bytes,
+            self.raw_bytes as f64 / self.stored_bytes.max(1) as f64
+        )?;
+        let width = self
+            .results
+            .iter()
+            .map(|r| r.label.len())
+            .chain([8])
+            .max()
+            .unwrap_or(8);
+        writeln!(
+            f,
+            "{:<width$} {:>8} {:>12} {:>12} {:>12}",
+            "pipeline", "ratio", "bytes", "enc MB/s", "dec MB/s"
+        )?;
+        for r in &self.results {
+            writeln!(
+                f,
+                "{:<width$} {:>8.2} {:>12} {:>12.1} {:>12.1}",
+                r.label,
+                r.ratio(),
+                r.encoded_bytes,
+                r.encode_mb_per_s(),
+                r.decode_mb_per_s()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn mb_per_s(bytes: usize, time: Duration) -> f64 {
+    bytes as f64 / 1e6 / time.as_secs_f64().max(1e-9)
+}
+
+// ---------------------------------------------------------------------------
+// Benchmarking
+// ---------------------------------------------------------------------------
+
+/// Measure each of `candidates` on up to `sample_chunks` of `array`'s
+/// chunks.
+///
+/// Chunks are sampled evenly across the chunk grid; ones that were never
+/// written are skipped, since their fill values say nothing about the data.
+/// Each sampled chunk is fetched and decoded once with the array's own
+/// codecs, then every candidate encodes and decodes it.  An empty
+/// candidate stores chunks uncompressed.
+///
+/// Fails if none of the sampled chunks hold data, or if a candidate can't
+/// encode the array's element type.
+pub async fn benchmark_codecs(
+    array: &UnifiedZarrArray,
+    candidates: &[Vec<AnyCodec>],
+    sample_chunks: usize,
+) -> ZarrResult<BenchmarkReport> {
+    let md = &array.metadata;
+    let keys = &md.keys;
+    let own_codecs = array.codecs()?;
+    let wanted = sample_chunks.min(keys.len());
+    let mut chunks = Vec::new();
+    let mut decoded = Vec::new();
+    let mut stored_bytes = 0;
+    for i in 0..wanted {
+        let key = parse_chunk_key(&keys[i * keys.len() / wanted], md.shape.len());
+        let Some(raw) = array.get_chunk_raw(&key).await? else {
+            continue;
+        };
+        stored_bytes += raw.len();
+        let key_str = array.chunk_key(&key)?;
+        decoded.push(
+            array
+                .decode_chunk(&key, &key_str, Some(&raw), own_codecs)
+                .await?,
+        );
+        chunks.push(key);
+    }
+    if chunks.is_empty() {
+        return Err(ZarrError::Other(
+            "None of the sampled chunks have been written".into(),
+        ));
+    }
+    let raw_bytes = decoded.iter().map(ZarrVectorValue::nbytes).sum();
+
+    let options = ArrayReadOptions::default();
+    let mut results = Vec::with_capacity(candidates.len());
+    for codecs in candidates {
+        let pipeline = CodecPipeline::new(codecs)?;
+        let mut encoded = Vec::with_capacity(decoded.len());
+        let start = Instant::now();
+        for values in &decoded {
+            encoded.push(pipeline.encode(values).await?);
+        }
+        let encode_time = start.elapsed();
+
+        let start = Instant::now();
+        for (bytes, values) in encoded.iter().zip(&decoded) {
+            pipeline
+                .decode(bytes, "<benchmark>", md.data_type, values.len(), &options)
+                .await?;
+        }
+        let decode_time = start.elapsed();
+
+        results.push(CodecBenchmark {
+            label: pipeline_label(codecs),
+            codecs: codecs.clone(),
+            raw_bytes,
+            encoded_bytes: encoded.iter().map(Vec::len).sum(),
+            encode_time,
+            decode_time,
+        });
+    }
+    Ok(BenchmarkReport {
+        chunks,
+        raw_bytes,
+        stored_bytes,
+        results,
+    })
+}
+
+fn pipeline_label(codecs: &[AnyCodec]) -> String {
+    if codecs.is_empty() {
+        return "none".into();
+    }
+    codecs
+        .iter()
+        .map(numcodecs_repr)
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
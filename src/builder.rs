@@ -0,0 +1,495 @@
+//! Creating new V2 arrays.
+//!
+//! [`ArrayBuilder`] writes a `.zarray` document from a few choices, filling
+//! in the rest (chunk shape, compressor, fill value) with defaults, and
+//! returns the opened array ready for
+//! [`write_chunk`](UnifiedZarrArray::write_chunk).
+
+use std::sync::Arc;
+
+use crate::array::UnifiedZarrArray;
+use crate::capabilities::FeatureId;
+use crate::codecs::bitround::BitRoundCodec;
+use crate::codecs::blosc::{BloscCname, BloscCodec, BloscShuffle};
+use crate::codecs::gzip::GzipCodec;
+use crate::codecs::lz4::Lz4Codec;
+use crate::codecs::zlib::ZlibCodec;
+use crate::codecs::zstd::ZstdCodec;
+use crate::codecs::{AnyCodec, CodecId, CodecPipeline};
+use crate::config::{self, SimplezarrConfig};
+use crate::error::{ZarrError, ZarrResult};
+use crate::metadata::fill_value_to_json;
+use crate::metadata::v2::{V2DataType, ZarrV2Metadata};
+use crate::store::StorageBackend;
+use crate::types::{
+    ArrayOrder, DataType, Endian, FillValue, ZarrValue, default_fill_value, f64_to_zarr_vector,
+};
+use crate::v2::{codec_to_compressor, codec_to_filter};
+
+/// Upper bound on the size of automatically chosen chunks (8 MiB).
+pub const AUTO_CHUNK_MAX_BYTES: usize = 8 << 20;
+
+/// Chunks smaller than this (4 KiB) are compressed without Blosc, whose
+/// header and shuffle pass don't pay off on so few bytes, and are stored
+/// uncompressed when the goal is [`CompressionGoal::Speed`].
+pub const SMALL_CHUNK_BYTES: usize = 4 << 10;
+
+// ---------------------------------------------------------------------------
+// Chunk shape
+// ---------------------------------------------------------------------------
+
+/// How [`ArrayBuilder`] picks the chunk shape.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ChunkShape {
+    /// Chosen by [`auto_chunks`].
+    #[default]
+    Auto,
+    /// An explicit chunk shape, one entry per dimension.
+    Fixed(Vec<usize>),
+}
+
+impl From<Vec<usize>> for ChunkShape {
+    fn from(chunks: Vec<usize>) -> Self {
+        ChunkShape::Fixed(chunks)
+    }
+}
+
+impl From<&[usize]> for ChunkShape {
+    fn from(chunks: &[usize]) -> Self {
+        ChunkShape::Fixed(chunks.to_vec())
+    }
+}
+
+impl<const N: usize> From<[usize; N]> for ChunkShape {
+    fn from(chunks: [usize; N]) -> Self {
+        ChunkShape::Fixed(chunks.to_vec())
+    }
+}
+
+/// A chunk shape for an array of `shape` with `item_size`-byte elements.
+///
+/// Starting from the whole array, the longest chunk dimension (the
+/// outermost, on ties) is halved until a chunk holds at most
+/// [`AUTO_CHUNK_MAX_BYTES`].  Chunks therefore land between roughly 4 and
+/// 8 MiB, unless the whole array is smaller.
+pub fn auto_chunks(shape: &[usize], item_size: usize) -> Vec<usize> {
+    let mut chunks: Vec<usize> = shape.iter().map(|&d| d.max(1)).collect();
+    while chunks.iter().product::<usize>().saturating_mul(item_size) > AUTO_CHUNK_MAX_BYTES {
+        let Some((longest, _)) = chunks
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|&(_, &c)| c)
+            .filter(|&(_, &c)| c > 1)
+        else {
+            break;
+        };
+        chunks[longest] = chunks[longest].div_ceil(2);
+    }
+    chunks
+}
+
+// ---------------------------------------------------------------------------
+// Compressor shorthands
+// ---------------------------------------------------------------------------
+
+/// Zstandard at `level`.
+pub fn zstd(level: i32) -> AnyCodec {
+    AnyCodec::Zstd(ZstdCodec::new(level))
+}
+
+/// Gzip at `level` (0-9).
+pub fn gzip(level: u32) -> AnyCodec {
+    AnyCodec::Gzip(GzipCodec { level })
+}
+
+/// Zlib at `level` (0-9).
+pub fn zlib(level: u32) -> AnyCodec {
+    AnyCodec::Zlib(ZlibCodec { level })
+}
+
+/// LZ4 with the given acceleration.
+pub fn lz4(acceleration: i32) -> AnyCodec {
+    AnyCodec::Lz4(Lz4Codec { acceleration })
+}
+
+/// Blosc with sub-compressor `cname` at `clevel` (0-9).
+pub fn blosc(cname: BloscCname, clevel: i32, shuffle: BloscShuffle) -> AnyCodec {
+    AnyCodec::Blosc(BloscCodec {
+        typesize: None,
+        cname,
+        clevel,
+        shuffle: Some(shuffle),
+        blocksize: 0,
+        nthreads: None,
+        max_nthreads: None,
+    })
+}
+
+/// BitRound filter keeping `keepbits` mantissa bits, for
+/// [`ArrayBuilder::filter`].
+pub fn bitround(keepbits: u32) -> AnyCodec {
+    AnyCodec::BitRound(BitRoundCodec::new(keepbits))
+}
+
+// ---------------------------------------------------------------------------
+// Codec suggestions
+// ---------------------------------------------------------------------------
+
+/// What [`suggest_codecs`] optimises for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompressionGoal {
+    /// Fastest reads and writes, at a modest ratio.
+    Speed,
+    /// A good ratio at close to the speed of `Speed`.
+    #[default]
+    Balanced,
+    /// The smallest chunks, at a higher cost to write.
+    Size,
+}
+
+/// A recommended compression pipeline for chunks of `chunk_shape` holding
+/// `dtype` elements, using only codecs compiled into this build.
+///
+/// Multi-byte elements get Blosc with a byte shuffle, which groups the
+/// similar high bytes of neighbouring numbers; single-byte elements get a
+/// bit shuffle instead, and variable-size ones no shuffle.  The
+/// sub-compressor is LZ4 for [`Speed`](CompressionGoal::Speed) and Zstandard
+/// otherwise.  Without the `blosc` feature, or for chunks under
+/// [`SMALL_CHUNK_BYTES`], a plain `lz4`, `zstd` or `zlib` codec is used.
+///
+/// The pipeline holds at most one codec, so it always fits a V2
+/// compressor; it is empty when chunks are best stored uncompressed.
+pub fn suggest_codecs(
+    dtype: DataType,
+    chunk_shape: &[usize],
+    goal: CompressionGoal,
+) -> Vec<AnyCodec> {
+    let item_size = dtype.byte_size();
+    let chunk_bytes = chunk_shape
+        .iter()
+        .product::<usize>()
+        .saturating_mul(item_size.unwrap_or(8));
+    let small = chunk_bytes < SMALL_CHUNK_BYTES;
+    if small && goal == CompressionGoal::Speed {
+        return Vec::new();
+    }
+    if CodecId::Blosc.is_compiled() && !small {
+        let shuffle = match item_size {
+            Some(1) => BloscShuffle::BitShuffle,
+            Some(_) => BloscShuffle::Shuffle,
+            None => BloscShuffle::NoShuffle,
+        };
+        let (cname, clevel) = match goal {
+            CompressionGoal::Speed => (BloscCname::Lz4, 5),
+            CompressionGoal::Balanced => (BloscCname::Zstd, 5),
+            CompressionGoal::Size => (BloscCname::Zstd, 9),
+        };
+        return vec![blosc(cname, clevel, shuffle)];
+    }
+    let zstd_compiled = CodecId::Zstd.is_compiled();
+    let codec = match goal {
+        CompressionGoal::Speed if CodecId::Lz4.is_compiled() => lz4(1),
+        CompressionGoal::Speed if zstd_compiled => zstd(1),
+        CompressionGoal::Speed => zlib(1),
+        CompressionGoal::Balanced if zstd_compiled => zstd(3),
+        CompressionGoal::Balanced => zlib(5),
+        CompressionGoal::Size if zstd_compiled => zstd(9),
+        CompressionGoal::Size => zlib(9),
+    };
+    vec![codec]
+}
+
+/// How [`ArrayBuilder`] picks the compressor.
+#[derive(Debug, Clone)]
+pub enum CodecChoice {
+    /// Chosen by [`suggest_codecs`] for the array's element type and chunk
+    /// shape.
+    Auto(CompressionGoal),
+    /// An explicit compressor, or `None` to store chunks uncompressed.
+    Fixed(Option<AnyCodec>),
+}
+
+impl From<AnyCodec> for CodecChoice {
+    fn from(codec: AnyCodec) -> Self {
+        CodecChoice::Fixed(Some(codec))
+    }
+}
+
+impl From<Option<AnyCodec>> for CodecChoice {
+    fn from(codec: Option<AnyCodec>) -> Self {
+        CodecChoice::Fixed(codec)
+    }
+}
+
+impl From<CompressionGoal> for CodecChoice {
+    fn from(goal: CompressionGoal) -> Self {
+        CodecChoice::Auto(goal)
+    }
+}
+
+/// Let [`ArrayBuilder`] choose a compressor for `goal`.
+pub fn auto(goal: CompressionGoal) -> CodecChoice {
+    CodecChoice::Auto(goal)
+}
+
+// ---------------------------------------------------------------------------
+// ArrayBuilder
+// ---------------------------------------------------------------------------
+
+/// Builder for a new V2 array.
+///
+/// Defaults: automatic chunks, `float64`, C order, a fill value of zero and
+/// `zstd(3)` compression (`zlib(1)` without the `zstd` feature).  Pass
+/// [`auto`] to [`codec`](Self::codec) to have one chosen for the data
+/// instead.
+///
+/// ```no_run
+/// # use simplezarr::store::StorageBackend;
+/// # async fn demo<S: StorageBackend + 'static>(store: std::sync::Arc<S>)
+/// # -> simplezarr::ZarrResult<()> {
+/// use simplezarr::builder::{ArrayBuilder, ChunkShape, zstd};
+/// use simplezarr::DataType;
+///
+/// let array = ArrayBuilder::new([1000, 1000])
+///     .chunks(ChunkShape::Auto)
+///     .dtype(DataType::Float32)
+///     .codec(zstd(3))
+///     .fill(0.0)
+///     .build(store, "temperature")
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ArrayBuilder {
+    shape: Vec<usize>,
+    chunks: ChunkShape,
+    dtype: DataType,
+    codec: CodecChoice,
+    filters: Vec<AnyCodec>,
+    fill: Option<FillValue>,
+    order: ArrayOrder,
+    overwrite: bool,
+}
+
+impl ArrayBuilder {
+    pub fn new(shape: impl Into<Vec<usize>>) -> Self {
+        let codec = if cfg!(feature = "zstd") {
+            zstd(3)
+        } else {
+            zlib(1)
+        };
+        Self {
+            shape: shape.into(),
+            chunks: ChunkShape::Auto,
+            dtype: DataType::Float64,
+            codec: CodecChoice::Fixed(Some(codec)),
+            filters: Vec::new(),
+            fill: None,
+            order: ArrayOrder::C,
+            overwrite: false,
+        }
+    }
+
+    /// The array shape.
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn chunks(mut self, chunks: impl Into<ChunkShape>) -> Self {
+        self.chunks = chunks.into();
+        self
+    }
+
+    /// Element type.  Variable-size types (`String`, `Bytes`) are not
+    /// supported.
+    pub fn dtype(mut self, dtype: DataType) -> Self {
+        self.dtype = dtype;
+        self
+    }
+
+    /// Compressor, `None` to store chunks uncompressed, or
+    /// [`auto`]`(goal)` to pick one with [`suggest_codecs`].
+    pub fn codec(mut self, codec: impl Into<CodecChoice>) -> Self {
+        self.codec = codec.into();
+        self
+    }
+
+    /// Add a filter, applied to each chunk's values before the compressor,
+    /// such as [`bitround`].  Filters run in the order they are added.
+    pub fn filter(mut self, filter: AnyCodec) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Fill value, cast to the element type when the array is built.  NaN
+    /// and infinities are only valid for float types.
+    pub fn fill(mut self, value: f64) -> Self {
+        self.fill = Some(if value.is_nan() {
+            FillValue::NaN
+        } else if value == f64::INFINITY {
+            FillValue::Infinity
+        } else if value == f64::NEG_INFINITY {
+            FillValue::NegativeInfinity
+        } else {
+            FillValue::Value(ZarrValue::Float64(value))
+        });
+        self
+    }
+
+    /// Fill value given exactly; it must match the element type.
+    pub fn fill_value(mut self, fill: FillValue) -> Self {
+        self.fill = Some(fill);
+        self
+    }
+
+    pub fn order(mut self, order: ArrayOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Replace an existing array's metadata instead of failing.  Existing
+    /// chunks are left in place.
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    /// The chunk shape the array will get.
+    pub fn chunk_shape(&self) -> ZarrResult<Vec<usize>> {
+        let item_size = self.dtype.byte_size().ok_or_else(|| {
+            ZarrError::UnsupportedFeature(FeatureId::DataType(self.dtype.to_string()))
+        })?;
+        match &self.chunks {
+            ChunkShape::Auto => Ok(auto_chunks(&self.shape, item_size)),
+            ChunkShape::Fixed(chunks) => {
+                if chunks.len() != self.shape.len() || chunks.contains(&0) {
+                    return Err(ZarrError::Metadata(format!(
+                        "Chunk shape {chunks:?} does not fit array shape {:?}",
+                        self.shape
+                    )));
+                }
+                Ok(chunks.clone())
+            }
+        }
+    }
+
+    /// The compressor the array will get.
+    pub fn compressor(&self) -> ZarrResult<Option<AnyCodec>> {
+        match &self.codec {
+            CodecChoice::Auto(goal) => {
+                let chunks = self.chunk_shape()?;
+                Ok(suggest_codecs(self.dtype, &chunks, *goal).pop())
+            }
+            CodecChoice::Fixed(codec) => Ok(codec.clone()),
+        }
+    }
+
+    /// The `.zarray` document [`build`](Self::build) writes.
+    pub fn to_json(&self) -> ZarrResult<serde_json::Value> {
+        let chunks = self.chunk_shape()?;
+        let dtype = V2DataType {
+            data_type: self.dtype,
+            byte_order: match self.dtype.byte_size() {
+                Some(1) | None => Endian::NotApplicable,
+                Some(_) => Endian::Little,
+            },
+            time_unit: None,
+        };
+        let compressor = self
+            .compressor()?
+            .as_ref()
+            .map(codec_to_compressor)
+            .transpose()?;
+        // Filters must accept the element type; BitRound only takes floats.
+        CodecPipeline::new(&self.filters)?.encoded_data_type(self.dtype)?;
+        let filters = if self.filters.is_empty() {
+            serde_json::Value::Null
+        } else {
+            self.filters
+                .iter()
+                .map(codec_to_filter)
+                .collect::<ZarrResult<_>>()?
+        };
+        Ok(serde_json::json!({
+            "zarr_format": 2,
+            "shape": self.shape,
+            "chunks": chunks,
+            "dtype": dtype,
+            "fill_value": fill_value_to_json(&self.resolved_fill()?),
+            "order": self.order,
+            "compressor": compressor,
+            "filters": filters,
+            "dimension_separator": ".",
+        }))
+    }
+
+    /// Write the array's metadata to `path` in `store` and open it.
+    ///
+    /// Fails if an array already exists there, unless
+    /// [`overwrite`](Self::overwrite) is set.  Uses the process-wide
+    /// [`config::global`] configuration.
+    pub async fn build<S: StorageBackend + 'static>(
+        &self,
+        store: Arc<S>,
+        path: &str,
+    ) -> ZarrResult<UnifiedZarrArray> {
+        self.build_with_config(store, path, config::global()).await
+    }
+
+    /// [`build`](Self::build) with an explicit configuration.
+    pub async fn build_with_config<S: StorageBackend + 'static>(
+        &self,
+        store: Arc<S>,
+        path: &str,
+        config: Arc<SimplezarrConfig>,
+    ) -> ZarrResult<UnifiedZarrArray> {
+        let document = serde_json::to_vec_pretty(&self.to_json()?)?;
+        let md = ZarrV2Metadata::parse(&document)?;
+        let zarray_path = store.join(path, ".zarray");
+        if !self.overwrite && store.head(&zarray_path).await?.is_some() {
+            return Err(ZarrError::Metadata(format!(
+                "An array already exists at {path}"
+            )));
+        }
+        store.put(&zarray_path, document.into()).await?;
+        crate::v2::array_from_metadata(store, path.to_string(), &md, None, config)
+    }
+
+    /// The fill value cast to the element type.
+    fn resolved_fill(&self) -> ZarrResult<FillValue> {
+        let invalid = |fill: &FillValue| {
+            ZarrError::Metadata(format!("Fill value {fill} is not valid for {}", self.dtype))
+        };
+        let is_float = matches!(
+            self.dtype,
+            DataType::Float16
+                | DataType::Float32
+                | DataType::Float64
+                | DataType::Complex64
+                | DataType::Complex128
+        );
+        match &self.fill {
+            None => Ok(default_fill_value(self.dtype)),
+            Some(FillValue::Value(v)) if v.data_type() == self.dtype => {
+                Ok(FillValue::Value(v.clone()))
+            }
+            Some(fill @ FillValue::Value(ZarrValue::Float64(x))) => {
+                let cast = f64_to_zarr_vector(self.dtype, &[*x]).map_err(|_| invalid(fill))?;
+                let value = cast
+                    .to_maybe_values()
+                    .pop()
+                    .flatten()
+                    .ok_or_else(|| invalid(fill))?;
+                Ok(FillValue::Value(value))
+            }
+            Some(special @ (FillValue::NaN | FillValue::Infinity | FillValue::NegativeInfinity))
+                if is_float =>
+            {
+                Ok(special.clone())
+            }
+            Some(fill) => Err(invalid(fill)),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! In-memory cache of decoded chunks, bounded by total size in bytes.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use crate::types::ZarrVectorValue;
+
+/// Least-recently-used cache of decoded chunks keyed by storage key.
+///
+/// Shared between clones of a [`UnifiedZarrArray`](crate::array::UnifiedZarrArray)
+/// so repeated reads of the same region skip both the fetch and the decode.
+#[derive(Debug)]
+pub struct ChunkCache {
+    capacity_bytes: usize,
+    inner: Mutex<CacheInner>,
+}
+
+#[derive(Debug, Default)]
+struct CacheInner {
+    entries: HashMap<String, ZarrVectorValue>,
+    /// Keys from least to most recently used.
+    order: VecDeque<String>,
+    size: usize,
+}
+
+impl ChunkCache {
+    pub fn new(capacity_bytes: usize) -> Self {
+        Self {
+            capacity_bytes,
+            inner: Mutex::new(CacheInner::default()),
+        }
+    }
+
+    pub fn capacity_bytes(&self) -> usize {
+        self.capacity_bytes
+    }
+
+    /// Total size of the cached chunks in bytes.
+    pub fn size_bytes(&self) -> usize {
+        self.lock().size
+    }
+
+    pub fn get(&self, key: &str) -> Option<ZarrVectorValue> {
+        let mut inner = self.lock();
+        let value = inner.entries.get(key)?.clone();
+        inner.touch(key);
+        Some(value)
+    }
+
+    /// Insert a chunk, evicting least-recently-used entries as needed.
+    /// Chunks larger than the whole cache are not stored.
+    pub fn insert(&self, key: &str, value: ZarrVectorValue) {
+        let size = value.nbytes();
+        if size > self.capacity_bytes {
+            return;
+        }
+        let mut inner = self.lock();
+        if let Some(old) = inner.entries.remove(key) {
+            inner.size -= old.nbytes();
+            inner.order.retain(|k| k != key);
+        }
+        while inner.size + size > self.capacity_bytes {
+            let Some(evict) = inner.order.pop_front() else {
+                break;
+            };
+            if let Some(old) = inner.entries.remove(&evict) {
+                inner.size -= old.nbytes();
+            }
+        }
+        inner.size += size;
+        inner.order.push_back(key.to_string());
+        inner.entries.insert(key.to_string(), value);
+    }
+
+    /// Drop the chunk stored under `key`, if any.
+    pub fn remove(&self, key: &str) {
+        let mut inner = self.lock();
+        if let Some(old) = inner.entries.remove(key) {
+            inner.size -= old.nbytes();
+            inner.order.retain(|k| k != key);
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.entries.clear();
+        inner.order.clear();
+        inner.size = 0;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheInner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl CacheInner {
+    fn touch(&mut self, key: &str) {
+        if let Some(pos) = self.order.iter().position(|k| k == key)
+            && let Some(k) = self.order.remove(pos)
+        {
+            self.order.push_back(k);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! What this build of the crate can read.
+//!
+//! Anything outside these lists fails with
+//! [`ZarrError::UnsupportedFeature`](crate::error::ZarrError::UnsupportedFeature),
+//! whose [`FeatureId`] says what was missing, so callers can skip or fall
+//! back without matching on error text.
+
+use crate::codecs::lookup_codec_id;
+use crate::metadata::v2::ZarrCompressor;
+use crate::metadata::v3::parse_v3_dtype;
+use crate::transformers::registered_storage_transformers;
+use crate::v2::{canonical_compressor_id, compressor_to_codecs};
+
+// ---------------------------------------------------------------------------
+// FeatureId
+// ---------------------------------------------------------------------------
+
+/// A format feature that may or may not be supported.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FeatureId {
+    /// A V3 codec, by name (`"zstd"`, `"sharding_indexed"`).
+    Codec(String),
+    /// A V2 compressor, by numcodecs id.
+    Compressor(String),
+    /// A V2 filter, by numcodecs id.
+    Filter(String),
+    /// A V3 data type or data type extension, by name.
+    DataType(String),
+    /// A Zarr format version.
+    ZarrFormat(u32),
+    /// A V3 metadata extension field flagged `must_understand`, by name.
+    Extension(String),
+    /// A V3 storage transformer, by name.
+    StorageTransformer(String),
+}
+
+impl std::fmt::Display for FeatureId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FeatureId::Codec(name) => write!(f, "codec: {name}"),
+            FeatureId::Compressor(id) => write!(f, "V2 compressor: {id}"),
+            FeatureId::Filter(id) => write!(f, "V2 filter: {id}"),
+            FeatureId::DataType(name) => write!(f, "data type: {name}"),
+            FeatureId::ZarrFormat(n) => write!(f, "zarr_format: {n}"),
+            FeatureId::Extension(name) => write!(f, "V3 extension: {name}"),
+            FeatureId::StorageTransformer(name) => write!(f, "storage transformer: {name}"),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Capabilities
+// ---------------------------------------------------------------------------
+
+const ZARR_FORMATS: &[u32] = &[2];
+
+const CODECS: &[&str] = &[
+    "bytes",
+    "gzip",
+    "blosc",
+    "zlib",
+    "zstd",
+    "lz4",
+    "numcodecs.snappy",
+    "numcodecs.brotli",
+    "numcodecs.lzma",
+    "numcodecs.fixedscaleoffset",
+    "numcodecs.bitround",
+];
+
+const V2_COMPRESSORS: &[&str] = &[
+    "gzip", "blosc", "zlib", "lz4", "lz4hc", "blosclz", "zstd", "snappy", "brotli", "lzma",
+];
+
+const V2_FILTERS: &[&str] = &["fixedscaleoffset", "bitround"];
+
+const DATA_TYPES: &[&str] = &[
+    "bool",
+    "int8",
+    "int16",
+    "int32",
+    "int64",
+    "uint8",
+    "uint16",
+    "uint32",
+    "uint64",
+    "float16",
+    "float32",
+    "float64",
+    "complex64",
+    "complex128",
+    "string",
+    "bytes",
+    "numpy.datetime64",
+    "numpy.timedelta64",
+    "variable_length_utf8",
+    "variable_length_bytes",
+    "fixed_length_bytes",
+];
+
+const EXTENSIONS: &[&str] = &[];
+
+/// The features compiled into this build; see [`capabilities`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    pub zarr_formats: Vec<u32>,
+    /// V3 codec names that can be decoded.
+    pub codecs: Vec<&'static str>,
+    /// V2 compressor ids that can be decoded.
+    pub v2_compressors: Vec<&'static str>,
+    /// V2 filter ids that can be decoded.
+    pub v2_filters: Vec<&'static str>,
+    /// V3 data type names and extensions.  Raw `r<bits>` types are also
+    /// accepted for any positive multiple of 8 bits.
+    pub data_types: Vec<&'static str>,
+    /// V3 metadata extension fields that are understood.  Unknown ones are
+    /// ignored when flagged `must_understand: false`.
+    pub extensions: Vec<&'static str>,
+    /// Storage transformers registered with
+    /// [`register_storage_transformer`](crate::transformers::register_storage_transformer).
+    pub storage_transformers: Vec<String>,
+}
+
+impl Capabilities {
+    /// Whether `feature` is supported by this build.
+    pub fn supports(&self, feature: &FeatureId) -> bool {
+        match feature {
+            FeatureId::Codec(name) => self.codecs.contains(&name.as_str()),
+            FeatureId::Compressor(id) => self
+                .v2_compressors
+                .contains(&canonical_compressor_id(id).0.as_str()),
+            FeatureId::Filter(id) => self.v2_filters.contains(&id.to_lowercase().as_str()),
+            FeatureId::DataType(name) => {
+                self.data_types.contains(&name.as_str())
+                    || parse_v3_dtype(&serde_json::Value::String(name.clone())).is_ok()
+            }
+            FeatureId::ZarrFormat(n) => self.zarr_formats.contains(n),
+            FeatureId::Extension(name) => self.extensions.contains(&name.as_str()),
+            FeatureId::StorageTransformer(name) => self.storage_transformers.contains(name),
+        }
+    }
+}
+
+/// List the formats, codecs and data types this build can read.  Codecs
+/// behind disabled cargo features are left out.
+pub fn capabilities() -> Capabilities {
+    let v2_compressor_compiled = |id: &&str| {
+        let comp = ZarrCompressor {
+            id: id.to_string(),
+            config: Default::default(),
+        };
+        compressor_to_codecs(&comp)
+            .is_ok_and(|codecs| codecs.iter().all(|c| c.codec_id().is_compiled()))
+    };
+    Capabilities {
+        zarr_formats: ZARR_FORMATS.to_vec(),
+        codecs: CODECS
+            .iter()
+            .copied()
+            .filter(|name| lookup_codec_id(name).is_some_and(|id| id.is_compiled()))
+            .collect(),
+        v2_compressors: V2_COMPRESSORS
+            .iter()
+            .copied()
+            .filter(v2_compressor_compiled)
+            .collect(),
+        v2_filters: V2_FILTERS.to_vec(),
+        data_types: DATA_TYPES.to_vec(),
+        extensions: EXTENSIONS.to_vec(),
+        storage_transformers: registered_storage_transformers(),
+    }
+}
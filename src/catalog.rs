@@ -0,0 +1,202 @@
+//! Opening datasets by name.
+//!
+//! A [`Catalog`] maps dataset names to the store URL and group path where
+//! each lives, plus the read options to open its arrays with, so code asks
+//! for `"era5"` instead of repeating bucket names and options:
+//!
+//! ```json
+//! {
+//!   "datasets": {
+//!     "era5": {
+//!       "url": "s3://public-data/era5.zarr",
+//!       "anonymous": true,
+//!       "description": "ERA5 single levels, hourly",
+//!       "read_options": {"default": {"squeeze": true}}
+//!     },
+//!     "stations": {
+//!       "url": "/data/stations",
+//!       "path": "2024",
+//!       "arrays": ["t2m", "station"]
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! URLs are anything [`ObjectStoreBackend::from_url`] takes; a plain path or
+//! `file://` URL opens a [`LocalBackend`](crate::store::LocalBackend) when
+//! the `fs` feature is on.  `arrays` names the arrays to open when the group
+//! has no consolidated metadata.  `user_agent` and `headers` are sent with
+//! every request, for portals that want a token or a client id.
+//!
+//! With the `yaml` feature, [`Catalog::load`] reads `.yaml` and `.yml`
+//! files as YAML, with the same fields.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, SimplezarrConfig};
+use crate::error::{ZarrError, ZarrResult};
+use crate::group::UnifiedZarrGroup;
+use crate::options::GroupReadOptions;
+use crate::runtime;
+use crate::store::{ObjectStoreBackend, UrlOptions};
+use crate::v2;
+
+// ---------------------------------------------------------------------------
+// Catalog
+// ---------------------------------------------------------------------------
+
+/// Datasets by name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Catalog {
+    pub datasets: BTreeMap<String, CatalogEntry>,
+}
+
+/// Where one dataset lives and how to read it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CatalogEntry {
+    /// Store URL, or a local directory.
+    pub url: String,
+    /// Path of the group within the store; the store root when empty.
+    pub path: String,
+    /// Arrays to open when the group has no consolidated metadata.
+    pub arrays: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// See [`UrlOptions::anonymous`].
+    pub anonymous: bool,
+    /// See [`UrlOptions::endpoint`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// See [`UrlOptions::region`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// See [`UrlOptions::user_agent`](UrlOptions#structfield.user_agent).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// See [`UrlOptions::headers`].
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Read options attached to the group's arrays.
+    pub read_options: GroupReadOptions,
+}
+
+impl Catalog {
+    /// Parse a catalog from JSON.
+    pub fn parse(json_bytes: &[u8]) -> ZarrResult<Self> {
+        serde_json::from_slice(json_bytes)
+            .map_err(|e| ZarrError::Metadata(format!("Invalid catalog: {e}")))
+    }
+
+    /// Parse a catalog from YAML, with the same fields as the JSON form.
+    #[cfg(feature = "yaml")]
+    pub fn parse_yaml(yaml_bytes: &[u8]) -> ZarrResult<Self> {
+        serde_yaml::from_slice(yaml_bytes)
+            .map_err(|e| ZarrError::Metadata(format!("Invalid catalog: {e}")))
+    }
+
+    /// Read and parse the catalog file at `path`: YAML if it ends in
+    /// `.yaml` or `.yml` (which needs the `yaml` feature), JSON otherwise.
+    pub async fn load(path: impl AsRef<Path>) -> ZarrResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let is_yaml = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"));
+        let bytes = runtime::spawn_blocking(move || {
+            std::fs::read(&path).map_err(|e| {
+                ZarrError::Storage(format!("Failed to read catalog {}: {e}", path.display()))
+            })
+        })
+        .await??;
+        if !is_yaml {
+            return Self::parse(&bytes);
+        }
+        #[cfg(feature = "yaml")]
+        return Self::parse_yaml(&bytes);
+        #[cfg(not(feature = "yaml"))]
+        Err(ZarrError::Metadata(
+            "YAML catalogs need the `yaml` feature".into(),
+        ))
+    }
+
+    /// Dataset names, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.datasets.keys().map(String::as_str)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CatalogEntry> {
+        self.datasets.get(name)
+    }
+
+    /// Open the dataset called `name`.
+    ///
+    /// Uses the process-wide [`config::global`] configuration.
+    pub async fn open(&self, name: &str) -> ZarrResult<UnifiedZarrGroup> {
+        self.open_with_config(name, config::global()).await
+    }
+
+    /// [`open`](Self::open) with an explicit configuration.
+    pub async fn open_with_config(
+        &self,
+        name: &str,
+        config: Arc<SimplezarrConfig>,
+    ) -> ZarrResult<UnifiedZarrGroup> {
+        let entry = self
+            .get(name)
+            .ok_or_else(|| ZarrError::NotFound(format!("No dataset {name} in catalog")))?;
+        entry.open_with_config(config).await
+    }
+}
+
+impl CatalogEntry {
+    /// Open the dataset's group, with its read options attached to each
+    /// array.
+    pub async fn open_with_config(
+        &self,
+        config: Arc<SimplezarrConfig>,
+    ) -> ZarrResult<UnifiedZarrGroup> {
+        let arrays: Vec<&str> = self.arrays.iter().map(String::as_str).collect();
+        #[cfg(feature = "fs")]
+        if let Some(root) = self.local_root() {
+            let store = Arc::new(crate::store::LocalBackend::new(root));
+            let group = v2::open_group_with_config(store, &self.path, &arrays, config).await?;
+            return Ok(self.with_read_options(group));
+        }
+        let options = UrlOptions {
+            anonymous: self.anonymous,
+            endpoint: self.endpoint.clone(),
+            region: self.region.clone(),
+            user_agent: self.user_agent.clone(),
+            headers: self.headers.clone(),
+        };
+        let store = Arc::new(ObjectStoreBackend::from_url(&self.url, &options)?);
+        let group = v2::open_group_with_config(store, &self.path, &arrays, config).await?;
+        Ok(self.with_read_options(group))
+    }
+
+    /// The directory a `file://` or scheme-less URL names.
+    #[cfg(feature = "fs")]
+    fn local_root(&self) -> Option<&str> {
+        match self.url.split_once("://") {
+            Some(("file", path)) => Some(path),
+            Some(_) => None,
+            None => Some(&self.url),
+        }
+    }
+
+    fn with_read_options(&self, mut group: UnifiedZarrGroup) -> UnifiedZarrGroup {
+        group.arrays = std::mem::take(&mut group.arrays)
+            .into_iter()
+            .map(|(name, array)| {
+                let options = self.read_options.for_array(&name).clone();
+                (name, array.with_read_options(options))
+            })
+            .collect();
+        group
+    }
+}
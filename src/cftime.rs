@@ -0,0 +1,407 @@
+//! CF time coordinate decoding.
+//!
+//! Climate datasets store time as numbers with a `units` attribute such as
+//! `"days since 1970-01-01"` and an optional `calendar`.  [`decode_cf_times`]
+//! turns them into [`CfDatetime`]s (UTC) in that calendar, following the CF
+//! conventions and Python's `cftime`:
+//!
+//! - `standard`/`gregorian` is Julian before the 1582 reform and Gregorian
+//!   from 1582-10-15; the ten days in between don't exist.  It has no year
+//!   zero: 1 BC is year `-1`.
+//! - `proleptic_gregorian` is Gregorian throughout, with a year zero.
+//! - `julian` is Julian throughout, with no year zero.
+//! - `noleap`/`365_day`, `all_leap`/`366_day` and `360_day` count days in
+//!   their own calendar, so 30 February is a valid `360_day` date.
+//!
+//! [`CfDatetime::to_naive`] converts to `chrono` where that makes sense.
+//! `months` and `years` units are rejected, as their length is ambiguous.
+
+use std::fmt;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+use crate::array::UnifiedZarrArray;
+use crate::error::{ZarrError, ZarrResult};
+
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+/// Julian day number of 1582-10-15, the first Gregorian day of `standard`.
+const GREGORIAN_REFORM: i64 = 2_299_161;
+
+// ---------------------------------------------------------------------------
+// Calendar
+// ---------------------------------------------------------------------------
+
+/// A CF `calendar` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Calendar {
+    /// `standard` and `gregorian`: Julian, then Gregorian from 1582-10-15.
+    #[default]
+    Standard,
+    /// `proleptic_gregorian`: Gregorian for all dates.
+    ProlepticGregorian,
+    /// `julian`: Julian for all dates.
+    Julian,
+    /// `noleap` / `365_day`: every year has 365 days.
+    NoLeap,
+    /// `all_leap` / `366_day`: every year has 366 days.
+    AllLeap,
+    /// `360_day`: twelve months of 30 days.
+    Day360,
+}
+
+impl Calendar {
+    /// Parse a calendar name, case-insensitively.
+    pub fn parse(name: &str) -> ZarrResult<Self> {
+        match name.to_lowercase().as_str() {
+            "standard" | "gregorian" => Ok(Calendar::Standard),
+            "proleptic_gregorian" => Ok(Calendar::ProlepticGregorian),
+            "julian" => Ok(Calendar::Julian),
+            "noleap" | "365_day" => Ok(Calendar::NoLeap),
+            "all_leap" | "366_day" => Ok(Calendar::AllLeap),
+            "360_day" => Ok(Calendar::Day360),
+            _ => Err(ZarrError::Metadata(format!("Unsupported calendar: {name}"))),
+        }
+    }
+
+    /// Whether years count ..., -2, -1, 1, 2, ... as in `cftime`.
+    fn skips_year_zero(self) -> bool {
+        matches!(self, Calendar::Standard | Calendar::Julian)
+    }
+
+    /// Month lengths of the model calendars, or `None` for the real ones.
+    fn month_lengths(self) -> Option<[i64; 12]> {
+        match self {
+            Calendar::Standard | Calendar::ProlepticGregorian | Calendar::Julian => None,
+            Calendar::NoLeap => Some([31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]),
+            Calendar::AllLeap => Some([31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]),
+            Calendar::Day360 => Some([30; 12]),
+        }
+    }
+
+    /// The day number of a date (with an astronomical `year`), or `None`
+    /// if the date doesn't exist in this calendar.  Real calendars number
+    /// days by Julian day; model calendars from their year zero.
+    fn day_number(self, year: i64, month: u32, day: u32) -> Option<i64> {
+        if !(1..=12).contains(&month) || day < 1 {
+            return None;
+        }
+        if let Some(months) = self.month_lengths() {
+            if day as i64 > months[month as usize - 1] {
+                return None;
+            }
+            let year_days: i64 = months.iter().sum();
+            let day_of_year = months[..month as usize - 1].iter().sum::<i64>() + day as i64 - 1;
+            return Some(year * year_days + day_of_year);
+        }
+        let gregorian = match self {
+            Calendar::ProlepticGregorian => true,
+            Calendar::Julian => false,
+            _ if (year, month, day) >= (1582, 10, 15) => true,
+            _ if (year, month, day) >= (1582, 10, 5) => return None,
+            _ => false,
+        };
+        let leap = year % 4 == 0 && (!gregorian || year % 100 != 0 || year % 400 == 0);
+        let length = match month {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        };
+        if day > length {
+            return None;
+        }
+        let a = (14 - month as i64) / 12;
+        let y = year + 4800 - a;
+        let m = month as i64 + 12 * a - 3;
+        let days = day as i64 + (153 * m + 2) / 5 + 365 * y + y.div_euclid(4);
+        Some(if gregorian {
+            days - y.div_euclid(100) + y.div_euclid(400) - 32045
+        } else {
+            days - 32083
+        })
+    }
+
+    /// The date (with an astronomical year) of a day number.
+    fn date(self, number: i64) -> (i64, u32, u32) {
+        if let Some(months) = self.month_lengths() {
+            let year_days: i64 = months.iter().sum();
+            let mut day = number.rem_euclid(year_days);
+            let mut month = 0;
+            while day >= months[month] {
+                day -= months[month];
+                month += 1;
+            }
+            return (
+                number.div_euclid(year_days),
+                month as u32 + 1,
+                day as u32 + 1,
+            );
+        }
+        let gregorian = match self {
+            Calendar::ProlepticGregorian => true,
+            Calendar::Julian => false,
+            _ => number >= GREGORIAN_REFORM,
+        };
+        let (century, c) = if gregorian {
+            let a = number + 32044;
+            let b = (4 * a + 3).div_euclid(146_097);
+            (b, a - (146_097 * b).div_euclid(4))
+        } else {
+            (0, number + 32082)
+        };
+        let d = (4 * c + 3).div_euclid(1461);
+        let e = c - (1461 * d).div_euclid(4);
+        let m = (5 * e + 2).div_euclid(153);
+        let day = e - (153 * m + 2).div_euclid(5) + 1;
+        let month = m + 3 - 12 * (m / 10);
+        let year = 100 * century + d - 4800 + m / 10;
+        (year, month as u32, day as u32)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Dates
+// ---------------------------------------------------------------------------
+
+/// A date and time in a CF calendar, like a `cftime.datetime`.
+///
+/// `year` is numbered as `cftime` numbers it, so it skips zero in the
+/// `standard` and `julian` calendars.  Displays as
+/// `2000-02-30 12:00:00[.ffffff]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CfDatetime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub microsecond: u32,
+    pub calendar: Calendar,
+}
+
+impl CfDatetime {
+    /// The date at `micros` microseconds after the start of day 0 of
+    /// `calendar`'s day numbering.
+    fn from_micros(micros: i64, calendar: Calendar) -> Self {
+        let (year, month, day) = calendar.date(micros.div_euclid(MICROS_PER_DAY));
+        let time = micros.rem_euclid(MICROS_PER_DAY);
+        let year = if calendar.skips_year_zero() && year <= 0 {
+            year - 1
+        } else {
+            year
+        };
+        Self {
+            year,
+            month,
+            day,
+            hour: (time / 3_600_000_000) as u32,
+            minute: (time / 60_000_000 % 60) as u32,
+            second: (time / 1_000_000 % 60) as u32,
+            microsecond: (time % 1_000_000) as u32,
+            calendar,
+        }
+    }
+
+    /// Microseconds since the start of day 0 of the calendar's day
+    /// numbering, or `None` if the date doesn't exist in it.
+    fn micros(&self) -> Option<i64> {
+        let year = match self.year {
+            0 if self.calendar.skips_year_zero() => return None,
+            year if year < 0 && self.calendar.skips_year_zero() => year + 1,
+            year => year,
+        };
+        let day = self.calendar.day_number(year, self.month, self.day)?;
+        if self.hour > 23 || self.minute > 59 || self.second > 59 || self.microsecond > 999_999 {
+            return None;
+        }
+        let time = ((self.hour as i64 * 60 + self.minute as i64) * 60 + self.second as i64)
+            * 1_000_000
+            + self.microsecond as i64;
+        day.checked_mul(MICROS_PER_DAY)?.checked_add(time)
+    }
+
+    /// This date as a `chrono` value (proleptic Gregorian).
+    ///
+    /// In the `standard` and `julian` calendars this is the same instant, so
+    /// Julian 1582-10-04 becomes 1582-10-14.  In the model calendars it is
+    /// the same date and time, and `None` where that date doesn't exist in
+    /// the Gregorian calendar, like `360_day`'s 30 February.
+    pub fn to_naive(&self) -> Option<NaiveDateTime> {
+        let (year, month, day) = match self.calendar {
+            Calendar::Standard | Calendar::Julian => {
+                let number = self.micros()?.div_euclid(MICROS_PER_DAY);
+                Calendar::ProlepticGregorian.date(number)
+            }
+            _ => (self.year, self.month, self.day),
+        };
+        NaiveDate::from_ymd_opt(i32::try_from(year).ok()?, month, day)?.and_hms_micro_opt(
+            self.hour,
+            self.minute,
+            self.second,
+            self.microsecond,
+        )
+    }
+}
+
+impl fmt::Display for CfDatetime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        if self.microsecond != 0 {
+            write!(f, ".{:06}", self.microsecond)?;
+        }
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Units
+// ---------------------------------------------------------------------------
+
+/// A parsed `"<unit> since <reference>"` string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CfTimeUnits {
+    /// Length of one unit in microseconds.
+    pub unit_micros: i64,
+    /// The reference date and time, in UTC.
+    pub reference: CfDatetime,
+}
+
+impl CfTimeUnits {
+    /// Parse e.g. `"days since 1970-01-01"` or
+    /// `"hours since 1900-01-01 00:00:00.0 +01:00"`; the reference date
+    /// must exist in `calendar`.
+    pub fn parse(units: &str, calendar: Calendar) -> ZarrResult<Self> {
+        let invalid = || ZarrError::Metadata(format!("Invalid CF time units: {units:?}"));
+        let (unit, reference) = units.trim().split_once(" since ").ok_or_else(invalid)?;
+        let unit_micros = match unit.trim().to_lowercase().as_str() {
+            "days" | "day" | "d" => MICROS_PER_DAY,
+            "hours" | "hour" | "hr" | "h" => 3_600_000_000,
+            "minutes" | "minute" | "min" => 60_000_000,
+            "seconds" | "second" | "sec" | "s" => 1_000_000,
+            "milliseconds" | "millisecond" | "msec" | "ms" => 1_000,
+            "microseconds" | "microsecond" | "usec" | "us" => 1,
+            _ => return Err(invalid()),
+        };
+        let reference = parse_reference(reference.trim(), calendar).ok_or_else(invalid)?;
+        Ok(Self {
+            unit_micros,
+            reference,
+        })
+    }
+}
+
+/// Parse a reference timestamp in `calendar`: a `Y-M-D` date (the year may
+/// be negative), then optionally a time (after a space or `T`) and a UTC
+/// offset (`Z`, `UTC` or `±HH[:MM]`).
+fn parse_reference(text: &str, calendar: Calendar) -> Option<CfDatetime> {
+    let (date, rest) = match text.find([' ', 'T']) {
+        Some(i) => (&text[..i], text[i + 1..].trim()),
+        None => (text, ""),
+    };
+    let (sign, date) = match date.strip_prefix('-') {
+        Some(date) => (-1, date),
+        None => (1, date),
+    };
+    let mut fields = date.splitn(3, '-');
+    let year = sign * fields.next()?.parse::<u32>().ok()? as i64;
+    let mut fields = fields.map(|f| f.parse::<u32>().ok());
+    let (month, day) = (fields.next()??, fields.next()??);
+
+    // Split the time from the offset.
+    let rest = rest.trim_end_matches("UTC").trim_end_matches('Z').trim();
+    let (time, offset) = match rest.find(['+', '-']) {
+        Some(i) => (rest[..i].trim(), Some(rest[i..].trim())),
+        None => (rest, None),
+    };
+    let time = if time.is_empty() {
+        NaiveTime::MIN
+    } else {
+        NaiveTime::parse_from_str(time, "%H:%M:%S%.f")
+            .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+            .ok()?
+    };
+    let offset_minutes = match offset {
+        None | Some("") => 0,
+        Some(offset) => {
+            let (sign, digits) = offset.split_at(1);
+            let sign = match sign {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            let (hours, minutes) = digits.split_once(':').unwrap_or((digits, "0"));
+            sign * (hours.parse::<i64>().ok()? * 60 + minutes.parse::<i64>().ok()?)
+        }
+    };
+    let local = CfDatetime {
+        year,
+        month,
+        day,
+        hour: time.hour(),
+        minute: time.minute(),
+        second: time.second(),
+        microsecond: time.nanosecond() / 1_000,
+        calendar,
+    }
+    .micros()?;
+    Some(CfDatetime::from_micros(
+        local.checked_sub(offset_minutes * 60_000_000)?,
+        calendar,
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// Decoding
+// ---------------------------------------------------------------------------
+
+/// Decode CF time values given their `units` and `calendar` attributes
+/// (`None` means `standard`).  NaN values decode to `None`.
+pub fn decode_cf_times(
+    values: &[f64],
+    units: &str,
+    calendar: Option<&str>,
+) -> ZarrResult<Vec<Option<CfDatetime>>> {
+    let calendar = calendar
+        .map(Calendar::parse)
+        .transpose()?
+        .unwrap_or_default();
+    let units = CfTimeUnits::parse(units, calendar)?;
+    let reference = units
+        .reference
+        .micros()
+        .expect("parsed references exist in their calendar");
+    values
+        .iter()
+        .map(|&value| {
+            if value.is_nan() {
+                return Ok(None);
+            }
+            let offset = value * units.unit_micros as f64;
+            let total = (offset.is_finite() && offset.abs() <= i64::MAX as f64)
+                .then(|| reference.checked_add(offset.round() as i64))
+                .flatten()
+                .ok_or_else(|| ZarrError::Decode(format!("Time value {value} is out of range")))?;
+            Ok(Some(CfDatetime::from_micros(total, calendar)))
+        })
+        .collect()
+}
+
+impl UnifiedZarrArray {
+    /// Load the array and decode it as CF times using its `units` and
+    /// `calendar` attributes, which need
+    /// [`load_attributes`](UnifiedZarrArray::load_attributes) unless the
+    /// array came from a consolidated group.
+    pub async fn load_cf_times(&self) -> ZarrResult<Vec<Option<CfDatetime>>> {
+        let attributes = self.metadata.attributes.as_ref();
+        let attribute = |name: &str| attributes.and_then(|attrs| attrs.get_str(name));
+        let units = attribute("units")
+            .ok_or_else(|| ZarrError::Metadata("Array has no 'units' attribute".into()))?;
+        decode_cf_times(&self.load().await?, units, attribute("calendar"))
+    }
+}
@@ -0,0 +1,110 @@
+use crate::error::{ZarrError, ZarrResult};
+use crate::types::{DataType, ZarrVectorValue};
+use half::f16;
+use serde::{Deserialize, Serialize};
+
+/// numcodecs `BitRound`: floats rounded to `keepbits` mantissa bits.
+///
+/// Rounding is to nearest with ties to even, on the bits, as in numcodecs;
+/// the dropped bits become zeros, which compressors then squeeze out.
+/// Decoding is the identity, since the rounded floats are stored as floats.
+/// NaN is left as it is, where numcodecs would round its payload.
+///
+/// This is an array→array codec: encoding needs the element type, so it
+/// only runs through the pipeline's [`encode_array`](Self::encode_array).
+/// Pick `keepbits` with
+/// [`BitInformation::keepbits`](crate::stats::BitInformation::keepbits).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BitRoundCodec {
+    pub keepbits: u32,
+}
+
+impl BitRoundCodec {
+    pub fn new(keepbits: u32) -> Self {
+        Self { keepbits }
+    }
+
+    /// The element type this codec produces for `decoded`: the same one,
+    /// which must be a float type with at least `keepbits` mantissa bits.
+    pub fn encoded_data_type(&self, decoded: DataType) -> ZarrResult<DataType> {
+        let bits = mantissa_bits(decoded).ok_or_else(|| {
+            ZarrError::Codec(format!("BitRound expects float input, got {decoded}"))
+        })?;
+        if self.keepbits > bits {
+            return Err(ZarrError::Codec(format!(
+                "BitRound keepbits {} exceeds the {bits} mantissa bits of {decoded}",
+                self.keepbits
+            )));
+        }
+        Ok(decoded)
+    }
+
+    /// Round each float to `keepbits` mantissa bits.
+    pub fn encode_array(&self, values: &ZarrVectorValue) -> ZarrResult<ZarrVectorValue> {
+        let keep = self.keepbits;
+        Ok(match values {
+            ZarrVectorValue::VFloat16(v) => {
+                self.encoded_data_type(DataType::Float16)?;
+                ZarrVectorValue::VFloat16(
+                    v.iter()
+                        .map(|x| f16::from_bits(round_bits(x.to_bits() as u64, 5, 10, keep) as u16))
+                        .collect(),
+                )
+            }
+            ZarrVectorValue::VFloat32(v) => {
+                self.encoded_data_type(DataType::Float32)?;
+                ZarrVectorValue::VFloat32(
+                    v.iter()
+                        .map(|x| f32::from_bits(round_bits(x.to_bits() as u64, 8, 23, keep) as u32))
+                        .collect(),
+                )
+            }
+            ZarrVectorValue::VFloat64(v) => {
+                self.encoded_data_type(DataType::Float64)?;
+                ZarrVectorValue::VFloat64(
+                    v.iter()
+                        .map(|x| f64::from_bits(round_bits(x.to_bits(), 11, 52, keep)))
+                        .collect(),
+                )
+            }
+            _ => return Err(ZarrError::Codec("BitRound expects float input".into())),
+        })
+    }
+
+    /// Stored chunks are plain floats, so decoding copies them.
+    pub fn decode(&self, data: &[u8]) -> ZarrResult<Vec<u8>> {
+        Ok(data.to_vec())
+    }
+
+    /// Byte-level encoding can't tell the element size.
+    pub fn encode(&self, _data: &[u8]) -> ZarrResult<Vec<u8>> {
+        Err(ZarrError::Codec(
+            "BitRound needs typed values; encode it through CodecPipeline".into(),
+        ))
+    }
+}
+
+/// Explicit mantissa bits of a float type, `None` for other types.
+pub fn mantissa_bits(dtype: DataType) -> Option<u32> {
+    match dtype {
+        DataType::Float16 => Some(10),
+        DataType::Float32 => Some(23),
+        DataType::Float64 => Some(52),
+        _ => None,
+    }
+}
+
+/// Round the bit pattern of a float with `exponent` exponent and
+/// `mantissa` mantissa bits to `keep` mantissa bits, ties to even.  NaN
+/// patterns are returned unchanged.
+fn round_bits(bits: u64, exponent: u32, mantissa: u32, keep: u32) -> u64 {
+    let drop = mantissa - keep;
+    let all_ones = (1 << exponent) - 1;
+    let is_nan = (bits >> mantissa) & all_ones == all_ones && bits & ((1 << mantissa) - 1) != 0;
+    if drop == 0 || is_nan {
+        return bits;
+    }
+    let half_minus_one = (1 << (drop - 1)) - 1;
+    let rounded = bits + ((bits >> drop) & 1) + half_minus_one;
+    rounded & !((1 << drop) - 1)
+}
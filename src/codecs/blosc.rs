@@ -1,5 +1,13 @@
-use crate::error::{ZarrError, ZarrResult};
+#[cfg(not(feature = "blosc"))]
+use crate::codecs::{CodecId, not_compiled};
+use crate::config::SimplezarrConfig;
+#[cfg(feature = "blosc")]
+use crate::error::ZarrError;
+use crate::error::ZarrResult;
+#[cfg(feature = "blosc")]
+use crate::runtime;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "blosc")]
 use std::ffi::CStr;
 
 // ---------------------------------------------------------------------------
@@ -88,10 +96,25 @@ pub struct BloscCodec {
     pub clevel: i32,
     #[serde(default)]
     pub shuffle: Option<BloscShuffle>,
+    /// Bytes per block when encoding; 0 lets blosc choose from `clevel` and
+    /// `typesize`.
     #[serde(default)]
     pub blocksize: usize,
+    /// Threads blosc uses inside one encode or decode (`None` is one).
+    /// Arrays opened from metadata take their config's
+    /// [`blosc_nthreads`](crate::config::SimplezarrConfig::blosc_nthreads).
+    /// A runtime setting, so it is never read from or written to metadata.
+    #[serde(skip)]
+    pub nthreads: Option<usize>,
+    /// Ceiling on `nthreads`, from the array's config; `None` uses the
+    /// global [`blosc_max_threads`](crate::config::SimplezarrConfig::blosc_max_threads).
+    #[serde(skip)]
+    pub max_nthreads: Option<usize>,
 }
 
+/// Most threads blosc accepts for one call (`BLOSC_MAX_THREADS`).
+const BLOSC_MAX_THREADS: usize = 256;
+
 fn default_cname() -> BloscCname {
     BloscCname::Zstd
 }
@@ -108,18 +131,61 @@ impl Default for BloscCodec {
             clevel: 5,
             shuffle: Some(BloscShuffle::NoShuffle),
             blocksize: 0,
+            nthreads: None,
+            max_nthreads: None,
         }
     }
 }
 
+impl BloscCodec {
+    /// Use up to `n` internal threads per encode or decode.
+    pub fn with_nthreads(mut self, n: usize) -> Self {
+        self.nthreads = Some(n);
+        self
+    }
+
+    /// Encode in blocks of `bytes`, or let blosc choose with `None`.
+    pub fn with_blocksize(mut self, bytes: Option<usize>) -> Self {
+        self.blocksize = bytes.unwrap_or(0);
+        self
+    }
+
+    /// Take thread settings from `config`: its default thread count, unless
+    /// `nthreads` was set, and its ceiling.
+    pub fn with_config(mut self, config: &SimplezarrConfig) -> Self {
+        self.nthreads = self.nthreads.or(Some(config.blosc_nthreads));
+        self.max_nthreads = Some(config.blosc_max_threads);
+        self
+    }
+
+    /// Internal threads for the next call: `nthreads` capped by the
+    /// ceiling and by blosc's own limit, and at least one.
+    pub fn effective_nthreads(&self) -> usize {
+        let ceiling = self
+            .max_nthreads
+            .unwrap_or_else(|| crate::config::global().blosc_max_threads);
+        self.nthreads
+            .unwrap_or(1)
+            .min(ceiling)
+            .clamp(1, BLOSC_MAX_THREADS)
+    }
+}
+
+#[cfg(feature = "blosc")]
 impl BloscCodec {
     /// Decompress blosc-compressed data.
-    /// Runs on a blocking thread since decompression can be CPU-intensive.
+    /// Runs on a blocking thread since decompression can be CPU-intensive;
+    /// see [`runtime::spawn_blocking`].
     pub async fn decode(&self, data: &[u8]) -> ZarrResult<Vec<u8>> {
+        self.decode_bounded(data, usize::MAX).await
+    }
+
+    /// Decompress, rejecting buffers whose header declares more than
+    /// `max_size` bytes before allocating the output.
+    pub async fn decode_bounded(&self, data: &[u8], max_size: usize) -> ZarrResult<Vec<u8>> {
         let data = data.to_vec();
-        tokio::task::spawn_blocking(move || blosc_decompress(&data))
-            .await
-            .map_err(|e| ZarrError::Decode(format!("Blosc task join error: {e}")))?
+        let nthreads = self.effective_nthreads();
+        runtime::spawn_blocking(move || blosc_decompress(&data, max_size, nthreads)).await?
     }
 
     /// Compress data using blosc.
@@ -130,11 +196,26 @@ impl BloscCodec {
         let typesize = self.typesize.unwrap_or(1);
         let cname = self.cname;
         let blocksize = self.blocksize;
-        tokio::task::spawn_blocking(move || {
-            blosc_compress(&data, clevel, shuffle, typesize, cname, blocksize)
+        let nthreads = self.effective_nthreads();
+        runtime::spawn_blocking(move || {
+            blosc_compress(&data, clevel, shuffle, typesize, cname, blocksize, nthreads)
         })
-        .await
-        .map_err(|e| ZarrError::Encode(format!("Blosc task join error: {e}")))?
+        .await?
+    }
+}
+
+#[cfg(not(feature = "blosc"))]
+impl BloscCodec {
+    pub async fn decode(&self, _data: &[u8]) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Blosc))
+    }
+
+    pub async fn decode_bounded(&self, _data: &[u8], _max_size: usize) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Blosc))
+    }
+
+    pub async fn encode(&self, _data: &[u8]) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Blosc))
     }
 }
 
@@ -143,6 +224,7 @@ impl BloscCodec {
 // ---------------------------------------------------------------------------
 
 /// Map a `BloscCname` to the corresponding C string expected by blosc.
+#[cfg(feature = "blosc")]
 fn compressor_as_cstr(cname: BloscCname) -> &'static CStr {
     match cname {
         BloscCname::Lz4 => c"lz4",
@@ -156,6 +238,7 @@ fn compressor_as_cstr(cname: BloscCname) -> &'static CStr {
 
 /// Validate a blosc compressed buffer and return the uncompressed size.
 /// Returns `None` if the buffer is invalid.
+#[cfg(feature = "blosc")]
 fn blosc_validate(data: &[u8]) -> Option<usize> {
     let mut nbytes: usize = 0;
     let result =
@@ -167,9 +250,15 @@ fn blosc_validate(data: &[u8]) -> Option<usize> {
 ///
 /// Uses `blosc_decompress_ctx` which is thread-safe and does not require
 /// `blosc_init()`.
-fn blosc_decompress(data: &[u8]) -> ZarrResult<Vec<u8>> {
+#[cfg(feature = "blosc")]
+fn blosc_decompress(data: &[u8], max_size: usize, nthreads: usize) -> ZarrResult<Vec<u8>> {
     let nbytes = blosc_validate(data)
         .ok_or_else(|| ZarrError::Decode("Blosc encoded value is invalid".into()))?;
+    if nbytes > max_size {
+        return Err(ZarrError::Decode(format!(
+            "Blosc buffer declares {nbytes} bytes, exceeding the limit of {max_size}"
+        )));
+    }
 
     if nbytes == 0 {
         return Ok(Vec::new());
@@ -181,7 +270,7 @@ fn blosc_decompress(data: &[u8]) -> ZarrResult<Vec<u8>> {
             data.as_ptr().cast(),
             output.as_mut_ptr().cast(),
             output.len(),
-            1, // numinternalthreads
+            nthreads as i32,
         )
     };
     if result < 0 {
@@ -196,6 +285,7 @@ fn blosc_decompress(data: &[u8]) -> ZarrResult<Vec<u8>> {
 ///
 /// Uses `blosc_compress_ctx` which is thread-safe, does not require
 /// `blosc_init()`, and accepts the compressor name directly (no global state).
+#[cfg(feature = "blosc")]
 fn blosc_compress(
     data: &[u8],
     clevel: i32,
@@ -203,6 +293,7 @@ fn blosc_compress(
     typesize: usize,
     cname: BloscCname,
     blocksize: usize,
+    nthreads: usize,
 ) -> ZarrResult<Vec<u8>> {
     let shuffle_int = match shuffle {
         BloscShuffle::NoShuffle => blosc_src::BLOSC_NOSHUFFLE as i32,
@@ -224,7 +315,7 @@ fn blosc_compress(
             destsize,
             compressor_as_cstr(cname).as_ptr(),
             blocksize,
-            1, // numinternalthreads
+            nthreads as i32,
         )
     };
 
@@ -0,0 +1,86 @@
+#[cfg(feature = "brotli")]
+use crate::codecs::read_to_end_limited;
+#[cfg(not(feature = "brotli"))]
+use crate::codecs::{CodecId, not_compiled};
+#[cfg(feature = "brotli")]
+use crate::error::ZarrError;
+use crate::error::ZarrResult;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "brotli")]
+use std::io::Read;
+
+/// Buffer size for the streaming encoder and decoder.
+#[cfg(feature = "brotli")]
+const BUFFER_SIZE: usize = 4096;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrotliCodec {
+    /// Quality, 0 (fastest) to 11 (smallest).
+    #[serde(default = "default_level")]
+    pub level: u32,
+    /// Window size as a power of two, 10 to 24.
+    #[serde(default = "default_lgwin")]
+    pub lgwin: u32,
+}
+
+fn default_level() -> u32 {
+    11
+}
+
+fn default_lgwin() -> u32 {
+    22
+}
+
+impl Default for BrotliCodec {
+    fn default() -> Self {
+        Self {
+            level: 11,
+            lgwin: 22,
+        }
+    }
+}
+
+#[cfg(feature = "brotli")]
+impl BrotliCodec {
+    pub fn decode(&self, data: &[u8]) -> ZarrResult<Vec<u8>> {
+        self.decode_bounded(data, usize::MAX)
+    }
+
+    /// Decode, failing once the output would exceed `max_size` bytes.
+    pub fn decode_bounded(&self, data: &[u8], max_size: usize) -> ZarrResult<Vec<u8>> {
+        let decoder = brotli::Decompressor::new(data, BUFFER_SIZE);
+        read_to_end_limited(decoder, max_size, "Brotli")
+    }
+
+    /// Encode, failing for a level above 11 or a window outside 10 to 24
+    /// rather than writing with a setting other than the one asked for.
+    pub fn encode(&self, data: &[u8]) -> ZarrResult<Vec<u8>> {
+        if self.level > 11 || !(10..=24).contains(&self.lgwin) {
+            return Err(ZarrError::Encode(format!(
+                "Brotli level {} or window {} out of range (expected 0..=11 and 10..=24)",
+                self.level, self.lgwin
+            )));
+        }
+        let mut encoder = brotli::CompressorReader::new(data, BUFFER_SIZE, self.level, self.lgwin);
+        let mut out = Vec::new();
+        encoder
+            .read_to_end(&mut out)
+            .map_err(|e| ZarrError::Encode(format!("Brotli compress failed: {e}")))?;
+        Ok(out)
+    }
+}
+
+#[cfg(not(feature = "brotli"))]
+impl BrotliCodec {
+    pub fn decode(&self, _data: &[u8]) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Brotli))
+    }
+
+    pub fn decode_bounded(&self, _data: &[u8], _max_size: usize) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Brotli))
+    }
+
+    pub fn encode(&self, _data: &[u8]) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Brotli))
+    }
+}
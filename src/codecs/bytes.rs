@@ -10,11 +10,12 @@ pub struct BytesCodec {
     pub endian: Option<Endian>,
 }
 
+/// No `endian`: chunks are read in the array's
+/// [`default_endian`](crate::options::ArrayReadOptions::default_endian)
+/// and written little-endian.
 impl Default for BytesCodec {
     fn default() -> Self {
-        Self {
-            endian: Some(Endian::Little),
-        }
+        Self { endian: None }
     }
 }
 
@@ -41,6 +42,7 @@ impl Serialize for Endian {
             Endian::Little => serializer.serialize_str("little"),
             Endian::Big => serializer.serialize_str("big"),
             Endian::NotApplicable => serializer.serialize_str("not_applicable"),
+            Endian::Native => serializer.serialize_str("native"),
         }
     }
 }
@@ -52,6 +54,7 @@ impl<'de> Deserialize<'de> for Endian {
             "little" => Ok(Endian::Little),
             "big" => Ok(Endian::Big),
             "not_applicable" | "na" | "" => Ok(Endian::NotApplicable),
+            "native" => Ok(Endian::Native),
             other => Err(serde::de::Error::custom(format!(
                 "Unknown endian: {other}"
             ))),
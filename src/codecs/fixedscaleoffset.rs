@@ -1,8 +1,22 @@
 use crate::error::{ZarrError, ZarrResult};
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use crate::metadata::v2::parse_numpy_dtype;
+use crate::types::{
+    DataType, Endian, ZarrVectorValue, bytes_to_zarr_vector, f64_to_zarr_vector,
+    zarr_vector_to_bytes,
+};
 use serde::{Deserialize, Serialize};
-use std::io::Cursor;
 
+/// numcodecs `FixedScaleOffset`: floats stored as scaled integers.
+///
+/// `dtype` is the decoded (float) type and `astype` the stored integer type,
+/// each given either as a name (`"float64"`, `"int16"`) or a NumPy string
+/// (`"<f8"`, `"<i2"`).  As in numcodecs, decoding computes
+/// `stored / scale + offset`.
+///
+/// This is an array→array codec: the pipeline hands it typed values through
+/// [`decode_array`](Self::decode_array) and [`encode_array`](Self::encode_array).
+/// The byte-level `decode`/`encode` read NumPy byte orders from the type
+/// strings and treat named types as little-endian.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FixedScaleOffsetCodec {
     pub scale: f64,
@@ -12,109 +26,131 @@ pub struct FixedScaleOffsetCodec {
 }
 
 impl FixedScaleOffsetCodec {
-    pub fn decode(&self, data: &[u8]) -> ZarrResult<Vec<u8>> {
-        match (self.astype.as_str(), self.dtype.as_str()) {
-            ("int16", "float32") => self.decode_int_to_float::<i16>(data, 2),
-            ("int32", "float32") => self.decode_int_to_float::<i32>(data, 4),
-            ("uint16", "float32") => self.decode_uint_to_float::<u16>(data, 2),
-            ("uint32", "float32") => self.decode_uint_to_float::<u32>(data, 4),
-            (a, d) => Err(ZarrError::Decode(format!(
-                "Unsupported FixedScaleOffset conversion: {a} -> {d}"
-            ))),
+    /// The element type this codec produces when encoding an array of
+    /// `decoded`, which must match `dtype`.
+    pub fn encoded_data_type(&self, decoded: DataType) -> ZarrResult<DataType> {
+        let (encoded, expected) = self.types()?;
+        if decoded != expected {
+            return Err(ZarrError::Codec(format!(
+                "FixedScaleOffset expects {} input, got {decoded}",
+                self.dtype
+            )));
         }
+        Ok(encoded)
     }
 
-    pub fn encode(&self, _data: &[u8]) -> ZarrResult<Vec<u8>> {
-        Err(ZarrError::Encode(
-            "FixedScaleOffsetCodec encoding not implemented".into(),
-        ))
+    /// Decode stored integers into floats.
+    pub fn decode_array(&self, values: &ZarrVectorValue) -> ZarrResult<ZarrVectorValue> {
+        let (_, decoded) = self.types()?;
+        let scaled: Vec<f64> = values
+            .to_f64_vec()?
+            .iter()
+            .map(|v| v / self.scale + self.offset)
+            .collect();
+        f64_to_zarr_vector(decoded, &scaled)
     }
 
-    fn decode_int_to_float<T>(&self, data: &[u8], elem_bytes: usize) -> ZarrResult<Vec<u8>>
-    where
-        T: ReadableInt,
-    {
-        let count = data.len() / elem_bytes;
-        let mut cursor = Cursor::new(data);
-        let mut out = Vec::with_capacity(count * 4);
-        let mut writer = std::io::Cursor::new(&mut out);
-        for _ in 0..count {
-            let ival = T::read_le(&mut cursor)
-                .map_err(|e| ZarrError::Decode(format!("FixedScaleOffset read: {e}")))?;
-            let fval = ival.to_f64() * self.scale + self.offset;
-            writer
-                .write_f32::<LittleEndian>(fval as f32)
-                .map_err(|e| ZarrError::Decode(format!("FixedScaleOffset write: {e}")))?;
-        }
-        Ok(out)
+    /// Encode floats as `round((value - offset) * scale)`, saturating at the
+    /// bounds of the stored integer type.  Halves round to even, as numpy's
+    /// `around` does in numcodecs.
+    pub fn encode_array(&self, values: &ZarrVectorValue) -> ZarrResult<ZarrVectorValue> {
+        let (encoded, _) = self.types()?;
+        let stored: Vec<f64> = values
+            .to_f64_vec()?
+            .iter()
+            .map(|v| ((v - self.offset) * self.scale).round_ties_even())
+            .collect();
+        f64_to_zarr_vector(encoded, &stored)
     }
 
-    fn decode_uint_to_float<T>(&self, data: &[u8], elem_bytes: usize) -> ZarrResult<Vec<u8>>
-    where
-        T: ReadableUInt,
-    {
-        let count = data.len() / elem_bytes;
-        let mut cursor = Cursor::new(data);
-        let mut out = Vec::with_capacity(count * 4);
-        let mut writer = std::io::Cursor::new(&mut out);
-        for _ in 0..count {
-            let uval = T::read_le(&mut cursor)
-                .map_err(|e| ZarrError::Decode(format!("FixedScaleOffset read: {e}")))?;
-            let fval = uval.to_f64() * self.scale + self.offset;
-            writer
-                .write_f32::<LittleEndian>(fval as f32)
-                .map_err(|e| ZarrError::Decode(format!("FixedScaleOffset write: {e}")))?;
-        }
-        Ok(out)
+    pub fn decode(&self, data: &[u8]) -> ZarrResult<Vec<u8>> {
+        let (encoded, _) = self.types()?;
+        let values = bytes_to_zarr_vector(byte_order(&self.astype), encoded, data)?;
+        zarr_vector_to_bytes(byte_order(&self.dtype), &self.decode_array(&values)?)
     }
-}
-
-trait ReadableInt {
-    fn read_le(cursor: &mut Cursor<&[u8]>) -> std::io::Result<Self>
-    where
-        Self: Sized;
-    fn to_f64(self) -> f64;
-}
 
-impl ReadableInt for i16 {
-    fn read_le(cursor: &mut Cursor<&[u8]>) -> std::io::Result<Self> {
-        cursor.read_i16::<LittleEndian>()
+    /// Decode, rejecting inputs whose decoded form would exceed `max_size`
+    /// bytes before decoding them.
+    pub fn decode_bounded(&self, data: &[u8], max_size: usize) -> ZarrResult<Vec<u8>> {
+        let (encoded, decoded) = self.types()?;
+        let decoded_len = data.len() / element_size(encoded) * element_size(decoded);
+        if decoded_len > max_size {
+            return Err(ZarrError::Decode(format!(
+                "FixedScaleOffset output of {decoded_len} bytes exceeds the limit of {max_size}"
+            )));
+        }
+        self.decode(data)
     }
-    fn to_f64(self) -> f64 {
-        self as f64
+
+    pub fn encode(&self, data: &[u8]) -> ZarrResult<Vec<u8>> {
+        let (_, decoded) = self.types()?;
+        let values = bytes_to_zarr_vector(byte_order(&self.dtype), decoded, data)?;
+        zarr_vector_to_bytes(byte_order(&self.astype), &self.encode_array(&values)?)
     }
-}
 
-impl ReadableInt for i32 {
-    fn read_le(cursor: &mut Cursor<&[u8]>) -> std::io::Result<Self> {
-        cursor.read_i32::<LittleEndian>()
+    /// Upper bound on the encoded size of `decoded_len` decoded bytes, or
+    /// `None` if either type is unsupported.
+    pub fn max_encoded_len(&self, decoded_len: usize) -> Option<usize> {
+        let (encoded, decoded) = self.types().ok()?;
+        Some(decoded_len / element_size(decoded) * element_size(encoded))
     }
-    fn to_f64(self) -> f64 {
-        self as f64
+
+    /// The stored (`astype`) and decoded (`dtype`) element types.
+    pub fn types(&self) -> ZarrResult<(DataType, DataType)> {
+        let unsupported = || {
+            ZarrError::Codec(format!(
+                "Unsupported FixedScaleOffset conversion: {} -> {}",
+                self.astype, self.dtype
+            ))
+        };
+        let encoded = parse_type(&self.astype).ok_or_else(unsupported)?;
+        let decoded = parse_type(&self.dtype).ok_or_else(unsupported)?;
+        let int_encoded = matches!(
+            encoded,
+            DataType::Int8
+                | DataType::Int16
+                | DataType::Int32
+                | DataType::Int64
+                | DataType::UInt8
+                | DataType::UInt16
+                | DataType::UInt32
+                | DataType::UInt64
+        );
+        let float_decoded = matches!(decoded, DataType::Float32 | DataType::Float64);
+        if int_encoded && float_decoded {
+            Ok((encoded, decoded))
+        } else {
+            Err(unsupported())
+        }
     }
 }
 
-trait ReadableUInt {
-    fn read_le(cursor: &mut Cursor<&[u8]>) -> std::io::Result<Self>
-    where
-        Self: Sized;
-    fn to_f64(self) -> f64;
+/// Parse a numcodecs type given as a name or a NumPy dtype string.
+fn parse_type(name: &str) -> Option<DataType> {
+    let dt = match name {
+        "int8" => DataType::Int8,
+        "int16" => DataType::Int16,
+        "int32" => DataType::Int32,
+        "int64" => DataType::Int64,
+        "uint8" => DataType::UInt8,
+        "uint16" => DataType::UInt16,
+        "uint32" => DataType::UInt32,
+        "uint64" => DataType::UInt64,
+        "float32" => DataType::Float32,
+        "float64" => DataType::Float64,
+        _ => return parse_numpy_dtype(name).ok().map(|d| d.data_type),
+    };
+    Some(dt)
 }
 
-impl ReadableUInt for u16 {
-    fn read_le(cursor: &mut Cursor<&[u8]>) -> std::io::Result<Self> {
-        cursor.read_u16::<LittleEndian>()
-    }
-    fn to_f64(self) -> f64 {
-        self as f64
+/// Byte order of a NumPy type string; little-endian for names.
+fn byte_order(type_name: &str) -> Endian {
+    match parse_numpy_dtype(type_name).map(|d| d.byte_order.resolve()) {
+        Ok(Endian::Big) => Endian::Big,
+        _ => Endian::Little,
     }
 }
 
-impl ReadableUInt for u32 {
-    fn read_le(cursor: &mut Cursor<&[u8]>) -> std::io::Result<Self> {
-        cursor.read_u32::<LittleEndian>()
-    }
-    fn to_f64(self) -> f64 {
-        self as f64
-    }
+fn element_size(dtype: DataType) -> usize {
+    dtype.byte_size().unwrap_or(1)
 }
@@ -1,6 +1,11 @@
-use crate::error::{ZarrError, ZarrResult};
+#[cfg(not(feature = "lz4"))]
+use crate::codecs::{CodecId, not_compiled};
+#[cfg(feature = "lz4")]
+use crate::error::ZarrError;
+use crate::error::ZarrResult;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "lz4")]
 const LZ4_SIZE_PREFIX_BYTES: usize = 4;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,10 +24,17 @@ impl Default for Lz4Codec {
     }
 }
 
+#[cfg(feature = "lz4")]
 impl Lz4Codec {
     /// Decode an LZ4 block that has a 4-byte little-endian size prefix
     /// (matching the Zarr / numcodecs convention).
     pub fn decode(&self, data: &[u8]) -> ZarrResult<Vec<u8>> {
+        self.decode_bounded(data, usize::MAX)
+    }
+
+    /// Decode, rejecting blocks whose declared size exceeds `max_size` bytes
+    /// before allocating the output buffer.
+    pub fn decode_bounded(&self, data: &[u8], max_size: usize) -> ZarrResult<Vec<u8>> {
         if data.len() < LZ4_SIZE_PREFIX_BYTES {
             return Err(ZarrError::Decode(
                 "LZ4 decode: compressed buffer missing 4-byte size prefix".into(),
@@ -32,6 +44,11 @@ impl Lz4Codec {
         let (prefix, payload) = data.split_at(LZ4_SIZE_PREFIX_BYTES);
         let dest_size =
             u32::from_le_bytes(prefix.try_into().unwrap()) as usize;
+        if dest_size > max_size {
+            return Err(ZarrError::Decode(format!(
+                "LZ4 block declares {dest_size} bytes, exceeding the limit of {max_size}"
+            )));
+        }
 
         let decompressed = lz4_flex::block::decompress(payload, dest_size)
             .map_err(|e| ZarrError::Decode(format!("LZ4 decompress failed: {e}")))?;
@@ -57,3 +74,18 @@ impl Lz4Codec {
         Ok(out)
     }
 }
+
+#[cfg(not(feature = "lz4"))]
+impl Lz4Codec {
+    pub fn decode(&self, _data: &[u8]) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Lz4))
+    }
+
+    pub fn decode_bounded(&self, _data: &[u8], _max_size: usize) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Lz4))
+    }
+
+    pub fn encode(&self, _data: &[u8]) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Lz4))
+    }
+}
@@ -0,0 +1,144 @@
+#[cfg(feature = "lzma")]
+use crate::codecs::read_to_end_limited;
+#[cfg(not(feature = "lzma"))]
+use crate::codecs::{CodecId, not_compiled};
+#[cfg(feature = "lzma")]
+use crate::error::ZarrError;
+use crate::error::ZarrResult;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "lzma")]
+use std::io::Read;
+#[cfg(feature = "lzma")]
+use xz2::stream::{Check, LzmaOptions, Stream};
+
+/// numcodecs' `lzma.FORMAT_XZ`.
+pub const FORMAT_XZ: i32 = 1;
+/// numcodecs' `lzma.FORMAT_ALONE` (legacy `.lzma`).
+pub const FORMAT_ALONE: i32 = 2;
+/// numcodecs' `lzma.FORMAT_RAW`, which needs an explicit filter chain.
+pub const FORMAT_RAW: i32 = 3;
+
+/// Decoder memory allowed even when the output limit is smaller: the
+/// dictionary is sized by the writer's preset (64 MiB at preset 9), not by
+/// the chunk.
+#[cfg(feature = "lzma")]
+const MIN_DECODER_MEMLIMIT: u64 = 128 << 20;
+
+/// numcodecs' `LZMA` compressor, via liblzma.  The xz and alone formats are
+/// supported; raw streams and custom filter chains are not.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LzmaCodec {
+    #[serde(default = "default_format")]
+    pub format: i32,
+    /// Integrity check for the xz format: -1 (default, CRC64), 0 (none),
+    /// 1 (CRC32), 4 (CRC64) or 10 (SHA-256).
+    #[serde(default = "default_check")]
+    pub check: i32,
+    /// Compression preset 0-9, optionally `| 0x80000000` for "extreme"
+    /// (`None` is 6).
+    #[serde(default)]
+    pub preset: Option<u32>,
+    #[serde(default)]
+    pub filters: Option<serde_json::Value>,
+}
+
+fn default_format() -> i32 {
+    FORMAT_XZ
+}
+
+fn default_check() -> i32 {
+    -1
+}
+
+impl Default for LzmaCodec {
+    fn default() -> Self {
+        Self {
+            format: FORMAT_XZ,
+            check: -1,
+            preset: None,
+            filters: None,
+        }
+    }
+}
+
+#[cfg(feature = "lzma")]
+impl LzmaCodec {
+    pub fn decode(&self, data: &[u8]) -> ZarrResult<Vec<u8>> {
+        self.decode_bounded(data, usize::MAX)
+    }
+
+    /// Decode, failing once the output would exceed `max_size` bytes.  Xz
+    /// and alone streams are told apart by their headers, as Python's `lzma`
+    /// does.
+    pub fn decode_bounded(&self, data: &[u8], max_size: usize) -> ZarrResult<Vec<u8>> {
+        if self.format == FORMAT_RAW {
+            return Err(raw_unsupported());
+        }
+        let memlimit = u64::try_from(max_size)
+            .unwrap_or(u64::MAX)
+            .max(MIN_DECODER_MEMLIMIT);
+        let stream = Stream::new_auto_decoder(memlimit, xz2::stream::CONCATENATED)
+            .map_err(|e| ZarrError::Decode(format!("LZMA decoder init failed: {e}")))?;
+        read_to_end_limited(
+            xz2::read::XzDecoder::new_stream(data, stream),
+            max_size,
+            "LZMA",
+        )
+    }
+
+    pub fn encode(&self, data: &[u8]) -> ZarrResult<Vec<u8>> {
+        let encode_err =
+            |e: xz2::stream::Error| ZarrError::Encode(format!("LZMA encoder init failed: {e}"));
+        if self.filters.as_ref().is_some_and(|f| !f.is_null()) {
+            return Err(ZarrError::Codec(
+                "LZMA filter chains are not supported for encoding".into(),
+            ));
+        }
+        let preset = self.preset.unwrap_or(6);
+        let stream = match self.format {
+            FORMAT_XZ => Stream::new_easy_encoder(preset, self.integrity_check()?),
+            FORMAT_ALONE => LzmaOptions::new_preset(preset)
+                .and_then(|options| Stream::new_lzma_encoder(&options)),
+            FORMAT_RAW => return Err(raw_unsupported()),
+            other => {
+                return Err(ZarrError::Codec(format!("Unknown LZMA format: {other}")));
+            }
+        }
+        .map_err(encode_err)?;
+        let mut out = Vec::new();
+        xz2::read::XzEncoder::new_stream(data, stream)
+            .read_to_end(&mut out)
+            .map_err(|e| ZarrError::Encode(format!("LZMA compress failed: {e}")))?;
+        Ok(out)
+    }
+
+    fn integrity_check(&self) -> ZarrResult<Check> {
+        match self.check {
+            -1 | 4 => Ok(Check::Crc64),
+            0 => Ok(Check::None),
+            1 => Ok(Check::Crc32),
+            10 => Ok(Check::Sha256),
+            other => Err(ZarrError::Codec(format!("Unknown LZMA check: {other}"))),
+        }
+    }
+}
+
+#[cfg(feature = "lzma")]
+fn raw_unsupported() -> ZarrError {
+    ZarrError::Codec("Raw LZMA streams (format 3) are not supported".into())
+}
+
+#[cfg(not(feature = "lzma"))]
+impl LzmaCodec {
+    pub fn decode(&self, _data: &[u8]) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Lzma))
+    }
+
+    pub fn decode_bounded(&self, _data: &[u8], _max_size: usize) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Lzma))
+    }
+
+    pub fn encode(&self, _data: &[u8]) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Lzma))
+    }
+}
@@ -1,14 +1,25 @@
+pub mod bitround;
 pub mod blosc;
+pub mod brotli;
 pub mod bytes;
 pub mod fixedscaleoffset;
 pub mod gzip;
 pub mod lz4;
+pub mod lzma;
 pub mod sharding;
+pub mod snappy;
 pub mod zlib;
 pub mod zstd;
 
+use crate::array::expected_chunk_bytes;
+use crate::capabilities::FeatureId;
 use crate::error::{ZarrError, ZarrResult};
+use crate::options::{ArrayReadOptions, BoolEncoding};
+use crate::types::{
+    DataType, Endian, ZarrVectorValue, bytes_to_zarr_vector, unpack_bools, zarr_vector_to_bytes,
+};
 use serde::{Deserialize, Serialize};
+use std::io::Read;
 
 // ---------------------------------------------------------------------------
 // CodecId
@@ -22,8 +33,30 @@ pub enum CodecId {
     Zlib,
     Zstd,
     Lz4,
+    Snappy,
+    Brotli,
+    Lzma,
     Sharding,
     FixedScaleOffset,
+    BitRound,
+}
+
+impl CodecId {
+    /// Whether this build can run the codec; some sit behind cargo features.
+    pub fn is_compiled(&self) -> bool {
+        let gated = [
+            (CodecId::Blosc, cfg!(feature = "blosc")),
+            (CodecId::Zstd, cfg!(feature = "zstd")),
+            (CodecId::Lz4, cfg!(feature = "lz4")),
+            (CodecId::Snappy, cfg!(feature = "snappy")),
+            (CodecId::Brotli, cfg!(feature = "brotli")),
+            (CodecId::Lzma, cfg!(feature = "lzma")),
+        ];
+        gated
+            .iter()
+            .find(|(id, _)| id == self)
+            .is_none_or(|&(_, enabled)| enabled)
+    }
 }
 
 impl std::fmt::Display for CodecId {
@@ -35,12 +68,28 @@ impl std::fmt::Display for CodecId {
             CodecId::Zlib => write!(f, "zlib"),
             CodecId::Zstd => write!(f, "zstd"),
             CodecId::Lz4 => write!(f, "lz4"),
+            CodecId::Snappy => write!(f, "numcodecs.snappy"),
+            CodecId::Brotli => write!(f, "numcodecs.brotli"),
+            CodecId::Lzma => write!(f, "numcodecs.lzma"),
             CodecId::Sharding => write!(f, "sharding_indexed"),
             CodecId::FixedScaleOffset => write!(f, "numcodecs.fixedscaleoffset"),
+            CodecId::BitRound => write!(f, "numcodecs.bitround"),
         }
     }
 }
 
+/// Where a codec sits in the V3 codec model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CodecKind {
+    /// Typed array in, typed array out (filters such as FixedScaleOffset and
+    /// BitRound).
+    ArrayToArray,
+    /// Typed array to serialised bytes (the bytes and sharding codecs).
+    ArrayToBytes,
+    /// Bytes in, bytes out (compressors).
+    BytesToBytes,
+}
+
 // ---------------------------------------------------------------------------
 // AnyCodec  (enum dispatch, no Box<dyn>)
 // ---------------------------------------------------------------------------
@@ -53,8 +102,12 @@ pub enum AnyCodec {
     Zlib(zlib::ZlibCodec),
     Zstd(zstd::ZstdCodec),
     Lz4(lz4::Lz4Codec),
+    Snappy(snappy::SnappyCodec),
+    Brotli(brotli::BrotliCodec),
+    Lzma(lzma::LzmaCodec),
     Sharding(sharding::ShardingCodec),
     FixedScaleOffset(fixedscaleoffset::FixedScaleOffsetCodec),
+    BitRound(bitround::BitRoundCodec),
 }
 
 impl AnyCodec {
@@ -66,8 +119,28 @@ impl AnyCodec {
             AnyCodec::Zlib(_) => CodecId::Zlib,
             AnyCodec::Zstd(_) => CodecId::Zstd,
             AnyCodec::Lz4(_) => CodecId::Lz4,
+            AnyCodec::Snappy(_) => CodecId::Snappy,
+            AnyCodec::Brotli(_) => CodecId::Brotli,
+            AnyCodec::Lzma(_) => CodecId::Lzma,
             AnyCodec::Sharding(_) => CodecId::Sharding,
             AnyCodec::FixedScaleOffset(_) => CodecId::FixedScaleOffset,
+            AnyCodec::BitRound(_) => CodecId::BitRound,
+        }
+    }
+
+    /// The pipeline stage this codec belongs to.
+    pub fn kind(&self) -> CodecKind {
+        match self {
+            AnyCodec::FixedScaleOffset(_) | AnyCodec::BitRound(_) => CodecKind::ArrayToArray,
+            AnyCodec::Bytes(_) | AnyCodec::Sharding(_) => CodecKind::ArrayToBytes,
+            AnyCodec::Gzip(_)
+            | AnyCodec::Blosc(_)
+            | AnyCodec::Zlib(_)
+            | AnyCodec::Zstd(_)
+            | AnyCodec::Lz4(_)
+            | AnyCodec::Snappy(_)
+            | AnyCodec::Brotli(_)
+            | AnyCodec::Lzma(_) => CodecKind::BytesToBytes,
         }
     }
 
@@ -80,10 +153,43 @@ impl AnyCodec {
             AnyCodec::Zlib(c) => c.decode(data),
             AnyCodec::Zstd(c) => c.decode(data),
             AnyCodec::Lz4(c) => c.decode(data),
-            AnyCodec::Sharding(_) => Err(ZarrError::Codec(
-                "Sharding codec decoding requires additional context".into(),
-            )),
+            AnyCodec::Snappy(c) => c.decode(data),
+            AnyCodec::Brotli(c) => c.decode(data),
+            AnyCodec::Lzma(c) => c.decode(data),
+            AnyCodec::Sharding(_) => Err(sharding_unsupported()),
             AnyCodec::FixedScaleOffset(c) => c.decode(data),
+            AnyCodec::BitRound(c) => c.decode(data),
+        }
+    }
+
+    /// Decode bytes using this codec, failing if the output would exceed
+    /// `max_size` bytes.  Codecs whose framing records the decoded size check
+    /// it before allocating.
+    pub async fn decode_bounded(&self, data: &[u8], max_size: usize) -> ZarrResult<Vec<u8>> {
+        match self {
+            AnyCodec::Bytes(c) => c.decode(data),
+            AnyCodec::Gzip(c) => c.decode_bounded(data, max_size),
+            AnyCodec::Blosc(c) => c.decode_bounded(data, max_size).await,
+            AnyCodec::Zlib(c) => c.decode_bounded(data, max_size),
+            AnyCodec::Zstd(c) => c.decode_bounded(data, max_size),
+            AnyCodec::Lz4(c) => c.decode_bounded(data, max_size),
+            AnyCodec::Snappy(c) => c.decode_bounded(data, max_size),
+            AnyCodec::Brotli(c) => c.decode_bounded(data, max_size),
+            AnyCodec::Lzma(c) => c.decode_bounded(data, max_size),
+            AnyCodec::Sharding(_) => Err(sharding_unsupported()),
+            AnyCodec::FixedScaleOffset(c) => c.decode_bounded(data, max_size),
+            AnyCodec::BitRound(c) => c.decode(data),
+        }
+    }
+
+    /// Largest input this codec may be given when its decoded output is at
+    /// most `decoded_len` bytes.  Compressed sizes have no useful bound, so
+    /// compressors return `usize::MAX`.
+    fn max_input_len(&self, decoded_len: usize) -> usize {
+        match self {
+            AnyCodec::Bytes(_) | AnyCodec::BitRound(_) => decoded_len,
+            AnyCodec::FixedScaleOffset(c) => c.max_encoded_len(decoded_len).unwrap_or(usize::MAX),
+            _ => usize::MAX,
         }
     }
 
@@ -96,10 +202,12 @@ impl AnyCodec {
             AnyCodec::Zlib(c) => c.encode(data),
             AnyCodec::Zstd(c) => c.encode(data),
             AnyCodec::Lz4(c) => c.encode(data),
-            AnyCodec::Sharding(_) => Err(ZarrError::Codec(
-                "Sharding codec encoding requires additional context".into(),
-            )),
+            AnyCodec::Snappy(c) => c.encode(data),
+            AnyCodec::Brotli(c) => c.encode(data),
+            AnyCodec::Lzma(c) => c.encode(data),
+            AnyCodec::Sharding(_) => Err(sharding_unsupported()),
             AnyCodec::FixedScaleOffset(c) => c.encode(data),
+            AnyCodec::BitRound(c) => c.encode(data),
         }
     }
 
@@ -112,20 +220,341 @@ impl AnyCodec {
     }
 }
 
+/// A short, readable form of the codec and its settings, such as
+/// `blosc(zstd, clevel=5, shuffle)`, `gzip(level=5)` or `bytes(BE)`.
+impl std::fmt::Display for AnyCodec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnyCodec::Bytes(c) => match c.endian {
+                Some(endian) => write!(f, "bytes({endian})"),
+                None => f.write_str("bytes"),
+            },
+            AnyCodec::Gzip(c) => write!(f, "gzip(level={})", c.level),
+            AnyCodec::Blosc(c) => {
+                let shuffle = match c.shuffle {
+                    Some(blosc::BloscShuffle::Shuffle) => "shuffle",
+                    Some(blosc::BloscShuffle::BitShuffle) => "bitshuffle",
+                    _ => "noshuffle",
+                };
+                write!(f, "blosc({}, clevel={}, {shuffle})", c.cname, c.clevel)
+            }
+            AnyCodec::Zlib(c) => write!(f, "zlib(level={})", c.level),
+            AnyCodec::Zstd(c) => {
+                write!(f, "zstd(level={}", c.level)?;
+                if let Some(window_log) = c.window_log_max {
+                    write!(f, ", window_log_max={window_log}")?;
+                }
+                if c.long_distance_matching {
+                    f.write_str(", long")?;
+                }
+                f.write_str(")")
+            }
+            AnyCodec::Lz4(c) => write!(f, "lz4(acceleration={})", c.acceleration),
+            AnyCodec::Snappy(_) => f.write_str("snappy"),
+            AnyCodec::Brotli(c) => write!(f, "brotli(level={}, lgwin={})", c.level, c.lgwin),
+            AnyCodec::Lzma(c) => match c.preset {
+                Some(preset) => write!(f, "lzma(preset={preset})"),
+                None => f.write_str("lzma"),
+            },
+            AnyCodec::Sharding(c) => write!(f, "sharding(chunk_shape={:?})", c.chunk_shape),
+            AnyCodec::FixedScaleOffset(c) => write!(
+                f,
+                "fixedscaleoffset(scale={}, offset={}, {} → {})",
+                c.scale, c.offset, c.dtype, c.astype
+            ),
+            AnyCodec::BitRound(c) => write!(f, "bitround(keepbits={})", c.keepbits),
+        }
+    }
+}
+
+/// Shard decoding needs the inner chunk layout, which byte-level codecs
+/// don't have.
+fn sharding_unsupported() -> ZarrError {
+    ZarrError::UnsupportedFeature(FeatureId::Codec("sharding_indexed".into()))
+}
+
+/// Error for a codec whose cargo feature is disabled.
+#[cfg(any(
+    not(feature = "blosc"),
+    not(feature = "zstd"),
+    not(feature = "lz4"),
+    not(feature = "snappy"),
+    not(feature = "brotli"),
+    not(feature = "lzma")
+))]
+pub(crate) fn not_compiled(id: CodecId) -> ZarrError {
+    ZarrError::UnsupportedFeature(FeatureId::Codec(id.to_string()))
+}
+
 // ---------------------------------------------------------------------------
 // Codec pipeline
 // ---------------------------------------------------------------------------
 
 /// Apply a list of codecs to decode data. Codecs are applied in *reverse* order
 /// (last codec decodes first), matching the Zarr spec.
-pub async fn apply_codec_pipeline(codecs: &[AnyCodec], data: &[u8]) -> ZarrResult<Vec<u8>> {
+///
+/// This works on raw bytes throughout, so array→array codecs see the
+/// serialised form of their input.  Chunk reads go through [`CodecPipeline`],
+/// which gives those codecs typed values instead.
+///
+/// `max_size` bounds the final decoded size.  Each stage's output is limited
+/// to what the stages decoded after it can accept, so a compressor can't
+/// inflate an untrusted chunk past that size.
+pub async fn apply_codec_pipeline(
+    codecs: &[AnyCodec],
+    data: &[u8],
+    max_size: usize,
+) -> ZarrResult<Vec<u8>> {
+    // codecs[0] decodes last and produces the final output.
+    let mut limits = Vec::with_capacity(codecs.len());
+    let mut limit = max_size;
+    for codec in codecs {
+        limits.push(limit);
+        limit = codec.max_input_len(limit);
+    }
+
     let mut buf = data.to_vec();
-    for codec in codecs.iter().rev() {
-        buf = codec.decode(&buf).await?;
+    for (codec, limit) in codecs.iter().zip(limits).rev() {
+        buf = codec.decode_bounded(&buf, limit).await?;
     }
     Ok(buf)
 }
 
+/// A codec list split into the three V3 stages: array→array codecs, one
+/// array→bytes codec, then bytes→bytes codecs.
+///
+/// Codecs are classified by [`CodecKind`] and keep their relative order, so
+/// both the V3 order and the V2 `[filters, compressor, bytes]` order work.
+/// Without an array→bytes codec a little-endian bytes codec is assumed.
+#[derive(Debug, Clone)]
+pub struct CodecPipeline<'a> {
+    array_to_array: Vec<&'a AnyCodec>,
+    array_to_bytes: Option<&'a AnyCodec>,
+    bytes_to_bytes: Vec<&'a AnyCodec>,
+}
+
+impl<'a> CodecPipeline<'a> {
+    pub fn new(codecs: &'a [AnyCodec]) -> ZarrResult<Self> {
+        let mut pipeline = CodecPipeline {
+            array_to_array: Vec::new(),
+            array_to_bytes: None,
+            bytes_to_bytes: Vec::new(),
+        };
+        for codec in codecs {
+            match codec.kind() {
+                CodecKind::ArrayToArray => pipeline.array_to_array.push(codec),
+                CodecKind::ArrayToBytes => {
+                    if pipeline.array_to_bytes.replace(codec).is_some() {
+                        return Err(ZarrError::Codec(
+                            "Codec pipeline has more than one array-to-bytes codec".into(),
+                        ));
+                    }
+                }
+                CodecKind::BytesToBytes => pipeline.bytes_to_bytes.push(codec),
+            }
+        }
+        Ok(pipeline)
+    }
+
+    /// The codecs in the order a chunk passes through them when it is
+    /// read, e.g. `blosc(zstd, clevel=5, shuffle) → bytes(LE)`.  Without an
+    /// array→bytes codec, a plain `bytes` stands in for the assumed one.
+    pub fn describe(&self) -> String {
+        let serialiser = self
+            .array_to_bytes
+            .map_or_else(|| "bytes".to_string(), |c| c.to_string());
+        let codecs: Vec<String> = self
+            .bytes_to_bytes
+            .iter()
+            .rev()
+            .map(|c| c.to_string())
+            .chain([serialiser])
+            .chain(self.array_to_array.iter().rev().map(|c| c.to_string()))
+            .collect();
+        codecs.join(" → ")
+    }
+
+    /// Byte order used by the array→bytes stage, little-endian when its
+    /// codec doesn't name one.  `Native` is resolved to the host's order.
+    pub fn endian(&self) -> Endian {
+        self.endian_or(Endian::Little)
+    }
+
+    /// [`endian`](Self::endian) with `default` for a codec that doesn't
+    /// name a byte order.
+    pub fn endian_or(&self, default: Endian) -> Endian {
+        self.array_to_bytes
+            .and_then(|c| c.bytes_endian())
+            .unwrap_or(default)
+            .resolve()
+    }
+
+    /// The element type the array→bytes stage sees for an array of `dtype`,
+    /// after every array→array codec has been applied.
+    pub fn encoded_data_type(&self, dtype: DataType) -> ZarrResult<DataType> {
+        self.array_to_array
+            .iter()
+            .try_fold(dtype, |dt, codec| match codec {
+                AnyCodec::FixedScaleOffset(c) => c.encoded_data_type(dt),
+                AnyCodec::BitRound(c) => c.encoded_data_type(dt),
+                other => Err(ZarrError::Codec(format!(
+                    "{} is not an array-to-array codec",
+                    other.codec_id()
+                ))),
+            })
+    }
+
+    /// Decode one chunk of `elements` elements of `dtype`.
+    ///
+    /// Compressors are bounded by the serialised size the array→bytes stage
+    /// expects, which is also checked exactly for fixed-size types unless
+    /// [`ArrayReadOptions::allow_trailing_bytes`] is set.  `key` identifies
+    /// the chunk in errors.
+    pub async fn decode(
+        &self,
+        data: &[u8],
+        key: &str,
+        dtype: DataType,
+        elements: usize,
+        options: &ArrayReadOptions,
+    ) -> ZarrResult<ZarrVectorValue> {
+        self.decode_edge(data, key, dtype, elements, None, options)
+            .await
+    }
+
+    /// [`decode`](Self::decode) for an edge chunk, which some writers store
+    /// trimmed to the `edge_elements` elements inside the array.  A chunk
+    /// whose serialised size matches that count decodes to that many
+    /// elements; the caller lays them out.
+    pub(crate) async fn decode_edge(
+        &self,
+        data: &[u8],
+        key: &str,
+        dtype: DataType,
+        mut elements: usize,
+        edge_elements: Option<usize>,
+        options: &ArrayReadOptions,
+    ) -> ZarrResult<ZarrVectorValue> {
+        let encoded_dtype = self.encoded_data_type(dtype)?;
+        let expected = expected_chunk_bytes(encoded_dtype, elements, options)?;
+        let max_size = match expected {
+            Some(expected) if options.allow_trailing_bytes => {
+                expected.max(options.max_variable_chunk_bytes)
+            }
+            Some(expected) => expected,
+            None => options.max_variable_chunk_bytes,
+        };
+
+        // bytes -> bytes
+        let mut buf = data.to_vec();
+        for codec in self.bytes_to_bytes.iter().rev() {
+            buf = match (codec, options.zstd_window_log_max) {
+                (AnyCodec::Zstd(c), Some(log)) => c
+                    .clone()
+                    .with_window_log_max(log)
+                    .decode_bounded(&buf, max_size)?,
+                _ => codec.decode_bounded(&buf, max_size).await?,
+            };
+        }
+        let trimmed = edge_elements.filter(|&edge| {
+            expected.is_some()
+                && expected_chunk_bytes(encoded_dtype, edge, options).ok() == Some(Some(buf.len()))
+        });
+        if let Some(edge) = trimmed {
+            elements = edge;
+        } else if let Some(expected) = expected {
+            if buf.len() > expected && options.allow_trailing_bytes {
+                buf.truncate(expected);
+            }
+            if buf.len() != expected {
+                return Err(ZarrError::ChunkSizeMismatch {
+                    chunk: key.to_string(),
+                    expected,
+                    actual: buf.len(),
+                });
+            }
+        }
+
+        // bytes -> array
+        let mut values = match self.array_to_bytes {
+            Some(AnyCodec::Sharding(_)) => return Err(sharding_unsupported()),
+            _ if encoded_dtype == DataType::Bool
+                && options.bool_encoding == BoolEncoding::BitPacked =>
+            {
+                ZarrVectorValue::VBool(unpack_bools(&buf, elements)?)
+            }
+            _ => bytes_to_zarr_vector(
+                self.endian_or(options.default_endian),
+                encoded_dtype,
+                &buf,
+            )?,
+        };
+
+        // array -> array
+        for codec in self.array_to_array.iter().rev() {
+            if let AnyCodec::FixedScaleOffset(c) = codec {
+                values = c.decode_array(&values)?;
+            }
+        }
+        Ok(values)
+    }
+
+    /// Encode a typed chunk; the inverse of [`CodecPipeline::decode`] for
+    /// one-byte-per-element bools.
+    pub async fn encode(&self, values: &ZarrVectorValue) -> ZarrResult<Vec<u8>> {
+        // array -> array
+        let mut values = values.clone();
+        for codec in &self.array_to_array {
+            match codec {
+                AnyCodec::FixedScaleOffset(c) => values = c.encode_array(&values)?,
+                AnyCodec::BitRound(c) => values = c.encode_array(&values)?,
+                _ => {}
+            }
+        }
+
+        // array -> bytes
+        if let Some(AnyCodec::Sharding(_)) = self.array_to_bytes {
+            return Err(sharding_unsupported());
+        }
+        let typesize = values.nbytes().checked_div(values.len()).unwrap_or(1);
+        let mut buf = zarr_vector_to_bytes(self.endian(), &values)?;
+
+        // bytes -> bytes
+        for codec in &self.bytes_to_bytes {
+            buf = match codec {
+                // Blosc shuffles by element, so it needs the element size.
+                AnyCodec::Blosc(c) if c.typesize.is_none() => {
+                    let mut c = c.clone();
+                    c.typesize = Some(typesize);
+                    c.encode(&buf).await?
+                }
+                other => other.encode(&buf).await?,
+            };
+        }
+        Ok(buf)
+    }
+}
+
+/// Read a decompressor to the end, failing once more than `max_size` bytes
+/// have been produced.
+pub(crate) fn read_to_end_limited<R: Read>(
+    reader: R,
+    max_size: usize,
+    codec: &str,
+) -> ZarrResult<Vec<u8>> {
+    let mut out = Vec::new();
+    reader
+        .take((max_size as u64).saturating_add(1))
+        .read_to_end(&mut out)
+        .map_err(|e| ZarrError::Decode(format!("{codec} decompress failed: {e}")))?;
+    if out.len() > max_size {
+        return Err(ZarrError::Decode(format!(
+            "{codec} output exceeds the limit of {max_size} bytes"
+        )));
+    }
+    Ok(out)
+}
+
 // ---------------------------------------------------------------------------
 // JSON-based codec parsing  (V3 style)
 // ---------------------------------------------------------------------------
@@ -144,11 +573,15 @@ pub fn lookup_codec_id(name: &str) -> Option<CodecId> {
         "bytes" => Some(CodecId::Bytes),
         "gzip" => Some(CodecId::Gzip),
         "blosc" => Some(CodecId::Blosc),
-        "zlib" => Some(CodecId::Zlib),
+        "zlib" | "numcodecs.zlib" => Some(CodecId::Zlib),
         "zstd" => Some(CodecId::Zstd),
-        "lz4" => Some(CodecId::Lz4),
+        "lz4" | "numcodecs.lz4" => Some(CodecId::Lz4),
+        "snappy" | "numcodecs.snappy" => Some(CodecId::Snappy),
+        "brotli" | "numcodecs.brotli" => Some(CodecId::Brotli),
+        "lzma" | "numcodecs.lzma" => Some(CodecId::Lzma),
         "sharding_indexed" => Some(CodecId::Sharding),
         "numcodecs.fixedscaleoffset" => Some(CodecId::FixedScaleOffset),
+        "numcodecs.bitround" => Some(CodecId::BitRound),
         _ => None,
     }
 }
@@ -160,10 +593,10 @@ pub fn parse_codec(value: &serde_json::Value) -> ZarrResult<AnyCodec> {
 
     let config = env.configuration.unwrap_or(serde_json::Value::Object(Default::default()));
 
-    match lookup_codec_id(&env.name) {
+    match lookup_codec_id(&env.name).filter(CodecId::is_compiled) {
         Some(CodecId::Bytes) => {
             let c: bytes::BytesCodec = serde_json::from_value(config)
-                .unwrap_or_else(|_| bytes::BytesCodec::default());
+                .map_err(|e| ZarrError::Codec(format!("Failed to parse BytesCodec: {e}")))?;
             Ok(AnyCodec::Bytes(c))
         }
         Some(CodecId::Gzip) => {
@@ -191,6 +624,17 @@ pub fn parse_codec(value: &serde_json::Value) -> ZarrResult<AnyCodec> {
                 .unwrap_or_else(|_| lz4::Lz4Codec::default());
             Ok(AnyCodec::Lz4(c))
         }
+        Some(CodecId::Snappy) => Ok(AnyCodec::Snappy(snappy::SnappyCodec::default())),
+        Some(CodecId::Brotli) => {
+            let c: brotli::BrotliCodec = serde_json::from_value(config)
+                .map_err(|e| ZarrError::Codec(format!("Failed to parse BrotliCodec: {e}")))?;
+            Ok(AnyCodec::Brotli(c))
+        }
+        Some(CodecId::Lzma) => {
+            let c: lzma::LzmaCodec = serde_json::from_value(config)
+                .map_err(|e| ZarrError::Codec(format!("Failed to parse LzmaCodec: {e}")))?;
+            Ok(AnyCodec::Lzma(c))
+        }
         Some(CodecId::Sharding) => {
             let c: sharding::ShardingCodec = serde_json::from_value(config)
                 .unwrap_or_else(|_| sharding::ShardingCodec::default());
@@ -203,7 +647,12 @@ pub fn parse_codec(value: &serde_json::Value) -> ZarrResult<AnyCodec> {
                 })?;
             Ok(AnyCodec::FixedScaleOffset(c))
         }
-        None => Err(ZarrError::Codec(format!("Unknown codec: {}", env.name))),
+        Some(CodecId::BitRound) => {
+            let c: bitround::BitRoundCodec = serde_json::from_value(config)
+                .map_err(|e| ZarrError::Codec(format!("Failed to parse BitRoundCodec: {e}")))?;
+            Ok(AnyCodec::BitRound(c))
+        }
+        None => Err(ZarrError::UnsupportedFeature(FeatureId::Codec(env.name))),
     }
 }
 
@@ -211,3 +660,69 @@ pub fn parse_codec(value: &serde_json::Value) -> ZarrResult<AnyCodec> {
 pub fn parse_codecs(values: &[serde_json::Value]) -> ZarrResult<Vec<AnyCodec>> {
     values.iter().map(parse_codec).collect()
 }
+
+/// Serialise a codec as a V3 `{ "name": ..., "configuration": ... }` entry;
+/// the inverse of [`parse_codec`].
+///
+/// Codecs that are not in the V3 core spec use their `numcodecs.*` names, as
+/// zarr-python does.
+pub fn codec_to_json(codec: &AnyCodec) -> serde_json::Value {
+    let (name, configuration) = match codec {
+        AnyCodec::Bytes(c) => match c.endian {
+            // Written metadata names a concrete byte order.
+            Some(endian @ (Endian::Little | Endian::Big | Endian::Native)) => (
+                "bytes",
+                Some(serde_json::json!({"endian": endian.resolve()})),
+            ),
+            _ => ("bytes", None),
+        },
+        AnyCodec::Gzip(c) => ("gzip", Some(serde_json::json!({"level": c.level}))),
+        AnyCodec::Blosc(c) => {
+            let mut config = serde_json::json!({
+                "cname": c.cname,
+                "clevel": c.clevel,
+                "shuffle": c.shuffle.unwrap_or(blosc::BloscShuffle::NoShuffle),
+                "blocksize": c.blocksize,
+            });
+            if let Some(typesize) = c.typesize {
+                config["typesize"] = typesize.into();
+            }
+            ("blosc", Some(config))
+        }
+        AnyCodec::Zlib(c) => ("numcodecs.zlib", Some(serde_json::json!({"level": c.level}))),
+        AnyCodec::Zstd(c) => (
+            "zstd",
+            Some(serde_json::json!({"level": c.level, "checksum": false})),
+        ),
+        AnyCodec::Lz4(c) => (
+            "numcodecs.lz4",
+            Some(serde_json::json!({"acceleration": c.acceleration})),
+        ),
+        AnyCodec::Snappy(_) => ("numcodecs.snappy", None),
+        AnyCodec::Brotli(c) => (
+            "numcodecs.brotli",
+            Some(serde_json::json!({"level": c.level, "lgwin": c.lgwin})),
+        ),
+        AnyCodec::Lzma(c) => (
+            "numcodecs.lzma",
+            Some(serde_json::json!({
+                "format": c.format,
+                "check": c.check,
+                "preset": c.preset,
+                "filters": c.filters,
+            })),
+        ),
+        AnyCodec::Sharding(c) => ("sharding_indexed", serde_json::to_value(c).ok()),
+        AnyCodec::FixedScaleOffset(c) => {
+            ("numcodecs.fixedscaleoffset", serde_json::to_value(c).ok())
+        }
+        AnyCodec::BitRound(c) => (
+            "numcodecs.bitround",
+            Some(serde_json::json!({"keepbits": c.keepbits})),
+        ),
+    };
+    match configuration {
+        Some(configuration) => serde_json::json!({"name": name, "configuration": configuration}),
+        None => serde_json::json!({"name": name}),
+    }
+}
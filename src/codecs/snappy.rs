@@ -0,0 +1,55 @@
+#[cfg(not(feature = "snappy"))]
+use crate::codecs::{CodecId, not_compiled};
+#[cfg(feature = "snappy")]
+use crate::error::ZarrError;
+use crate::error::ZarrResult;
+use serde::{Deserialize, Serialize};
+
+/// Raw (unframed) Snappy, as written by python-snappy and numcodecs-style
+/// `snappy` compressors.  Blosc's `snappy` sub-compressor is a different
+/// format and stays a [`BloscCodec`](crate::codecs::blosc::BloscCodec).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnappyCodec {}
+
+#[cfg(feature = "snappy")]
+impl SnappyCodec {
+    pub fn decode(&self, data: &[u8]) -> ZarrResult<Vec<u8>> {
+        self.decode_bounded(data, usize::MAX)
+    }
+
+    /// Decode, rejecting blocks whose declared size exceeds `max_size` bytes
+    /// before allocating the output buffer.
+    pub fn decode_bounded(&self, data: &[u8], max_size: usize) -> ZarrResult<Vec<u8>> {
+        let dest_size = snap::raw::decompress_len(data)
+            .map_err(|e| ZarrError::Decode(format!("Snappy decompress failed: {e}")))?;
+        if dest_size > max_size {
+            return Err(ZarrError::Decode(format!(
+                "Snappy block declares {dest_size} bytes, exceeding the limit of {max_size}"
+            )));
+        }
+        snap::raw::Decoder::new()
+            .decompress_vec(data)
+            .map_err(|e| ZarrError::Decode(format!("Snappy decompress failed: {e}")))
+    }
+
+    pub fn encode(&self, data: &[u8]) -> ZarrResult<Vec<u8>> {
+        snap::raw::Encoder::new()
+            .compress_vec(data)
+            .map_err(|e| ZarrError::Encode(format!("Snappy compress failed: {e}")))
+    }
+}
+
+#[cfg(not(feature = "snappy"))]
+impl SnappyCodec {
+    pub fn decode(&self, _data: &[u8]) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Snappy))
+    }
+
+    pub fn decode_bounded(&self, _data: &[u8], _max_size: usize) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Snappy))
+    }
+
+    pub fn encode(&self, _data: &[u8]) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Snappy))
+    }
+}
@@ -1,3 +1,4 @@
+use crate::codecs::read_to_end_limited;
 use crate::error::{ZarrError, ZarrResult};
 use flate2::read::{ZlibDecoder, ZlibEncoder};
 use flate2::Compression;
@@ -22,12 +23,12 @@ impl Default for ZlibCodec {
 
 impl ZlibCodec {
     pub fn decode(&self, data: &[u8]) -> ZarrResult<Vec<u8>> {
-        let mut decoder = ZlibDecoder::new(data);
-        let mut out = Vec::new();
-        decoder
-            .read_to_end(&mut out)
-            .map_err(|e| ZarrError::Decode(format!("Zlib decompress failed: {e}")))?;
-        Ok(out)
+        self.decode_bounded(data, usize::MAX)
+    }
+
+    /// Decode, failing once the output would exceed `max_size` bytes.
+    pub fn decode_bounded(&self, data: &[u8], max_size: usize) -> ZarrResult<Vec<u8>> {
+        read_to_end_limited(ZlibDecoder::new(data), max_size, "Zlib")
     }
 
     pub fn encode(&self, data: &[u8]) -> ZarrResult<Vec<u8>> {
@@ -1,11 +1,35 @@
-use crate::error::{ZarrError, ZarrResult};
+#[cfg(feature = "zstd")]
+use crate::codecs::read_to_end_limited;
+#[cfg(not(feature = "zstd"))]
+use crate::codecs::{CodecId, not_compiled};
+#[cfg(feature = "zstd")]
+use crate::error::ZarrError;
+use crate::error::ZarrResult;
 use serde::{Deserialize, Serialize};
-use std::io::Read;
+#[cfg(feature = "zstd")]
+use zstd::stream::raw::CParameter;
+
+/// Window log zstd uses for long-distance matching unless told otherwise
+/// (128 MiB), which is also the decoder's default limit.
+pub const DEFAULT_LONG_WINDOW_LOG: u32 = 27;
+
+/// Largest window log zstd supports on 64-bit targets (2 GiB).
+pub const MAX_WINDOW_LOG: u32 = 31;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZstdCodec {
     #[serde(default = "default_level")]
     pub level: i32,
+    /// Largest window, as a power of two, the decoder accepts.  `None` keeps
+    /// zstd's limit of 2^27, or 2^31 with `long_distance_matching`.  Frames
+    /// written with `zstd --long=N` for `N > 27` need this raised to `N`.
+    #[serde(skip)]
+    pub window_log_max: Option<u32>,
+    /// Encode with long-distance matching (`zstd --long`), using a window of
+    /// 2^`window_log_max` (default 2^27); decode frames with windows up to
+    /// 2^31.  Runtime settings, never read from or written to metadata.
+    #[serde(skip)]
+    pub long_distance_matching: bool,
 }
 
 fn default_level() -> i32 {
@@ -14,25 +38,95 @@ fn default_level() -> i32 {
 
 impl Default for ZstdCodec {
     fn default() -> Self {
-        Self { level: 5 }
+        Self {
+            level: 5,
+            window_log_max: None,
+            long_distance_matching: false,
+        }
+    }
+}
+
+impl ZstdCodec {
+    /// Zstd at `level` with no window settings.
+    pub fn new(level: i32) -> Self {
+        Self {
+            level,
+            ..Self::default()
+        }
+    }
+
+    /// Accept windows up to 2^`log` when decoding (at most 31).
+    pub fn with_window_log_max(mut self, log: u32) -> Self {
+        self.window_log_max = Some(log);
+        self
+    }
+
+    /// Encode with long-distance matching and accept long windows on decode.
+    pub fn with_long_distance_matching(mut self, enabled: bool) -> Self {
+        self.long_distance_matching = enabled;
+        self
+    }
+
+    /// The window log limit the decoder is given, if any.
+    #[cfg(feature = "zstd")]
+    fn decode_window_log_max(&self) -> Option<u32> {
+        match self.window_log_max {
+            Some(log) => Some(log.min(MAX_WINDOW_LOG)),
+            None if self.long_distance_matching => Some(MAX_WINDOW_LOG),
+            None => None,
+        }
     }
 }
 
+#[cfg(feature = "zstd")]
 impl ZstdCodec {
     pub fn decode(&self, data: &[u8]) -> ZarrResult<Vec<u8>> {
+        self.decode_bounded(data, usize::MAX)
+    }
+
+    /// Decode, failing once the output would exceed `max_size` bytes.
+    pub fn decode_bounded(&self, data: &[u8], max_size: usize) -> ZarrResult<Vec<u8>> {
         // Use streaming decoder -- handles frames that lack a content-size field
         // (common with numcodecs' zstd output).
         let mut decoder = zstd::Decoder::new(data)
             .map_err(|e| ZarrError::Decode(format!("Zstd decoder init failed: {e}")))?;
-        let mut out = Vec::new();
-        decoder
-            .read_to_end(&mut out)
-            .map_err(|e| ZarrError::Decode(format!("Zstd decompress failed: {e}")))?;
-        Ok(out)
+        if let Some(log) = self.decode_window_log_max() {
+            decoder
+                .window_log_max(log)
+                .map_err(|e| ZarrError::Decode(format!("Zstd window log {log} rejected: {e}")))?;
+        }
+        read_to_end_limited(decoder, max_size, "Zstd")
     }
 
     pub fn encode(&self, data: &[u8]) -> ZarrResult<Vec<u8>> {
-        zstd::bulk::compress(data, self.level)
-            .map_err(|e| ZarrError::Encode(format!("Zstd compress failed: {e}")))
+        let encode_err = |e| ZarrError::Encode(format!("Zstd compress failed: {e}"));
+        if !self.long_distance_matching {
+            return zstd::bulk::compress(data, self.level).map_err(encode_err);
+        }
+        let window_log = self
+            .window_log_max
+            .unwrap_or(DEFAULT_LONG_WINDOW_LOG)
+            .min(MAX_WINDOW_LOG);
+        let mut compressor = zstd::bulk::Compressor::new(self.level).map_err(encode_err)?;
+        compressor
+            .set_parameter(CParameter::EnableLongDistanceMatching(true))
+            .and_then(|()| compressor.set_parameter(CParameter::WindowLog(window_log)))
+            .map_err(encode_err)?;
+        compressor.compress(data).map_err(encode_err)
+    }
+}
+
+#[cfg(not(feature = "zstd"))]
+impl ZstdCodec {
+    pub fn decode(&self, _data: &[u8]) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Zstd))
+    }
+
+    pub fn decode_bounded(&self, _data: &[u8], _max_size: usize) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Zstd))
+    }
+
+    pub fn encode(&self, _data: &[u8]) -> ZarrResult<Vec<u8>> {
+        Err(not_compiled(CodecId::Zstd))
     }
 }
@@ -0,0 +1,334 @@
+//! Comparing values and arrays, exactly or within a tolerance.
+//!
+//! [`Tolerance`] follows NumPy's `isclose`: `a` is close to the reference
+//! `b` when `|a - b| <= atol + rtol * |b|`, infinities only match themselves
+//! and NaN matches nothing unless [`nan_equal`](Tolerance::nan_equal) is
+//! set.  A [`Comparison`] counts the elements that differ and prints as a
+//! one-line summary, which makes a readable assertion message:
+//!
+//! ```ignore
+//! let reference = v2::open(reference_store, "t2m").await?;
+//! let comparison = array
+//!     .compare(&reference, &Tolerance::default().nan_equal(true))
+//!     .await?;
+//! assert!(comparison.is_equal(), "{comparison}");
+//! ```
+//!
+//! Integers are compared as `f64`, complex numbers by the modulus of their
+//! difference, and strings and bytes exactly.
+
+use std::fmt;
+
+use num_complex::Complex;
+
+use crate::array::{ArrayData, UnifiedZarrArray, strides};
+use crate::error::{ZarrError, ZarrResult};
+use crate::types::{DataType, ZarrValue, ZarrVectorValue};
+
+// ---------------------------------------------------------------------------
+// Tolerance
+// ---------------------------------------------------------------------------
+
+/// How close two elements must be to count as equal.
+///
+/// The default is NumPy's: `rtol = 1e-5`, `atol = 1e-8`, NaN unequal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    /// Allowed difference relative to the reference value.
+    pub rtol: f64,
+    /// Allowed absolute difference.
+    pub atol: f64,
+    /// Whether NaN equals NaN.
+    pub nan_equal: bool,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self {
+            rtol: 1e-5,
+            atol: 1e-8,
+            nan_equal: false,
+        }
+    }
+}
+
+impl Tolerance {
+    /// Equal values only, so `0.0` matches `-0.0` but NaN matches nothing.
+    pub fn exact() -> Self {
+        Self {
+            rtol: 0.0,
+            atol: 0.0,
+            nan_equal: false,
+        }
+    }
+
+    pub fn rtol(mut self, rtol: f64) -> Self {
+        self.rtol = rtol;
+        self
+    }
+
+    pub fn atol(mut self, atol: f64) -> Self {
+        self.atol = atol;
+        self
+    }
+
+    pub fn nan_equal(mut self, nan_equal: bool) -> Self {
+        self.nan_equal = nan_equal;
+        self
+    }
+
+    /// Whether `a` is close to the reference `b`.
+    pub fn is_close(&self, a: f64, b: f64) -> bool {
+        if a.is_nan() || b.is_nan() {
+            return self.nan_equal && a.is_nan() && b.is_nan();
+        }
+        if a.is_infinite() || b.is_infinite() {
+            return a == b;
+        }
+        (a - b).abs() <= self.atol + self.rtol * b.abs()
+    }
+
+    /// [`is_close`](Self::is_close) for complex numbers.
+    fn is_close_complex(&self, a: Complex<f64>, b: Complex<f64>) -> bool {
+        if a.is_nan() || b.is_nan() {
+            return self.nan_equal && a.is_nan() && b.is_nan();
+        }
+        if a.is_infinite() || b.is_infinite() {
+            return a == b;
+        }
+        (a - b).norm() <= self.atol + self.rtol * b.norm()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Comparison
+// ---------------------------------------------------------------------------
+
+/// The outcome of comparing two vectors or arrays element by element.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// Number of elements compared.
+    pub elements: usize,
+    /// Number of elements that weren't close.
+    pub mismatches: usize,
+    /// Index of the first element that wasn't close, one entry per
+    /// dimension (a single entry for vectors), in the first operand's
+    /// memory order.
+    pub first_mismatch: Option<Vec<usize>>,
+    /// Largest finite `|a - b|` over all numeric elements, mismatched or
+    /// not.
+    pub max_abs_diff: f64,
+}
+
+impl Comparison {
+    /// Whether every element was close.
+    pub fn is_equal(&self) -> bool {
+        self.mismatches == 0
+    }
+}
+
+/// `35 elements equal (max |a - b| = 0)` or
+/// `3 of 35 elements differ, first at [0, 2] (max |a - b| = 0.5)`.
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.first_mismatch {
+            None => write!(f, "{} elements equal", self.elements)?,
+            Some(index) => write!(
+                f,
+                "{} of {} elements differ, first at {index:?}",
+                self.mismatches, self.elements
+            )?,
+        }
+        write!(f, " (max |a - b| = {})", self.max_abs_diff)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Vectors and arrays
+// ---------------------------------------------------------------------------
+
+impl ZarrVectorValue {
+    /// Whether every element is within `atol + rtol * |other|` of the
+    /// matching element of `other`, like NumPy's `allclose`.  NaN never
+    /// matches; use [`compare`](Self::compare) with
+    /// [`Tolerance::nan_equal`] to let it.  Vectors of different lengths
+    /// are never equal.
+    pub fn approx_eq(&self, other: &ZarrVectorValue, rtol: f64, atol: f64) -> bool {
+        let tolerance = Tolerance::default().rtol(rtol).atol(atol);
+        self.compare(other, &tolerance)
+            .is_ok_and(|comparison| comparison.is_equal())
+    }
+
+    /// Compare element by element with `other` as the reference.  Fails if
+    /// the lengths differ.
+    pub fn compare(
+        &self,
+        other: &ZarrVectorValue,
+        tolerance: &Tolerance,
+    ) -> ZarrResult<Comparison> {
+        if self.len() != other.len() {
+            return Err(ZarrError::Other(format!(
+                "Cannot compare {} values with {}",
+                self.len(),
+                other.len()
+            )));
+        }
+        let (mismatches, first, max_abs_diff) = compare_elements(self, other, |i| i, tolerance)?;
+        Ok(Comparison {
+            elements: self.len(),
+            mismatches,
+            first_mismatch: first.map(|i| vec![i]),
+            max_abs_diff,
+        })
+    }
+}
+
+impl ArrayData<ZarrVectorValue> {
+    /// Compare with `other` as the reference, matching elements by index
+    /// so arrays in different memory orders compare by value.  Fails if
+    /// the shapes differ.
+    pub fn compare(
+        &self,
+        other: &ArrayData<ZarrVectorValue>,
+        tolerance: &Tolerance,
+    ) -> ZarrResult<Comparison> {
+        if self.shape != other.shape {
+            return Err(ZarrError::Other(format!(
+                "Cannot compare shape {:?} with {:?}",
+                self.shape, other.shape
+            )));
+        }
+        let elements: usize = self.shape.iter().product();
+        if self.data.len() != elements || other.data.len() != elements {
+            return Err(ZarrError::Other(format!(
+                "Values don't fill shape {:?}",
+                self.shape
+            )));
+        }
+        let own = strides(&self.shape, self.order);
+        let theirs = strides(&other.shape, other.order);
+        let unravel = |i: usize| -> Vec<usize> {
+            own.iter()
+                .zip(&self.shape)
+                .map(|(s, len)| i / s % len)
+                .collect()
+        };
+        let (mismatches, first, max_abs_diff) = if self.order == other.order {
+            compare_elements(&self.data, &other.data, |i| i, tolerance)?
+        } else {
+            compare_elements(
+                &self.data,
+                &other.data,
+                |i| unravel(i).iter().zip(&theirs).map(|(i, s)| i * s).sum(),
+                tolerance,
+            )?
+        };
+        Ok(Comparison {
+            elements,
+            mismatches,
+            first_mismatch: first.map(unravel),
+            max_abs_diff,
+        })
+    }
+}
+
+impl UnifiedZarrArray {
+    /// Load this array and `other` and compare them, `other` being the
+    /// reference; see [`ArrayData::compare`].
+    pub async fn compare(
+        &self,
+        other: &UnifiedZarrArray,
+        tolerance: &Tolerance,
+    ) -> ZarrResult<Comparison> {
+        let (own, theirs) = futures::try_join!(self.load_value(), other.load_value())?;
+        own.compare(&theirs, tolerance)
+    }
+}
+
+/// Compare `a[i]` with `b[b_index(i)]` for every `i`, returning the number
+/// of mismatches, the first mismatched `i` and the largest finite
+/// difference.
+fn compare_elements(
+    a: &ZarrVectorValue,
+    b: &ZarrVectorValue,
+    b_index: impl Fn(usize) -> usize,
+    tolerance: &Tolerance,
+) -> ZarrResult<(usize, Option<usize>, f64)> {
+    let mut mismatches = 0;
+    let mut first = None;
+    let mut max_abs_diff = 0.0f64;
+    let mut record = |i: usize, close: bool, diff: f64| {
+        if !close {
+            mismatches += 1;
+            first.get_or_insert(i);
+        }
+        if diff.is_finite() {
+            max_abs_diff = max_abs_diff.max(diff);
+        }
+    };
+    if is_real(a) && is_real(b) {
+        let (a, a_valid) = a.to_f64_with_validity()?;
+        let (b, b_valid) = b.to_f64_with_validity()?;
+        for i in 0..a.len() {
+            let j = b_index(i);
+            match (a_valid[i], b_valid[j]) {
+                (true, true) => record(i, tolerance.is_close(a[i], b[j]), (a[i] - b[j]).abs()),
+                (valid_a, valid_b) => record(i, valid_a == valid_b, f64::NAN),
+            }
+        }
+    } else {
+        let (a, b) = (a.to_maybe_values(), b.to_maybe_values());
+        for (i, x) in a.iter().enumerate() {
+            let y = &b[b_index(i)];
+            let (close, diff) = values_close(x.as_ref(), y.as_ref(), tolerance);
+            record(i, close, diff);
+        }
+    }
+    Ok((mismatches, first, max_abs_diff))
+}
+
+/// Whether `values` holds real numbers, with or without nulls.
+fn is_real(values: &ZarrVectorValue) -> bool {
+    let dtype = match values {
+        ZarrVectorValue::VWithNulls(dtype, _) => *dtype,
+        ZarrVectorValue::VComplex64(_)
+        | ZarrVectorValue::VComplex128(_)
+        | ZarrVectorValue::VString(_)
+        | ZarrVectorValue::VBytes(_) => return false,
+        _ => return true,
+    };
+    !matches!(
+        dtype,
+        DataType::Complex64
+            | DataType::Complex128
+            | DataType::String
+            | DataType::Bytes
+            | DataType::Raw(_)
+    )
+}
+
+/// Whether `a` is close to `b`, and their distance when both are numbers.
+fn values_close(
+    a: Option<&ZarrValue>,
+    b: Option<&ZarrValue>,
+    tolerance: &Tolerance,
+) -> (bool, f64) {
+    let a = a.filter(|v| !matches!(v, ZarrValue::Null(_)));
+    let b = b.filter(|v| !matches!(v, ZarrValue::Null(_)));
+    let (Some(a), Some(b)) = (a, b) else {
+        return (a.is_none() && b.is_none(), f64::NAN);
+    };
+    match (as_complex(a), as_complex(b)) {
+        (Some(x), Some(y)) => (tolerance.is_close_complex(x, y), (x - y).norm()),
+        _ => (a == b, f64::NAN),
+    }
+}
+
+/// A number as a complex number; `None` for strings and bytes.
+fn as_complex(value: &ZarrValue) -> Option<Complex<f64>> {
+    match value {
+        ZarrValue::Complex64(c) => Some(Complex::new(c.re as f64, c.im as f64)),
+        ZarrValue::Complex128(c) => Some(*c),
+        other => other.to_f64().map(|x| Complex::new(x, 0.0)),
+    }
+}
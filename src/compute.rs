@@ -0,0 +1,461 @@
+//! Lazy elementwise computation over array views.
+//!
+//! A [`ComputedArray`] is an expression whose leaves are [`ArrayView`]s,
+//! built with e.g. `(view.lazy() + other)?.scale(2.0)`.  Nothing is read
+//! until [`read`](ComputedArray::read), which evaluates the expression one
+//! block at a time, following the chunk grid of its first leaf's array, so
+//! no intermediate is larger than a block and no chunk is read for more
+//! than one block.
+//!
+//! [`concat()`] joins several arrays or expressions into one virtual array,
+//! and [`mosaic`] stitches a grid of tiles.
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt, TryStreamExt};
+
+use crate::array::{chunk_indices_for_region, strides, validate_region};
+use crate::config::{self, SimplezarrConfig};
+use crate::error::{ZarrError, ZarrResult};
+use crate::types::ArrayOrder;
+use crate::view::ArrayView;
+
+// ---------------------------------------------------------------------------
+// ComputedArray
+// ---------------------------------------------------------------------------
+
+/// An elementwise function of one value.
+pub type UnaryFn = Arc<dyn Fn(f64) -> f64 + Send + Sync>;
+
+/// An elementwise operation on two arrays of the same shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl BinaryOp {
+    fn apply(self, a: f64, b: f64) -> f64 {
+        match self {
+            BinaryOp::Add => a + b,
+            BinaryOp::Sub => a - b,
+            BinaryOp::Mul => a * b,
+            BinaryOp::Div => a / b,
+        }
+    }
+}
+
+enum Node {
+    View(ArrayView),
+    Map(ComputedArray, UnaryFn),
+    Binary(BinaryOp, ComputedArray, ComputedArray),
+    /// The first array, NaN where the second is zero or NaN.
+    Mask(ComputedArray, ComputedArray),
+    /// Every element is the same value.
+    Constant(f64),
+    /// The parts end to end along `axis`.
+    Concat {
+        axis: usize,
+        parts: Vec<ComputedArray>,
+    },
+}
+
+/// A lazily evaluated elementwise expression over array views.
+#[derive(Clone)]
+pub struct ComputedArray {
+    node: Arc<Node>,
+    shape: Vec<usize>,
+    block_shape: Vec<usize>,
+    /// Where the block grid starts: blocks begin at view coordinates `k *
+    /// block_shape - block_offset`, the first leaf's chunk boundaries.
+    block_offset: Vec<usize>,
+    config: Arc<SimplezarrConfig>,
+}
+
+impl std::fmt::Debug for ComputedArray {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let node = match &*self.node {
+            Node::View(_) => "View",
+            Node::Map(..) => "Map",
+            Node::Binary(..) => "Binary",
+            Node::Mask(..) => "Mask",
+            Node::Constant(_) => "Constant",
+            Node::Concat { .. } => "Concat",
+        };
+        f.debug_struct("ComputedArray")
+            .field("node", &node)
+            .field("shape", &self.shape)
+            .finish()
+    }
+}
+
+impl From<ArrayView> for ComputedArray {
+    fn from(view: ArrayView) -> Self {
+        Self {
+            shape: view.shape(),
+            block_shape: view.chunk_shape(),
+            block_offset: view.chunk_offset(),
+            config: view.array().config.clone(),
+            node: Arc::new(Node::View(view)),
+        }
+    }
+}
+
+// `a + b` and friends build a `Binary` node.  They return a `ZarrResult`
+// because the shapes must match.
+macro_rules! binary_ops {
+    ($($trait:ident, $method:ident, $op:ident;)*) => {
+        $(impl<T: Into<ComputedArray>> std::ops::$trait<T> for ComputedArray {
+            type Output = ZarrResult<ComputedArray>;
+
+            fn $method(self, other: T) -> Self::Output {
+                self.binary(BinaryOp::$op, other)
+            }
+        })*
+    };
+}
+
+binary_ops! {
+    Add, add, Add;
+    Sub, sub, Sub;
+    Mul, mul, Mul;
+    Div, div, Div;
+}
+
+impl ArrayView {
+    /// Start a lazy expression from this view.
+    pub fn lazy(self) -> ComputedArray {
+        self.into()
+    }
+}
+
+impl ComputedArray {
+    pub fn shape(&self) -> Vec<usize> {
+        self.shape.clone()
+    }
+
+    /// The block size [`read`](Self::read) evaluates at a time.
+    pub fn block_shape(&self) -> Vec<usize> {
+        self.block_shape.clone()
+    }
+
+    fn with_node(&self, node: Node) -> Self {
+        Self {
+            node: Arc::new(node),
+            shape: self.shape.clone(),
+            block_shape: self.block_shape.clone(),
+            block_offset: self.block_offset.clone(),
+            config: self.config.clone(),
+        }
+    }
+
+    /// The configuration of the first leaf's array, or the [`global`](config::global)
+    /// one for arrays built with [`full`].
+    fn config(&self) -> &SimplezarrConfig {
+        &self.config
+    }
+
+    fn check_shape(&self, other: &ComputedArray) -> ZarrResult<()> {
+        if self.shape != other.shape {
+            return Err(ZarrError::Other(format!(
+                "Shapes {:?} and {:?} differ; broadcast the views first",
+                self.shape, other.shape
+            )));
+        }
+        Ok(())
+    }
+
+    /// Elementwise `op` of this array and `other`, which must have the same
+    /// shape.  `a + b`, `a - b`, `a * b` and `a / b` call this.
+    pub fn binary(self, op: BinaryOp, other: impl Into<ComputedArray>) -> ZarrResult<Self> {
+        let other = other.into();
+        self.check_shape(&other)?;
+        Ok(self.with_node(Node::Binary(op, self.clone(), other)))
+    }
+
+    /// Apply `f` to every element.
+    pub fn map(self, f: impl Fn(f64) -> f64 + Send + Sync + 'static) -> Self {
+        self.with_node(Node::Map(self.clone(), Arc::new(f)))
+    }
+
+    /// Multiply every element by `factor`.
+    pub fn scale(self, factor: f64) -> Self {
+        self.map(move |x| x * factor)
+    }
+
+    /// Add `offset` to every element, e.g. to convert Kelvin to Celsius.
+    pub fn offset(self, offset: f64) -> Self {
+        self.map(move |x| x + offset)
+    }
+
+    /// Replace elements with NaN where `mask` is zero or NaN.
+    pub fn mask(self, mask: impl Into<ComputedArray>) -> ZarrResult<Self> {
+        let mask = mask.into();
+        self.check_shape(&mask)?;
+        Ok(self.with_node(Node::Mask(self.clone(), mask)))
+    }
+
+    /// Evaluate the whole expression, in row-major order.
+    pub async fn read(&self) -> ZarrResult<Vec<f64>> {
+        let region: Vec<Range<usize>> = self.shape.iter().map(|&len| 0..len).collect();
+        self.read_region(&region).await
+    }
+
+    /// Evaluate `region`, block by block with up to `max_concurrency`
+    /// blocks in flight.  Blocks are cut at the first leaf's chunk
+    /// boundaries, so each of its chunks is read by one block.  The result
+    /// is in row-major order.
+    pub async fn read_region(&self, region: &[Range<usize>]) -> ZarrResult<Vec<f64>> {
+        validate_region(&self.shape, region)?;
+        let out_shape: Vec<usize> = region.iter().map(|r| r.len()).collect();
+        let mut out = vec![f64::NAN; out_shape.iter().product()];
+        if out.is_empty() {
+            return Ok(out);
+        }
+        // The region on the chunk grid, whose blocks are shifted back after.
+        let shifted: Vec<Range<usize>> = region
+            .iter()
+            .zip(&self.block_offset)
+            .map(|(r, off)| r.start + off..r.end + off)
+            .collect();
+        let blocks: Vec<Vec<Range<usize>>> = chunk_indices_for_region(&self.block_shape, &shifted)
+            .into_iter()
+            .map(|idx| {
+                idx.iter()
+                    .zip(&self.block_shape)
+                    .zip(&shifted)
+                    .zip(&self.block_offset)
+                    .map(|(((i, b), r), off)| {
+                        (i * b).max(r.start) - off..(i * b + b).min(r.end) - off
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut evaluated = futures::stream::iter(blocks)
+            .map(|block| async move {
+                let values = self.eval(&block).await?;
+                Ok::<_, ZarrError>((block, values))
+            })
+            .buffer_unordered(self.config().max_concurrency.max(1));
+        while let Some((block, values)) = evaluated.try_next().await? {
+            scatter_block(&mut out, region, &block, values);
+        }
+        Ok(out)
+    }
+
+    /// Evaluate `region` in one pass.
+    fn eval<'a>(&'a self, region: &'a [Range<usize>]) -> BoxFuture<'a, ZarrResult<Vec<f64>>> {
+        async move {
+            match &*self.node {
+                Node::View(view) => view.read_region(region).await,
+                Node::Map(inner, f) => {
+                    let mut values = inner.eval(region).await?;
+                    values.iter_mut().for_each(|x| *x = f(*x));
+                    Ok(values)
+                }
+                Node::Binary(op, a, b) => {
+                    let (a, b) = futures::try_join!(a.eval(region), b.eval(region))?;
+                    Ok(a.into_iter().zip(b).map(|(a, b)| op.apply(a, b)).collect())
+                }
+                Node::Mask(values, mask) => {
+                    let (values, mask) =
+                        futures::try_join!(values.eval(region), mask.eval(region))?;
+                    Ok(values
+                        .into_iter()
+                        .zip(mask)
+                        .map(|(x, m)| if m == 0.0 || m.is_nan() { f64::NAN } else { x })
+                        .collect())
+                }
+                Node::Constant(value) => Ok(vec![*value; region.iter().map(|r| r.len()).product()]),
+                Node::Concat { axis, parts } => {
+                    let axis = *axis;
+                    let mut start = 0;
+                    let mut reads = Vec::new();
+                    for part in parts {
+                        let len = part.shape[axis];
+                        let (lo, hi) = (
+                            region[axis].start.max(start),
+                            region[axis].end.min(start + len),
+                        );
+                        if lo < hi {
+                            let mut local = region.to_vec();
+                            local[axis] = lo - start..hi - start;
+                            let mut block = region.to_vec();
+                            block[axis] = lo..hi;
+                            reads.push(async move {
+                                let values = part.eval(&local).await?;
+                                Ok::<_, ZarrError>((block, values))
+                            });
+                        }
+                        start += len;
+                    }
+                    let mut out = vec![f64::NAN; region.iter().map(|r| r.len()).product()];
+                    for (block, values) in futures::future::try_join_all(reads).await? {
+                        scatter_block(&mut out, region, &block, values);
+                    }
+                    Ok(out)
+                }
+            }
+        }
+        .boxed()
+    }
+}
+
+/// Copy the row-major `values` of `block` into `out`, the row-major values
+/// of `region`, which contains `block`.
+fn scatter_block(
+    out: &mut [f64],
+    region: &[Range<usize>],
+    block: &[Range<usize>],
+    values: Vec<f64>,
+) {
+    let out_shape: Vec<usize> = region.iter().map(|r| r.len()).collect();
+    let out_strides = strides(&out_shape, ArrayOrder::C);
+    let block_shape: Vec<usize> = block.iter().map(|r| r.len()).collect();
+    for (i, value) in values.into_iter().enumerate() {
+        let mut rest = i;
+        let mut dst = 0;
+        for d in (0..block.len()).rev() {
+            let local = rest % block_shape[d];
+            rest /= block_shape[d];
+            dst += (block[d].start + local - region[d].start) * out_strides[d];
+        }
+        out[dst] = value;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Concatenation
+// ---------------------------------------------------------------------------
+
+/// Join `parts` end to end along `axis`, e.g. one array per day into one
+/// time series.  The parts must agree on every other axis.  Reads touch
+/// only the parts a region overlaps.
+pub fn concat<T: Into<ComputedArray>>(
+    parts: impl IntoIterator<Item = T>,
+    axis: usize,
+) -> ZarrResult<ComputedArray> {
+    let parts: Vec<ComputedArray> = parts.into_iter().map(Into::into).collect();
+    let first = parts
+        .first()
+        .ok_or_else(|| ZarrError::Other("Nothing to concatenate".into()))?;
+    if axis >= first.shape.len() {
+        return Err(ZarrError::Other(format!(
+            "Axis {axis} out of range for {}-dimensional arrays",
+            first.shape.len()
+        )));
+    }
+    let mut shape = first.shape.clone();
+    shape[axis] = 0;
+    for part in &parts {
+        let matches = part.shape.len() == shape.len()
+            && (0..shape.len()).all(|d| d == axis || part.shape[d] == shape[d]);
+        if !matches {
+            return Err(ZarrError::Other(format!(
+                "Cannot concatenate shape {:?} with {:?} along axis {axis}",
+                part.shape, first.shape
+            )));
+        }
+        shape[axis] += part.shape[axis];
+    }
+    Ok(ComputedArray {
+        shape,
+        block_shape: first.block_shape.clone(),
+        block_offset: first.block_offset.clone(),
+        config: first.config.clone(),
+        node: Arc::new(Node::Concat { axis, parts }),
+    })
+}
+
+/// An array of `shape` holding `value` everywhere.
+pub fn full(shape: &[usize], value: f64) -> ComputedArray {
+    ComputedArray {
+        shape: shape.to_vec(),
+        block_shape: shape.iter().map(|&len| len.max(1)).collect(),
+        block_offset: vec![0; shape.len()],
+        config: config::global(),
+        node: Arc::new(Node::Constant(value)),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Mosaic
+// ---------------------------------------------------------------------------
+
+/// Stitch a grid of tiles, e.g. separately stored scenes, into one virtual
+/// array.  `tiles[row][col]` is placed along the last two axes (y, x); every
+/// tile in a row has the same height, every tile in a column the same
+/// width, and the leading axes agree.  `None` tiles read as `fill`.
+pub fn mosaic<T: Into<ComputedArray>>(
+    tiles: Vec<Vec<Option<T>>>,
+    fill: f64,
+) -> ZarrResult<ComputedArray> {
+    let tiles: Vec<Vec<Option<ComputedArray>>> = tiles
+        .into_iter()
+        .map(|row| row.into_iter().map(|tile| tile.map(Into::into)).collect())
+        .collect();
+    let cols = tiles.first().map_or(0, Vec::len);
+    if cols == 0 || tiles.iter().any(|row| row.len() != cols) {
+        return Err(ZarrError::Other(
+            "A mosaic needs a non-empty rectangular grid of tiles".into(),
+        ));
+    }
+    let Some(first) = tiles.iter().flatten().flatten().next() else {
+        return Err(ZarrError::Other("A mosaic needs at least one tile".into()));
+    };
+    let ndim = first.shape.len();
+    if ndim < 2 {
+        return Err(ZarrError::Other(format!(
+            "Mosaic tiles need at least 2 dimensions, got {ndim}"
+        )));
+    }
+    let lead = first.shape[..ndim - 2].to_vec();
+
+    if let Some(tile) = tiles
+        .iter()
+        .flatten()
+        .flatten()
+        .find(|tile| tile.shape.len() != ndim || tile.shape[..ndim - 2] != lead)
+    {
+        return Err(ZarrError::Other(format!(
+            "Mosaic tile shape {:?} doesn't match {:?}",
+            tile.shape, first.shape
+        )));
+    }
+
+    // The height of each row and width of each column, from its tiles.
+    let extent = |tiles: &mut dyn Iterator<Item = &Option<ComputedArray>>, axis: usize| {
+        let mut sizes = tiles.flatten().map(|tile| tile.shape[axis]);
+        match sizes.next() {
+            Some(size) if sizes.all(|s| s == size) => Ok(size),
+            Some(_) => Err(ZarrError::Other(format!(
+                "Mosaic tiles disagree on the size of axis {axis}"
+            ))),
+            None => Err(ZarrError::Other(
+                "Every mosaic row and column needs at least one tile".into(),
+            )),
+        }
+    };
+    let heights = tiles
+        .iter()
+        .map(|row| extent(&mut row.iter(), ndim - 2))
+        .collect::<ZarrResult<Vec<_>>>()?;
+    let widths = (0..cols)
+        .map(|c| extent(&mut tiles.iter().map(|row| &row[c]), ndim - 1))
+        .collect::<ZarrResult<Vec<_>>>()?;
+    let rows = tiles
+        .into_iter()
+        .zip(&heights)
+        .map(|(row, &height)| {
+            let row = row.into_iter().zip(&widths).map(|(tile, &width)| {
+                tile.unwrap_or_else(|| full(&[lead.as_slice(), &[height, width]].concat(), fill))
+            });
+            concat(row, ndim - 1)
+        })
+        .collect::<ZarrResult<Vec<_>>>()?;
+    concat(rows, ndim - 2)
+}
@@ -0,0 +1,283 @@
+//! Crate-wide configuration: concurrency, read-ahead, chunk caching, retries,
+//! timeouts and blosc threading.
+//!
+//! A [`SimplezarrConfig`] can be built explicitly with
+//! [`SimplezarrConfig::builder`], read from `SIMPLEZARR_*` environment
+//! variables with [`SimplezarrConfig::from_env`], or installed process-wide
+//! with [`set_global`].  Arrays opened without an explicit config pick up
+//! [`global`].
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::error::{ZarrError, ZarrResult};
+
+// ---------------------------------------------------------------------------
+// Environment variable names
+// ---------------------------------------------------------------------------
+
+pub const ENV_MAX_CONCURRENCY: &str = "SIMPLEZARR_MAX_CONCURRENCY";
+pub const ENV_READ_AHEAD: &str = "SIMPLEZARR_READ_AHEAD";
+pub const ENV_CHUNK_CACHE_BYTES: &str = "SIMPLEZARR_CHUNK_CACHE_BYTES";
+pub const ENV_MAX_RETRIES: &str = "SIMPLEZARR_MAX_RETRIES";
+pub const ENV_RETRY_BACKOFF_MS: &str = "SIMPLEZARR_RETRY_BACKOFF_MS";
+pub const ENV_REQUEST_TIMEOUT_MS: &str = "SIMPLEZARR_REQUEST_TIMEOUT_MS";
+pub const ENV_BLOSC_NTHREADS: &str = "SIMPLEZARR_BLOSC_NTHREADS";
+pub const ENV_BLOSC_MAX_THREADS: &str = "SIMPLEZARR_BLOSC_MAX_THREADS";
+pub const ENV_FAIL_FAST: &str = "SIMPLEZARR_FAIL_FAST";
+pub const ENV_MAX_CHUNKS: &str = "SIMPLEZARR_MAX_CHUNKS";
+
+// ---------------------------------------------------------------------------
+// RetryPolicy
+// ---------------------------------------------------------------------------
+
+/// Retry behaviour for storage reads.  Only storage errors and timeouts are
+/// retried; a missing key is never an error and is never retried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt (0 disables retrying).
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled after every further attempt.
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between attempts.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Backoff to wait before retry number `attempt` (0-based).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt).unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// SimplezarrConfig
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimplezarrConfig {
+    /// Maximum number of chunk (or array metadata) requests in flight at once.
+    pub max_concurrency: usize,
+    /// Number of chunks [`iter_blocks`](crate::array::UnifiedZarrArray::iter_blocks)
+    /// fetches ahead of the one being consumed (0 fetches one at a time).
+    pub read_ahead: usize,
+    /// Capacity of the per-array decoded chunk cache in bytes (0 disables it).
+    pub chunk_cache_bytes: usize,
+    /// Retry policy applied to every storage read.
+    pub retry: RetryPolicy,
+    /// Timeout for a single storage read attempt (`None` waits forever).
+    pub request_timeout: Option<Duration>,
+    /// Threads each blosc encode or decode of an array opened with this
+    /// configuration uses, unless its codec asks for a number.  Chunks are
+    /// already decoded `max_concurrency` at a time, so more pays off mostly
+    /// for large chunks read one by one.
+    pub blosc_nthreads: usize,
+    /// Most threads a single blosc encode or decode may use, whatever the
+    /// codec's [`nthreads`](crate::codecs::blosc::BloscCodec::nthreads) asks
+    /// for.  Codecs not belonging to an array use the [`global`] one.
+    pub blosc_max_threads: usize,
+    /// Fail a read at its first chunk error, dropping the chunk fetches
+    /// still in flight.  When off, every chunk is fetched and all errors
+    /// are returned together as [`ZarrError::ChunkErrors`].
+    pub fail_fast: bool,
+    /// Largest chunk grid an opened array may have.  Every chunk key is
+    /// listed when metadata is parsed, so this keeps a hostile `.zarray`
+    /// from exhausting memory; raise it for arrays of more chunks.
+    pub max_chunks: usize,
+}
+
+impl Default for SimplezarrConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 32,
+            read_ahead: 2,
+            chunk_cache_bytes: 0,
+            retry: RetryPolicy::default(),
+            request_timeout: None,
+            blosc_nthreads: 1,
+            blosc_max_threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            fail_fast: true,
+            max_chunks: 1 << 22,
+        }
+    }
+}
+
+impl SimplezarrConfig {
+    /// Start a builder from the default configuration.
+    pub fn builder() -> SimplezarrConfigBuilder {
+        SimplezarrConfigBuilder::default()
+    }
+
+    /// Build a configuration from the defaults overridden by any
+    /// `SIMPLEZARR_*` environment variables that are set.
+    pub fn from_env() -> ZarrResult<Self> {
+        Ok(SimplezarrConfigBuilder::default().with_env()?.build())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Builder
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Default)]
+pub struct SimplezarrConfigBuilder {
+    config: SimplezarrConfig,
+}
+
+impl SimplezarrConfigBuilder {
+    pub fn max_concurrency(mut self, n: usize) -> Self {
+        self.config.max_concurrency = n.max(1);
+        self
+    }
+
+    pub fn read_ahead(mut self, n: usize) -> Self {
+        self.config.read_ahead = n;
+        self
+    }
+
+    pub fn chunk_cache_bytes(mut self, bytes: usize) -> Self {
+        self.config.chunk_cache_bytes = bytes;
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.config.retry = retry;
+        self
+    }
+
+    pub fn max_retries(mut self, n: u32) -> Self {
+        self.config.retry.max_retries = n;
+        self
+    }
+
+    pub fn retry_backoff(mut self, initial: Duration) -> Self {
+        self.config.retry.initial_backoff = initial;
+        self
+    }
+
+    pub fn request_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.request_timeout = timeout;
+        self
+    }
+
+    pub fn blosc_nthreads(mut self, n: usize) -> Self {
+        self.config.blosc_nthreads = n.max(1);
+        self
+    }
+
+    pub fn blosc_max_threads(mut self, n: usize) -> Self {
+        self.config.blosc_max_threads = n.max(1);
+        self
+    }
+
+    pub fn fail_fast(mut self, fail_fast: bool) -> Self {
+        self.config.fail_fast = fail_fast;
+        self
+    }
+
+    pub fn max_chunks(mut self, n: usize) -> Self {
+        self.config.max_chunks = n;
+        self
+    }
+
+    /// Override the current settings with any `SIMPLEZARR_*` environment
+    /// variables that are set.  Unparseable values are reported as errors
+    /// rather than ignored.
+    pub fn with_env(mut self) -> ZarrResult<Self> {
+        if let Some(n) = env_parse::<usize>(ENV_MAX_CONCURRENCY)? {
+            self = self.max_concurrency(n);
+        }
+        if let Some(n) = env_parse::<usize>(ENV_READ_AHEAD)? {
+            self = self.read_ahead(n);
+        }
+        if let Some(n) = env_parse::<usize>(ENV_CHUNK_CACHE_BYTES)? {
+            self = self.chunk_cache_bytes(n);
+        }
+        if let Some(n) = env_parse::<u32>(ENV_MAX_RETRIES)? {
+            self = self.max_retries(n);
+        }
+        if let Some(ms) = env_parse::<u64>(ENV_RETRY_BACKOFF_MS)? {
+            self = self.retry_backoff(Duration::from_millis(ms));
+        }
+        if let Some(ms) = env_parse::<u64>(ENV_REQUEST_TIMEOUT_MS)? {
+            // 0 means "no timeout", matching the builder default.
+            let timeout = (ms > 0).then(|| Duration::from_millis(ms));
+            self = self.request_timeout(timeout);
+        }
+        if let Some(n) = env_parse::<usize>(ENV_BLOSC_NTHREADS)? {
+            self = self.blosc_nthreads(n);
+        }
+        if let Some(n) = env_parse::<usize>(ENV_BLOSC_MAX_THREADS)? {
+            self = self.blosc_max_threads(n);
+        }
+        if let Some(fail_fast) = env_parse::<bool>(ENV_FAIL_FAST)? {
+            self = self.fail_fast(fail_fast);
+        }
+        if let Some(n) = env_parse::<usize>(ENV_MAX_CHUNKS)? {
+            self = self.max_chunks(n);
+        }
+        Ok(self)
+    }
+
+    pub fn build(self) -> SimplezarrConfig {
+        self.config
+    }
+}
+
+fn env_parse<T: std::str::FromStr>(name: &str) -> ZarrResult<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    match std::env::var(name) {
+        Ok(s) if s.trim().is_empty() => Ok(None),
+        Ok(s) => s
+            .trim()
+            .parse::<T>()
+            .map(Some)
+            .map_err(|e| ZarrError::Other(format!("Invalid value for {name}={s}: {e}"))),
+        Err(std::env::VarError::NotPresent) => Ok(None),
+        Err(e) => Err(ZarrError::Other(format!("Invalid value for {name}: {e}"))),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Global configuration
+// ---------------------------------------------------------------------------
+
+static GLOBAL: RwLock<Option<Arc<SimplezarrConfig>>> = RwLock::new(None);
+
+/// The process-wide configuration used by `open`/`open_group`.
+///
+/// On first use this is initialised from the environment.  Unlike
+/// [`SimplezarrConfig::from_env`], a malformed `SIMPLEZARR_*` value is not
+/// reported: the whole environment is ignored and the defaults are used.  Call
+/// `from_env` yourself and pass the result to [`set_global`] to surface such
+/// errors.
+pub fn global() -> Arc<SimplezarrConfig> {
+    if let Some(cfg) = GLOBAL.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        return cfg.clone();
+    }
+    let mut guard = GLOBAL.write().unwrap_or_else(|e| e.into_inner());
+    guard
+        .get_or_insert_with(|| Arc::new(SimplezarrConfig::from_env().unwrap_or_default()))
+        .clone()
+}
+
+/// Replace the process-wide configuration.  Arrays that are already open keep
+/// the configuration they were opened with.
+pub fn set_global(config: SimplezarrConfig) {
+    *GLOBAL.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(config));
+}
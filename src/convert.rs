@@ -0,0 +1,501 @@
+//! Converting arrays between Zarr V2 and V3.
+//!
+//! Metadata is translated field by field: NumPy dtype strings to V3 names,
+//! the V2 compressor and filters to a V3 codec list, fill values and chunk
+//! key encodings.  The codecs produce the same bytes in both formats, so
+//! chunks are copied unchanged unless [`ConvertOptions::reencode`] asks for
+//! a different compressor.
+//!
+//! Chunks are copied before the target metadata is written, so an
+//! interrupted conversion leaves no array behind.  Only arrays are
+//! converted; groups are left to the caller.
+
+use std::sync::Arc;
+
+use futures::{StreamExt, TryStreamExt};
+
+use crate::array::chunk_indices_for_region;
+use crate::attributes::Attributes;
+use crate::capabilities::FeatureId;
+use crate::codecs::{AnyCodec, CodecKind, CodecPipeline, codec_to_json};
+use crate::config::{self, SimplezarrConfig};
+use crate::error::{ZarrError, ZarrResult};
+use crate::metadata::fill_value_to_json;
+use crate::metadata::v2::{V2DataType, ZarrV2Metadata};
+use crate::metadata::v3::{ChunkKeyEncoding, ZarrV3Metadata, v3_dtype_name};
+use crate::options::ArrayReadOptions;
+use crate::store::{StorageBackend, get_with_retry};
+use crate::transformers::apply_storage_transformers;
+use crate::types::{ArrayOrder, DataType, Endian, FillValue, ZarrValue, default_fill_value};
+use crate::v2::{codec_to_compressor, codec_to_filter, get_codec_equivalents};
+
+// ---------------------------------------------------------------------------
+// Options
+// ---------------------------------------------------------------------------
+
+/// How [`convert_v2_to_v3`] and [`convert_v3_to_v2`] write the target.
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    /// Chunk key encoding of V3 targets.  V2 targets always use `0.0` keys.
+    pub chunk_key_encoding: ChunkKeyEncoding,
+    /// Decode every chunk and encode it again with [`compressor`](Self::compressor)
+    /// instead of copying the stored bytes.
+    pub reencode: bool,
+    /// Compressor used when re-encoding; `None` stores chunks uncompressed.
+    pub compressor: Option<AnyCodec>,
+    /// Replace existing metadata at the target instead of failing.
+    pub overwrite: bool,
+}
+
+impl ConvertOptions {
+    pub fn chunk_key_encoding(mut self, encoding: ChunkKeyEncoding) -> Self {
+        self.chunk_key_encoding = encoding;
+        self
+    }
+
+    /// Re-encode chunks with `compressor`.  Needed when converting a V3 array
+    /// with several compressors to V2, which allows only one.
+    pub fn reencode(mut self, compressor: impl Into<Option<AnyCodec>>) -> Self {
+        self.reencode = true;
+        self.compressor = compressor.into();
+        self
+    }
+
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Metadata translation
+// ---------------------------------------------------------------------------
+
+/// The V3 `zarr.json` document for a V2 array.
+///
+/// Fails for Fortran-ordered arrays (V3 needs a `transpose` codec, which is
+/// not supported) and fixed-width string types, which have no V3
+/// equivalent with the same chunk bytes.
+pub fn v2_to_v3_metadata(
+    md: &ZarrV2Metadata,
+    attributes: Option<&Attributes>,
+    options: &ConvertOptions,
+) -> ZarrResult<serde_json::Value> {
+    let dtype_str = md.document["dtype"].as_str().unwrap_or_default();
+    let data_type = match (md.dtype.data_type, &md.dtype.time_unit) {
+        (DataType::String | DataType::Bytes, _) => {
+            return Err(ZarrError::UnsupportedFeature(FeatureId::DataType(
+                dtype_str.to_string(),
+            )));
+        }
+        (_, Some(unit)) => {
+            let name = if dtype_str.chars().nth(1) == Some('m') {
+                "numpy.timedelta64"
+            } else {
+                "numpy.datetime64"
+            };
+            serde_json::json!({
+                "name": name,
+                "configuration": {"unit": unit, "scale_factor": 1},
+            })
+        }
+        (dtype, None) => v3_dtype_name(dtype).into(),
+    };
+
+    let codecs = v3_codecs_json(
+        md.order,
+        &get_codec_equivalents(md)?,
+        md.dtype.data_type,
+        options,
+    )?;
+    let mut document = serde_json::json!({
+        "zarr_format": 3,
+        "node_type": "array",
+        "shape": md.shape,
+        "data_type": data_type,
+        "chunk_grid": {
+            "name": "regular",
+            "configuration": {"chunk_shape": md.chunks},
+        },
+        "chunk_key_encoding": options.chunk_key_encoding.to_json(),
+        "fill_value": v3_fill_json(md.dtype.data_type, &md.fill_value),
+        "codecs": codecs,
+    });
+    if let Some(attributes) = attributes {
+        document["attributes"] = attributes.clone().into();
+    }
+    Ok(document)
+}
+
+/// The V2 `.zarray` document for a V3 array.
+///
+/// Fails for codecs V2 can't express: sharding, array-to-array codecs
+/// other than numcodecs filters, and more than one compressor (unless
+/// re-encoding).  V3 attributes go in `.zattrs`, which this doesn't cover.
+pub fn v3_to_v2_metadata(
+    md: &ZarrV3Metadata,
+    options: &ConvertOptions,
+) -> ZarrResult<serde_json::Value> {
+    let (filters, compressor, byte_order) = v2_compression(&md.codecs, md.data_type, options)?;
+    if matches!(md.data_type, DataType::String | DataType::Bytes) {
+        return Err(ZarrError::UnsupportedFeature(FeatureId::DataType(
+            v3_dtype_name(md.data_type),
+        )));
+    }
+    let time_unit = md.document["data_type"]
+        .pointer("/configuration/unit")
+        .and_then(|u| u.as_str());
+    let dtype = match (md.document["data_type"]["name"].as_str(), time_unit) {
+        (Some(name @ ("numpy.datetime64" | "numpy.timedelta64")), Some(unit)) => {
+            let code = if name == "numpy.datetime64" { 'M' } else { 'm' };
+            let order = if byte_order == Endian::Big { '>' } else { '<' };
+            format!("{order}{code}8[{unit}]").into()
+        }
+        _ => serde_json::to_value(V2DataType {
+            data_type: md.data_type,
+            byte_order,
+            time_unit: None,
+        })?,
+    };
+
+    Ok(serde_json::json!({
+        "zarr_format": 2,
+        "shape": md.shape,
+        "chunks": md.chunks,
+        "dtype": dtype,
+        "fill_value": fill_value_to_json(&md.fill_value),
+        "order": ArrayOrder::C,
+        "compressor": compressor,
+        "filters": filters,
+    }))
+}
+
+/// The V3 codec list, as JSON, for an array stored in `order` with the
+/// unified codecs `source`.  Fortran order would need a `transpose` codec,
+/// which is not supported.
+pub(crate) fn v3_codecs_json(
+    order: ArrayOrder,
+    source: &[AnyCodec],
+    dtype: DataType,
+    options: &ConvertOptions,
+) -> ZarrResult<Vec<serde_json::Value>> {
+    if order == ArrayOrder::F {
+        return Err(ZarrError::UnsupportedFeature(FeatureId::Codec(
+            "transpose".into(),
+        )));
+    }
+    let codecs = target_codecs(source, dtype, options)?;
+    Ok(codecs.iter().map(codec_to_json).collect())
+}
+
+/// The V2 `filters` and `compressor` for the V3 codecs `source`, and the
+/// byte order of the stored elements.
+pub(crate) fn v2_compression(
+    source: &[AnyCodec],
+    dtype: DataType,
+    options: &ConvertOptions,
+) -> ZarrResult<(
+    Option<Vec<serde_json::Value>>,
+    Option<serde_json::Value>,
+    Endian,
+)> {
+    let codecs = target_codecs(source, dtype, options)?;
+    let pipeline = CodecPipeline::new(&codecs)?;
+    let mut filters = Vec::new();
+    let mut compressors = Vec::new();
+    for codec in &codecs {
+        match codec.kind() {
+            CodecKind::ArrayToArray => filters.push(codec_to_filter(codec)?),
+            CodecKind::ArrayToBytes => {
+                if let AnyCodec::Sharding(_) = codec {
+                    return Err(ZarrError::UnsupportedFeature(FeatureId::Codec(
+                        codec.codec_id().to_string(),
+                    )));
+                }
+            }
+            CodecKind::BytesToBytes => compressors.push(codec_to_compressor(codec)?),
+        }
+    }
+    if compressors.len() > 1 {
+        return Err(ZarrError::Codec(format!(
+            "V2 arrays have one compressor, this array has {}; re-encode it",
+            compressors.len()
+        )));
+    }
+
+    let byte_order = match dtype.byte_size() {
+        Some(1) | None => Endian::NotApplicable,
+        _ if matches!(dtype, DataType::Raw(_)) => Endian::NotApplicable,
+        _ => pipeline.endian(),
+    };
+    Ok((
+        (!filters.is_empty()).then_some(filters),
+        compressors.pop(),
+        byte_order,
+    ))
+}
+
+/// `source` in V3 stage order, with its compressors replaced when
+/// re-encoding.  Blosc gets the element size V3 requires.
+pub(crate) fn target_codecs(
+    source: &[AnyCodec],
+    dtype: DataType,
+    options: &ConvertOptions,
+) -> ZarrResult<Vec<AnyCodec>> {
+    let stage = |kind: CodecKind| source.iter().filter(move |c| c.kind() == kind).cloned();
+    let mut codecs: Vec<AnyCodec> = stage(CodecKind::ArrayToArray)
+        .chain(stage(CodecKind::ArrayToBytes))
+        .collect();
+    if options.reencode {
+        codecs.extend(options.compressor.clone());
+    } else {
+        codecs.extend(stage(CodecKind::BytesToBytes));
+    }
+    let typesize = CodecPipeline::new(&codecs)?
+        .encoded_data_type(dtype)?
+        .byte_size();
+    for codec in &mut codecs {
+        if let AnyCodec::Blosc(c) = codec {
+            c.typesize = c.typesize.or(typesize);
+        }
+    }
+    Ok(codecs)
+}
+
+/// A V3 fill value: never null, and raw values as byte arrays.
+pub(crate) fn v3_fill_json(dtype: DataType, fill: &FillValue) -> serde_json::Value {
+    let is_float = matches!(
+        dtype,
+        DataType::Float16
+            | DataType::Float32
+            | DataType::Float64
+            | DataType::Complex64
+            | DataType::Complex128
+    );
+    match fill {
+        // V2 `null` fills parse as NaN; V3 needs a value of the right type.
+        FillValue::NaN | FillValue::Infinity | FillValue::NegativeInfinity if !is_float => {
+            fill_value_to_json(&default_fill_value(dtype))
+        }
+        FillValue::Value(ZarrValue::Bytes(bytes)) if matches!(dtype, DataType::Raw(_)) => {
+            bytes.as_slice().into()
+        }
+        // Complex fills are `[real, imag]`, non-finite ones included.
+        FillValue::NaN | FillValue::Infinity | FillValue::NegativeInfinity
+            if matches!(dtype, DataType::Complex64 | DataType::Complex128) =>
+        {
+            serde_json::json!([fill_value_to_json(fill), 0.0])
+        }
+        other => fill_value_to_json(other),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Conversion
+// ---------------------------------------------------------------------------
+
+/// Convert the V2 array at `src_path` in `src` to a V3 array at `dst_path`
+/// in `dst`, returning how many chunks were copied.
+///
+/// Uses the process-wide [`config::global`] configuration.
+pub async fn convert_v2_to_v3<S, D>(
+    src: Arc<S>,
+    src_path: &str,
+    dst: Arc<D>,
+    dst_path: &str,
+    options: &ConvertOptions,
+) -> ZarrResult<usize>
+where
+    S: StorageBackend + 'static,
+    D: StorageBackend + 'static,
+{
+    convert_v2_to_v3_with_config(src, src_path, dst, dst_path, options, config::global()).await
+}
+
+/// [`convert_v2_to_v3`] with an explicit configuration.  At most
+/// `max_concurrency` chunks are copied at once.
+pub async fn convert_v2_to_v3_with_config<S, D>(
+    src: Arc<S>,
+    src_path: &str,
+    dst: Arc<D>,
+    dst_path: &str,
+    options: &ConvertOptions,
+    config: Arc<SimplezarrConfig>,
+) -> ZarrResult<usize>
+where
+    S: StorageBackend + 'static,
+    D: StorageBackend + 'static,
+{
+    let zarray = get_with_retry(src.as_ref(), &src.join(src_path, ".zarray"), &config)
+        .await?
+        .ok_or_else(|| ZarrError::NotFound(format!("No .zarray at {src_path}")))?;
+    let md = ZarrV2Metadata::parse(&zarray)?;
+    let attributes =
+        match get_with_retry(src.as_ref(), &src.join(src_path, ".zattrs"), &config).await? {
+            Some(bytes) => Some(serde_json::from_slice::<Attributes>(&bytes)?),
+            None => None,
+        };
+    let document =
+        serde_json::to_vec_pretty(&v2_to_v3_metadata(&md, attributes.as_ref(), options)?)?;
+    let target = ZarrV3Metadata::parse(&document)?;
+
+    let zarr_json = dst.join(dst_path, "zarr.json");
+    check_target(dst.as_ref(), &zarr_json, options).await?;
+    let copy = ChunkCopy {
+        shape: &md.shape,
+        chunks: &md.chunks,
+        dtype: md.dtype.data_type,
+        source_codecs: &get_codec_equivalents(&md)?,
+        source_keys: ChunkKeyEncoding::V2 {
+            separator: md.dimension_separator,
+        },
+        target_codecs: &target.codecs,
+        target_keys: target.chunk_key_encoding,
+        reencode: options.reencode,
+    };
+    let copied = copy
+        .run(src.as_ref(), src_path, dst.as_ref(), dst_path, &config)
+        .await?;
+    dst.put(&zarr_json, document.into()).await?;
+    Ok(copied)
+}
+
+/// Convert the V3 array at `src_path` in `src` to a V2 array at `dst_path`
+/// in `dst`, returning how many chunks were copied.  Attributes are
+/// written to `.zattrs`.
+///
+/// Uses the process-wide [`config::global`] configuration.
+pub async fn convert_v3_to_v2<S, D>(
+    src: Arc<S>,
+    src_path: &str,
+    dst: Arc<D>,
+    dst_path: &str,
+    options: &ConvertOptions,
+) -> ZarrResult<usize>
+where
+    S: StorageBackend + 'static,
+    D: StorageBackend + 'static,
+{
+    convert_v3_to_v2_with_config(src, src_path, dst, dst_path, options, config::global()).await
+}
+
+/// [`convert_v3_to_v2`] with an explicit configuration.  At most
+/// `max_concurrency` chunks are copied at once.
+pub async fn convert_v3_to_v2_with_config<S, D>(
+    src: Arc<S>,
+    src_path: &str,
+    dst: Arc<D>,
+    dst_path: &str,
+    options: &ConvertOptions,
+    config: Arc<SimplezarrConfig>,
+) -> ZarrResult<usize>
+where
+    S: StorageBackend + 'static,
+    D: StorageBackend + 'static,
+{
+    let zarr_json = get_with_retry(src.as_ref(), &src.join(src_path, "zarr.json"), &config)
+        .await?
+        .ok_or_else(|| ZarrError::NotFound(format!("No zarr.json at {src_path}")))?;
+    let md = ZarrV3Metadata::parse(&zarr_json)?;
+    let document = serde_json::to_vec_pretty(&v3_to_v2_metadata(&md, options)?)?;
+    let target = ZarrV2Metadata::parse(&document)?;
+
+    let source = apply_storage_transformers(src, &md.storage_transformers)?;
+
+    let zarray = dst.join(dst_path, ".zarray");
+    check_target(dst.as_ref(), &zarray, options).await?;
+    let copy = ChunkCopy {
+        shape: &md.shape,
+        chunks: &md.chunks,
+        dtype: md.data_type,
+        source_codecs: &md.codecs,
+        source_keys: md.chunk_key_encoding,
+        target_codecs: &get_codec_equivalents(&target)?,
+        target_keys: ChunkKeyEncoding::V2 { separator: '.' },
+        reencode: options.reencode,
+    };
+    let copied = copy
+        .run(source.as_ref(), src_path, dst.as_ref(), dst_path, &config)
+        .await?;
+    if let Some(attributes) = md.attributes.as_ref().filter(|a| !a.is_empty()) {
+        let zattrs = dst.join(dst_path, ".zattrs");
+        dst.put(&zattrs, serde_json::to_vec_pretty(attributes)?.into())
+            .await?;
+    }
+    dst.put(&zarray, document.into()).await?;
+    Ok(copied)
+}
+
+/// Fail if `metadata_path` exists and overwriting wasn't asked for.
+async fn check_target(
+    dst: &dyn StorageBackend,
+    metadata_path: &str,
+    options: &ConvertOptions,
+) -> ZarrResult<()> {
+    if !options.overwrite && dst.head(metadata_path).await?.is_some() {
+        return Err(ZarrError::Metadata(format!(
+            "An array already exists at {metadata_path}"
+        )));
+    }
+    Ok(())
+}
+
+/// The chunks of one array and how to move them to the other format.
+struct ChunkCopy<'a> {
+    shape: &'a [usize],
+    chunks: &'a [usize],
+    dtype: DataType,
+    source_codecs: &'a [AnyCodec],
+    source_keys: ChunkKeyEncoding,
+    target_codecs: &'a [AnyCodec],
+    target_keys: ChunkKeyEncoding,
+    reencode: bool,
+}
+
+impl ChunkCopy<'_> {
+    /// Copy every stored chunk, returning how many there were.
+    async fn run(
+        &self,
+        src: &dyn StorageBackend,
+        src_path: &str,
+        dst: &dyn StorageBackend,
+        dst_path: &str,
+        config: &SimplezarrConfig,
+    ) -> ZarrResult<usize> {
+        let region: Vec<_> = self.shape.iter().map(|&s| 0..s).collect();
+        let indices = chunk_indices_for_region(self.chunks, &region);
+        let copied: Vec<bool> = futures::stream::iter(indices)
+            .map(|index| async move {
+                let key = self.source_keys.key(&index);
+                let Some(raw) = get_with_retry(src, &src.join(src_path, &key), config).await?
+                else {
+                    return Ok(false);
+                };
+                let data = if self.reencode {
+                    let elements = self.chunks.iter().product();
+                    let values = CodecPipeline::new(self.source_codecs)?
+                        .decode(
+                            &raw,
+                            &key,
+                            self.dtype,
+                            elements,
+                            &ArrayReadOptions::default(),
+                        )
+                        .await?;
+                    CodecPipeline::new(self.target_codecs)?
+                        .encode(&values)
+                        .await?
+                        .into()
+                } else {
+                    raw
+                };
+                let target = dst.join(dst_path, &self.target_keys.key(&index));
+                dst.put(&target, data).await?;
+                Ok::<_, ZarrError>(true)
+            })
+            .buffer_unordered(config.max_concurrency.max(1))
+            .try_collect()
+            .await?;
+        Ok(copied.into_iter().filter(|&c| c).count())
+    }
+}
@@ -0,0 +1,158 @@
+//! Polars DataFrames from group arrays.
+//!
+//! [`to_polars`](UnifiedZarrGroup::to_polars) reads 1-D arrays of one
+//! length into the columns of a [`DataFrame`].
+//! [`to_polars_with_index`](UnifiedZarrGroup::to_polars_with_index) also
+//! takes N-D arrays of one shape, flattened in row-major order next to an
+//! `Int64` index column per dimension.  Index columns take the arrays'
+//! dimension names (xarray's `_ARRAY_DIMENSIONS`), or `dim_0`, `dim_1`, ...
+//! when there are none or they clash with an array name.
+//!
+//! Element types map to the Polars type of the same name; float16 widens to
+//! `Float32`, byte and raw elements become `Binary`, and complex arrays are
+//! rejected.  Elements stored as null read as null.
+
+use polars::prelude::*;
+
+use crate::array::{
+    NullableColumn, complex_column_error, index_column_names, strides, table_shape,
+};
+use crate::error::{ZarrError, ZarrResult};
+use crate::group::UnifiedZarrGroup;
+use crate::types::{ArrayOrder, DataType, ZarrValue, ZarrVectorValue};
+
+impl UnifiedZarrGroup {
+    /// Read the 1-D arrays `vars`, which must have one length, into the
+    /// columns of a DataFrame, in that order.
+    pub async fn to_polars(&self, vars: &[&str]) -> ZarrResult<DataFrame> {
+        self.dataframe(vars, false).await
+    }
+
+    /// Read the arrays `vars`, which must have one shape, into a DataFrame
+    /// with a row per element: an index column per dimension, then the
+    /// arrays in the order given.
+    pub async fn to_polars_with_index(&self, vars: &[&str]) -> ZarrResult<DataFrame> {
+        self.dataframe(vars, true).await
+    }
+
+    /// Arrays are loaded one at a time; each fetches up to
+    /// `max_concurrency` chunks at once.
+    async fn dataframe(&self, vars: &[&str], with_index: bool) -> ZarrResult<DataFrame> {
+        let arrays = vars
+            .iter()
+            .map(|&name| {
+                self.get_array(name)
+                    .map(|array| (name, array))
+                    .ok_or_else(|| ZarrError::NotFound(format!("No array {name} in group")))
+            })
+            .collect::<ZarrResult<Vec<_>>>()?;
+        let shape = table_shape("DataFrame", &arrays)?;
+        let (first_name, first) = arrays[0];
+        if !with_index && shape.len() != 1 {
+            return Err(ZarrError::Other(format!(
+                "Array {first_name} is {}-dimensional; use to_polars_with_index to flatten it",
+                shape.len()
+            )));
+        }
+
+        let rows: usize = shape.iter().product();
+        let mut columns = Vec::new();
+        if with_index {
+            for (axis, name) in index_column_names(first, vars).into_iter().enumerate() {
+                let inner: usize = shape[axis + 1..].iter().product();
+                let index: Vec<i64> = (0..rows)
+                    .map(|k| (k / inner % shape[axis]) as i64)
+                    .collect();
+                columns.push(Column::new(name.into(), index));
+            }
+        }
+        for (name, array) in arrays {
+            let values = array.load_value().await?;
+            let mut series = series(name, values.data)?;
+            if let Some(positions) = row_major_positions(shape, values.order) {
+                series = series.take(&positions).map_err(polars_error)?;
+            }
+            columns.push(series.into_column());
+        }
+        DataFrame::new(rows, columns).map_err(polars_error)
+    }
+}
+
+fn polars_error(e: PolarsError) -> ZarrError {
+    ZarrError::Other(format!("polars: {e}"))
+}
+
+/// For each row-major position of `shape`, where `order` stores that
+/// element; `None` when the layouts agree.
+fn row_major_positions(shape: &[usize], order: ArrayOrder) -> Option<IdxCa> {
+    if order == ArrayOrder::C || shape.len() < 2 {
+        return None;
+    }
+    let stored = strides(shape, order);
+    let rows: usize = shape.iter().product();
+    let positions = (0..rows).map(|k| {
+        let mut rest = k;
+        let mut position = 0;
+        for axis in (0..shape.len()).rev() {
+            position += rest % shape[axis] * stored[axis];
+            rest /= shape[axis];
+        }
+        position as IdxSize
+    });
+    Some(IdxCa::from_iter_values(PlSmallStr::EMPTY, positions))
+}
+
+fn series(name: &str, values: ZarrVectorValue) -> ZarrResult<Series> {
+    let name = PlSmallStr::from(name);
+    Ok(match values {
+        ZarrVectorValue::VBool(v) => Series::new(name, v),
+        ZarrVectorValue::VInt8(v) => Series::new(name, v),
+        ZarrVectorValue::VInt16(v) => Series::new(name, v),
+        ZarrVectorValue::VInt32(v) => Series::new(name, v),
+        ZarrVectorValue::VInt64(v) => Series::new(name, v),
+        ZarrVectorValue::VUInt8(v) => Series::new(name, v),
+        ZarrVectorValue::VUInt16(v) => Series::new(name, v),
+        ZarrVectorValue::VUInt32(v) => Series::new(name, v),
+        ZarrVectorValue::VUInt64(v) => Series::new(name, v),
+        ZarrVectorValue::VFloat16(v) => {
+            Series::new(name, v.into_iter().map(f32::from).collect::<Vec<_>>())
+        }
+        ZarrVectorValue::VFloat32(v) => Series::new(name, v),
+        ZarrVectorValue::VFloat64(v) => Series::new(name, v),
+        ZarrVectorValue::VString(v) => Series::new(name, v),
+        ZarrVectorValue::VBytes(v) => BinaryChunked::from_iter_values(name, v.iter()).into_series(),
+        ZarrVectorValue::VWithNulls(dtype, v) => nullable_series(name, dtype, &v)?,
+        ZarrVectorValue::VComplex64(_) | ZarrVectorValue::VComplex128(_) => {
+            return Err(complex_column_error());
+        }
+    })
+}
+
+/// A series of `dtype` values with nulls.
+fn nullable_series(
+    name: PlSmallStr,
+    dtype: DataType,
+    values: &[Option<ZarrValue>],
+) -> ZarrResult<Series> {
+    Ok(match NullableColumn::new(dtype, values)? {
+        NullableColumn::Bool(v) => Series::new(name, v),
+        NullableColumn::Int8(v) => Series::new(name, v),
+        NullableColumn::Int16(v) => Series::new(name, v),
+        NullableColumn::Int32(v) => Series::new(name, v),
+        NullableColumn::Int64(v) => Series::new(name, v),
+        NullableColumn::UInt8(v) => Series::new(name, v),
+        NullableColumn::UInt16(v) => Series::new(name, v),
+        NullableColumn::UInt32(v) => Series::new(name, v),
+        NullableColumn::UInt64(v) => Series::new(name, v),
+        NullableColumn::Float16(v) => Series::new(
+            name,
+            v.into_iter().map(|x| x.map(f32::from)).collect::<Vec<_>>(),
+        ),
+        NullableColumn::Float32(v) => Series::new(name, v),
+        NullableColumn::Float64(v) => Series::new(name, v),
+        NullableColumn::String(v) => Series::new(name, v),
+        NullableColumn::Bytes(v) => {
+            BinaryChunked::from_iter_options(name, v.into_iter()).into_series()
+        }
+    })
+}
@@ -0,0 +1,282 @@
+//! Metadata introspection.
+//!
+//! [`UnifiedMetadata`] can be rendered as the `.zarray` or `zarr.json`
+//! document zarr-python would write for the same array, and summarised in
+//! the layout of zarr-python's `Array.info`.  Comparing these with Python's
+//! output is a quick way to find interop mismatches.
+//!
+//! Datetime types are read as `int64` and are rendered as such.
+
+use serde::Serialize;
+
+use crate::array::{CompressionInfo, UnifiedMetadata};
+use crate::codecs::blosc::BloscShuffle;
+use crate::codecs::{AnyCodec, CodecKind};
+use crate::convert::{ConvertOptions, v2_compression, v3_codecs_json, v3_fill_json};
+use crate::error::{ZarrError, ZarrResult};
+use crate::metadata::fill_value_to_json;
+use crate::metadata::v2::{V2DataType, ZarrCompressor};
+use crate::metadata::v3::{ChunkKeyEncoding, v3_dtype_name};
+use crate::types::{DataType, Endian};
+use crate::v2::{compressor_to_codecs, filter_to_codec};
+
+impl UnifiedMetadata {
+    /// The V2 `.zarray` document for this array.
+    ///
+    /// Fails for V3 codec lists V2 can't express (sharding, several
+    /// compressors).
+    pub fn to_v2_json(&self) -> ZarrResult<serde_json::Value> {
+        let (filters, compressor, byte_order) = match &self.compression_info {
+            CompressionInfo::V2Compression {
+                compressor,
+                filters,
+            } => (
+                filters.clone().unwrap_or_default(),
+                serde_json::to_value(compressor)?,
+                self.byte_order,
+            ),
+            CompressionInfo::V3Codecs(codecs) => {
+                let (filters, compressor, byte_order) =
+                    v2_compression(codecs, self.data_type, &ConvertOptions::default())?;
+                (
+                    serde_json::to_value(filters)?,
+                    compressor.into(),
+                    byte_order,
+                )
+            }
+        };
+        Ok(serde_json::json!({
+            "zarr_format": 2,
+            "shape": self.shape,
+            "chunks": self.chunk_shape,
+            "dtype": V2DataType {
+                data_type: self.data_type,
+                byte_order,
+                time_unit: None,
+            },
+            "fill_value": fill_value_to_json(&self.fill_value),
+            "order": self.order,
+            "compressor": compressor,
+            "filters": filters,
+            "dimension_separator": self.dimension_separator.to_string(),
+        }))
+    }
+
+    /// The V3 `zarr.json` document for this array, with zarr-python's
+    /// defaults for the fields V2 lacks.
+    ///
+    /// V2 arrays keep their `0.0` chunk keys through the `v2` key encoding.
+    /// Fails where [`v2_to_v3_metadata`](crate::convert::v2_to_v3_metadata)
+    /// does, such as for Fortran order.
+    pub fn to_v3_json(&self) -> ZarrResult<serde_json::Value> {
+        let key_encoding = match &self.compression_info {
+            CompressionInfo::V2Compression { .. } => ChunkKeyEncoding::V2 {
+                separator: self.dimension_separator,
+            },
+            CompressionInfo::V3Codecs(_) => ChunkKeyEncoding::default(),
+        };
+        let codecs = v3_codecs_json(
+            self.order,
+            &self.codecs()?,
+            self.data_type,
+            &ConvertOptions::default(),
+        )?;
+
+        let mut document = serde_json::json!({
+            "shape": self.shape,
+            "data_type": v3_dtype_name(self.data_type),
+            "chunk_grid": {
+                "name": "regular",
+                "configuration": {"chunk_shape": self.chunk_shape},
+            },
+            "chunk_key_encoding": key_encoding.to_json(),
+            "fill_value": v3_fill_json(self.data_type, &self.fill_value),
+            "codecs": codecs,
+            "attributes": self.attributes.clone().unwrap_or_default(),
+            "zarr_format": 3,
+            "node_type": "array",
+            "storage_transformers": [],
+        });
+        if let Some(names) = &self.dimension_names {
+            document["dimension_names"] = serde_json::to_value(names)?;
+        }
+        for (name, value) in &self.extensions {
+            document[name] = value.clone();
+        }
+        Ok(document)
+    }
+
+    /// A summary in the layout of zarr-python's `Array.info`:
+    ///
+    /// ```text
+    /// Zarr format : 2
+    /// Data type   : int16
+    /// Shape       : (4, 6)
+    /// ...
+    /// ```
+    pub fn describe(&self) -> String {
+        let mut items: Vec<(String, String)> = vec![
+            ("Type".into(), "Array".into()),
+            ("Zarr format".into(), self.zarr_format.to_string()),
+            ("Data type".into(), self.dtype_name()),
+            ("Shape".into(), python_tuple(&self.shape)),
+            ("Chunk shape".into(), python_tuple(&self.chunk_shape)),
+            ("Order".into(), format!("{:?}", self.order)),
+            (
+                "Fill value".into(),
+                fill_value_to_json(&self.fill_value).to_string(),
+            ),
+        ];
+        match &self.compression_info {
+            CompressionInfo::V2Compression {
+                compressor,
+                filters,
+            } => {
+                let filters: Vec<ZarrCompressor> = filters
+                    .clone()
+                    .and_then(|f| serde_json::from_value(f).ok())
+                    .unwrap_or_default();
+                for (i, filter) in filters.iter().enumerate() {
+                    let repr = filter_to_codec(filter)
+                        .map(|c| numcodecs_repr(&c))
+                        .unwrap_or_else(|_| filter.id.clone());
+                    items.push((format!("Filter [{i}]"), repr));
+                }
+                let compressor = match compressor {
+                    None => "None".to_string(),
+                    Some(comp) => match compressor_to_codecs(comp).as_deref() {
+                        Ok([codec, ..]) => numcodecs_repr(codec),
+                        _ => comp.id.clone(),
+                    },
+                };
+                items.push(("Compressor".into(), compressor));
+            }
+            CompressionInfo::V3Codecs(codecs) => {
+                let stage = |kind: CodecKind| {
+                    let reprs: Vec<String> = codecs
+                        .iter()
+                        .filter(|c| c.kind() == kind)
+                        .map(numcodecs_repr)
+                        .collect();
+                    python_tuple(&reprs)
+                };
+                items.push(("Filters".into(), stage(CodecKind::ArrayToArray)));
+                items.push(("Serializer".into(), stage(CodecKind::ArrayToBytes)));
+                items.push(("Compressors".into(), stage(CodecKind::BytesToBytes)));
+            }
+        }
+        let elements: usize = self.shape.iter().product();
+        if let Some(size) = self.data_type.byte_size() {
+            items.push(("No. bytes".into(), human_bytes(elements * size)));
+        }
+        items.push(("No. chunks".into(), self.keys.len().to_string()));
+
+        let width = items.iter().map(|(k, _)| k.len()).max().unwrap_or(0);
+        items
+            .iter()
+            .map(|(k, v)| format!("{k:<width$} : {v}\n"))
+            .collect()
+    }
+
+    /// The NumPy dtype string, e.g. `<f8`.
+    fn numpy_dtype(&self) -> V2DataType {
+        V2DataType {
+            data_type: self.data_type,
+            byte_order: self.byte_order,
+            time_unit: None,
+        }
+    }
+
+    /// The NumPy dtype name, e.g. `float64`; the dtype string for types
+    /// without one.
+    fn dtype_name(&self) -> String {
+        match self.data_type {
+            DataType::String | DataType::Bytes | DataType::Raw(_) => {
+                serde_json::to_value(self.numpy_dtype())
+                    .ok()
+                    .and_then(|v| v.as_str().map(str::to_string))
+                    .unwrap_or_default()
+            }
+            dtype => v3_dtype_name(dtype),
+        }
+    }
+}
+
+/// How numcodecs / zarr-python print a codec, e.g. `Zstd(level=3)`.
+pub(crate) fn numcodecs_repr(codec: &AnyCodec) -> String {
+    match codec {
+        AnyCodec::Bytes(c) => match c.endian.map(Endian::resolve) {
+            Some(Endian::Big) => "BytesCodec(endian='big')".into(),
+            Some(Endian::Little) => "BytesCodec(endian='little')".into(),
+            _ => "BytesCodec(endian=None)".into(),
+        },
+        AnyCodec::Gzip(c) => format!("GZip(level={})", c.level),
+        AnyCodec::Zlib(c) => format!("Zlib(level={})", c.level),
+        AnyCodec::Zstd(c) => format!("Zstd(level={})", c.level),
+        AnyCodec::Lz4(c) => format!("LZ4(acceleration={})", c.acceleration),
+        AnyCodec::Snappy(_) => "Snappy()".into(),
+        AnyCodec::Lzma(c) => {
+            let preset = c.preset.map_or("None".to_string(), |p| p.to_string());
+            format!(
+                "LZMA(format={}, check={}, preset={preset})",
+                c.format, c.check
+            )
+        }
+        AnyCodec::Brotli(c) => format!("Brotli(level={}, lgwin={})", c.level, c.lgwin),
+        AnyCodec::Blosc(c) => {
+            let shuffle = match c.shuffle {
+                Some(BloscShuffle::Shuffle) => "SHUFFLE",
+                Some(BloscShuffle::BitShuffle) => "BITSHUFFLE",
+                _ => "NOSHUFFLE",
+            };
+            format!(
+                "Blosc(cname='{}', clevel={}, shuffle={shuffle}, blocksize={})",
+                c.cname, c.clevel, c.blocksize
+            )
+        }
+        AnyCodec::Sharding(c) => format!("ShardingCodec(chunk_shape={:?})", c.chunk_shape),
+        AnyCodec::FixedScaleOffset(c) => format!(
+            "FixedScaleOffset(scale={}, offset={}, dtype='{}', astype='{}')",
+            c.scale, c.offset, c.dtype, c.astype
+        ),
+        AnyCodec::BitRound(c) => format!("BitRound(keepbits={})", c.keepbits),
+    }
+}
+
+/// A Python tuple literal: `(4, 6)`, `(3,)` or `()`.
+fn python_tuple<T: std::fmt::Display>(items: &[T]) -> String {
+    let parts: Vec<String> = items.iter().map(|i| i.to_string()).collect();
+    match parts.len() {
+        1 => format!("({},)", parts[0]),
+        _ => format!("({})", parts.join(", ")),
+    }
+}
+
+/// A byte count as zarr-python prints it: `48`, or `800000000 (762.9M)`.
+fn human_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+    let mut size = bytes as f64;
+    let mut unit = None;
+    for u in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = Some(u);
+    }
+    match unit {
+        None => bytes.to_string(),
+        Some(unit) => format!("{bytes} ({size:.1}{unit})"),
+    }
+}
+
+/// Render `value` with `indent` spaces per level and sorted keys, as
+/// zarr-python 2 writes `.zarray` (with an indent of 4).
+pub fn to_python_json(value: &serde_json::Value, indent: usize) -> ZarrResult<String> {
+    let indent = " ".repeat(indent);
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut out = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+    value.serialize(&mut serializer)?;
+    String::from_utf8(out).map_err(|e| ZarrError::Other(e.to_string()))
+}
@@ -0,0 +1,209 @@
+//! Per-chunk fetch and decode timings.
+//!
+//! Attach a [`ChunkDiagnostics`] collector to an array with
+//! [`UnifiedZarrArray::with_diagnostics`] and every chunk it fetches is
+//! recorded: how long the fetch and the decode took, the stored and decoded
+//! sizes and the codecs.  Read the records after a load to find the chunks
+//! that dominate it, such as an uncompressed outlier among compressed
+//! neighbours:
+//!
+//! ```ignore
+//! let diagnostics = Arc::new(ChunkDiagnostics::default());
+//! let array = array.with_diagnostics(diagnostics.clone());
+//! array.load().await?;
+//! println!("{diagnostics}");
+//! ```
+//!
+//! Chunks served from the decoded-chunk cache aren't fetched, so aren't
+//! recorded; nor are chunks whose fetch or decode fails.
+
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::array::UnifiedZarrArray;
+use crate::codecs::AnyCodec;
+use crate::describe::numcodecs_repr;
+use crate::group::UnifiedZarrGroup;
+
+// ---------------------------------------------------------------------------
+// Records
+// ---------------------------------------------------------------------------
+
+/// One fetched chunk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkRecord {
+    /// The chunk's key within the array, e.g. `0.1`.
+    pub key: String,
+    /// Time spent fetching the stored bytes, retries included.
+    pub fetch_time: Duration,
+    /// Time spent decoding them, or filling a missing chunk.
+    pub decode_time: Duration,
+    /// Stored size; `None` for a missing chunk.
+    pub stored_bytes: Option<usize>,
+    pub decoded_bytes: usize,
+    /// The compressor and filters as numcodecs prints them, e.g.
+    /// `Zstd(level=3)`, or `none`.
+    pub codec: String,
+}
+
+impl ChunkRecord {
+    pub fn total_time(&self) -> Duration {
+        self.fetch_time + self.decode_time
+    }
+
+    /// Decoded size over stored size; `None` for a missing chunk.
+    pub fn ratio(&self) -> Option<f64> {
+        self.stored_bytes
+            .map(|stored| self.decoded_bytes as f64 / stored.max(1) as f64)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Collector
+// ---------------------------------------------------------------------------
+
+/// Collects a [`ChunkRecord`] for each chunk fetched by the arrays it is
+/// attached to.  Shared between tasks, so one collector can cover a whole
+/// group load.
+#[derive(Debug, Default)]
+pub struct ChunkDiagnostics {
+    records: Mutex<Vec<ChunkRecord>>,
+}
+
+impl ChunkDiagnostics {
+    /// The records so far, in the order chunks finished decoding.
+    pub fn records(&self) -> Vec<ChunkRecord> {
+        self.lock().clone()
+    }
+
+    /// Remove and return the records so far.
+    pub fn take(&self) -> Vec<ChunkRecord> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// The `n` records with the longest fetch plus decode time, slowest
+    /// first.
+    pub fn slowest(&self, n: usize) -> Vec<ChunkRecord> {
+        let mut records = self.records();
+        records.sort_by_key(|r| std::cmp::Reverse(r.total_time()));
+        records.truncate(n);
+        records
+    }
+
+    /// The `n` records with the most stored bytes, largest first.
+    pub fn largest(&self, n: usize) -> Vec<ChunkRecord> {
+        let mut records = self.records();
+        records.sort_by_key(|r| std::cmp::Reverse(r.stored_bytes));
+        records.truncate(n);
+        records
+    }
+
+    pub(crate) fn record(&self, record: ChunkRecord) {
+        self.lock().push(record);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<ChunkRecord>> {
+        self.records.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A table of the records, slowest first.
+impl fmt::Display for ChunkDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let records = self.slowest(usize::MAX);
+        let (fetch, decode) = records
+            .iter()
+            .fold((Duration::ZERO, Duration::ZERO), |acc, r| {
+                (acc.0 + r.fetch_time, acc.1 + r.decode_time)
+            });
+        writeln!(
+            f,
+            "{} chunks, {:.1} ms fetching, {:.1} ms decoding",
+            records.len(),
+            ms(fetch),
+            ms(decode)
+        )?;
+        let width = records
+            .iter()
+            .map(|r| r.key.len())
+            .chain([5])
+            .max()
+            .unwrap_or(5);
+        writeln!(
+            f,
+            "{:<width$} {:>10} {:>10} {:>12} {:>12}  codec",
+            "chunk", "fetch ms", "decode ms", "stored", "decoded"
+        )?;
+        for r in &records {
+            let stored = r
+                .stored_bytes
+                .map_or("missing".to_string(), |b| b.to_string());
+            writeln!(
+                f,
+                "{:<width$} {:>10.2} {:>10.2} {:>12} {:>12}  {}",
+                r.key,
+                ms(r.fetch_time),
+                ms(r.decode_time),
+                stored,
+                r.decoded_bytes,
+                r.codec
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn ms(time: Duration) -> f64 {
+    time.as_secs_f64() * 1e3
+}
+
+/// `codecs` without the final `bytes` codec, as [`ChunkRecord::codec`]
+/// shows them.
+pub(crate) fn codec_label(codecs: &[AnyCodec]) -> String {
+    let labels: Vec<String> = codecs
+        .iter()
+        .filter(|c| !matches!(c, AnyCodec::Bytes(_)))
+        .map(numcodecs_repr)
+        .collect();
+    if labels.is_empty() {
+        return "none".into();
+    }
+    labels.join(" | ")
+}
+
+// ---------------------------------------------------------------------------
+// Arrays and groups
+// ---------------------------------------------------------------------------
+
+impl UnifiedZarrArray {
+    /// Record each chunk this array (and its clones) fetches into
+    /// `diagnostics`.
+    pub fn with_diagnostics(mut self, diagnostics: Arc<ChunkDiagnostics>) -> Self {
+        self.diagnostics = Some(diagnostics);
+        self
+    }
+
+    pub fn diagnostics(&self) -> Option<&Arc<ChunkDiagnostics>> {
+        self.diagnostics.as_ref()
+    }
+}
+
+impl UnifiedZarrGroup {
+    /// [`UnifiedZarrArray::with_diagnostics`] for every array in the group,
+    /// all recording into `diagnostics`.
+    pub fn with_diagnostics(mut self, diagnostics: Arc<ChunkDiagnostics>) -> Self {
+        for array in self.arrays.values_mut() {
+            array.diagnostics = Some(diagnostics.clone());
+        }
+        self
+    }
+}
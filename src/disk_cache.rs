@@ -0,0 +1,510 @@
+//! Persistent on-disk cache in front of any storage backend.
+//!
+//! [`DiskCachedStore`] keeps fetched values in a local directory so later
+//! sessions over the same remote data read from disk.  Values are stored
+//! content-addressed (`objects/<sha256>`, shared by identical values) and
+//! tracked in `index.json`, which records each key's object, size, ETag and
+//! fetch time.  The total size is capped by evicting least-recently-used
+//! keys, and entries older than the TTL are revalidated by ETag or
+//! refetched.
+//!
+//! A cache directory should be used by one process at a time.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{ZarrError, ZarrResult};
+use crate::runtime;
+use crate::store::{ListEntry, ObjectMeta, StorageBackend, temp_path};
+
+const INDEX_FILE: &str = "index.json";
+const OBJECTS_DIR: &str = "objects";
+/// Unsaved index changes, or time since the last save, after which adding
+/// or refreshing an entry writes the index.
+const SAVE_AFTER_CHANGES: usize = 256;
+const SAVE_AFTER: Duration = Duration::from_secs(5);
+
+// ---------------------------------------------------------------------------
+// DiskCacheOptions
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskCacheOptions {
+    /// Upper bound on the total size of cached objects in bytes.
+    pub max_bytes: u64,
+    /// How long a cached value is served without asking the backend
+    /// (`None` keeps values until they are evicted).
+    pub ttl: Option<Duration>,
+    /// Revalidate expired entries with an ETag check instead of refetching
+    /// them.  Has no effect for backends that don't report ETags.
+    pub validate_etag: bool,
+}
+
+impl Default for DiskCacheOptions {
+    fn default() -> Self {
+        Self {
+            max_bytes: 1 << 30,
+            ttl: None,
+            validate_etag: true,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Index
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    /// SHA-256 of the value, naming its object file.
+    hash: String,
+    size: u64,
+    etag: Option<String>,
+    /// When the value was fetched or last revalidated, in ms since the epoch.
+    fetched_ms: u64,
+    /// Access counter value at the last read; larger is more recent.
+    last_used: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Index {
+    entries: HashMap<String, Entry>,
+    clock: u64,
+    /// Number of keys using each object; rebuilt from `entries` on load.
+    #[serde(skip)]
+    refs: HashMap<String, usize>,
+    /// Total size of the distinct objects referenced by the index.
+    #[serde(skip)]
+    size: u64,
+    /// Changes since the index was last written.
+    #[serde(skip)]
+    unsaved: usize,
+    #[serde(skip)]
+    saved_at: Option<Instant>,
+}
+
+impl Index {
+    /// Recount `refs` and `size` from `entries`.
+    fn rebuild(&mut self) {
+        self.refs.clear();
+        self.size = 0;
+        for entry in self.entries.values() {
+            let refs = self.refs.entry(entry.hash.clone()).or_insert(0);
+            if *refs == 0 {
+                self.size += entry.size;
+            }
+            *refs += 1;
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.unsaved += 1;
+        self.clock
+    }
+
+    fn references(&self, hash: &str) -> bool {
+        self.refs.contains_key(hash)
+    }
+
+    /// Whether enough has changed since the index was written to write it.
+    fn save_due(&self) -> bool {
+        self.unsaved >= SAVE_AFTER_CHANGES
+            || (self.unsaved > 0 && self.saved_at.is_none_or(|t| t.elapsed() >= SAVE_AFTER))
+    }
+
+    /// Add `entry` under `key`, which must not have one.
+    fn insert(&mut self, key: String, entry: Entry) {
+        let refs = self.refs.entry(entry.hash.clone()).or_insert(0);
+        if *refs == 0 {
+            self.size += entry.size;
+        }
+        *refs += 1;
+        self.entries.insert(key, entry);
+        self.unsaved += 1;
+    }
+
+    /// Remove `key`, returning its object hash and size if nothing else
+    /// uses the object.
+    fn remove(&mut self, key: &str) -> Option<(String, u64)> {
+        let entry = self.entries.remove(key)?;
+        self.unsaved += 1;
+        let refs = self.refs.get_mut(&entry.hash)?;
+        *refs -= 1;
+        if *refs > 0 {
+            return None;
+        }
+        self.refs.remove(&entry.hash);
+        self.size -= entry.size;
+        Some((entry.hash, entry.size))
+    }
+
+    /// Remove every entry, returning the object hashes.
+    fn clear(&mut self) -> Vec<String> {
+        self.entries.clear();
+        self.size = 0;
+        self.unsaved += 1;
+        self.refs.drain().map(|(h, _)| h).collect()
+    }
+
+    /// Evict least-recently-used keys until the objects fit in `max_bytes`,
+    /// returning the object hashes that are no longer referenced.
+    fn evict_to(&mut self, max_bytes: u64) -> Vec<String> {
+        if self.size <= max_bytes {
+            return Vec::new();
+        }
+        let mut by_age: Vec<(u64, String)> = self
+            .entries
+            .iter()
+            .map(|(k, e)| (e.last_used, k.clone()))
+            .collect();
+        by_age.sort_unstable();
+        let mut freed = Vec::new();
+        for (_, key) in by_age {
+            if self.size <= max_bytes {
+                break;
+            }
+            freed.extend(self.remove(&key).map(|(h, _)| h));
+        }
+        freed
+    }
+}
+
+// ---------------------------------------------------------------------------
+// DiskCachedStore
+// ---------------------------------------------------------------------------
+
+/// Backend wrapper caching values from `inner` in a local directory.
+///
+/// Reads of fresh entries never reach `inner`.  Missing keys are not
+/// cached, and [`put`](StorageBackend::put) writes through to `inner` and
+/// drops the cached entry.  The index is saved whenever entries are
+/// dropped, every few seconds or few hundred changes while entries are
+/// added or refreshed, on [`flush`](Self::flush), and on drop; entries
+/// added since the last save are refetched after a crash.
+#[derive(Debug)]
+pub struct DiskCachedStore<B> {
+    inner: B,
+    dir: PathBuf,
+    options: DiskCacheOptions,
+    index: Arc<Mutex<Index>>,
+}
+
+impl<B: StorageBackend> DiskCachedStore<B> {
+    /// Open (or create) the cache in `dir`.  Object files not referenced by
+    /// the index, e.g. left over from a crash, are removed; an unreadable
+    /// index starts the cache afresh.
+    pub fn open(inner: B, dir: impl Into<PathBuf>, options: DiskCacheOptions) -> ZarrResult<Self> {
+        let dir = dir.into();
+        let objects = dir.join(OBJECTS_DIR);
+        std::fs::create_dir_all(&objects).map_err(|e| io_error(&objects, e))?;
+        let mut index: Index = std::fs::read(dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        index.entries.retain(|_, e| objects.join(&e.hash).is_file());
+        index.rebuild();
+        let entries = std::fs::read_dir(&objects).map_err(|e| io_error(&objects, e))?;
+        for file in entries.flatten() {
+            let name = file.file_name();
+            let referenced = name.to_str().is_some_and(|h| index.references(h));
+            if !referenced {
+                let _ = std::fs::remove_file(file.path());
+            }
+        }
+        let freed = index.evict_to(options.max_bytes);
+        remove_objects(&dir, &freed);
+        let store = Self {
+            inner,
+            dir,
+            options,
+            index: Arc::new(Mutex::new(index)),
+        };
+        store.save_index()?;
+        Ok(store)
+    }
+
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    pub fn options(&self) -> &DiskCacheOptions {
+        &self.options
+    }
+
+    /// Total size of the cached objects in bytes.
+    pub fn size_bytes(&self) -> u64 {
+        self.lock().size
+    }
+
+    /// Whether `path` currently has a cached entry, fresh or not.
+    pub fn contains(&self, path: &str) -> bool {
+        self.lock().entries.contains_key(path)
+    }
+
+    /// Write the index to disk if it has changed.
+    pub async fn flush(&self) -> ZarrResult<()> {
+        let index = self.index.clone();
+        let path = self.dir.join(INDEX_FILE);
+        runtime::spawn_blocking(move || write_index(&index, &path)).await?
+    }
+
+    /// Remove every cached entry and object.
+    pub async fn clear(&self) -> ZarrResult<()> {
+        let hashes = self.lock().clear();
+        let dir = self.dir.clone();
+        runtime::spawn_blocking(move || remove_objects(&dir, &hashes)).await?;
+        self.flush().await
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Index> {
+        self.index.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Write the index if [`Index::save_due`].
+    async fn flush_if_due(&self) -> ZarrResult<()> {
+        if self.lock().save_due() {
+            self.flush().await
+        } else {
+            Ok(())
+        }
+    }
+
+    fn save_index(&self) -> ZarrResult<()> {
+        write_index(&self.index, &self.dir.join(INDEX_FILE))
+    }
+
+    fn is_fresh(&self, entry: &Entry) -> bool {
+        self.options
+            .ttl
+            .is_none_or(|ttl| now_ms().saturating_sub(entry.fetched_ms) < ttl.as_millis() as u64)
+    }
+
+    /// Read the object behind `entry`, or `None` if its file has gone.
+    async fn read_object(&self, entry: &Entry) -> ZarrResult<Option<Bytes>> {
+        let path = self.dir.join(OBJECTS_DIR).join(&entry.hash);
+        let size = entry.size;
+        runtime::spawn_blocking(move || match std::fs::read(&path) {
+            Ok(data) if data.len() as u64 == size => Ok(Some(Bytes::from(data))),
+            Ok(_) => Ok(None),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(&path, e)),
+        })
+        .await?
+    }
+
+    /// Serve `path` from `entry`, marking it used.
+    async fn hit(&self, path: &str, entry: &Entry) -> ZarrResult<Option<Bytes>> {
+        let data = self.read_object(entry).await?;
+        let mut index = self.lock();
+        if data.is_some() {
+            let used = index.tick();
+            if let Some(e) = index.entries.get_mut(path) {
+                e.last_used = used;
+            }
+        } else {
+            index.remove(path);
+        }
+        Ok(data)
+    }
+
+    /// The cached value for `path`, if its entry is fresh or an expired
+    /// entry still matches the backend's ETag.
+    async fn lookup(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        let Some(entry) = self.lock().entries.get(path).cloned() else {
+            return Ok(None);
+        };
+        if self.is_fresh(&entry) {
+            return self.hit(path, &entry).await;
+        }
+        let unchanged = self.options.validate_etag
+            && entry.etag.is_some()
+            && self.inner.etag(path).await? == entry.etag;
+        if !unchanged {
+            return Ok(None);
+        }
+        let data = self.hit(path, &entry).await?;
+        if data.is_some() {
+            self.refresh(path);
+            self.flush_if_due().await?;
+        }
+        Ok(data)
+    }
+
+    fn refresh(&self, path: &str) {
+        let mut index = self.lock();
+        if let Some(entry) = index.entries.get_mut(path) {
+            entry.fetched_ms = now_ms();
+            index.unsaved += 1;
+        }
+    }
+
+    /// Cache `data` under `path`, evicting to stay within the size cap.
+    async fn insert(&self, path: &str, data: Bytes, etag: Option<String>) -> ZarrResult<()> {
+        let size = data.len() as u64;
+        if size > self.options.max_bytes {
+            return Ok(());
+        }
+        let hash = hex(&Sha256::digest(&data));
+        let object = self.dir.join(OBJECTS_DIR).join(&hash);
+        runtime::spawn_blocking(move || {
+            // Objects are published whole by rename, so one of the right
+            // size already holds this value.
+            if std::fs::metadata(&object).is_ok_and(|m| m.is_file() && m.len() == size) {
+                return Ok(());
+            }
+            let tmp = temp_path(&object);
+            std::fs::write(&tmp, &data).map_err(|e| io_error(&object, e))?;
+            std::fs::rename(&tmp, &object).map_err(|e| {
+                let _ = std::fs::remove_file(&tmp);
+                io_error(&object, e)
+            })
+        })
+        .await??;
+
+        let freed = {
+            let mut index = self.lock();
+            let last_used = index.tick();
+            let mut freed: Vec<String> = index.remove(path).into_iter().map(|(h, _)| h).collect();
+            index.insert(
+                path.to_string(),
+                Entry {
+                    hash,
+                    size,
+                    etag,
+                    fetched_ms: now_ms(),
+                    last_used,
+                },
+            );
+            freed.extend(index.evict_to(self.options.max_bytes));
+            freed.retain(|h| !index.references(h));
+            freed
+        };
+        let dir = self.dir.clone();
+        runtime::spawn_blocking(move || remove_objects(&dir, &freed)).await?;
+        self.flush_if_due().await
+    }
+
+    /// Drop the entry for `path`, deleting its object if unused.  The index
+    /// is saved at once so a stale entry can't outlive a crash.
+    async fn invalidate(&self, path: &str) -> ZarrResult<()> {
+        let freed: Vec<String> = {
+            let mut index = self.lock();
+            if !index.entries.contains_key(path) {
+                return Ok(());
+            }
+            index.remove(path).into_iter().map(|(h, _)| h).collect()
+        };
+        let dir = self.dir.clone();
+        runtime::spawn_blocking(move || remove_objects(&dir, &freed)).await?;
+        self.flush().await
+    }
+}
+
+impl<B> Drop for DiskCachedStore<B> {
+    fn drop(&mut self) {
+        let _ = write_index(&self.index, &self.dir.join(INDEX_FILE));
+    }
+}
+
+#[async_trait]
+impl<B: StorageBackend> StorageBackend for DiskCachedStore<B> {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        if let Some(data) = self.lookup(path).await? {
+            return Ok(Some(data));
+        }
+        match self.inner.get_with_etag(path).await? {
+            Some((data, etag)) => {
+                self.insert(path, data.clone(), etag).await?;
+                Ok(Some(data))
+            }
+            None => {
+                self.invalidate(path).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> ZarrResult<Vec<String>> {
+        self.inner.list(prefix).await
+    }
+
+    fn list_stream<'a>(&'a self, prefix: &'a str) -> BoxStream<'a, ZarrResult<ListEntry>> {
+        self.inner.list_stream(prefix)
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        self.inner.join(base, segment)
+    }
+
+    async fn get_with_etag(&self, path: &str) -> ZarrResult<Option<(Bytes, Option<String>)>> {
+        let data = self.get(path).await?;
+        let etag = self.lock().entries.get(path).and_then(|e| e.etag.clone());
+        Ok(data.map(|d| (d, etag)))
+    }
+
+    async fn etag(&self, path: &str) -> ZarrResult<Option<String>> {
+        self.inner.etag(path).await
+    }
+
+    async fn head(&self, path: &str) -> ZarrResult<Option<ObjectMeta>> {
+        self.inner.head(path).await
+    }
+
+    async fn put(&self, path: &str, data: Bytes) -> ZarrResult<()> {
+        self.inner.put(path, data).await?;
+        self.invalidate(path).await
+    }
+
+    async fn delete(&self, path: &str) -> ZarrResult<()> {
+        self.inner.delete(path).await?;
+        self.invalidate(path).await
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn write_index(index: &Mutex<Index>, path: &Path) -> ZarrResult<()> {
+    let mut index = index.lock().unwrap_or_else(|e| e.into_inner());
+    if index.unsaved == 0 && path.is_file() {
+        return Ok(());
+    }
+    let bytes = serde_json::to_vec(&*index)?;
+    let mut tmp = path.to_path_buf().into_os_string();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, bytes).map_err(|e| io_error(path, e))?;
+    std::fs::rename(&tmp, path).map_err(|e| io_error(path, e))?;
+    index.unsaved = 0;
+    index.saved_at = Some(Instant::now());
+    Ok(())
+}
+
+fn remove_objects(dir: &Path, hashes: &[String]) {
+    for hash in hashes {
+        let _ = std::fs::remove_file(dir.join(OBJECTS_DIR).join(hash));
+    }
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> ZarrError {
+    ZarrError::Storage(format!("Disk cache error for {}: {e}", path.display()))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::capabilities::FeatureId;
+
 pub type ZarrResult<T> = Result<T, ZarrError>;
 
 #[derive(Error, Debug)]
@@ -28,9 +30,27 @@ pub enum ZarrError {
     #[error("Codec error: {0}")]
     Codec(String),
 
+    #[error("Chunk {chunk}: decoded {actual} bytes, expected {expected}")]
+    ChunkSizeMismatch {
+        chunk: String,
+        expected: usize,
+        actual: usize,
+    },
+
+    /// Every error of a read that hit more than one, in completion order;
+    /// only when [`fail_fast`](crate::config::SimplezarrConfig::fail_fast)
+    /// is off.
+    #[error("{} chunks failed, first: {}", .0.len(), .0[0])]
+    ChunkErrors(Vec<ZarrError>),
+
     #[error("Not found: {0}")]
     NotFound(String),
 
+    /// The data needs a codec, data type or format this build can't read;
+    /// see [`capabilities`](crate::capabilities::capabilities).
+    #[error("Unsupported {0}")]
+    UnsupportedFeature(FeatureId),
+
     #[error("{0}")]
     Other(String),
 }
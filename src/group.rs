@@ -1,6 +1,15 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::ops::{Range, RangeInclusive};
 
-use crate::array::UnifiedZarrArray;
+use futures::StreamExt;
+
+use crate::aligned::DerivedVariable;
+use crate::array::{ArrayData, UnifiedZarrArray, collect_chunks};
+use crate::attributes::Attributes;
+use crate::error::{ZarrError, ZarrResult};
+use crate::options::GroupReadOptions;
+use crate::types::ZarrVectorValue;
 
 // ---------------------------------------------------------------------------
 // UnifiedGroupMetadata
@@ -9,19 +18,77 @@ use crate::array::UnifiedZarrArray;
 #[derive(Debug, Clone)]
 pub struct UnifiedGroupMetadata {
     pub zarr_format: u32,
-    pub attributes: Option<serde_json::Map<String, serde_json::Value>>,
+    pub attributes: Option<Attributes>,
     pub consolidated: bool,
+    /// Sorted by name for consolidated groups, in the order requested
+    /// otherwise; see [`UnifiedZarrGroup::sort_array_names`].
     pub array_names: Vec<String>,
     pub path: String,
 }
 
+// ---------------------------------------------------------------------------
+// Array name ordering
+// ---------------------------------------------------------------------------
+
+/// How [`UnifiedZarrGroup::sort_array_names`] orders names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArrayNameOrder {
+    /// Byte-wise, as the group's `arrays` map iterates.
+    #[default]
+    Sorted,
+    /// Runs of digits compare as numbers, so `t2` sorts before `t10`.
+    Natural,
+}
+
+/// Compare `a` and `b` with digit runs taken as numbers, falling back to a
+/// byte-wise comparison so names like `t01` and `t1` still have a fixed
+/// order.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut x, mut y) = (a.as_bytes(), b.as_bytes());
+    while let (Some(&cx), Some(&cy)) = (x.first(), y.first()) {
+        let ordering = if cx.is_ascii_digit() && cy.is_ascii_digit() {
+            let (dx, rest_x) = split_digits(x);
+            let (dy, rest_y) = split_digits(y);
+            (x, y) = (rest_x, rest_y);
+            let (dx, dy) = (trim_zeros(dx), trim_zeros(dy));
+            dx.len().cmp(&dy.len()).then_with(|| dx.cmp(dy))
+        } else {
+            (x, y) = (&x[1..], &y[1..]);
+            cx.cmp(&cy)
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    x.len().cmp(&y.len()).then_with(|| a.cmp(b))
+}
+
+fn split_digits(s: &[u8]) -> (&[u8], &[u8]) {
+    s.split_at(
+        s.iter()
+            .position(|c| !c.is_ascii_digit())
+            .unwrap_or(s.len()),
+    )
+}
+
+fn trim_zeros(digits: &[u8]) -> &[u8] {
+    let start = digits
+        .iter()
+        .position(|&c| c != b'0')
+        .unwrap_or(digits.len());
+    &digits[start..]
+}
+
 // ---------------------------------------------------------------------------
 // UnifiedZarrGroup
 // ---------------------------------------------------------------------------
 
 pub struct UnifiedZarrGroup {
     pub metadata: UnifiedGroupMetadata,
-    pub arrays: HashMap<String, UnifiedZarrArray>,
+    pub arrays: BTreeMap<String, UnifiedZarrArray>,
+    /// Variables computed from `arrays`; see
+    /// [`with_derived`](Self::with_derived).
+    pub derived: BTreeMap<String, DerivedVariable>,
 }
 
 impl std::fmt::Debug for UnifiedZarrGroup {
@@ -29,6 +96,7 @@ impl std::fmt::Debug for UnifiedZarrGroup {
         f.debug_struct("UnifiedZarrGroup")
             .field("metadata", &self.metadata)
             .field("arrays", &self.arrays.keys().collect::<Vec<_>>())
+            .field("derived", &self.derived.keys().collect::<Vec<_>>())
             .finish()
     }
 }
@@ -40,7 +108,7 @@ impl UnifiedZarrGroup {
         self.metadata.zarr_format
     }
 
-    pub fn attributes(&self) -> Option<&serde_json::Map<String, serde_json::Value>> {
+    pub fn attributes(&self) -> Option<&Attributes> {
         self.metadata.attributes.as_ref()
     }
 
@@ -52,6 +120,14 @@ impl UnifiedZarrGroup {
         &self.metadata.array_names
     }
 
+    /// Reorder [`array_names`](Self::array_names) by `order`.
+    pub fn sort_array_names(&mut self, order: ArrayNameOrder) {
+        match order {
+            ArrayNameOrder::Sorted => self.metadata.array_names.sort(),
+            ArrayNameOrder::Natural => self.metadata.array_names.sort_by(|a, b| natural_cmp(a, b)),
+        }
+    }
+
     pub fn path(&self) -> &str {
         &self.metadata.path
     }
@@ -59,4 +135,157 @@ impl UnifiedZarrGroup {
     pub fn get_array(&self, name: &str) -> Option<&UnifiedZarrArray> {
         self.arrays.get(name)
     }
+
+    /// Whether reads stop at their first error, as the first array's
+    /// [`fail_fast`](crate::config::SimplezarrConfig::fail_fast) says.
+    fn fail_fast(&self) -> bool {
+        self.arrays
+            .values()
+            .next()
+            .is_none_or(|array| array.config().fail_fast)
+    }
+
+    /// Read every array's `.zattrs`; see
+    /// [`UnifiedZarrArray::load_attributes`].  Consolidated groups have them
+    /// already.
+    pub async fn load_attributes(&mut self) -> ZarrResult<()> {
+        let concurrency = self
+            .arrays
+            .values()
+            .next()
+            .map_or(1, |array| array.config().max_concurrency.max(1));
+        let fail_fast = self.fail_fast();
+        let reads = futures::stream::iter(self.arrays.values_mut())
+            .map(|array| array.load_attributes())
+            .buffer_unordered(concurrency);
+        collect_chunks(reads, fail_fast).await?;
+        Ok(())
+    }
+
+    /// Load every array, each with its own read options, keyed by name.
+    ///
+    /// Arrays are loaded one at a time; each fetches up to
+    /// `max_concurrency` chunks at once.  Without `fail_fast`, the other
+    /// arrays are still loaded when one fails, and every error is returned.
+    pub async fn load_all(&self) -> ZarrResult<BTreeMap<String, ArrayData<ZarrVectorValue>>> {
+        let loads = futures::stream::iter(&self.arrays)
+            .then(|(name, array)| async move { Ok((name.clone(), array.load_value().await?)) });
+        let loaded = collect_chunks(loads, self.fail_fast()).await?;
+        Ok(loaded.into_iter().collect())
+    }
+
+    /// [`load_all`](Self::load_all) with the read options in `options`
+    /// instead of each array's own.
+    ///
+    /// Arrays whose options change are read through a copy with an empty
+    /// chunk cache.  Overrides naming an array the group doesn't have are
+    /// an error, to catch typos.
+    pub async fn load_all_with_options(
+        &self,
+        options: &GroupReadOptions,
+    ) -> ZarrResult<BTreeMap<String, ArrayData<ZarrVectorValue>>> {
+        if let Some(name) = options
+            .overrides
+            .keys()
+            .find(|name| !self.arrays.contains_key(*name))
+        {
+            return Err(ZarrError::NotFound(format!(
+                "Read options given for unknown array {name}"
+            )));
+        }
+        let loads = futures::stream::iter(&self.arrays).then(|(name, array)| async move {
+            let wanted = options.for_array(name);
+            let value = if array.read_options() == wanted {
+                array.load_value().await?
+            } else {
+                let array = array.clone().with_read_options(wanted.clone());
+                array.load_value().await?
+            };
+            Ok((name.clone(), value))
+        });
+        let loaded = collect_chunks(loads, self.fail_fast()).await?;
+        Ok(loaded.into_iter().collect())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Coordinate selection
+// ---------------------------------------------------------------------------
+
+impl UnifiedZarrGroup {
+    /// The coordinate array of dimension `dim`: the 1-D array of the same
+    /// name whose only dimension is `dim`.  Dimension names come from
+    /// `.zattrs`, so non-consolidated groups need
+    /// [`load_attributes`](Self::load_attributes) first.
+    pub fn coordinate(&self, dim: &str) -> Option<&UnifiedZarrArray> {
+        self.arrays.get(dim).filter(|array| {
+            matches!(array.metadata.dimension_names.as_deref(), Some([Some(name)]) if name == dim)
+        })
+    }
+
+    /// The index range along `dim` whose coordinates lie within `values`,
+    /// like xarray's `sel(dim=slice(lo, hi))`.
+    ///
+    /// The coordinate array is loaded and binary-searched, so it must be
+    /// sorted, ascending or descending.
+    pub async fn select(&self, dim: &str, values: RangeInclusive<f64>) -> ZarrResult<Range<usize>> {
+        let coordinate = self.coordinate(dim).ok_or_else(|| {
+            ZarrError::NotFound(format!("No coordinate array for dimension {dim}"))
+        })?;
+        let coords = coordinate.load().await?;
+        index_range(&coords, &values)
+            .ok_or_else(|| ZarrError::Metadata(format!("Coordinate {dim} is not sorted")))
+    }
+
+    /// Read the part of `array` whose coordinates lie within `selection`,
+    /// one value range per dimension name; other dimensions are read whole.
+    /// Returns the index region alongside the values.
+    pub async fn read_selection(
+        &self,
+        array: &str,
+        selection: &[(&str, RangeInclusive<f64>)],
+    ) -> ZarrResult<(Vec<Range<usize>>, Vec<f64>)> {
+        let target = self
+            .get_array(array)
+            .ok_or_else(|| ZarrError::NotFound(format!("No array {array} in group")))?;
+        let dims =
+            target.metadata.dimension_names.as_deref().ok_or_else(|| {
+                ZarrError::Metadata(format!("Array {array} has no dimension names"))
+            })?;
+        let mut region = target.full_region();
+        for (dim, values) in selection {
+            let axis = dims
+                .iter()
+                .position(|name| name.as_deref() == Some(*dim))
+                .ok_or_else(|| {
+                    ZarrError::NotFound(format!("Array {array} has no dimension {dim}"))
+                })?;
+            region[axis] = self.select(dim, values.clone()).await?;
+        }
+        if region.iter().any(|r| r.is_empty()) {
+            return Ok((region, Vec::new()));
+        }
+        let values = target.read_region(&region).await?;
+        Ok((region, values.into_inner()))
+    }
+}
+
+/// The indices of sorted `coords` that lie within `values`; `None` if
+/// `coords` isn't sorted.
+fn index_range(coords: &[f64], values: &RangeInclusive<f64>) -> Option<Range<usize>> {
+    let (lo, hi) = (*values.start(), *values.end());
+    let (start, end) = if coords.windows(2).all(|w| w[0] <= w[1]) {
+        (
+            coords.partition_point(|&c| c < lo),
+            coords.partition_point(|&c| c <= hi),
+        )
+    } else if coords.windows(2).all(|w| w[0] >= w[1]) {
+        (
+            coords.partition_point(|&c| c > hi),
+            coords.partition_point(|&c| c >= lo),
+        )
+    } else {
+        return None;
+    };
+    Some(start..end.max(start))
 }
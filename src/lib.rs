@@ -1,17 +1,86 @@
+pub mod aligned;
 pub mod array;
+pub mod attributes;
+pub mod benchmark;
+pub mod builder;
+pub mod cache;
+pub mod capabilities;
+#[cfg(feature = "cloud")]
+pub mod catalog;
+#[cfg(feature = "chrono")]
+pub mod cftime;
 pub mod codecs;
+pub mod compare;
+pub mod compute;
+pub mod config;
+pub mod convert;
+#[cfg(feature = "polars")]
+pub mod dataframe;
+pub mod describe;
+pub mod diagnostics;
+#[cfg(feature = "fs")]
+pub mod disk_cache;
 pub mod error;
 pub mod group;
+pub mod manifest;
+pub mod map;
 pub mod metadata;
+pub mod options;
+pub mod plan;
+pub mod pyramid;
+pub mod reduce;
+pub mod runtime;
+pub mod sampler;
+#[cfg(feature = "datafusion")]
+pub mod sql;
+#[cfg(feature = "cloud")]
+pub mod stac;
+pub mod stats;
 pub mod store;
+#[cfg(feature = "candle")]
+pub mod tensor;
+pub mod transformers;
 pub mod types;
 pub mod v2;
+pub mod validate;
+pub mod versioned;
+pub mod view;
+pub mod write;
+pub mod xarray;
 
 // Re-export key types at crate root for convenience.
-pub use array::{UnifiedMetadata, UnifiedZarrArray};
+pub use array::{ArrayData, UnifiedMetadata, UnifiedZarrArray};
+pub use attributes::Attributes;
+pub use builder::{ArrayBuilder, ChunkShape};
+pub use capabilities::{Capabilities, FeatureId, capabilities};
+pub use compare::{Comparison, Tolerance};
+pub use compute::ComputedArray;
+pub use config::SimplezarrConfig;
+pub use diagnostics::ChunkDiagnostics;
 pub use error::{ZarrError, ZarrResult};
-pub use group::{UnifiedGroupMetadata, UnifiedZarrGroup};
-pub use store::{LocalBackend, ObjectStoreBackend, StorageBackend};
+pub use group::{ArrayNameOrder, UnifiedGroupMetadata, UnifiedZarrGroup};
+pub use manifest::{ChunkManifest, ChunkRef, ManifestStore};
+pub use options::{ArrayReadOptions, ArrayWriteOptions, GroupReadOptions};
+pub use plan::ReadPlan;
+pub use sampler::{BatchLoader, ChunkSampler};
+pub use store::{ObjectMeta, ObjectVersion, StorageBackend, TieredStore};
+pub use versioned::{VersionManifest, VersionedStore};
+pub use view::ArrayView;
+#[cfg(feature = "fs")]
+pub use disk_cache::{DiskCacheOptions, DiskCachedStore};
+#[cfg(feature = "fs")]
+pub use store::LocalBackend;
+#[cfg(feature = "object_store")]
+pub use store::ObjectStoreBackend;
+#[cfg(feature = "cloud")]
+pub use catalog::{Catalog, CatalogEntry};
+#[cfg(feature = "cloud")]
+pub use stac::ZarrAsset;
+#[cfg(feature = "cloud")]
+pub use store::{PresignedUrlBackend, UrlOptions};
+#[cfg(feature = "datafusion")]
+pub use sql::ZarrTableProvider;
 pub use types::{
     ArrayOrder, DataType, Endian, FillValue, ZarrValue, ZarrVectorValue,
 };
+pub use write::WriteOutcome;
@@ -0,0 +1,277 @@
+//! Reading arrays through a manifest of chunk references.
+//!
+//! Versioned storage engines don't keep chunks at their Zarr keys: a
+//! committed manifest maps each key to an object, or to a byte range
+//! within one, and small values such as metadata are stored inline.
+//! [`ManifestStore`] serves Zarr keys from such a [`ChunkManifest`], reading
+//! the referenced bytes from an underlying store.
+//!
+//! Manifests are read from JSON in the kerchunk reference layout:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "refs": {
+//!     "temperature/.zarray": "{\"zarr_format\": 2, ...}",
+//!     "temperature/0.0": ["blobs/2024-06-01", 0, 4096],
+//!     "temperature/0.1": ["blobs/2024-06-01", 4096, 3920],
+//!     "temperature/1.0": ["chunks/1.0"],
+//!     "temperature/1.1": "base64:KLUv/QBYpQAA..."
+//!   }
+//! }
+//! ```
+//!
+//! A string is the value itself (`base64:`-prefixed for binary data), a
+//! one-element list a whole object, and `[path, offset, length]` a byte
+//! range.  Paths are keys of the underlying store; URL schemes are not
+//! interpreted.  A manifest is a snapshot: open the store again to see a
+//! later commit.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use bytes::Bytes;
+
+use crate::error::{ZarrError, ZarrResult};
+use crate::store::{ObjectMeta, StorageBackend, check_range};
+
+// ---------------------------------------------------------------------------
+// ChunkManifest
+// ---------------------------------------------------------------------------
+
+/// Where the value of one key is stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkRef {
+    /// The value itself, kept in the manifest.
+    Inline(Bytes),
+    /// A whole object of the underlying store.
+    Object(String),
+    /// `length` bytes of an object, starting at `offset`.
+    Range {
+        path: String,
+        offset: u64,
+        length: u64,
+    },
+}
+
+impl ChunkRef {
+    fn parse(key: &str, value: &serde_json::Value) -> ZarrResult<Self> {
+        let invalid = || ZarrError::Metadata(format!("Invalid reference for {key}: {value}"));
+        if let Some(text) = value.as_str() {
+            return match text.strip_prefix("base64:") {
+                Some(encoded) => BASE64
+                    .decode(encoded)
+                    .map(|data| ChunkRef::Inline(data.into()))
+                    .map_err(|_| invalid()),
+                None => Ok(ChunkRef::Inline(Bytes::copy_from_slice(text.as_bytes()))),
+            };
+        }
+        match value.as_array().map(Vec::as_slice) {
+            Some([path]) => Ok(ChunkRef::Object(path.as_str().ok_or_else(invalid)?.into())),
+            Some([path, offset, length]) => {
+                let offset = offset.as_u64().ok_or_else(invalid)?;
+                let length = length.as_u64().ok_or_else(invalid)?;
+                // Reads fetch `offset..offset + length`, which must fit.
+                offset.checked_add(length).ok_or_else(invalid)?;
+                Ok(ChunkRef::Range {
+                    path: path.as_str().ok_or_else(invalid)?.into(),
+                    offset,
+                    length,
+                })
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            ChunkRef::Inline(data) => match std::str::from_utf8(data) {
+                Ok(text) if !text.starts_with("base64:") => text.into(),
+                _ => format!("base64:{}", BASE64.encode(data)).into(),
+            },
+            ChunkRef::Object(path) => serde_json::json!([path]),
+            ChunkRef::Range {
+                path,
+                offset,
+                length,
+            } => serde_json::json!([path, offset, length]),
+        }
+    }
+}
+
+/// A committed mapping of Zarr keys to [`ChunkRef`]s.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChunkManifest {
+    pub refs: BTreeMap<String, ChunkRef>,
+}
+
+impl ChunkManifest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a reference manifest: `{"version": 1, "refs": {...}}`, or a
+    /// bare `{key: reference}` object.
+    pub fn parse(json_bytes: &[u8]) -> ZarrResult<Self> {
+        let raw: serde_json::Value = serde_json::from_slice(json_bytes)
+            .map_err(|e| ZarrError::Metadata(format!("Invalid manifest JSON: {e}")))?;
+        let refs = match raw.get("version") {
+            Some(version) if version.as_u64() == Some(1) => raw.get("refs"),
+            Some(version) => {
+                return Err(ZarrError::Metadata(format!(
+                    "Unsupported manifest version {version}"
+                )));
+            }
+            None => Some(&raw),
+        };
+        let refs = refs
+            .and_then(|refs| refs.as_object())
+            .ok_or_else(|| ZarrError::Metadata("Manifest 'refs' must be an object".into()))?;
+        let refs = refs
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), ChunkRef::parse(key, value)?)))
+            .collect::<ZarrResult<_>>()?;
+        Ok(ChunkManifest { refs })
+    }
+
+    /// The manifest as version-1 reference JSON.
+    pub fn to_json(&self) -> serde_json::Value {
+        let refs: serde_json::Map<_, _> = self
+            .refs
+            .iter()
+            .map(|(key, r)| (key.clone(), r.to_json()))
+            .collect();
+        serde_json::json!({"version": 1, "refs": refs})
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ManifestStore
+// ---------------------------------------------------------------------------
+
+/// Serves the keys of a [`ChunkManifest`], reading referenced bytes from
+/// `objects`.
+///
+/// Keys absent from the manifest read as missing.  A reference to a
+/// missing object fails with [`ZarrError::NotFound`].  The store is
+/// read-only.
+pub struct ManifestStore<S> {
+    objects: S,
+    manifest: ChunkManifest,
+}
+
+impl<S: StorageBackend> ManifestStore<S> {
+    pub fn new(objects: S, manifest: ChunkManifest) -> Self {
+        Self { objects, manifest }
+    }
+
+    /// Read the manifest at `manifest_path` of `objects`.
+    pub async fn open(objects: S, manifest_path: &str) -> ZarrResult<Self> {
+        let bytes = objects
+            .get(manifest_path)
+            .await?
+            .ok_or_else(|| ZarrError::NotFound(format!("No manifest at {manifest_path}")))?;
+        let manifest = ChunkManifest::parse(&bytes)?;
+        Ok(Self::new(objects, manifest))
+    }
+
+    pub fn manifest(&self) -> &ChunkManifest {
+        &self.manifest
+    }
+
+    pub fn objects(&self) -> &S {
+        &self.objects
+    }
+
+    /// Read `range` of the object at `path`, which `key` refers to.
+    async fn read(&self, key: &str, path: &str, range: Option<Range<u64>>) -> ZarrResult<Bytes> {
+        let data = match range {
+            Some(range) => self.objects.get_range(path, range).await?,
+            None => self.objects.get(path).await?,
+        };
+        data.ok_or_else(|| ZarrError::NotFound(format!("{key} refers to missing object {path}")))
+    }
+}
+
+#[async_trait]
+impl<S: StorageBackend> StorageBackend for ManifestStore<S> {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        let data = match self.manifest.refs.get(path) {
+            None => return Ok(None),
+            Some(ChunkRef::Inline(data)) => data.clone(),
+            Some(ChunkRef::Object(object)) => self.read(path, object, None).await?,
+            Some(ChunkRef::Range {
+                path: object,
+                offset,
+                length,
+            }) => {
+                self.read(path, object, Some(*offset..offset + length))
+                    .await?
+            }
+        };
+        Ok((!data.is_empty()).then_some(data))
+    }
+
+    /// Keys of the manifest under `prefix`.
+    async fn list(&self, prefix: &str) -> ZarrResult<Vec<String>> {
+        let prefix = prefix.trim_end_matches('/');
+        Ok(self
+            .manifest
+            .refs
+            .keys()
+            .filter(|key| {
+                prefix.is_empty()
+                    || key
+                        .strip_prefix(prefix)
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .cloned()
+            .collect())
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        if base.is_empty() {
+            segment.to_string()
+        } else {
+            format!("{base}/{segment}")
+        }
+    }
+
+    /// Inline values and byte ranges are measured from the manifest alone.
+    async fn head(&self, path: &str) -> ZarrResult<Option<ObjectMeta>> {
+        let size = match self.manifest.refs.get(path) {
+            None => return Ok(None),
+            Some(ChunkRef::Inline(data)) => data.len() as u64,
+            Some(ChunkRef::Object(object)) => return self.objects.head(object).await,
+            Some(ChunkRef::Range { length, .. }) => *length,
+        };
+        Ok((size > 0).then_some(ObjectMeta {
+            size,
+            etag: None,
+            modified: None,
+            version: None,
+        }))
+    }
+
+    /// Ranges of a byte-range reference are read straight from its object.
+    async fn get_range(&self, path: &str, range: Range<u64>) -> ZarrResult<Option<Bytes>> {
+        let Some(ChunkRef::Range {
+            path: object,
+            offset,
+            length,
+        }) = self.manifest.refs.get(path)
+        else {
+            let Some(data) = self.get(path).await? else {
+                return Ok(None);
+            };
+            check_range(path, &range, data.len() as u64)?;
+            return Ok(Some(data.slice(range.start as usize..range.end as usize)));
+        };
+        check_range(path, &range, *length)?;
+        let range = offset + range.start..offset + range.end;
+        Ok(Some(self.read(path, object, Some(range)).await?))
+    }
+}
@@ -0,0 +1,108 @@
+//! Chunk-at-a-time transforms into a new array.
+//!
+//! [`map_to`](UnifiedZarrArray::map_to) creates a destination array with the
+//! source's chunk grid, then reads each source chunk, passes it through a
+//! function and writes the result to the matching destination chunk, so a
+//! unit conversion or mask over an array larger than memory is a few lines:
+//!
+//! ```ignore
+//! let celsius = kelvin
+//!     .map_to(
+//!         ArrayBuilder::new(kelvin.metadata.shape.clone()).dtype(DataType::Float32),
+//!         store,
+//!         "t2m_celsius",
+//!         |_block, values| {
+//!             let values = values.data.to_f64_vec()?;
+//!             let celsius: Vec<f64> = values.iter().map(|k| k - 273.15).collect();
+//!             f64_to_zarr_vector(DataType::Float32, &celsius)
+//!         },
+//!     )
+//!     .await?;
+//! ```
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use futures::{StreamExt, TryStreamExt};
+
+use crate::array::{ArrayData, UnifiedZarrArray, chunk_indices_for_region, merge_chunks_value};
+use crate::builder::{ArrayBuilder, ChunkShape};
+use crate::error::{ZarrError, ZarrResult};
+use crate::runtime;
+use crate::store::StorageBackend;
+use crate::types::ZarrVectorValue;
+
+impl UnifiedZarrArray {
+    /// Build a new array at `path` in `store` and fill it with `f` applied to
+    /// each of this array's chunks.
+    ///
+    /// `builder` chooses the destination's element type, codec and fill
+    /// value; its shape must match this array's, and its chunk shape and
+    /// memory order are replaced with this array's so every source chunk
+    /// maps onto one destination chunk.  `f(block, values)` gets one chunk
+    /// as [`reduce`](Self::reduce) passes it, edge padding trimmed, and
+    /// returns the block's values in the destination's element type and
+    /// the same order.  Chunks are processed at most `max_concurrency` at a
+    /// time, with `f` run off the calling thread; results equal to the
+    /// destination's fill value are not stored.  Returns the destination.
+    pub async fn map_to<S, F>(
+        &self,
+        builder: ArrayBuilder,
+        store: Arc<S>,
+        path: &str,
+        f: F,
+    ) -> ZarrResult<UnifiedZarrArray>
+    where
+        S: StorageBackend + 'static,
+        F: Fn(&[Range<usize>], ArrayData<ZarrVectorValue>) -> ZarrResult<ZarrVectorValue>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let md = &self.metadata;
+        if builder.shape() != md.shape.as_slice() {
+            return Err(ZarrError::Metadata(format!(
+                "Cannot map shape {:?} to shape {:?}",
+                md.shape,
+                builder.shape()
+            )));
+        }
+        let dst = builder
+            .chunks(ChunkShape::Fixed(md.chunk_shape.clone()))
+            .order(md.order)
+            .build(store, path)
+            .await?;
+        let full_region = self.full_region();
+        let keys = chunk_indices_for_region(&md.chunk_shape, &full_region);
+        let f = Arc::new(f);
+        let dst_ref = &dst;
+        futures::stream::iter(keys)
+            .map(|key| {
+                let f = f.clone();
+                async move {
+                    let chunk = self.get_chunk(&key).await?;
+                    let block: Vec<Range<usize>> = key
+                        .iter()
+                        .zip(&md.chunk_shape)
+                        .zip(&md.shape)
+                        .map(|((&k, &len), &size)| k * len..((k + 1) * len).min(size))
+                        .collect();
+                    let values = merge_chunks_value(md, &block, &[(key, chunk)])?;
+                    let shape = block.iter().map(|r| r.len()).collect();
+                    let values = ArrayData::new(values, shape, md.order);
+                    let (block, mapped) = runtime::spawn_blocking(move || {
+                        let mapped = f(&block, values);
+                        (block, mapped)
+                    })
+                    .await?;
+                    // The block covers the chunk's in-bounds elements, so
+                    // the chunk is written without being read back.
+                    dst_ref.set_region(&block, &mapped?).await
+                }
+            })
+            .buffer_unordered(self.config().max_concurrency.max(1))
+            .try_collect::<()>()
+            .await?;
+        Ok(dst)
+    }
+}
@@ -1,12 +1,38 @@
 pub mod v2;
+pub mod v3;
 
+use std::borrow::Cow;
+
+use crate::codecs::gzip::GzipCodec;
+use crate::error::ZarrResult;
 use crate::types::{DataType, FillValue, ZarrValue};
+use base64::Engine;
 use half::f16;
 use num_complex::Complex;
 
+/// Largest metadata document [`gunzip_metadata`] will inflate (256 MiB).
+const MAX_METADATA_BYTES: usize = 256 << 20;
+
+/// `bytes` decompressed if they start with gzip's magic bytes, as for a
+/// gzipped `.zmetadata` or one served with `Content-Encoding: gzip` that
+/// the client left encoded; otherwise `bytes` as they are.  JSON can't
+/// start with those bytes, so plain documents are never mistaken for gzip.
+pub fn gunzip_metadata(bytes: &[u8]) -> ZarrResult<Cow<'_, [u8]>> {
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        Ok(Cow::Owned(
+            GzipCodec::default().decode_bounded(bytes, MAX_METADATA_BYTES)?,
+        ))
+    } else {
+        Ok(Cow::Borrowed(bytes))
+    }
+}
+
 /// Parse a fill value from a JSON value, given the target data type.
 /// Handles special string values like "NaN", "Infinity", "-Infinity",
 /// JSON null, and normal numeric/bool/string values.
+///
+/// Float fill values keep their exact bits: V3 hex strings (`"0x7fc00001"`)
+/// preserve NaN payloads and `-0.0` stays negative.
 pub fn parse_fill_value(dtype: DataType, value: &serde_json::Value) -> Result<FillValue, String> {
     match value {
         serde_json::Value::Null => Ok(FillValue::NaN),
@@ -15,38 +41,170 @@ pub fn parse_fill_value(dtype: DataType, value: &serde_json::Value) -> Result<Fi
             "NaN" => match dtype {
                 DataType::Float16 | DataType::Float32 | DataType::Float64
                 | DataType::Complex64 | DataType::Complex128 => Ok(FillValue::NaN),
-                _ => Err(format!("NaN not valid for {dtype:?}")),
+                _ => Err(format!("NaN not valid for {dtype}")),
             },
             "Infinity" => match dtype {
                 DataType::Float16 | DataType::Float32 | DataType::Float64
                 | DataType::Complex64 | DataType::Complex128 => Ok(FillValue::Infinity),
-                _ => Err(format!("Infinity not valid for {dtype:?}")),
+                _ => Err(format!("Infinity not valid for {dtype}")),
             },
             "-Infinity" => match dtype {
                 DataType::Float16 | DataType::Float32 | DataType::Float64
                 | DataType::Complex64 | DataType::Complex128 => Ok(FillValue::NegativeInfinity),
-                _ => Err(format!("-Infinity not valid for {dtype:?}")),
+                _ => Err(format!("-Infinity not valid for {dtype}")),
             },
             _ => match dtype {
+                // V3 spells exact float bit patterns (NaN payloads) in hex.
+                DataType::Float16 | DataType::Float32 | DataType::Float64
+                    if s.starts_with("0x") =>
+                {
+                    hex_float_fill(dtype, s)
+                }
                 DataType::String => Ok(FillValue::Value(ZarrValue::String(s.clone()))),
                 DataType::Bytes => Ok(FillValue::Value(ZarrValue::Bytes(s.as_bytes().to_vec()))),
+                // V2 (NumPy void) fill values are base64-encoded.
+                DataType::Raw(bits) => {
+                    let bytes = base64::engine::general_purpose::STANDARD
+                        .decode(s)
+                        .map_err(|e| format!("Invalid base64 fill value for r{bits}: {e}"))?;
+                    raw_fill(bits, bytes)
+                }
                 _ => Err(format!(
-                    "Expected {dtype:?} value, got string: {s}"
+                    "Expected {dtype} value, got string: {s}"
                 )),
             },
         },
 
         serde_json::Value::Bool(b) => match dtype {
             DataType::Bool => Ok(FillValue::Value(ZarrValue::Bool(*b))),
-            _ => Err(format!("Expected {dtype:?}, got bool")),
+            _ => Err(format!("Expected {dtype}, got bool")),
         },
 
         serde_json::Value::Number(n) => parse_numeric_fill(dtype, n),
 
+        // V3 raw fill values are arrays of byte values.
+        serde_json::Value::Array(items) => match dtype {
+            DataType::Raw(bits) => {
+                let bytes = items
+                    .iter()
+                    .map(|v| {
+                        v.as_u64()
+                            .and_then(|b| u8::try_from(b).ok())
+                            .ok_or_else(|| format!("Invalid byte in raw fill value: {v}"))
+                    })
+                    .collect::<Result<Vec<u8>, String>>()?;
+                raw_fill(bits, bytes)
+            }
+            // Complex fill values are `[real, imag]`, each a float fill.
+            DataType::Complex64 | DataType::Complex128 => {
+                let [re, im] = items.as_slice() else {
+                    return Err(format!(
+                        "Expected [real, imag] for {dtype}, got {} items",
+                        items.len()
+                    ));
+                };
+                let (re, im) = (complex_part(re)?, complex_part(im)?);
+                Ok(FillValue::Value(match dtype {
+                    DataType::Complex64 => {
+                        ZarrValue::Complex64(Complex::new(re as f32, im as f32))
+                    }
+                    _ => ZarrValue::Complex128(Complex::new(re, im)),
+                }))
+            }
+            _ => Err(format!("Expected {dtype} value, got array")),
+        },
+
         _ => Err(format!("Unexpected fill_value JSON: {value}")),
     }
 }
 
+/// Serialise a fill value as V2 `.zarray` JSON; the inverse of
+/// [`parse_fill_value`].
+///
+/// Non-finite floats become `"NaN"`, `"Infinity"` or `"-Infinity"`, complex
+/// values become `[real, imag]`, and raw bytes are base64-encoded.
+pub fn fill_value_to_json(fill: &FillValue) -> serde_json::Value {
+    match fill {
+        FillValue::NaN => float_json(f64::NAN),
+        FillValue::Infinity => float_json(f64::INFINITY),
+        FillValue::NegativeInfinity => float_json(f64::NEG_INFINITY),
+        FillValue::Value(v) => match v {
+            ZarrValue::Bool(b) => (*b).into(),
+            ZarrValue::Int8(i) => (*i).into(),
+            ZarrValue::Int16(i) => (*i).into(),
+            ZarrValue::Int32(i) => (*i).into(),
+            ZarrValue::Int64(i) => (*i).into(),
+            ZarrValue::UInt8(i) => (*i).into(),
+            ZarrValue::UInt16(i) => (*i).into(),
+            ZarrValue::UInt32(i) => (*i).into(),
+            ZarrValue::UInt64(i) => (*i).into(),
+            ZarrValue::Float16(f) => float_json(f.to_f64()),
+            ZarrValue::Float32(f) => float_json(f64::from(*f)),
+            ZarrValue::Float64(f) => float_json(*f),
+            ZarrValue::Complex64(c) => complex_json(f64::from(c.re), f64::from(c.im)),
+            ZarrValue::Complex128(c) => complex_json(c.re, c.im),
+            ZarrValue::String(s) => s.clone().into(),
+            ZarrValue::Bytes(b) => base64::engine::general_purpose::STANDARD.encode(b).into(),
+            ZarrValue::Null(_) => serde_json::Value::Null,
+        },
+    }
+}
+
+fn complex_json(re: f64, im: f64) -> serde_json::Value {
+    serde_json::Value::Array(vec![float_json(re), float_json(im)])
+}
+
+/// One part of a `[real, imag]` complex fill value.
+fn complex_part(value: &serde_json::Value) -> Result<f64, String> {
+    match parse_fill_value(DataType::Float64, value)? {
+        FillValue::NaN => Ok(f64::NAN),
+        FillValue::Infinity => Ok(f64::INFINITY),
+        FillValue::NegativeInfinity => Ok(f64::NEG_INFINITY),
+        FillValue::Value(ZarrValue::Float64(f)) => Ok(f),
+        other => Err(format!("Invalid complex fill value part: {other:?}")),
+    }
+}
+
+fn float_json(f: f64) -> serde_json::Value {
+    match serde_json::Number::from_f64(f) {
+        Some(n) => n.into(),
+        None if f.is_nan() => "NaN".into(),
+        None if f > 0.0 => "Infinity".into(),
+        None => "-Infinity".into(),
+    }
+}
+
+/// Parse a V3 hex float fill value such as `"0x7fc00000"`, keeping the exact
+/// bit pattern.  The digit count must match the type's width.
+fn hex_float_fill(dtype: DataType, s: &str) -> Result<FillValue, String> {
+    let digits = &s[2..];
+    let width = dtype.byte_size().unwrap_or(0) * 2;
+    if digits.len() != width || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!(
+            "Invalid hex fill value for {dtype}: {s} (expected {width} hex digits)"
+        ));
+    }
+    let bits = u64::from_str_radix(digits, 16)
+        .map_err(|e| format!("Invalid hex fill value for {dtype}: {s}: {e}"))?;
+    let value = match dtype {
+        DataType::Float16 => ZarrValue::Float16(f16::from_bits(bits as u16)),
+        DataType::Float32 => ZarrValue::Float32(f32::from_bits(bits as u32)),
+        _ => ZarrValue::Float64(f64::from_bits(bits)),
+    };
+    Ok(FillValue::Value(value))
+}
+
+fn raw_fill(bits: usize, bytes: Vec<u8>) -> Result<FillValue, String> {
+    if bytes.len() * 8 != bits {
+        return Err(format!(
+            "Raw fill value has {} bytes, expected {} for r{bits}",
+            bytes.len(),
+            bits / 8
+        ));
+    }
+    Ok(FillValue::Value(ZarrValue::Bytes(bytes)))
+}
+
 fn parse_numeric_fill(dtype: DataType, n: &serde_json::Number) -> Result<FillValue, String> {
     match dtype {
         DataType::Int8 => {
@@ -118,7 +276,8 @@ fn parse_numeric_fill(dtype: DataType, n: &serde_json::Number) -> Result<FillVal
             Ok(FillValue::Value(ZarrValue::Bool(i != 0)))
         }
         DataType::String | DataType::Bytes => {
-            Err(format!("Expected string for {dtype:?}, got number"))
+            Err(format!("Expected string for {dtype}, got number"))
         }
+        DataType::Raw(bits) => Err(format!("Expected byte array for r{bits}, got number")),
     }
 }
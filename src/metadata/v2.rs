@@ -1,7 +1,9 @@
+use crate::attributes::Attributes;
+use crate::capabilities::FeatureId;
 use crate::error::{ZarrError, ZarrResult};
 use crate::types::{ArrayOrder, DataType, Endian, FillValue};
 use serde::{Deserialize, Deserializer, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 // ---------------------------------------------------------------------------
 // V2 DataType  (NumPy format wrapper)
@@ -39,7 +41,7 @@ fn parse_numpy_format(s: &str) -> Result<NumPyFormat, String> {
     }
 
     let byte_order = chars[0];
-    if !['<', '>', '|'].contains(&byte_order) {
+    if !['<', '>', '|', '='].contains(&byte_order) {
         return Err(format!("Invalid byte order: {byte_order}"));
     }
 
@@ -105,6 +107,7 @@ fn parse_byte_order(c: char) -> Result<Endian, String> {
         '<' => Ok(Endian::Little),
         '>' => Ok(Endian::Big),
         '|' => Ok(Endian::NotApplicable),
+        '=' => Ok(Endian::Native),
         _ => Err(format!("Invalid byte order: {c}")),
     }
 }
@@ -126,7 +129,7 @@ fn numpy_format_to_dtype(fmt: &NumPyFormat) -> Result<V2DataType, String> {
         ('c', 8) => DataType::Complex64,
         ('c', 16) => DataType::Complex128,
         ('S', _) | ('U', _) => DataType::String,
-        ('V', _) => DataType::Bytes,
+        ('V', n) => DataType::Raw(n * 8),
         ('M', _) | ('m', _) => {
             // Treat datetime/timedelta as Int64 (epoch-based)
             DataType::Int64
@@ -149,28 +152,31 @@ fn numpy_format_to_dtype(fmt: &NumPyFormat) -> Result<V2DataType, String> {
 // Serde: V2DataType serialises as the NumPy format string
 impl Serialize for V2DataType {
     fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        let bo = match self.byte_order {
-            Endian::Little => "<",
+        // Native order is written as the host's, so the document stays
+        // readable elsewhere.
+        let bo = match self.byte_order.resolve() {
             Endian::Big => ">",
             Endian::NotApplicable => "|",
+            Endian::Little | Endian::Native => "<",
         };
         let (tc, bs) = match self.data_type {
-            DataType::Bool => ("b", "1"),
-            DataType::Int8 => ("i", "1"),
-            DataType::Int16 => ("i", "2"),
-            DataType::Int32 => ("i", "4"),
-            DataType::Int64 => ("i", "8"),
-            DataType::UInt8 => ("u", "1"),
-            DataType::UInt16 => ("u", "2"),
-            DataType::UInt32 => ("u", "4"),
-            DataType::UInt64 => ("u", "8"),
-            DataType::Float16 => ("f", "2"),
-            DataType::Float32 => ("f", "4"),
-            DataType::Float64 => ("f", "8"),
-            DataType::Complex64 => ("c", "8"),
-            DataType::Complex128 => ("c", "16"),
-            DataType::String => ("S", "1"),
-            DataType::Bytes => ("V", "1"),
+            DataType::Bool => ("b", 1),
+            DataType::Int8 => ("i", 1),
+            DataType::Int16 => ("i", 2),
+            DataType::Int32 => ("i", 4),
+            DataType::Int64 => ("i", 8),
+            DataType::UInt8 => ("u", 1),
+            DataType::UInt16 => ("u", 2),
+            DataType::UInt32 => ("u", 4),
+            DataType::UInt64 => ("u", 8),
+            DataType::Float16 => ("f", 2),
+            DataType::Float32 => ("f", 4),
+            DataType::Float64 => ("f", 8),
+            DataType::Complex64 => ("c", 8),
+            DataType::Complex128 => ("c", 16),
+            DataType::String => ("S", 1),
+            DataType::Bytes => ("V", 1),
+            DataType::Raw(bits) => ("V", bits / 8),
         };
         let tu = self
             .time_unit
@@ -224,9 +230,22 @@ pub struct ZarrV2Metadata {
     #[serde(default = "default_zarr_format", alias = "zarr_format")]
     pub zarr_format: u32,
 
+    /// Separator between chunk indices in storage keys: `.` (`0.1.2`, the
+    /// default) or `/` (`0/1/2`, a nested directory layout).
+    #[serde(
+        default = "default_dimension_separator",
+        deserialize_with = "deserialize_dimension_separator"
+    )]
+    pub dimension_separator: char,
+
     /// Computed storage keys (not from JSON; filled in after parsing).
     #[serde(skip)]
     pub keys: Vec<String>,
+
+    /// The metadata document as parsed, kept so an opened array can be
+    /// described without going back to the store.
+    #[serde(skip)]
+    pub document: serde_json::Value,
 }
 
 fn default_order() -> ArrayOrder {
@@ -237,6 +256,22 @@ fn default_zarr_format() -> u32 {
     2
 }
 
+fn default_dimension_separator() -> char {
+    '.'
+}
+
+fn deserialize_dimension_separator<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<char, D::Error> {
+    match Option::<String>::deserialize(deserializer)?.as_deref() {
+        None | Some(".") => Ok('.'),
+        Some("/") => Ok('/'),
+        Some(other) => Err(serde::de::Error::custom(format!(
+            "Invalid dimension_separator: {other:?}"
+        ))),
+    }
+}
+
 /// Custom serde for `ArrayOrder`
 impl Serialize for ArrayOrder {
     fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
@@ -273,8 +308,17 @@ fn deserialize_fill_value_field<'de, D: Deserializer<'de>>(
 }
 
 impl ZarrV2Metadata {
-    /// Parse from raw JSON bytes, fully resolving fill_value and computing keys.
+    /// Parse from raw JSON bytes, fully resolving fill_value and computing
+    /// keys.  Grids of more than the global
+    /// [`max_chunks`](crate::config::SimplezarrConfig::max_chunks) chunks are
+    /// rejected.
     pub fn parse(json_bytes: &[u8]) -> ZarrResult<Self> {
+        Self::parse_with_max_chunks(json_bytes, crate::config::global().max_chunks)
+    }
+
+    /// [`parse`](Self::parse), rejecting grids of more than `max_chunks`
+    /// chunks.
+    pub fn parse_with_max_chunks(json_bytes: &[u8], max_chunks: usize) -> ZarrResult<Self> {
         // First parse to get the raw JSON
         let raw: serde_json::Value = serde_json::from_slice(json_bytes)
             .map_err(|e| ZarrError::Metadata(format!("Invalid JSON: {e}")))?;
@@ -287,6 +331,13 @@ impl ZarrV2Metadata {
         let dtype_val = obj
             .get("dtype")
             .ok_or_else(|| ZarrError::Metadata("Missing 'dtype' field".into()))?;
+        // Structured (record) dtypes are lists of `[name, dtype]` fields.
+        // Reading them, and per-field access, isn't supported yet.
+        if dtype_val.is_array() {
+            return Err(ZarrError::UnsupportedFeature(FeatureId::DataType(format!(
+                "structured {dtype_val}"
+            ))));
+        }
         let dtype_str = dtype_val
             .as_str()
             .ok_or_else(|| ZarrError::Metadata("'dtype' must be a string".into()))?;
@@ -299,11 +350,14 @@ impl ZarrV2Metadata {
             .map_err(|e| ZarrError::Metadata(format!("fill_value: {e}")))?;
 
         // Parse the rest using serde
-        let mut md: ZarrV2Metadata = serde_json::from_value(raw)
+        let mut md: ZarrV2Metadata = serde_json::from_value(raw.clone())
             .map_err(|e| ZarrError::Metadata(format!("Metadata parse error: {e}")))?;
 
+        let itemsize = v2dtype.data_type.byte_size().unwrap_or(1);
+        check_grid(&md.shape, &md.chunks, itemsize, max_chunks)?;
         md.fill_value = fill_value;
-        md.keys = list_keys(&md.shape, &md.chunks);
+        md.keys = list_keys_with_separator(&md.shape, &md.chunks, md.dimension_separator);
+        md.document = raw;
         Ok(md)
     }
 }
@@ -315,13 +369,24 @@ impl ZarrV2Metadata {
 #[derive(Debug, Clone)]
 pub struct ZarrConsolidatedMetadata {
     pub zarr_consolidated_format: u32,
-    pub metadata: HashMap<String, ZarrV2Metadata>,
+    /// Array metadata by name, sorted by name.
+    pub metadata: BTreeMap<String, ZarrV2Metadata>,
+    /// `.zattrs` contents by node name; the root group is `""`.
+    pub attributes: BTreeMap<String, Attributes>,
 }
 
 impl ZarrConsolidatedMetadata {
-    /// Parse consolidated `.zmetadata` JSON.
+    /// Parse consolidated `.zmetadata` JSON, gzipped or not.  Arrays are
+    /// parsed as [`ZarrV2Metadata::parse`] does.
     pub fn parse(json_bytes: &[u8]) -> ZarrResult<Self> {
-        let raw: serde_json::Value = serde_json::from_slice(json_bytes)
+        Self::parse_with_max_chunks(json_bytes, crate::config::global().max_chunks)
+    }
+
+    /// [`parse`](Self::parse), skipping arrays of more than `max_chunks`
+    /// chunks as it skips other invalid arrays.
+    pub fn parse_with_max_chunks(json_bytes: &[u8], max_chunks: usize) -> ZarrResult<Self> {
+        let json_bytes = super::gunzip_metadata(json_bytes)?;
+        let raw: serde_json::Value = serde_json::from_slice(&json_bytes)
             .map_err(|e| ZarrError::Metadata(format!("Invalid consolidated JSON: {e}")))?;
 
         let obj = raw
@@ -338,9 +403,17 @@ impl ZarrConsolidatedMetadata {
             .and_then(|v| v.as_object())
             .ok_or_else(|| ZarrError::Metadata("Missing 'metadata' field".into()))?;
 
-        let mut arrays = HashMap::new();
+        let mut arrays = BTreeMap::new();
+        let mut attributes = BTreeMap::new();
 
         for (key, value) in metadata_obj {
+            if let Some(node) = key.strip_suffix(".zattrs")
+                && let Some(attrs) = value.as_object()
+            {
+                let node = node.trim_end_matches('/').trim_start_matches('/');
+                attributes.insert(node.to_string(), attrs.clone().into());
+                continue;
+            }
             // Filter out non-array keys
             if key.starts_with(".z") || key.ends_with(".zattrs") || key.ends_with(".zgroup") {
                 continue;
@@ -351,7 +424,7 @@ impl ZarrConsolidatedMetadata {
                 if value.is_object() && value.get("shape").is_some() {
                     let json_bytes = serde_json::to_vec(value)
                         .map_err(|e| ZarrError::Metadata(format!("Re-serialize: {e}")))?;
-                    match ZarrV2Metadata::parse(&json_bytes) {
+                    match ZarrV2Metadata::parse_with_max_chunks(&json_bytes, max_chunks) {
                         Ok(md) => {
                             let name = key.replace("/.zarray", "").replace(".zarray", "");
                             let name = name.trim_start_matches('/').to_string();
@@ -365,7 +438,7 @@ impl ZarrConsolidatedMetadata {
 
             let json_bytes = serde_json::to_vec(value)
                 .map_err(|e| ZarrError::Metadata(format!("Re-serialize: {e}")))?;
-            match ZarrV2Metadata::parse(&json_bytes) {
+            match ZarrV2Metadata::parse_with_max_chunks(&json_bytes, max_chunks) {
                 Ok(md) => {
                     let name = key.replace("/.zarray", "").replace(".zarray", "");
                     let name = name.trim_start_matches('/').to_string();
@@ -378,31 +451,107 @@ impl ZarrConsolidatedMetadata {
         Ok(ZarrConsolidatedMetadata {
             zarr_consolidated_format: format,
             metadata: arrays,
+            attributes,
         })
     }
 }
 
+/// Parse a `.zattrs` document, which must be a JSON object.
+pub fn parse_attributes(json_bytes: &[u8]) -> ZarrResult<Attributes> {
+    match serde_json::from_slice(json_bytes) {
+        Ok(serde_json::Value::Object(attrs)) => Ok(attrs.into()),
+        Ok(other) => Err(ZarrError::Metadata(format!(
+            ".zattrs must be a JSON object, got {other}"
+        ))),
+        Err(e) => Err(ZarrError::Metadata(format!("Invalid .zattrs JSON: {e}"))),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Key generation
 // ---------------------------------------------------------------------------
 
+/// Check that `chunks` tiles `shape` with at most `max_chunks` chunks, and
+/// that a chunk of `itemsize`-byte elements fits in memory at all.  Every
+/// key is listed when the metadata is parsed, so the limit keeps a hostile
+/// `.zarray` with a huge grid from exhausting memory.
+fn check_grid(
+    shape: &[usize],
+    chunks: &[usize],
+    itemsize: usize,
+    max_chunks: usize,
+) -> ZarrResult<()> {
+    if shape.len() != chunks.len() {
+        return Err(ZarrError::Metadata(format!(
+            "chunks {chunks:?} don't match the {}-dimensional shape {shape:?}",
+            shape.len()
+        )));
+    }
+    if chunks.contains(&0) {
+        return Err(ZarrError::Metadata(format!(
+            "chunks {chunks:?} contain a zero-length dimension"
+        )));
+    }
+    let chunk_bytes = chunks
+        .iter()
+        .try_fold(itemsize, |n, c| n.checked_mul(*c))
+        .filter(|&n| n <= isize::MAX as usize);
+    if chunk_bytes.is_none() {
+        return Err(ZarrError::Metadata(format!(
+            "chunks {chunks:?} of {itemsize}-byte elements are too large to hold in memory"
+        )));
+    }
+    let count = shape
+        .iter()
+        .zip(chunks)
+        .try_fold(1usize, |n, (s, c)| n.checked_mul(s.div_ceil(*c)));
+    match count {
+        Some(n) if n <= max_chunks => Ok(()),
+        _ => Err(ZarrError::Metadata(format!(
+            "shape {shape:?} with chunks {chunks:?} has more than {max_chunks} chunks"
+        ))),
+    }
+}
+
+/// The storage key of the chunk at `indices`: `0.1.2`, or `0` for the
+/// single chunk of a 0-d array.
+pub fn chunk_key(indices: &[usize]) -> String {
+    chunk_key_with_separator(indices, '.')
+}
+
+/// [`chunk_key`] with `separator` between the indices, e.g. `0/1/2`.
+pub fn chunk_key_with_separator(indices: &[usize], separator: char) -> String {
+    if indices.is_empty() {
+        return "0".to_string();
+    }
+    indices
+        .iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(&separator.to_string())
+}
+
 /// Generate all storage keys for a given array shape and chunk sizes.
+///
+/// An array with a zero-length dimension has no chunks, and so no keys.
 pub fn list_keys(shape: &[usize], chunks: &[usize]) -> Vec<String> {
+    list_keys_with_separator(shape, chunks, '.')
+}
+
+/// [`list_keys`] with `separator` between the indices of each key.
+pub fn list_keys_with_separator(shape: &[usize], chunks: &[usize], separator: char) -> Vec<String> {
+    if shape.contains(&0) {
+        return Vec::new();
+    }
     let chunks_per_dim: Vec<usize> = shape
         .iter()
         .zip(chunks.iter())
         .map(|(s, c)| (*s).div_ceil(*c))
         .collect();
 
-    let all_indices = cartesian_product(&chunks_per_dim);
-    all_indices
-        .into_iter()
-        .map(|idx| {
-            idx.iter()
-                .map(|i| i.to_string())
-                .collect::<Vec<_>>()
-                .join(".")
-        })
+    cartesian_product(&chunks_per_dim)
+        .iter()
+        .map(|idx| chunk_key_with_separator(idx, separator))
         .collect()
 }
 
@@ -0,0 +1,338 @@
+use crate::attributes::Attributes;
+use crate::capabilities::FeatureId;
+use crate::codecs::{AnyCodec, parse_codecs};
+use crate::error::{ZarrError, ZarrResult};
+use crate::transformers::{StorageTransformerSpec, parse_storage_transformers};
+use crate::types::{DataType, FillValue};
+
+// ---------------------------------------------------------------------------
+// V3 DataType  (named / extension form)
+// ---------------------------------------------------------------------------
+
+/// Parse a V3 `data_type` value into a [`DataType`].
+///
+/// Accepts the core names (`"float32"`, `"complex64"`, ...), raw-bits names
+/// (`"r8"`, `"r16"`, ...) and extension objects of the form
+/// `{"name": ..., "configuration": {...}}`.  Extensions whose elements can't be
+/// decoded from fixed-width chunk bytes are rejected.
+pub fn parse_v3_dtype(value: &serde_json::Value) -> ZarrResult<DataType> {
+    match value {
+        serde_json::Value::String(name) => parse_v3_dtype_name(name)?.ok_or_else(|| {
+            ZarrError::UnsupportedFeature(FeatureId::DataType(name.clone()))
+        }),
+        serde_json::Value::Object(obj) => {
+            let name = obj.get("name").and_then(|v| v.as_str()).ok_or_else(|| {
+                ZarrError::Metadata(format!(
+                    "V3 data type object is missing a 'name' string: {value}"
+                ))
+            })?;
+            // Core types may also be spelled in object form.
+            if let Some(dt) = parse_v3_dtype_name(name)? {
+                return Ok(dt);
+            }
+            parse_v3_dtype_extension(name, obj.get("configuration"))
+        }
+        _ => Err(ZarrError::Metadata(format!(
+            "V3 data_type must be a string or object, got: {value}"
+        ))),
+    }
+}
+
+/// Parse a core V3 data type name.  Returns `Ok(None)` for names that are not
+/// core types, and an error for malformed raw-bits names.
+fn parse_v3_dtype_name(name: &str) -> ZarrResult<Option<DataType>> {
+    let dt = match name {
+        "bool" => DataType::Bool,
+        "int8" => DataType::Int8,
+        "int16" => DataType::Int16,
+        "int32" => DataType::Int32,
+        "int64" => DataType::Int64,
+        "uint8" => DataType::UInt8,
+        "uint16" => DataType::UInt16,
+        "uint32" => DataType::UInt32,
+        "uint64" => DataType::UInt64,
+        "float16" => DataType::Float16,
+        "float32" => DataType::Float32,
+        "float64" => DataType::Float64,
+        "complex64" => DataType::Complex64,
+        "complex128" => DataType::Complex128,
+        "string" => DataType::String,
+        "bytes" => DataType::Bytes,
+        _ => match name.strip_prefix('r') {
+            Some(bits) if !bits.is_empty() && bits.chars().all(|c| c.is_ascii_digit()) => {
+                DataType::Raw(parse_raw_bits(name, bits)?)
+            }
+            _ => return Ok(None),
+        },
+    };
+    Ok(Some(dt))
+}
+
+/// Validate the bit width of an `r<bits>` raw data type.
+fn parse_raw_bits(name: &str, bits: &str) -> ZarrResult<usize> {
+    let n: usize = bits
+        .parse()
+        .map_err(|_| ZarrError::Metadata(format!("Invalid raw data type: {name}")))?;
+    if n == 0 || !n.is_multiple_of(8) {
+        return Err(ZarrError::Metadata(format!(
+            "Raw data type {name} must have a positive multiple of 8 bits"
+        )));
+    }
+    Ok(n)
+}
+
+/// Map the registered V3 data type extensions that have a natural
+/// [`DataType`] equivalent.
+fn parse_v3_dtype_extension(
+    name: &str,
+    configuration: Option<&serde_json::Value>,
+) -> ZarrResult<DataType> {
+    match name {
+        // Epoch-based, like the V2 `M8`/`m8` NumPy types.
+        "numpy.datetime64" | "numpy.timedelta64" => Ok(DataType::Int64),
+        "variable_length_utf8" => Ok(DataType::String),
+        "variable_length_bytes" => Ok(DataType::Bytes),
+        // Opaque fixed-width elements, like the V2 `|V<n>` void type.
+        "fixed_length_bytes" => {
+            let length = configuration
+                .and_then(|c| c.get("length_bytes"))
+                .and_then(|v| v.as_u64())
+                .filter(|&n| n > 0)
+                .ok_or_else(|| {
+                    ZarrError::Metadata(format!(
+                        "{name} requires a positive configuration.length_bytes"
+                    ))
+                })?;
+            Ok(DataType::Raw(length as usize * 8))
+        }
+        _ => Err(ZarrError::UnsupportedFeature(FeatureId::DataType(
+            name.to_string(),
+        ))),
+    }
+}
+
+/// The V3 core name for a [`DataType`].
+pub fn v3_dtype_name(dtype: DataType) -> String {
+    dtype.to_string()
+}
+
+// ---------------------------------------------------------------------------
+// Chunk key encoding
+// ---------------------------------------------------------------------------
+
+/// How chunk indices map to storage keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkKeyEncoding {
+    /// `c/0/1` (or `c.0.1` with a `.` separator); `c` for 0-d arrays.
+    Default { separator: char },
+    /// V2-style `0.1` (or `0/1`); `0` for 0-d arrays.
+    V2 { separator: char },
+}
+
+impl Default for ChunkKeyEncoding {
+    fn default() -> Self {
+        ChunkKeyEncoding::Default { separator: '/' }
+    }
+}
+
+impl ChunkKeyEncoding {
+    /// Parse a `chunk_key_encoding` object.
+    pub fn parse(value: &serde_json::Value) -> ZarrResult<Self> {
+        let name = value.get("name").and_then(|v| v.as_str());
+        let separator = match value.pointer("/configuration/separator") {
+            None => None,
+            Some(serde_json::Value::String(s)) if s == "/" || s == "." => s.chars().next(),
+            Some(other) => {
+                return Err(ZarrError::Metadata(format!(
+                    "Invalid chunk key separator: {other}"
+                )));
+            }
+        };
+        match name {
+            Some("default") => Ok(ChunkKeyEncoding::Default {
+                separator: separator.unwrap_or('/'),
+            }),
+            Some("v2") => Ok(ChunkKeyEncoding::V2 {
+                separator: separator.unwrap_or('.'),
+            }),
+            _ => Err(ZarrError::Metadata(format!(
+                "Unsupported chunk_key_encoding: {value}"
+            ))),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let (name, separator) = match self {
+            ChunkKeyEncoding::Default { separator } => ("default", separator),
+            ChunkKeyEncoding::V2 { separator } => ("v2", separator),
+        };
+        serde_json::json!({
+            "name": name,
+            "configuration": {"separator": separator.to_string()},
+        })
+    }
+
+    /// The storage key of the chunk at `indices`.
+    pub fn key(&self, indices: &[usize]) -> String {
+        let join = |separator: char| {
+            indices
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+                .join(&separator.to_string())
+        };
+        match self {
+            ChunkKeyEncoding::Default { .. } if indices.is_empty() => "c".to_string(),
+            ChunkKeyEncoding::Default { separator } => format!("c{separator}{}", join(*separator)),
+            ChunkKeyEncoding::V2 { .. } if indices.is_empty() => "0".to_string(),
+            ChunkKeyEncoding::V2 { separator } => join(*separator),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ZarrV3Metadata
+// ---------------------------------------------------------------------------
+
+/// Top-level `zarr.json` fields defined by the V3 spec.
+const CORE_FIELDS: &[&str] = &[
+    "zarr_format",
+    "node_type",
+    "shape",
+    "data_type",
+    "chunk_grid",
+    "chunk_key_encoding",
+    "fill_value",
+    "codecs",
+    "attributes",
+    "dimension_names",
+    "storage_transformers",
+];
+
+/// The extension fields of `document` that may be ignored.  An extension
+/// must be understood unless it is an object with `"must_understand": false`.
+fn parse_extensions(
+    document: &serde_json::Value,
+) -> ZarrResult<serde_json::Map<String, serde_json::Value>> {
+    let mut extensions = serde_json::Map::new();
+    let fields = document.as_object().into_iter().flatten();
+    for (name, value) in fields.filter(|(name, _)| !CORE_FIELDS.contains(&name.as_str())) {
+        if value.get("must_understand") != Some(&serde_json::Value::Bool(false)) {
+            return Err(ZarrError::UnsupportedFeature(FeatureId::Extension(
+                name.clone(),
+            )));
+        }
+        extensions.insert(name.clone(), value.clone());
+    }
+    Ok(extensions)
+}
+
+/// A parsed V3 array `zarr.json`.
+///
+/// Only regular chunk grids are supported.  Codecs are resolved to
+/// [`AnyCodec`]s, so an array using a codec this build lacks fails to parse.
+///
+/// Unknown top-level fields are extensions.  Those flagged
+/// `"must_understand": false` are kept in `extensions`; any other fails with
+/// [`FeatureId::Extension`].
+#[derive(Debug, Clone)]
+pub struct ZarrV3Metadata {
+    pub shape: Vec<usize>,
+    pub chunks: Vec<usize>,
+    pub data_type: DataType,
+    pub fill_value: FillValue,
+    pub codecs: Vec<AnyCodec>,
+    pub chunk_key_encoding: ChunkKeyEncoding,
+    pub attributes: Option<Attributes>,
+    pub dimension_names: Option<Vec<Option<String>>>,
+    /// Storage transformers, applied when chunks are accessed; see
+    /// [`transformers`](crate::transformers).
+    pub storage_transformers: Vec<StorageTransformerSpec>,
+    /// Unknown extension fields that may be ignored, as read.
+    pub extensions: serde_json::Map<String, serde_json::Value>,
+    /// The metadata document as parsed.
+    pub document: serde_json::Value,
+}
+
+impl ZarrV3Metadata {
+    /// Parse from raw `zarr.json` bytes, gzipped or not.
+    pub fn parse(json_bytes: &[u8]) -> ZarrResult<Self> {
+        let json_bytes = super::gunzip_metadata(json_bytes)?;
+        let raw: serde_json::Value = serde_json::from_slice(&json_bytes)
+            .map_err(|e| ZarrError::Metadata(format!("Invalid JSON: {e}")))?;
+        let field = |name: &str| {
+            raw.get(name)
+                .ok_or_else(|| ZarrError::Metadata(format!("Missing '{name}' field")))
+        };
+        let usizes = |value: &serde_json::Value, name: &str| -> ZarrResult<Vec<usize>> {
+            serde_json::from_value(value.clone())
+                .map_err(|e| ZarrError::Metadata(format!("Invalid '{name}': {e}")))
+        };
+
+        let zarr_format = field("zarr_format")?.as_u64().unwrap_or(0);
+        if zarr_format != 3 {
+            return Err(ZarrError::UnsupportedFeature(FeatureId::ZarrFormat(
+                zarr_format as u32,
+            )));
+        }
+        if field("node_type")?.as_str() != Some("array") {
+            return Err(ZarrError::Metadata("zarr.json is not an array".into()));
+        }
+        let extensions = parse_extensions(&raw)?;
+
+        let shape = usizes(field("shape")?, "shape")?;
+        let grid = field("chunk_grid")?;
+        if grid.get("name").and_then(|v| v.as_str()) != Some("regular") {
+            return Err(ZarrError::Metadata(format!(
+                "Unsupported chunk_grid: {grid}"
+            )));
+        }
+        let chunks = usizes(
+            grid.pointer("/configuration/chunk_shape")
+                .unwrap_or(&serde_json::Value::Null),
+            "chunk_shape",
+        )?;
+        if chunks.len() != shape.len() || chunks.contains(&0) {
+            return Err(ZarrError::Metadata(format!(
+                "Chunk shape {chunks:?} does not fit array shape {shape:?}"
+            )));
+        }
+
+        let data_type = parse_v3_dtype(field("data_type")?)?;
+        let fill_value = super::parse_fill_value(data_type, field("fill_value")?)
+            .map_err(|e| ZarrError::Metadata(format!("fill_value: {e}")))?;
+        let codecs = field("codecs")?
+            .as_array()
+            .ok_or_else(|| ZarrError::Metadata("'codecs' must be an array".into()))?;
+        let codecs = parse_codecs(codecs)?;
+        let chunk_key_encoding = match raw.get("chunk_key_encoding") {
+            Some(value) => ChunkKeyEncoding::parse(value)?,
+            None => ChunkKeyEncoding::default(),
+        };
+        let attributes = raw
+            .get("attributes")
+            .and_then(|v| v.as_object())
+            .map(|attrs| attrs.clone().into());
+        let storage_transformers = parse_storage_transformers(raw.get("storage_transformers"))?;
+        let dimension_names = match raw.get("dimension_names") {
+            None | Some(serde_json::Value::Null) => None,
+            Some(names) => Some(serde_json::from_value(names.clone()).map_err(|e| {
+                ZarrError::Metadata(format!("Invalid 'dimension_names': {e}"))
+            })?),
+        };
+
+        Ok(Self {
+            shape,
+            chunks,
+            data_type,
+            fill_value,
+            codecs,
+            chunk_key_encoding,
+            attributes,
+            dimension_names,
+            storage_transformers,
+            extensions,
+            document: raw,
+        })
+    }
+}
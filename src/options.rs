@@ -0,0 +1,183 @@
+//! Per-array read and write options.
+//!
+//! Unlike [`SimplezarrConfig`](crate::config::SimplezarrConfig), which holds
+//! process-wide defaults, these describe how one array's chunks are decoded,
+//! assembled and written.  Attach them with
+//! [`UnifiedZarrArray::with_read_options`](crate::array::UnifiedZarrArray::with_read_options)
+//! and
+//! [`UnifiedZarrArray::with_write_options`](crate::array::UnifiedZarrArray::with_write_options).
+//! [`GroupReadOptions`] sets read options per array for
+//! [`UnifiedZarrGroup::load_all_with_options`](crate::group::UnifiedZarrGroup::load_all_with_options).
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::Endian;
+
+/// Layout of `bool` elements in decoded chunk bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoolEncoding {
+    /// One byte per element, `0x00` or `0x01` (the Zarr spec encoding).
+    #[default]
+    Byte,
+    /// Eight elements per byte, most significant bit first (NumPy
+    /// `packbits` order), with the last byte zero-padded.
+    BitPacked,
+}
+
+/// Default decoded-size limit for chunks of variable-size types (256 MiB).
+pub const DEFAULT_MAX_VARIABLE_CHUNK_BYTES: usize = 256 << 20;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArrayReadOptions {
+    pub bool_encoding: BoolEncoding,
+    /// Decoded-size limit for chunks whose size can't be derived from the
+    /// chunk shape (string and bytes types).  Fixed-size types are always
+    /// limited to exactly `elements × element size`.
+    pub max_variable_chunk_bytes: usize,
+    /// Window log limit for zstd-compressed chunks, overriding the codec's
+    /// [`window_log_max`](crate::codecs::zstd::ZstdCodec::window_log_max).
+    /// Raise it to read frames written with large windows (`zstd --long=31`).
+    pub zstd_window_log_max: Option<u32>,
+    /// Leave the array's length-1 dimensions out of the
+    /// [`shape`](crate::array::ArrayData::shape) of loaded data.  Only
+    /// dimensions of length 1 in the array are dropped, so a region's rank
+    /// doesn't depend on its extent.  Off by default.
+    pub squeeze: bool,
+    /// Byte order assumed for chunks whose `bytes` codec has no `endian`
+    /// (allowed by V3 for single-byte types, but also written by some
+    /// tools for wider ones).  [`Endian::Native`] means the host's order.
+    /// Little-endian by default.
+    pub default_endian: Endian,
+    /// Value for the elements of missing chunks in integer arrays whose fill
+    /// value is NaN (a JSON `null` fill), which otherwise read as 0.  Handy
+    /// for handing data to systems without nulls, e.g. `-9999`.  Must fit
+    /// the element type.  Reads only: writes still store the fill value.
+    pub int_fill_sentinel: Option<i64>,
+    /// Drop decoded bytes past the end of a fixed-size chunk instead of
+    /// failing with [`ZarrError::ChunkSizeMismatch`](crate::error::ZarrError::ChunkSizeMismatch).
+    /// Excess bytes usually mean a misconfigured pipeline (the wrong dtype,
+    /// a missing shuffle filter), so this is off by default.  Decompressors
+    /// may then produce up to `max_variable_chunk_bytes`.  Short chunks are
+    /// an error either way.
+    pub allow_trailing_bytes: bool,
+}
+
+impl Default for ArrayReadOptions {
+    fn default() -> Self {
+        Self {
+            bool_encoding: BoolEncoding::default(),
+            max_variable_chunk_bytes: DEFAULT_MAX_VARIABLE_CHUNK_BYTES,
+            zstd_window_log_max: None,
+            squeeze: false,
+            default_endian: Endian::Little,
+            int_fill_sentinel: None,
+            allow_trailing_bytes: false,
+        }
+    }
+}
+
+impl ArrayReadOptions {
+    pub fn max_variable_chunk_bytes(mut self, bytes: usize) -> Self {
+        self.max_variable_chunk_bytes = bytes;
+        self
+    }
+
+    pub fn bool_encoding(mut self, encoding: BoolEncoding) -> Self {
+        self.bool_encoding = encoding;
+        self
+    }
+
+    pub fn zstd_window_log_max(mut self, log: u32) -> Self {
+        self.zstd_window_log_max = Some(log);
+        self
+    }
+
+    pub fn squeeze(mut self, squeeze: bool) -> Self {
+        self.squeeze = squeeze;
+        self
+    }
+
+    pub fn default_endian(mut self, endian: Endian) -> Self {
+        self.default_endian = endian;
+        self
+    }
+
+    pub fn int_fill_sentinel(mut self, sentinel: i64) -> Self {
+        self.int_fill_sentinel = Some(sentinel);
+        self
+    }
+
+    pub fn allow_trailing_bytes(mut self, allow: bool) -> Self {
+        self.allow_trailing_bytes = allow;
+        self
+    }
+}
+
+/// Read options for the arrays of a group: `default` for every array, except
+/// those named in `overrides`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GroupReadOptions {
+    pub default: ArrayReadOptions,
+    pub overrides: BTreeMap<String, ArrayReadOptions>,
+}
+
+impl GroupReadOptions {
+    pub fn new(default: ArrayReadOptions) -> Self {
+        Self {
+            default,
+            overrides: BTreeMap::new(),
+        }
+    }
+
+    /// Use `options` for the array called `name`.
+    pub fn with_override(mut self, name: impl Into<String>, options: ArrayReadOptions) -> Self {
+        self.overrides.insert(name.into(), options);
+        self
+    }
+
+    /// The options for the array called `name`.
+    pub fn for_array(&self, name: &str) -> &ArrayReadOptions {
+        self.overrides.get(name).unwrap_or(&self.default)
+    }
+}
+
+impl From<HashMap<String, ArrayReadOptions>> for GroupReadOptions {
+    /// Per-array options, with the defaults for arrays not in the map.
+    fn from(overrides: HashMap<String, ArrayReadOptions>) -> Self {
+        Self {
+            default: ArrayReadOptions::default(),
+            overrides: overrides.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArrayWriteOptions {
+    /// Skip writing a chunk when its key already holds the same encoded
+    /// bytes.  Matches are checked by size, then by ETag when it is an MD5
+    /// of the content (as on S3, GCS and MinIO), otherwise by reading the
+    /// stored value back and comparing hashes.
+    pub dedup: bool,
+    /// Store chunks whose elements all equal the fill value.  When off (the
+    /// default), such chunks are left out of the store, and any stored copy
+    /// is removed, since readers fill missing chunks anyway.
+    pub write_empty_chunks: bool,
+}
+
+impl ArrayWriteOptions {
+    pub fn dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    pub fn write_empty_chunks(mut self, write: bool) -> Self {
+        self.write_empty_chunks = write;
+        self
+    }
+}
@@ -0,0 +1,114 @@
+//! Read plans for callers that do their own I/O.
+//!
+//! [`plan_read`](UnifiedZarrArray::plan_read) maps a region to the chunks it
+//! touches without fetching anything.  Each [`ReadPlan`] names the store key
+//! to fetch and which part of the chunk lands where in the output, so an
+//! external scheduler can fetch chunks on its own workers, then hand the
+//! bytes back for [`decode_planned`](UnifiedZarrArray::decode_planned) and
+//! [`assemble_region`](UnifiedZarrArray::assemble_region):
+//!
+//! ```ignore
+//! let region = [0..10, 20..40];
+//! let mut chunks = Vec::new();
+//! for plan in array.plan_read(&region)? {
+//!     let bytes = my_scheduler.fetch(&plan.path).await?;
+//!     let chunk = array.decode_planned(&plan, bytes.as_deref()).await?;
+//!     chunks.push((plan.chunk_index, chunk));
+//! }
+//! let data = array.assemble_region(&region, &chunks)?;
+//! ```
+
+use std::ops::Range;
+
+use crate::array::{
+    ArrayData, UnifiedZarrArray, chunk_indices_for_region, merge_chunks_value, validate_region,
+};
+use crate::error::ZarrResult;
+use crate::metadata::v2::chunk_key_with_separator;
+use crate::types::ZarrVectorValue;
+
+/// One chunk's share of a region read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadPlan {
+    /// The chunk's indices in the chunk grid.
+    pub chunk_index: Vec<usize>,
+    /// The chunk's key within the array, e.g. `0.1`.
+    pub chunk_key: String,
+    /// The store key holding the chunk's bytes.
+    pub path: String,
+    /// The elements of the chunk inside the region, per dimension, counted
+    /// from the chunk's origin.
+    pub chunk_ranges: Vec<Range<usize>>,
+    /// Where those elements go, counted from the region's origin.
+    pub output_ranges: Vec<Range<usize>>,
+}
+
+impl ReadPlan {
+    /// Number of elements this chunk contributes to the region.
+    pub fn len(&self) -> usize {
+        self.chunk_ranges.iter().map(|r| r.len()).product()
+    }
+
+    /// Whether the chunk contributes no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl UnifiedZarrArray {
+    /// The chunks overlapping `region`, in key order, without fetching
+    /// any.  Chunks that were never written are planned too: their store
+    /// key just doesn't exist, and decoding `None` gives the fill value.
+    pub fn plan_read(&self, region: &[Range<usize>]) -> ZarrResult<Vec<ReadPlan>> {
+        let md = &self.metadata;
+        validate_region(&md.shape, region)?;
+        Ok(chunk_indices_for_region(&md.chunk_shape, region)
+            .into_iter()
+            .map(|chunk_index| {
+                let chunk_key = chunk_key_with_separator(&chunk_index, md.dimension_separator);
+                let (chunk_ranges, output_ranges) = chunk_index
+                    .iter()
+                    .zip(&md.chunk_shape)
+                    .zip(region)
+                    .map(|((&i, &len), r)| {
+                        let origin = i * len;
+                        let (lo, hi) = (origin.max(r.start), (origin + len).min(r.end));
+                        (lo - origin..hi - origin, lo - r.start..hi - r.start)
+                    })
+                    .unzip();
+                ReadPlan {
+                    path: self.store.join(&self.path, &chunk_key),
+                    chunk_index,
+                    chunk_key,
+                    chunk_ranges,
+                    output_ranges,
+                }
+            })
+            .collect())
+    }
+
+    /// Decode the bytes fetched for `plan`, `None` meaning the chunk was
+    /// never written.  The chunk is decoded whole, in the chunk shape, as
+    /// [`get_chunk`](Self::get_chunk) returns it, bypassing the chunk cache.
+    pub async fn decode_planned(
+        &self,
+        plan: &ReadPlan,
+        data: Option<&[u8]>,
+    ) -> ZarrResult<ZarrVectorValue> {
+        let key_str = self.chunk_key(&plan.chunk_index)?;
+        self.decode_chunk(&plan.chunk_index, &key_str, data, self.codecs()?)
+            .await
+    }
+
+    /// Assemble decoded `(chunk index, chunk)` pairs into `region`, as
+    /// [`read_region_value`](Self::read_region_value) does after fetching.
+    pub fn assemble_region(
+        &self,
+        region: &[Range<usize>],
+        chunks: &[(Vec<usize>, ZarrVectorValue)],
+    ) -> ZarrResult<ArrayData<ZarrVectorValue>> {
+        validate_region(&self.metadata.shape, region)?;
+        let values = merge_chunks_value(&self.metadata, region, chunks)?;
+        Ok(self.region_data(region, values))
+    }
+}
@@ -0,0 +1,270 @@
+//! Writing multi-resolution image pyramids.
+//!
+//! [`build_pyramid`] writes an array and successively downsampled copies of
+//! it as the levels `0`, `1`, … of a V2 group, with the `multiscales`
+//! attribute OME-NGFF 0.4 readers (napari, OMERO, `ome-zarr-py`) look for:
+//!
+//! ```ignore
+//! let pyramid = build_pyramid(
+//!     &image,
+//!     4,
+//!     |window| window.iter().sum::<f64>() / window.len() as f64,
+//!     store,
+//!     "image.ome.zarr",
+//! )
+//! .await?;
+//! ```
+//!
+//! Each level halves the last two (`y`, `x`) dimensions of the one before,
+//! rounding up; other dimensions are kept.  Levels are written one output
+//! chunk at a time, reading only the matching region of the level above.
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use futures::{StreamExt, TryStreamExt};
+use serde_json::json;
+
+use crate::array::{UnifiedZarrArray, chunk_indices_for_region, strides};
+use crate::attributes::Attributes;
+use crate::builder::{ArrayBuilder, ChunkShape};
+use crate::error::{ZarrError, ZarrResult};
+use crate::group::UnifiedZarrGroup;
+use crate::runtime;
+use crate::store::StorageBackend;
+use crate::types::{ArrayOrder, f64_to_zarr_vector};
+
+/// OME-NGFF version written into the `multiscales` attribute.
+pub const NGFF_VERSION: &str = "0.4";
+
+/// Write `array` and `levels - 1` downsampled copies of it as a multiscale
+/// image group at `path` in `store`, and open the group.
+///
+/// Level `n + 1` halves the last two dimensions of level `n`, each element
+/// being `downsample` of the (up to) 2×2 window of level `n` it covers, as
+/// `f64`; edge windows of odd-sized levels are smaller.  Use a mean for
+/// intensities and a max or mode for label images.  Every level keeps the
+/// source's element type, chunk shape (clipped to the level's shape), memory
+/// order and fill value, with the default [`ArrayBuilder`] codec.
+///
+/// Axes are named from the source's dimension names, or `t`, `c`, `z`, `y`,
+/// `x` from the right when it has none; NGFF images have 2 to 5 dimensions.
+/// The group's `.zgroup` and `.zattrs` are written last, and the returned
+/// group carries the attributes.
+pub async fn build_pyramid<S, F>(
+    array: &UnifiedZarrArray,
+    levels: usize,
+    downsample: F,
+    store: Arc<S>,
+    path: &str,
+) -> ZarrResult<UnifiedZarrGroup>
+where
+    S: StorageBackend + 'static,
+    F: Fn(&[f64]) -> f64 + Send + Sync + 'static,
+{
+    let md = &array.metadata;
+    let ndim = md.shape.len();
+    if !(2..=5).contains(&ndim) {
+        return Err(ZarrError::Metadata(format!(
+            "OME-NGFF images have 2 to 5 dimensions, got shape {:?}",
+            md.shape
+        )));
+    }
+    if levels == 0 {
+        return Err(ZarrError::Other(
+            "A pyramid needs at least one level".into(),
+        ));
+    }
+    let builder = |shape: &[usize]| {
+        let chunks: Vec<usize> = md
+            .chunk_shape
+            .iter()
+            .zip(shape)
+            .map(|(&c, &s)| c.min(s).max(1))
+            .collect();
+        ArrayBuilder::new(shape)
+            .chunks(ChunkShape::Fixed(chunks))
+            .dtype(md.data_type)
+            .fill_value(md.fill_value.clone())
+            .order(md.order)
+    };
+
+    let mut level = array
+        .map_to(
+            builder(&md.shape),
+            store.clone(),
+            &store.join(path, "0"),
+            |_, block| Ok(block.data),
+        )
+        .await?;
+    let downsample = Arc::new(downsample);
+    for n in 1..levels {
+        let shape: Vec<usize> = level
+            .metadata
+            .shape
+            .iter()
+            .enumerate()
+            .map(|(d, &s)| if d + 2 >= ndim { s.div_ceil(2) } else { s })
+            .collect();
+        let next = builder(&shape)
+            .build(store.clone(), &store.join(path, &n.to_string()))
+            .await?;
+        downsample_level(&level, &next, downsample.clone()).await?;
+        level = next;
+    }
+
+    let group = json!({"zarr_format": 2});
+    store
+        .put(
+            &store.join(path, ".zgroup"),
+            serde_json::to_vec_pretty(&group)?.into(),
+        )
+        .await?;
+    let attributes: Attributes = [(
+        "multiscales".to_string(),
+        json!([multiscales(array, levels)]),
+    )]
+    .into_iter()
+    .collect();
+    store
+        .put(
+            &store.join(path, ".zattrs"),
+            serde_json::to_vec_pretty(&attributes)?.into(),
+        )
+        .await?;
+    let names: Vec<String> = (0..levels).map(|n| n.to_string()).collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let mut group = crate::v2::open_group(store, path, &names).await?;
+    group.metadata.attributes = Some(attributes);
+    Ok(group)
+}
+
+/// The `multiscales` entry describing `levels` levels of `array`.
+fn multiscales(array: &UnifiedZarrArray, levels: usize) -> serde_json::Value {
+    let ndim = array.metadata.shape.len();
+    let defaults = ["t", "c", "z", "y", "x"];
+    let names = array.metadata.dimension_names.clone().unwrap_or_default();
+    let axes: Vec<serde_json::Value> = (0..ndim)
+        .map(|d| {
+            let name = names
+                .get(d)
+                .cloned()
+                .flatten()
+                .unwrap_or_else(|| defaults[5 - ndim + d].to_string());
+            let kind = match name.as_str() {
+                "t" | "time" => "time",
+                "c" | "channel" => "channel",
+                _ => "space",
+            };
+            json!({"name": name, "type": kind})
+        })
+        .collect();
+    let datasets: Vec<serde_json::Value> = (0..levels)
+        .map(|n| {
+            let factor = (1u64 << n) as f64;
+            let scale: Vec<f64> = (0..ndim)
+                .map(|d| if d + 2 >= ndim { factor } else { 1.0 })
+                .collect();
+            json!({
+                "path": n.to_string(),
+                "coordinateTransformations": [{"type": "scale", "scale": scale}],
+            })
+        })
+        .collect();
+    json!({
+        "version": NGFF_VERSION,
+        "name": array.path.rsplit('/').next().unwrap_or_default(),
+        "axes": axes,
+        "datasets": datasets,
+    })
+}
+
+/// Fill `dst` chunk by chunk from 2×2 windows of `src`.
+async fn downsample_level<F>(
+    src: &UnifiedZarrArray,
+    dst: &UnifiedZarrArray,
+    downsample: Arc<F>,
+) -> ZarrResult<()>
+where
+    F: Fn(&[f64]) -> f64 + Send + Sync + 'static,
+{
+    let md = &dst.metadata;
+    let ndim = md.shape.len();
+    let keys = chunk_indices_for_region(&md.chunk_shape, &dst.full_region());
+    futures::stream::iter(keys)
+        .map(|key| {
+            let downsample = downsample.clone();
+            async move {
+                let block: Vec<Range<usize>> = key
+                    .iter()
+                    .zip(&md.chunk_shape)
+                    .zip(&md.shape)
+                    .map(|((&k, &len), &size)| k * len..((k + 1) * len).min(size))
+                    .collect();
+                let source: Vec<Range<usize>> = block
+                    .iter()
+                    .zip(&src.metadata.shape)
+                    .enumerate()
+                    .map(|(d, (r, &size))| {
+                        if d + 2 >= ndim {
+                            2 * r.start..(2 * r.end).min(size)
+                        } else {
+                            r.clone()
+                        }
+                    })
+                    .collect();
+                let input = src.read_region_value(&source).await?;
+                let dtype = md.data_type;
+                let order = md.order;
+                let (block, values) = runtime::spawn_blocking(move || {
+                    let values = input.data.to_f64_vec().and_then(|values| {
+                        let windows = windows(&block, &input.shape, order, &values, &*downsample);
+                        f64_to_zarr_vector(dtype, &windows)
+                    });
+                    (block, values)
+                })
+                .await?;
+                dst.set_region(&block, &values?).await
+            }
+        })
+        .buffer_unordered(dst.config().max_concurrency.max(1))
+        .try_collect()
+        .await
+}
+
+/// `downsample` of each window of `input` (shape `input_shape`), one per
+/// element of `block`, in `order`.  The last two dimensions are halved.
+fn windows(
+    block: &[Range<usize>],
+    input_shape: &[usize],
+    order: ArrayOrder,
+    input: &[f64],
+    downsample: &dyn Fn(&[f64]) -> f64,
+) -> Vec<f64> {
+    let ndim = block.len();
+    let shape: Vec<usize> = block.iter().map(|r| r.len()).collect();
+    let out_strides = strides(&shape, order);
+    let in_strides = strides(input_shape, order);
+    let mut window = Vec::with_capacity(4);
+    (0..shape.iter().product())
+        .map(|i: usize| {
+            let mut offsets = vec![0];
+            for d in 0..ndim {
+                let index = i / out_strides[d] % shape[d];
+                let span = if d + 2 >= ndim {
+                    2 * index..(2 * index + 2).min(input_shape[d])
+                } else {
+                    index..index + 1
+                };
+                let stride = in_strides[d];
+                offsets = offsets
+                    .iter()
+                    .flat_map(|o| span.clone().map(move |j| o + j * stride))
+                    .collect();
+            }
+            window.clear();
+            window.extend(offsets.iter().map(|&o| input[o]));
+            downsample(&window)
+        })
+        .collect()
+}
@@ -0,0 +1,99 @@
+//! Chunk-at-a-time reductions.
+//!
+//! [`reduce`](UnifiedZarrArray::reduce) folds each chunk into a partial
+//! result and combines the partials, so a sum, histogram or min/max over an
+//! array never holds more than `max_concurrency` decoded chunks at once.
+//! Folds run off the calling thread with
+//! [`spawn_blocking`](crate::runtime::spawn_blocking), several at a time.
+//!
+//! ```ignore
+//! let (sum, count) = array
+//!     .reduce(
+//!         (0.0, 0),
+//!         |(sum, count), _region, block| {
+//!             let values = block.data.to_f64_vec().unwrap_or_default();
+//!             (sum + values.iter().sum::<f64>(), count + values.len())
+//!         },
+//!         |a, b| (a.0 + b.0, a.1 + b.1),
+//!     )
+//!     .await?;
+//! ```
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use futures::{StreamExt, TryStreamExt};
+
+use crate::array::{
+    ArrayData, UnifiedZarrArray, chunk_indices_for_region, merge_chunks_value, validate_region,
+};
+use crate::error::ZarrResult;
+use crate::runtime;
+use crate::types::ZarrVectorValue;
+
+impl UnifiedZarrArray {
+    /// Reduce the whole array; see [`reduce_region`](Self::reduce_region).
+    pub async fn reduce<A, F, C>(&self, init: A, fold_chunk: F, combine: C) -> ZarrResult<A>
+    where
+        A: Clone + Send + 'static,
+        F: Fn(A, &[Range<usize>], ArrayData<ZarrVectorValue>) -> A + Send + Sync + 'static,
+        C: FnMut(A, A) -> A,
+    {
+        self.reduce_region(&self.full_region(), init, fold_chunk, combine)
+            .await
+    }
+
+    /// Fold every chunk overlapping `region`, then combine the partials.
+    ///
+    /// `fold_chunk(init, block, values)` gets the part of one chunk inside
+    /// `region`: its index ranges and its values, shaped like them in the
+    /// array's memory order.  Chunks are fetched at most `max_concurrency` at
+    /// a time and their partials combined in completion order, so `combine`
+    /// should be associative and commutative.  A region touching no chunks
+    /// reduces to `init`.
+    pub async fn reduce_region<A, F, C>(
+        &self,
+        region: &[Range<usize>],
+        init: A,
+        fold_chunk: F,
+        mut combine: C,
+    ) -> ZarrResult<A>
+    where
+        A: Clone + Send + 'static,
+        F: Fn(A, &[Range<usize>], ArrayData<ZarrVectorValue>) -> A + Send + Sync + 'static,
+        C: FnMut(A, A) -> A,
+    {
+        validate_region(&self.metadata.shape, region)?;
+        let keys = chunk_indices_for_region(&self.metadata.chunk_shape, region);
+        let fold_chunk = Arc::new(fold_chunk);
+        let partials = futures::stream::iter(keys)
+            .map(|key| {
+                let fold_chunk = fold_chunk.clone();
+                let init = init.clone();
+                async move {
+                    let chunk = self.get_chunk(&key).await?;
+                    let block: Vec<Range<usize>> = key
+                        .iter()
+                        .zip(&self.metadata.chunk_shape)
+                        .zip(region)
+                        .map(|((&k, &len), r)| (k * len).max(r.start)..((k + 1) * len).min(r.end))
+                        .collect();
+                    // Edge chunks are trimmed to the block, dropping padding.
+                    let values = merge_chunks_value(&self.metadata, &block, &[(key, chunk)])?;
+                    let shape = block.iter().map(|r| r.len()).collect();
+                    let values = ArrayData::new(values, shape, self.metadata.order);
+                    runtime::spawn_blocking(move || fold_chunk(init, &block, values)).await
+                }
+            })
+            .buffer_unordered(self.config().max_concurrency.max(1));
+        let combined = partials
+            .try_fold(None, |acc: Option<A>, partial| {
+                futures::future::ready(Ok(Some(match acc {
+                    Some(acc) => combine(acc, partial),
+                    None => partial,
+                })))
+            })
+            .await?;
+        Ok(combined.unwrap_or(init))
+    }
+}
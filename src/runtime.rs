@@ -0,0 +1,195 @@
+//! Runtime-agnostic task helpers.
+//!
+//! The crate never spawns tasks on an executor, so its futures run under
+//! tokio, async-std, smol, or a plain `futures::executor::block_on`.  Timers
+//! come from `futures-timer`, and CPU-heavy work moves off the calling
+//! thread with [`spawn_blocking`], unless the caller is inside
+//! [`block_on_inline`].
+//!
+//! With the `tokio-console` feature, and when built with
+//! `RUSTFLAGS="--cfg tokio_unstable"`, blocking tasks spawned for a chunk are
+//! named after its storage path (`read g/temp/0.1`, `write g/temp/0.1`), so
+//! tokio-console shows which chunk a stuck task belongs to.
+
+use std::any::Any;
+use std::cell::Cell;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+
+use futures::future::{Either, select};
+use tokio::task::JoinHandle;
+
+use crate::error::{ZarrError, ZarrResult};
+
+thread_local! {
+    static INLINE: Cell<bool> = const { Cell::new(false) };
+}
+
+#[cfg(all(feature = "tokio-console", tokio_unstable))]
+thread_local! {
+    /// Name for tasks spawned while a [`named`] future is being polled.
+    static TASK_NAME: std::cell::RefCell<Option<std::sync::Arc<str>>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Give the tasks `future` spawns through [`spawn_blocking`] the name
+/// `name()`.  Without the `tokio-console` feature and `tokio_unstable`, this
+/// returns `future` unchanged and never calls `name`.
+#[cfg(not(all(feature = "tokio-console", tokio_unstable)))]
+pub(crate) fn named<F: Future>(
+    _name: impl FnOnce() -> String,
+    future: F,
+) -> impl Future<Output = F::Output> {
+    future
+}
+
+#[cfg(all(feature = "tokio-console", tokio_unstable))]
+pub(crate) fn named<F: Future>(
+    name: impl FnOnce() -> String,
+    future: F,
+) -> impl Future<Output = F::Output> {
+    Named {
+        name: name().into(),
+        future: Box::pin(future),
+    }
+}
+
+#[cfg(all(feature = "tokio-console", tokio_unstable))]
+struct Named<F> {
+    name: std::sync::Arc<str>,
+    future: std::pin::Pin<Box<F>>,
+}
+
+#[cfg(all(feature = "tokio-console", tokio_unstable))]
+impl<F: Future> Future for Named<F> {
+    type Output = F::Output;
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<F::Output> {
+        struct Restore(Option<std::sync::Arc<str>>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                TASK_NAME.with(|name| *name.borrow_mut() = self.0.take());
+            }
+        }
+        let outer = TASK_NAME.with(|name| name.replace(Some(self.name.clone())));
+        let _restore = Restore(outer);
+        self.future.as_mut().poll(cx)
+    }
+}
+
+/// Drive `future` to completion on the calling thread.  While it runs,
+/// [`spawn_blocking`] calls made from this thread run their closure inline,
+/// so nothing is spawned at all.
+pub fn block_on_inline<F: Future>(future: F) -> F::Output {
+    struct Reset(bool);
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            INLINE.with(|inline| inline.set(self.0));
+        }
+    }
+    let _reset = Reset(INLINE.with(|inline| inline.replace(true)));
+    futures::executor::block_on(future)
+}
+
+/// Run `f` on a thread where blocking is allowed: tokio's blocking pool when
+/// called inside a tokio runtime, otherwise a dedicated thread.  Under
+/// [`block_on_inline`], `f` runs on the calling thread instead.
+///
+/// A panic in `f` resumes in the caller, as if `f` had run inline.
+/// Dropping the returned future before `f` starts keeps it from running at
+/// all, so a cancelled read leaves no detached work behind: under tokio the
+/// task is aborted, and a dedicated thread that starts after the drop exits
+/// without calling `f`.
+pub async fn spawn_blocking<T, F>(f: F) -> ZarrResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    if INLINE.with(Cell::get) {
+        return Ok(f());
+    }
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        let mut task = AbortOnDrop(spawn_blocking_on(f, &handle)?);
+        return match (&mut task.0).await {
+            Ok(value) => Ok(value),
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(e) => Err(ZarrError::Other(format!("Blocking task failed: {e}"))),
+        };
+    }
+    let (tx, rx) = futures::channel::oneshot::channel();
+    std::thread::spawn(move || {
+        if tx.is_canceled() {
+            return;
+        }
+        let _ = tx.send(std::panic::catch_unwind(AssertUnwindSafe(f)));
+    });
+    match rx.await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(panic)) => std::panic::resume_unwind(panic),
+        Err(_) => Err(ZarrError::Other("Blocking task was dropped".into())),
+    }
+}
+
+#[cfg(not(all(feature = "tokio-console", tokio_unstable)))]
+fn spawn_blocking_on<T, F>(f: F, handle: &tokio::runtime::Handle) -> ZarrResult<JoinHandle<T>>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    Ok(handle.spawn_blocking(f))
+}
+
+/// Spawn `f` under the name set by an enclosing [`named`] future, if any.
+#[cfg(all(feature = "tokio-console", tokio_unstable))]
+fn spawn_blocking_on<T, F>(f: F, handle: &tokio::runtime::Handle) -> ZarrResult<JoinHandle<T>>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let Some(name) = TASK_NAME.with(|name| name.borrow().clone()) else {
+        return Ok(handle.spawn_blocking(f));
+    };
+    tokio::task::Builder::new()
+        .name(&name)
+        .spawn_blocking_on(f, handle)
+        .map_err(|e| ZarrError::Other(format!("Failed to spawn task {name}: {e}")))
+}
+
+/// Aborts a blocking task that hasn't started yet when its waiter goes away.
+struct AbortOnDrop<T>(JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// The message a panic was raised with, for error reports.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic payload".into()
+    }
+}
+
+/// Wait for `duration`.
+pub async fn sleep(duration: Duration) {
+    futures_timer::Delay::new(duration).await
+}
+
+/// Run `future`, giving up after `duration`.  Returns `None` on timeout.
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    let future = std::pin::pin!(future);
+    let delay = futures_timer::Delay::new(duration);
+    match select(future, delay).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
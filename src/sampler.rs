@@ -0,0 +1,257 @@
+//! Shuffled window sampling for training loops.
+//!
+//! A [`ChunkSampler`] draws fixed-size windows from random positions in an
+//! array.  Each epoch visits the chunk grid in a fresh random order and
+//! draws [`samples_per_chunk`](ChunkSampler::samples_per_chunk) windows
+//! starting in each chunk, so neighbouring samples share chunks and a
+//! decoded-chunk cache (`chunk_cache_bytes`) serves most of their reads.
+//! The same seed and epoch always give the same samples.
+//!
+//! A [`BatchLoader`] groups the samples into batches and reads the next few
+//! batches while the current one is being consumed.
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use futures::{Stream, StreamExt};
+
+use crate::array::{UnifiedZarrArray, chunk_indices_for_region};
+use crate::error::{ZarrError, ZarrResult};
+use crate::view::ArrayView;
+
+// ---------------------------------------------------------------------------
+// ChunkSampler
+// ---------------------------------------------------------------------------
+
+/// Seeded, epoch-aware sampler of random windows from an array.
+#[derive(Debug, Clone)]
+pub struct ChunkSampler {
+    array: Arc<UnifiedZarrArray>,
+    window: Vec<usize>,
+    seed: u64,
+    samples_per_chunk: usize,
+}
+
+impl ChunkSampler {
+    /// Sample windows of shape `window`, which must fit inside the array.
+    pub fn new(array: Arc<UnifiedZarrArray>, window: &[usize]) -> ZarrResult<Self> {
+        let shape = &array.metadata.shape;
+        if window.len() != shape.len() || window.iter().zip(shape).any(|(&w, &s)| w == 0 || w > s) {
+            return Err(ZarrError::Other(format!(
+                "Window {window:?} does not fit an array of shape {shape:?}"
+            )));
+        }
+        Ok(Self {
+            array,
+            window: window.to_vec(),
+            seed: 0,
+            samples_per_chunk: 1,
+        })
+    }
+
+    /// Seed for the sample order (default 0).
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Windows drawn per chunk each epoch (default 1).
+    pub fn samples_per_chunk(mut self, n: usize) -> Self {
+        self.samples_per_chunk = n;
+        self
+    }
+
+    /// The sampled array.
+    pub fn array(&self) -> &UnifiedZarrArray {
+        &self.array
+    }
+
+    /// Shape of every sample.
+    pub fn window(&self) -> &[usize] {
+        &self.window
+    }
+
+    /// Number of samples in an epoch.
+    pub fn len(&self) -> usize {
+        self.origin_chunks().len() * self.samples_per_chunk
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The sample regions of `epoch`, in visiting order.
+    pub fn epoch(&self, epoch: u64) -> Vec<Vec<Range<usize>>> {
+        let mut rng = SplitMix64::new(self.seed, epoch);
+        let mut chunks = self.origin_chunks();
+        for i in (1..chunks.len()).rev() {
+            chunks.swap(i, rng.below(i + 1));
+        }
+        let shape = &self.array.metadata.shape;
+        let chunk_shape = &self.array.metadata.chunk_shape;
+        let mut samples = Vec::with_capacity(self.len());
+        for chunk in chunks {
+            for _ in 0..self.samples_per_chunk {
+                let region = chunk
+                    .iter()
+                    .zip(chunk_shape)
+                    .zip(shape.iter().zip(&self.window))
+                    .map(|((&c, &cs), (&s, &w))| {
+                        // Origins in this chunk that leave room for the window.
+                        let lo = c * cs;
+                        let hi = (lo + cs).min(s - w + 1);
+                        let start = lo + rng.below(hi - lo);
+                        start..start + w
+                    })
+                    .collect();
+                samples.push(region);
+            }
+        }
+        samples
+    }
+
+    /// Batch the samples `batch_size` at a time.
+    pub fn batches(self, batch_size: usize) -> BatchLoader {
+        let prefetch = self.array.config().read_ahead;
+        BatchLoader {
+            sampler: self,
+            batch_size: batch_size.max(1),
+            prefetch,
+            drop_last: false,
+        }
+    }
+
+    /// Chunks containing at least one valid window origin.
+    fn origin_chunks(&self) -> Vec<Vec<usize>> {
+        let origins: Vec<Range<usize>> = self
+            .array
+            .metadata
+            .shape
+            .iter()
+            .zip(&self.window)
+            .map(|(&s, &w)| 0..s - w + 1)
+            .collect();
+        chunk_indices_for_region(&self.array.metadata.chunk_shape, &origins)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// BatchLoader
+// ---------------------------------------------------------------------------
+
+/// Batches of samples from a [`ChunkSampler`], read ahead of the consumer.
+#[derive(Debug, Clone)]
+pub struct BatchLoader {
+    sampler: ChunkSampler,
+    batch_size: usize,
+    prefetch: usize,
+    drop_last: bool,
+}
+
+/// One batch of windows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batch {
+    /// The region each window was read from.
+    pub regions: Vec<Vec<Range<usize>>>,
+    /// `[windows, window...]`.
+    pub shape: Vec<usize>,
+    /// The windows one after another, each in row-major order.
+    pub values: Vec<f64>,
+}
+
+impl BatchLoader {
+    /// Batches read ahead of the one being consumed (defaults to the
+    /// array's [`read_ahead`](crate::config::SimplezarrConfig::read_ahead)).
+    pub fn prefetch(mut self, batches: usize) -> Self {
+        self.prefetch = batches;
+        self
+    }
+
+    /// Skip a final batch smaller than the batch size.
+    pub fn drop_last(mut self, drop_last: bool) -> Self {
+        self.drop_last = drop_last;
+        self
+    }
+
+    pub fn sampler(&self) -> &ChunkSampler {
+        &self.sampler
+    }
+
+    /// Number of batches in an epoch.
+    pub fn len(&self) -> usize {
+        let samples = self.sampler.len();
+        if self.drop_last {
+            samples / self.batch_size
+        } else {
+            samples.div_ceil(self.batch_size)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The batches of `epoch`, in order.
+    pub fn epoch(&self, epoch: u64) -> impl Stream<Item = ZarrResult<Batch>> + '_ {
+        let mut batches: Vec<Vec<Vec<Range<usize>>>> = self
+            .sampler
+            .epoch(epoch)
+            .chunks(self.batch_size)
+            .map(<[_]>::to_vec)
+            .collect();
+        batches.truncate(self.len());
+        let view = ArrayView::new(self.sampler.array.clone());
+        futures::stream::iter(batches)
+            .map(move |regions| {
+                let view = view.clone();
+                async move { self.read_batch(&view, regions).await }
+            })
+            .buffered(self.prefetch.saturating_add(1))
+    }
+
+    async fn read_batch(
+        &self,
+        view: &ArrayView,
+        regions: Vec<Vec<Range<usize>>>,
+    ) -> ZarrResult<Batch> {
+        let windows =
+            futures::future::try_join_all(regions.iter().map(|region| view.read_region(region)))
+                .await?;
+        let mut shape = vec![regions.len()];
+        shape.extend_from_slice(&self.sampler.window);
+        Ok(Batch {
+            regions,
+            shape,
+            values: windows.concat(),
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Random numbers
+// ---------------------------------------------------------------------------
+
+/// SplitMix64: small, fast and stable across releases, so a seed keeps
+/// naming the same samples.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn new(seed: u64, epoch: u64) -> Self {
+        let mut rng = Self(seed);
+        rng.0 ^= rng.next().wrapping_add(epoch);
+        rng
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n`; `n` must be non-zero.
+    fn below(&mut self, n: usize) -> usize {
+        ((u128::from(self.next()) * n as u128) >> 64) as usize
+    }
+}
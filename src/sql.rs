@@ -0,0 +1,431 @@
+//! SQL over Zarr groups with DataFusion.
+//!
+//! [`ZarrTableProvider`] presents arrays of one 1-D or 2-D shape as the
+//! columns of a table, one row per element, next to `Int64` index columns
+//! holding each row's position along every dimension.  Index columns take
+//! the arrays' dimension names (xarray's `_ARRAY_DIMENSIONS`), or `dim_0`
+//! and `dim_1` when there are none or they clash with an array name.
+//!
+//! ```ignore
+//! let table = ZarrTableProvider::new(&group, &["temperature", "humidity"])?;
+//! let ctx = SessionContext::new();
+//! ctx.register_table("weather", Arc::new(table))?;
+//! let df = ctx
+//!     .sql("SELECT avg(temperature) FROM weather WHERE time BETWEEN 100 AND 199")
+//!     .await?;
+//! ```
+//!
+//! Comparisons of an index column with a literal (`=`, `<`, `<=`, `>`,
+//! `>=`, `BETWEEN`, joined by `AND`) narrow the region that is read, so
+//! only the chunks it touches are fetched; DataFusion still applies every
+//! filter to the rows read.  Only projected arrays are read.  Element types
+//! map to the Arrow type of the same name; strings become `Utf8`, byte and
+//! raw elements `Binary`, and complex arrays are rejected.  Array columns
+//! are nullable, for elements stored as null.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::arrow::array::{
+    ArrayRef, BinaryArray, BooleanArray, Float16Array, Float32Array, Float64Array, Int8Array,
+    Int16Array, Int32Array, Int64Array, StringArray, UInt8Array, UInt16Array, UInt32Array,
+    UInt64Array,
+};
+use datafusion::arrow::compute::take;
+use datafusion::arrow::datatypes::{DataType as ArrowType, Field, Schema, SchemaRef};
+use datafusion::arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use datafusion::catalog::{Session, TableProvider};
+use datafusion::datasource::memory::MemorySourceConfig;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::{
+    Between, BinaryExpr, Expr, Operator, TableProviderFilterPushDown, TableType,
+};
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::scalar::ScalarValue;
+
+use crate::array::{
+    NullableColumn, UnifiedZarrArray, complex_column_error, index_column_names, table_shape,
+};
+use crate::error::{ZarrError, ZarrResult};
+use crate::group::UnifiedZarrGroup;
+use crate::types::{ArrayOrder, DataType, ZarrValue, ZarrVectorValue};
+
+/// Rows per record batch unless [`batch_rows`](ZarrTableProvider::batch_rows)
+/// says otherwise.
+pub const DEFAULT_BATCH_ROWS: usize = 8192;
+
+// ---------------------------------------------------------------------------
+// ZarrTableProvider
+// ---------------------------------------------------------------------------
+
+/// A DataFusion table over arrays sharing one 1-D or 2-D shape.
+#[derive(Clone)]
+pub struct ZarrTableProvider {
+    arrays: Vec<(String, UnifiedZarrArray)>,
+    shape: Vec<usize>,
+    index_columns: Vec<String>,
+    schema: SchemaRef,
+    batch_rows: usize,
+}
+
+impl fmt::Debug for ZarrTableProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZarrTableProvider")
+            .field("shape", &self.shape)
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl ZarrTableProvider {
+    /// A table of `group`'s arrays `names`, in that order.
+    pub fn new(group: &UnifiedZarrGroup, names: &[&str]) -> ZarrResult<Self> {
+        let arrays = names
+            .iter()
+            .map(|&name| {
+                let array = group
+                    .get_array(name)
+                    .ok_or_else(|| ZarrError::NotFound(format!("No array {name} in group")))?;
+                Ok((name.to_string(), array.clone()))
+            })
+            .collect::<ZarrResult<Vec<_>>>()?;
+        Self::from_arrays(arrays)
+    }
+
+    /// A table with one column per `(name, array)`.
+    pub fn from_arrays(
+        arrays: impl IntoIterator<Item = (String, UnifiedZarrArray)>,
+    ) -> ZarrResult<Self> {
+        let arrays: Vec<_> = arrays.into_iter().collect();
+        let named: Vec<(&str, &UnifiedZarrArray)> =
+            arrays.iter().map(|(name, a)| (name.as_str(), a)).collect();
+        let shape = table_shape("table", &named)?.to_vec();
+        let (first_name, first) = named[0];
+        if !matches!(shape.len(), 1 | 2) {
+            return Err(ZarrError::Other(format!(
+                "Array {first_name} is {}-dimensional; tables hold 1-D or 2-D arrays",
+                shape.len()
+            )));
+        }
+        let names: Vec<&str> = named.iter().map(|&(name, _)| name).collect();
+        let index_columns = index_column_names(first, &names);
+
+        let mut fields: Vec<Field> = index_columns
+            .iter()
+            .map(|name| Field::new(name, ArrowType::Int64, false))
+            .collect();
+        for (name, array) in &arrays {
+            fields.push(Field::new(
+                name,
+                arrow_type(array.metadata.data_type)?,
+                true,
+            ));
+        }
+        let mut seen = HashSet::new();
+        if let Some(field) = fields.iter().find(|f| !seen.insert(f.name().clone())) {
+            return Err(ZarrError::Other(format!(
+                "Column {} appears twice",
+                field.name()
+            )));
+        }
+        let schema = Arc::new(Schema::new(fields));
+        Ok(Self {
+            arrays,
+            shape,
+            index_columns,
+            schema,
+            batch_rows: DEFAULT_BATCH_ROWS,
+        })
+    }
+
+    /// Split scans into record batches of at most `rows` rows.
+    pub fn batch_rows(mut self, rows: usize) -> Self {
+        self.batch_rows = rows.max(1);
+        self
+    }
+
+    /// The index column of each dimension.
+    pub fn index_columns(&self) -> &[String] {
+        &self.index_columns
+    }
+
+    /// The region selected by `filters`: the whole table narrowed by every
+    /// index-column comparison among them.
+    pub fn region_for(&self, filters: &[Expr]) -> Vec<Range<usize>> {
+        let mut region: Vec<_> = self.shape.iter().map(|&n| 0..n).collect();
+        for filter in filters {
+            self.narrow(&mut region, filter);
+        }
+        region
+    }
+
+    /// Narrow `region` by the index bounds in `filter`; whether it has any.
+    fn narrow(&self, region: &mut [Range<usize>], filter: &Expr) -> bool {
+        match filter {
+            Expr::BinaryExpr(BinaryExpr {
+                left,
+                op: Operator::And,
+                right,
+            }) => {
+                let left = self.narrow(region, left);
+                self.narrow(region, right) | left
+            }
+            Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+                let (column, op, value) = match (self.axis(left), self.axis(right)) {
+                    (Some(axis), None) => (axis, *op, right),
+                    (None, Some(axis)) => match op.swap() {
+                        Some(op) => (axis, op, left),
+                        None => return false,
+                    },
+                    _ => return false,
+                };
+                let Some(value) = literal(value) else {
+                    return false;
+                };
+                bound(&mut region[column], op, value)
+            }
+            Expr::Between(Between {
+                expr,
+                negated: false,
+                low,
+                high,
+            }) => {
+                let (Some(axis), Some(low), Some(high)) =
+                    (self.axis(expr), literal(low), literal(high))
+                else {
+                    return false;
+                };
+                bound(&mut region[axis], Operator::GtEq, low);
+                bound(&mut region[axis], Operator::LtEq, high)
+            }
+            _ => false,
+        }
+    }
+
+    /// The dimension whose index column `expr` is, looking through numeric
+    /// casts, which keep the order of indices.
+    fn axis(&self, expr: &Expr) -> Option<usize> {
+        match expr {
+            Expr::Column(column) => self.index_columns.iter().position(|c| *c == column.name),
+            Expr::Cast(cast) if cast.field.data_type().is_numeric() => self.axis(&cast.expr),
+            Expr::TryCast(cast) if cast.field.data_type().is_numeric() => self.axis(&cast.expr),
+            _ => None,
+        }
+    }
+
+    /// Read `region` of the projected columns as record batches.
+    async fn read(
+        &self,
+        region: &[Range<usize>],
+        projection: &[usize],
+    ) -> ZarrResult<Vec<RecordBatch>> {
+        let extent: Vec<usize> = region.iter().map(|r| r.len()).collect();
+        let rows: usize = extent.iter().product();
+        let extent = extent.as_slice();
+        let reads = projection.iter().map(|&column| async move {
+            let Some(array) = column.checked_sub(self.index_columns.len()) else {
+                return Ok(index_column(region, extent, column));
+            };
+            let values = self.arrays[array].1.read_region_value(region).await?;
+            let array = arrow_array(values.data)?;
+            match values.order {
+                ArrayOrder::F if extent.len() == 2 => {
+                    // Row-major position k holds element (k / n1, k % n1).
+                    let indices: UInt64Array = (0..rows)
+                        .map(|k| ((k % extent[1]) * extent[0] + k / extent[1]) as u64)
+                        .collect();
+                    take(&array, &indices, None)
+                        .map_err(|e| ZarrError::TypeConversion(e.to_string()))
+                }
+                _ => Ok(array),
+            }
+        });
+        let columns = futures::future::try_join_all(reads).await?;
+
+        let schema = Arc::new(
+            self.schema
+                .project(projection)
+                .map_err(|e| ZarrError::Other(e.to_string()))?,
+        );
+        let options = RecordBatchOptions::new().with_row_count(Some(rows));
+        let batch = RecordBatch::try_new_with_options(schema, columns, &options)
+            .map_err(|e| ZarrError::TypeConversion(e.to_string()))?;
+        Ok((0..rows)
+            .step_by(self.batch_rows)
+            .map(|offset| batch.slice(offset, self.batch_rows.min(rows - offset)))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl TableProvider for ZarrTableProvider {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> Result<Vec<TableProviderFilterPushDown>> {
+        Ok(filters
+            .iter()
+            .map(|filter| {
+                let mut region = self.region_for(&[]);
+                if self.narrow(&mut region, filter) {
+                    TableProviderFilterPushDown::Inexact
+                } else {
+                    TableProviderFilterPushDown::Unsupported
+                }
+            })
+            .collect())
+    }
+
+    async fn scan(
+        &self,
+        _state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let mut region = self.region_for(filters);
+        // Without filters, the first `limit` rows are the first rows read.
+        if let (true, Some(limit)) = (filters.is_empty(), limit) {
+            let row_len: usize = region[1..].iter().map(|r| r.len()).product();
+            let keep = limit.div_ceil(row_len.max(1));
+            region[0].end = region[0].end.min(region[0].start + keep);
+        }
+        let projection = match projection {
+            Some(projection) => projection.clone(),
+            None => (0..self.schema.fields().len()).collect(),
+        };
+        let batches = self
+            .read(&region, &projection)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let schema = Arc::new(self.schema.project(&projection)?);
+        Ok(MemorySourceConfig::try_new_exec(&[batches], schema, None)?)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Index bounds
+// ---------------------------------------------------------------------------
+
+/// A numeric literal as `f64`.
+fn literal(expr: &Expr) -> Option<f64> {
+    let Expr::Literal(value, _) = expr else {
+        return None;
+    };
+    match value.cast_to(&ArrowType::Float64).ok()? {
+        ScalarValue::Float64(Some(value)) if !value.is_nan() => Some(value),
+        _ => None,
+    }
+}
+
+/// Narrow `range` to the indices `i` with `i op value`; whether `op` is a
+/// comparison that can.
+fn bound(range: &mut Range<usize>, op: Operator, value: f64) -> bool {
+    // Clamped into the range, so out-of-range literals select nothing.
+    let clamp = |index: f64| index.clamp(range.start as f64, range.end as f64) as usize;
+    let (start, end) = match op {
+        Operator::Eq if value.fract() == 0.0 => (clamp(value), clamp(value + 1.0)),
+        Operator::Eq => (range.start, range.start),
+        Operator::Gt => (clamp(value.floor() + 1.0), range.end),
+        Operator::GtEq => (clamp(value.ceil()), range.end),
+        Operator::Lt => (range.start, clamp(value.ceil())),
+        Operator::LtEq => (range.start, clamp(value.floor() + 1.0)),
+        _ => return false,
+    };
+    *range = start..end.max(start);
+    true
+}
+
+// ---------------------------------------------------------------------------
+// Arrow conversion
+// ---------------------------------------------------------------------------
+
+fn arrow_type(dtype: DataType) -> ZarrResult<ArrowType> {
+    Ok(match dtype {
+        DataType::Bool => ArrowType::Boolean,
+        DataType::Int8 => ArrowType::Int8,
+        DataType::Int16 => ArrowType::Int16,
+        DataType::Int32 => ArrowType::Int32,
+        DataType::Int64 => ArrowType::Int64,
+        DataType::UInt8 => ArrowType::UInt8,
+        DataType::UInt16 => ArrowType::UInt16,
+        DataType::UInt32 => ArrowType::UInt32,
+        DataType::UInt64 => ArrowType::UInt64,
+        DataType::Float16 => ArrowType::Float16,
+        DataType::Float32 => ArrowType::Float32,
+        DataType::Float64 => ArrowType::Float64,
+        DataType::String => ArrowType::Utf8,
+        DataType::Bytes | DataType::Raw(_) => ArrowType::Binary,
+        DataType::Complex64 | DataType::Complex128 => {
+            return Err(ZarrError::TypeConversion(format!(
+                "{dtype} arrays have no Arrow column type"
+            )));
+        }
+    })
+}
+
+fn arrow_array(values: ZarrVectorValue) -> ZarrResult<ArrayRef> {
+    Ok(match values {
+        ZarrVectorValue::VBool(v) => Arc::new(BooleanArray::from(v)),
+        ZarrVectorValue::VInt8(v) => Arc::new(Int8Array::from(v)),
+        ZarrVectorValue::VInt16(v) => Arc::new(Int16Array::from(v)),
+        ZarrVectorValue::VInt32(v) => Arc::new(Int32Array::from(v)),
+        ZarrVectorValue::VInt64(v) => Arc::new(Int64Array::from(v)),
+        ZarrVectorValue::VUInt8(v) => Arc::new(UInt8Array::from(v)),
+        ZarrVectorValue::VUInt16(v) => Arc::new(UInt16Array::from(v)),
+        ZarrVectorValue::VUInt32(v) => Arc::new(UInt32Array::from(v)),
+        ZarrVectorValue::VUInt64(v) => Arc::new(UInt64Array::from(v)),
+        ZarrVectorValue::VFloat16(v) => Arc::new(Float16Array::from(v)),
+        ZarrVectorValue::VFloat32(v) => Arc::new(Float32Array::from(v)),
+        ZarrVectorValue::VFloat64(v) => Arc::new(Float64Array::from(v)),
+        ZarrVectorValue::VString(v) => Arc::new(StringArray::from(v)),
+        ZarrVectorValue::VBytes(v) => Arc::new(BinaryArray::from_iter_values(v)),
+        ZarrVectorValue::VWithNulls(dtype, v) => nullable_array(dtype, &v)?,
+        ZarrVectorValue::VComplex64(_) | ZarrVectorValue::VComplex128(_) => {
+            return Err(complex_column_error());
+        }
+    })
+}
+
+/// A column of `dtype` values with nulls.
+fn nullable_array(dtype: DataType, values: &[Option<ZarrValue>]) -> ZarrResult<ArrayRef> {
+    Ok(match NullableColumn::new(dtype, values)? {
+        NullableColumn::Bool(v) => Arc::new(BooleanArray::from(v)),
+        NullableColumn::Int8(v) => Arc::new(Int8Array::from(v)),
+        NullableColumn::Int16(v) => Arc::new(Int16Array::from(v)),
+        NullableColumn::Int32(v) => Arc::new(Int32Array::from(v)),
+        NullableColumn::Int64(v) => Arc::new(Int64Array::from(v)),
+        NullableColumn::UInt8(v) => Arc::new(UInt8Array::from(v)),
+        NullableColumn::UInt16(v) => Arc::new(UInt16Array::from(v)),
+        NullableColumn::UInt32(v) => Arc::new(UInt32Array::from(v)),
+        NullableColumn::UInt64(v) => Arc::new(UInt64Array::from(v)),
+        NullableColumn::Float16(v) => Arc::new(Float16Array::from(v)),
+        NullableColumn::Float32(v) => Arc::new(Float32Array::from(v)),
+        NullableColumn::Float64(v) => Arc::new(Float64Array::from(v)),
+        NullableColumn::String(v) => Arc::new(StringArray::from(v)),
+        NullableColumn::Bytes(v) => Arc::new(BinaryArray::from(v)),
+    })
+}
+
+/// Index column `axis` for the rows of `region`, in row-major order.
+fn index_column(region: &[Range<usize>], extent: &[usize], axis: usize) -> ArrayRef {
+    let inner: usize = extent[axis + 1..].iter().product();
+    let rows: usize = extent.iter().product();
+    let start = region[axis].start;
+    Arc::new(
+        (0..rows)
+            .map(|k| (start + k / inner % extent[axis]) as i64)
+            .collect::<Int64Array>(),
+    )
+}
@@ -0,0 +1,188 @@
+//! Opening the Zarr assets of STAC items.
+//!
+//! Catalogs built on [STAC](https://stacspec.org) point at data through an
+//! item's (or collection's) `assets`, each with an `href` and a media
+//! `type`.  [`ZarrAsset::from_item`] picks out a Zarr asset and
+//! [`open_group`](ZarrAsset::open_group) or
+//! [`open_array`](ZarrAsset::open_array) opens it through
+//! [`ObjectStoreBackend::from_url`]:
+//!
+//! ```json
+//! {
+//!   "type": "Feature",
+//!   "assets": {
+//!     "zarr-s3": {
+//!       "href": "s3://bucket/era5.zarr",
+//!       "type": "application/vnd+zarr",
+//!       "xarray:storage_options": {"anon": true}
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! The `anon`, `endpoint_url`, `region_name` and `headers` entries of the
+//! xarray extension's `xarray:storage_options` (or its `client_kwargs`)
+//! become [`UrlOptions`].  Relative hrefs are resolved against the item's
+//! `self` link.
+
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::array::UnifiedZarrArray;
+use crate::config::{self, SimplezarrConfig};
+use crate::error::{ZarrError, ZarrResult};
+use crate::group::UnifiedZarrGroup;
+use crate::store::{ObjectStoreBackend, UrlOptions};
+use crate::v2;
+
+/// Media types that mark an asset as Zarr.
+const ZARR_MEDIA_TYPES: [&str; 3] = [
+    "application/vnd+zarr",
+    "application/vnd.zarr",
+    "application/x-zarr",
+];
+
+// ---------------------------------------------------------------------------
+// ZarrAsset
+// ---------------------------------------------------------------------------
+
+/// A Zarr asset of a STAC item: where it is and how to reach it.
+#[derive(Debug, Clone)]
+pub struct ZarrAsset {
+    /// The asset's key in `assets`.
+    pub key: String,
+    /// Absolute URL of the Zarr store.
+    pub href: String,
+    pub options: UrlOptions,
+}
+
+impl ZarrAsset {
+    /// The asset called `key` in `item`'s assets, or with `None` the first
+    /// (by key) whose media type is Zarr, or failing that whose href ends
+    /// in `.zarr`.
+    pub fn from_item(item: &Value, key: Option<&str>) -> ZarrResult<Self> {
+        let assets = item
+            .get("assets")
+            .and_then(Value::as_object)
+            .ok_or_else(|| ZarrError::Metadata("STAC item has no assets".into()))?;
+        let (key, asset) = match key {
+            Some(key) => assets
+                .get_key_value(key)
+                .ok_or_else(|| ZarrError::NotFound(format!("No asset {key} in STAC item")))?,
+            None => {
+                let mut assets: Vec<_> = assets.iter().collect();
+                assets.sort_by_key(|(key, _)| key.as_str());
+                let is_zarr = |asset: &Value| {
+                    str_field(asset, "type").is_some_and(|t| ZARR_MEDIA_TYPES.contains(&t))
+                };
+                let has_zarr_href = |asset: &Value| {
+                    str_field(asset, "href")
+                        .is_some_and(|h| h.trim_end_matches('/').ends_with(".zarr"))
+                };
+                assets
+                    .iter()
+                    .find(|(_, asset)| is_zarr(asset))
+                    .or_else(|| assets.iter().find(|(_, asset)| has_zarr_href(asset)))
+                    .copied()
+                    .ok_or_else(|| ZarrError::NotFound("No Zarr asset in STAC item".into()))?
+            }
+        };
+        let href = str_field(asset, "href")
+            .ok_or_else(|| ZarrError::Metadata(format!("STAC asset {key} has no href")))?;
+        Ok(Self {
+            key: key.clone(),
+            href: resolve_href(item, href)?,
+            options: url_options(asset.get("xarray:storage_options")),
+        })
+    }
+
+    /// The store at [`href`](Self::href).
+    pub fn store(&self) -> ZarrResult<ObjectStoreBackend> {
+        ObjectStoreBackend::from_url(&self.href, &self.options)
+    }
+
+    /// Open the asset as a V2 group.  `arrays` are opened when the group
+    /// has no consolidated metadata.
+    ///
+    /// Uses the process-wide [`config::global`] configuration.
+    pub async fn open_group(&self, arrays: &[&str]) -> ZarrResult<UnifiedZarrGroup> {
+        self.open_group_with_config(arrays, config::global()).await
+    }
+
+    /// [`open_group`](Self::open_group) with an explicit configuration.
+    pub async fn open_group_with_config(
+        &self,
+        arrays: &[&str],
+        config: Arc<SimplezarrConfig>,
+    ) -> ZarrResult<UnifiedZarrGroup> {
+        v2::open_group_with_config(Arc::new(self.store()?), "", arrays, config).await
+    }
+
+    /// Open the asset as a single V2 array.
+    ///
+    /// Uses the process-wide [`config::global`] configuration.
+    pub async fn open_array(&self) -> ZarrResult<UnifiedZarrArray> {
+        self.open_array_with_config(config::global()).await
+    }
+
+    /// [`open_array`](Self::open_array) with an explicit configuration.
+    pub async fn open_array_with_config(
+        &self,
+        config: Arc<SimplezarrConfig>,
+    ) -> ZarrResult<UnifiedZarrArray> {
+        v2::open_with_config(Arc::new(self.store()?), "", config).await
+    }
+}
+
+/// `href`, made absolute against the item's `self` link if it is relative.
+fn resolve_href(item: &Value, href: &str) -> ZarrResult<String> {
+    if href.contains("://") {
+        return Ok(href.to_string());
+    }
+    let base = item
+        .get("links")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .find(|link| str_field(link, "rel") == Some("self"))
+        .and_then(|link| str_field(link, "href"))
+        .ok_or_else(|| {
+            ZarrError::Metadata(format!(
+                "Relative STAC href {href} needs a self link to resolve against"
+            ))
+        })?;
+    let dir = &base[..base.rfind('/').map_or(0, |i| i + 1)];
+    let href = href.strip_prefix("./").unwrap_or(href);
+    Ok(format!("{dir}{href}"))
+}
+
+fn str_field<'a>(value: &'a Value, name: &str) -> Option<&'a str> {
+    value.get(name).and_then(Value::as_str)
+}
+
+/// [`UrlOptions`] from fsspec-style `xarray:storage_options`.
+fn url_options(storage_options: Option<&Value>) -> UrlOptions {
+    let Some(storage_options) = storage_options else {
+        return UrlOptions::default();
+    };
+    let get = |name: &str| {
+        storage_options
+            .get(name)
+            .or_else(|| storage_options.pointer(&format!("/client_kwargs/{name}")))
+    };
+    let string = |name: &str| get(name).and_then(Value::as_str).map(str::to_string);
+    let headers = get("headers")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+        .collect();
+    UrlOptions {
+        anonymous: get("anon").and_then(Value::as_bool).unwrap_or(false),
+        endpoint: string("endpoint_url"),
+        region: string("region_name"),
+        user_agent: None,
+        headers,
+    }
+}
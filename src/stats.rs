@@ -0,0 +1,493 @@
+//! Streaming histograms and quantiles.
+//!
+//! Built on [`reduce`](UnifiedZarrArray::reduce), these summarise an array
+//! one chunk at a time, for choosing colour scales or bins without loading
+//! it.  [`histogram`](UnifiedZarrArray::histogram) counts values into equal
+//! bins; [`quantiles`](UnifiedZarrArray::quantiles) estimates quantiles from
+//! a [`TDigest`], whose error is smallest in the tails.  NaN and null
+//! elements are skipped; string, byte, raw and complex arrays are rejected.
+//!
+//! [`keepbits`](UnifiedZarrArray::keepbits) measures how many mantissa bits
+//! of a float array carry real information, from its [`BitInformation`], to
+//! choose the precision of a [`BitRoundCodec`](crate::codecs::bitround::BitRoundCodec).
+
+use std::f64::consts::PI;
+use std::ops::RangeInclusive;
+
+use crate::array::{ArrayData, UnifiedZarrArray};
+use crate::codecs::bitround::mantissa_bits;
+use crate::error::{ZarrError, ZarrResult};
+use crate::types::{ArrayOrder, DataType, ZarrVectorValue};
+
+/// Compression of the digests behind [`UnifiedZarrArray::quantiles`]: at
+/// most about this many centroids.
+pub const DEFAULT_COMPRESSION: f64 = 100.0;
+
+// ---------------------------------------------------------------------------
+// Histogram
+// ---------------------------------------------------------------------------
+
+/// Counts of values in equal-width bins.
+///
+/// Bin `i` holds values in `edges[i]..edges[i + 1]`; the last bin also
+/// holds its upper edge, as in numpy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub edges: Vec<f64>,
+    pub counts: Vec<u64>,
+}
+
+impl Histogram {
+    /// An empty histogram of `bins` bins spanning `range`.
+    pub fn new(bins: usize, range: RangeInclusive<f64>) -> Self {
+        let (lo, hi) = range.into_inner();
+        let bins = bins.max(1);
+        let edges = (0..=bins)
+            .map(|i| lo + (hi - lo) * i as f64 / bins as f64)
+            .collect();
+        Self {
+            edges,
+            counts: vec![0; bins],
+        }
+    }
+
+    /// Count `value`; NaN and values outside the edges are ignored.
+    pub fn add(&mut self, value: f64) {
+        let (lo, hi) = (self.edges[0], self.edges[self.edges.len() - 1]);
+        if !(lo..=hi).contains(&value) {
+            return;
+        }
+        let bins = self.counts.len();
+        let bin = if hi > lo {
+            (((value - lo) / (hi - lo)) * bins as f64) as usize
+        } else {
+            0
+        };
+        self.counts[bin.min(bins - 1)] += 1;
+    }
+
+    /// Add the counts of `other`, which must have the same edges.
+    pub fn merge(&mut self, other: &Histogram) {
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+    }
+
+    /// Values counted.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// TDigest
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// A merging t-digest (Dunning & Ertl): a sketch of a distribution in
+/// about `compression` centroids, small near the extremes so tail quantiles
+/// stay accurate.  Digests of parts merge into a digest of the whole.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TDigest {
+    compression: f64,
+    centroids: Vec<Centroid>,
+    min: f64,
+    max: f64,
+}
+
+impl Default for TDigest {
+    fn default() -> Self {
+        Self::new(DEFAULT_COMPRESSION)
+    }
+}
+
+impl TDigest {
+    pub fn new(compression: f64) -> Self {
+        Self {
+            compression: compression.max(1.0),
+            centroids: Vec::new(),
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Number of values added.
+    pub fn count(&self) -> u64 {
+        self.centroids.iter().map(|c| c.weight).sum::<f64>() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.centroids.is_empty()
+    }
+
+    /// Add `values`, skipping NaN.
+    pub fn add(&mut self, values: impl IntoIterator<Item = f64>) {
+        let mut points = std::mem::take(&mut self.centroids);
+        for value in values.into_iter().filter(|v| !v.is_nan()) {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+            points.push(Centroid {
+                mean: value,
+                weight: 1.0,
+            });
+        }
+        self.compress(points);
+    }
+
+    /// Add everything `other` has seen.
+    pub fn merge(&mut self, other: &TDigest) {
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        let mut points = std::mem::take(&mut self.centroids);
+        points.extend_from_slice(&other.centroids);
+        self.compress(points);
+    }
+
+    /// Merge sorted `points` greedily into centroids no wider than one unit
+    /// of the `k1` scale function, `compression / 2π · asin(2q - 1)`.
+    fn compress(&mut self, mut points: Vec<Centroid>) {
+        points.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+        let total: f64 = points.iter().map(|c| c.weight).sum();
+        let compression = self.compression;
+        let k = |q: f64| compression / (2.0 * PI) * (2.0 * q - 1.0).asin();
+        let k_inv = |k: f64| ((k * 2.0 * PI / compression).sin() + 1.0) / 2.0;
+        let limit = |before: f64| total * k_inv((k(before / total) + 1.0).min(k(1.0)));
+
+        let mut points = points.into_iter();
+        let Some(mut current) = points.next() else {
+            return;
+        };
+        let mut before = 0.0;
+        let mut weight_limit = limit(before);
+        for point in points {
+            if before + current.weight + point.weight <= weight_limit {
+                let weight = current.weight + point.weight;
+                current.mean += (point.mean - current.mean) * point.weight / weight;
+                current.weight = weight;
+            } else {
+                before += current.weight;
+                self.centroids.push(current);
+                weight_limit = limit(before);
+                current = point;
+            }
+        }
+        self.centroids.push(current);
+    }
+
+    /// The estimated `q` quantile, `0 <= q <= 1`; NaN for an empty digest.
+    ///
+    /// Interpolates linearly between centroid centres, and between the
+    /// extreme centroids and the exact minimum and maximum.
+    pub fn quantile(&self, q: f64) -> f64 {
+        if self.centroids.is_empty() || q.is_nan() {
+            return f64::NAN;
+        }
+        let total: f64 = self.centroids.iter().map(|c| c.weight).sum();
+        let target = q.clamp(0.0, 1.0) * total;
+        // Interpolation knots: (cumulative weight, value).
+        let mut previous = (0.0, self.min);
+        let mut cumulative = 0.0;
+        for centroid in &self.centroids {
+            let centre = cumulative + centroid.weight / 2.0;
+            if target < centre {
+                return interpolate(previous, (centre, centroid.mean), target);
+            }
+            previous = (centre, centroid.mean);
+            cumulative += centroid.weight;
+        }
+        interpolate(previous, (total, self.max), target)
+    }
+}
+
+fn interpolate((x0, y0): (f64, f64), (x1, y1): (f64, f64), x: f64) -> f64 {
+    if x1 <= x0 {
+        return y1;
+    }
+    y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+}
+
+// ---------------------------------------------------------------------------
+// Bit information
+// ---------------------------------------------------------------------------
+
+/// How much each bit of a float tells about the same bit of its neighbour,
+/// following Klöwer et al. (2021), "Compressing atmospheric data into its
+/// real information content".
+///
+/// Bits that are random from one element to the next carry no real
+/// information and can be rounded away with
+/// [`BitRoundCodec`](crate::codecs::bitround::BitRoundCodec).  Neighbours
+/// are counted pair by pair, so the counts of separate chunks merge into
+/// those of the whole array.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitInformation {
+    dtype: DataType,
+    /// Counts of neighbouring bit pairs `00`, `01`, `10` and `11` for each
+    /// bit, sign bit first.
+    counts: Vec<[u64; 4]>,
+}
+
+impl BitInformation {
+    /// No pairs counted yet, for floats of `dtype`.
+    pub fn new(dtype: DataType) -> ZarrResult<Self> {
+        let bytes = dtype
+            .byte_size()
+            .filter(|_| mantissa_bits(dtype).is_some())
+            .ok_or_else(|| {
+                ZarrError::TypeConversion(format!("Bit information needs floats, got {dtype}"))
+            })?;
+        Ok(Self {
+            dtype,
+            counts: vec![[0; 4]; bytes * 8],
+        })
+    }
+
+    /// Count the pairs of neighbours along the fastest-varying dimension
+    /// of `values`: the last in C order, the first in F order.  Values of
+    /// another element type are ignored.
+    pub fn add(&mut self, values: &ArrayData<ZarrVectorValue>) {
+        let run = match values.order {
+            ArrayOrder::C => values.shape.last(),
+            ArrayOrder::F => values.shape.first(),
+        };
+        let run = run.copied().unwrap_or(1);
+        let bits: Vec<u64> = match (&values.data, self.dtype) {
+            (ZarrVectorValue::VFloat16(v), DataType::Float16) => {
+                v.iter().map(|x| x.to_bits() as u64).collect()
+            }
+            (ZarrVectorValue::VFloat32(v), DataType::Float32) => {
+                v.iter().map(|x| x.to_bits() as u64).collect()
+            }
+            (ZarrVectorValue::VFloat64(v), DataType::Float64) => {
+                v.iter().map(|x| x.to_bits()).collect()
+            }
+            _ => return,
+        };
+        let width = self.counts.len();
+        for (i, pair) in bits.windows(2).enumerate() {
+            // Skip pairs that wrap from the end of one row to the next.
+            if (i + 1) % run == 0 {
+                continue;
+            }
+            for (bit, counts) in self.counts.iter_mut().enumerate() {
+                let shift = width - 1 - bit;
+                let (a, b) = ((pair[0] >> shift) & 1, (pair[1] >> shift) & 1);
+                counts[(2 * a + b) as usize] += 1;
+            }
+        }
+    }
+
+    /// Add the counts of `other`, which must be for the same type.
+    pub fn merge(&mut self, other: &BitInformation) {
+        for (counts, other) in self.counts.iter_mut().zip(&other.counts) {
+            for (count, other) in counts.iter_mut().zip(other) {
+                *count += other;
+            }
+        }
+    }
+
+    /// Neighbouring pairs counted.
+    pub fn pairs(&self) -> u64 {
+        self.counts.first().map_or(0, |c| c.iter().sum())
+    }
+
+    /// The mutual information, in bits, between each bit of an element and
+    /// the same bit of its neighbour, sign bit first.  Information a random
+    /// bit could show by chance in this many pairs, at 99% confidence, is
+    /// set to zero.
+    pub fn information(&self) -> Vec<f64> {
+        let pairs = self.pairs();
+        if pairs == 0 {
+            return vec![0.0; self.counts.len()];
+        }
+        let noise = noise_information(pairs);
+        self.counts
+            .iter()
+            .map(|counts| {
+                let p = counts.map(|c| c as f64 / pairs as f64);
+                let first = [p[0] + p[1], p[2] + p[3]];
+                let second = [p[0] + p[2], p[1] + p[3]];
+                let mut information = 0.0;
+                for a in 0..2 {
+                    for b in 0..2 {
+                        let joint = p[2 * a + b];
+                        if joint > 0.0 {
+                            information += joint * (joint / (first[a] * second[b])).log2();
+                        }
+                    }
+                }
+                if information > noise {
+                    information
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+
+    /// The fewest mantissa bits that keep `inflevel` (such as `0.99`) of
+    /// the total information, as xbitinfo's `get_keepbits` counts it; the
+    /// sign and exponent bits are always kept.  Zero when no bit carries
+    /// information.
+    pub fn keepbits(&self, inflevel: f64) -> u32 {
+        let information = self.information();
+        let mantissa = mantissa_bits(self.dtype).unwrap_or(0);
+        let leading = information.len() as u32 - mantissa;
+        let total: f64 = information.iter().sum();
+        if total <= 0.0 {
+            return 0;
+        }
+        let mut cumulative = 0.0;
+        for (bit, information) in information.iter().enumerate() {
+            cumulative += information;
+            if cumulative / total >= inflevel {
+                return (bit as u32 + 1).saturating_sub(leading).min(mantissa);
+            }
+        }
+        mantissa
+    }
+}
+
+/// The mutual information a random bit shows by chance in `pairs` pairs:
+/// one minus the entropy of the largest frequency of ones a fair bit
+/// reaches at 99% confidence.
+fn noise_information(pairs: u64) -> f64 {
+    // Two-sided 99% quantile of the standard normal distribution.
+    const Z: f64 = 2.575_829_303_548_901;
+    let p = (0.5 + Z / (2.0 * (pairs as f64).sqrt())).min(1.0);
+    let entropy = if p >= 1.0 {
+        0.0
+    } else {
+        -p * p.log2() - (1.0 - p) * (1.0 - p).log2()
+    };
+    1.0 - entropy
+}
+
+// ---------------------------------------------------------------------------
+// Array summaries
+// ---------------------------------------------------------------------------
+
+impl UnifiedZarrArray {
+    /// The smallest and largest finite values, or `None` if there are none.
+    pub async fn value_range(&self) -> ZarrResult<Option<RangeInclusive<f64>>> {
+        self.check_numeric()?;
+        let (lo, hi) = self
+            .reduce(
+                (f64::INFINITY, f64::NEG_INFINITY),
+                |(lo, hi), _, block| {
+                    let values = block.data.to_f64_vec().unwrap_or_default();
+                    values
+                        .into_iter()
+                        .filter(|v| v.is_finite())
+                        .fold((lo, hi), |(lo, hi), v| (lo.min(v), hi.max(v)))
+                },
+                |a, b| (a.0.min(b.0), a.1.max(b.1)),
+            )
+            .await?;
+        Ok((lo <= hi).then_some(lo..=hi))
+    }
+
+    /// A histogram of `bins` bins spanning the finite values, like numpy's
+    /// `histogram(a, bins)`.  Reads the array twice: once for the range,
+    /// once to count.
+    pub async fn histogram(&self, bins: usize) -> ZarrResult<Histogram> {
+        let range = match self.value_range().await? {
+            // numpy widens a single value to a unit-width range.
+            Some(range) if range.start() == range.end() => range.start() - 0.5..=range.end() + 0.5,
+            Some(range) => range,
+            None => 0.0..=1.0,
+        };
+        self.histogram_in(bins, range).await
+    }
+
+    /// A histogram of `bins` bins spanning `range`, in one pass.
+    pub async fn histogram_in(
+        &self,
+        bins: usize,
+        range: RangeInclusive<f64>,
+    ) -> ZarrResult<Histogram> {
+        self.check_numeric()?;
+        let empty = Histogram::new(bins, range);
+        self.reduce(
+            empty,
+            |mut histogram, _, block| {
+                for value in block.data.to_f64_vec().unwrap_or_default() {
+                    histogram.add(value);
+                }
+                histogram
+            },
+            |mut a, b| {
+                a.merge(&b);
+                a
+            },
+        )
+        .await
+    }
+
+    /// A t-digest of the values with [`DEFAULT_COMPRESSION`].
+    pub async fn digest(&self) -> ZarrResult<TDigest> {
+        self.check_numeric()?;
+        self.reduce(
+            TDigest::default(),
+            |mut digest, _, block| {
+                digest.add(block.data.to_f64_vec().unwrap_or_default());
+                digest
+            },
+            |mut a, b| {
+                a.merge(&b);
+                a
+            },
+        )
+        .await
+    }
+
+    /// Estimated quantiles `qs`, each in `0..=1`, from one pass over the
+    /// array; NaN if it has no values.
+    pub async fn quantiles(&self, qs: &[f64]) -> ZarrResult<Vec<f64>> {
+        let digest = self.digest().await?;
+        Ok(qs.iter().map(|&q| digest.quantile(q)).collect())
+    }
+
+    /// The [`BitInformation`] of a float array, counted one chunk at a
+    /// time; pairs that straddle two chunks aren't counted.
+    pub async fn bit_information(&self) -> ZarrResult<BitInformation> {
+        let empty = BitInformation::new(self.metadata.data_type)?;
+        self.reduce(
+            empty,
+            |mut information, _, block| {
+                information.add(&block);
+                information
+            },
+            |mut a, b| {
+                a.merge(&b);
+                a
+            },
+        )
+        .await
+    }
+
+    /// Mantissa bits that keep `inflevel` of a float array's information;
+    /// see [`BitInformation::keepbits`].
+    pub async fn keepbits(&self, inflevel: f64) -> ZarrResult<u32> {
+        Ok(self.bit_information().await?.keepbits(inflevel))
+    }
+
+    /// Summaries convert elements to `f64`, which these types can't.
+    fn check_numeric(&self) -> ZarrResult<()> {
+        match self.metadata.data_type {
+            dtype @ (DataType::String
+            | DataType::Bytes
+            | DataType::Raw(_)
+            | DataType::Complex64
+            | DataType::Complex128) => Err(ZarrError::TypeConversion(format!(
+                "Cannot summarise {dtype} values as numbers"
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! `candle` tensors from array regions.
+//!
+//! [`read_region_tensor`](UnifiedZarrArray::read_region_tensor) decodes a
+//! region straight into a [`candle_core::Tensor`] of the region's shape.
+//! Element types map to the nearest tensor dtype that holds every value:
+//!
+//! | Zarr | Tensor |
+//! |---|---|
+//! | `bool`, `uint8` | `U8` |
+//! | `int8`, `int16` | `I16` |
+//! | `int32` | `I32` |
+//! | `int64`, `uint64` (if every value fits) | `I64` |
+//! | `uint16`, `uint32` | `U32` |
+//! | `float16`, `float32`, `float64` | `F16`, `F32`, `F64` |
+//!
+//! Complex, string and byte arrays are rejected.
+
+use std::ops::Range;
+
+use candle_core::{Device, Tensor, WithDType};
+
+use crate::array::UnifiedZarrArray;
+use crate::error::{ZarrError, ZarrResult};
+use crate::types::{ArrayOrder, ZarrVectorValue};
+
+impl UnifiedZarrArray {
+    /// Read `region` into a tensor on `device` whose shape is the region's.
+    pub async fn read_region_tensor(
+        &self,
+        region: &[Range<usize>],
+        device: &Device,
+    ) -> ZarrResult<Tensor> {
+        let values = self.read_region_value(region).await?;
+        zarr_vector_to_tensor(values.data, &values.shape, values.order, device)
+    }
+
+    /// Load the whole array into a tensor on `device`.
+    pub async fn load_tensor(&self, device: &Device) -> ZarrResult<Tensor> {
+        self.read_region_tensor(&self.full_region(), device).await
+    }
+}
+
+/// Build a tensor of `shape` from `values` stored in `order`.
+pub fn zarr_vector_to_tensor(
+    values: ZarrVectorValue,
+    shape: &[usize],
+    order: ArrayOrder,
+    device: &Device,
+) -> ZarrResult<Tensor> {
+    match values {
+        ZarrVectorValue::VBool(v) => {
+            build(v.into_iter().map(u8::from).collect(), shape, order, device)
+        }
+        ZarrVectorValue::VInt8(v) => {
+            build(v.into_iter().map(i16::from).collect(), shape, order, device)
+        }
+        ZarrVectorValue::VInt16(v) => build(v, shape, order, device),
+        ZarrVectorValue::VInt32(v) => build(v, shape, order, device),
+        ZarrVectorValue::VInt64(v) => build(v, shape, order, device),
+        ZarrVectorValue::VUInt8(v) => build(v, shape, order, device),
+        ZarrVectorValue::VUInt16(v) => {
+            build(v.into_iter().map(u32::from).collect(), shape, order, device)
+        }
+        ZarrVectorValue::VUInt32(v) => build(v, shape, order, device),
+        ZarrVectorValue::VUInt64(v) => {
+            let v = v
+                .into_iter()
+                .map(i64::try_from)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| {
+                    ZarrError::TypeConversion("uint64 value too large for an I64 tensor".into())
+                })?;
+            build(v, shape, order, device)
+        }
+        ZarrVectorValue::VFloat16(v) => build(v, shape, order, device),
+        ZarrVectorValue::VFloat32(v) => build(v, shape, order, device),
+        ZarrVectorValue::VFloat64(v) => build(v, shape, order, device),
+        ZarrVectorValue::VWithNulls(dtype, _) => Err(ZarrError::TypeConversion(format!(
+            "Cannot build a tensor from {dtype} values with nulls"
+        ))),
+        ZarrVectorValue::VComplex64(_) | ZarrVectorValue::VComplex128(_) => Err(
+            ZarrError::TypeConversion("Cannot build a tensor from complex values".into()),
+        ),
+        ZarrVectorValue::VString(_) | ZarrVectorValue::VBytes(_) => Err(ZarrError::TypeConversion(
+            "Cannot build a tensor from string or byte values".into(),
+        )),
+    }
+}
+
+fn build<T: WithDType>(
+    values: Vec<T>,
+    shape: &[usize],
+    order: ArrayOrder,
+    device: &Device,
+) -> ZarrResult<Tensor> {
+    let expected: usize = shape.iter().product();
+    if values.len() != expected {
+        return Err(ZarrError::Other(format!(
+            "{} values don't fill a tensor of shape {shape:?}",
+            values.len()
+        )));
+    }
+    let tensor = match order {
+        ArrayOrder::C => Tensor::from_vec(values, shape, device),
+        // Column-major data is the row-major layout of the reversed shape.
+        ArrayOrder::F => {
+            let reversed: Vec<usize> = shape.iter().rev().copied().collect();
+            let axes: Vec<usize> = (0..shape.len()).rev().collect();
+            Tensor::from_vec(values, reversed, device)
+                .and_then(|t| t.permute(axes))
+                .and_then(|t| t.contiguous())
+        }
+    };
+    tensor.map_err(|e| ZarrError::Other(format!("candle: {e}")))
+}
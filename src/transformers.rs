@@ -0,0 +1,153 @@
+//! V3 storage transformers.
+//!
+//! A storage transformer sits between an array and its store and changes how
+//! chunk keys map to stored values.  `zarr.json` lists them under
+//! `storage_transformers`; each entry parses to a [`StorageTransformerSpec`].
+//!
+//! None are built in.  An implementation registered with
+//! [`register_storage_transformer`] is applied whenever an array lists its
+//! name.  Unregistered transformers flagged `"must_understand": false` are
+//! skipped; any other fails with [`FeatureId::StorageTransformer`] when
+//! chunks are accessed, so metadata stays readable.
+//!
+//! Some early V3 writers expressed sharding as a storage transformer
+//! (`{"type": "indexed", "extension": ".../sharding/1.0"}`).  That form
+//! parses with the name `sharding_indexed`.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use crate::capabilities::FeatureId;
+use crate::error::{ZarrError, ZarrResult};
+use crate::store::StorageBackend;
+
+// ---------------------------------------------------------------------------
+// StorageTransformerSpec
+// ---------------------------------------------------------------------------
+
+/// One `storage_transformers` entry from `zarr.json`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageTransformerSpec {
+    pub name: String,
+    pub configuration: serde_json::Value,
+    /// Whether the array is unreadable without this transformer.  Defaults
+    /// to `true` when the entry doesn't say.
+    pub must_understand: bool,
+}
+
+impl StorageTransformerSpec {
+    /// Parse an entry in either the `{"name": ...}` form or the older
+    /// `{"type": ..., "extension": ...}` form.
+    pub fn parse(value: &serde_json::Value) -> ZarrResult<Self> {
+        let obj = value
+            .as_object()
+            .ok_or_else(|| ZarrError::Metadata(format!("Invalid storage transformer: {value}")))?;
+        let text = |key: &str| obj.get(key).and_then(|v| v.as_str());
+        let name = match (text("name"), text("extension")) {
+            (Some(name), _) => name.to_string(),
+            (None, Some(url)) if url.contains("sharding") => "sharding_indexed".to_string(),
+            (None, Some(url)) => url.to_string(),
+            (None, None) => {
+                return Err(ZarrError::Metadata(format!(
+                    "Storage transformer without a name: {value}"
+                )));
+            }
+        };
+        Ok(Self {
+            name,
+            configuration: obj
+                .get("configuration")
+                .cloned()
+                .unwrap_or_else(|| serde_json::json!({})),
+            must_understand: obj
+                .get("must_understand")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true),
+        })
+    }
+
+    /// The `{"name": ..., "configuration": ...}` entry for `zarr.json`.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut entry = serde_json::json!({
+            "name": self.name,
+            "configuration": self.configuration,
+        });
+        if !self.must_understand {
+            entry["must_understand"] = false.into();
+        }
+        entry
+    }
+}
+
+/// Parse a `storage_transformers` list; a missing or null list is empty.
+pub fn parse_storage_transformers(
+    value: Option<&serde_json::Value>,
+) -> ZarrResult<Vec<StorageTransformerSpec>> {
+    match value {
+        None | Some(serde_json::Value::Null) => Ok(Vec::new()),
+        Some(serde_json::Value::Array(entries)) => {
+            entries.iter().map(StorageTransformerSpec::parse).collect()
+        }
+        Some(other) => Err(ZarrError::Metadata(format!(
+            "'storage_transformers' must be an array, got {other}"
+        ))),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// StorageTransformer
+// ---------------------------------------------------------------------------
+
+/// A storage transformer implementation.
+pub trait StorageTransformer: Send + Sync {
+    /// Wrap `store`; the array's chunk reads and writes go through the
+    /// returned backend.
+    fn apply(&self, store: Arc<dyn StorageBackend>) -> ZarrResult<Arc<dyn StorageBackend>>;
+}
+
+/// Builds a transformer from its `zarr.json` entry.
+pub type StorageTransformerFactory =
+    fn(&StorageTransformerSpec) -> ZarrResult<Arc<dyn StorageTransformer>>;
+
+static REGISTRY: RwLock<BTreeMap<String, StorageTransformerFactory>> = RwLock::new(BTreeMap::new());
+
+/// Use `factory` for arrays listing the storage transformer `name`,
+/// replacing any earlier registration.
+pub fn register_storage_transformer(name: &str, factory: StorageTransformerFactory) {
+    REGISTRY
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), factory);
+}
+
+/// Names of the registered storage transformers.
+pub fn registered_storage_transformers() -> Vec<String> {
+    REGISTRY
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .keys()
+        .cloned()
+        .collect()
+}
+
+/// Wrap `store` in the transformers `specs` lists.  The first entry ends up
+/// outermost, nearest the array.
+pub fn apply_storage_transformers(
+    store: Arc<dyn StorageBackend>,
+    specs: &[StorageTransformerSpec],
+) -> ZarrResult<Arc<dyn StorageBackend>> {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    let mut store = store;
+    for spec in specs.iter().rev() {
+        match registry.get(&spec.name) {
+            Some(factory) => store = factory(spec)?.apply(store)?,
+            None if !spec.must_understand => {}
+            None => {
+                return Err(ZarrError::UnsupportedFeature(
+                    FeatureId::StorageTransformer(spec.name.clone()),
+                ));
+            }
+        }
+    }
+    Ok(store)
+}
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use half::f16;
 use num_complex::Complex;
 
@@ -12,6 +14,40 @@ pub enum Endian {
     Little,
     Big,
     NotApplicable,
+    /// The host's byte order (NumPy's `=`), resolved to `Little` or `Big`
+    /// when bytes are read or written.
+    Native,
+}
+
+impl Endian {
+    /// The byte order of the machine running this code.
+    pub const fn host() -> Endian {
+        if cfg!(target_endian = "big") {
+            Endian::Big
+        } else {
+            Endian::Little
+        }
+    }
+
+    /// `Native` as the host's byte order; other values unchanged.
+    pub const fn resolve(self) -> Endian {
+        match self {
+            Endian::Native => Endian::host(),
+            other => other,
+        }
+    }
+}
+
+/// `LE`, `BE`, `native` or `n/a`.
+impl fmt::Display for Endian {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Endian::Little => f.write_str("LE"),
+            Endian::Big => f.write_str("BE"),
+            Endian::Native => f.write_str("native"),
+            Endian::NotApplicable => f.write_str("n/a"),
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -47,6 +83,9 @@ pub enum DataType {
     Complex128,
     String,
     Bytes,
+    /// Opaque fixed-width elements of the given number of bits (V3 `r<bits>`,
+    /// V2 `|V<n>`), decoded as fixed-size byte slices.
+    Raw(usize),
 }
 
 impl DataType {
@@ -67,11 +106,38 @@ impl DataType {
             DataType::Float64 => Some(8),
             DataType::Complex64 => Some(8),
             DataType::Complex128 => Some(16),
+            DataType::Raw(bits) => Some(bits / 8),
             DataType::String | DataType::Bytes => None,
         }
     }
 }
 
+/// The V3 `data_type` name: `float32`, `uint8`, `r16`, ...
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DataType::Bool => "bool",
+            DataType::Int8 => "int8",
+            DataType::Int16 => "int16",
+            DataType::Int32 => "int32",
+            DataType::Int64 => "int64",
+            DataType::UInt8 => "uint8",
+            DataType::UInt16 => "uint16",
+            DataType::UInt32 => "uint32",
+            DataType::UInt64 => "uint64",
+            DataType::Float16 => "float16",
+            DataType::Float32 => "float32",
+            DataType::Float64 => "float64",
+            DataType::Complex64 => "complex64",
+            DataType::Complex128 => "complex128",
+            DataType::String => "string",
+            DataType::Bytes => "bytes",
+            DataType::Raw(bits) => return write!(f, "r{bits}"),
+        };
+        f.write_str(name)
+    }
+}
+
 // ---------------------------------------------------------------------------
 // ZarrValue  (scalar)
 // ---------------------------------------------------------------------------
@@ -144,6 +210,35 @@ impl ZarrValue {
     }
 }
 
+/// The value as written in Python: `3`, `-0.5`, `1+2i`, `"text"`, `0x0aff`,
+/// or `null`.
+impl fmt::Display for ZarrValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZarrValue::Bool(v) => write!(f, "{v}"),
+            ZarrValue::Int8(v) => write!(f, "{v}"),
+            ZarrValue::Int16(v) => write!(f, "{v}"),
+            ZarrValue::Int32(v) => write!(f, "{v}"),
+            ZarrValue::Int64(v) => write!(f, "{v}"),
+            ZarrValue::UInt8(v) => write!(f, "{v}"),
+            ZarrValue::UInt16(v) => write!(f, "{v}"),
+            ZarrValue::UInt32(v) => write!(f, "{v}"),
+            ZarrValue::UInt64(v) => write!(f, "{v}"),
+            ZarrValue::Float16(v) => write!(f, "{v}"),
+            ZarrValue::Float32(v) => write!(f, "{v}"),
+            ZarrValue::Float64(v) => write!(f, "{v}"),
+            ZarrValue::Complex64(v) => write!(f, "{v}"),
+            ZarrValue::Complex128(v) => write!(f, "{v}"),
+            ZarrValue::String(v) => write!(f, "{v:?}"),
+            ZarrValue::Bytes(v) => {
+                f.write_str("0x")?;
+                v.iter().try_for_each(|b| write!(f, "{b:02x}"))
+            }
+            ZarrValue::Null(_) => f.write_str("null"),
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // FillValue
 // ---------------------------------------------------------------------------
@@ -172,11 +267,39 @@ impl FillValue {
     pub fn to_zarr_value(&self, dtype: DataType) -> ZarrValue {
         match self {
             FillValue::Value(v) if v.data_type() == dtype => v.clone(),
+            FillValue::Value(ZarrValue::Bytes(b))
+                if matches!(dtype, DataType::Raw(bits) if b.len() * 8 == bits) =>
+            {
+                ZarrValue::Bytes(b.clone())
+            }
+            FillValue::NaN | FillValue::Infinity | FillValue::NegativeInfinity => {
+                let x = self.to_f64();
+                match dtype {
+                    DataType::Float16 => ZarrValue::Float16(f16::from_f64(x)),
+                    DataType::Float32 => ZarrValue::Float32(x as f32),
+                    DataType::Float64 => ZarrValue::Float64(x),
+                    DataType::Complex64 => ZarrValue::Complex64(Complex::new(x as f32, 0.0)),
+                    DataType::Complex128 => ZarrValue::Complex128(Complex::new(x, 0.0)),
+                    _ => default_scalar(dtype),
+                }
+            }
             _ => default_scalar(dtype),
         }
     }
 }
 
+/// `NaN`, `Infinity`, `-Infinity`, or the value itself.
+impl fmt::Display for FillValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FillValue::Value(v) => write!(f, "{v}"),
+            FillValue::NaN => f.write_str("NaN"),
+            FillValue::Infinity => f.write_str("Infinity"),
+            FillValue::NegativeInfinity => f.write_str("-Infinity"),
+        }
+    }
+}
+
 /// Default zero/false/empty scalar for a data type.
 pub fn default_scalar(dtype: DataType) -> ZarrValue {
     match dtype {
@@ -196,6 +319,7 @@ pub fn default_scalar(dtype: DataType) -> ZarrValue {
         DataType::Complex128 => ZarrValue::Complex128(Complex::new(0.0f64, 0.0)),
         DataType::String => ZarrValue::String(std::string::String::new()),
         DataType::Bytes => ZarrValue::Bytes(Vec::new()),
+        DataType::Raw(bits) => ZarrValue::Bytes(vec![0; bits / 8]),
     }
 }
 
@@ -257,6 +381,29 @@ impl ZarrVectorValue {
         self.len() == 0
     }
 
+    /// Approximate in-memory size of the element data in bytes.
+    pub fn nbytes(&self) -> usize {
+        match self {
+            ZarrVectorValue::VBool(v) => v.len(),
+            ZarrVectorValue::VInt8(v) => v.len(),
+            ZarrVectorValue::VInt16(v) => v.len() * 2,
+            ZarrVectorValue::VInt32(v) => v.len() * 4,
+            ZarrVectorValue::VInt64(v) => v.len() * 8,
+            ZarrVectorValue::VUInt8(v) => v.len(),
+            ZarrVectorValue::VUInt16(v) => v.len() * 2,
+            ZarrVectorValue::VUInt32(v) => v.len() * 4,
+            ZarrVectorValue::VUInt64(v) => v.len() * 8,
+            ZarrVectorValue::VFloat16(v) => v.len() * 2,
+            ZarrVectorValue::VFloat32(v) => v.len() * 4,
+            ZarrVectorValue::VFloat64(v) => v.len() * 8,
+            ZarrVectorValue::VComplex64(v) => v.len() * 8,
+            ZarrVectorValue::VComplex128(v) => v.len() * 16,
+            ZarrVectorValue::VString(v) => v.iter().map(|s| s.len()).sum(),
+            ZarrVectorValue::VBytes(v) => v.iter().map(|b| b.len()).sum(),
+            ZarrVectorValue::VWithNulls(_, v) => v.len() * std::mem::size_of::<Option<ZarrValue>>(),
+        }
+    }
+
     /// Lossily convert the entire vector to `Vec<f64>`.
     pub fn to_f64_vec(&self) -> ZarrResult<Vec<f64>> {
         match self {
@@ -287,6 +434,67 @@ impl ZarrVectorValue {
         }
     }
 
+    /// Convert the entire vector to `Vec<f32>` without an `f64` copy.
+    /// Finite values beyond the `f32` range saturate to `f32::MAX` or
+    /// `f32::MIN`; nulls become NaN.
+    pub fn to_f32_vec(&self) -> ZarrResult<Vec<f32>> {
+        match self {
+            ZarrVectorValue::VBool(v) => Ok(v.iter().map(|b| if *b { 1.0 } else { 0.0 }).collect()),
+            ZarrVectorValue::VInt8(v) => Ok(v.iter().map(|x| *x as f32).collect()),
+            ZarrVectorValue::VInt16(v) => Ok(v.iter().map(|x| *x as f32).collect()),
+            ZarrVectorValue::VInt32(v) => Ok(v.iter().map(|x| *x as f32).collect()),
+            ZarrVectorValue::VInt64(v) => Ok(v.iter().map(|x| *x as f32).collect()),
+            ZarrVectorValue::VUInt8(v) => Ok(v.iter().map(|x| *x as f32).collect()),
+            ZarrVectorValue::VUInt16(v) => Ok(v.iter().map(|x| *x as f32).collect()),
+            ZarrVectorValue::VUInt32(v) => Ok(v.iter().map(|x| *x as f32).collect()),
+            ZarrVectorValue::VUInt64(v) => Ok(v.iter().map(|x| *x as f32).collect()),
+            ZarrVectorValue::VFloat16(v) => Ok(v.iter().map(|x| x.to_f32()).collect()),
+            ZarrVectorValue::VFloat32(v) => Ok(v.clone()),
+            ZarrVectorValue::VFloat64(v) => Ok(v.iter().map(|x| saturating_f32(*x)).collect()),
+            ZarrVectorValue::VComplex64(v) => Ok(v.iter().map(|c| c.re).collect()),
+            ZarrVectorValue::VComplex128(v) => Ok(v.iter().map(|c| saturating_f32(c.re)).collect()),
+            ZarrVectorValue::VString(_) => Err(ZarrError::TypeConversion(
+                "Cannot convert String to f32".into(),
+            )),
+            ZarrVectorValue::VBytes(_) => Err(ZarrError::TypeConversion(
+                "Cannot convert Bytes to f32".into(),
+            )),
+            ZarrVectorValue::VWithNulls(_, v) => Ok(v
+                .iter()
+                .map(|opt| {
+                    opt.as_ref()
+                        .and_then(|zv| zv.to_f64())
+                        .map_or(f32::NAN, saturating_f32)
+                })
+                .collect()),
+        }
+    }
+
+    /// Like [`to_f64_vec`](Self::to_f64_vec), but with a validity mask
+    /// (`false` for nulls) so nulls aren't confused with NaN data.  Nulls
+    /// read as NaN in the values.
+    pub fn to_f64_with_validity(&self) -> ZarrResult<(Vec<f64>, Vec<bool>)> {
+        let ZarrVectorValue::VWithNulls(dtype, v) = self else {
+            // Typed vectors have no nulls.
+            let values = self.to_f64_vec()?;
+            let validity = vec![true; values.len()];
+            return Ok((values, validity));
+        };
+        if matches!(dtype, DataType::String | DataType::Bytes | DataType::Raw(_)) {
+            return Err(ZarrError::TypeConversion(format!(
+                "Cannot convert {dtype} to f64"
+            )));
+        }
+        let mut values = Vec::with_capacity(v.len());
+        let mut validity = Vec::with_capacity(v.len());
+        for opt in v {
+            let value = opt.as_ref().and_then(|zv| zv.to_f64());
+            values.push(value.unwrap_or(f64::NAN));
+            validity.push(value.is_some());
+        }
+        Ok((values, validity))
+    }
+
     /// Convert to `Vec<Option<ZarrValue>>`, wrapping each element.
     pub fn to_maybe_values(&self) -> Vec<Option<ZarrValue>> {
         match self {
@@ -327,6 +535,16 @@ impl ZarrVectorValue {
     }
 }
 
+/// `x` as `f32`, clamping finite values to the `f32` range instead of
+/// rounding them to infinity.
+pub fn saturating_f32(x: f64) -> f32 {
+    if x.is_finite() {
+        x.clamp(f32::MIN as f64, f32::MAX as f64) as f32
+    } else {
+        x as f32
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Raw bytes -> typed vector
 // ---------------------------------------------------------------------------
@@ -342,9 +560,15 @@ pub fn bytes_to_zarr_vector(
     data: &[u8],
 ) -> ZarrResult<ZarrVectorValue> {
     match dtype {
-        DataType::Bool => Ok(ZarrVectorValue::VBool(
-            data.iter().map(|b| *b != 0).collect(),
-        )),
+        DataType::Bool => {
+            if let Some(pos) = data.iter().position(|b| *b > 1) {
+                return Err(ZarrError::Decode(format!(
+                    "Invalid bool byte {:#04x} at element {pos}",
+                    data[pos]
+                )));
+            }
+            Ok(ZarrVectorValue::VBool(data.iter().map(|b| *b != 0).collect()))
+        }
         DataType::Int8 => Ok(ZarrVectorValue::VInt8(
             data.iter().map(|b| *b as i8).collect(),
         )),
@@ -446,6 +670,19 @@ pub fn bytes_to_zarr_vector(
             Ok(ZarrVectorValue::VComplex128(out))
         }
 
+        DataType::Raw(bits) => {
+            let width = bits / 8;
+            if width == 0 || !data.len().is_multiple_of(width) {
+                return Err(ZarrError::Decode(format!(
+                    "Data length {} is not a multiple of element size {width} for r{bits}",
+                    data.len()
+                )));
+            }
+            Ok(ZarrVectorValue::VBytes(
+                data.chunks_exact(width).map(|c| c.to_vec()).collect(),
+            ))
+        }
+
         DataType::String | DataType::Bytes => Err(ZarrError::Decode(
             "Cannot interpret raw bytes as String/Bytes vector without length info".into(),
         )),
@@ -480,9 +717,9 @@ fn read_vec_fast_raw<T: Copy, const N: usize>(
             data.len()
         )));
     }
-    let convert = match endian {
-        Endian::Little | Endian::NotApplicable => from_le,
+    let convert = match endian.resolve() {
         Endian::Big => from_be,
+        _ => from_le,
     };
     // chunks_exact(N) guarantees each chunk is exactly N bytes,
     // so the try_into().unwrap() is infallible and optimised away.
@@ -495,6 +732,111 @@ fn read_vec_fast_raw<T: Copy, const N: usize>(
         .collect())
 }
 
+/// Unpack `count` bit-packed booleans (most significant bit first).
+pub fn unpack_bools(data: &[u8], count: usize) -> ZarrResult<Vec<bool>> {
+    if data.len() != count.div_ceil(8) {
+        return Err(ZarrError::Decode(format!(
+            "Bit-packed bool data is {} bytes, expected {} for {count} elements",
+            data.len(),
+            count.div_ceil(8)
+        )));
+    }
+    Ok((0..count)
+        .map(|i| data[i / 8] & (0x80 >> (i % 8)) != 0)
+        .collect())
+}
+
+// ---------------------------------------------------------------------------
+// Typed vector -> raw bytes
+// ---------------------------------------------------------------------------
+
+/// Serialise a typed vector to raw bytes in `endian` order; the inverse of
+/// [`bytes_to_zarr_vector`].
+pub fn zarr_vector_to_bytes(endian: Endian, values: &ZarrVectorValue) -> ZarrResult<Vec<u8>> {
+    match values {
+        ZarrVectorValue::VBool(v) => Ok(v.iter().map(|b| *b as u8).collect()),
+        ZarrVectorValue::VInt8(v) => Ok(v.iter().map(|x| *x as u8).collect()),
+        ZarrVectorValue::VInt16(v) => Ok(write_vec_fast(endian, v, i16::to_le_bytes, i16::to_be_bytes)),
+        ZarrVectorValue::VInt32(v) => Ok(write_vec_fast(endian, v, i32::to_le_bytes, i32::to_be_bytes)),
+        ZarrVectorValue::VInt64(v) => Ok(write_vec_fast(endian, v, i64::to_le_bytes, i64::to_be_bytes)),
+        ZarrVectorValue::VUInt8(v) => Ok(v.clone()),
+        ZarrVectorValue::VUInt16(v) => Ok(write_vec_fast(endian, v, u16::to_le_bytes, u16::to_be_bytes)),
+        ZarrVectorValue::VUInt32(v) => Ok(write_vec_fast(endian, v, u32::to_le_bytes, u32::to_be_bytes)),
+        ZarrVectorValue::VUInt64(v) => Ok(write_vec_fast(endian, v, u64::to_le_bytes, u64::to_be_bytes)),
+        ZarrVectorValue::VFloat16(v) => Ok(write_vec_fast(endian, v, f16::to_le_bytes, f16::to_be_bytes)),
+        ZarrVectorValue::VFloat32(v) => Ok(write_vec_fast(endian, v, f32::to_le_bytes, f32::to_be_bytes)),
+        ZarrVectorValue::VFloat64(v) => Ok(write_vec_fast(endian, v, f64::to_le_bytes, f64::to_be_bytes)),
+        ZarrVectorValue::VComplex64(v) => {
+            let parts: Vec<f32> = v.iter().flat_map(|c| [c.re, c.im]).collect();
+            Ok(write_vec_fast(endian, &parts, f32::to_le_bytes, f32::to_be_bytes))
+        }
+        ZarrVectorValue::VComplex128(v) => {
+            let parts: Vec<f64> = v.iter().flat_map(|c| [c.re, c.im]).collect();
+            Ok(write_vec_fast(endian, &parts, f64::to_le_bytes, f64::to_be_bytes))
+        }
+        // Raw elements are already fixed-width byte strings.
+        ZarrVectorValue::VBytes(v) => Ok(v.concat()),
+        ZarrVectorValue::VString(_) => Err(ZarrError::Encode(
+            "Cannot serialise a String vector without length info".into(),
+        )),
+        ZarrVectorValue::VWithNulls(dtype, _) => Err(ZarrError::Encode(format!(
+            "Cannot serialise a {dtype} vector containing nulls"
+        ))),
+    }
+}
+
+#[inline]
+fn write_vec_fast<T: Copy, const N: usize>(
+    endian: Endian,
+    values: &[T],
+    to_le: fn(T) -> [u8; N],
+    to_be: fn(T) -> [u8; N],
+) -> Vec<u8> {
+    let convert = match endian.resolve() {
+        Endian::Big => to_be,
+        _ => to_le,
+    };
+    let mut out = Vec::with_capacity(values.len() * N);
+    for v in values {
+        out.extend_from_slice(&convert(*v));
+    }
+    out
+}
+
+/// Cast `f64` values into a typed vector of `dtype`.
+///
+/// Integer targets use Rust `as` semantics: values truncate toward zero,
+/// saturate at the type's bounds, and NaN becomes 0.
+pub fn f64_to_zarr_vector(dtype: DataType, values: &[f64]) -> ZarrResult<ZarrVectorValue> {
+    Ok(match dtype {
+        DataType::Bool => ZarrVectorValue::VBool(values.iter().map(|x| *x != 0.0).collect()),
+        DataType::Int8 => ZarrVectorValue::VInt8(values.iter().map(|x| *x as i8).collect()),
+        DataType::Int16 => ZarrVectorValue::VInt16(values.iter().map(|x| *x as i16).collect()),
+        DataType::Int32 => ZarrVectorValue::VInt32(values.iter().map(|x| *x as i32).collect()),
+        DataType::Int64 => ZarrVectorValue::VInt64(values.iter().map(|x| *x as i64).collect()),
+        DataType::UInt8 => ZarrVectorValue::VUInt8(values.iter().map(|x| *x as u8).collect()),
+        DataType::UInt16 => ZarrVectorValue::VUInt16(values.iter().map(|x| *x as u16).collect()),
+        DataType::UInt32 => ZarrVectorValue::VUInt32(values.iter().map(|x| *x as u32).collect()),
+        DataType::UInt64 => ZarrVectorValue::VUInt64(values.iter().map(|x| *x as u64).collect()),
+        DataType::Float16 => {
+            ZarrVectorValue::VFloat16(values.iter().map(|x| f16::from_f64(*x)).collect())
+        }
+        DataType::Float32 => ZarrVectorValue::VFloat32(values.iter().map(|x| *x as f32).collect()),
+        DataType::Float64 => ZarrVectorValue::VFloat64(values.to_vec()),
+        DataType::Complex64 => ZarrVectorValue::VComplex64(
+            values.iter().map(|x| Complex::new(*x as f32, 0.0)).collect(),
+        ),
+        DataType::Complex128 => {
+            ZarrVectorValue::VComplex128(values.iter().map(|x| Complex::new(*x, 0.0)).collect())
+        }
+        DataType::String | DataType::Bytes | DataType::Raw(_) => {
+            return Err(ZarrError::TypeConversion(format!(
+                "Cannot convert f64 to {dtype}"
+            )));
+        }
+    })
+}
+
 /// Create a filled chunk vector by replicating a scalar value.
 pub fn fill_chunk(value: &ZarrValue, chunk_shape: &[usize]) -> ZarrVectorValue {
     let total: usize = chunk_shape.iter().product();
@@ -518,3 +860,92 @@ pub fn fill_chunk(value: &ZarrValue, chunk_shape: &[usize]) -> ZarrVectorValue {
         ZarrValue::Null(dt) => ZarrVectorValue::VWithNulls(*dt, vec![None; total]),
     }
 }
+
+/// Write `src[s]` as `f64` to `out[d]` for every `(s, d)` in `pairs`,
+/// converting each element as [`ZarrVectorValue::to_f64_vec`] does but
+/// without an `f64` copy of the whole of `src`.  Every `s` must be in
+/// bounds.
+pub(crate) fn scatter_f64(
+    out: &mut [f64],
+    src: &ZarrVectorValue,
+    pairs: &[(usize, usize)],
+) -> ZarrResult<()> {
+    macro_rules! scatter_as {
+        ($values:expr, $convert:expr) => {
+            for &(si, di) in pairs {
+                out[di] = $convert(&$values[si]);
+            }
+        };
+    }
+    match src {
+        ZarrVectorValue::VBool(v) => scatter_as!(v, |b: &bool| if *b { 1.0 } else { 0.0 }),
+        ZarrVectorValue::VInt8(v) => scatter_as!(v, |x: &i8| *x as f64),
+        ZarrVectorValue::VInt16(v) => scatter_as!(v, |x: &i16| *x as f64),
+        ZarrVectorValue::VInt32(v) => scatter_as!(v, |x: &i32| *x as f64),
+        ZarrVectorValue::VInt64(v) => scatter_as!(v, |x: &i64| *x as f64),
+        ZarrVectorValue::VUInt8(v) => scatter_as!(v, |x: &u8| *x as f64),
+        ZarrVectorValue::VUInt16(v) => scatter_as!(v, |x: &u16| *x as f64),
+        ZarrVectorValue::VUInt32(v) => scatter_as!(v, |x: &u32| *x as f64),
+        ZarrVectorValue::VUInt64(v) => scatter_as!(v, |x: &u64| *x as f64),
+        ZarrVectorValue::VFloat16(v) => scatter_as!(v, |x: &f16| x.to_f64()),
+        ZarrVectorValue::VFloat32(v) => scatter_as!(v, |x: &f32| *x as f64),
+        ZarrVectorValue::VFloat64(v) => scatter_as!(v, |x: &f64| *x),
+        ZarrVectorValue::VComplex64(v) => scatter_as!(v, |c: &Complex<f32>| c.re as f64),
+        ZarrVectorValue::VComplex128(v) => scatter_as!(v, |c: &Complex<f64>| c.re),
+        ZarrVectorValue::VWithNulls(_, v) => scatter_as!(v, |opt: &Option<ZarrValue>| {
+            opt.as_ref().and_then(|zv| zv.to_f64()).unwrap_or(f64::NAN)
+        }),
+        ZarrVectorValue::VString(_) | ZarrVectorValue::VBytes(_) => {
+            // Same error as the whole-vector conversion.
+            src.to_f64_vec()?;
+        }
+    }
+    Ok(())
+}
+
+/// Copy `src[s]` into `dst[d]` for every `(d, s)` in `pairs`.  Fails, leaving
+/// `dst` unchanged, when the two hold different element types.
+pub(crate) fn scatter(
+    dst: &mut ZarrVectorValue,
+    src: &ZarrVectorValue,
+    pairs: Vec<(usize, usize)>,
+) -> Result<(), ()> {
+    macro_rules! scatter_variants {
+        ($($variant:ident),*) => {
+            match (dst, src) {
+                $((ZarrVectorValue::$variant(d), ZarrVectorValue::$variant(s)) => {
+                    for (di, si) in pairs {
+                        d[di] = s[si].clone();
+                    }
+                })*
+                (ZarrVectorValue::VWithNulls(dt, d), ZarrVectorValue::VWithNulls(st, s))
+                    if dt == st =>
+                {
+                    for (di, si) in pairs {
+                        d[di] = s[si].clone();
+                    }
+                }
+                _ => return Err(()),
+            }
+        };
+    }
+    scatter_variants!(
+        VBool,
+        VInt8,
+        VInt16,
+        VInt32,
+        VInt64,
+        VUInt8,
+        VUInt16,
+        VUInt32,
+        VUInt64,
+        VFloat16,
+        VFloat32,
+        VFloat64,
+        VComplex64,
+        VComplex128,
+        VString,
+        VBytes
+    );
+    Ok(())
+}
@@ -1,77 +1,148 @@
 //! Zarr V2 array and group opening / loading.
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
 use std::sync::Arc;
 
 use crate::array::{
     ChunkGetterFn, CompressionInfo, UnifiedMetadata, UnifiedZarrArray, parse_chunk,
 };
+use crate::attributes::Attributes;
+use crate::capabilities::FeatureId;
 use crate::codecs::AnyCodec;
+use crate::codecs::bitround::BitRoundCodec;
 use crate::codecs::blosc::{BloscCname, BloscCodec, BloscShuffle};
+use crate::codecs::brotli::BrotliCodec;
 use crate::codecs::bytes::BytesCodec;
+use crate::codecs::fixedscaleoffset::FixedScaleOffsetCodec;
 use crate::codecs::gzip::GzipCodec;
 use crate::codecs::lz4::Lz4Codec;
+use crate::codecs::lzma::LzmaCodec;
+use crate::codecs::snappy::SnappyCodec;
 use crate::codecs::zlib::ZlibCodec;
 use crate::codecs::zstd::ZstdCodec;
+use crate::config::{self, SimplezarrConfig};
 use crate::error::{ZarrError, ZarrResult};
 use crate::group::{UnifiedGroupMetadata, UnifiedZarrGroup};
-use crate::metadata::v2::{ZarrCompressor, ZarrConsolidatedMetadata, ZarrV2Metadata};
-use crate::store::StorageBackend;
+use crate::metadata::v2::{
+    ZarrCompressor, ZarrConsolidatedMetadata, ZarrV2Metadata, chunk_key_with_separator,
+    parse_attributes, parse_numpy_dtype,
+};
+use crate::store::{StorageBackend, get_with_etag_retry, get_with_retry};
+use crate::types::Endian;
+use futures::{StreamExt, TryStreamExt};
 
 // ---------------------------------------------------------------------------
 // Compressor -> codec list conversion
 // ---------------------------------------------------------------------------
 
 /// Convert a V2 compressor JSON object to a list of codecs, matching the
-/// Haskell `zarrCompressorToAnyCodec` function.
-pub fn compressor_to_codecs(comp: &ZarrCompressor) -> Vec<AnyCodec> {
-    let id_lower = comp.id.to_lowercase();
-    match id_lower.as_str() {
-        "gzip" => {
-            let level = get_config_int(&comp.config, "level").unwrap_or(5) as u32;
-            vec![AnyCodec::Gzip(GzipCodec {
-                level: level.min(9),
-            })]
-        }
-        "blosc" => vec![AnyCodec::Blosc(blosc_codec_from_config(comp, None))],
-        "zlib" => {
-            let level = get_config_int(&comp.config, "level").unwrap_or(1) as u32;
-            vec![AnyCodec::Zlib(ZlibCodec {
-                level: level.min(9),
-            })]
-        }
+/// Haskell `zarrCompressorToAnyCodec` function.  Ids are matched
+/// case-insensitively and through the aliases in [`canonical_compressor_id`].
+/// Unknown compressors are an error: an empty pipeline would silently return
+/// compressed bytes as data.  So are levels outside the compressor's own
+/// range (zstd -7..=22, gzip, zlib and blosc 0..=9, brotli 0..=11), a
+/// brotli window outside 10..=24 and an lz4 acceleration below 1.
+pub fn compressor_to_codecs(comp: &ZarrCompressor) -> ZarrResult<Vec<AnyCodec>> {
+    let (id, id_level) = canonical_compressor_id(&comp.id);
+    let level = |default: i64| {
+        get_config_int(&comp.config, "level")
+            .or(id_level)
+            .unwrap_or(default)
+    };
+    Ok(match id.as_str() {
+        "gzip" => vec![AnyCodec::Gzip(GzipCodec {
+            level: config_in_range("gzip level", level(5), 0..=9)? as u32,
+        })],
+        "blosc" => vec![AnyCodec::Blosc(blosc_codec_from_config(comp, None)?)],
+        "zlib" => vec![AnyCodec::Zlib(ZlibCodec {
+            level: config_in_range("zlib level", level(1), 0..=9)? as u32,
+        })],
         "lz4" => {
-            let acc = get_config_int(&comp.config, "acceleration").unwrap_or(1) as i32;
+            let acc = get_config_int(&comp.config, "acceleration").unwrap_or(1);
+            let acc = config_in_range("lz4 acceleration", acc, 1..=i32::MAX.into())?;
             vec![AnyCodec::Lz4(Lz4Codec {
-                acceleration: acc.clamp(0, 9),
+                acceleration: acc as i32,
             })]
         }
         "lz4hc" => vec![AnyCodec::Blosc(blosc_codec_from_config(
             comp,
             Some(BloscCname::Lz4hc),
-        ))],
+        )?)],
         "blosclz" => vec![AnyCodec::Blosc(blosc_codec_from_config(
             comp,
             Some(BloscCname::Blosclz),
-        ))],
+        )?)],
         "zstd" => {
-            let level = get_config_int(&comp.config, "level").unwrap_or(5) as i32;
-            vec![AnyCodec::Zstd(ZstdCodec {
-                level: level.clamp(0, 9),
-            })]
+            let level = config_in_range("zstd level", level(5), -7..=22)?;
+            vec![AnyCodec::Zstd(ZstdCodec::new(level as i32))]
         }
-        "snappy" => vec![AnyCodec::Blosc(blosc_codec_from_config(
-            comp,
-            Some(BloscCname::Snappy),
-        ))],
-        _ => vec![],
+        "snappy" => vec![AnyCodec::Snappy(SnappyCodec::default())],
+        "lzma" => {
+            let config = serde_json::Value::Object(comp.config.clone());
+            let codec: LzmaCodec = serde_json::from_value(config)
+                .map_err(|e| ZarrError::Metadata(format!("Invalid lzma compressor: {e}")))?;
+            vec![AnyCodec::Lzma(codec)]
+        }
+        "brotli" => vec![AnyCodec::Brotli(BrotliCodec {
+            level: config_in_range("brotli level", level(11), 0..=11)? as u32,
+            lgwin: config_in_range(
+                "brotli lgwin",
+                get_config_int(&comp.config, "lgwin").unwrap_or(22),
+                10..=24,
+            )? as u32,
+        })],
+        _ => {
+            return Err(ZarrError::UnsupportedFeature(FeatureId::Compressor(
+                comp.id.clone(),
+            )));
+        }
+    })
+}
+
+/// `value` if it is in `range`; out-of-range settings are an error rather
+/// than clamped, since clamping would silently change what was written.
+fn config_in_range(name: &str, value: i64, range: RangeInclusive<i64>) -> ZarrResult<i64> {
+    if range.contains(&value) {
+        Ok(value)
+    } else {
+        Err(ZarrError::Metadata(format!(
+            "Invalid {name} {value}: expected {}..={}",
+            range.start(),
+            range.end()
+        )))
+    }
+}
+
+/// The canonical numcodecs id for a compressor id as other writers spell it,
+/// plus any level the id carries:
+///
+/// - case and surrounding whitespace are ignored (`"GZip"`, `" zstd"`);
+/// - a `numcodecs.` or `numcodecs:` prefix is dropped (`"numcodecs.zstd"`);
+/// - `"zstandard"` is `"zstd"`;
+/// - `"gzip-6"` is `"gzip"` at level 6, as is `"gzip-"` followed by any
+///   suffix that isn't a level (`"gzip-deflate"`).
+///
+/// A `level` in the compressor's configuration wins over one in the id.
+pub fn canonical_compressor_id(id: &str) -> (String, Option<i64>) {
+    let id = id.trim().to_lowercase();
+    let id = id
+        .strip_prefix("numcodecs.")
+        .or_else(|| id.strip_prefix("numcodecs:"))
+        .unwrap_or(&id);
+    if let Some(suffix) = id.strip_prefix("gzip-") {
+        return ("gzip".into(), suffix.parse().ok());
+    }
+    match id {
+        "zstandard" => ("zstd".into(), None),
+        other => (other.into(), None),
     }
 }
 
 fn blosc_codec_from_config(
     comp: &ZarrCompressor,
     fallback_cname: Option<BloscCname>,
-) -> BloscCodec {
+) -> ZarrResult<BloscCodec> {
     let cname = comp
         .config
         .get("cname")
@@ -82,36 +153,55 @@ fn blosc_codec_from_config(
 
     let clevel = get_config_int(&comp.config, "clevel")
         .or_else(|| get_config_int(&comp.config, "level"))
-        .unwrap_or(5) as i32;
-
-    let shuffle = comp.config.get("shuffle").and_then(|v| {
-        if let Some(n) = v.as_i64() {
-            match n {
-                0 => Some(BloscShuffle::NoShuffle),
-                1 => Some(BloscShuffle::Shuffle),
-                2 => Some(BloscShuffle::BitShuffle),
-                _ => None,
-            }
-        } else if let Some(s) = v.as_str() {
-            match s.to_lowercase().as_str() {
-                "noshuffle" | "0" => Some(BloscShuffle::NoShuffle),
-                "shuffle" | "1" => Some(BloscShuffle::Shuffle),
-                "bitshuffle" | "2" => Some(BloscShuffle::BitShuffle),
-                _ => None,
-            }
-        } else {
-            None
-        }
-    });
+        .unwrap_or(5);
+    let clevel = config_in_range("blosc clevel", clevel, 0..=9)? as i32;
 
-    let blocksize = get_config_int(&comp.config, "blocksize").unwrap_or(0) as usize;
+    let shuffle = comp
+        .config
+        .get("shuffle")
+        .map(parse_blosc_shuffle)
+        .transpose()?
+        .flatten();
+
+    let blocksize = get_config_int(&comp.config, "blocksize").unwrap_or(0);
+    let blocksize = config_in_range("blosc blocksize", blocksize, 0..=i64::MAX)? as usize;
 
-    BloscCodec {
+    Ok(BloscCodec {
         typesize: None,
         cname,
-        clevel: clevel.clamp(0, 9),
+        clevel,
         shuffle,
         blocksize,
+        nthreads: None,
+        max_nthreads: None,
+    })
+}
+
+/// A numcodecs blosc `shuffle` setting, by number or by name.  `-1` is
+/// numcodecs' AUTOSHUFFLE, which leaves the choice to the codec (`None`);
+/// anything else unrecognised is an error, like an out-of-range level.
+fn parse_blosc_shuffle(v: &serde_json::Value) -> ZarrResult<Option<BloscShuffle>> {
+    let shuffle = match v {
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(-1) => return Ok(None),
+            Some(0) => Some(BloscShuffle::NoShuffle),
+            Some(1) => Some(BloscShuffle::Shuffle),
+            Some(2) => Some(BloscShuffle::BitShuffle),
+            _ => None,
+        },
+        serde_json::Value::String(s) => match s.to_lowercase().as_str() {
+            "noshuffle" | "0" => Some(BloscShuffle::NoShuffle),
+            "shuffle" | "1" => Some(BloscShuffle::Shuffle),
+            "bitshuffle" | "2" => Some(BloscShuffle::BitShuffle),
+            _ => None,
+        },
+        _ => None,
+    };
+    match shuffle {
+        Some(shuffle) => Ok(Some(shuffle)),
+        None => Err(ZarrError::Metadata(format!(
+            "Invalid blosc shuffle {v}: expected 0..=2 or noshuffle, shuffle, bitshuffle"
+        ))),
     }
 }
 
@@ -134,15 +224,156 @@ fn get_config_int(config: &serde_json::Map<String, serde_json::Value>, key: &str
     })
 }
 
-/// Build the full codec list for a V2 array (compressor codecs + endian bytes codec).
-fn get_codec_equivalents(md: &ZarrV2Metadata) -> Vec<AnyCodec> {
-    let mut codecs = match &md.compressor {
-        Some(comp) => compressor_to_codecs(comp),
-        None => vec![],
-    };
+/// Convert a V2 filter JSON object to a codec.  Unknown filters are an
+/// error: skipping one would silently return undecoded data.
+pub fn filter_to_codec(filter: &ZarrCompressor) -> ZarrResult<AnyCodec> {
+    match filter.id.to_lowercase().as_str() {
+        "fixedscaleoffset" => {
+            let get_f64 = |key: &str, default: f64| {
+                filter.config.get(key).and_then(|v| v.as_f64()).unwrap_or(default)
+            };
+            let get_str = |key: &str| {
+                filter
+                    .config
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+                    .ok_or_else(|| {
+                        ZarrError::Codec(format!("fixedscaleoffset filter is missing '{key}'"))
+                    })
+            };
+            let dtype = get_str("dtype")?;
+            Ok(AnyCodec::FixedScaleOffset(FixedScaleOffsetCodec {
+                scale: get_f64("scale", 1.0),
+                offset: get_f64("offset", 0.0),
+                astype: get_str("astype").unwrap_or_else(|_| dtype.clone()),
+                dtype,
+            }))
+        }
+        "bitround" => {
+            let keepbits = get_config_int(&filter.config, "keepbits")
+                .and_then(|k| u32::try_from(k).ok())
+                .ok_or_else(|| {
+                    ZarrError::Codec("bitround filter needs a non-negative 'keepbits'".into())
+                })?;
+            Ok(AnyCodec::BitRound(BitRoundCodec::new(keepbits)))
+        }
+        _ => Err(ZarrError::UnsupportedFeature(FeatureId::Filter(
+            filter.id.clone(),
+        ))),
+    }
+}
+
+/// The numcodecs compressor object for `codec`; the inverse of
+/// [`compressor_to_codecs`].
+pub fn codec_to_compressor(codec: &AnyCodec) -> ZarrResult<serde_json::Value> {
+    Ok(match codec {
+        AnyCodec::Gzip(c) => serde_json::json!({"id": "gzip", "level": c.level}),
+        AnyCodec::Zlib(c) => serde_json::json!({"id": "zlib", "level": c.level}),
+        AnyCodec::Zstd(c) => serde_json::json!({"id": "zstd", "level": c.level}),
+        AnyCodec::Lz4(c) => serde_json::json!({"id": "lz4", "acceleration": c.acceleration}),
+        AnyCodec::Snappy(_) => serde_json::json!({"id": "snappy"}),
+        AnyCodec::Lzma(c) => serde_json::json!({
+            "id": "lzma",
+            "format": c.format,
+            "check": c.check,
+            "preset": c.preset,
+            "filters": c.filters,
+        }),
+        AnyCodec::Brotli(c) => {
+            serde_json::json!({"id": "brotli", "level": c.level, "lgwin": c.lgwin})
+        }
+        AnyCodec::Blosc(c) => {
+            let shuffle = match c.shuffle.unwrap_or(BloscShuffle::NoShuffle) {
+                BloscShuffle::NoShuffle => 0,
+                BloscShuffle::Shuffle => 1,
+                BloscShuffle::BitShuffle => 2,
+            };
+            serde_json::json!({
+                "id": "blosc",
+                "cname": c.cname.to_string(),
+                "clevel": c.clevel,
+                "shuffle": shuffle,
+                "blocksize": c.blocksize,
+            })
+        }
+        other => {
+            return Err(ZarrError::Codec(format!(
+                "{} cannot be used as a V2 compressor",
+                other.codec_id()
+            )));
+        }
+    })
+}
+
+/// The numcodecs filter object for `codec`; the inverse of
+/// [`filter_to_codec`].
+pub fn codec_to_filter(codec: &AnyCodec) -> ZarrResult<serde_json::Value> {
+    match codec {
+        AnyCodec::FixedScaleOffset(c) => Ok(serde_json::json!({
+            "id": "fixedscaleoffset",
+            "scale": c.scale,
+            "offset": c.offset,
+            "dtype": c.dtype,
+            "astype": c.astype,
+        })),
+        AnyCodec::BitRound(c) => Ok(serde_json::json!({
+            "id": "bitround",
+            "keepbits": c.keepbits,
+        })),
+        other => Err(ZarrError::Codec(format!(
+            "{} cannot be used as a V2 filter",
+            other.codec_id()
+        ))),
+    }
+}
+
+/// Build the full codec list for a V2 array: filters, then compressor codecs,
+/// then an endian bytes codec.  Decoding runs in reverse, so the compressor is
+/// undone before the filters, as in numcodecs.
+///
+/// The stored bytes are the last filter's output, so its NumPy byte order
+/// (when it has one) sets the bytes codec's endianness.
+pub(crate) fn get_codec_equivalents(md: &ZarrV2Metadata) -> ZarrResult<Vec<AnyCodec>> {
+    v2_codecs(
+        md.compressor.as_ref(),
+        md.filters.as_ref(),
+        md.dtype.byte_order,
+    )
+}
+
+/// [`get_codec_equivalents`] from the parts of the metadata it reads.
+pub(crate) fn v2_codecs(
+    compressor: Option<&ZarrCompressor>,
+    filters: Option<&serde_json::Value>,
+    mut byte_order: Endian,
+) -> ZarrResult<Vec<AnyCodec>> {
+    let mut codecs = Vec::new();
+    if let Some(filters) = filters.filter(|f| !f.is_null()) {
+        let filters: Vec<ZarrCompressor> = serde_json::from_value(filters.clone())
+            .map_err(|e| ZarrError::Metadata(format!("Invalid filters: {e}")))?;
+        for filter in &filters {
+            let codec = filter_to_codec(filter)?;
+            if let AnyCodec::FixedScaleOffset(c) = &codec
+                && let Ok(astype) = parse_numpy_dtype(&c.astype)
+            {
+                byte_order = astype.byte_order;
+            }
+            codecs.push(codec);
+        }
+    }
+    if let Some(comp) = compressor {
+        let compressor = compressor_to_codecs(comp)?;
+        if !compressor.iter().all(|c| c.codec_id().is_compiled()) {
+            return Err(ZarrError::UnsupportedFeature(FeatureId::Compressor(
+                comp.id.clone(),
+            )));
+        }
+        codecs.extend(compressor);
+    }
     // Append a BytesCodec with the correct endianness
-    codecs.push(AnyCodec::Bytes(BytesCodec::new(md.dtype.byte_order)));
-    codecs
+    codecs.push(AnyCodec::Bytes(BytesCodec::new(byte_order)));
+    Ok(codecs)
 }
 
 // ---------------------------------------------------------------------------
@@ -154,7 +385,8 @@ fn create_v2_chunk_getter<S: StorageBackend + 'static>(
     base_path: String,
     md: ZarrV2Metadata,
 ) -> ChunkGetterFn {
-    let codecs = get_codec_equivalents(&md);
+    // Errors are kept as text so every call can report them.
+    let codecs = get_codec_equivalents(&md).map_err(|e| e.to_string());
     let md = Arc::new(md);
     let codecs = Arc::new(codecs);
 
@@ -171,23 +403,29 @@ fn create_v2_chunk_getter<S: StorageBackend + 'static>(
                 ));
             }
 
-            let key_str: String = key
-                .iter()
-                .map(|i| i.to_string())
-                .collect::<Vec<_>>()
-                .join(".");
-
+            let key_str = chunk_key_with_separator(&key, md.dimension_separator);
             if !md.keys.contains(&key_str) {
                 return Err(ZarrError::NotFound(format!(
                     "Storage key {key_str} not found"
                 )));
             }
 
+            let codecs = codecs
+                .as_ref()
+                .as_ref()
+                .map_err(|e| ZarrError::Codec(e.clone()))?;
             let chunk_path = store.join(&base_path, &key_str);
             let bytes = store.get(&chunk_path).await?;
 
             let raw: Option<&[u8]> = bytes.as_deref();
-            parse_chunk(raw, md.dtype.data_type, &md.chunks, &md.fill_value, &codecs).await
+            parse_chunk(
+                raw,
+                md.dtype.data_type,
+                &md.chunks,
+                &md.fill_value,
+                codecs,
+            )
+            .await
         })
     })
 }
@@ -197,22 +435,138 @@ fn create_v2_chunk_getter<S: StorageBackend + 'static>(
 // ---------------------------------------------------------------------------
 
 /// Open a Zarr V2 array, returning a `UnifiedZarrArray` ready for chunk access.
+///
+/// Uses the process-wide [`config::global`] configuration.
 pub async fn open<S: StorageBackend + 'static>(
     store: Arc<S>,
     path: &str,
+) -> ZarrResult<UnifiedZarrArray> {
+    open_with_config(store, path, config::global()).await
+}
+
+/// Open a Zarr V2 array with an explicit configuration.
+pub async fn open_with_config<S: StorageBackend + 'static>(
+    store: Arc<S>,
+    path: &str,
+    config: Arc<SimplezarrConfig>,
 ) -> ZarrResult<UnifiedZarrArray> {
     let zarray_path = store.join(path, ".zarray");
-    let bytes = store
-        .get(&zarray_path)
+    let (bytes, etag) = get_with_etag_retry(store.as_ref(), &zarray_path, &config)
         .await?
         .ok_or_else(|| ZarrError::NotFound(format!("No .zarray at {path}")))?;
 
-    let md = ZarrV2Metadata::parse(&bytes)?;
+    let md = ZarrV2Metadata::parse_with_max_chunks(&bytes, config.max_chunks)?;
+    let mut array = array_from_metadata(store, path.to_string(), &md, None, config)?;
+    array.metadata_etag = etag;
+    Ok(array)
+}
+
+/// Open a Zarr V2 array from a `.zarray` document the caller already has,
+/// such as one from a catalog service, without fetching it from the store.
+/// `.zattrs` isn't read either; see
+/// [`load_attributes`](UnifiedZarrArray::load_attributes).
+///
+/// Uses the process-wide [`config::global`] configuration.
+pub fn open_with_metadata<S: StorageBackend + 'static>(
+    store: Arc<S>,
+    path: &str,
+    metadata: &serde_json::Value,
+) -> ZarrResult<UnifiedZarrArray> {
+    open_with_metadata_and_config(store, path, metadata, config::global())
+}
+
+/// [`open_with_metadata`] with an explicit configuration.
+pub fn open_with_metadata_and_config<S: StorageBackend + 'static>(
+    store: Arc<S>,
+    path: &str,
+    metadata: &serde_json::Value,
+    config: Arc<SimplezarrConfig>,
+) -> ZarrResult<UnifiedZarrArray> {
+    let md =
+        ZarrV2Metadata::parse_with_max_chunks(&serde_json::to_vec(metadata)?, config.max_chunks)?;
+    if md.zarr_format != 2 {
+        return Err(ZarrError::UnsupportedFeature(FeatureId::ZarrFormat(
+            md.zarr_format,
+        )));
+    }
+    array_from_metadata(store, path.to_string(), &md, None, config)
+}
+
+/// Delete the V2 array at `path`: every chunk in its grid, then `.zattrs`
+/// and `.zarray`.
+///
+/// Uses the process-wide [`config::global`] configuration.
+pub async fn delete_array<S: StorageBackend + 'static>(
+    store: Arc<S>,
+    path: &str,
+) -> ZarrResult<()> {
+    delete_array_with_config(store, path, config::global()).await
+}
 
+/// Delete a V2 array with an explicit configuration.  At most
+/// `max_concurrency` chunks are deleted at once.
+///
+/// `.zarray` goes last, so an interrupted delete can be retried.  A parent
+/// group's consolidated `.zmetadata` is not updated.
+pub async fn delete_array_with_config<S: StorageBackend + 'static>(
+    store: Arc<S>,
+    path: &str,
+    config: Arc<SimplezarrConfig>,
+) -> ZarrResult<()> {
+    let array = open_with_config(store.clone(), path, config.clone()).await?;
+    futures::stream::iter(&array.metadata.keys)
+        .map(|key| {
+            let store = store.clone();
+            async move { store.delete(&store.join(path, key)).await }
+        })
+        .buffer_unordered(config.max_concurrency.max(1))
+        .try_collect::<Vec<()>>()
+        .await?;
+    store.delete(&store.join(path, ".zattrs")).await?;
+    store.delete(&store.join(path, ".zarray")).await
+}
+
+/// Dimension names from xarray's `_ARRAY_DIMENSIONS` attribute, if it names
+/// all `ndim` dimensions.
+pub(crate) fn dimension_names_from_attributes(
+    attributes: &Attributes,
+    ndim: usize,
+) -> Option<Vec<Option<String>>> {
+    attributes
+        .get_vec_str("_ARRAY_DIMENSIONS")
+        .filter(|dims| dims.len() == ndim)
+        .map(|dims| dims.into_iter().map(|d| Some(d.to_string())).collect())
+}
+
+/// Read the `.zattrs` at `path`, if there is one.
+pub(crate) async fn read_attributes(
+    store: &dyn StorageBackend,
+    path: &str,
+    config: &SimplezarrConfig,
+) -> ZarrResult<Option<Attributes>> {
+    get_with_retry(store, &store.join(path, ".zattrs"), config)
+        .await?
+        .as_deref()
+        .map(parse_attributes)
+        .transpose()
+}
+
+/// Build an array handle from parsed metadata, without touching the store.
+pub(crate) fn array_from_metadata(
+    store: Arc<dyn StorageBackend>,
+    path: String,
+    md: &ZarrV2Metadata,
+    attributes: Option<Attributes>,
+    config: Arc<SimplezarrConfig>,
+) -> ZarrResult<UnifiedZarrArray> {
+    let dimension_names = attributes
+        .as_ref()
+        .and_then(|attrs| dimension_names_from_attributes(attrs, md.shape.len()));
     let unified_md = UnifiedMetadata {
         shape: md.shape.clone(),
         chunk_shape: md.chunks.clone(),
         data_type: md.dtype.data_type,
+        byte_order: md.dtype.byte_order,
         fill_value: md.fill_value.clone(),
         order: md.order,
         zarr_format: md.zarr_format,
@@ -220,72 +574,77 @@ pub async fn open<S: StorageBackend + 'static>(
             compressor: md.compressor.clone(),
             filters: md.filters.clone(),
         },
-        attributes: None,
-        dimension_names: None,
+        attributes,
+        dimension_names,
+        extensions: serde_json::Map::new(),
+        dimension_separator: md.dimension_separator,
         keys: md.keys.clone(),
     };
 
-    Ok(UnifiedZarrArray {
-        metadata: unified_md,
-        store: store.clone(),
-        path: path.to_string(),
-        codecs: get_codec_equivalents(&md),
-    })
+    // Unsupported codecs fail on decode rather than here, so the array can
+    // still be inspected.
+    let codecs = match get_codec_equivalents(md) {
+        Ok(codecs) => Ok(codecs),
+        Err(ZarrError::UnsupportedFeature(feature)) => Err(feature),
+        Err(e) => return Err(e),
+    };
+    Ok(UnifiedZarrArray::new(
+        unified_md,
+        store,
+        path,
+        md.document.clone(),
+        codecs,
+        config,
+    ))
 }
 
 /// Open a group of V2 arrays. Tries `.zmetadata` (consolidated) first,
 /// falls back to opening each array individually.
+///
+/// Uses the process-wide [`config::global`] configuration.
 pub async fn open_group<S: StorageBackend + 'static>(
     store: Arc<S>,
     path: &str,
     array_names: &[&str],
+) -> ZarrResult<UnifiedZarrGroup> {
+    open_group_with_config(store, path, array_names, config::global()).await
+}
+
+/// Open a group of V2 arrays with an explicit configuration.  When falling
+/// back to per-array metadata, at most `max_concurrency` arrays are opened
+/// at once.
+pub async fn open_group_with_config<S: StorageBackend + 'static>(
+    store: Arc<S>,
+    path: &str,
+    array_names: &[&str],
+    config: Arc<SimplezarrConfig>,
 ) -> ZarrResult<UnifiedZarrGroup> {
     let zmetadata_path = store.join(path, ".zmetadata");
 
-    match store.get(&zmetadata_path).await? {
+    match get_with_retry(store.as_ref(), &zmetadata_path, &config).await? {
         Some(bytes) => {
             // Consolidated metadata
-            let consolidated = ZarrConsolidatedMetadata::parse(&bytes)?;
+            let consolidated =
+                ZarrConsolidatedMetadata::parse_with_max_chunks(&bytes, config.max_chunks)?;
             if consolidated.zarr_consolidated_format != 1 {
                 return Err(ZarrError::Metadata(
                     "Metadata is not in zarr-consolidated-v1 format".into(),
                 ));
             }
 
-            let mut arrays = HashMap::new();
+            let mut arrays = BTreeMap::new();
             for (name, md) in &consolidated.metadata {
-                let unified_md = UnifiedMetadata {
-                    shape: md.shape.clone(),
-                    chunk_shape: md.chunks.clone(),
-                    data_type: md.dtype.data_type,
-                    fill_value: md.fill_value.clone(),
-                    order: md.order,
-                    zarr_format: md.zarr_format,
-                    compression_info: CompressionInfo::V2Compression {
-                        compressor: md.compressor.clone(),
-                        filters: md.filters.clone(),
-                    },
-                    attributes: None,
-                    dimension_names: None,
-                    keys: md.keys.clone(),
-                };
-
                 let array_path = store.join(path, name);
-
+                let attributes = consolidated.attributes.get(name).cloned();
                 arrays.insert(
                     name.clone(),
-                    UnifiedZarrArray {
-                        metadata: unified_md,
-                        store: store.clone(),
-                        path: array_path,
-                        codecs: get_codec_equivalents(&md),
-                    },
+                    array_from_metadata(store.clone(), array_path, md, attributes, config.clone())?,
                 );
             }
 
             let group_md = UnifiedGroupMetadata {
                 zarr_format: 2,
-                attributes: None,
+                attributes: consolidated.attributes.get("").cloned(),
                 consolidated: true,
                 array_names: consolidated.metadata.keys().cloned().collect(),
                 path: path.to_string(),
@@ -294,33 +653,35 @@ pub async fn open_group<S: StorageBackend + 'static>(
             Ok(UnifiedZarrGroup {
                 metadata: group_md,
                 arrays,
+                derived: BTreeMap::new(),
             })
         }
         None => {
             // No consolidated metadata -- open arrays individually.
-            let mut arrays = HashMap::new();
+            let mut arrays = BTreeMap::new();
             let mut errors = Vec::new();
 
-            let handles: Vec<_> = array_names
-                .iter()
+            // Tasks are spawned lazily as the stream is polled, so no more than
+            // `max_concurrency` opens are in flight at once.
+            let mut results = futures::stream::iter(array_names)
                 .map(|name| {
                     let store = store.clone();
                     let array_path = store.join(path, name);
                     let name = name.to_string();
-                    tokio::spawn(async move {
-                        let result = open(store, &array_path).await;
+                    let config = config.clone();
+                    async move {
+                        let result = open_with_config(store, &array_path, config).await;
                         (name, result)
-                    })
+                    }
                 })
-                .collect();
+                .buffer_unordered(config.max_concurrency.max(1));
 
-            for handle in handles {
-                match handle.await {
-                    Ok((name, Ok(array))) => {
+            while let Some((name, result)) = results.next().await {
+                match result {
+                    Ok(array) => {
                         arrays.insert(name, array);
                     }
-                    Ok((_, Err(e))) => errors.push(e),
-                    Err(e) => errors.push(ZarrError::Other(format!("Task join error: {e}"))),
+                    Err(e) => errors.push(e),
                 }
             }
 
@@ -339,6 +700,7 @@ pub async fn open_group<S: StorageBackend + 'static>(
             Ok(UnifiedZarrGroup {
                 metadata: group_md,
                 arrays,
+                derived: BTreeMap::new(),
             })
         }
     }
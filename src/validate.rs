@@ -0,0 +1,768 @@
+//! Strict validation of a store against the Zarr specs.
+//!
+//! Opening an array is lenient: missing V2 fields get zarr-python's
+//! defaults, unknown ones are ignored and chunks are only read on demand.
+//! [`validate_store`] is not.  It checks the node at a path, and for a
+//! consolidated group every array in it:
+//!
+//! * metadata documents against the spec: required and unknown fields,
+//!   data types, fill values and `.zattrs`;
+//! * that every codec can be run by this build;
+//! * that consolidated `.zmetadata` matches the documents it copies;
+//! * a sample of chunks spread across each grid: how many are stored, and
+//!   that they decode to the right size (which also checks the CRCs and
+//!   checksums inside gzip, zstd and Blosc frames);
+//! * for chunks whose ETag is an MD5, as S3, GCS and MinIO report for
+//!   single-part uploads, that the ETag matches the bytes read.
+//!
+//! Problems go in the returned [`ValidationReport`], which serializes to
+//! JSON for CI and prints one line per issue:
+//!
+//! ```ignore
+//! let report = validate_store(store, "era5.zarr").await?;
+//! println!("{}", serde_json::to_string_pretty(&report)?);
+//! if !report.is_valid() { std::process::exit(1); }
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::array::{UnifiedZarrArray, parse_chunk_key};
+use crate::codecs::CodecPipeline;
+use crate::config::{self, SimplezarrConfig};
+use crate::error::{ZarrError, ZarrResult};
+use crate::metadata::gunzip_metadata;
+use crate::metadata::v2::{ZarrConsolidatedMetadata, ZarrV2Metadata, parse_attributes};
+use crate::metadata::v3::ZarrV3Metadata;
+use crate::options::ArrayReadOptions;
+use crate::store::{StorageBackend, get_with_etag_retry, get_with_retry};
+use crate::transformers::apply_storage_transformers;
+use crate::v2::array_from_metadata;
+use crate::write::etag_is_md5_of;
+
+/// Fields a V2 `.zarray` must have.
+const V2_REQUIRED: [&str; 8] = [
+    "zarr_format",
+    "shape",
+    "chunks",
+    "dtype",
+    "compressor",
+    "fill_value",
+    "order",
+    "filters",
+];
+
+/// Fields a V2 `.zarray` may have besides [`V2_REQUIRED`].
+const V2_OPTIONAL: [&str; 1] = ["dimension_separator"];
+
+/// Fields a V3 array's `zarr.json` must have.
+const V3_REQUIRED: [&str; 8] = [
+    "zarr_format",
+    "node_type",
+    "shape",
+    "data_type",
+    "chunk_grid",
+    "chunk_key_encoding",
+    "fill_value",
+    "codecs",
+];
+
+// ---------------------------------------------------------------------------
+// Options
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValidateOptions {
+    /// Chunks to read from each array, spread evenly across its grid.  0
+    /// checks metadata only.
+    pub sample_chunks: usize,
+    /// Compare MD5-shaped ETags of sampled chunks with the bytes read.
+    /// Turn off for S3 buckets with SSE-KMS or SSE-C encryption, whose
+    /// ETags look like MD5s but aren't.
+    pub verify_etags: bool,
+}
+
+impl Default for ValidateOptions {
+    fn default() -> Self {
+        Self {
+            sample_chunks: 16,
+            verify_etags: true,
+        }
+    }
+}
+
+impl ValidateOptions {
+    pub fn sample_chunks(mut self, sample_chunks: usize) -> Self {
+        self.sample_chunks = sample_chunks;
+        self
+    }
+
+    pub fn verify_etags(mut self, verify_etags: bool) -> Self {
+        self.verify_etags = verify_etags;
+        self
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Report
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// Worth knowing, but within the spec.
+    Info,
+    /// Allowed, but likely to trip up some readers.
+    Warning,
+    /// A spec violation or unreadable data.
+    Error,
+}
+
+/// What kind of problem an issue is, as a stable name for scripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// No Zarr metadata document at the path.
+    MissingMetadata,
+    /// A document that isn't valid JSON, or not an object.
+    InvalidJson,
+    /// A required field is absent.
+    MissingField,
+    /// A field the spec doesn't define.
+    UnknownField,
+    /// A field with an invalid value.
+    InvalidMetadata,
+    /// A valid feature this build can't read.
+    Unsupported,
+    /// `.zmetadata` disagrees with a document it copies.
+    StaleConsolidated,
+    /// None of the sampled chunks are stored.
+    NoChunks,
+    /// A stored chunk that fails to decode.
+    ChunkDecode,
+    /// A chunk whose bytes don't match its MD5 ETag.
+    ChecksumMismatch,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub kind: IssueKind,
+    /// The store key the issue is about.
+    pub key: String,
+    pub message: String,
+}
+
+/// The outcome of [`validate_store`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub path: String,
+    pub arrays_checked: usize,
+    /// Sampled chunks that were stored, and so read and decoded.
+    pub chunks_read: usize,
+    /// Sampled chunks that were never written.
+    pub chunks_missing: usize,
+    /// Sampled chunks whose MD5 ETag was compared.
+    pub checksums_verified: usize,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Whether no issue is an [`Error`](Severity::Error).
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+    }
+
+    fn push(&mut self, severity: Severity, kind: IssueKind, key: &str, message: String) {
+        self.issues.push(ValidationIssue {
+            severity,
+            kind,
+            key: key.to_string(),
+            message,
+        });
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {} arrays, {} chunks read, {} missing, {} checksums verified",
+            self.path,
+            self.arrays_checked,
+            self.chunks_read,
+            self.chunks_missing,
+            self.checksums_verified
+        )?;
+        for issue in &self.issues {
+            let severity = match issue.severity {
+                Severity::Info => "info",
+                Severity::Warning => "warning",
+                Severity::Error => "error",
+            };
+            writeln!(f, "{severity:<7} {}: {}", issue.key, issue.message)?;
+        }
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Validation
+// ---------------------------------------------------------------------------
+
+/// Validate the node at `path` with [`ValidateOptions::default`].
+///
+/// Uses the process-wide [`config::global`] configuration.
+pub async fn validate_store<S: StorageBackend + 'static>(
+    store: Arc<S>,
+    path: &str,
+) -> ZarrResult<ValidationReport> {
+    validate_store_with_options(store, path, &ValidateOptions::default(), config::global()).await
+}
+
+/// Validate the V2 or V3 array or group at `path`.
+///
+/// Groups are checked through their consolidated `.zmetadata`, since
+/// stores can't be listed reliably; a V2 group without one gets its
+/// `.zgroup` checked and a warning.  Problems with the data are reported,
+/// not returned: only storage failures are errors.
+pub async fn validate_store_with_options<S: StorageBackend + 'static>(
+    store: Arc<S>,
+    path: &str,
+    options: &ValidateOptions,
+    config: Arc<SimplezarrConfig>,
+) -> ZarrResult<ValidationReport> {
+    let mut validator = Validator {
+        store,
+        options,
+        config,
+        report: ValidationReport {
+            path: path.to_string(),
+            ..ValidationReport::default()
+        },
+    };
+    validator.node(path).await?;
+    Ok(validator.report)
+}
+
+struct Validator<'a, S> {
+    store: Arc<S>,
+    options: &'a ValidateOptions,
+    config: Arc<SimplezarrConfig>,
+    report: ValidationReport,
+}
+
+impl<S: StorageBackend + 'static> Validator<'_, S> {
+    async fn get(&self, key: &str) -> ZarrResult<Option<bytes::Bytes>> {
+        get_with_retry(self.store.as_ref(), key, &self.config).await
+    }
+
+    /// Parse `bytes` as a JSON object, reporting it if it isn't one.
+    fn json_object(&mut self, key: &str, bytes: &[u8]) -> Option<Value> {
+        match serde_json::from_slice::<Value>(bytes) {
+            Ok(value) if value.is_object() => Some(value),
+            Ok(value) => {
+                self.report.push(
+                    Severity::Error,
+                    IssueKind::InvalidJson,
+                    key,
+                    format!("Expected a JSON object, got {value}"),
+                );
+                None
+            }
+            Err(e) => {
+                self.report.push(
+                    Severity::Error,
+                    IssueKind::InvalidJson,
+                    key,
+                    format!("Invalid JSON: {e}"),
+                );
+                None
+            }
+        }
+    }
+
+    /// `bytes` gunzipped if need be, as `.zmetadata` and `zarr.json` may
+    /// be; see [`gunzip_metadata`].
+    fn gunzip(&mut self, key: &str, bytes: &[u8]) -> Option<Vec<u8>> {
+        match gunzip_metadata(bytes) {
+            Ok(bytes) => Some(bytes.into_owned()),
+            Err(e) => {
+                self.report
+                    .push(Severity::Error, IssueKind::InvalidJson, key, e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Report an error from a metadata parser under `key`.
+    fn parse_error(&mut self, key: &str, error: ZarrError) {
+        let kind = match error {
+            ZarrError::UnsupportedFeature(_) => IssueKind::Unsupported,
+            _ => IssueKind::InvalidMetadata,
+        };
+        self.report
+            .push(Severity::Error, kind, key, error.to_string());
+    }
+
+    async fn node(&mut self, path: &str) -> ZarrResult<()> {
+        let zarray_key = self.store.join(path, ".zarray");
+        if let Some(bytes) = self.get(&zarray_key).await? {
+            return self.v2_array(path, &bytes, None).await;
+        }
+        let zmetadata_key = self.store.join(path, ".zmetadata");
+        if let Some(bytes) = self.get(&zmetadata_key).await? {
+            self.v2_group(path).await?;
+            let Some(bytes) = self.gunzip(&zmetadata_key, &bytes) else {
+                return Ok(());
+            };
+            return self.consolidated(path, &zmetadata_key, &bytes).await;
+        }
+        let zgroup_key = self.store.join(path, ".zgroup");
+        if self.get(&zgroup_key).await?.is_some() {
+            self.v2_group(path).await?;
+            self.report.push(
+                Severity::Warning,
+                IssueKind::MissingMetadata,
+                &zmetadata_key,
+                "Group has no consolidated metadata, so its arrays weren't checked".into(),
+            );
+            return Ok(());
+        }
+        let zarr_json_key = self.store.join(path, "zarr.json");
+        if let Some(bytes) = self.get(&zarr_json_key).await? {
+            let Some(bytes) = self.gunzip(&zarr_json_key, &bytes) else {
+                return Ok(());
+            };
+            return self.v3_node(&zarr_json_key, &bytes).await;
+        }
+        self.report.push(
+            Severity::Error,
+            IssueKind::MissingMetadata,
+            path,
+            "No .zarray, .zgroup, .zmetadata or zarr.json".into(),
+        );
+        Ok(())
+    }
+
+    async fn v2_group(&mut self, path: &str) -> ZarrResult<()> {
+        let key = self.store.join(path, ".zgroup");
+        match self.get(&key).await? {
+            Some(bytes) => {
+                if let Some(zgroup) = self.json_object(&key, &bytes)
+                    && zgroup.get("zarr_format") != Some(&Value::from(2))
+                {
+                    self.report.push(
+                        Severity::Error,
+                        IssueKind::InvalidMetadata,
+                        &key,
+                        format!("zarr_format must be 2, got {}", zgroup["zarr_format"]),
+                    );
+                }
+            }
+            None => self.report.push(
+                Severity::Error,
+                IssueKind::MissingMetadata,
+                &key,
+                "Consolidated group has no .zgroup".into(),
+            ),
+        }
+        self.zattrs(path).await
+    }
+
+    async fn zattrs(&mut self, path: &str) -> ZarrResult<()> {
+        let key = self.store.join(path, ".zattrs");
+        if let Some(bytes) = self.get(&key).await?
+            && let Err(e) = parse_attributes(&bytes)
+        {
+            self.parse_error(&key, e);
+        }
+        Ok(())
+    }
+
+    /// Check the arrays listed in `.zmetadata` and that its copies of their
+    /// documents are current.
+    async fn consolidated(&mut self, path: &str, key: &str, bytes: &[u8]) -> ZarrResult<()> {
+        let Some(document) = self.json_object(key, bytes) else {
+            return Ok(());
+        };
+        let consolidated = match ZarrConsolidatedMetadata::parse(bytes) {
+            Ok(consolidated) => consolidated,
+            Err(e) => {
+                self.parse_error(key, e);
+                return Ok(());
+            }
+        };
+        if consolidated.zarr_consolidated_format != 1 {
+            self.report.push(
+                Severity::Error,
+                IssueKind::InvalidMetadata,
+                key,
+                format!(
+                    "zarr_consolidated_format must be 1, got {}",
+                    consolidated.zarr_consolidated_format
+                ),
+            );
+        }
+        let Some(entries) = document.get("metadata").and_then(Value::as_object) else {
+            return Ok(());
+        };
+        for (entry, copy) in entries {
+            if entry.ends_with(".zattrs") && !copy.is_object() {
+                self.report.push(
+                    Severity::Error,
+                    IssueKind::InvalidMetadata,
+                    key,
+                    format!("Entry {entry} must be an object"),
+                );
+            }
+            let stored_key = self.store.join(path, entry);
+            match self.get(&stored_key).await? {
+                Some(stored) => {
+                    if serde_json::from_slice::<Value>(&stored).ok().as_ref() != Some(copy) {
+                        self.report.push(
+                            Severity::Error,
+                            IssueKind::StaleConsolidated,
+                            key,
+                            format!("Entry {entry} differs from {stored_key}"),
+                        );
+                    }
+                }
+                None => self.report.push(
+                    Severity::Error,
+                    IssueKind::StaleConsolidated,
+                    key,
+                    format!("Entry {entry} has no {stored_key}"),
+                ),
+            }
+        }
+        for name in entries
+            .keys()
+            .filter_map(|entry| entry.strip_suffix("/.zarray"))
+        {
+            let array_path = self.store.join(path, name);
+            let bytes = serde_json::to_vec(&entries[&format!("{name}/.zarray")])?;
+            self.v2_array(&array_path, &bytes, Some(name)).await?;
+        }
+        Ok(())
+    }
+
+    /// Check the V2 array at `path` whose `.zarray` is `bytes`; `name` is
+    /// its name in a consolidated group, whose `.zattrs` were checked there.
+    async fn v2_array(&mut self, path: &str, bytes: &[u8], name: Option<&str>) -> ZarrResult<()> {
+        self.report.arrays_checked += 1;
+        let key = self.store.join(path, ".zarray");
+        if name.is_none() {
+            self.zattrs(path).await?;
+        }
+        let Some(document) = self.json_object(&key, bytes) else {
+            return Ok(());
+        };
+        let fields = document.as_object().into_iter().flat_map(|o| o.keys());
+        for field in fields {
+            if !V2_REQUIRED.contains(&field.as_str()) && !V2_OPTIONAL.contains(&field.as_str()) {
+                self.report.push(
+                    Severity::Warning,
+                    IssueKind::UnknownField,
+                    &key,
+                    format!("Unknown field '{field}'"),
+                );
+            }
+        }
+        for field in V2_REQUIRED {
+            if document.get(field).is_none() {
+                self.report.push(
+                    Severity::Error,
+                    IssueKind::MissingField,
+                    &key,
+                    format!("Missing '{field}' field"),
+                );
+            }
+        }
+        if let Some(format) = document.get("zarr_format")
+            && format != &Value::from(2)
+        {
+            self.report.push(
+                Severity::Error,
+                IssueKind::InvalidMetadata,
+                &key,
+                format!("zarr_format must be 2, got {format}"),
+            );
+        }
+        let md = match ZarrV2Metadata::parse(bytes) {
+            Ok(md) => md,
+            Err(e) => {
+                self.parse_error(&key, e);
+                return Ok(());
+            }
+        };
+        let array = match array_from_metadata(
+            self.store.clone(),
+            path.to_string(),
+            &md,
+            None,
+            self.config.clone(),
+        ) {
+            Ok(array) => array,
+            Err(e) => {
+                self.parse_error(&key, e);
+                return Ok(());
+            }
+        };
+        if let Some(feature) = array.unsupported_codec() {
+            self.report.push(
+                Severity::Error,
+                IssueKind::Unsupported,
+                &key,
+                format!("Chunks can't be decoded: unsupported {feature}"),
+            );
+            return Ok(());
+        }
+        self.sample_v2_chunks(&array).await
+    }
+
+    async fn sample_v2_chunks(&mut self, array: &UnifiedZarrArray) -> ZarrResult<()> {
+        let md = &array.metadata;
+        let codecs = array.codecs()?;
+        let keys = sample(&md.keys, self.options.sample_chunks);
+        let mut stored = 0;
+        for key_str in &keys {
+            let chunk_key = self.store.join(&array.path, key_str);
+            let Some(raw) = self.fetch_chunk(self.store.clone(), &chunk_key).await? else {
+                continue;
+            };
+            stored += 1;
+            let key = parse_chunk_key(key_str, md.shape.len());
+            if let Err(e) = array.decode_chunk(&key, key_str, Some(&raw), codecs).await {
+                self.chunk_error(&chunk_key, e);
+            }
+        }
+        self.no_chunks(&array.path, keys.len(), stored);
+        Ok(())
+    }
+
+    async fn v3_node(&mut self, key: &str, bytes: &[u8]) -> ZarrResult<()> {
+        let Some(document) = self.json_object(key, bytes) else {
+            return Ok(());
+        };
+        if document.get("zarr_format") != Some(&Value::from(3)) {
+            self.report.push(
+                Severity::Error,
+                IssueKind::InvalidMetadata,
+                key,
+                format!("zarr_format must be 3, got {}", document["zarr_format"]),
+            );
+            return Ok(());
+        }
+        match document.get("node_type").and_then(Value::as_str) {
+            Some("array") => {}
+            Some("group") => {
+                if let Some(attributes) = document.get("attributes")
+                    && !attributes.is_object()
+                {
+                    self.report.push(
+                        Severity::Error,
+                        IssueKind::InvalidMetadata,
+                        key,
+                        "'attributes' must be an object".into(),
+                    );
+                }
+                self.report.push(
+                    Severity::Info,
+                    IssueKind::MissingMetadata,
+                    key,
+                    "V3 groups don't list their members, so they weren't checked".into(),
+                );
+                return Ok(());
+            }
+            other => {
+                self.report.push(
+                    Severity::Error,
+                    IssueKind::InvalidMetadata,
+                    key,
+                    format!("node_type must be \"array\" or \"group\", got {other:?}"),
+                );
+                return Ok(());
+            }
+        }
+
+        self.report.arrays_checked += 1;
+        for field in V3_REQUIRED {
+            if document.get(field).is_none() {
+                self.report.push(
+                    Severity::Error,
+                    IssueKind::MissingField,
+                    key,
+                    format!("Missing '{field}' field"),
+                );
+            }
+        }
+        let md = match ZarrV3Metadata::parse(bytes) {
+            Ok(md) => md,
+            Err(e) => {
+                self.parse_error(key, e);
+                return Ok(());
+            }
+        };
+        let path = key.strip_suffix("zarr.json").unwrap_or(key);
+        let path = path.trim_end_matches('/');
+        let store = match apply_storage_transformers(self.store.clone(), &md.storage_transformers) {
+            Ok(store) => store,
+            Err(e) => {
+                self.parse_error(key, e);
+                return Ok(());
+            }
+        };
+        let pipeline = CodecPipeline::new(&md.codecs)?;
+        let grid = grid_shape(&md.shape, &md.chunks);
+        let count = grid.iter().try_fold(1usize, |n, g| n.checked_mul(*g));
+        let elements = md.chunks.iter().try_fold(1usize, |n, c| n.checked_mul(*c));
+        let itemsize = md.data_type.byte_size().unwrap_or(1);
+        let (Some(count), Some(elements)) = (
+            count,
+            elements.filter(|e| e.checked_mul(itemsize).is_some()),
+        ) else {
+            self.report.push(
+                Severity::Error,
+                IssueKind::InvalidMetadata,
+                key,
+                format!(
+                    "shape {:?} with chunks {:?} is too large to address",
+                    md.shape, md.chunks
+                ),
+            );
+            return Ok(());
+        };
+        let keys: Vec<String> = sample_indices(&grid, count, self.options.sample_chunks)
+            .iter()
+            .map(|index| md.chunk_key_encoding.key(index))
+            .collect();
+        let mut stored = 0;
+        for key_str in &keys {
+            let chunk_key = store.join(path, key_str);
+            let Some(raw) = self.fetch_chunk(store.clone(), &chunk_key).await? else {
+                continue;
+            };
+            stored += 1;
+            let options = ArrayReadOptions::default();
+            if let Err(e) = pipeline
+                .decode(&raw, key_str, md.data_type, elements, &options)
+                .await
+            {
+                self.chunk_error(&chunk_key, e);
+            }
+        }
+        self.no_chunks(path, keys.len(), stored);
+        Ok(())
+    }
+
+    /// Fetch a sampled chunk, comparing its ETag with its MD5 if asked to.
+    async fn fetch_chunk(
+        &mut self,
+        store: Arc<dyn StorageBackend>,
+        key: &str,
+    ) -> ZarrResult<Option<bytes::Bytes>> {
+        if !self.options.verify_etags {
+            let raw = get_with_retry(store.as_ref(), key, &self.config).await?;
+            self.count_chunk(raw.is_some());
+            return Ok(raw);
+        }
+        let Some((raw, etag)) = get_with_etag_retry(store.as_ref(), key, &self.config).await?
+        else {
+            self.count_chunk(false);
+            return Ok(None);
+        };
+        self.count_chunk(true);
+        if let Some(etag) = etag.filter(|etag| looks_like_md5(etag)) {
+            self.report.checksums_verified += 1;
+            if !etag_is_md5_of(&etag, &Md5::digest(&raw)) {
+                self.report.push(
+                    Severity::Error,
+                    IssueKind::ChecksumMismatch,
+                    key,
+                    format!("Stored bytes don't match ETag {etag}"),
+                );
+            }
+        }
+        Ok(Some(raw))
+    }
+
+    fn count_chunk(&mut self, stored: bool) {
+        if stored {
+            self.report.chunks_read += 1;
+        } else {
+            self.report.chunks_missing += 1;
+        }
+    }
+
+    fn chunk_error(&mut self, key: &str, error: ZarrError) {
+        self.report.push(
+            Severity::Error,
+            IssueKind::ChunkDecode,
+            key,
+            error.to_string(),
+        );
+    }
+
+    /// Warn when none of `sampled` chunks are stored: the array may be
+    /// empty, or its chunk keys wrong (a mistaken `dimension_separator`).
+    fn no_chunks(&mut self, path: &str, sampled: usize, stored: usize) {
+        if sampled > 0 && stored == 0 {
+            self.report.push(
+                Severity::Warning,
+                IssueKind::NoChunks,
+                path,
+                format!("None of the {sampled} sampled chunks are stored"),
+            );
+        }
+    }
+}
+
+/// Up to `n` of `keys`, evenly spread.
+fn sample(keys: &[String], n: usize) -> Vec<String> {
+    let n = n.min(keys.len());
+    (0..n).map(|i| keys[i * keys.len() / n].clone()).collect()
+}
+
+/// Up to `n` chunk indices of a grid of `count` chunks, evenly spread in
+/// C order.  The grid isn't listed, so a huge one costs nothing.
+fn sample_indices(grid: &[usize], count: usize, n: usize) -> Vec<Vec<usize>> {
+    let n = n.min(count);
+    (0..n)
+        .map(|i| {
+            let mut linear = (i as u128 * count as u128 / n as u128) as usize;
+            let mut index = vec![0; grid.len()];
+            for (d, &len) in grid.iter().enumerate().rev() {
+                index[d] = linear % len;
+                linear /= len;
+            }
+            index
+        })
+        .collect()
+}
+
+fn grid_shape(shape: &[usize], chunks: &[usize]) -> Vec<usize> {
+    shape
+        .iter()
+        .zip(chunks)
+        .map(|(s, c)| s.div_ceil(*c))
+        .collect()
+}
+
+/// Whether `etag` is 32 hex digits, as an MD5 ETag is.  Multipart uploads
+/// get a `-<parts>` suffix and aren't.
+fn looks_like_md5(etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/").trim_matches('"');
+    etag.len() == 32 && etag.bytes().all(|b| b.is_ascii_hexdigit())
+}
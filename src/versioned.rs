@@ -0,0 +1,222 @@
+//! Reading a store as of a snapshot.
+//!
+//! A reader of an array that is being rewritten can see a new `.zarray`
+//! next to old chunks, or half of a rewrite.  [`VersionedStore`] pins every
+//! key it reads to one version, recorded in a [`VersionManifest`] of
+//! key → ETag / version id, and fetches that version with
+//! [`StorageBackend::get_pinned`].  Stores that keep old versions (S3 with
+//! versioning, GCS generations) keep serving the snapshot after keys are
+//! overwritten; on others, a key that changed fails the read rather than
+//! mixing versions.
+//!
+//! A manifest taken up front with [`VersionManifest::capture_array`] or
+//! [`capture_group`](VersionManifest::capture_group) pins the whole array
+//! or consolidated group at once.  It serializes with serde, so other
+//! readers can be handed the same snapshot.
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::error::{ZarrError, ZarrResult};
+use crate::metadata::v2::{ZarrConsolidatedMetadata, ZarrV2Metadata};
+use crate::store::{ListEntry, ObjectVersion, StorageBackend};
+
+// ---------------------------------------------------------------------------
+// VersionManifest
+// ---------------------------------------------------------------------------
+
+/// The version each key of a snapshot is pinned to; `None` pins a key as
+/// missing, so values written after the snapshot stay invisible.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionManifest {
+    pub entries: BTreeMap<String, Option<ObjectVersion>>,
+}
+
+impl VersionManifest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Pin `paths` at their current versions, at most `max_concurrency` of
+    /// the global configuration at a time.
+    pub async fn capture<S: StorageBackend + ?Sized>(
+        store: &S,
+        paths: impl IntoIterator<Item = String>,
+    ) -> ZarrResult<Self> {
+        let entries = futures::stream::iter(paths)
+            .map(|path| async move {
+                let meta = store.head(&path).await?;
+                Ok::<_, ZarrError>((path, meta.map(ObjectVersion::from)))
+            })
+            .buffer_unordered(config::global().max_concurrency.max(1))
+            .try_collect()
+            .await?;
+        Ok(VersionManifest { entries })
+    }
+
+    /// Pin the V2 array at `path`: its `.zarray` and `.zattrs`, and every
+    /// chunk in the grid that `.zarray` describes.
+    pub async fn capture_array<S: StorageBackend + ?Sized>(
+        store: &S,
+        path: &str,
+    ) -> ZarrResult<Self> {
+        let zarray_path = store.join(path, ".zarray");
+        let (pin, bytes) = pin_document(store, &zarray_path).await?;
+        let bytes = bytes.ok_or_else(|| ZarrError::NotFound(format!("No .zarray at {path}")))?;
+        let md = ZarrV2Metadata::parse(&bytes)?;
+
+        let mut paths = vec![store.join(path, ".zattrs")];
+        paths.extend(md.keys.iter().map(|key| store.join(path, key)));
+        let mut manifest = Self::capture(store, paths).await?;
+        manifest.entries.insert(zarray_path, pin);
+        Ok(manifest)
+    }
+
+    /// Pin the consolidated V2 group at `path`: its `.zmetadata`, and every
+    /// chunk of the arrays it lists.
+    pub async fn capture_group<S: StorageBackend + ?Sized>(
+        store: &S,
+        path: &str,
+    ) -> ZarrResult<Self> {
+        let zmetadata_path = store.join(path, ".zmetadata");
+        let (pin, bytes) = pin_document(store, &zmetadata_path).await?;
+        let bytes = bytes
+            .ok_or_else(|| ZarrError::NotFound(format!("No consolidated .zmetadata at {path}")))?;
+        let consolidated = ZarrConsolidatedMetadata::parse(&bytes)?;
+
+        let mut paths = Vec::new();
+        for (name, md) in &consolidated.metadata {
+            let array_path = store.join(path, name);
+            paths.extend(md.keys.iter().map(|key| store.join(&array_path, key)));
+        }
+        let mut manifest = Self::capture(store, paths).await?;
+        manifest.entries.insert(zmetadata_path, pin);
+        Ok(manifest)
+    }
+}
+
+/// Pin a metadata document and read that version of it, so the keys listed
+/// in it belong to the same snapshot.
+async fn pin_document<S: StorageBackend + ?Sized>(
+    store: &S,
+    path: &str,
+) -> ZarrResult<(Option<ObjectVersion>, Option<Bytes>)> {
+    let Some(meta) = store.head(path).await? else {
+        return Ok((None, None));
+    };
+    let pin = ObjectVersion::from(meta);
+    let bytes = store.get_pinned(path, &pin).await?;
+    Ok((Some(pin), bytes))
+}
+
+// ---------------------------------------------------------------------------
+// VersionedStore
+// ---------------------------------------------------------------------------
+
+/// Serves every key at the version pinned in its [`VersionManifest`].
+///
+/// Keys missing from the manifest are pinned when first read, so repeated
+/// reads of a key agree; a snapshot across keys needs a manifest captured
+/// up front.  [`head`](StorageBackend::head) measures the pinned value.
+/// Listing is not versioned, and the store is read-only.
+pub struct VersionedStore<S> {
+    inner: S,
+    manifest: Mutex<VersionManifest>,
+}
+
+impl<S: StorageBackend> VersionedStore<S> {
+    /// Pin keys as they are first read.
+    pub fn new(inner: S) -> Self {
+        Self::with_manifest(inner, VersionManifest::new())
+    }
+
+    /// Read the snapshot recorded in `manifest`.
+    pub fn with_manifest(inner: S, manifest: VersionManifest) -> Self {
+        Self {
+            inner,
+            manifest: Mutex::new(manifest),
+        }
+    }
+
+    /// The versions pinned so far.
+    pub fn manifest(&self) -> VersionManifest {
+        self.lock().clone()
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VersionManifest> {
+        self.manifest.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The version `path` is pinned to, pinning its current one if it
+    /// isn't yet.
+    async fn pin(&self, path: &str) -> ZarrResult<Option<ObjectVersion>> {
+        if let Some(pin) = self.lock().entries.get(path) {
+            return Ok(pin.clone());
+        }
+        let current = self.inner.head(path).await?.map(ObjectVersion::from);
+        // A concurrent read may have pinned it meanwhile; the first pin wins.
+        Ok(self
+            .lock()
+            .entries
+            .entry(path.to_string())
+            .or_insert(current)
+            .clone())
+    }
+}
+
+#[async_trait]
+impl<S: StorageBackend> StorageBackend for VersionedStore<S> {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        match self.pin(path).await? {
+            Some(pin) => self.inner.get_pinned(path, &pin).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> ZarrResult<Vec<String>> {
+        self.inner.list(prefix).await
+    }
+
+    fn list_stream<'a>(&'a self, prefix: &'a str) -> BoxStream<'a, ZarrResult<ListEntry>> {
+        self.inner.list_stream(prefix)
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        self.inner.join(base, segment)
+    }
+
+    async fn get_with_etag(&self, path: &str) -> ZarrResult<Option<(Bytes, Option<String>)>> {
+        let Some(pin) = self.pin(path).await? else {
+            return Ok(None);
+        };
+        let data = self.inner.get_pinned(path, &pin).await?;
+        Ok(data.map(|data| (data, pin.etag)))
+    }
+
+    async fn etag(&self, path: &str) -> ZarrResult<Option<String>> {
+        Ok(self.pin(path).await?.and_then(|pin| pin.etag))
+    }
+
+    async fn get_pinned(&self, path: &str, pin: &ObjectVersion) -> ZarrResult<Option<Bytes>> {
+        self.inner.get_pinned(path, pin).await
+    }
+}
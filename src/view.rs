@@ -0,0 +1,266 @@
+//! Lazy views over an array.
+//!
+//! An [`ArrayView`] records slicing, indexing, squeezing, inserted axes,
+//! transposes and broadcasts without reading anything.  Reading a view
+//! issues one [`UnifiedZarrArray::read_region`] for the part of the array it
+//! covers and rearranges the result, so a chain of view operations never
+//! materializes intermediates.
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::array::{UnifiedZarrArray, strides, validate_region};
+use crate::error::{ZarrError, ZarrResult};
+
+// ---------------------------------------------------------------------------
+// ArrayView
+// ---------------------------------------------------------------------------
+
+/// One axis of a view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ViewAxis {
+    /// Array dimension `dim`, over the view's source range for it.
+    Source(usize),
+    /// An axis of the given length repeating a single element: an inserted
+    /// axis, or a broadcast length-1 dimension.
+    Repeat(usize),
+}
+
+/// A lazily sliced, transposed or broadcast view of an array.
+///
+/// Views are cheap to clone and every operation returns a new view.  Reads
+/// return values in C (row-major) order over the view's shape, whatever
+/// the array's own memory order.
+#[derive(Debug, Clone)]
+pub struct ArrayView {
+    array: Arc<UnifiedZarrArray>,
+    /// The range read from each array dimension.  Dimensions with no view
+    /// axis (indexed or squeezed away) have length 1.
+    source: Vec<Range<usize>>,
+    axes: Vec<ViewAxis>,
+}
+
+impl ArrayView {
+    /// A view of the whole array.
+    pub fn new(array: Arc<UnifiedZarrArray>) -> Self {
+        let source = array.full_region();
+        let axes = (0..source.len()).map(ViewAxis::Source).collect();
+        Self {
+            array,
+            source,
+            axes,
+        }
+    }
+
+    /// The underlying array.
+    pub fn array(&self) -> &UnifiedZarrArray {
+        &self.array
+    }
+
+    /// The view's shape.
+    pub fn shape(&self) -> Vec<usize> {
+        self.axes.iter().map(|axis| self.axis_len(*axis)).collect()
+    }
+
+    pub fn ndim(&self) -> usize {
+        self.axes.len()
+    }
+
+    /// The array's chunk length along each view axis.  Inserted and
+    /// broadcast axes are one chunk long.
+    pub fn chunk_shape(&self) -> Vec<usize> {
+        self.axes
+            .iter()
+            .map(|axis| match *axis {
+                ViewAxis::Source(dim) => self.array.metadata.chunk_shape[dim],
+                ViewAxis::Repeat(len) => len.max(1),
+            })
+            .collect()
+    }
+
+    /// How far into its first chunk the view starts along each view axis,
+    /// so blocks can follow the array's chunk grid.
+    pub(crate) fn chunk_offset(&self) -> Vec<usize> {
+        self.axes
+            .iter()
+            .map(|axis| match *axis {
+                ViewAxis::Source(dim) => {
+                    self.source[dim].start % self.array.metadata.chunk_shape[dim].max(1)
+                }
+                ViewAxis::Repeat(_) => 0,
+            })
+            .collect()
+    }
+
+    fn axis_len(&self, axis: ViewAxis) -> usize {
+        match axis {
+            ViewAxis::Source(dim) => self.source[dim].len(),
+            ViewAxis::Repeat(len) => len,
+        }
+    }
+
+    fn check_axis(&self, axis: usize) -> ZarrResult<()> {
+        if axis >= self.axes.len() {
+            return Err(ZarrError::Other(format!(
+                "Axis {axis} out of range for a {}-dimensional view",
+                self.axes.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Restrict `axis` to `range`, relative to the view.
+    pub fn slice(mut self, axis: usize, range: Range<usize>) -> ZarrResult<Self> {
+        self.check_axis(axis)?;
+        let len = self.axis_len(self.axes[axis]);
+        if range.start > range.end || range.end > len {
+            return Err(ZarrError::Other(format!(
+                "Slice {range:?} out of bounds for axis {axis} of size {len}"
+            )));
+        }
+        match &mut self.axes[axis] {
+            ViewAxis::Source(dim) => {
+                let start = self.source[*dim].start;
+                self.source[*dim] = start + range.start..start + range.end;
+            }
+            ViewAxis::Repeat(len) => *len = range.len(),
+        }
+        Ok(self)
+    }
+
+    /// Select element `index` of `axis`, dropping the axis.
+    pub fn index(self, axis: usize, index: usize) -> ZarrResult<Self> {
+        self.slice(axis, index..index + 1)?.squeeze(axis)
+    }
+
+    /// Drop `axis`, which must have length 1.
+    pub fn squeeze(mut self, axis: usize) -> ZarrResult<Self> {
+        self.check_axis(axis)?;
+        let len = self.axis_len(self.axes[axis]);
+        if len != 1 {
+            return Err(ZarrError::Other(format!(
+                "Cannot squeeze axis {axis} of size {len}"
+            )));
+        }
+        self.axes.remove(axis);
+        Ok(self)
+    }
+
+    /// Insert a length-1 axis before `axis` (or at the end when `axis` is
+    /// the view's dimensionality).
+    pub fn expand_dims(mut self, axis: usize) -> ZarrResult<Self> {
+        if axis > self.axes.len() {
+            return Err(ZarrError::Other(format!(
+                "Cannot insert axis {axis} into a {}-dimensional view",
+                self.axes.len()
+            )));
+        }
+        self.axes.insert(axis, ViewAxis::Repeat(1));
+        Ok(self)
+    }
+
+    /// Reorder the axes: axis `i` of the result is axis `order[i]` of this
+    /// view.
+    pub fn transpose(mut self, order: &[usize]) -> ZarrResult<Self> {
+        let mut seen = vec![false; self.axes.len()];
+        if order.len() != self.axes.len()
+            || !order
+                .iter()
+                .all(|&axis| axis < seen.len() && !std::mem::replace(&mut seen[axis], true))
+        {
+            return Err(ZarrError::Other(format!(
+                "{order:?} is not a permutation of {} axes",
+                self.axes.len()
+            )));
+        }
+        self.axes = order.iter().map(|&axis| self.axes[axis]).collect();
+        Ok(self)
+    }
+
+    /// Broadcast to `shape` by NumPy's rules: missing leading axes are
+    /// inserted, and length-1 axes repeat to the target length.
+    pub fn broadcast_to(mut self, shape: &[usize]) -> ZarrResult<Self> {
+        let current = self.shape();
+        let incompatible =
+            || ZarrError::Other(format!("Cannot broadcast shape {current:?} to {shape:?}"));
+        let Some(extra) = shape.len().checked_sub(current.len()) else {
+            return Err(incompatible());
+        };
+        let mut axes = vec![ViewAxis::Repeat(1); extra];
+        axes.append(&mut self.axes);
+        for (axis, &target) in axes.iter_mut().zip(shape) {
+            let len = match *axis {
+                ViewAxis::Source(dim) => self.source[dim].len(),
+                ViewAxis::Repeat(len) => len,
+            };
+            if len == 1 {
+                *axis = ViewAxis::Repeat(target);
+            } else if len != target {
+                return Err(incompatible());
+            }
+        }
+        self.axes = axes;
+        Ok(self)
+    }
+
+    /// Read the whole view.
+    pub async fn read(&self) -> ZarrResult<Vec<f64>> {
+        let region: Vec<Range<usize>> = self.shape().into_iter().map(|len| 0..len).collect();
+        self.read_region(&region).await
+    }
+
+    /// Read `region` (one half-open range per view axis) with a single
+    /// region read of the array.
+    pub async fn read_region(&self, region: &[Range<usize>]) -> ZarrResult<Vec<f64>> {
+        validate_region(&self.shape(), region)?;
+        let out_shape: Vec<usize> = region.iter().map(|r| r.len()).collect();
+        let total: usize = out_shape.iter().product();
+        if total == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut source = self.source.clone();
+        for (axis, r) in self.axes.iter().zip(region) {
+            if let ViewAxis::Source(dim) = *axis {
+                let start = source[dim].start;
+                source[dim] = start + r.start..start + r.end;
+            }
+        }
+        let data = self.array.read_region(&source).await?;
+
+        // Walk the output in C order, tracking the offset into `data`.
+        let source_shape: Vec<usize> = source.iter().map(|r| r.len()).collect();
+        let source_strides = strides(&source_shape, self.array.metadata.order);
+        let axis_strides: Vec<usize> = self
+            .axes
+            .iter()
+            .map(|axis| match axis {
+                ViewAxis::Source(dim) => source_strides[*dim],
+                ViewAxis::Repeat(_) => 0,
+            })
+            .collect();
+        let mut out = Vec::with_capacity(total);
+        let mut index = vec![0; out_shape.len()];
+        let mut offset = 0;
+        for _ in 0..total {
+            out.push(data[offset]);
+            for axis in (0..index.len()).rev() {
+                index[axis] += 1;
+                offset += axis_strides[axis];
+                if index[axis] < out_shape[axis] {
+                    break;
+                }
+                offset -= axis_strides[axis] * index[axis];
+                index[axis] = 0;
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl UnifiedZarrArray {
+    /// A lazy [`ArrayView`] of the whole array.
+    pub fn view(&self) -> ArrayView {
+        ArrayView::new(Arc::new(self.clone()))
+    }
+}
@@ -0,0 +1,387 @@
+//! Chunk writing.
+//!
+//! Chunks are encoded with the array's codec pipeline and stored through
+//! [`StorageBackend::put`](crate::store::StorageBackend::put), so writing
+//! needs a backend that overrides it.  How chunks are written is set per
+//! array with [`ArrayWriteOptions`].
+
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Range;
+
+use bytes::Bytes;
+use futures::{StreamExt, TryStreamExt};
+use md5::{Digest, Md5};
+
+use crate::array::{
+    UnifiedMetadata, UnifiedZarrArray, chunk_copy_pairs, chunk_indices_for_region, parse_chunk_key,
+    strides, validate_region,
+};
+use crate::capabilities::FeatureId;
+use crate::codecs::CodecPipeline;
+use crate::error::{ZarrError, ZarrResult};
+use crate::metadata::v2::list_keys_with_separator;
+use crate::options::ArrayWriteOptions;
+use crate::runtime;
+use crate::store::get_with_retry;
+use crate::types::{Endian, ZarrValue, ZarrVectorValue, fill_chunk, scatter, zarr_vector_to_bytes};
+
+/// What [`UnifiedZarrArray::write_chunk`] did with a chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteOutcome {
+    /// The chunk was stored.
+    Written,
+    /// The key already held identical encoded bytes, so nothing was sent
+    /// (only with [`dedup`](ArrayWriteOptions::dedup)).
+    Unchanged,
+    /// Every element equals the fill value, so the chunk was not stored and
+    /// any stored copy was removed (unless
+    /// [`write_empty_chunks`](ArrayWriteOptions::write_empty_chunks) is set).
+    Empty,
+}
+
+impl UnifiedZarrArray {
+    /// Replace the options used for subsequent chunk writes.
+    pub fn with_write_options(mut self, options: ArrayWriteOptions) -> Self {
+        self.write_options = options;
+        self
+    }
+
+    pub fn write_options(&self) -> &ArrayWriteOptions {
+        &self.write_options
+    }
+
+    /// Encode `values`, a whole chunk in the array's memory order, and store
+    /// it as chunk `key`.
+    ///
+    /// `values` must hold exactly one chunk's worth of elements, edge chunks
+    /// included.  The chunk's entry in the decoded-chunk cache is dropped.
+    /// Elements are compared with the fill value bitwise, so a NaN fill
+    /// matches NaN elements and `-0.0` does not match `0.0`.
+    pub async fn write_chunk(
+        &self,
+        key: &[usize],
+        values: &ZarrVectorValue,
+    ) -> ZarrResult<WriteOutcome> {
+        let key_str = self.chunk_key(key)?;
+        let elements: usize = self.metadata.chunk_shape.iter().product();
+        if values.len() != elements {
+            return Err(ZarrError::Encode(format!(
+                "Chunk {key_str} has {} elements, expected {elements}",
+                values.len()
+            )));
+        }
+        let path = self.store.join(&self.path, &key_str);
+        if !self.write_options.write_empty_chunks && is_fill(values, &self.fill_scalar()) {
+            self.store.delete(&path).await?;
+            if let Some(cache) = &self.cache {
+                cache.remove(&key_str);
+            }
+            return Ok(WriteOutcome::Empty);
+        }
+        let pipeline = CodecPipeline::new(self.codecs()?)?;
+        let encoded = runtime::named(|| format!("write {path}"), pipeline.encode(values)).await?;
+        let encoded = Bytes::from(encoded);
+        if self.write_options.dedup && self.is_stored(&path, &encoded).await? {
+            return Ok(WriteOutcome::Unchanged);
+        }
+        self.store.put(&path, encoded).await?;
+        if let Some(cache) = &self.cache {
+            cache.remove(&key_str);
+        }
+        Ok(WriteOutcome::Written)
+    }
+
+    /// Overwrite a hyper-rectangular region with `values`, given in the
+    /// array's memory order.
+    ///
+    /// Each touched chunk is read, updated and written back, at most
+    /// `max_concurrency` at a time; chunks the region covers entirely are
+    /// written without being read.  Updates are not atomic: concurrent
+    /// writers to the same chunk can lose each other's changes.
+    pub async fn set_region(
+        &self,
+        region: &[Range<usize>],
+        values: &ZarrVectorValue,
+    ) -> ZarrResult<()> {
+        validate_region(&self.metadata.shape, region)?;
+        let total: usize = region.iter().map(|r| r.end - r.start).product();
+        if values.len() != total {
+            return Err(ZarrError::Encode(format!(
+                "Region {region:?} has {total} elements, got {} values",
+                values.len()
+            )));
+        }
+        let keys = chunk_indices_for_region(&self.metadata.chunk_shape, region);
+        futures::stream::iter(keys)
+            .map(|key| async move {
+                let pairs = chunk_copy_pairs(&self.metadata, region, &key);
+                let chunk = if pairs.len() == self.elements_in_bounds(&key) {
+                    fill_chunk(&self.fill_scalar(), &self.metadata.chunk_shape)
+                } else {
+                    self.chunk_for_update(&key).await?
+                };
+                self.update_chunk(&key, chunk, values, pairs).await
+            })
+            .buffer_unordered(self.config.max_concurrency.max(1))
+            .try_collect()
+            .await
+    }
+
+    /// Set the element at each of `points` to the matching entry of
+    /// `values`.  When a point repeats, its last value wins.
+    ///
+    /// Each touched chunk is read, updated and written back once; see
+    /// [`set_region`](Self::set_region).
+    pub async fn set_points(
+        &self,
+        points: &[Vec<usize>],
+        values: &ZarrVectorValue,
+    ) -> ZarrResult<()> {
+        if points.len() != values.len() {
+            return Err(ZarrError::Encode(format!(
+                "Got {} points but {} values",
+                points.len(),
+                values.len()
+            )));
+        }
+        let md = &self.metadata;
+        let chunk_strides = strides(&md.chunk_shape, md.order);
+        let mut by_chunk: BTreeMap<Vec<usize>, Vec<(usize, usize)>> = BTreeMap::new();
+        for (i, point) in points.iter().enumerate() {
+            if point.len() != md.shape.len() || point.iter().zip(&md.shape).any(|(p, s)| p >= s) {
+                return Err(ZarrError::Other(format!(
+                    "Point {point:?} out of bounds for shape {:?}",
+                    md.shape
+                )));
+            }
+            let key = point
+                .iter()
+                .zip(&md.chunk_shape)
+                .map(|(p, c)| p / c)
+                .collect();
+            let within = point
+                .iter()
+                .zip(&md.chunk_shape)
+                .zip(&chunk_strides)
+                .map(|((p, c), s)| (p % c) * s)
+                .sum();
+            by_chunk.entry(key).or_default().push((within, i));
+        }
+        futures::stream::iter(by_chunk)
+            .map(|(key, pairs)| async move {
+                let chunk = self.chunk_for_update(&key).await?;
+                self.update_chunk(&key, chunk, values, pairs).await
+            })
+            .buffer_unordered(self.config.max_concurrency.max(1))
+            .try_collect()
+            .await
+    }
+
+    /// Chunk `key` to update in place.  Missing chunks start as the fill
+    /// value, never [`int_fill_sentinel`](crate::options::ArrayReadOptions::int_fill_sentinel),
+    /// which would otherwise be written back.
+    async fn chunk_for_update(&self, key: &[usize]) -> ZarrResult<ZarrVectorValue> {
+        if self.read_options.int_fill_sentinel.is_none() {
+            return self.get_chunk(key).await;
+        }
+        let key_str = self.chunk_key(key)?;
+        match self.get_chunk_raw(key).await? {
+            Some(raw) if !raw.is_empty() => {
+                self.decode_chunk(key, &key_str, Some(&raw), self.codecs()?)
+                    .await
+            }
+            _ => Ok(fill_chunk(&self.fill_scalar(), &self.metadata.chunk_shape)),
+        }
+    }
+
+    /// Copy `values[src]` into `chunk[dst]` for every `(dst, src)` in
+    /// `pairs` and write the chunk back.
+    async fn update_chunk(
+        &self,
+        key: &[usize],
+        mut chunk: ZarrVectorValue,
+        values: &ZarrVectorValue,
+        pairs: Vec<(usize, usize)>,
+    ) -> ZarrResult<()> {
+        scatter(&mut chunk, values, pairs).map_err(|_| {
+            ZarrError::TypeConversion(format!(
+                "Values don't match the element type of chunk {key:?}"
+            ))
+        })?;
+        self.write_chunk(key, &chunk).await.map(|_| ())
+    }
+
+    /// Number of elements of chunk `key` inside the array; less than the
+    /// chunk size for edge chunks.
+    fn elements_in_bounds(&self, key: &[usize]) -> usize {
+        key.iter()
+            .zip(&self.metadata.chunk_shape)
+            .zip(&self.metadata.shape)
+            .map(|((k, c), s)| (k * c + c).min(*s).saturating_sub(k * c))
+            .product()
+    }
+
+    /// Remove stored chunks whose elements all equal the fill value,
+    /// returning how many were removed.  Reads are unaffected, since missing
+    /// chunks read as the fill value.
+    ///
+    /// Every stored chunk is fetched and decoded, at most `max_concurrency`
+    /// at a time.
+    pub async fn prune_empty_chunks(&self) -> ZarrResult<usize> {
+        let fill = self.fill_scalar();
+        let fill = &fill;
+        let removed: Vec<bool> = futures::stream::iter(&self.metadata.keys)
+            .map(|key| async move {
+                let path = self.store.join(&self.path, key);
+                let Some(raw) = get_with_retry(self.store.as_ref(), &path, &self.config).await?
+                else {
+                    return Ok(false);
+                };
+                let indices = parse_chunk_key(key, self.metadata.shape.len());
+                let chunk = self
+                    .decode_chunk(&indices, key, Some(&raw), self.codecs()?)
+                    .await?;
+                if !is_fill(&chunk, fill) {
+                    return Ok(false);
+                }
+                self.store.delete(&path).await?;
+                if let Some(cache) = &self.cache {
+                    cache.remove(key);
+                }
+                Ok::<_, ZarrError>(true)
+            })
+            .buffer_unordered(self.config.max_concurrency.max(1))
+            .try_collect()
+            .await?;
+        Ok(removed.into_iter().filter(|&r| r).count())
+    }
+
+    /// Shrink the array to `new_shape`, rewriting `.zarray` and deleting the
+    /// chunks that fall entirely outside it.  Returns how many chunk keys
+    /// left the grid.
+    ///
+    /// The metadata is written first, so an interrupted truncate leaves
+    /// unreachable chunks rather than a grid pointing past its data.  Edge
+    /// chunks keep their out-of-range elements, which reappear if the array
+    /// is later grown again.
+    pub async fn truncate(&mut self, new_shape: &[usize]) -> ZarrResult<usize> {
+        let md = &self.metadata;
+        if md.zarr_format != 2 {
+            return Err(ZarrError::UnsupportedFeature(FeatureId::ZarrFormat(
+                md.zarr_format,
+            )));
+        }
+        if new_shape.len() != md.shape.len() || new_shape.iter().zip(&md.shape).any(|(n, s)| n > s)
+        {
+            return Err(ZarrError::Metadata(format!(
+                "Cannot truncate shape {:?} to {new_shape:?}",
+                md.shape
+            )));
+        }
+        let keys = list_keys_with_separator(new_shape, &md.chunk_shape, md.dimension_separator);
+        let kept: HashSet<&String> = keys.iter().collect();
+        let removed: Vec<String> = md
+            .keys
+            .iter()
+            .filter(|k| !kept.contains(k))
+            .cloned()
+            .collect();
+
+        let mut document = self.document.clone();
+        document["shape"] = serde_json::json!(new_shape);
+        let zarray_path = self.store.join(&self.path, ".zarray");
+        self.store
+            .put(&zarray_path, serde_json::to_vec_pretty(&document)?.into())
+            .await?;
+        self.document = document;
+        self.metadata.shape = new_shape.to_vec();
+        self.metadata.keys = keys;
+
+        let this = &*self;
+        futures::stream::iter(&removed)
+            .map(|key| async move {
+                this.store.delete(&this.store.join(&this.path, key)).await?;
+                if let Some(cache) = &this.cache {
+                    cache.remove(key);
+                }
+                Ok::<_, ZarrError>(())
+            })
+            .buffer_unordered(this.config.max_concurrency.max(1))
+            .try_collect::<Vec<()>>()
+            .await?;
+        Ok(removed.len())
+    }
+
+    fn fill_scalar(&self) -> ZarrValue {
+        self.metadata
+            .fill_value
+            .to_zarr_value(self.metadata.data_type)
+    }
+
+    /// Whether `path` already holds exactly `encoded`.
+    async fn is_stored(&self, path: &str, encoded: &[u8]) -> ZarrResult<bool> {
+        let Some(meta) = self.store.head(path).await? else {
+            return Ok(false);
+        };
+        if meta.size != encoded.len() as u64 {
+            return Ok(false);
+        }
+        let digest = Md5::digest(encoded);
+        if meta.etag.is_some_and(|etag| etag_is_md5_of(&etag, &digest)) {
+            return Ok(true);
+        }
+        // The ETag is missing or opaque, so compare the stored bytes.
+        Ok(self
+            .store
+            .get(path)
+            .await?
+            .is_some_and(|stored| Md5::digest(&stored) == digest))
+    }
+}
+
+/// Encode `values`, a whole chunk in the array's memory order, into the
+/// bytes [`UnifiedZarrArray::write_chunk`] would store, using nothing but
+/// the array's metadata.
+///
+/// For services that produce chunk payloads and store them themselves.
+/// `values` must hold exactly one chunk's worth of elements; unlike
+/// `write_chunk`, a chunk equal to the fill value is still encoded.
+pub async fn encode_chunk_bytes(
+    metadata: &UnifiedMetadata,
+    values: &ZarrVectorValue,
+) -> ZarrResult<Vec<u8>> {
+    let elements: usize = metadata.chunk_shape.iter().product();
+    if values.len() != elements {
+        return Err(ZarrError::Encode(format!(
+            "Chunk has {} elements, expected {elements}",
+            values.len()
+        )));
+    }
+    let codecs = metadata.codecs()?;
+    CodecPipeline::new(&codecs)?.encode(values).await
+}
+
+/// Whether `etag` is the hex MD5 `digest`, as S3, GCS and MinIO report for
+/// objects uploaded in one part.
+pub(crate) fn etag_is_md5_of(etag: &str, digest: &[u8]) -> bool {
+    let hex: String = digest.iter().map(|b| format!("{b:02x}")).collect();
+    etag.trim_start_matches("W/")
+        .trim_matches('"')
+        .eq_ignore_ascii_case(&hex)
+}
+
+/// Whether every element of `values` is bitwise equal to `fill`.
+fn is_fill(values: &ZarrVectorValue, fill: &ZarrValue) -> bool {
+    let element = zarr_vector_to_bytes(Endian::Little, &fill_chunk(fill, &[1]));
+    match (element, zarr_vector_to_bytes(Endian::Little, values)) {
+        (Ok(element), Ok(bytes)) if !element.is_empty() => {
+            bytes.len() == element.len() * values.len()
+                && bytes.chunks(element.len()).all(|e| e == element)
+        }
+        // Strings, nulls and empty elements: compare values.
+        _ => values.to_maybe_values().iter().all(|v| match v {
+            Some(v) => v == fill,
+            None => matches!(fill, ZarrValue::Null(_)),
+        }),
+    }
+}
@@ -0,0 +1,294 @@
+//! Writing the metadata xarray expects of a V2 group.
+//!
+//! xarray's Zarr backend names each array's dimensions from its
+//! `_ARRAY_DIMENSIONS` attribute and refuses arrays without one; it finds
+//! coordinates other than the dimension coordinates through a variable's
+//! `coordinates` attribute; and it warns when a group has no consolidated
+//! `.zmetadata`.  [`write_xarray_conventions`] writes all three:
+//!
+//! ```ignore
+//! let mut group = v2::open_group(store, "era5.zarr", &["time", "lat", "lon", "t2m"]).await?;
+//! write_xarray_conventions(&mut group, &["lat", "lon"]).await?;
+//! // xr.open_zarr("era5.zarr") now opens without warnings.
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use serde_json::json;
+
+use crate::array::UnifiedZarrArray;
+use crate::attributes::Attributes;
+use crate::error::{ZarrError, ZarrResult};
+use crate::group::UnifiedZarrGroup;
+use crate::store::StorageBackend;
+use crate::v2::{dimension_names_from_attributes, read_attributes};
+
+/// Write `_ARRAY_DIMENSIONS` and `coordinates` into every array's
+/// `.zattrs`, then the group's `.zgroup` and consolidated `.zmetadata`, and
+/// mark `group` consolidated.
+///
+/// Dimension names come from each array's
+/// [`dimension_names`](crate::UnifiedMetadata::dimension_names) or, failing
+/// that, its `_ARRAY_DIMENSIONS`; a 1-D array with neither is taken as the
+/// coordinate of a dimension of its own name.  Any other unnamed dimension,
+/// or a dimension whose length differs between arrays, is an error, since
+/// xarray would reject the group.
+///
+/// `coordinates` names the group's auxiliary coordinates, like `lat(y, x)`
+/// for `t2m(time, y, x)`.  Each other variable's `coordinates` attribute
+/// lists those whose dimensions are all among its own; variables that
+/// already have a `coordinates` attribute keep it.
+///
+/// Attributes not yet read are read first, so existing ones are kept.
+/// Arrays and subgroups under the group that `group` didn't open are found
+/// by listing the store, and their documents go into `.zmetadata` as
+/// stored, so consolidated reads still see them.
+pub async fn write_xarray_conventions(
+    group: &mut UnifiedZarrGroup,
+    coordinates: &[&str],
+) -> ZarrResult<()> {
+    if group.zarr_format() != 2 {
+        return Err(ZarrError::Metadata(
+            "xarray conventions can only be written for V2 groups".into(),
+        ));
+    }
+    let Some(first) = group.arrays.values().next() else {
+        return Err(ZarrError::NotFound(format!(
+            "Group {} has no arrays",
+            group.path()
+        )));
+    };
+    let store: Arc<dyn StorageBackend> = first.store.clone();
+    let config = first.config.clone();
+
+    for array in group.arrays.values_mut() {
+        if array.metadata.attributes.is_none() {
+            array.metadata.attributes =
+                read_attributes(store.as_ref(), &array.path, &config).await?;
+        }
+    }
+    if group.metadata.attributes.is_none() {
+        group.metadata.attributes = read_attributes(store.as_ref(), group.path(), &config).await?;
+    }
+
+    let mut dims = BTreeMap::new();
+    let mut lengths = BTreeMap::new();
+    for (name, array) in &group.arrays {
+        let names = array_dimensions(name, array)?;
+        for (dim, &len) in names.iter().zip(&array.metadata.shape) {
+            let seen = *lengths.entry(dim.clone()).or_insert(len);
+            if seen != len {
+                return Err(ZarrError::Metadata(format!(
+                    "Dimension {dim} has length {len} in array {name} but {seen} elsewhere"
+                )));
+            }
+        }
+        dims.insert(name.clone(), names);
+    }
+    if let Some(missing) = coordinates.iter().find(|c| !dims.contains_key(**c)) {
+        return Err(ZarrError::NotFound(format!(
+            "Coordinate {missing} is not an array of group {}",
+            group.path()
+        )));
+    }
+    let coordinates = auxiliary_coordinates(&dims, coordinates);
+
+    let mut consolidated = serde_json::Map::new();
+    for (name, array) in group.arrays.iter_mut() {
+        let names = &dims[name];
+        let attributes = array
+            .metadata
+            .attributes
+            .get_or_insert_with(Attributes::new);
+        attributes.insert("_ARRAY_DIMENSIONS".into(), json!(names));
+        if let Some(coords) = coordinates.get(name)
+            && !attributes.contains_key("coordinates")
+        {
+            attributes.insert("coordinates".into(), coords.join(" ").into());
+        }
+        array.metadata.dimension_names = Some(names.iter().cloned().map(Some).collect());
+
+        store
+            .put(
+                &store.join(&array.path, ".zattrs"),
+                serde_json::to_vec_pretty(attributes)?.into(),
+            )
+            .await?;
+        consolidated.insert(format!("{name}/.zarray"), array.document.clone());
+        consolidated.insert(format!("{name}/.zattrs"), attributes.clone().into());
+    }
+
+    let path = group.path().to_string();
+    copy_unopened(store.as_ref(), &path, group, &mut consolidated).await?;
+    let zgroup = json!({"zarr_format": 2});
+    store
+        .put(
+            &store.join(&path, ".zgroup"),
+            serde_json::to_vec_pretty(&zgroup)?.into(),
+        )
+        .await?;
+    consolidated.insert(".zgroup".into(), zgroup);
+    if let Some(attributes) = group.metadata.attributes.as_ref().filter(|a| !a.is_empty()) {
+        store
+            .put(
+                &store.join(&path, ".zattrs"),
+                serde_json::to_vec_pretty(attributes)?.into(),
+            )
+            .await?;
+        consolidated.insert(".zattrs".into(), attributes.clone().into());
+    }
+    let zmetadata = json!({
+        "metadata": consolidated,
+        "zarr_consolidated_format": 1,
+    });
+    store
+        .put(
+            &store.join(&path, ".zmetadata"),
+            serde_json::to_vec_pretty(&zmetadata)?.into(),
+        )
+        .await?;
+
+    group.metadata.consolidated = true;
+    group.metadata.array_names = group.arrays.keys().cloned().collect();
+    Ok(())
+}
+
+/// The dimension names of the array `name`, as xarray will read them.
+fn array_dimensions(name: &str, array: &UnifiedZarrArray) -> ZarrResult<Vec<String>> {
+    let ndim = array.metadata.shape.len();
+    let named = array
+        .metadata
+        .dimension_names
+        .as_ref()
+        .filter(|names| names.len() == ndim)
+        .cloned()
+        .or_else(|| {
+            array
+                .metadata
+                .attributes
+                .as_ref()
+                .and_then(|attrs| dimension_names_from_attributes(attrs, ndim))
+        });
+    if let Some(names) = named.and_then(|names| names.into_iter().collect::<Option<Vec<_>>>()) {
+        return Ok(names);
+    }
+    match ndim {
+        0 => Ok(Vec::new()),
+        1 => Ok(vec![name.to_string()]),
+        _ => Err(ZarrError::Metadata(format!(
+            "Array {name} has unnamed dimensions"
+        ))),
+    }
+}
+
+/// For each variable, the given `coordinates` whose dimensions are all
+/// among its own, by name.  Coordinates and dimension coordinates get none.
+fn auxiliary_coordinates(
+    dims: &BTreeMap<String, Vec<String>>,
+    coordinates: &[&str],
+) -> BTreeMap<String, Vec<String>> {
+    let is_dimension_coordinate =
+        |name: &str, names: &[String]| matches!(names, [dim] if dim == name);
+    let candidates: Vec<(&str, BTreeSet<&String>)> = coordinates
+        .iter()
+        .map(|&name| (name, dims[name].iter().collect()))
+        .collect();
+    let mut by_variable = BTreeMap::new();
+    for (name, names) in dims {
+        if is_dimension_coordinate(name, names) || coordinates.contains(&name.as_str()) {
+            continue;
+        }
+        let own: BTreeSet<&String> = names.iter().collect();
+        let coords: Vec<String> = candidates
+            .iter()
+            .filter(|(_, other_dims)| other_dims.is_subset(&own))
+            .map(|(other, _)| other.to_string())
+            .collect();
+        if !coords.is_empty() {
+            by_variable.insert(name.clone(), coords);
+        }
+    }
+    by_variable
+}
+
+/// Add the `.zarray`, `.zgroup` and `.zattrs` documents of the nodes under
+/// the group at `path` that `group` has no array for to `consolidated`,
+/// keyed relative to the group.
+async fn copy_unopened(
+    store: &dyn StorageBackend,
+    path: &str,
+    group: &UnifiedZarrGroup,
+    consolidated: &mut serde_json::Map<String, serde_json::Value>,
+) -> ZarrResult<()> {
+    let mut pending = vec![String::new()];
+    while let Some(relative) = pending.pop() {
+        let dir = if relative.is_empty() {
+            path.to_string()
+        } else {
+            store.join(path, &relative)
+        };
+        let children: BTreeSet<String> = store
+            .list(&dir)
+            .await?
+            .iter()
+            .filter_map(|entry| child_name(entry, &dir))
+            .collect();
+        for child in children {
+            let node = if relative.is_empty() {
+                child
+            } else {
+                format!("{relative}/{child}")
+            };
+            if group.arrays.contains_key(&node) {
+                continue;
+            }
+            let node_path = store.join(path, &node);
+            let is_group = if let Some(zarray) = document(store, &node_path, ".zarray").await? {
+                consolidated.insert(format!("{node}/.zarray"), zarray);
+                false
+            } else if let Some(zgroup) = document(store, &node_path, ".zgroup").await? {
+                consolidated.insert(format!("{node}/.zgroup"), zgroup);
+                true
+            } else {
+                continue;
+            };
+            if let Some(zattrs) = document(store, &node_path, ".zattrs").await? {
+                consolidated.insert(format!("{node}/.zattrs"), zattrs);
+            }
+            if is_group {
+                pending.push(node);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The JSON document `name` of the node at `path`, if there is one.
+async fn document(
+    store: &dyn StorageBackend,
+    path: &str,
+    name: &str,
+) -> ZarrResult<Option<serde_json::Value>> {
+    match store.get(&store.join(path, name)).await? {
+        Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        None => Ok(None),
+    }
+}
+
+/// The name of the child of `dir` that a listing entry is under.  Listings
+/// name a directory's entries (local files) or whole keys (object stores).
+fn child_name(entry: &str, dir: &str) -> Option<String> {
+    let dir = dir.trim_end_matches('/');
+    let relative = if dir.is_empty() {
+        entry
+    } else {
+        let inner = format!("/{dir}/");
+        entry
+            .strip_prefix(&inner[1..])
+            .or_else(|| entry.find(&inner).map(|i| &entry[i + inner.len()..]))
+            .unwrap_or(entry)
+    };
+    let name = relative.split('/').next()?;
+    (!name.is_empty() && !name.starts_with('.')).then(|| name.to_string())
+}
@@ -0,0 +1,148 @@
+//! Tests for reading several arrays over the same grid, and for derived
+//! variables built on it.
+
+use std::sync::Arc;
+
+use object_store::memory::InMemory;
+
+use simplezarr::UnifiedZarrArray;
+use simplezarr::aligned::read_region_multi;
+use simplezarr::builder::ArrayBuilder;
+use simplezarr::error::ZarrError;
+use simplezarr::store::ObjectStoreBackend;
+use simplezarr::types::{DataType, ZarrVectorValue};
+use simplezarr::v2;
+
+/// A 4x6 `float64` array at `path` in `store`, holding `f(i, j)`.
+async fn array(
+    store: &Arc<ObjectStoreBackend>,
+    path: &str,
+    chunks: [usize; 2],
+    f: impl Fn(usize, usize) -> f64,
+) -> UnifiedZarrArray {
+    let array = ArrayBuilder::new([4, 6])
+        .chunks(chunks)
+        .dtype(DataType::Float64)
+        .codec(None)
+        .build(store.clone(), path)
+        .await
+        .unwrap();
+    let values = (0..4)
+        .flat_map(|i| (0..6).map(move |j| (i, j)))
+        .map(|(i, j)| f(i, j));
+    array
+        .set_region(
+            &array.full_region(),
+            &ZarrVectorValue::VFloat64(values.collect()),
+        )
+        .await
+        .unwrap();
+    array
+}
+
+fn store() -> Arc<ObjectStoreBackend> {
+    Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""))
+}
+
+#[tokio::test]
+async fn aligned_reads_return_each_array_by_name() {
+    let store = store();
+    let u = array(&store, "wind/u", [3, 4], |i, j| (i * 6 + j) as f64).await;
+    let v = array(&store, "wind/v", [3, 4], |i, j| -((i * 6 + j) as f64)).await;
+
+    let region = [1..3, 2..5];
+    let data = read_region_multi(&[&u, &v], &region).await.unwrap();
+    assert_eq!(data.keys().collect::<Vec<_>>(), ["u", "v"]);
+    assert_eq!(data["u"].shape, [2, 3]);
+    assert_eq!(*data["u"], [8.0, 9.0, 10.0, 14.0, 15.0, 16.0]);
+    assert_eq!(
+        *data["u"],
+        u.read_region(&region).await.unwrap().into_inner()
+    );
+    let speed: Vec<f64> = data["u"]
+        .iter()
+        .zip(data["v"].iter())
+        .map(|(u, v)| u.hypot(*v))
+        .collect();
+    assert_eq!(speed[0], 8.0 * 2f64.sqrt());
+
+    assert!(read_region_multi(&[], &region).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn misaligned_or_repeated_arrays_are_rejected() {
+    let store = store();
+    let u = array(&store, "u", [3, 4], |_, _| 1.0).await;
+    let v = array(&store, "v", [2, 2], |_, _| 2.0).await;
+    let region = u.full_region();
+
+    let err = read_region_multi(&[&u, &v], &region).await.unwrap_err();
+    assert!(matches!(err, ZarrError::Metadata(_)), "{err:?}");
+    let err = read_region_multi(&[&u, &u], &region).await.unwrap_err();
+    assert!(matches!(err, ZarrError::Other(_)), "{err:?}");
+    let err = read_region_multi(&[&u], &[0..5, 0..6]).await.unwrap_err();
+    assert!(matches!(err, ZarrError::Other(_)), "{err:?}");
+}
+
+// ---------------------------------------------------------------------------
+// Derived variables
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn derived_variables_read_like_stored_ones() {
+    let store = store();
+    array(&store, "wind/u", [3, 4], |i, _| 3.0 * i as f64).await;
+    array(&store, "wind/v", [3, 4], |i, _| 4.0 * i as f64).await;
+    array(&store, "wind/mask", [2, 2], |_, _| 1.0).await;
+    let group = v2::open_group(store, "wind", &["u", "v", "mask"])
+        .await
+        .unwrap()
+        .with_derived("speed", &["u", "v"], |uv| uv[0].hypot(uv[1]))
+        .unwrap();
+
+    assert_eq!(group.variable_names(), ["mask", "speed", "u", "v"]);
+    assert_eq!(group.get_derived("speed").unwrap().inputs, ["u", "v"]);
+    assert_eq!(group.variable_shape("speed"), Some(&[4, 6][..]));
+    let speed = group.read_variable("speed", &[1..3, 0..2]).await.unwrap();
+    assert_eq!(speed.shape, [2, 2]);
+    assert_eq!(*speed, [5.0, 5.0, 10.0, 10.0]);
+    let loaded = group.load_variable("speed").await.unwrap();
+    assert_eq!(loaded[23], 15.0);
+    let u = group.load_variable("u").await.unwrap();
+    assert_eq!(u[23], 9.0);
+
+    let err = group
+        .read_variable("gust", &[0..1, 0..1])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::NotFound(_)), "{err:?}");
+    let err = group.with_derived("u", &["v"], |v| v[0]).unwrap_err();
+    assert!(matches!(err, ZarrError::Other(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn derived_inputs_must_exist_and_align() {
+    let store = store();
+    array(&store, "g/u", [3, 4], |_, _| 1.0).await;
+    array(&store, "g/mask", [2, 2], |_, _| 1.0).await;
+    let open = || v2::open_group(store.clone(), "g", &["u", "mask"]);
+
+    let err = open()
+        .await
+        .unwrap()
+        .with_derived("x", &["u", "w"], |v| v[0])
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::NotFound(_)), "{err:?}");
+    let err = open()
+        .await
+        .unwrap()
+        .with_derived("x", &["u", "mask"], |v| v[0])
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::Metadata(_)), "{err:?}");
+    let err = open()
+        .await
+        .unwrap()
+        .with_derived("x", &[], |_| 0.0)
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::Other(_)), "{err:?}");
+}
@@ -0,0 +1,95 @@
+//! Tests for typed attribute access.
+
+use serde::Deserialize;
+use serde_json::json;
+
+use simplezarr::Attributes;
+use simplezarr::error::ZarrError;
+use simplezarr::metadata::v2::parse_attributes;
+
+fn attrs(value: serde_json::Value) -> Attributes {
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn getters_check_types() {
+    let a = attrs(json!({
+        "units": "K",
+        "scale_factor": 0.5,
+        "count": 3,
+        "flag": true,
+        "missing": "NaN",
+    }));
+    assert_eq!(a.get_str("units"), Some("K"));
+    assert_eq!(a.get_f64("scale_factor"), Some(0.5));
+    assert_eq!(a.get_f64("count"), Some(3.0));
+    assert_eq!(a.get_i64("count"), Some(3));
+    assert_eq!(a.get_i64("scale_factor"), None);
+    assert_eq!(a.get_bool("flag"), Some(true));
+    assert!(a.get_f64("missing").unwrap().is_nan());
+    assert_eq!(a.get_str("scale_factor"), None);
+    assert_eq!(a.get_str("absent"), None);
+}
+
+#[test]
+fn vectors_accept_a_single_value() {
+    let a = attrs(json!({
+        "valid_range": [0, 100.5],
+        "valid_min": 0,
+        "dims": ["y", "x"],
+        "mixed": [1, "a"],
+    }));
+    assert_eq!(a.get_vec_f64("valid_range"), Some(vec![0.0, 100.5]));
+    assert_eq!(a.get_vec_f64("valid_min"), Some(vec![0.0]));
+    assert_eq!(a.get_vec_str("dims"), Some(vec!["y", "x"]));
+    assert_eq!(a.get_vec_f64("mixed"), None);
+}
+
+#[test]
+fn paths_reach_nested_objects_and_arrays() {
+    let a = attrs(json!({
+        "grid_mapping": {"name": "lcc", "parallels": [30.0, 60.0]},
+        "a/b": 1,
+    }));
+    assert_eq!(a.get_str("grid_mapping/name"), Some("lcc"));
+    assert_eq!(a.get_f64("grid_mapping/parallels/1"), Some(60.0));
+    assert_eq!(a.get_f64("grid_mapping/parallels/2"), None);
+    assert_eq!(a.get_f64("a/b"), Some(1.0));
+    assert_eq!(a.get_str("grid_mapping/name/x"), None);
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Cf {
+    units: String,
+    #[serde(default)]
+    long_name: Option<String>,
+}
+
+#[test]
+fn attributes_deserialize_into_structs() {
+    let a = attrs(json!({"units": "m", "other": 1, "mapping": {"units": "deg"}}));
+    assert_eq!(
+        a.deserialize::<Cf>().unwrap(),
+        Cf {
+            units: "m".into(),
+            long_name: None
+        }
+    );
+    assert_eq!(
+        a.get_as::<Cf>("mapping").unwrap().unwrap().units,
+        "deg".to_string()
+    );
+    assert!(a.get_as::<Cf>("absent").unwrap().is_none());
+    assert!(matches!(
+        a.get_as::<Cf>("other"),
+        Err(ZarrError::Metadata(_))
+    ));
+}
+
+#[test]
+fn zattrs_parse_to_attributes() {
+    let a = parse_attributes(br#"{"units": "K"}"#).unwrap();
+    assert_eq!(a["units"], "K");
+    assert_eq!(serde_json::to_value(&a).unwrap(), json!({"units": "K"}));
+    assert!(parse_attributes(b"[1]").is_err());
+}
@@ -0,0 +1,132 @@
+//! Tests for comparing codecs with `benchmark_codecs`.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use object_store::memory::InMemory;
+
+use simplezarr::UnifiedZarrArray;
+use simplezarr::benchmark::benchmark_codecs;
+use simplezarr::builder::{ArrayBuilder, lz4, zstd};
+use simplezarr::error::{ZarrError, ZarrResult};
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+use simplezarr::types::{DataType, ZarrVectorValue};
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Backend wrapper counting reads that reach it.
+struct Counting {
+    inner: ObjectStoreBackend,
+    gets: AtomicUsize,
+}
+
+#[async_trait]
+impl StorageBackend for Counting {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        self.gets.fetch_add(1, Ordering::SeqCst);
+        self.inner.get(path).await
+    }
+
+    async fn list(&self, prefix: &str) -> ZarrResult<Vec<String>> {
+        self.inner.list(prefix).await
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        self.inner.join(base, segment)
+    }
+
+    async fn put(&self, path: &str, data: Bytes) -> ZarrResult<()> {
+        self.inner.put(path, data).await
+    }
+
+    async fn delete(&self, path: &str) -> ZarrResult<()> {
+        self.inner.delete(path).await
+    }
+}
+
+/// A 4x4 grid of 32x32 `int32` chunks; only the first row of chunks is
+/// written.
+async fn partly_written() -> UnifiedZarrArray {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    written_to(store).await
+}
+
+async fn written_to<S: StorageBackend + 'static>(store: Arc<S>) -> UnifiedZarrArray {
+    let array = ArrayBuilder::new([128, 128])
+        .chunks([32, 32])
+        .dtype(DataType::Int32)
+        .codec(zstd(3))
+        .build(store, "a")
+        .await
+        .unwrap();
+    for j in 0..4 {
+        let values = (0..32 * 32).map(|i| (i % 32 + 1) * (j + 1)).collect();
+        array
+            .write_chunk(&[0, j as usize], &ZarrVectorValue::VInt32(values))
+            .await
+            .unwrap();
+    }
+    array
+}
+
+// ---------------------------------------------------------------------------
+// benchmark_codecs
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn candidates_are_measured_on_written_chunks() {
+    let array = partly_written().await;
+    let candidates = vec![Vec::new(), vec![zstd(3)], vec![lz4(1)]];
+    let report = benchmark_codecs(&array, &candidates, 16).await.unwrap();
+
+    assert_eq!(report.chunks.len(), 4);
+    assert!(report.chunks.iter().all(|key| key[0] == 0));
+    assert_eq!(report.raw_bytes, 4 * 32 * 32 * 4);
+    assert!(report.stored_bytes < report.raw_bytes);
+
+    let labels: Vec<&str> = report.results.iter().map(|r| r.label.as_str()).collect();
+    assert_eq!(labels, ["none", "Zstd(level=3)", "LZ4(acceleration=1)"]);
+    assert_eq!(report.results[0].encoded_bytes, report.raw_bytes);
+    assert!(report.results[1].ratio() > 1.0);
+    assert_eq!(report.smallest().unwrap().label, "Zstd(level=3)");
+
+    let table = report.to_string();
+    assert!(table.starts_with("4 chunks, 16384 bytes decoded"));
+    assert_eq!(table.lines().count(), 2 + candidates.len());
+}
+
+#[tokio::test]
+async fn sampling_is_spread_over_the_grid() {
+    let array = partly_written().await;
+    // Every fourth chunk of the grid: only [0, 0] is in the written row.
+    let report = benchmark_codecs(&array, &[vec![zstd(1)]], 4).await.unwrap();
+    assert_eq!(report.chunks, vec![vec![0, 0]]);
+}
+
+#[tokio::test]
+async fn sampled_chunks_are_fetched_once() {
+    let store = Arc::new(Counting {
+        inner: ObjectStoreBackend::new(Box::new(InMemory::new()), ""),
+        gets: AtomicUsize::new(0),
+    });
+    let array = written_to(store.clone()).await;
+    let before = store.gets.load(Ordering::SeqCst);
+    let report = benchmark_codecs(&array, &[vec![zstd(3)]], 16)
+        .await
+        .unwrap();
+    // One read per grid chunk sampled, written or not.
+    assert_eq!(store.gets.load(Ordering::SeqCst) - before, 16);
+    assert_eq!(report.chunks.len(), 4);
+}
+
+#[tokio::test]
+async fn unwritten_arrays_are_an_error() {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let array = ArrayBuilder::new([8]).build(store, "a").await.unwrap();
+    let result = benchmark_codecs(&array, &[vec![zstd(3)]], 4).await;
+    assert!(matches!(result, Err(ZarrError::Other(_))));
+}
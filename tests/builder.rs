@@ -0,0 +1,289 @@
+//! Tests for creating arrays with `ArrayBuilder`.
+
+use std::sync::Arc;
+
+use object_store::memory::InMemory;
+
+use simplezarr::builder::{
+    AUTO_CHUNK_MAX_BYTES, ArrayBuilder, ChunkShape, CompressionGoal, SMALL_CHUNK_BYTES, auto,
+    auto_chunks, bitround, blosc, gzip, lz4, suggest_codecs, zstd,
+};
+use simplezarr::codecs::AnyCodec;
+use simplezarr::codecs::blosc::{BloscCname, BloscShuffle};
+use simplezarr::error::ZarrError;
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+use simplezarr::types::{DataType, FillValue, ZarrValue, ZarrVectorValue};
+use simplezarr::v2;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn memory_store() -> Arc<ObjectStoreBackend> {
+    Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""))
+}
+
+async fn zarray(store: &ObjectStoreBackend, path: &str) -> serde_json::Value {
+    let bytes = store
+        .get(&store.join(path, ".zarray"))
+        .await
+        .unwrap()
+        .unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+// ---------------------------------------------------------------------------
+// Automatic chunks
+// ---------------------------------------------------------------------------
+
+#[test]
+fn small_arrays_are_one_chunk() {
+    assert_eq!(auto_chunks(&[100, 200], 8), vec![100, 200]);
+    assert_eq!(auto_chunks(&[0, 5], 4), vec![1, 5]);
+}
+
+#[test]
+fn large_arrays_are_split_along_the_longest_dimension() {
+    let chunks = auto_chunks(&[10_000, 10_000], 4);
+    let bytes = chunks.iter().product::<usize>() * 4;
+    assert!(bytes <= AUTO_CHUNK_MAX_BYTES);
+    assert!(bytes > AUTO_CHUNK_MAX_BYTES / 4);
+    assert_eq!(chunks, vec![1250, 1250]);
+
+    // A long time axis is split before the short spatial ones.
+    let chunks = auto_chunks(&[100_000, 64, 64], 8);
+    assert_eq!(&chunks[1..], &[64, 64]);
+    assert!(chunks.iter().product::<usize>() * 8 <= AUTO_CHUNK_MAX_BYTES);
+}
+
+#[test]
+fn explicit_chunks_must_match_the_shape() {
+    let builder = ArrayBuilder::new([10, 10]).chunks([5]);
+    assert!(matches!(builder.chunk_shape(), Err(ZarrError::Metadata(_))));
+    let builder = ArrayBuilder::new([10, 10]).chunks(vec![5, 0]);
+    assert!(matches!(builder.chunk_shape(), Err(ZarrError::Metadata(_))));
+    let builder = ArrayBuilder::new([10, 10]).chunks([5, 4]);
+    assert_eq!(builder.chunk_shape().unwrap(), vec![5, 4]);
+}
+
+// ---------------------------------------------------------------------------
+// Metadata
+// ---------------------------------------------------------------------------
+
+#[test]
+fn metadata_document() {
+    let json = ArrayBuilder::new([100, 50])
+        .chunks(ChunkShape::Auto)
+        .dtype(DataType::Float32)
+        .codec(zstd(3))
+        .fill(f64::NAN)
+        .to_json()
+        .unwrap();
+    assert_eq!(json["zarr_format"], 2);
+    assert_eq!(json["shape"], serde_json::json!([100, 50]));
+    assert_eq!(json["chunks"], serde_json::json!([100, 50]));
+    assert_eq!(json["dtype"], "<f4");
+    assert_eq!(json["fill_value"], "NaN");
+    assert_eq!(json["order"], "C");
+    assert_eq!(
+        json["compressor"],
+        serde_json::json!({"id": "zstd", "level": 3})
+    );
+    assert_eq!(json["filters"], serde_json::Value::Null);
+}
+
+#[test]
+fn compressors_and_fill_values_are_encoded() {
+    let json = ArrayBuilder::new([4])
+        .dtype(DataType::UInt8)
+        .codec(blosc(BloscCname::Lz4, 5, BloscShuffle::BitShuffle))
+        .fill(7.0)
+        .to_json()
+        .unwrap();
+    assert_eq!(json["dtype"], "|u1");
+    assert_eq!(json["fill_value"], 7);
+    assert_eq!(json["compressor"]["cname"], "lz4");
+    assert_eq!(json["compressor"]["shuffle"], 2);
+
+    let json = ArrayBuilder::new([4]).codec(None).to_json().unwrap();
+    assert_eq!(json["compressor"], serde_json::Value::Null);
+    assert_eq!(json["fill_value"], 0.0);
+}
+
+#[test]
+fn invalid_fill_values_are_rejected() {
+    let nan_int = ArrayBuilder::new([4]).dtype(DataType::Int32).fill(f64::NAN);
+    assert!(matches!(nan_int.to_json(), Err(ZarrError::Metadata(_))));
+    let mismatched = ArrayBuilder::new([4])
+        .dtype(DataType::Int32)
+        .fill_value(FillValue::Value(ZarrValue::Bool(true)));
+    assert!(matches!(mismatched.to_json(), Err(ZarrError::Metadata(_))));
+    let string = ArrayBuilder::new([4]).dtype(DataType::String);
+    assert!(string.to_json().is_err());
+}
+
+// ---------------------------------------------------------------------------
+// Codec suggestions
+// ---------------------------------------------------------------------------
+
+fn suggested_blosc(dtype: DataType, goal: CompressionGoal) -> (BloscCname, i32, BloscShuffle) {
+    match suggest_codecs(dtype, &[256, 256], goal).as_slice() {
+        [AnyCodec::Blosc(c)] => (c.cname, c.clevel, c.shuffle.unwrap()),
+        other => panic!("expected a single blosc codec, got {other:?}"),
+    }
+}
+
+#[test]
+fn suggestions_shuffle_by_element_size() {
+    assert_eq!(
+        suggested_blosc(DataType::Float32, CompressionGoal::Balanced),
+        (BloscCname::Zstd, 5, BloscShuffle::Shuffle)
+    );
+    assert_eq!(
+        suggested_blosc(DataType::UInt8, CompressionGoal::Balanced),
+        (BloscCname::Zstd, 5, BloscShuffle::BitShuffle)
+    );
+    assert_eq!(
+        suggested_blosc(DataType::Int64, CompressionGoal::Speed),
+        (BloscCname::Lz4, 5, BloscShuffle::Shuffle)
+    );
+    assert_eq!(
+        suggested_blosc(DataType::Float64, CompressionGoal::Size),
+        (BloscCname::Zstd, 9, BloscShuffle::Shuffle)
+    );
+}
+
+#[test]
+fn small_chunks_skip_blosc() {
+    let elements = SMALL_CHUNK_BYTES / 8 - 1;
+    assert!(suggest_codecs(DataType::Float64, &[elements], CompressionGoal::Speed).is_empty());
+    assert!(matches!(
+        suggest_codecs(DataType::Float64, &[elements], CompressionGoal::Balanced).as_slice(),
+        [AnyCodec::Zstd(c)] if c.level == 3
+    ));
+    assert!(matches!(
+        suggest_codecs(DataType::Float64, &[elements], CompressionGoal::Size).as_slice(),
+        [AnyCodec::Zstd(c)] if c.level == 9
+    ));
+}
+
+#[test]
+fn auto_codecs_follow_the_chunk_shape() {
+    let json = ArrayBuilder::new([1000, 1000])
+        .dtype(DataType::Float32)
+        .codec(auto(CompressionGoal::Balanced))
+        .to_json()
+        .unwrap();
+    assert_eq!(json["compressor"]["id"], "blosc");
+    assert_eq!(json["compressor"]["cname"], "zstd");
+    assert_eq!(json["compressor"]["shuffle"], 1);
+
+    let json = ArrayBuilder::new([10])
+        .chunks([10])
+        .dtype(DataType::Int16)
+        .codec(CompressionGoal::Speed)
+        .to_json()
+        .unwrap();
+    assert_eq!(json["compressor"], serde_json::Value::Null);
+}
+
+// ---------------------------------------------------------------------------
+// Building
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn built_arrays_round_trip_written_chunks() {
+    for codec in [Some(zstd(3)), Some(gzip(5)), Some(lz4(1)), None] {
+        let store = memory_store();
+        let array = ArrayBuilder::new([4, 6])
+            .chunks([2, 3])
+            .dtype(DataType::Int16)
+            .codec(codec)
+            .fill(-1.0)
+            .build(store.clone(), "a")
+            .await
+            .unwrap();
+        array
+            .write_chunk(&[1, 0], &ZarrVectorValue::VInt16(vec![1, 2, 3, 4, 5, 6]))
+            .await
+            .unwrap();
+
+        let reopened = v2::open(store.clone(), "a").await.unwrap();
+        assert_eq!(reopened.metadata.chunk_shape, vec![2, 3]);
+        assert_eq!(
+            reopened.read_region(&[1..3, 0..4]).await.unwrap(),
+            vec![-1.0, -1.0, -1.0, -1.0, 1.0, 2.0, 3.0, -1.0]
+        );
+        assert_eq!(zarray(&store, "a").await["dtype"], "<i2");
+    }
+}
+
+#[tokio::test]
+async fn bitround_filters_shrink_written_floats() {
+    // A smooth signal with noise in the low mantissa bits.
+    let values: Vec<f32> = (0..4096)
+        .map(|i| (i as f32 / 100.0).sin() + ((i * 7919) % 1000) as f32 * 1e-7)
+        .collect();
+    let mut sizes = Vec::new();
+    for filter in [None, Some(bitround(6))] {
+        let store = memory_store();
+        let mut builder = ArrayBuilder::new([4096])
+            .chunks([4096])
+            .dtype(DataType::Float32)
+            .codec(zstd(3));
+        if let Some(filter) = &filter {
+            builder = builder.filter(filter.clone());
+        }
+        let array = builder.build(store.clone(), "a").await.unwrap();
+        array
+            .write_chunk(&[0], &ZarrVectorValue::VFloat32(values.clone()))
+            .await
+            .unwrap();
+        sizes.push(store.get("a/0").await.unwrap().unwrap().len());
+
+        let reopened = v2::open(store.clone(), "a").await.unwrap();
+        let read = reopened.read_region(&reopened.full_region()).await.unwrap();
+        for (read, value) in read.iter().zip(&values) {
+            let tolerance = if filter.is_some() { 1.0 / 64.0 } else { 0.0 };
+            assert!((read - *value as f64).abs() <= value.abs() as f64 * tolerance);
+        }
+        let filters = &zarray(&store, "a").await["filters"];
+        match filter {
+            Some(_) => assert_eq!(
+                *filters,
+                serde_json::json!([{"id": "bitround", "keepbits": 6}])
+            ),
+            None => assert!(filters.is_null()),
+        }
+    }
+    assert!(sizes[1] * 2 < sizes[0], "{sizes:?}");
+
+    // BitRound only rounds floats.
+    let err = ArrayBuilder::new([4])
+        .dtype(DataType::Int16)
+        .filter(bitround(6))
+        .build(memory_store(), "ints")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::Codec(_)), "{err}");
+}
+
+#[tokio::test]
+async fn existing_arrays_are_only_replaced_with_overwrite() {
+    let store = memory_store();
+    ArrayBuilder::new([4])
+        .build(store.clone(), "a")
+        .await
+        .unwrap();
+    let again = ArrayBuilder::new([8]).build(store.clone(), "a").await;
+    assert!(matches!(again, Err(ZarrError::Metadata(_))));
+    assert_eq!(zarray(&store, "a").await["shape"], serde_json::json!([4]));
+
+    let array = ArrayBuilder::new([8])
+        .overwrite(true)
+        .build(store.clone(), "a")
+        .await
+        .unwrap();
+    assert_eq!(array.metadata.shape, vec![8]);
+    assert_eq!(zarray(&store, "a").await["shape"], serde_json::json!([8]));
+}
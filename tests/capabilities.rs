@@ -0,0 +1,311 @@
+//! Tests for `capabilities()` and `ZarrError::UnsupportedFeature`.
+
+use std::sync::Arc;
+
+use object_store::memory::InMemory;
+use object_store::path::Path as ObjectPath;
+use object_store::{ObjectStoreExt, PutPayload};
+use serde_json::json;
+
+use simplezarr::capabilities::{FeatureId, capabilities};
+use simplezarr::codecs::{CodecPipeline, parse_codec, parse_codecs};
+use simplezarr::error::ZarrError;
+use simplezarr::metadata::v2::ZarrCompressor;
+use simplezarr::metadata::v3::{ZarrV3Metadata, parse_v3_dtype};
+use simplezarr::options::ArrayReadOptions;
+use simplezarr::store::ObjectStoreBackend;
+use simplezarr::types::DataType;
+use simplezarr::v2;
+
+fn unsupported(err: ZarrError) -> FeatureId {
+    match err {
+        ZarrError::UnsupportedFeature(feature) => feature,
+        other => panic!("expected UnsupportedFeature, got {other:?}"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// The lists match what the parsers accept
+// ---------------------------------------------------------------------------
+
+#[test]
+fn listed_codecs_parse() {
+    for name in capabilities().codecs {
+        let config = match name {
+            "numcodecs.fixedscaleoffset" => json!({
+                "scale": 1.0, "offset": 0.0, "dtype": "float64", "astype": "int16"}),
+            "numcodecs.bitround" => json!({"keepbits": 10}),
+            _ => json!({}),
+        };
+        parse_codec(&json!({"name": name, "configuration": config}))
+            .unwrap_or_else(|e| panic!("{name}: {e}"));
+    }
+}
+
+#[test]
+fn listed_v2_compressors_map_to_codecs() {
+    for id in capabilities().v2_compressors {
+        let comp: ZarrCompressor = serde_json::from_value(json!({"id": id})).unwrap();
+        let codecs = v2::compressor_to_codecs(&comp).unwrap_or_else(|e| panic!("{id}: {e}"));
+        assert!(!codecs.is_empty(), "{id}");
+    }
+}
+
+#[test]
+fn v2_compressor_ids_are_matched_through_aliases() {
+    let codecs = |compressor: serde_json::Value| {
+        let comp: ZarrCompressor = serde_json::from_value(compressor).unwrap();
+        format!("{:?}", v2::compressor_to_codecs(&comp).unwrap())
+    };
+    let same = [
+        (
+            json!({"id": "GZip", "level": 3}),
+            json!({"id": "gzip", "level": 3}),
+        ),
+        (json!({"id": "gzip-3"}), json!({"id": "gzip", "level": 3})),
+        (
+            json!({"id": "gzip-9", "level": 3}),
+            json!({"id": "gzip", "level": 3}),
+        ),
+        (json!({"id": "gzip-deflate"}), json!({"id": "gzip"})),
+        (
+            json!({"id": "Zstandard", "level": 2}),
+            json!({"id": "zstd", "level": 2}),
+        ),
+        (json!({"id": "numcodecs.zstd"}), json!({"id": "zstd"})),
+        (json!({"id": "numcodecs.Blosc"}), json!({"id": "blosc"})),
+        (json!({"id": " zlib "}), json!({"id": "zlib"})),
+    ];
+    for (alias, canonical) in same {
+        assert_eq!(codecs(alias.clone()), codecs(canonical), "{alias}");
+    }
+
+    let caps = capabilities();
+    assert!(caps.supports(&FeatureId::Compressor("zstandard".into())));
+    assert!(caps.supports(&FeatureId::Compressor("numcodecs.gzip".into())));
+}
+
+#[test]
+fn unknown_v2_compressors_are_an_error() {
+    for id in ["bz2", "ppmd", "numcodecs.bz2", "gzipx"] {
+        let comp: ZarrCompressor = serde_json::from_value(json!({"id": id})).unwrap();
+        let err = v2::compressor_to_codecs(&comp).unwrap_err();
+        assert_eq!(unsupported(err), FeatureId::Compressor(id.into()));
+    }
+}
+
+#[test]
+fn v2_compressor_levels_keep_their_full_range() {
+    let codecs = |compressor: serde_json::Value| {
+        let comp: ZarrCompressor = serde_json::from_value(compressor).unwrap();
+        v2::compressor_to_codecs(&comp)
+    };
+    for (compressor, expected) in [
+        (json!({"id": "zstd", "level": 22}), "level: 22"),
+        (json!({"id": "zstd", "level": -7}), "level: -7"),
+        (json!({"id": "lz4", "acceleration": 20}), "acceleration: 20"),
+        (json!({"id": "gzip", "level": 0}), "level: 0"),
+        (json!({"id": "blosc", "clevel": 9}), "clevel: 9"),
+        (json!({"id": "brotli", "lgwin": 10}), "lgwin: 10"),
+        (json!({"id": "blosc", "shuffle": 2}), "shuffle: Some(BitShuffle)"),
+        (json!({"id": "blosc", "shuffle": -1}), "shuffle: None"),
+        (json!({"id": "blosc", "blocksize": 256}), "blocksize: 256"),
+    ] {
+        let codecs = format!("{:?}", codecs(compressor.clone()).unwrap());
+        assert!(codecs.contains(expected), "{compressor}: {codecs}");
+    }
+
+    for compressor in [
+        json!({"id": "zstd", "level": 23}),
+        json!({"id": "zstd", "level": -8}),
+        json!({"id": "lz4", "acceleration": 0}),
+        json!({"id": "gzip", "level": -1}),
+        json!({"id": "gzip-12"}),
+        json!({"id": "zlib", "level": 10}),
+        json!({"id": "brotli", "level": 12}),
+        json!({"id": "brotli", "lgwin": 25}),
+        json!({"id": "brotli", "lgwin": 9}),
+        json!({"id": "blosc", "clevel": 10}),
+        json!({"id": "lz4hc", "clevel": -1}),
+        json!({"id": "blosc", "blocksize": -1}),
+        json!({"id": "blosc", "shuffle": 3}),
+        json!({"id": "blosc", "shuffle": "byteshuffle"}),
+        json!({"id": "blosc", "shuffle": true}),
+    ] {
+        match codecs(compressor.clone()) {
+            Err(ZarrError::Metadata(msg)) => assert!(msg.contains("expected"), "{msg}"),
+            other => panic!("{compressor}: expected a metadata error, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn listed_data_types_parse() {
+    for name in capabilities().data_types {
+        let value = json!({"name": name, "configuration": {"length_bytes": 2}});
+        parse_v3_dtype(&value).unwrap_or_else(|e| panic!("{name}: {e}"));
+    }
+}
+
+#[test]
+fn supports_checks_each_kind_of_feature() {
+    let caps = capabilities();
+    assert!(caps.supports(&FeatureId::Codec("zstd".into())));
+    assert!(!caps.supports(&FeatureId::Codec("sharding_indexed".into())));
+    assert!(caps.supports(&FeatureId::Compressor("Blosc".into())));
+    assert!(!caps.supports(&FeatureId::Compressor("bz2".into())));
+    assert!(caps.supports(&FeatureId::Filter("fixedscaleoffset".into())));
+    assert!(!caps.supports(&FeatureId::Filter("delta".into())));
+    assert!(caps.supports(&FeatureId::DataType("r24".into())));
+    assert!(!caps.supports(&FeatureId::DataType("float128".into())));
+    assert!(caps.supports(&FeatureId::ZarrFormat(2)));
+    assert!(!caps.supports(&FeatureId::ZarrFormat(3)));
+}
+
+// ---------------------------------------------------------------------------
+// Unsupported features surface as UnsupportedFeature
+// ---------------------------------------------------------------------------
+
+#[test]
+fn unknown_codecs_are_unsupported() {
+    let err = parse_codec(&json!({"name": "bitround"})).unwrap_err();
+    assert_eq!(unsupported(err), FeatureId::Codec("bitround".into()));
+    assert_eq!(
+        unsupported(parse_codec(&json!({"name": "bitround"})).unwrap_err()).to_string(),
+        "codec: bitround"
+    );
+}
+
+#[tokio::test]
+async fn sharded_chunks_are_unsupported() {
+    let codecs = parse_codecs(&[json!({"name": "sharding_indexed"})]).unwrap();
+    let pipeline = CodecPipeline::new(&codecs).unwrap();
+    let err = pipeline
+        .decode(
+            &[0; 8],
+            "c/0",
+            DataType::Int32,
+            2,
+            &ArrayReadOptions::default(),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        unsupported(err),
+        FeatureId::Codec("sharding_indexed".into())
+    );
+}
+
+async fn open_with_compressor(compressor: serde_json::Value) -> simplezarr::UnifiedZarrArray {
+    let mem = InMemory::new();
+    let zarray = json!({
+        "zarr_format": 2,
+        "shape": [4],
+        "chunks": [4],
+        "dtype": "<i4",
+        "fill_value": 0,
+        "compressor": compressor,
+        "filters": null,
+    });
+    mem.put(
+        &ObjectPath::from("a/.zarray"),
+        PutPayload::from(serde_json::to_vec(&zarray).unwrap()),
+    )
+    .await
+    .unwrap();
+    mem.put(&ObjectPath::from("a/0"), PutPayload::from_static(b"BZh9"))
+        .await
+        .unwrap();
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(mem), ""));
+    v2::open(store, "a").await.unwrap()
+}
+
+#[tokio::test]
+async fn unknown_v2_compressors_fail_on_decode() {
+    let array = open_with_compressor(json!({"id": "bz2", "level": 1})).await;
+    assert_eq!(
+        array.unsupported_codec(),
+        Some(&FeatureId::Compressor("bz2".into()))
+    );
+    let err = array.get_chunk(&[0]).await.unwrap_err();
+    assert_eq!(err.to_string(), "Unsupported V2 compressor: bz2");
+    assert_eq!(unsupported(err), FeatureId::Compressor("bz2".into()));
+    let err = array.load().await.unwrap_err();
+    assert_eq!(unsupported(err), FeatureId::Compressor("bz2".into()));
+}
+
+#[tokio::test]
+async fn unknown_codecs_allow_raw_access() {
+    let array = open_with_compressor(json!({"id": "bz2", "level": 1})).await;
+    assert_eq!(array.metadata.shape, vec![4]);
+    assert_eq!(
+        array.get_chunk_raw(&[0]).await.unwrap().as_deref(),
+        Some(&b"BZh9"[..])
+    );
+    assert!(array.metadata.describe().contains("Compressor  : bz2\n"));
+
+    let array = open_with_compressor(json!({"id": "zlib", "level": 1})).await;
+    assert_eq!(array.unsupported_codec(), None);
+    assert_eq!(
+        array.get_chunk_raw(&[0]).await.unwrap().as_deref(),
+        Some(&b"BZh9"[..])
+    );
+}
+
+// ---------------------------------------------------------------------------
+// V3 extensions
+// ---------------------------------------------------------------------------
+
+fn v3_with_extension(extension: serde_json::Value) -> Result<ZarrV3Metadata, ZarrError> {
+    let document = json!({
+        "zarr_format": 3,
+        "node_type": "array",
+        "shape": [4],
+        "data_type": "int32",
+        "chunk_grid": {"name": "regular", "configuration": {"chunk_shape": [4]}},
+        "chunk_key_encoding": {"name": "default"},
+        "fill_value": 0,
+        "codecs": [{"name": "bytes", "configuration": {"endian": "little"}}],
+        "consolidated_stats": extension,
+    });
+    ZarrV3Metadata::parse(&serde_json::to_vec(&document).unwrap())
+}
+
+#[test]
+fn optional_extensions_are_kept() {
+    let extension = json!({"must_understand": false, "min": 0, "max": 9});
+    let md = v3_with_extension(extension.clone()).unwrap();
+    assert_eq!(md.extensions.len(), 1);
+    assert_eq!(md.extensions["consolidated_stats"], extension);
+}
+
+#[test]
+fn mandatory_extensions_are_unsupported() {
+    for extension in [
+        json!({"must_understand": true}),
+        json!({"min": 0}),
+        json!("stats"),
+    ] {
+        let err = v3_with_extension(extension).unwrap_err();
+        assert_eq!(
+            unsupported(err),
+            FeatureId::Extension("consolidated_stats".into())
+        );
+    }
+    assert!(!capabilities().supports(&FeatureId::Extension("consolidated_stats".into())));
+}
+
+#[tokio::test]
+async fn extensions_are_written_back() {
+    let mut array = open_with_compressor(serde_json::Value::Null).await;
+    assert!(array.metadata.extensions.is_empty());
+    let extension = json!({"must_understand": false, "min": 0});
+    array
+        .metadata
+        .extensions
+        .insert("consolidated_stats".into(), extension.clone());
+    let document = array.metadata.to_v3_json().unwrap();
+    assert_eq!(document["consolidated_stats"], extension);
+    let md = ZarrV3Metadata::parse(&serde_json::to_vec(&document).unwrap()).unwrap();
+    assert_eq!(md.extensions["consolidated_stats"], extension);
+}
@@ -0,0 +1,96 @@
+//! Tests for opening datasets through a catalog.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use simplezarr::builder::ArrayBuilder;
+use simplezarr::catalog::Catalog;
+use simplezarr::error::ZarrError;
+use simplezarr::store::LocalBackend;
+use simplezarr::types::{DataType, ZarrVectorValue};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("simplezarr-catalog-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// A group `g` under `dir` with a 1x3 array `t` holding 1, 2, 3.
+async fn write_group(dir: &PathBuf) {
+    let store = Arc::new(LocalBackend::new(dir));
+    let array = ArrayBuilder::new([1, 3])
+        .dtype(DataType::Int32)
+        .codec(None)
+        .build(store, "g/t")
+        .await
+        .unwrap();
+    array
+        .set_region(
+            &array.full_region(),
+            &ZarrVectorValue::VInt32(vec![1, 2, 3]),
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn datasets_open_by_name_with_their_read_options() {
+    let dir = temp_dir("open");
+    write_group(&dir).await;
+    let catalog = serde_json::json!({
+        "datasets": {
+            "plain": {"url": dir, "path": "g", "arrays": ["t"]},
+            "squeezed": {
+                "url": format!("file://{}", dir.display()),
+                "path": "g",
+                "arrays": ["t"],
+                "description": "t without its singleton axis",
+                "read_options": {"default": {"squeeze": true}},
+            },
+        }
+    });
+    let file = dir.join("catalog.json");
+    std::fs::write(&file, serde_json::to_vec(&catalog).unwrap()).unwrap();
+    let catalog = Catalog::load(&file).await.unwrap();
+    assert_eq!(catalog.names().collect::<Vec<_>>(), ["plain", "squeezed"]);
+
+    let plain = catalog.open("plain").await.unwrap();
+    let t = plain.get_array("t").unwrap().load().await.unwrap();
+    assert_eq!(t.shape, vec![1, 3]);
+    assert_eq!(t, vec![1.0, 2.0, 3.0]);
+    let squeezed = catalog.open("squeezed").await.unwrap();
+    assert_eq!(
+        squeezed.get_array("t").unwrap().load().await.unwrap().shape,
+        vec![3]
+    );
+
+    let err = catalog.open("missing").await.unwrap_err();
+    assert!(matches!(err, ZarrError::NotFound(_)), "{err:?}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn malformed_catalogs_are_metadata_errors() {
+    let err = Catalog::parse(br#"{"datasets": {"a": {"url": 3}}}"#).unwrap_err();
+    assert!(matches!(err, ZarrError::Metadata(_)), "{err:?}");
+    let catalog = Catalog::parse(br#"{"datasets": {"a": {"url": "ftp://x/y"}}}"#).unwrap();
+    assert_eq!(catalog.get("a").unwrap().path, "");
+    let err = catalog.open("a").await.unwrap_err();
+    assert!(matches!(err, ZarrError::Storage(_)), "{err:?}");
+    assert!(Catalog::load("/nonexistent/catalog.json").await.is_err());
+
+    let catalog = Catalog::parse(
+        br#"{"datasets": {"portal": {
+            "url": "https://host/d.zarr",
+            "user_agent": "portal-client/1.0",
+            "headers": {"Bad Header": "x"}
+        }}}"#,
+    )
+    .unwrap();
+    let entry = catalog.get("portal").unwrap();
+    assert_eq!(entry.user_agent.as_deref(), Some("portal-client/1.0"));
+    assert_eq!(entry.headers["Bad Header"], "x");
+    let err = catalog.open("portal").await.unwrap_err();
+    assert!(matches!(err, ZarrError::Storage(_)), "{err:?}");
+}
@@ -0,0 +1,74 @@
+//! Tests for YAML dataset catalogs.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use simplezarr::builder::ArrayBuilder;
+use simplezarr::catalog::Catalog;
+use simplezarr::error::ZarrError;
+use simplezarr::store::LocalBackend;
+use simplezarr::types::{DataType, ZarrVectorValue};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "simplezarr-catalog-yaml-{}-{name}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[tokio::test]
+async fn yaml_catalogs_load_by_extension() {
+    let dir = temp_dir("load");
+    let store = Arc::new(LocalBackend::new(&dir));
+    let array = ArrayBuilder::new([1, 3])
+        .dtype(DataType::Int32)
+        .codec(None)
+        .build(store, "g/t")
+        .await
+        .unwrap();
+    array
+        .set_region(
+            &array.full_region(),
+            &ZarrVectorValue::VInt32(vec![1, 2, 3]),
+        )
+        .await
+        .unwrap();
+
+    let yaml = format!(
+        "datasets:\n  \
+           squeezed:\n    \
+             url: {}\n    \
+             path: g\n    \
+             arrays: [t]\n    \
+             read_options:\n      \
+               default: {{squeeze: true}}\n",
+        dir.display()
+    );
+    for name in ["catalog.yaml", "catalog.YML"] {
+        let file = dir.join(name);
+        std::fs::write(&file, &yaml).unwrap();
+        let catalog = Catalog::load(&file).await.unwrap();
+        assert_eq!(catalog, Catalog::parse_yaml(yaml.as_bytes()).unwrap());
+        let group = catalog.open("squeezed").await.unwrap();
+        let t = group.get_array("t").unwrap().load().await.unwrap();
+        assert_eq!(t.shape, vec![3]);
+        assert_eq!(t, vec![1.0, 2.0, 3.0]);
+    }
+
+    // Other extensions are still JSON.
+    let file = dir.join("catalog.txt");
+    std::fs::write(&file, &yaml).unwrap();
+    let err = Catalog::load(&file).await.unwrap_err();
+    assert!(matches!(err, ZarrError::Metadata(_)), "{err:?}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn malformed_yaml_is_a_metadata_error() {
+    let err = Catalog::parse_yaml(b"datasets:\n  a:\n    url: [1, 2]\n").unwrap_err();
+    assert!(matches!(err, ZarrError::Metadata(_)), "{err:?}");
+    let catalog = Catalog::parse_yaml(b"datasets: {a: {url: 's3://b/a.zarr'}}").unwrap();
+    assert_eq!(catalog.get("a").unwrap().url, "s3://b/a.zarr");
+}
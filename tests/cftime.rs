@@ -0,0 +1,275 @@
+//! Tests for CF time coordinate decoding.
+
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+use object_store::memory::InMemory;
+use serde_json::json;
+
+use simplezarr::cftime::{Calendar, CfDatetime, CfTimeUnits, decode_cf_times};
+use simplezarr::error::ZarrError;
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+use simplezarr::v2;
+
+fn at(calendar: Calendar, year: i64, month: u32, day: u32, hour: u32) -> CfDatetime {
+    CfDatetime {
+        year,
+        month,
+        day,
+        hour,
+        minute: 0,
+        second: 0,
+        microsecond: 0,
+        calendar,
+    }
+}
+
+fn day(calendar: Calendar, year: i64, month: u32, day: u32) -> Option<CfDatetime> {
+    Some(at(calendar, year, month, day, 0))
+}
+
+fn strings(times: &[Option<CfDatetime>]) -> Vec<String> {
+    times
+        .iter()
+        .map(|t| t.map_or("NaT".into(), |t| t.to_string()))
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Units and calendars
+// ---------------------------------------------------------------------------
+
+#[test]
+fn units_parse_with_times_and_offsets() {
+    use Calendar::Standard;
+    let units = CfTimeUnits::parse("days since 1970-01-01", Standard).unwrap();
+    assert_eq!(units.unit_micros, 86_400_000_000);
+    assert_eq!(units.reference, at(Standard, 1970, 1, 1, 0));
+
+    let units = CfTimeUnits::parse("Hours since 1900-01-01 06:30:00.0 +01:00", Standard).unwrap();
+    assert_eq!(units.unit_micros, 3_600_000_000);
+    assert_eq!(units.reference.to_string(), "1900-01-01 05:30:00");
+
+    let units = CfTimeUnits::parse("seconds since 2000-01-01T12:00:00Z", Standard).unwrap();
+    assert_eq!(units.reference, at(Standard, 2000, 1, 1, 12));
+    let units = CfTimeUnits::parse("minutes since 2000-01-01 00:00 -02", Standard).unwrap();
+    assert_eq!(units.reference, at(Standard, 2000, 1, 1, 2));
+
+    let units = CfTimeUnits::parse("days since -4713-01-01 12:00", Calendar::Julian).unwrap();
+    assert_eq!(units.reference, at(Calendar::Julian, -4713, 1, 1, 12));
+    let units = CfTimeUnits::parse("days since 2000-02-30", Calendar::Day360).unwrap();
+    assert_eq!(units.reference, at(Calendar::Day360, 2000, 2, 30, 0));
+
+    for (bad, calendar) in [
+        ("days", Standard),
+        ("months since 2000-01-01", Standard),
+        ("years since 2000-01-01", Standard),
+        ("days since yesterday", Standard),
+        ("days since 2000-13-01", Standard),
+        ("days since 2000-02-30", Standard),
+        ("days since 2001-02-29", Calendar::NoLeap),
+        ("days since 1582-10-10", Standard),
+        ("days since 0000-01-01", Standard),
+    ] {
+        let err = CfTimeUnits::parse(bad, calendar).unwrap_err();
+        assert!(matches!(err, ZarrError::Metadata(_)), "{bad}: {err:?}");
+    }
+    // Both exist where the calendar has them.
+    CfTimeUnits::parse("days since 1582-10-10", Calendar::ProlepticGregorian).unwrap();
+    CfTimeUnits::parse("days since 0000-01-01", Calendar::ProlepticGregorian).unwrap();
+}
+
+#[test]
+fn calendars_parse_by_name() {
+    assert_eq!(Calendar::parse("gregorian").unwrap(), Calendar::Standard);
+    assert_eq!(
+        Calendar::parse("proleptic_gregorian").unwrap(),
+        Calendar::ProlepticGregorian
+    );
+    assert_eq!(Calendar::parse("Julian").unwrap(), Calendar::Julian);
+    assert_eq!(Calendar::parse("365_day").unwrap(), Calendar::NoLeap);
+    assert_eq!(Calendar::parse("ALL_LEAP").unwrap(), Calendar::AllLeap);
+    assert_eq!(Calendar::parse("360_day").unwrap(), Calendar::Day360);
+    assert!(Calendar::parse("lunar").is_err());
+}
+
+// ---------------------------------------------------------------------------
+// Decoding
+// ---------------------------------------------------------------------------
+
+#[test]
+fn standard_calendar_decodes_offsets() {
+    use Calendar::Standard;
+    let times = decode_cf_times(
+        &[0.0, 1.5, -1.0, f64::NAN, 59.0],
+        "days since 2000-01-01",
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        times,
+        vec![
+            day(Standard, 2000, 1, 1),
+            Some(at(Standard, 2000, 1, 2, 12)),
+            day(Standard, 1999, 12, 31),
+            None,
+            day(Standard, 2000, 2, 29),
+        ]
+    );
+
+    let times = decode_cf_times(&[90.5], "minutes since 2000-01-01", Some("standard")).unwrap();
+    assert_eq!(strings(&times), ["2000-01-01 01:30:30"]);
+    let times = decode_cf_times(&[0.25], "seconds since 2000-01-01", None).unwrap();
+    assert_eq!(strings(&times), ["2000-01-01 00:00:00.250000"]);
+
+    let err = decode_cf_times(&[f64::INFINITY], "days since 2000-01-01", None).unwrap_err();
+    assert!(matches!(err, ZarrError::Decode(_)), "{err:?}");
+}
+
+#[test]
+fn standard_calendar_is_julian_before_the_reform() {
+    // cftime: num2date([-1, 0, 1], "days since 1582-10-15", "standard").
+    let times = decode_cf_times(&[-1.0, 0.0, 1.0], "days since 1582-10-15", None).unwrap();
+    assert_eq!(
+        strings(&times),
+        [
+            "1582-10-04 00:00:00",
+            "1582-10-15 00:00:00",
+            "1582-10-16 00:00:00"
+        ]
+    );
+    // 1500 is a Julian leap year but not a Gregorian one.
+    let times = decode_cf_times(&[59.0], "days since 1500-01-01", Some("gregorian")).unwrap();
+    assert_eq!(strings(&times), ["1500-02-29 00:00:00"]);
+    let times = decode_cf_times(
+        &[59.0],
+        "days since 1500-01-01",
+        Some("proleptic_gregorian"),
+    )
+    .unwrap();
+    assert_eq!(strings(&times), ["1500-03-01 00:00:00"]);
+
+    // Julian day numbers, and the year before 1 AD.
+    let times = decode_cf_times(
+        &[0.0, 2_451_545.0, 1_721_423.5],
+        "days since -4713-01-01 12:00",
+        Some("julian"),
+    )
+    .unwrap();
+    assert_eq!(
+        strings(&times),
+        [
+            "-4713-01-01 12:00:00",
+            "1999-12-19 12:00:00",
+            "0001-01-01 00:00:00"
+        ]
+    );
+    let times = decode_cf_times(&[-1.0], "days since 0001-01-01", None).unwrap();
+    assert_eq!(times, vec![day(Calendar::Standard, -1, 12, 31)]);
+
+    // chrono is proleptic Gregorian, so the instant is kept.
+    let julian = decode_cf_times(&[0.0], "days since 1582-10-04", None).unwrap()[0].unwrap();
+    assert_eq!(
+        julian.to_naive().unwrap(),
+        NaiveDate::from_ymd_opt(1582, 10, 14)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    );
+}
+
+#[test]
+fn model_calendars_count_their_own_days() {
+    use Calendar::{AllLeap, Day360, NoLeap};
+    // 2000 is a leap year, but not in a 365-day calendar.
+    let times = decode_cf_times(&[59.0, 365.0], "days since 2000-01-01", Some("noleap")).unwrap();
+    assert_eq!(
+        times,
+        vec![day(NoLeap, 2000, 3, 1), day(NoLeap, 2001, 1, 1)]
+    );
+
+    // Every year has 366 days.
+    let times = decode_cf_times(
+        &[58.0, 59.0, 366.0],
+        "days since 2003-01-01",
+        Some("all_leap"),
+    )
+    .unwrap();
+    assert_eq!(
+        times,
+        vec![
+            day(AllLeap, 2003, 2, 28),
+            day(AllLeap, 2003, 2, 29),
+            day(AllLeap, 2004, 1, 1)
+        ]
+    );
+
+    let times = decode_cf_times(
+        &[30.0, 59.0, 359.5, -1.0],
+        "days since 2000-01-01",
+        Some("360_day"),
+    )
+    .unwrap();
+    assert_eq!(
+        times,
+        vec![
+            day(Day360, 2000, 2, 1),
+            day(Day360, 2000, 2, 30),
+            Some(at(Day360, 2000, 12, 30, 12)),
+            day(Day360, 1999, 12, 30),
+        ]
+    );
+
+    // Only dates the Gregorian calendar has convert to chrono.
+    assert!(times[1].unwrap().to_naive().is_none());
+    assert_eq!(
+        times[0].unwrap().to_naive().unwrap(),
+        NaiveDate::from_ymd_opt(2000, 2, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    );
+}
+
+#[tokio::test]
+async fn arrays_decode_with_their_attributes() {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let zarray = json!({
+        "zarr_format": 2,
+        "shape": [3],
+        "chunks": [3],
+        "dtype": "<f8",
+        "fill_value": "NaN",
+        "order": "C",
+        "compressor": null,
+        "filters": null,
+    });
+    store
+        .put("time/.zarray", serde_json::to_vec(&zarray).unwrap().into())
+        .await
+        .unwrap();
+    let chunk: Vec<u8> = [0.0f64, 6.0, 12.0]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    store.put("time/0", chunk.into()).await.unwrap();
+
+    let mut time = v2::open(store.clone(), "time").await.unwrap();
+    let err = time.load_cf_times().await.unwrap_err();
+    assert!(matches!(err, ZarrError::Metadata(_)), "{err:?}");
+
+    let zattrs = json!({"units": "hours since 2020-06-01", "calendar": "proleptic_gregorian"});
+    store
+        .put("time/.zattrs", serde_json::to_vec(&zattrs).unwrap().into())
+        .await
+        .unwrap();
+    time.load_attributes().await.unwrap();
+    assert_eq!(
+        time.load_cf_times().await.unwrap(),
+        vec![
+            day(Calendar::ProlepticGregorian, 2020, 6, 1),
+            Some(at(Calendar::ProlepticGregorian, 2020, 6, 1, 6)),
+            Some(at(Calendar::ProlepticGregorian, 2020, 6, 1, 12)),
+        ]
+    );
+}
@@ -0,0 +1,634 @@
+//! Tests for individual codecs, independent of any store.
+
+use serde_json::json;
+
+use simplezarr::capabilities::{FeatureId, capabilities};
+use simplezarr::codecs::bitround::BitRoundCodec;
+use simplezarr::codecs::blosc::BloscCodec;
+#[cfg(feature = "brotli")]
+use simplezarr::codecs::brotli::BrotliCodec;
+use simplezarr::codecs::fixedscaleoffset::FixedScaleOffsetCodec;
+use simplezarr::codecs::zlib::ZlibCodec;
+use simplezarr::codecs::{
+    AnyCodec, CodecKind, CodecPipeline, codec_to_json, parse_codec, parse_codecs,
+};
+use simplezarr::config::{self, SimplezarrConfig};
+use simplezarr::error::ZarrError;
+use simplezarr::options::ArrayReadOptions;
+use simplezarr::types::{DataType, Endian, ZarrVectorValue, zarr_vector_to_bytes};
+use simplezarr::v2::{codec_to_filter, filter_to_codec};
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn fso(dtype: &str, astype: &str, scale: f64, offset: f64) -> FixedScaleOffsetCodec {
+    FixedScaleOffsetCodec {
+        scale,
+        offset,
+        dtype: dtype.into(),
+        astype: astype.into(),
+    }
+}
+
+fn f64_bytes(values: &[f64]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn f64_values(bytes: &[u8]) -> Vec<f64> {
+    bytes
+        .chunks_exact(8)
+        .map(|c| f64::from_le_bytes(c.try_into().unwrap()))
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// FixedScaleOffset
+// ---------------------------------------------------------------------------
+
+#[test]
+fn fixedscaleoffset_int8_to_float64() {
+    let codec = fso("float64", "int8", 2.0, 10.0);
+    let stored = [-128i8, 0, 127].map(|v| v as u8);
+    let decoded = f64_values(&codec.decode(&stored).unwrap());
+    assert_eq!(decoded, vec![-54.0, 10.0, 73.5]);
+}
+
+#[test]
+fn fixedscaleoffset_int64_to_float32() {
+    let codec = fso("float32", "int64", 4.0, 0.0);
+    let stored: Vec<u8> = [-8i64, 4].iter().flat_map(|v| v.to_le_bytes()).collect();
+    let out = codec.decode(&stored).unwrap();
+    assert_eq!(out.len(), 8);
+    assert_eq!(f32::from_le_bytes(out[..4].try_into().unwrap()), -2.0);
+    assert_eq!(f32::from_le_bytes(out[4..].try_into().unwrap()), 1.0);
+}
+
+#[test]
+fn fixedscaleoffset_accepts_numpy_type_strings() {
+    let codec = fso("<f8", "|u1", 0.5, 1.0);
+    assert_eq!(codec.types().unwrap(), (DataType::UInt8, DataType::Float64));
+    assert_eq!(
+        f64_values(&codec.decode(&[0, 255]).unwrap()),
+        vec![1.0, 511.0]
+    );
+}
+
+#[test]
+fn fixedscaleoffset_encode_round_trips() {
+    for astype in ["int8", "int16", "int32", "int64", "uint16", "<u4"] {
+        let codec = fso("float64", astype, 10.0, 5.0);
+        let values = [5.0, 5.3, 12.7];
+        let encoded = codec.encode(&f64_bytes(&values)).unwrap();
+        let decoded = f64_values(&codec.decode(&encoded).unwrap());
+        for (got, want) in decoded.iter().zip(values) {
+            assert!((got - want).abs() < 1e-9, "{astype}: {got} != {want}");
+        }
+    }
+}
+
+#[test]
+fn fixedscaleoffset_encode_saturates() {
+    let codec = fso("float64", "int8", 1.0, 0.0);
+    let encoded = codec.encode(&f64_bytes(&[1000.0, -1000.0, 2.6])).unwrap();
+    assert_eq!(encoded, vec![127u8, 128, 3]);
+}
+
+#[test]
+fn fixedscaleoffset_encode_rounds_halves_to_even() {
+    let codec = fso("float64", "int8", 1.0, 0.0);
+    let encoded = codec
+        .encode(&f64_bytes(&[0.5, 1.5, 2.5, -0.5, -1.5]))
+        .unwrap();
+    assert_eq!(encoded, [0i8, 2, 2, 0, -2].map(|v| v as u8));
+}
+
+#[test]
+fn fixedscaleoffset_rejects_unsupported_types() {
+    for (dtype, astype) in [
+        ("int32", "int16"),
+        ("float64", "float32"),
+        ("float64", "int128"),
+    ] {
+        let err = fso(dtype, astype, 1.0, 0.0).decode(&[0; 8]).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Unsupported FixedScaleOffset conversion"),
+            "{err}"
+        );
+    }
+}
+
+#[test]
+fn fixedscaleoffset_byte_level_codec_reads_numpy_byte_order() {
+    let codec = fso(">f4", ">i2", 1.0, 0.0);
+    let out = codec.decode(&300i16.to_be_bytes()).unwrap();
+    assert_eq!(out, 300f32.to_be_bytes());
+    assert_eq!(codec.encode(&out).unwrap(), 300i16.to_be_bytes());
+}
+
+// ---------------------------------------------------------------------------
+// BitRound
+// ---------------------------------------------------------------------------
+
+#[test]
+fn bitround_rounds_mantissas_like_numcodecs() {
+    // Expected bit patterns from numcodecs' formula; 1.1875 is a tie that
+    // rounds to the even mantissa at 3 bits.
+    let values = ZarrVectorValue::VFloat32(vec![1.234_567_8, -std::f32::consts::PI, 1.1875, 0.1]);
+    for (keepbits, expected) in [
+        (3, [0x3fa0_0000, 0xc050_0000, 0x3fa0_0000, 0x3dd0_0000]),
+        (7, [0x3f9e_0000, 0xc049_0000, 0x3f98_0000, 0x3dcd_0000]),
+    ] {
+        let ZarrVectorValue::VFloat32(rounded) =
+            BitRoundCodec::new(keepbits).encode_array(&values).unwrap()
+        else {
+            panic!("bitround changed the element type");
+        };
+        let bits: Vec<u32> = rounded.iter().map(|x| x.to_bits()).collect();
+        assert_eq!(bits, expected, "keepbits {keepbits}");
+    }
+
+    // Keeping every bit is the identity, and NaN and infinities survive.
+    let values = ZarrVectorValue::VFloat64(vec![0.1, f64::INFINITY, f64::NAN]);
+    let ZarrVectorValue::VFloat64(kept) = BitRoundCodec::new(52).encode_array(&values).unwrap()
+    else {
+        panic!("bitround changed the element type");
+    };
+    assert_eq!(kept[0], 0.1);
+    let ZarrVectorValue::VFloat64(rounded) = BitRoundCodec::new(0).encode_array(&values).unwrap()
+    else {
+        panic!("bitround changed the element type");
+    };
+    assert_eq!(rounded[0], 0.125);
+    assert_eq!(rounded[1], f64::INFINITY);
+    assert!(rounded[2].is_nan());
+}
+
+#[test]
+fn bitround_needs_floats_with_enough_mantissa_bits() {
+    let codec = BitRoundCodec::new(11);
+    assert!(codec.encoded_data_type(DataType::Float16).is_err());
+    assert_eq!(
+        codec.encoded_data_type(DataType::Float32).unwrap(),
+        DataType::Float32
+    );
+    assert!(codec.encoded_data_type(DataType::Int32).is_err());
+    assert!(
+        codec
+            .encode_array(&ZarrVectorValue::VInt32(vec![1]))
+            .is_err()
+    );
+    // Without the element type only decoding works.
+    assert_eq!(codec.decode(&[1, 2, 3, 4]).unwrap(), vec![1, 2, 3, 4]);
+    assert!(codec.encode(&[1, 2, 3, 4]).is_err());
+}
+
+#[test]
+fn bitround_metadata_round_trips() {
+    let codec =
+        parse_codec(&json!({"name": "numcodecs.bitround", "configuration": {"keepbits": 7}}))
+            .unwrap();
+    assert!(matches!(
+        codec,
+        AnyCodec::BitRound(BitRoundCodec { keepbits: 7 })
+    ));
+    assert_eq!(codec.kind(), CodecKind::ArrayToArray);
+    assert_eq!(codec.to_string(), "bitround(keepbits=7)");
+    assert_eq!(
+        codec_to_json(&codec),
+        json!({"name": "numcodecs.bitround", "configuration": {"keepbits": 7}})
+    );
+
+    let filter = codec_to_filter(&codec).unwrap();
+    assert_eq!(filter, json!({"id": "bitround", "keepbits": 7}));
+    let filter = serde_json::from_value(filter).unwrap();
+    assert!(matches!(
+        filter_to_codec(&filter).unwrap(),
+        AnyCodec::BitRound(BitRoundCodec { keepbits: 7 })
+    ));
+    let filter = serde_json::from_value(json!({"id": "bitround"})).unwrap();
+    assert!(filter_to_codec(&filter).is_err());
+}
+
+#[tokio::test]
+async fn bitround_pipelines_round_on_encode_only() {
+    let codecs = parse_codecs(&[
+        json!({"name": "numcodecs.bitround", "configuration": {"keepbits": 3}}),
+        json!({"name": "bytes", "configuration": {"endian": "little"}}),
+    ])
+    .unwrap();
+    let pipeline = CodecPipeline::new(&codecs).unwrap();
+    let encoded = pipeline
+        .encode(&ZarrVectorValue::VFloat32(vec![1.234_567_8, 0.1]))
+        .await
+        .unwrap();
+    let decoded = pipeline
+        .decode(
+            &encoded,
+            "0",
+            DataType::Float32,
+            2,
+            &ArrayReadOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(decoded.to_f64_vec().unwrap(), vec![1.25, 0.101_562_5]);
+}
+
+// ---------------------------------------------------------------------------
+// CodecPipeline
+// ---------------------------------------------------------------------------
+
+fn fso_pipeline(endian: &str) -> Vec<AnyCodec> {
+    parse_codecs(&[
+        json!({"name": "numcodecs.fixedscaleoffset", "configuration": {
+            "scale": 10.0, "offset": 1.0, "dtype": "float64", "astype": "int16"}}),
+        json!({"name": "bytes", "configuration": {"endian": endian}}),
+        json!({"name": "zlib", "configuration": {"level": 1}}),
+    ])
+    .unwrap()
+}
+
+#[test]
+fn pipeline_stages_codecs_by_kind() {
+    let codecs = fso_pipeline("big");
+    let kinds: Vec<CodecKind> = codecs.iter().map(AnyCodec::kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            CodecKind::ArrayToArray,
+            CodecKind::ArrayToBytes,
+            CodecKind::BytesToBytes
+        ]
+    );
+    let pipeline = CodecPipeline::new(&codecs).unwrap();
+    assert_eq!(pipeline.endian(), Endian::Big);
+    assert_eq!(
+        pipeline.encoded_data_type(DataType::Float64).unwrap(),
+        DataType::Int16
+    );
+    assert!(pipeline.encoded_data_type(DataType::Float32).is_err());
+}
+
+#[tokio::test]
+async fn pipeline_serialises_the_encoded_type_in_the_bytes_codec_order() {
+    let options = ArrayReadOptions::default();
+    for (endian, to_bytes) in [
+        ("big", i16::to_be_bytes as fn(i16) -> [u8; 2]),
+        ("little", i16::to_le_bytes),
+    ] {
+        let codecs = fso_pipeline(endian);
+        let pipeline = CodecPipeline::new(&codecs).unwrap();
+        let values = ZarrVectorValue::VFloat64(vec![1.0, 2.5, -3.0]);
+        let encoded = pipeline.encode(&values).await.unwrap();
+
+        // The compressed payload holds int16s in the bytes codec's order.
+        let stored = ZlibCodec { level: 1 }.decode(&encoded).unwrap();
+        let want: Vec<u8> = [0i16, 15, -40].into_iter().flat_map(to_bytes).collect();
+        assert_eq!(stored, want, "{endian}");
+
+        let decoded = pipeline
+            .decode(&encoded, "c/0", DataType::Float64, 3, &options)
+            .await
+            .unwrap();
+        assert_eq!(
+            decoded.to_f64_vec().unwrap(),
+            vec![1.0, 2.5, -3.0],
+            "{endian}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn pipeline_checks_the_encoded_size() {
+    let mut codecs = fso_pipeline("little");
+    codecs.pop();
+    let pipeline = CodecPipeline::new(&codecs).unwrap();
+    let options = ArrayReadOptions::default();
+    // Four int16s are eight bytes, which would pass for one float64.
+    let err = pipeline
+        .decode(&[0u8; 8], "c/0", DataType::Float64, 1, &options)
+        .await
+        .unwrap_err();
+    match err {
+        ZarrError::ChunkSizeMismatch {
+            expected, actual, ..
+        } => assert_eq!((expected, actual), (2, 8)),
+        other => panic!("expected ChunkSizeMismatch, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn native_and_unspecified_byte_orders_resolve_at_decode_time() {
+    assert_eq!(Endian::Native.resolve(), Endian::host());
+    assert_eq!(Endian::Big.resolve(), Endian::Big);
+
+    let codecs = fso_pipeline("native");
+    let pipeline = CodecPipeline::new(&codecs).unwrap();
+    assert_eq!(pipeline.endian(), Endian::host());
+    let values = ZarrVectorValue::VFloat64(vec![1.0, 2.5, -3.0]);
+    let encoded = pipeline.encode(&values).await.unwrap();
+    let stored = ZlibCodec { level: 1 }.decode(&encoded).unwrap();
+    let want: Vec<u8> = [0i16, 15, -40]
+        .into_iter()
+        .flat_map(i16::to_ne_bytes)
+        .collect();
+    assert_eq!(stored, want);
+
+    // Written metadata names the concrete order.
+    assert_eq!(
+        codec_to_json(&codecs[1])["configuration"]["endian"],
+        json!(Endian::host())
+    );
+
+    // A bytes codec without `endian` reads in the configured default.
+    let codecs = parse_codecs(&[json!({"name": "bytes"})]).unwrap();
+    let pipeline = CodecPipeline::new(&codecs).unwrap();
+    let chunk = 258u16.to_be_bytes();
+    for (default, want) in [(Endian::Little, 513), (Endian::Big, 258)] {
+        let options = ArrayReadOptions::default().default_endian(default);
+        let decoded = pipeline
+            .decode(&chunk, "c/0", DataType::UInt16, 1, &options)
+            .await
+            .unwrap();
+        assert!(
+            matches!(decoded, ZarrVectorValue::VUInt16(ref v) if v == &[want]),
+            "{default:?}: {decoded:?}"
+        );
+    }
+}
+
+/// Two values of each data type next to their big-endian encoding, as
+/// numpy writes them with `astype(">...").tobytes()`.
+fn big_endian_references() -> Vec<(DataType, ZarrVectorValue, Vec<u8>)> {
+    use half::f16;
+    use num_complex::Complex;
+    vec![
+        (
+            DataType::Bool,
+            ZarrVectorValue::VBool(vec![true, false]),
+            vec![1, 0],
+        ),
+        (
+            DataType::Int8,
+            ZarrVectorValue::VInt8(vec![1, -2]),
+            vec![0x01, 0xfe],
+        ),
+        (
+            DataType::UInt8,
+            ZarrVectorValue::VUInt8(vec![1, 254]),
+            vec![0x01, 0xfe],
+        ),
+        (
+            DataType::Int16,
+            ZarrVectorValue::VInt16(vec![1, -2]),
+            vec![0x00, 0x01, 0xff, 0xfe],
+        ),
+        (
+            DataType::UInt16,
+            ZarrVectorValue::VUInt16(vec![1, 0x0102]),
+            vec![0x00, 0x01, 0x01, 0x02],
+        ),
+        (
+            DataType::Int32,
+            ZarrVectorValue::VInt32(vec![1, -2]),
+            vec![0, 0, 0, 0x01, 0xff, 0xff, 0xff, 0xfe],
+        ),
+        (
+            DataType::UInt32,
+            ZarrVectorValue::VUInt32(vec![1, 0x01020304]),
+            vec![0, 0, 0, 0x01, 0x01, 0x02, 0x03, 0x04],
+        ),
+        (
+            DataType::Int64,
+            ZarrVectorValue::VInt64(vec![1, -2]),
+            [
+                [0, 0, 0, 0, 0, 0, 0, 0x01],
+                [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe],
+            ]
+            .concat(),
+        ),
+        (
+            DataType::UInt64,
+            ZarrVectorValue::VUInt64(vec![1, 0x0102030405060708]),
+            [[0, 0, 0, 0, 0, 0, 0, 0x01], [1, 2, 3, 4, 5, 6, 7, 8]].concat(),
+        ),
+        (
+            DataType::Float16,
+            ZarrVectorValue::VFloat16(vec![f16::from_f32(1.0), f16::from_f32(-2.0)]),
+            vec![0x3c, 0x00, 0xc0, 0x00],
+        ),
+        (
+            DataType::Float32,
+            ZarrVectorValue::VFloat32(vec![1.0, -2.0]),
+            vec![0x3f, 0x80, 0, 0, 0xc0, 0, 0, 0],
+        ),
+        (
+            DataType::Float64,
+            ZarrVectorValue::VFloat64(vec![1.0, -2.0]),
+            [[0x3f, 0xf0, 0, 0, 0, 0, 0, 0], [0xc0, 0, 0, 0, 0, 0, 0, 0]].concat(),
+        ),
+        (
+            DataType::Complex64,
+            ZarrVectorValue::VComplex64(vec![Complex::new(1.0, -2.0), Complex::new(-2.0, 1.0)]),
+            [
+                [0x3f, 0x80, 0, 0, 0xc0, 0, 0, 0],
+                [0xc0, 0, 0, 0, 0x3f, 0x80, 0, 0],
+            ]
+            .concat(),
+        ),
+        (
+            DataType::Complex128,
+            ZarrVectorValue::VComplex128(vec![Complex::new(1.0, -2.0)]),
+            [[0x3f, 0xf0, 0, 0, 0, 0, 0, 0], [0xc0, 0, 0, 0, 0, 0, 0, 0]].concat(),
+        ),
+    ]
+}
+
+#[tokio::test]
+async fn big_endian_bytes_codec_reads_and_writes_every_dtype() {
+    let codecs = parse_codecs(&[
+        json!({"name": "bytes", "configuration": {"endian": "big"}}),
+        json!({"name": "zlib", "configuration": {"level": 1}}),
+    ])
+    .unwrap();
+    let pipeline = CodecPipeline::new(&codecs).unwrap();
+    assert_eq!(pipeline.endian(), Endian::Big);
+    // A big-endian codec wins over a little-endian default.
+    let options = ArrayReadOptions::default().default_endian(Endian::Little);
+
+    for (dtype, values, reference) in big_endian_references() {
+        let chunk = ZlibCodec { level: 1 }.encode(&reference).unwrap();
+        let decoded = pipeline
+            .decode(&chunk, "c/0", dtype, values.len(), &options)
+            .await
+            .unwrap();
+        assert_eq!(
+            zarr_vector_to_bytes(Endian::Little, &decoded).unwrap(),
+            zarr_vector_to_bytes(Endian::Little, &values).unwrap(),
+            "{dtype:?}"
+        );
+
+        let encoded = pipeline.encode(&values).await.unwrap();
+        assert_eq!(
+            ZlibCodec { level: 1 }.decode(&encoded).unwrap(),
+            reference,
+            "{dtype:?}"
+        );
+    }
+}
+
+#[test]
+fn invalid_bytes_codec_configuration_is_rejected() {
+    for config in [json!({"endian": "middle"}), json!({"endian": 1})] {
+        let err = parse_codec(&json!({"name": "bytes", "configuration": config})).unwrap_err();
+        assert!(matches!(err, ZarrError::Codec(_)), "{config}: {err:?}");
+    }
+}
+
+#[test]
+fn pipelines_describe_codecs_in_read_order() {
+    let codecs = parse_codecs(&[
+        json!({"name": "bytes", "configuration": {"endian": "little"}}),
+        json!({"name": "blosc", "configuration": {
+            "cname": "zstd", "clevel": 5, "shuffle": "shuffle", "typesize": 4, "blocksize": 0}}),
+    ])
+    .unwrap();
+    let pipeline = CodecPipeline::new(&codecs).unwrap();
+    assert_eq!(
+        pipeline.describe(),
+        "blosc(zstd, clevel=5, shuffle) → bytes(LE)"
+    );
+
+    let codecs = fso_pipeline("big");
+    assert_eq!(
+        CodecPipeline::new(&codecs).unwrap().describe(),
+        "zlib(level=1) → bytes(BE) → fixedscaleoffset(scale=10, offset=1, float64 → int16)"
+    );
+    assert_eq!(codecs[2].to_string(), "zlib(level=1)");
+
+    // Without a bytes codec the assumed one is shown.
+    let codecs = parse_codecs(&[json!({"name": "gzip", "configuration": {"level": 5}})]).unwrap();
+    assert_eq!(
+        CodecPipeline::new(&codecs).unwrap().describe(),
+        "gzip(level=5) → bytes"
+    );
+}
+
+#[test]
+fn pipeline_rejects_two_array_to_bytes_codecs() {
+    let codecs = parse_codecs(&[json!({"name": "bytes"}), json!({"name": "bytes"})]).unwrap();
+    let err = CodecPipeline::new(&codecs).unwrap_err();
+    assert!(err.to_string().contains("more than one"), "{err}");
+}
+
+// ---------------------------------------------------------------------------
+// Feature-gated codecs
+// ---------------------------------------------------------------------------
+
+#[test]
+fn parse_codec_follows_compiled_features() {
+    let caps = capabilities();
+    for (name, compiled) in [
+        ("blosc", cfg!(feature = "blosc")),
+        ("zstd", cfg!(feature = "zstd")),
+        ("lz4", cfg!(feature = "lz4")),
+        ("numcodecs.snappy", cfg!(feature = "snappy")),
+        ("numcodecs.brotli", cfg!(feature = "brotli")),
+    ] {
+        assert_eq!(caps.codecs.contains(&name), compiled, "{name}");
+        match parse_codec(&json!({"name": name})) {
+            Ok(_) => assert!(compiled, "{name} parsed without its feature"),
+            Err(ZarrError::UnsupportedFeature(FeatureId::Codec(got))) => {
+                assert!(!compiled, "{name} is compiled in");
+                assert_eq!(got, name);
+            }
+            Err(other) => panic!("{name}: unexpected error {other:?}"),
+        }
+    }
+    // Always available: flate2 is pure Rust.
+    assert!(caps.v2_compressors.contains(&"zlib"));
+    assert!(caps.v2_compressors.contains(&"gzip"));
+    assert_eq!(
+        caps.v2_compressors.contains(&"snappy"),
+        cfg!(feature = "snappy")
+    );
+}
+
+#[cfg(feature = "brotli")]
+#[test]
+fn brotli_settings_are_checked_not_clamped() {
+    let config = json!({"name": "numcodecs.brotli", "configuration": {"level": "max"}});
+    let err = parse_codec(&config).unwrap_err();
+    assert!(matches!(err, ZarrError::Codec(_)), "{err:?}");
+
+    for (level, lgwin) in [(12, 22), (5, 9), (5, 25)] {
+        let codec = BrotliCodec { level, lgwin };
+        let err = codec.encode(b"data").unwrap_err();
+        assert!(
+            matches!(err, ZarrError::Encode(_)),
+            "{level}/{lgwin}: {err:?}"
+        );
+    }
+    let codec = BrotliCodec {
+        level: 11,
+        lgwin: 10,
+    };
+    assert_eq!(
+        codec.decode(&codec.encode(b"data").unwrap()).unwrap(),
+        b"data"
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Blosc threading
+// ---------------------------------------------------------------------------
+
+/// The only test in this binary that touches the global configuration.
+#[test]
+fn blosc_threads_are_capped_by_the_global_ceiling() {
+    let codec = BloscCodec::default();
+    assert_eq!(codec.effective_nthreads(), 1);
+
+    config::set_global(SimplezarrConfig::builder().blosc_max_threads(4).build());
+    assert_eq!(codec.clone().with_nthreads(2).effective_nthreads(), 2);
+    assert_eq!(codec.clone().with_nthreads(16).effective_nthreads(), 4);
+    assert_eq!(codec.clone().with_nthreads(0).effective_nthreads(), 1);
+    config::set_global(SimplezarrConfig::default());
+
+    let codec = codec.with_blocksize(Some(1 << 16));
+    assert_eq!(codec.blocksize, 1 << 16);
+    assert_eq!(codec.with_blocksize(None).blocksize, 0);
+}
+
+#[test]
+fn blosc_threads_follow_the_array_config() {
+    let config = SimplezarrConfig::builder()
+        .blosc_nthreads(3)
+        .blosc_max_threads(6)
+        .build();
+    let codec = BloscCodec::default().with_config(&config);
+    assert_eq!(codec.nthreads, Some(3));
+    assert_eq!(codec.effective_nthreads(), 3);
+    let codec = BloscCodec::default()
+        .with_nthreads(usize::MAX)
+        .with_config(&config);
+    assert_eq!(codec.effective_nthreads(), 6);
+
+    let unlimited = SimplezarrConfig::builder()
+        .blosc_max_threads(usize::MAX)
+        .build();
+    let codec = BloscCodec::default()
+        .with_nthreads(usize::MAX)
+        .with_config(&unlimited);
+    assert!(codec.effective_nthreads() <= i32::MAX as usize);
+}
+
+#[test]
+fn blosc_threads_stay_out_of_metadata() {
+    let codec: BloscCodec = serde_json::from_value(json!({"cname": "lz4", "nthreads": 8})).unwrap();
+    assert_eq!(codec.nthreads, None);
+    let written = serde_json::to_value(codec.with_nthreads(8)).unwrap();
+    assert!(written.get("nthreads").is_none(), "{written}");
+}
@@ -0,0 +1,152 @@
+//! Tests for comparing values and arrays within a tolerance.
+
+use std::sync::Arc;
+
+use num_complex::Complex;
+use object_store::memory::InMemory;
+
+use simplezarr::ArrayData;
+use simplezarr::builder::ArrayBuilder;
+use simplezarr::compare::Tolerance;
+use simplezarr::store::ObjectStoreBackend;
+use simplezarr::types::{ArrayOrder, DataType, ZarrValue, ZarrVectorValue};
+
+fn f64s(values: &[f64]) -> ZarrVectorValue {
+    ZarrVectorValue::VFloat64(values.to_vec())
+}
+
+// ---------------------------------------------------------------------------
+// Vectors
+// ---------------------------------------------------------------------------
+
+#[test]
+fn approx_eq_follows_numpy_allclose() {
+    let reference = f64s(&[1.0, 100.0, 0.0, f64::INFINITY]);
+    let close = f64s(&[1.0 + 1e-6, 100.0 + 1e-3, 1e-9, f64::INFINITY]);
+    assert!(close.approx_eq(&reference, 1e-5, 1e-8));
+    assert!(!close.approx_eq(&reference, 0.0, 0.0));
+    assert!(!f64s(&[1e300]).approx_eq(&f64s(&[f64::INFINITY]), 1.0, 0.0));
+    assert!(!f64s(&[1.0]).approx_eq(&f64s(&[1.0, 2.0]), 1.0, 1.0));
+
+    // NaN only matches NaN when asked to.
+    let nan = f64s(&[f64::NAN]);
+    assert!(!nan.approx_eq(&nan, 1e-5, 1e-8));
+    let comparison = nan
+        .compare(&nan, &Tolerance::default().nan_equal(true))
+        .unwrap();
+    assert!(comparison.is_equal(), "{comparison}");
+
+    // Different element types compare by value.
+    let ints = ZarrVectorValue::VInt16(vec![1, 100]);
+    assert!(ints.approx_eq(&ZarrVectorValue::VFloat32(vec![1.0, 100.0]), 0.0, 0.0));
+}
+
+#[test]
+fn comparisons_count_and_locate_mismatches() {
+    let comparison = f64s(&[1.0, 2.5, 3.0, 7.0])
+        .compare(&f64s(&[1.0, 2.0, 3.0, 4.0]), &Tolerance::exact())
+        .unwrap();
+    assert_eq!(comparison.mismatches, 2);
+    assert_eq!(comparison.first_mismatch, Some(vec![1]));
+    assert_eq!(comparison.max_abs_diff, 3.0);
+    assert_eq!(
+        comparison.to_string(),
+        "2 of 4 elements differ, first at [1] (max |a - b| = 3)"
+    );
+}
+
+#[test]
+fn complex_strings_and_nulls_compare() {
+    let a = ZarrVectorValue::VComplex64(vec![Complex::new(3.0, 4.0)]);
+    let b = ZarrVectorValue::VComplex64(vec![Complex::new(3.0, 4.0 + 1e-6)]);
+    assert!(a.approx_eq(&b, 1e-5, 0.0));
+    let c = ZarrVectorValue::VComplex64(vec![Complex::new(3.0, -4.0)]);
+    assert!(!a.approx_eq(&c, 1e-5, 0.0));
+
+    let words = ZarrVectorValue::VString(vec!["a".into(), "b".into()]);
+    assert!(words.approx_eq(&words, 0.0, 0.0));
+    assert!(!words.approx_eq(
+        &ZarrVectorValue::VString(vec!["a".into(), "c".into()]),
+        1.0,
+        1.0
+    ));
+
+    let with_nulls = |values: Vec<Option<f32>>| {
+        ZarrVectorValue::VWithNulls(
+            DataType::Float32,
+            values
+                .into_iter()
+                .map(|v| v.map(ZarrValue::Float32))
+                .collect(),
+        )
+    };
+    let a = with_nulls(vec![Some(1.0), None]);
+    assert!(a.approx_eq(&with_nulls(vec![Some(1.0), None]), 0.0, 0.0));
+    assert!(!a.approx_eq(&with_nulls(vec![Some(1.0), Some(0.0)]), 1.0, 1.0));
+}
+
+// ---------------------------------------------------------------------------
+// Arrays
+// ---------------------------------------------------------------------------
+
+#[test]
+fn arrays_compare_by_index_across_memory_orders() {
+    // [[0, 1, 2], [3, 4, 5]] in both orders.
+    let c = ArrayData::new(
+        f64s(&[0.0, 1.0, 2.0, 3.0, 4.0, 5.0]),
+        vec![2, 3],
+        ArrayOrder::C,
+    );
+    let f = ArrayData::new(
+        f64s(&[0.0, 3.0, 1.0, 4.0, 2.0, 5.0]),
+        vec![2, 3],
+        ArrayOrder::F,
+    );
+    assert!(c.compare(&f, &Tolerance::exact()).unwrap().is_equal());
+
+    let changed = ArrayData::new(
+        f64s(&[0.0, 3.0, 1.0, 4.0, 9.0, 5.0]),
+        vec![2, 3],
+        ArrayOrder::F,
+    );
+    let comparison = c.compare(&changed, &Tolerance::exact()).unwrap();
+    assert_eq!(comparison.mismatches, 1);
+    assert_eq!(comparison.first_mismatch, Some(vec![0, 2]));
+
+    let flat = ArrayData::new(f64s(&[0.0; 6]), vec![6], ArrayOrder::C);
+    assert!(c.compare(&flat, &Tolerance::exact()).is_err());
+}
+
+#[tokio::test]
+async fn stored_arrays_compare_against_a_reference() {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let mut arrays = Vec::new();
+    for (name, dtype, scale) in [
+        ("f64", DataType::Float64, 1.0),
+        ("f32", DataType::Float32, 1.0 + 1e-7),
+    ] {
+        let array = ArrayBuilder::new([4, 5])
+            .chunks([2, 2])
+            .dtype(dtype)
+            .build(store.clone(), name)
+            .await
+            .unwrap();
+        let values: Vec<f64> = (0..20).map(|v| v as f64 * 0.1 * scale).collect();
+        let values = simplezarr::types::f64_to_zarr_vector(dtype, &values).unwrap();
+        array
+            .set_region(&array.full_region(), &values)
+            .await
+            .unwrap();
+        arrays.push(array);
+    }
+    let comparison = arrays[1]
+        .compare(&arrays[0], &Tolerance::default())
+        .await
+        .unwrap();
+    assert!(comparison.is_equal(), "{comparison}");
+    let comparison = arrays[1]
+        .compare(&arrays[0], &Tolerance::exact())
+        .await
+        .unwrap();
+    assert!(!comparison.is_equal());
+}
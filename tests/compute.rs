@@ -0,0 +1,285 @@
+//! Tests for lazy elementwise expressions.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use object_store::memory::InMemory;
+
+use simplezarr::builder::ArrayBuilder;
+use simplezarr::compute::{BinaryOp, concat, full, mosaic};
+use simplezarr::error::ZarrResult;
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+use simplezarr::types::{DataType, ZarrVectorValue};
+use simplezarr::{ComputedArray, UnifiedZarrArray};
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Backend wrapper counting reads that reach it.
+struct Counting {
+    inner: ObjectStoreBackend,
+    gets: AtomicUsize,
+}
+
+impl Counting {
+    fn gets(&self) -> usize {
+        self.gets.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for Counting {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        self.gets.fetch_add(1, Ordering::SeqCst);
+        self.inner.get(path).await
+    }
+
+    async fn list(&self, prefix: &str) -> ZarrResult<Vec<String>> {
+        self.inner.list(prefix).await
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        self.inner.join(base, segment)
+    }
+
+    async fn put(&self, path: &str, data: Bytes) -> ZarrResult<()> {
+        self.inner.put(path, data).await
+    }
+
+    async fn delete(&self, path: &str) -> ZarrResult<()> {
+        self.inner.delete(path).await
+    }
+}
+
+/// A `shape` `f8` array with 2x3 chunks holding `values` in row-major order.
+async fn array(shape: [usize; 2], values: Vec<f64>) -> UnifiedZarrArray {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let array = ArrayBuilder::new(shape)
+        .chunks([2, 3])
+        .dtype(DataType::Float64)
+        .codec(None)
+        .build(store, "a")
+        .await
+        .unwrap();
+    array
+        .set_region(&array.full_region(), &ZarrVectorValue::VFloat64(values))
+        .await
+        .unwrap();
+    array
+}
+
+/// A 3x4 array whose element (i, j) is `10 * i + j`.
+async fn grid() -> UnifiedZarrArray {
+    array(
+        [3, 4],
+        (0..3)
+            .flat_map(|i| (0..4).map(move |j| (10 * i + j) as f64))
+            .collect(),
+    )
+    .await
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn expressions_evaluate_by_block() {
+    let grid = grid().await;
+    let ones = array([3, 4], vec![1.0; 12]).await;
+    let expr = ((grid.view().lazy() + ones.view()).unwrap().scale(2.0) - grid.view())
+        .unwrap()
+        .offset(-2.0);
+    assert_eq!(expr.shape(), vec![3, 4]);
+    assert_eq!(expr.block_shape(), vec![2, 3]);
+    // 2 * (x + 1) - x - 2 == x
+    assert_eq!(
+        expr.read().await.unwrap(),
+        grid.view().read().await.unwrap()
+    );
+    // Regions straddle blocks.
+    assert_eq!(
+        expr.read_region(&[1..3, 2..4]).await.unwrap(),
+        vec![12.0, 13.0, 22.0, 23.0]
+    );
+    assert!(expr.read_region(&[0..4, 0..1]).await.is_err());
+
+    let ratio = grid
+        .view()
+        .lazy()
+        .binary(BinaryOp::Div, ones.view().lazy().scale(10.0))
+        .unwrap()
+        .map(f64::floor);
+    assert_eq!(
+        ratio.read_region(&[2..3, 0..4]).await.unwrap(),
+        vec![2.0; 4]
+    );
+}
+
+#[tokio::test]
+async fn blocks_follow_the_source_chunk_grid() {
+    let store = Arc::new(Counting {
+        inner: ObjectStoreBackend::new(Box::new(InMemory::new()), ""),
+        gets: AtomicUsize::new(0),
+    });
+    let array = ArrayBuilder::new([4, 6])
+        .chunks([2, 3])
+        .dtype(DataType::Float64)
+        .codec(None)
+        .build(store.clone(), "a")
+        .await
+        .unwrap();
+    let values: Vec<f64> = (0..24).map(f64::from).collect();
+    array
+        .set_region(&array.full_region(), &ZarrVectorValue::VFloat64(values))
+        .await
+        .unwrap();
+
+    // Starting one element into the first chunk on both axes, the view
+    // covers 2x2 chunks; blocks cut at the view's origin would read 3x3.
+    let view = array.view().slice(0, 1..4).unwrap().slice(1, 1..6).unwrap();
+    let before = store.gets();
+    let read = view.lazy().scale(1.0).read().await.unwrap();
+    assert_eq!(store.gets() - before, 4);
+    let want: Vec<f64> = (1..4)
+        .flat_map(|i| (1..6).map(move |j| (6 * i + j) as f64))
+        .collect();
+    assert_eq!(read, want);
+}
+
+#[tokio::test]
+async fn masks_and_views_combine() {
+    let grid = grid().await;
+    let mask = array(
+        [3, 4],
+        (0..12)
+            .map(|i| if i % 2 == 0 { 1.0 } else { 0.0 })
+            .collect(),
+    )
+    .await;
+    let masked = grid.view().lazy().mask(mask.view()).unwrap();
+    let values = masked.read_region(&[0..1, 0..4]).await.unwrap();
+    assert_eq!(values[0], 0.0);
+    assert!(values[1].is_nan());
+    assert_eq!(values[2], 2.0);
+    assert!(values[3].is_nan());
+
+    // A column broadcast against the grid.
+    let column = grid
+        .view()
+        .index(1, 0)
+        .unwrap()
+        .expand_dims(1)
+        .unwrap()
+        .broadcast_to(&[3, 4])
+        .unwrap();
+    let anomaly = (grid.view().lazy() - column).unwrap();
+    let want: Vec<f64> = (0..3).flat_map(|_| (0..4).map(f64::from)).collect();
+    assert_eq!(anomaly.read().await.unwrap(), want);
+
+    let transposed = ComputedArray::from(grid.view().transpose(&[1, 0]).unwrap());
+    assert!((grid.view().lazy() + transposed.clone()).is_err());
+    assert!(grid.view().lazy().mask(transposed).is_err());
+}
+
+#[tokio::test]
+async fn concat_maps_regions_onto_parts() {
+    // Three "days" of 2x4, each holding its day number plus the column.
+    let mut days = Vec::new();
+    for day in 0..3 {
+        let values = (0..8).map(|i| (100 * day + i % 4) as f64).collect();
+        days.push(array([2, 4], values).await.view());
+    }
+    let cube = concat(days.clone(), 0).unwrap();
+    assert_eq!(cube.shape(), vec![6, 4]);
+    let values = cube.read().await.unwrap();
+    assert_eq!(values.len(), 24);
+    assert_eq!(values[..4], [0.0, 1.0, 2.0, 3.0]);
+    assert_eq!(values[20..], [200.0, 201.0, 202.0, 203.0]);
+    // A region across the first two parts.
+    assert_eq!(
+        cube.read_region(&[1..3, 2..4]).await.unwrap(),
+        vec![2.0, 3.0, 102.0, 103.0]
+    );
+
+    // Side by side, and over expressions.
+    let wide = concat(
+        [days[0].clone().lazy(), days[1].clone().lazy().scale(-1.0)],
+        1,
+    )
+    .unwrap();
+    assert_eq!(wide.shape(), vec![2, 8]);
+    assert_eq!(
+        wide.read_region(&[0..1, 3..6]).await.unwrap(),
+        vec![3.0, -100.0, -101.0]
+    );
+    assert_eq!(
+        (cube.clone() - cube).unwrap().read().await.unwrap(),
+        vec![0.0; 24]
+    );
+
+    let column = days[0].clone().slice(1, 0..1).unwrap();
+    assert!(concat([days[0].clone(), column.clone()], 0).is_err());
+    assert_eq!(
+        concat([days[0].clone(), column], 1).unwrap().shape(),
+        vec![2, 5]
+    );
+    assert!(concat(days.clone(), 2).is_err());
+    assert!(concat(Vec::<ComputedArray>::new(), 0).is_err());
+}
+
+#[tokio::test]
+async fn mosaics_route_reads_to_tiles() {
+    // A 2x2 grid of 2x3 tiles with the bottom-right tile missing; tile
+    // (r, c) holds 10 * r + c.
+    let tile = |r: usize, c: usize| array([2, 3], vec![(10 * r + c) as f64; 6]);
+    let (a, b, c) = (tile(0, 0).await, tile(0, 1).await, tile(1, 0).await);
+    let scene = mosaic(
+        vec![
+            vec![Some(a.view()), Some(b.view())],
+            vec![Some(c.view()), None],
+        ],
+        -1.0,
+    )
+    .unwrap();
+    assert_eq!(scene.shape(), vec![4, 6]);
+    let row = |r: usize| {
+        let scene = &scene;
+        async move { scene.read_region(&[r..r + 1, 0..6]).await }
+    };
+    assert_eq!(row(0).await.unwrap(), [0.0, 0.0, 0.0, 1.0, 1.0, 1.0]);
+    assert_eq!(row(3).await.unwrap(), [10.0, 10.0, 10.0, -1.0, -1.0, -1.0]);
+    assert_eq!(
+        scene.read_region(&[1..3, 2..4]).await.unwrap(),
+        vec![0.0, 1.0, 10.0, -1.0]
+    );
+    assert_eq!(scene.read().await.unwrap().len(), 24);
+
+    // Leading axes pass through.
+    let band = || Some(full(&[2, 1, 1], 5.0));
+    let banded = mosaic(vec![vec![band(), band()], vec![band(), None]], 0.0).unwrap();
+    assert_eq!(banded.shape(), vec![2, 2, 2]);
+    assert_eq!(
+        banded.read().await.unwrap(),
+        [[5.0, 5.0, 5.0, 0.0]; 2].concat()
+    );
+
+    let narrow = a.view().slice(1, 0..2).unwrap();
+    let bad = [
+        vec![
+            vec![Some(a.view()), Some(b.view())],
+            vec![Some(narrow.clone())],
+        ],
+        vec![vec![Some(a.view())], vec![Some(narrow)]],
+        vec![vec![Some(a.view()), None], vec![None, None]],
+        vec![vec![None]],
+        vec![],
+        vec![vec![Some(a.view().index(0, 0).unwrap())]],
+    ];
+    for tiles in bad {
+        assert!(mosaic(tiles, 0.0).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+//! Tests for `SimplezarrConfig` construction and the retrying storage read.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use simplezarr::config::{
+    ENV_BLOSC_MAX_THREADS, ENV_BLOSC_NTHREADS, ENV_CHUNK_CACHE_BYTES, ENV_FAIL_FAST,
+    ENV_MAX_CHUNKS, ENV_MAX_CONCURRENCY, ENV_MAX_RETRIES, ENV_READ_AHEAD, ENV_REQUEST_TIMEOUT_MS,
+    ENV_RETRY_BACKOFF_MS, RetryPolicy, SimplezarrConfig,
+};
+use simplezarr::error::{ZarrError, ZarrResult};
+use simplezarr::metadata::v2::ZarrV2Metadata;
+use simplezarr::store::{StorageBackend, get_with_retry};
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Backend whose first `failures` reads fail with a storage error (or hang,
+/// when `hang` is set) before it starts returning `b"ok"`.
+struct FlakyBackend {
+    failures: u32,
+    hang: bool,
+    calls: AtomicU32,
+}
+
+impl FlakyBackend {
+    fn new(failures: u32, hang: bool) -> Self {
+        Self {
+            failures,
+            hang,
+            calls: AtomicU32::new(0),
+        }
+    }
+
+    fn calls(&self) -> u32 {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for FlakyBackend {
+    async fn get(&self, _path: &str) -> ZarrResult<Option<Bytes>> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
+        if call < self.failures {
+            if self.hang {
+                std::future::pending::<()>().await;
+            }
+            return Err(ZarrError::Storage("transient".into()));
+        }
+        Ok(Some(Bytes::from_static(b"ok")))
+    }
+
+    async fn list(&self, _prefix: &str) -> ZarrResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        format!("{base}/{segment}")
+    }
+}
+
+fn fast_retries(max_retries: u32) -> SimplezarrConfig {
+    SimplezarrConfig::builder()
+        .max_retries(max_retries)
+        .retry_backoff(Duration::from_millis(1))
+        .build()
+}
+
+// ---------------------------------------------------------------------------
+// Environment and builder
+// ---------------------------------------------------------------------------
+
+const ALL_VARS: [&str; 10] = [
+    ENV_MAX_CONCURRENCY,
+    ENV_READ_AHEAD,
+    ENV_CHUNK_CACHE_BYTES,
+    ENV_MAX_RETRIES,
+    ENV_RETRY_BACKOFF_MS,
+    ENV_REQUEST_TIMEOUT_MS,
+    ENV_BLOSC_NTHREADS,
+    ENV_BLOSC_MAX_THREADS,
+    ENV_FAIL_FAST,
+    ENV_MAX_CHUNKS,
+];
+
+/// All environment cases live in one test so they can't race each other.
+#[test]
+fn from_env_parses_and_rejects_values() {
+    // SAFETY: no other test in this binary reads or writes the environment.
+    unsafe {
+        for var in ALL_VARS {
+            std::env::remove_var(var);
+        }
+    }
+    assert_eq!(
+        SimplezarrConfig::from_env().unwrap(),
+        SimplezarrConfig::default()
+    );
+
+    unsafe {
+        std::env::set_var(ENV_MAX_CONCURRENCY, "4");
+        std::env::set_var(ENV_READ_AHEAD, "0");
+        std::env::set_var(ENV_CHUNK_CACHE_BYTES, " 1024 ");
+        std::env::set_var(ENV_MAX_RETRIES, "0");
+        std::env::set_var(ENV_RETRY_BACKOFF_MS, "250");
+        std::env::set_var(ENV_REQUEST_TIMEOUT_MS, "1500");
+        std::env::set_var(ENV_BLOSC_NTHREADS, "2");
+        std::env::set_var(ENV_BLOSC_MAX_THREADS, "3");
+        std::env::set_var(ENV_FAIL_FAST, "false");
+        std::env::set_var(ENV_MAX_CHUNKS, "4096");
+    }
+    let config = SimplezarrConfig::from_env().unwrap();
+    assert_eq!(config.max_concurrency, 4);
+    assert_eq!(config.read_ahead, 0);
+    assert_eq!(config.chunk_cache_bytes, 1024);
+    assert_eq!(config.retry.max_retries, 0);
+    assert_eq!(config.retry.initial_backoff, Duration::from_millis(250));
+    assert_eq!(config.request_timeout, Some(Duration::from_millis(1500)));
+    assert_eq!(config.blosc_nthreads, 2);
+    assert_eq!(config.blosc_max_threads, 3);
+    assert!(!config.fail_fast);
+    assert_eq!(config.max_chunks, 4096);
+
+    // Zero timeout means none; an empty value counts as unset.
+    unsafe {
+        std::env::set_var(ENV_REQUEST_TIMEOUT_MS, "0");
+        std::env::set_var(ENV_MAX_CONCURRENCY, "");
+    }
+    let config = SimplezarrConfig::builder()
+        .max_concurrency(7)
+        .with_env()
+        .unwrap()
+        .build();
+    assert_eq!(config.request_timeout, None);
+    assert_eq!(config.max_concurrency, 7);
+
+    unsafe {
+        std::env::set_var(ENV_MAX_RETRIES, "many");
+    }
+    let err = SimplezarrConfig::from_env().unwrap_err();
+    assert!(err.to_string().contains(ENV_MAX_RETRIES), "{err}");
+
+    unsafe {
+        std::env::set_var(ENV_MAX_RETRIES, "1");
+        std::env::set_var(ENV_MAX_CONCURRENCY, "-3");
+    }
+    assert!(SimplezarrConfig::from_env().is_err());
+
+    unsafe {
+        for var in ALL_VARS {
+            std::env::remove_var(var);
+        }
+    }
+}
+
+#[test]
+fn builder_clamps_concurrency_to_one() {
+    let config = SimplezarrConfig::builder().max_concurrency(0).build();
+    assert_eq!(config.max_concurrency, 1);
+    let config = SimplezarrConfig::builder()
+        .blosc_nthreads(0)
+        .blosc_max_threads(0)
+        .build();
+    assert_eq!(config.blosc_nthreads, 1);
+    assert_eq!(config.blosc_max_threads, 1);
+}
+
+#[test]
+fn backoff_doubles_up_to_the_cap() {
+    let policy = RetryPolicy {
+        max_retries: 10,
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_millis(500),
+    };
+    assert_eq!(policy.backoff(0), Duration::from_millis(100));
+    assert_eq!(policy.backoff(1), Duration::from_millis(200));
+    assert_eq!(policy.backoff(2), Duration::from_millis(400));
+    assert_eq!(policy.backoff(3), Duration::from_millis(500));
+    assert_eq!(policy.backoff(64), Duration::from_millis(500));
+}
+
+// ---------------------------------------------------------------------------
+// get_with_retry
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn storage_errors_are_retried() {
+    let store = FlakyBackend::new(2, false);
+    let data = get_with_retry(&store, "k", &fast_retries(3)).await.unwrap();
+    assert_eq!(data.as_deref(), Some(&b"ok"[..]));
+    assert_eq!(store.calls(), 3);
+}
+
+#[tokio::test]
+async fn retries_are_bounded() {
+    let store = FlakyBackend::new(5, false);
+    let err = get_with_retry(&store, "k", &fast_retries(2))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::Storage(_)), "{err:?}");
+    assert_eq!(store.calls(), 3);
+}
+
+#[tokio::test]
+async fn timed_out_reads_are_retried() {
+    let store = FlakyBackend::new(1, true);
+    let config = SimplezarrConfig::builder()
+        .max_retries(1)
+        .retry_backoff(Duration::from_millis(1))
+        .request_timeout(Some(Duration::from_millis(20)))
+        .build();
+    let data = get_with_retry(&store, "k", &config).await.unwrap();
+    assert_eq!(data.as_deref(), Some(&b"ok"[..]));
+    assert_eq!(store.calls(), 2);
+
+    let store = FlakyBackend::new(1, true);
+    let config = SimplezarrConfig {
+        retry: RetryPolicy {
+            max_retries: 0,
+            ..RetryPolicy::default()
+        },
+        ..config
+    };
+    match get_with_retry(&store, "k", &config).await {
+        Err(ZarrError::Storage(msg)) => assert!(msg.contains("Timed out"), "{msg}"),
+        other => panic!("expected a timeout, got {other:?}"),
+    }
+}
+
+#[test]
+fn chunk_grid_limit_is_configurable() {
+    assert_eq!(SimplezarrConfig::default().max_chunks, 1 << 22);
+    let zarray = serde_json::to_vec(&serde_json::json!({
+        "zarr_format": 2,
+        "shape": [100, 100],
+        "chunks": [10, 10],
+        "dtype": "<f4",
+        "fill_value": null,
+        "order": "C",
+        "compressor": null,
+        "filters": null,
+    }))
+    .unwrap();
+    let md = ZarrV2Metadata::parse_with_max_chunks(&zarray, 100).unwrap();
+    assert_eq!(md.keys.len(), 100);
+    let err = ZarrV2Metadata::parse_with_max_chunks(&zarray, 99).unwrap_err();
+    assert!(matches!(err, ZarrError::Metadata(_)), "{err}");
+}
@@ -0,0 +1,161 @@
+//! Tests for exporting group arrays to Polars DataFrames.
+
+use std::sync::Arc;
+
+use object_store::memory::InMemory;
+use polars::prelude::*;
+use serde_json::json;
+
+use simplezarr::UnifiedZarrGroup;
+use simplezarr::builder::ArrayBuilder;
+use simplezarr::error::ZarrError;
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+use simplezarr::types::{ArrayOrder, DataType as ZarrType, ZarrVectorValue};
+use simplezarr::v2;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Group `g` with 1-D `station` (int32) and `height` (float16) of length 4
+/// on dimension `station`, and 2x3 arrays `t` (C order) and `p` (F order)
+/// on (`y`, `x`) both holding `10 * i + j`.
+async fn stations() -> UnifiedZarrGroup {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let one_d = [
+        (
+            "station",
+            ZarrType::Int32,
+            ZarrVectorValue::VInt32(vec![7, 8, 9, 10]),
+        ),
+        (
+            "height",
+            ZarrType::Float16,
+            ZarrVectorValue::VFloat16([1.5, 2.0, 2.5, 3.0].map(half::f16::from_f32).to_vec()),
+        ),
+    ];
+    for (name, dtype, values) in one_d {
+        let array = ArrayBuilder::new([4])
+            .chunks([3])
+            .dtype(dtype)
+            .codec(None)
+            .build(store.clone(), &format!("g/{name}"))
+            .await
+            .unwrap();
+        array
+            .set_region(&array.full_region(), &values)
+            .await
+            .unwrap();
+        put_dims(&store, name, &["station"]).await;
+    }
+
+    for (name, order) in [("t", ArrayOrder::C), ("p", ArrayOrder::F)] {
+        let array = ArrayBuilder::new([2, 3])
+            .chunks([1, 2])
+            .dtype(ZarrType::Float64)
+            .codec(None)
+            .order(order)
+            .build(store.clone(), &format!("g/{name}"))
+            .await
+            .unwrap();
+        let values = match order {
+            ArrayOrder::C => vec![0.0, 1.0, 2.0, 10.0, 11.0, 12.0],
+            ArrayOrder::F => vec![0.0, 10.0, 1.0, 11.0, 2.0, 12.0],
+        };
+        array
+            .set_region(&array.full_region(), &ZarrVectorValue::VFloat64(values))
+            .await
+            .unwrap();
+        put_dims(&store, name, &["y", "x"]).await;
+    }
+
+    let mut group = v2::open_group(store, "g", &["station", "height", "t", "p"])
+        .await
+        .unwrap();
+    group.load_attributes().await.unwrap();
+    group
+}
+
+async fn put_dims(store: &ObjectStoreBackend, name: &str, dims: &[&str]) {
+    let zattrs = json!({"_ARRAY_DIMENSIONS": dims});
+    store
+        .put(&format!("g/{name}/.zattrs"), zattrs.to_string().into())
+        .await
+        .unwrap();
+}
+
+fn names(df: &DataFrame) -> Vec<String> {
+    df.get_column_names()
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+fn i64s(df: &DataFrame, name: &str) -> Vec<i64> {
+    df.column(name)
+        .unwrap()
+        .i64()
+        .unwrap()
+        .into_no_null_iter()
+        .collect()
+}
+
+fn f64s(df: &DataFrame, name: &str) -> Vec<f64> {
+    df.column(name)
+        .unwrap()
+        .f64()
+        .unwrap()
+        .into_no_null_iter()
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn one_dimensional_arrays_become_columns() {
+    let group = stations().await;
+    let df = group.to_polars(&["height", "station"]).await.unwrap();
+    assert_eq!(df.shape(), (4, 2));
+    assert_eq!(names(&df), ["height", "station"]);
+    assert_eq!(df.column("height").unwrap().dtype(), &DataType::Float32);
+    let stations: Vec<i32> = df
+        .column("station")
+        .unwrap()
+        .i32()
+        .unwrap()
+        .into_no_null_iter()
+        .collect();
+    assert_eq!(stations, [7, 8, 9, 10]);
+
+    // The coordinate array is a column already, so the index is `dim_0`.
+    let df = group.to_polars_with_index(&["station"]).await.unwrap();
+    assert_eq!(names(&df), ["dim_0", "station"]);
+    let df = group.to_polars_with_index(&["height"]).await.unwrap();
+    assert_eq!(names(&df), ["station", "height"]);
+    assert_eq!(i64s(&df, "station"), [0, 1, 2, 3]);
+}
+
+#[tokio::test]
+async fn n_dimensional_arrays_flatten_in_row_major_order() {
+    let group = stations().await;
+    let df = group.to_polars_with_index(&["t", "p"]).await.unwrap();
+    assert_eq!(names(&df), ["y", "x", "t", "p"]);
+    assert_eq!(i64s(&df, "y"), [0, 0, 0, 1, 1, 1]);
+    assert_eq!(i64s(&df, "x"), [0, 1, 2, 0, 1, 2]);
+    let expected = [0.0, 1.0, 2.0, 10.0, 11.0, 12.0];
+    assert_eq!(f64s(&df, "t"), expected);
+    assert_eq!(f64s(&df, "p"), expected);
+}
+
+#[tokio::test]
+async fn arrays_must_share_a_shape() {
+    let group = stations().await;
+    let err = group.to_polars(&["t"]).await.unwrap_err();
+    assert!(matches!(err, ZarrError::Other(_)), "{err:?}");
+    let err = group.to_polars(&["station", "t"]).await.unwrap_err();
+    assert!(matches!(err, ZarrError::Other(_)), "{err:?}");
+    let err = group.to_polars(&["missing"]).await.unwrap_err();
+    assert!(matches!(err, ZarrError::NotFound(_)), "{err:?}");
+}
@@ -0,0 +1,213 @@
+//! Tests for querying groups with DataFusion through `ZarrTableProvider`.
+
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Array, Float64Array, Int32Array, Int64Array};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::catalog::TableProvider;
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::prelude::{SessionContext, col, lit};
+use object_store::memory::InMemory;
+use serde_json::json;
+
+use simplezarr::UnifiedZarrGroup;
+use simplezarr::builder::ArrayBuilder;
+use simplezarr::error::ZarrError;
+use simplezarr::sql::ZarrTableProvider;
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+use simplezarr::types::{ArrayOrder, DataType, ZarrVectorValue};
+use simplezarr::v2;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Group `g` with `temp` (float64, `10 * i`) and `count` (int32, `i`) of
+/// length 10 on dimension `time`, chunked by 3, and a 2x3 Fortran-order
+/// `grid` holding `10 * i + j`.
+async fn weather() -> UnifiedZarrGroup {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let columns = [
+        (
+            "temp",
+            DataType::Float64,
+            ZarrVectorValue::VFloat64((0..10).map(|i| 10.0 * i as f64).collect()),
+        ),
+        (
+            "count",
+            DataType::Int32,
+            ZarrVectorValue::VInt32((0..10).collect()),
+        ),
+    ];
+    for (name, dtype, values) in columns {
+        let array = ArrayBuilder::new([10])
+            .chunks([3])
+            .dtype(dtype)
+            .codec(None)
+            .build(store.clone(), &format!("g/{name}"))
+            .await
+            .unwrap();
+        array
+            .set_region(&array.full_region(), &values)
+            .await
+            .unwrap();
+        let zattrs = json!({"_ARRAY_DIMENSIONS": ["time"]});
+        store
+            .put(&format!("g/{name}/.zattrs"), zattrs.to_string().into())
+            .await
+            .unwrap();
+    }
+
+    let grid = ArrayBuilder::new([2, 3])
+        .chunks([1, 2])
+        .dtype(DataType::Float64)
+        .codec(None)
+        .order(ArrayOrder::F)
+        .build(store.clone(), "g/grid")
+        .await
+        .unwrap();
+    // Column-major: (0,0), (1,0), (0,1), ...
+    let values = vec![0.0, 10.0, 1.0, 11.0, 2.0, 12.0];
+    grid.set_region(&grid.full_region(), &ZarrVectorValue::VFloat64(values))
+        .await
+        .unwrap();
+
+    let mut group = v2::open_group(store, "g", &["temp", "count", "grid"])
+        .await
+        .unwrap();
+    group.load_attributes().await.unwrap();
+    group
+}
+
+async fn query(table: ZarrTableProvider, sql: &str) -> Vec<RecordBatch> {
+    let ctx = SessionContext::new();
+    ctx.register_table("t", Arc::new(table)).unwrap();
+    ctx.sql(sql).await.unwrap().collect().await.unwrap()
+}
+
+fn column<T: Array + Clone + 'static>(batches: &[RecordBatch], index: usize) -> Vec<T> {
+    batches
+        .iter()
+        .map(|batch| {
+            batch
+                .column(index)
+                .as_any()
+                .downcast_ref::<T>()
+                .unwrap()
+                .clone()
+        })
+        .collect()
+}
+
+fn i64s(batches: &[RecordBatch], index: usize) -> Vec<i64> {
+    column::<Int64Array>(batches, index)
+        .iter()
+        .flat_map(|a| a.values().to_vec())
+        .collect()
+}
+
+fn f64s(batches: &[RecordBatch], index: usize) -> Vec<f64> {
+    column::<Float64Array>(batches, index)
+        .iter()
+        .flat_map(|a| a.values().to_vec())
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Queries
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn arrays_are_queried_as_columns() {
+    let group = weather().await;
+    let table = ZarrTableProvider::new(&group, &["temp", "count"])
+        .unwrap()
+        .batch_rows(4);
+    assert_eq!(table.index_columns(), ["time"]);
+    let names: Vec<_> = table
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| f.name().clone())
+        .collect();
+    assert_eq!(names, ["time", "temp", "count"]);
+
+    let batches = query(
+        table.clone(),
+        "SELECT time, temp, count FROM t WHERE time >= 4 AND time < 7 AND count <> 5 ORDER BY time",
+    )
+    .await;
+    assert_eq!(i64s(&batches, 0), [4, 6]);
+    assert_eq!(f64s(&batches, 1), [40.0, 60.0]);
+    let counts: Vec<i32> = column::<Int32Array>(&batches, 2)
+        .iter()
+        .flat_map(|a| a.values().to_vec())
+        .collect();
+    assert_eq!(counts, [4, 6]);
+
+    let batches = query(table.clone(), "SELECT sum(temp) FROM t WHERE 8 <= time").await;
+    assert_eq!(f64s(&batches, 0), [170.0]);
+    let batches = query(table.clone(), "SELECT count(*) FROM t").await;
+    assert_eq!(i64s(&batches, 0), [10]);
+    let batches = query(table, "SELECT temp FROM t WHERE time > 20").await;
+    assert!(batches.iter().all(|b| b.num_rows() == 0));
+}
+
+#[tokio::test]
+async fn fortran_order_arrays_give_row_major_rows() {
+    let group = weather().await;
+    let table = ZarrTableProvider::new(&group, &["grid"]).unwrap();
+    assert_eq!(table.index_columns(), ["dim_0", "dim_1"]);
+
+    let batches = query(table.clone(), "SELECT dim_0, dim_1, grid FROM t").await;
+    assert_eq!(i64s(&batches, 0), [0, 0, 0, 1, 1, 1]);
+    assert_eq!(i64s(&batches, 1), [0, 1, 2, 0, 1, 2]);
+    assert_eq!(f64s(&batches, 2), [0.0, 1.0, 2.0, 10.0, 11.0, 12.0]);
+
+    let batches = query(
+        table,
+        "SELECT grid FROM t WHERE dim_1 BETWEEN 1 AND 2 AND dim_0 = 1",
+    )
+    .await;
+    assert_eq!(f64s(&batches, 0), [11.0, 12.0]);
+}
+
+// ---------------------------------------------------------------------------
+// Pushdown and validation
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn index_comparisons_narrow_the_region() {
+    let group = weather().await;
+    let table = ZarrTableProvider::new(&group, &["temp"]).unwrap();
+
+    let time_range = |filter: Expr| table.region_for(&[filter])[0].clone();
+    let filter = col("time").gt(lit(2)).and(col("time").lt_eq(lit(5.5)));
+    assert_eq!(time_range(filter.clone()), 3..6);
+    assert_eq!(time_range(lit(7).eq(col("time"))), 7..8);
+    assert_eq!(time_range(col("time").lt(lit(-3))), 0..0);
+    assert_eq!(time_range(col("temp").gt(lit(3))), 0..10);
+
+    let value_filter = col("temp").gt(lit(3));
+    let pushdown = table
+        .supports_filters_pushdown(&[&filter, &value_filter])
+        .unwrap();
+    assert_eq!(
+        pushdown,
+        [
+            TableProviderFilterPushDown::Inexact,
+            TableProviderFilterPushDown::Unsupported
+        ]
+    );
+}
+
+#[tokio::test]
+async fn tables_need_arrays_of_one_small_shape() {
+    let group = weather().await;
+    let err = ZarrTableProvider::new(&group, &["temp", "grid"]).unwrap_err();
+    assert!(matches!(err, ZarrError::Other(_)), "{err:?}");
+    let err = ZarrTableProvider::new(&group, &["missing"]).unwrap_err();
+    assert!(matches!(err, ZarrError::NotFound(_)), "{err:?}");
+    let err = ZarrTableProvider::new(&group, &[]).unwrap_err();
+    assert!(matches!(err, ZarrError::Other(_)), "{err:?}");
+}
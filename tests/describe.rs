@@ -0,0 +1,162 @@
+//! Tests for metadata introspection.
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use object_store::memory::InMemory;
+
+use simplezarr::UnifiedZarrArray;
+use simplezarr::capabilities::FeatureId;
+use simplezarr::describe::to_python_json;
+use simplezarr::error::ZarrError;
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+use simplezarr::v2;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Open a V2 array whose `.zarray` is `zarray`.
+async fn open(zarray: &str) -> UnifiedZarrArray {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    store
+        .put("a/.zarray", Bytes::copy_from_slice(zarray.as_bytes()))
+        .await
+        .unwrap();
+    v2::open(store, "a").await.unwrap()
+}
+
+/// A `.zarray` as zarr-python 2 writes it.
+const PYTHON_ZARRAY: &str = r#"{
+    "chunks": [
+        2,
+        3
+    ],
+    "compressor": {
+        "blocksize": 0,
+        "clevel": 5,
+        "cname": "lz4",
+        "id": "blosc",
+        "shuffle": 1
+    },
+    "dimension_separator": ".",
+    "dtype": "<f8",
+    "fill_value": "NaN",
+    "filters": null,
+    "order": "C",
+    "shape": [
+        4,
+        6
+    ],
+    "zarr_format": 2
+}"#;
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn v2_json_matches_zarr_python() {
+    let array = open(PYTHON_ZARRAY).await;
+    let json = array.metadata.to_v2_json().unwrap();
+    assert_eq!(to_python_json(&json, 4).unwrap(), PYTHON_ZARRAY);
+}
+
+#[tokio::test]
+async fn v3_json_describes_the_same_array() {
+    let array = open(
+        r#"{"zarr_format": 2, "shape": [10, 4], "chunks": [5, 4], "dtype": ">i4",
+            "fill_value": null, "order": "C", "compressor": {"id": "zstd", "level": 3},
+            "filters": null}"#,
+    )
+    .await;
+    let json = array.metadata.to_v3_json().unwrap();
+    assert_eq!(json["zarr_format"], 3);
+    assert_eq!(json["node_type"], "array");
+    assert_eq!(json["data_type"], "int32");
+    assert_eq!(json["fill_value"], 0);
+    assert_eq!(
+        json["chunk_key_encoding"],
+        serde_json::json!({"name": "v2", "configuration": {"separator": "."}})
+    );
+    assert_eq!(
+        json["codecs"],
+        serde_json::json!([
+            {"name": "bytes", "configuration": {"endian": "big"}},
+            {"name": "zstd", "configuration": {"level": 3, "checksum": false}},
+        ])
+    );
+    assert_eq!(json["attributes"], serde_json::json!({}));
+    assert_eq!(json["storage_transformers"], serde_json::json!([]));
+    assert!(json.get("dimension_names").is_none());
+}
+
+#[tokio::test]
+async fn v3_json_agrees_with_conversion() {
+    // The stored bytes are the filter's big-endian int16s.
+    let array = open(
+        r#"{"zarr_format": 2, "shape": [4], "chunks": [4], "dtype": "<f8",
+            "fill_value": 0, "order": "C", "compressor": null,
+            "filters": [{"id": "fixedscaleoffset", "scale": 10, "offset": 0,
+                         "dtype": "<f8", "astype": ">i2"}]}"#,
+    )
+    .await;
+    let json = array.metadata.to_v3_json().unwrap();
+    assert_eq!(json["codecs"][0]["name"], "numcodecs.fixedscaleoffset");
+    assert_eq!(
+        json["codecs"][1],
+        serde_json::json!({"name": "bytes", "configuration": {"endian": "big"}})
+    );
+
+    // Fortran order would need a transpose codec, which conversion rejects.
+    let array = open(
+        r#"{"zarr_format": 2, "shape": [10, 4], "chunks": [5, 4], "dtype": "<i4",
+            "fill_value": 0, "order": "F", "compressor": null, "filters": null}"#,
+    )
+    .await;
+    let err = array.metadata.to_v3_json().unwrap_err();
+    assert!(
+        matches!(err, ZarrError::UnsupportedFeature(FeatureId::Codec(ref id)) if id == "transpose"),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn describe_prints_an_info_block() {
+    let array = open(PYTHON_ZARRAY).await;
+    assert_eq!(
+        array.metadata.describe(),
+        "Type        : Array\n\
+         Zarr format : 2\n\
+         Data type   : float64\n\
+         Shape       : (4, 6)\n\
+         Chunk shape : (2, 3)\n\
+         Order       : C\n\
+         Fill value  : \"NaN\"\n\
+         Compressor  : Blosc(cname='lz4', clevel=5, shuffle=SHUFFLE, blocksize=0)\n\
+         No. bytes   : 192\n\
+         No. chunks  : 4\n"
+    );
+
+    let large = open(
+        r#"{"zarr_format": 2, "shape": [10000, 10000], "chunks": [1000, 1000],
+            "dtype": "<f8", "fill_value": 0, "order": "C", "compressor": null,
+            "filters": [{"id": "fixedscaleoffset", "scale": 10, "offset": 0,
+                         "dtype": "<f8", "astype": "<i2"}]}"#,
+    )
+    .await;
+    let info = large.metadata.describe();
+    assert!(info.contains("Shape       : (10000, 10000)\n"), "{info}");
+    assert!(
+        info.contains(
+            "Filter [0]  : FixedScaleOffset(scale=10, offset=0, dtype='<f8', astype='<i2')\n"
+        ),
+        "{info}"
+    );
+    assert!(info.contains("Compressor  : None\n"), "{info}");
+    assert!(
+        info.contains("No. bytes   : 800000000 (762.9M)\n"),
+        "{info}"
+    );
+    assert!(info.contains("No. chunks  : 100\n"), "{info}");
+}
@@ -0,0 +1,63 @@
+//! Tests for per-chunk fetch and decode diagnostics.
+
+use std::sync::Arc;
+
+use object_store::memory::InMemory;
+
+use simplezarr::builder::{ArrayBuilder, zstd};
+use simplezarr::config::SimplezarrConfig;
+use simplezarr::diagnostics::ChunkDiagnostics;
+use simplezarr::store::ObjectStoreBackend;
+use simplezarr::types::{DataType, ZarrVectorValue};
+use simplezarr::v2;
+
+#[tokio::test]
+async fn loads_record_every_fetched_chunk() {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let array = ArrayBuilder::new([8])
+        .chunks([2])
+        .dtype(DataType::Int32)
+        .codec(zstd(3))
+        .build(store.clone(), "a")
+        .await
+        .unwrap();
+    for chunk in [0, 1, 3] {
+        array
+            .write_chunk(&[chunk], &ZarrVectorValue::VInt32(vec![7; 2]))
+            .await
+            .unwrap();
+    }
+
+    let config = SimplezarrConfig::builder()
+        .chunk_cache_bytes(1 << 20)
+        .build();
+    let diagnostics = Arc::new(ChunkDiagnostics::default());
+    let array = v2::open_with_config(store, "a", Arc::new(config))
+        .await
+        .unwrap()
+        .with_diagnostics(diagnostics.clone());
+    array.load().await.unwrap();
+
+    let mut records = diagnostics.records();
+    records.sort_by(|a, b| a.key.cmp(&b.key));
+    let keys: Vec<&str> = records.iter().map(|r| r.key.as_str()).collect();
+    assert_eq!(keys, ["0", "1", "2", "3"]);
+    for record in &records {
+        assert_eq!(record.decoded_bytes, 8);
+        assert_eq!(record.codec, "Zstd(level=3)");
+    }
+    assert_eq!(records[2].stored_bytes, None);
+    assert!(records[0].stored_bytes.is_some_and(|b| b > 0));
+    assert!(records[0].ratio().is_some());
+    assert_eq!(diagnostics.largest(4)[3].key, "2");
+    assert_eq!(diagnostics.slowest(2).len(), 2);
+
+    let table = diagnostics.to_string();
+    assert!(table.starts_with("4 chunks, "), "{table}");
+    assert!(table.contains("missing"), "{table}");
+
+    // Cached chunks aren't fetched again.
+    assert_eq!(diagnostics.take().len(), 4);
+    array.load().await.unwrap();
+    assert!(diagnostics.is_empty());
+}
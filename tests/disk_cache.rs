@@ -0,0 +1,265 @@
+//! Tests for the persistent on-disk chunk cache.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use object_store::memory::InMemory;
+
+use simplezarr::disk_cache::{DiskCacheOptions, DiskCachedStore};
+use simplezarr::error::ZarrResult;
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// In-memory backend (which reports ETags) counting fetches and ETag checks.
+struct Remote {
+    inner: ObjectStoreBackend,
+    fetches: AtomicUsize,
+    etag_checks: AtomicUsize,
+}
+
+impl Remote {
+    async fn with(values: &[(&str, &[u8])]) -> Self {
+        let inner = ObjectStoreBackend::new(Box::new(InMemory::new()), "");
+        for (key, value) in values {
+            inner.put(key, Bytes::copy_from_slice(value)).await.unwrap();
+        }
+        Self {
+            inner,
+            fetches: AtomicUsize::new(0),
+            etag_checks: AtomicUsize::new(0),
+        }
+    }
+
+    fn fetches(&self) -> usize {
+        self.fetches.load(Ordering::SeqCst)
+    }
+
+    fn etag_checks(&self) -> usize {
+        self.etag_checks.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for Remote {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        self.fetches.fetch_add(1, Ordering::SeqCst);
+        self.inner.get(path).await
+    }
+
+    async fn list(&self, prefix: &str) -> ZarrResult<Vec<String>> {
+        self.inner.list(prefix).await
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        self.inner.join(base, segment)
+    }
+
+    async fn get_with_etag(&self, path: &str) -> ZarrResult<Option<(Bytes, Option<String>)>> {
+        self.fetches.fetch_add(1, Ordering::SeqCst);
+        self.inner.get_with_etag(path).await
+    }
+
+    async fn etag(&self, path: &str) -> ZarrResult<Option<String>> {
+        self.etag_checks.fetch_add(1, Ordering::SeqCst);
+        self.inner.etag(path).await
+    }
+
+    async fn put(&self, path: &str, data: Bytes) -> ZarrResult<()> {
+        self.inner.put(path, data).await
+    }
+}
+
+fn cache_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "simplezarr-disk-cache-{}-{name}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn object_count(dir: &Path) -> usize {
+    std::fs::read_dir(dir.join("objects")).unwrap().count()
+}
+
+async fn get(store: &impl StorageBackend, key: &str) -> Option<Vec<u8>> {
+    store.get(key).await.unwrap().map(|b| b.to_vec())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn hits_skip_the_backend_and_survive_reopening() {
+    let dir = cache_dir("persist");
+    let remote = Remote::with(&[("a/0.0", b"chunk")]).await;
+    let store = DiskCachedStore::open(remote, &dir, DiskCacheOptions::default()).unwrap();
+    assert_eq!(get(&store, "a/0.0").await.as_deref(), Some(&b"chunk"[..]));
+    assert_eq!(get(&store, "a/0.0").await.as_deref(), Some(&b"chunk"[..]));
+    assert_eq!(store.inner().fetches(), 1);
+    drop(store);
+
+    // A new session over an empty remote is served from disk.
+    let remote = Remote::with(&[]).await;
+    let store = DiskCachedStore::open(remote, &dir, DiskCacheOptions::default()).unwrap();
+    assert!(store.contains("a/0.0"));
+    assert_eq!(get(&store, "a/0.0").await.as_deref(), Some(&b"chunk"[..]));
+    assert_eq!(store.inner().fetches(), 0);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn identical_values_share_one_object() {
+    let dir = cache_dir("dedup");
+    let remote = Remote::with(&[("a", b"same"), ("b", b"same"), ("c", b"other")]).await;
+    let store = DiskCachedStore::open(remote, &dir, DiskCacheOptions::default()).unwrap();
+    for key in ["a", "b", "c"] {
+        get(&store, key).await.unwrap();
+    }
+    assert_eq!(object_count(&dir), 2);
+    assert_eq!(store.size_bytes(), 9);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn least_recently_used_entries_are_evicted_over_the_cap() {
+    let dir = cache_dir("lru");
+    let remote = Remote::with(&[("a", b"aaaa"), ("b", b"bbbb"), ("c", b"cccc")]).await;
+    let options = DiskCacheOptions {
+        max_bytes: 10,
+        ..DiskCacheOptions::default()
+    };
+    let store = DiskCachedStore::open(remote, &dir, options.clone()).unwrap();
+    get(&store, "a").await.unwrap();
+    get(&store, "b").await.unwrap();
+    get(&store, "a").await.unwrap();
+    get(&store, "c").await.unwrap();
+    assert!(store.contains("a"));
+    assert!(!store.contains("b"));
+    assert!(store.contains("c"));
+    assert_eq!(store.size_bytes(), 8);
+    assert_eq!(object_count(&dir), 2);
+    drop(store);
+
+    // Reopening with a smaller cap trims the cache, oldest first.
+    let store = DiskCachedStore::open(
+        Remote::with(&[]).await,
+        &dir,
+        DiskCacheOptions {
+            max_bytes: 4,
+            ..options
+        },
+    )
+    .unwrap();
+    assert!(!store.contains("a"));
+    assert!(store.contains("c"));
+    assert_eq!(object_count(&dir), 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn expired_entries_are_revalidated_by_etag() {
+    let dir = cache_dir("etag");
+    let remote = Remote::with(&[("k", b"v1")]).await;
+    let options = DiskCacheOptions {
+        ttl: Some(Duration::ZERO),
+        ..DiskCacheOptions::default()
+    };
+    let store = DiskCachedStore::open(remote, &dir, options).unwrap();
+    assert_eq!(get(&store, "k").await.as_deref(), Some(&b"v1"[..]));
+    assert_eq!(get(&store, "k").await.as_deref(), Some(&b"v1"[..]));
+    assert_eq!(store.inner().fetches(), 1);
+    assert_eq!(store.inner().etag_checks(), 1);
+
+    // A changed ETag triggers a refetch.
+    store
+        .inner()
+        .inner
+        .put("k", Bytes::from_static(b"v2"))
+        .await
+        .unwrap();
+    assert_eq!(get(&store, "k").await.as_deref(), Some(&b"v2"[..]));
+    assert_eq!(store.inner().fetches(), 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn expired_entries_are_refetched_without_etag_validation() {
+    let dir = cache_dir("ttl");
+    let remote = Remote::with(&[("k", b"v1")]).await;
+    let options = DiskCacheOptions {
+        ttl: Some(Duration::ZERO),
+        validate_etag: false,
+        ..DiskCacheOptions::default()
+    };
+    let store = DiskCachedStore::open(remote, &dir, options).unwrap();
+    get(&store, "k").await.unwrap();
+    get(&store, "k").await.unwrap();
+    assert_eq!(store.inner().fetches(), 2);
+    assert_eq!(store.inner().etag_checks(), 0);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn misses_and_writes_leave_no_stale_entries() {
+    let dir = cache_dir("writes");
+    let remote = Remote::with(&[("k", b"old")]).await;
+    let store = DiskCachedStore::open(remote, &dir, DiskCacheOptions::default()).unwrap();
+    assert_eq!(get(&store, "missing").await, None);
+    assert!(!store.contains("missing"));
+
+    get(&store, "k").await.unwrap();
+    store.put("k", Bytes::from_static(b"new")).await.unwrap();
+    assert!(!store.contains("k"));
+    assert_eq!(object_count(&dir), 0);
+    assert_eq!(get(&store, "k").await.as_deref(), Some(&b"new"[..]));
+
+    store.clear().await.unwrap();
+    assert_eq!(store.size_bytes(), 0);
+    assert_eq!(object_count(&dir), 0);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn stray_objects_are_removed_on_open() {
+    let dir = cache_dir("stray");
+    std::fs::create_dir_all(dir.join("objects")).unwrap();
+    std::fs::write(dir.join("objects/leftover.tmp-1"), b"partial").unwrap();
+    std::fs::write(dir.join("index.json"), b"not json").unwrap();
+    let store =
+        DiskCachedStore::open(Remote::with(&[]).await, &dir, DiskCacheOptions::default()).unwrap();
+    assert_eq!(store.size_bytes(), 0);
+    assert_eq!(object_count(&dir), 0);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn concurrent_misses_of_identical_values_share_one_whole_object() {
+    let dir = cache_dir("concurrent");
+    let value = vec![7u8; 256 * 1024];
+    let keys: Vec<String> = (0..32).map(|i| format!("a/{i}")).collect();
+    let values: Vec<(&str, &[u8])> = keys.iter().map(|k| (k.as_str(), &value[..])).collect();
+    let remote = Remote::with(&values).await;
+    let store = DiskCachedStore::open(remote, &dir, DiskCacheOptions::default()).unwrap();
+    let gets = keys.iter().map(|k| store.get(k));
+    for result in futures::future::join_all(gets).await {
+        assert_eq!(result.unwrap().as_deref(), Some(&value[..]));
+    }
+    assert_eq!(object_count(&dir), 1);
+    assert_eq!(store.size_bytes(), value.len() as u64);
+    drop(store);
+
+    let store =
+        DiskCachedStore::open(Remote::with(&[]).await, &dir, DiskCacheOptions::default()).unwrap();
+    for key in &keys {
+        assert_eq!(get(&store, key).await.as_deref(), Some(&value[..]));
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,360 @@
+//! Tests for data type parsing and decoding that don't need stored arrays.
+
+use serde_json::json;
+
+use simplezarr::capabilities::FeatureId;
+use simplezarr::error::ZarrError;
+use simplezarr::metadata::parse_fill_value;
+use simplezarr::metadata::v2::{ZarrV2Metadata, parse_numpy_dtype};
+use simplezarr::metadata::v3::{parse_v3_dtype, v3_dtype_name};
+use simplezarr::types::{
+    DataType, Endian, FillValue, ZarrValue, ZarrVectorValue, bytes_to_zarr_vector, unpack_bools,
+};
+
+// ---------------------------------------------------------------------------
+// V3 data type names
+// ---------------------------------------------------------------------------
+
+#[test]
+fn v3_core_names_round_trip() {
+    let all = [
+        DataType::Bool,
+        DataType::Int8,
+        DataType::Int16,
+        DataType::Int32,
+        DataType::Int64,
+        DataType::UInt8,
+        DataType::UInt16,
+        DataType::UInt32,
+        DataType::UInt64,
+        DataType::Float16,
+        DataType::Float32,
+        DataType::Float64,
+        DataType::Complex64,
+        DataType::Complex128,
+        DataType::String,
+        DataType::Bytes,
+        DataType::Raw(8),
+        DataType::Raw(48),
+    ];
+    for dt in all {
+        let name = v3_dtype_name(dt);
+        assert_eq!(parse_v3_dtype(&json!(name)).unwrap(), dt, "{name}");
+    }
+    assert_eq!(
+        parse_v3_dtype(&json!("float32")).unwrap(),
+        DataType::Float32
+    );
+}
+
+#[test]
+fn v3_raw_bits_must_be_whole_bytes() {
+    assert_eq!(parse_v3_dtype(&json!("r8")).unwrap(), DataType::Raw(8));
+    assert!(parse_v3_dtype(&json!("r12")).is_err());
+    assert!(parse_v3_dtype(&json!("r0")).is_err());
+}
+
+#[test]
+fn v3_unknown_names_are_rejected() {
+    match parse_v3_dtype(&json!("float128")) {
+        Err(ZarrError::UnsupportedFeature(FeatureId::DataType(name))) => {
+            assert_eq!(name, "float128")
+        }
+        other => panic!("expected an unsupported data type, got {other:?}"),
+    }
+    assert!(parse_v3_dtype(&json!(4)).is_err());
+    assert!(parse_v3_dtype(&json!({"configuration": {}})).is_err());
+}
+
+#[test]
+fn v3_object_form_core_names() {
+    assert_eq!(
+        parse_v3_dtype(&json!({"name": "int16"})).unwrap(),
+        DataType::Int16
+    );
+    assert_eq!(
+        parse_v3_dtype(&json!({"name": "r16", "configuration": {}})).unwrap(),
+        DataType::Raw(16)
+    );
+}
+
+#[test]
+fn v3_known_extensions() {
+    let cases = [
+        (
+            json!({"name": "numpy.datetime64", "configuration": {"unit": "s", "scale_factor": 1}}),
+            DataType::Int64,
+        ),
+        (
+            json!({"name": "numpy.timedelta64", "configuration": {"unit": "ns", "scale_factor": 1}}),
+            DataType::Int64,
+        ),
+        (json!({"name": "variable_length_utf8"}), DataType::String),
+        (json!({"name": "variable_length_bytes"}), DataType::Bytes),
+        (
+            json!({"name": "fixed_length_bytes", "configuration": {"length_bytes": 6}}),
+            DataType::Raw(48),
+        ),
+    ];
+    for (value, want) in cases {
+        assert_eq!(parse_v3_dtype(&value).unwrap(), want, "{value}");
+    }
+}
+
+#[test]
+fn v3_extensions_without_a_decodable_layout_are_rejected() {
+    for value in [
+        json!({"name": "fixed_length_bytes"}),
+        json!({"name": "fixed_length_bytes", "configuration": {"length_bytes": 0}}),
+    ] {
+        assert!(parse_v3_dtype(&value).is_err(), "{value}");
+    }
+    for name in [
+        "fixed_length_utf32",
+        "null_terminated_bytes",
+        "example.custom",
+    ] {
+        let err = parse_v3_dtype(&json!({"name": name, "configuration": {"length_bytes": 4}}))
+            .unwrap_err();
+        match err {
+            ZarrError::UnsupportedFeature(FeatureId::DataType(got)) => assert_eq!(got, name),
+            other => panic!("expected an unsupported data type, got {other:?}"),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Float fill values
+// ---------------------------------------------------------------------------
+
+#[test]
+fn hex_float_fill_values_keep_their_bits() {
+    let cases = [
+        (DataType::Float16, "0x7e01", 0x7e01u64),
+        (DataType::Float32, "0x7fc00001", 0x7fc0_0001),
+        (DataType::Float32, "0xFF800000", 0xff80_0000),
+        (
+            DataType::Float64,
+            "0x7ff8000000000123",
+            0x7ff8_0000_0000_0123,
+        ),
+    ];
+    for (dtype, hex, want) in cases {
+        let fill = parse_fill_value(dtype, &json!(hex)).unwrap();
+        let bits = match fill.to_zarr_value(dtype) {
+            ZarrValue::Float16(v) => v.to_bits() as u64,
+            ZarrValue::Float32(v) => v.to_bits() as u64,
+            ZarrValue::Float64(v) => v.to_bits(),
+            other => panic!("{hex}: expected a float, got {other:?}"),
+        };
+        assert_eq!(bits, want, "{hex}");
+    }
+}
+
+#[test]
+fn malformed_hex_fill_values_are_rejected() {
+    for (dtype, hex) in [
+        (DataType::Float32, "0x7fc0"),
+        (DataType::Float64, "0x7fc00000"),
+        (DataType::Float32, "0x7fc0000g"),
+        (DataType::Float32, "0x"),
+    ] {
+        let err = parse_fill_value(dtype, &json!(hex)).unwrap_err();
+        assert!(err.contains("Invalid hex fill value"), "{err}");
+    }
+    // Hex is only a float spelling.
+    assert!(parse_fill_value(DataType::Int32, &json!("0x00000001")).is_err());
+}
+
+#[test]
+fn negative_zero_fill_values_keep_their_sign() {
+    for text in ["-0.0", "-0", "-0e0"] {
+        let value: serde_json::Value = serde_json::from_str(text).unwrap();
+        for dtype in [DataType::Float16, DataType::Float32, DataType::Float64] {
+            let fill = parse_fill_value(dtype, &value).unwrap();
+            let negative = match fill.to_zarr_value(dtype) {
+                ZarrValue::Float16(v) => v.is_sign_negative() && v.to_f64() == 0.0,
+                ZarrValue::Float32(v) => v.is_sign_negative() && v == 0.0,
+                ZarrValue::Float64(v) => v.is_sign_negative() && v == 0.0,
+                other => panic!("expected a float, got {other:?}"),
+            };
+            assert!(negative, "{dtype:?} from {value}");
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Raw (opaque fixed-width) data types
+// ---------------------------------------------------------------------------
+
+#[test]
+fn numpy_void_parses_as_raw() {
+    let dt = parse_numpy_dtype("|V4").unwrap();
+    assert_eq!(dt.data_type, DataType::Raw(32));
+    assert_eq!(dt.data_type.byte_size(), Some(4));
+}
+
+#[test]
+fn numpy_native_byte_order_is_written_as_the_host_order() {
+    let dt = parse_numpy_dtype("=f8").unwrap();
+    assert_eq!(dt.byte_order, Endian::Native);
+    assert_eq!(dt.data_type, DataType::Float64);
+    let host = if cfg!(target_endian = "big") {
+        ">f8"
+    } else {
+        "<f8"
+    };
+    assert_eq!(serde_json::to_value(&dt).unwrap(), json!(host));
+}
+
+#[test]
+fn structured_dtypes_are_reported_as_unsupported() {
+    let zarray = json!({
+        "zarr_format": 2,
+        "shape": [4],
+        "chunks": [2],
+        "dtype": [["temperature", "<f4"], ["pressure", "<f8"]],
+        "fill_value": null,
+        "order": "C",
+        "compressor": null,
+        "filters": null,
+    });
+    let err = ZarrV2Metadata::parse(&serde_json::to_vec(&zarray).unwrap()).unwrap_err();
+    match err {
+        ZarrError::UnsupportedFeature(FeatureId::DataType(name)) => {
+            assert!(name.starts_with("structured "), "{name}");
+            assert!(name.contains("temperature"), "{name}");
+        }
+        other => panic!("expected an unsupported data type, got {other:?}"),
+    }
+}
+
+#[test]
+fn raw_chunks_split_into_fixed_width_elements() {
+    let data = [1u8, 2, 3, 4, 5, 6];
+    match bytes_to_zarr_vector(Endian::Little, DataType::Raw(16), &data).unwrap() {
+        ZarrVectorValue::VBytes(v) => assert_eq!(v, vec![vec![1, 2], vec![3, 4], vec![5, 6]]),
+        other => panic!("expected VBytes, got {other:?}"),
+    }
+    assert!(bytes_to_zarr_vector(Endian::Little, DataType::Raw(32), &data).is_err());
+}
+
+#[test]
+fn raw_fill_values() {
+    // V2 stores void fill values as base64.
+    assert_eq!(
+        parse_fill_value(DataType::Raw(32), &json!("AQIDBA==")).unwrap(),
+        FillValue::Value(ZarrValue::Bytes(vec![1, 2, 3, 4]))
+    );
+    // V3 stores them as arrays of byte values.
+    assert_eq!(
+        parse_fill_value(DataType::Raw(16), &json!([255, 0])).unwrap(),
+        FillValue::Value(ZarrValue::Bytes(vec![255, 0]))
+    );
+
+    let err = parse_fill_value(DataType::Raw(32), &json!([1, 2])).unwrap_err();
+    assert!(err.contains("has 2 bytes, expected 4"), "{err}");
+    assert!(parse_fill_value(DataType::Raw(16), &json!("AQIDBA==")).is_err());
+    assert!(parse_fill_value(DataType::Raw(8), &json!([256])).is_err());
+    assert!(parse_fill_value(DataType::Raw(8), &json!("not base64!")).is_err());
+    assert!(parse_fill_value(DataType::Raw(8), &json!(0)).is_err());
+}
+
+// ---------------------------------------------------------------------------
+// Bool decoding
+// ---------------------------------------------------------------------------
+
+#[test]
+fn bool_bytes_must_be_zero_or_one() {
+    match bytes_to_zarr_vector(Endian::Little, DataType::Bool, &[0, 1, 1]).unwrap() {
+        ZarrVectorValue::VBool(v) => assert_eq!(v, vec![false, true, true]),
+        other => panic!("expected VBool, got {other:?}"),
+    }
+    assert!(bytes_to_zarr_vector(Endian::Little, DataType::Bool, &[0, 2]).is_err());
+}
+
+#[test]
+fn bit_packed_bools_ignore_padding() {
+    // 10 elements, MSB first; the last six bits of the second byte are padding.
+    let bools = unpack_bools(&[0b1010_0001, 0b0111_1111], 10).unwrap();
+    assert_eq!(
+        bools,
+        vec![
+            true, false, true, false, false, false, false, true, false, true
+        ]
+    );
+    assert!(unpack_bools(&[0xff], 10).is_err());
+    assert!(unpack_bools(&[0xff, 0xff], 8).is_err());
+}
+
+// ---------------------------------------------------------------------------
+// f64 conversion
+// ---------------------------------------------------------------------------
+
+#[test]
+fn validity_separates_nulls_from_nan() {
+    let v = ZarrVectorValue::VWithNulls(
+        DataType::Float32,
+        vec![
+            Some(ZarrValue::Float32(1.5)),
+            None,
+            Some(ZarrValue::Float32(f32::NAN)),
+            Some(ZarrValue::Null(DataType::Float32)),
+        ],
+    );
+    let (values, validity) = v.to_f64_with_validity().unwrap();
+    assert_eq!(values[0], 1.5);
+    assert!(values[1..].iter().all(|x| x.is_nan()));
+    assert_eq!(validity, vec![true, false, true, false]);
+
+    // Typed vectors are all valid.
+    let v = ZarrVectorValue::VInt16(vec![-1, 2]);
+    assert_eq!(
+        v.to_f64_with_validity().unwrap(),
+        (vec![-1.0, 2.0], vec![true, true])
+    );
+
+    for v in [
+        ZarrVectorValue::VString(vec!["a".into()]),
+        ZarrVectorValue::VWithNulls(DataType::String, vec![None]),
+    ] {
+        let err = v.to_f64_with_validity().unwrap_err();
+        assert!(matches!(err, ZarrError::TypeConversion(_)), "{err:?}");
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Display
+// ---------------------------------------------------------------------------
+
+#[test]
+fn data_types_and_fill_values_display_readably() {
+    assert_eq!(DataType::Float32.to_string(), "float32");
+    assert_eq!(DataType::UInt8.to_string(), "uint8");
+    assert_eq!(DataType::Raw(48).to_string(), "r48");
+    assert_eq!(Endian::Big.to_string(), "BE");
+    assert_eq!(Endian::Little.to_string(), "LE");
+
+    for (fill, want) in [
+        (FillValue::NaN, "NaN"),
+        (FillValue::NegativeInfinity, "-Infinity"),
+        (FillValue::Value(ZarrValue::Int16(-3)), "-3"),
+        (FillValue::Value(ZarrValue::Float64(0.5)), "0.5"),
+        (FillValue::Value(ZarrValue::Bool(true)), "true"),
+        (
+            FillValue::Value(ZarrValue::Complex64(num_complex::Complex::new(1.0, -2.0))),
+            "1-2i",
+        ),
+        (FillValue::Value(ZarrValue::String("n/a".into())), "\"n/a\""),
+        (
+            FillValue::Value(ZarrValue::Bytes(vec![0x0a, 0xff])),
+            "0x0aff",
+        ),
+    ] {
+        assert_eq!(fill.to_string(), want);
+    }
+
+    // Errors name types the way metadata does.
+    let err = parse_fill_value(DataType::Int32, &json!("NaN")).unwrap_err();
+    assert_eq!(err, "NaN not valid for int32");
+}
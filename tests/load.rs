@@ -0,0 +1,1619 @@
+//! Integration tests for whole-array and region loads.
+//!
+//! These build small uncompressed V2 arrays in an in-memory object store, so
+//! they don't depend on the Python-generated test data.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{StreamExt, TryStreamExt};
+use tokio::sync::mpsc;
+
+use object_store::memory::InMemory;
+use object_store::path::Path as ObjectPath;
+use object_store::{ObjectStoreExt, PutPayload};
+
+use simplezarr::aligned::read_region_multi;
+use simplezarr::array::{
+    ArrayData, ArrayDescriptor, UnifiedZarrArray, expected_chunk_bytes, merge_chunks,
+    merge_chunks_value,
+};
+use simplezarr::builder::ArrayBuilder;
+use simplezarr::capabilities::FeatureId;
+use simplezarr::codecs::brotli::BrotliCodec;
+use simplezarr::codecs::gzip::GzipCodec;
+use simplezarr::codecs::lz4::Lz4Codec;
+use simplezarr::codecs::snappy::SnappyCodec;
+use simplezarr::codecs::zlib::ZlibCodec;
+use simplezarr::codecs::zstd::ZstdCodec;
+use simplezarr::codecs::{AnyCodec, CodecPipeline};
+use simplezarr::config::SimplezarrConfig;
+use simplezarr::error::{ZarrError, ZarrResult};
+use simplezarr::group::ArrayNameOrder;
+use simplezarr::metadata::v2::ZarrConsolidatedMetadata;
+use simplezarr::metadata::v3::ZarrV3Metadata;
+use simplezarr::options::{ArrayReadOptions, BoolEncoding, GroupReadOptions};
+use simplezarr::store::{ObjectMeta, ObjectStoreBackend, StorageBackend};
+use simplezarr::types::{ArrayOrder, DataType, ZarrVectorValue};
+use simplezarr::v2;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Build a 5x4 little-endian `<i4` array with 2x3 chunks whose element at
+/// (i, j) is `10 * i + j`.  Chunk `1.1` is left absent to exercise fill values.
+async fn grid_store() -> Arc<ObjectStoreBackend> {
+    grid_store_with_metadata(true).await
+}
+
+/// [`grid_store`], optionally without its `.zarray`.
+async fn grid_store_with_metadata(metadata: bool) -> Arc<ObjectStoreBackend> {
+    let mem = InMemory::new();
+    let zarray = serde_json::json!({
+        "zarr_format": 2,
+        "shape": [5, 4],
+        "chunks": [2, 3],
+        "dtype": "<i4",
+        "fill_value": -1,
+        "order": "C",
+        "compressor": null,
+        "filters": null,
+    });
+    if metadata {
+        put(&mem, "grid/.zarray", serde_json::to_vec(&zarray).unwrap()).await;
+    }
+
+    for ci in 0..3usize {
+        for cj in 0..2usize {
+            if (ci, cj) == (1, 1) {
+                continue;
+            }
+            let mut bytes = Vec::new();
+            for li in 0..2 {
+                for lj in 0..3 {
+                    let v = (10 * (ci * 2 + li) + cj * 3 + lj) as i32;
+                    bytes.extend_from_slice(&v.to_le_bytes());
+                }
+            }
+            put(&mem, &format!("grid/{ci}.{cj}"), bytes).await;
+        }
+    }
+    Arc::new(ObjectStoreBackend::new(Box::new(mem), ""))
+}
+
+async fn put(mem: &InMemory, key: &str, bytes: Vec<u8>) {
+    mem.put(&ObjectPath::from(key), PutPayload::from(bytes))
+        .await
+        .expect("put");
+}
+
+/// Store a single-chunk 1-D array `name` with the given dtype and compressor,
+/// whose only chunk holds `chunk` verbatim.
+async fn single_chunk_store(
+    name: &str,
+    dtype: &str,
+    len: usize,
+    compressor: serde_json::Value,
+    chunk: Vec<u8>,
+) -> Arc<ObjectStoreBackend> {
+    let mem = InMemory::new();
+    let zarray = serde_json::json!({
+        "zarr_format": 2,
+        "shape": [len],
+        "chunks": [len],
+        "dtype": dtype,
+        "fill_value": null,
+        "compressor": compressor,
+    });
+    put(
+        &mem,
+        &format!("{name}/.zarray"),
+        serde_json::to_vec(&zarray).unwrap(),
+    )
+    .await;
+    put(&mem, &format!("{name}/0"), chunk).await;
+    Arc::new(ObjectStoreBackend::new(Box::new(mem), ""))
+}
+
+fn assert_limit_error<T: std::fmt::Debug>(result: Result<T, ZarrError>) {
+    match result {
+        Err(ZarrError::Decode(msg)) => {
+            assert!(msg.contains("limit"), "unexpected decode error: {msg}")
+        }
+        other => panic!("expected a decode limit error, got {other:?}"),
+    }
+}
+
+/// Wraps a store and counts reads and `head` requests.
+struct CountingStore {
+    inner: Arc<ObjectStoreBackend>,
+    gets: AtomicUsize,
+    heads: AtomicUsize,
+}
+
+impl CountingStore {
+    fn new(inner: Arc<ObjectStoreBackend>) -> Arc<Self> {
+        Arc::new(Self {
+            inner,
+            gets: AtomicUsize::new(0),
+            heads: AtomicUsize::new(0),
+        })
+    }
+
+    fn gets(&self) -> usize {
+        self.gets.load(Ordering::SeqCst)
+    }
+
+    fn heads(&self) -> usize {
+        self.heads.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for CountingStore {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        self.gets.fetch_add(1, Ordering::SeqCst);
+        self.inner.get(path).await
+    }
+
+    async fn list(&self, prefix: &str) -> ZarrResult<Vec<String>> {
+        self.inner.list(prefix).await
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        self.inner.join(base, segment)
+    }
+
+    async fn get_with_etag(&self, path: &str) -> ZarrResult<Option<(Bytes, Option<String>)>> {
+        self.gets.fetch_add(1, Ordering::SeqCst);
+        self.inner.get_with_etag(path).await
+    }
+
+    async fn head(&self, path: &str) -> ZarrResult<Option<ObjectMeta>> {
+        self.heads.fetch_add(1, Ordering::SeqCst);
+        self.inner.head(path).await
+    }
+}
+
+/// Wraps a store; reads of the `broken` keys fail at once, and every other
+/// read takes 50ms, counting those that finish.
+struct BrokenStore {
+    inner: Arc<ObjectStoreBackend>,
+    broken: Vec<&'static str>,
+    finished: AtomicUsize,
+}
+
+#[async_trait]
+impl StorageBackend for BrokenStore {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        if self.broken.contains(&path) {
+            return Err(ZarrError::Storage(format!("{path} is broken")));
+        }
+        if !path.ends_with(".zarray") {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            self.finished.fetch_add(1, Ordering::SeqCst);
+        }
+        self.inner.get(path).await
+    }
+
+    async fn list(&self, prefix: &str) -> ZarrResult<Vec<String>> {
+        self.inner.list(prefix).await
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        self.inner.join(base, segment)
+    }
+}
+
+fn expected(i: usize, j: usize) -> f64 {
+    // Chunk 1.1 covers rows 2..4, columns 3..4 and is missing.
+    if (2..4).contains(&i) && j >= 3 {
+        -1.0
+    } else {
+        (10 * i + j) as f64
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn load_whole_array() {
+    let arr = v2::open(grid_store().await, "grid").await.expect("open");
+    let data = arr.load().await.expect("load");
+    assert_eq!(data.len(), 20);
+    for i in 0..5 {
+        for j in 0..4 {
+            assert_eq!(data[i * 4 + j], expected(i, j), "element ({i}, {j})");
+        }
+    }
+}
+
+#[tokio::test]
+async fn loads_carry_their_shape_and_order() {
+    let arr = v2::open(grid_store().await, "grid").await.expect("open");
+    let data = arr.load().await.expect("load");
+    assert_eq!(data.shape, vec![5, 4]);
+    assert_eq!(data.order, ArrayOrder::C);
+    assert_eq!(data.get(&[2, 3]), Some(&expected(2, 3)));
+    assert_eq!(data.get(&[5, 0]), None);
+    assert_eq!(data.get(&[1]), None);
+
+    let region = arr.read_region(&[1..4, 2..4]).await.expect("read_region");
+    assert_eq!(region.shape, vec![3, 2]);
+    assert_eq!(region.get(&[0, 1]), Some(&expected(1, 3)));
+    assert_eq!(arr.load_sync().expect("load_sync").shape, vec![5, 4]);
+    assert_eq!(
+        arr.load_value().await.expect("load_value").shape,
+        vec![5, 4]
+    );
+
+    // Column-major values are indexed through their order.
+    let fortran = ArrayData::new(
+        vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+        vec![2, 3],
+        ArrayOrder::F,
+    );
+    assert_eq!(fortran.get(&[1, 0]), Some(&2.0));
+    assert_eq!(fortran.get(&[0, 1]), Some(&3.0));
+    let doubled = fortran.map(|v| v.into_iter().map(|x| x * 2.0).collect::<Vec<_>>());
+    assert_eq!(doubled.order, ArrayOrder::F);
+    assert_eq!(doubled.into_inner()[5], 12.0);
+}
+
+#[tokio::test]
+async fn singleton_dimensions_are_squeezed_on_request() {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let arr = ArrayBuilder::new([1, 3, 1, 2])
+        .chunks([1, 3, 1, 2])
+        .dtype(DataType::Int16)
+        .codec(None)
+        .build(store, "a")
+        .await
+        .unwrap();
+    arr.write_chunk(
+        &[0, 0, 0, 0],
+        &ZarrVectorValue::VInt16(vec![1, 2, 3, 4, 5, 6]),
+    )
+    .await
+    .unwrap();
+    assert_eq!(arr.metadata.singleton_axes(), vec![0, 2]);
+    assert_eq!(arr.metadata.squeeze(), vec![3, 2]);
+
+    let kept = arr.load().await.unwrap();
+    assert_eq!(kept.shape, vec![1, 3, 1, 2]);
+
+    let arr = arr.with_read_options(ArrayReadOptions::default().squeeze(true));
+    let squeezed = arr.load().await.unwrap();
+    assert_eq!(squeezed.shape, vec![3, 2]);
+    assert_eq!(squeezed, kept.data);
+    assert_eq!(squeezed.get(&[2, 1]), Some(&6.0));
+
+    // Only the array's singleton dimensions go, not a region's.
+    let region = arr.read_region(&[0..1, 1..2, 0..1, 0..2]).await.unwrap();
+    assert_eq!(region.shape, vec![1, 2]);
+    assert_eq!(region, vec![3.0, 4.0]);
+}
+
+#[tokio::test]
+async fn nan_fills_of_integer_arrays_read_as_the_sentinel() {
+    let mem = InMemory::new();
+    for (name, dtype) in [("a", "<i2"), ("unsigned", "|u1")] {
+        let zarray = serde_json::json!({
+            "zarr_format": 2,
+            "shape": [4],
+            "chunks": [2],
+            "dtype": dtype,
+            "fill_value": null,
+            "compressor": null,
+        });
+        put(
+            &mem,
+            &format!("{name}/.zarray"),
+            serde_json::to_vec(&zarray).unwrap(),
+        )
+        .await;
+    }
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(mem), ""));
+    let arr = v2::open(store.clone(), "a").await.unwrap();
+    arr.write_chunk(&[0], &ZarrVectorValue::VInt16(vec![1, 2]))
+        .await
+        .unwrap();
+    assert_eq!(arr.load().await.unwrap(), vec![1.0, 2.0, 0.0, 0.0]);
+
+    let options = ArrayReadOptions::default().int_fill_sentinel(-9999);
+    let arr = arr.with_read_options(options.clone());
+    assert_eq!(arr.load().await.unwrap(), vec![1.0, 2.0, -9999.0, -9999.0]);
+    let tail = arr.read_region_value(std::slice::from_ref(&(1..4)));
+    match tail.await.unwrap().data {
+        ZarrVectorValue::VInt16(values) => assert_eq!(values, [2, -9999, -9999]),
+        other => panic!("expected int16 values, got {other:?}"),
+    }
+
+    // Writes into a missing chunk start from the fill value, not the sentinel.
+    arr.set_points(&[vec![3]], &ZarrVectorValue::VInt16(vec![7]))
+        .await
+        .unwrap();
+    assert_eq!(arr.load().await.unwrap(), vec![1.0, 2.0, 0.0, 7.0]);
+
+    // Numeric fills are kept, and the sentinel must fit the element type.
+    let filled = ArrayBuilder::new([2])
+        .dtype(DataType::Int16)
+        .fill(5.0)
+        .build(store.clone(), "filled")
+        .await
+        .unwrap()
+        .with_read_options(options.clone());
+    assert_eq!(filled.load().await.unwrap(), vec![5.0, 5.0]);
+    let unsigned = v2::open(store, "unsigned")
+        .await
+        .unwrap()
+        .with_read_options(options);
+    let err = unsigned.load().await.unwrap_err();
+    assert!(matches!(err, ZarrError::TypeConversion(_)), "{err:?}");
+}
+
+#[test]
+fn load_sync_reads_every_chunk_in_order() {
+    let store = CountingStore::new(futures::executor::block_on(grid_store()));
+    let arr = futures::executor::block_on(v2::open(store.clone(), "grid")).expect("open");
+    let opened = store.gets();
+    let data = arr.load_sync().expect("load_sync");
+    assert_eq!(store.gets() - opened, 6);
+    for i in 0..5 {
+        for j in 0..4 {
+            assert_eq!(data[i * 4 + j], expected(i, j), "element ({i}, {j})");
+        }
+    }
+}
+
+#[tokio::test]
+async fn read_region_across_chunks() {
+    let arr = v2::open(grid_store().await, "grid").await.expect("open");
+    let data = arr.read_region(&[1..4, 2..4]).await.expect("read_region");
+    let want: Vec<f64> = (1..4)
+        .flat_map(|i| (2..4).map(move |j| expected(i, j)))
+        .collect();
+    assert_eq!(data, want);
+}
+
+#[tokio::test]
+async fn read_region_value_keeps_the_element_type() {
+    let arr = v2::open(grid_store().await, "grid").await.expect("open");
+    let value = arr.read_region_value(&[1..4, 2..4]).await.expect("read");
+    assert_eq!(value.shape, vec![3, 2]);
+    let ZarrVectorValue::VInt32(data) = &value.data else {
+        panic!("expected VInt32, got {value:?}");
+    };
+    let want: Vec<i32> = (1..4)
+        .flat_map(|i| (2..4).map(move |j| expected(i, j) as i32))
+        .collect();
+    assert_eq!(data, &want);
+
+    // Mixed variants and uncovered positions fall back to nulls.
+    let md = &arr.metadata;
+    let chunks = vec![
+        (vec![0, 0], ZarrVectorValue::VInt32(vec![1; 6])),
+        (vec![0, 1], ZarrVectorValue::VInt64(vec![2; 6])),
+    ];
+    let merged = merge_chunks_value(md, &[0..1, 2..4], &chunks).unwrap();
+    assert!(matches!(merged, ZarrVectorValue::VWithNulls(_, _)));
+    assert_eq!(merged.to_f64_vec().unwrap(), vec![1.0, 2.0]);
+    let merged = merge_chunks_value(md, &[0..1, 2..4], &chunks[..1]).unwrap();
+    assert_eq!(merged.to_f64_with_validity().unwrap().1, vec![true, false]);
+}
+
+#[tokio::test]
+async fn f64_merges_convert_each_chunk_in_place() {
+    let arr = v2::open(grid_store().await, "grid").await.expect("open");
+    let md = &arr.metadata;
+    let chunks = vec![
+        (vec![0, 0], ZarrVectorValue::VBool(vec![true; 6])),
+        (vec![0, 1], ZarrVectorValue::VUInt16(vec![7; 6])),
+    ];
+    assert_eq!(
+        merge_chunks(md, &[0..1, 2..4], &chunks).unwrap(),
+        vec![1.0, 7.0]
+    );
+    let nulls = ZarrVectorValue::VWithNulls(md.data_type, vec![None; 6]);
+    let merged = merge_chunks(md, &[0..1, 0..1], &[(vec![0, 0], nulls)]).unwrap();
+    assert!(merged[0].is_nan());
+
+    let short = vec![(vec![0, 0], ZarrVectorValue::VInt32(vec![1; 2]))];
+    assert!(matches!(
+        merge_chunks(md, &[0..2, 0..3], &short),
+        Err(ZarrError::Decode(_))
+    ));
+    let strings = vec![(vec![0, 0], ZarrVectorValue::VString(vec![String::new(); 6]))];
+    assert!(matches!(
+        merge_chunks(md, &[0..1, 0..1], &strings),
+        Err(ZarrError::TypeConversion(_))
+    ));
+}
+
+#[tokio::test]
+async fn f32_loads_saturate() {
+    let arr = v2::open(grid_store().await, "grid").await.expect("open");
+    let want: Vec<f32> = arr
+        .load()
+        .await
+        .unwrap()
+        .iter()
+        .map(|&x| x as f32)
+        .collect();
+    assert_eq!(arr.load_f32().await.unwrap(), want);
+    assert_eq!(
+        arr.read_region_f32(&[3..5, 2..4]).await.unwrap(),
+        vec![32.0, -1.0, 42.0, 43.0]
+    );
+
+    let values = [1.5, 1e300, -1e300, f64::INFINITY];
+    let chunk = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    let store = single_chunk_store("big", "<f8", 4, serde_json::Value::Null, chunk).await;
+    let arr = v2::open(store, "big").await.expect("open");
+    assert_eq!(
+        arr.load_f32().await.unwrap(),
+        vec![1.5, f32::MAX, f32::MIN, f32::INFINITY]
+    );
+}
+
+#[tokio::test]
+async fn read_slice_drops_the_sliced_axis() {
+    let arr = v2::open(grid_store().await, "grid").await.expect("open");
+    let row = arr.read_slice(0, 3).await.expect("row");
+    assert_eq!(row.shape, vec![4]);
+    assert_eq!(row, (0..4).map(|j| expected(3, j)).collect::<Vec<_>>());
+    let column = arr.read_slice(1, 3).await.expect("column");
+    assert_eq!(column.shape, vec![5]);
+    assert_eq!(column, (0..5).map(|i| expected(i, 3)).collect::<Vec<_>>());
+
+    assert!(arr.read_slice(0, 5).await.is_err());
+    assert!(arr.read_slice(2, 0).await.is_err());
+
+    // With squeezing, the array's other singleton axes go too.
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let arr = ArrayBuilder::new([2, 1, 3])
+        .dtype(DataType::Int16)
+        .codec(None)
+        .order(ArrayOrder::F)
+        .build(store, "a")
+        .await
+        .unwrap();
+    arr.set_region(
+        &arr.full_region(),
+        &ZarrVectorValue::VInt16(vec![1, 2, 3, 4, 5, 6]),
+    )
+    .await
+    .unwrap();
+    assert_eq!(arr.read_slice(2, 1).await.unwrap().shape, vec![2, 1]);
+    let arr = arr.with_read_options(ArrayReadOptions::default().squeeze(true));
+    let step = arr.read_slice(2, 1).await.unwrap();
+    assert_eq!(step.shape, vec![2]);
+    assert_eq!(step, vec![3.0, 4.0]);
+}
+
+/// A 2x2 `<i4` array at `a` in 1x1 chunks holding 0..4, stored under keys
+/// joined with `separator`, optionally declaring it.
+async fn layout_store(separator: char, declared: bool) -> Arc<ObjectStoreBackend> {
+    let mem = InMemory::new();
+    let mut zarray = serde_json::json!({
+        "zarr_format": 2,
+        "shape": [2, 2],
+        "chunks": [1, 1],
+        "dtype": "<i4",
+        "fill_value": -1,
+        "compressor": null,
+    });
+    if declared {
+        zarray["dimension_separator"] = separator.to_string().into();
+    }
+    put(&mem, "a/.zarray", serde_json::to_vec(&zarray).unwrap()).await;
+    for (i, j) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+        let value: i32 = 2 * i + j;
+        put(
+            &mem,
+            &format!("a/{i}{separator}{j}"),
+            value.to_le_bytes().to_vec(),
+        )
+        .await;
+    }
+    Arc::new(ObjectStoreBackend::new(Box::new(mem), ""))
+}
+
+#[tokio::test]
+async fn nested_chunk_layouts_are_declared_detected_or_set() {
+    let arr = v2::open(layout_store('/', true).await, "a").await.unwrap();
+    assert_eq!(arr.metadata.dimension_separator, '/');
+    assert_eq!(arr.metadata.keys[1], "0/1");
+    assert_eq!(arr.load().await.unwrap(), vec![0.0, 1.0, 2.0, 3.0]);
+
+    let arr = v2::open(layout_store('/', false).await, "a").await.unwrap();
+    assert_eq!(arr.metadata.dimension_separator, '.');
+    let arr = arr.detect_dimension_separator().await.unwrap();
+    assert_eq!(arr.metadata.dimension_separator, '/');
+    assert_eq!(arr.load().await.unwrap(), vec![0.0, 1.0, 2.0, 3.0]);
+    let arr = v2::open(layout_store('.', false).await, "a").await.unwrap();
+    let arr = arr.detect_dimension_separator().await.unwrap();
+    assert_eq!(arr.metadata.dimension_separator, '.');
+    assert_eq!(arr.load().await.unwrap(), vec![0.0, 1.0, 2.0, 3.0]);
+
+    // A declared layout is trusted, even when the `.` keys it names are
+    // all missing.
+    let store = layout_store('/', false).await;
+    let mut zarray: serde_json::Value =
+        serde_json::from_slice(&store.get("a/.zarray").await.unwrap().unwrap()).unwrap();
+    zarray["dimension_separator"] = ".".into();
+    store
+        .put("a/.zarray", serde_json::to_vec(&zarray).unwrap().into())
+        .await
+        .unwrap();
+    let arr = v2::open(store, "a").await.unwrap();
+    let arr = arr.detect_dimension_separator().await.unwrap();
+    assert_eq!(arr.load().await.unwrap(), vec![-1.0; 4]);
+    let arr = arr.with_dimension_separator('/').unwrap();
+    assert_eq!(arr.load().await.unwrap(), vec![0.0, 1.0, 2.0, 3.0]);
+    assert_eq!(arr.descriptor().metadata["dimension_separator"], "/");
+    assert!(arr.with_dimension_separator('-').is_err());
+
+    let mem = InMemory::new();
+    let zarray = serde_json::json!({
+        "zarr_format": 2,
+        "shape": [2],
+        "chunks": [1],
+        "dtype": "<i4",
+        "fill_value": 0,
+        "compressor": null,
+        "dimension_separator": "-",
+    });
+    put(&mem, "b/.zarray", serde_json::to_vec(&zarray).unwrap()).await;
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(mem), ""));
+    assert!(matches!(
+        v2::open(store, "b").await,
+        Err(ZarrError::Metadata(_))
+    ));
+}
+
+#[tokio::test]
+async fn read_region_rejects_out_of_bounds() {
+    let arr = v2::open(grid_store().await, "grid").await.expect("open");
+    assert!(arr.read_region(&[0..6, 0..4]).await.is_err());
+    assert!(
+        arr.read_region(std::slice::from_ref(&(0..5)))
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn chunk_cache_is_used_when_configured() {
+    let config = SimplezarrConfig::builder()
+        .chunk_cache_bytes(1 << 20)
+        .max_concurrency(2)
+        .build();
+    let arr = v2::open_with_config(grid_store().await, "grid", Arc::new(config))
+        .await
+        .expect("open");
+    let first = arr.load().await.expect("load");
+    assert!(arr.chunk_cache().unwrap().size_bytes() > 0);
+    assert_eq!(arr.load().await.expect("reload"), first);
+}
+
+#[tokio::test]
+async fn short_chunk_reports_size_mismatch() {
+    let mem = InMemory::new();
+    let zarray = serde_json::json!({
+        "zarr_format": 2,
+        "shape": [4],
+        "chunks": [4],
+        "dtype": "<i4",
+        "fill_value": 0,
+        "compressor": null,
+    });
+    put(&mem, "short/.zarray", serde_json::to_vec(&zarray).unwrap()).await;
+    put(&mem, "short/0", vec![0u8; 12]).await;
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(mem), ""));
+
+    let arr = v2::open(store, "short").await.expect("open");
+    match arr.get_chunk(&[0]).await {
+        Err(ZarrError::ChunkSizeMismatch {
+            chunk,
+            expected,
+            actual,
+        }) => {
+            assert_eq!(chunk, "0");
+            assert_eq!(expected, 16);
+            assert_eq!(actual, 12);
+        }
+        other => panic!("expected ChunkSizeMismatch, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn trailing_chunk_bytes_are_an_error_unless_allowed() {
+    let mut chunk: Vec<u8> = (1i32..=4).flat_map(|v| v.to_le_bytes()).collect();
+    chunk.extend([0xff; 4]);
+    let store = single_chunk_store("long", "<i4", 4, serde_json::Value::Null, chunk).await;
+    let arr = v2::open(store.clone(), "long").await.expect("open");
+    match arr.load().await {
+        Err(ZarrError::ChunkSizeMismatch {
+            expected, actual, ..
+        }) => assert_eq!((expected, actual), (16, 20)),
+        other => panic!("expected ChunkSizeMismatch, got {other:?}"),
+    }
+
+    let arr = arr.with_read_options(ArrayReadOptions::default().allow_trailing_bytes(true));
+    assert_eq!(arr.load().await.unwrap(), vec![1.0, 2.0, 3.0, 4.0]);
+
+    // Short chunks can't be completed, allowed or not.
+    let store = single_chunk_store("short", "<i4", 4, serde_json::Value::Null, vec![0; 12]).await;
+    let arr = v2::open(store, "short")
+        .await
+        .expect("open")
+        .with_read_options(ArrayReadOptions::default().allow_trailing_bytes(true));
+    assert!(matches!(
+        arr.load().await,
+        Err(ZarrError::ChunkSizeMismatch { .. })
+    ));
+}
+
+#[tokio::test]
+async fn trimmed_edge_chunks_are_laid_out_in_place() {
+    // A 3x5 array in 2x2 chunks whose edge chunks hold only the elements
+    // inside the array, as some writers store them.
+    for order in ["C", "F"] {
+        let mem = InMemory::new();
+        let zarray = serde_json::json!({
+            "zarr_format": 2,
+            "shape": [3, 5],
+            "chunks": [2, 2],
+            "dtype": "<i2",
+            "fill_value": 0,
+            "order": order,
+            "compressor": null,
+        });
+        put(&mem, "t/.zarray", serde_json::to_vec(&zarray).unwrap()).await;
+        for ki in 0..2 {
+            for kj in 0..3 {
+                let rows = 2 * ki..(2 * ki + 2).min(3);
+                let cols = 2 * kj..(2 * kj + 2).min(5);
+                let mut cells: Vec<(i16, i16)> = Vec::new();
+                for i in rows.clone() {
+                    for j in cols.clone() {
+                        cells.push((i, j));
+                    }
+                }
+                if order == "F" {
+                    cells.sort_by_key(|&(i, j)| (j, i));
+                }
+                let bytes = cells
+                    .iter()
+                    .flat_map(|(i, j)| (i * 10 + j).to_le_bytes())
+                    .collect();
+                put(&mem, &format!("t/{ki}.{kj}"), bytes).await;
+            }
+        }
+        let store = Arc::new(ObjectStoreBackend::new(Box::new(mem), ""));
+        let arr = v2::open(store, "t").await.expect("open");
+        let data = arr.load().await.expect(order);
+        for i in 0..3 {
+            for j in 0..5 {
+                assert_eq!(data.get(&[i, j]), Some(&((i * 10 + j) as f64)), "{order}");
+            }
+        }
+
+        // Updating a trimmed chunk writes it back whole.
+        arr.set_points(&[vec![2, 4]], &ZarrVectorValue::VInt16(vec![-1]))
+            .await
+            .unwrap();
+        assert_eq!(arr.get_chunk_raw(&[1, 2]).await.unwrap().unwrap().len(), 8);
+        let data = arr.load().await.unwrap();
+        assert_eq!(data.get(&[2, 4]), Some(&-1.0));
+        assert_eq!(data.get(&[2, 3]), Some(&23.0));
+    }
+}
+
+#[tokio::test]
+async fn malformed_chunk_grids_are_rejected() {
+    let mem = InMemory::new();
+    for (name, shape, chunks) in [
+        ("zero", vec![4], vec![0]),
+        ("ragged", vec![4, 4], vec![2]),
+        // 2^40 single-element chunks would be listed up front, past the
+        // default limit.
+        ("huge", vec![1u64 << 40], vec![1]),
+        // A single chunk of 2^62 four-byte elements can't be addressed.
+        ("vast", vec![1], vec![1u64 << 62]),
+    ] {
+        let zarray = serde_json::json!({
+            "zarr_format": 2,
+            "shape": shape,
+            "chunks": chunks,
+            "dtype": "<i4",
+            "fill_value": 0,
+            "compressor": null,
+        });
+        put(
+            &mem,
+            &format!("{name}/.zarray"),
+            serde_json::to_vec(&zarray).unwrap(),
+        )
+        .await;
+    }
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(mem), ""));
+    let config = Arc::new(SimplezarrConfig::default());
+    for name in ["zero", "ragged", "huge", "vast"] {
+        let err = v2::open_with_config(store.clone(), name, config.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ZarrError::Metadata(_)), "{name}: {err:?}");
+    }
+}
+
+#[tokio::test]
+async fn bit_packed_bool_chunks() {
+    let mem = InMemory::new();
+    let zarray = serde_json::json!({
+        "zarr_format": 2,
+        "shape": [10],
+        "chunks": [10],
+        "dtype": "|b1",
+        "fill_value": false,
+        "compressor": null,
+    });
+    put(&mem, "flags/.zarray", serde_json::to_vec(&zarray).unwrap()).await;
+    // Ten elements packed MSB first; the final byte carries six padding bits.
+    put(&mem, "flags/0", vec![0b1100_0000, 0b0100_0000]).await;
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(mem), ""));
+
+    let arr = v2::open(store, "flags").await.expect("open");
+    // As one byte per element the two bytes are too short for ten elements.
+    assert!(matches!(
+        arr.get_chunk(&[0]).await,
+        Err(ZarrError::ChunkSizeMismatch {
+            expected: 10,
+            actual: 2,
+            ..
+        })
+    ));
+
+    let arr =
+        arr.with_read_options(ArrayReadOptions::default().bool_encoding(BoolEncoding::BitPacked));
+    match arr.get_chunk(&[0]).await.expect("get_chunk") {
+        ZarrVectorValue::VBool(v) => {
+            let mut want = vec![false; 10];
+            want[0] = true;
+            want[1] = true;
+            want[9] = true;
+            assert_eq!(v, want);
+        }
+        other => panic!("expected VBool, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn descriptor_reopens_without_metadata() {
+    let options = ArrayReadOptions::default().max_variable_chunk_bytes(1024);
+    let arr = v2::open(grid_store().await, "grid")
+        .await
+        .expect("open")
+        .with_read_options(options.clone());
+    let json = serde_json::to_string(&arr.descriptor()).unwrap();
+
+    // The worker's store has chunks but no .zarray.
+    let descriptor: ArrayDescriptor = serde_json::from_str(&json).unwrap();
+    let store = grid_store_with_metadata(false).await;
+    assert!(v2::open(store.clone(), "grid").await.is_err());
+    let reopened = UnifiedZarrArray::from_descriptor(store, &descriptor).expect("reopen");
+
+    assert_eq!(reopened.metadata.shape, vec![5, 4]);
+    assert_eq!(reopened.read_options(), &options);
+    assert_eq!(reopened.descriptor(), descriptor);
+    assert_eq!(
+        reopened.load().await.expect("load"),
+        arr.load().await.expect("load")
+    );
+}
+
+#[tokio::test]
+async fn refresh_revalidates_with_the_metadata_etag() {
+    let store = CountingStore::new(grid_store().await);
+    let mut arr = v2::open(store.clone(), "grid").await.expect("open");
+    let etag = arr.metadata_etag().expect("etag").to_string();
+    assert_eq!(
+        arr.descriptor().metadata_etag.as_deref(),
+        Some(etag.as_str())
+    );
+
+    let gets = store.gets();
+    assert!(!arr.refresh().await.unwrap());
+    assert_eq!((store.gets(), store.heads()), (gets, 1));
+
+    // Rewritten with one more row.
+    let mut zarray = arr.descriptor().metadata;
+    zarray["shape"] = serde_json::json!([6, 4]);
+    store
+        .inner
+        .put("grid/.zarray", serde_json::to_vec(&zarray).unwrap().into())
+        .await
+        .unwrap();
+    assert!(arr.refresh().await.unwrap());
+    assert_eq!(arr.metadata.shape, vec![6, 4]);
+    assert_ne!(arr.metadata_etag(), Some(etag.as_str()));
+    assert_eq!(arr.metadata.keys.len(), 6);
+    assert!(!arr.refresh().await.unwrap());
+
+    store
+        .inner
+        .put("grid/.zattrs", b"{\"units\": \"K\"}".to_vec().into())
+        .await
+        .unwrap();
+    arr.load_attributes().await.unwrap();
+    zarray["shape"] = serde_json::json!([5, 4]);
+    store
+        .inner
+        .put("grid/.zarray", serde_json::to_vec(&zarray).unwrap().into())
+        .await
+        .unwrap();
+    assert!(arr.refresh().await.unwrap());
+    assert_eq!(arr.metadata.shape, vec![5, 4]);
+    assert_eq!(
+        arr.metadata.attributes.as_ref().unwrap().get_str("units"),
+        Some("K")
+    );
+}
+
+#[tokio::test]
+async fn refresh_compares_documents_without_an_etag() {
+    let catalog = v2::open(grid_store().await, "grid").await.expect("open");
+    let metadata = catalog.descriptor().metadata;
+    let store = grid_store().await;
+    let mut arr = v2::open_with_metadata(store.clone(), "grid", &metadata)
+        .expect("open")
+        .with_dimension_separator('/')
+        .unwrap();
+    assert!(arr.metadata_etag().is_none());
+    assert!(!arr.refresh().await.unwrap());
+    assert!(arr.metadata_etag().is_some());
+    assert_eq!(arr.metadata.dimension_separator, '/');
+
+    let mut arr = v2::open_with_metadata(store.clone(), "grid", &metadata).expect("open");
+    let mut zarray = metadata;
+    zarray["fill_value"] = serde_json::json!(0);
+    store
+        .put("grid/.zarray", serde_json::to_vec(&zarray).unwrap().into())
+        .await
+        .unwrap();
+    assert!(arr.refresh().await.unwrap());
+    assert_eq!(arr.load().await.unwrap().get(&[2, 3]), Some(&0.0));
+}
+
+#[tokio::test]
+async fn descriptor_rejects_unsupported_formats() {
+    let arr = v2::open(grid_store().await, "grid").await.expect("open");
+    let mut descriptor = arr.descriptor();
+    descriptor.zarr_format = 3;
+    let err = UnifiedZarrArray::from_descriptor(grid_store().await, &descriptor).unwrap_err();
+    assert!(
+        matches!(err, ZarrError::UnsupportedFeature(FeatureId::ZarrFormat(3))),
+        "{err:?}"
+    );
+
+    let mut descriptor = arr.descriptor();
+    descriptor.metadata["dtype"] = serde_json::json!("<q9");
+    assert!(UnifiedZarrArray::from_descriptor(grid_store().await, &descriptor).is_err());
+}
+
+#[tokio::test]
+async fn open_with_metadata_skips_the_zarray_fetch() {
+    let catalog = v2::open(grid_store().await, "grid").await.expect("open");
+    let metadata = catalog.descriptor().metadata;
+
+    let store = CountingStore::new(grid_store_with_metadata(false).await);
+    let arr = v2::open_with_metadata(store.clone(), "grid", &metadata).expect("open");
+    assert_eq!(store.gets(), 0);
+    assert_eq!(arr.metadata.shape, vec![5, 4]);
+    assert_eq!(
+        arr.load().await.expect("load"),
+        catalog.load().await.expect("load")
+    );
+    assert_eq!(store.gets(), 6);
+
+    let mut v3 = metadata.clone();
+    v3["zarr_format"] = 3.into();
+    assert!(matches!(
+        v2::open_with_metadata(store.clone(), "grid", &v3),
+        Err(ZarrError::UnsupportedFeature(FeatureId::ZarrFormat(3)))
+    ));
+    let mut broken = metadata;
+    broken["dtype"] = "<q9".into();
+    assert!(matches!(
+        v2::open_with_metadata(store, "grid", &broken),
+        Err(ZarrError::Metadata(_))
+    ));
+}
+
+#[tokio::test]
+async fn chunks_partition_between_workers() {
+    let arr = v2::open(grid_store().await, "grid").await.expect("open");
+    let parts = arr.partition_chunks(4).expect("partition");
+    let sizes: Vec<usize> = parts.iter().map(Vec::len).collect();
+    assert_eq!(sizes, vec![2, 2, 1, 1]);
+    for (worker, part) in parts.iter().enumerate() {
+        assert_eq!(&arr.chunks_for_worker(worker, 4).unwrap(), part);
+    }
+
+    // Together the workers cover every chunk exactly once.
+    let all: Vec<Vec<usize>> = parts.into_iter().flatten().collect();
+    let want: Vec<Vec<usize>> = (0..3)
+        .flat_map(|i| (0..2).map(move |j| vec![i, j]))
+        .collect();
+    assert_eq!(all, want);
+
+    // Extra workers get nothing.
+    let parts = arr.partition_chunks(8).expect("partition");
+    assert_eq!(parts.iter().filter(|p| p.is_empty()).count(), 2);
+}
+
+#[tokio::test]
+async fn chunk_partition_rejects_bad_worker_counts() {
+    let arr = v2::open(grid_store().await, "grid").await.expect("open");
+    assert!(arr.partition_chunks(0).is_err());
+    assert!(arr.chunks_for_worker(3, 3).is_err());
+    assert!(arr.chunks_for_worker(0, 0).is_err());
+}
+
+#[tokio::test]
+async fn iter_blocks_yields_chunks_in_key_order() {
+    let arr = v2::open(grid_store().await, "grid").await.expect("open");
+    let blocks: Vec<_> = arr.iter_blocks().try_collect().await.expect("iter_blocks");
+    let keys: Vec<Vec<usize>> = blocks.iter().map(|(key, _)| key.clone()).collect();
+    assert_eq!(
+        keys,
+        vec![
+            vec![0, 0],
+            vec![0, 1],
+            vec![1, 0],
+            vec![1, 1],
+            vec![2, 0],
+            vec![2, 1]
+        ]
+    );
+    let (_, missing) = &blocks[3];
+    assert_eq!(missing.to_f64_vec().unwrap(), vec![-1.0; 6]);
+}
+
+#[tokio::test]
+async fn iter_blocks_fetches_read_ahead_chunks_early() {
+    for read_ahead in [0, 2] {
+        let store = CountingStore::new(grid_store().await);
+        let config = SimplezarrConfig::builder().read_ahead(read_ahead).build();
+        let arr = v2::open_with_config(store.clone(), "grid", Arc::new(config))
+            .await
+            .expect("open");
+        let opened = store.gets();
+
+        let mut blocks = std::pin::pin!(arr.iter_blocks());
+        blocks.next().await.unwrap().expect("first block");
+        assert_eq!(
+            store.gets() - opened,
+            1 + read_ahead,
+            "read_ahead {read_ahead}"
+        );
+        let mut count = 1;
+        while let Some(block) = blocks.next().await {
+            block.expect("block");
+            count += 1;
+        }
+        assert_eq!(count, 6);
+        assert_eq!(store.gets() - opened, 6);
+    }
+}
+
+#[tokio::test]
+async fn iter_windows_slides_over_whole_windows() {
+    let store = CountingStore::new(grid_store().await);
+    let config = SimplezarrConfig::builder()
+        .chunk_cache_bytes(1 << 20)
+        .read_ahead(0)
+        .build();
+    let arr = v2::open_with_config(store.clone(), "grid", Arc::new(config))
+        .await
+        .expect("open");
+    let opened = store.gets();
+
+    let windows: Vec<_> = arr
+        .iter_windows(&[2, 2], &[1, 2])
+        .expect("iter_windows")
+        .try_collect()
+        .await
+        .expect("windows");
+    let regions: Vec<Vec<std::ops::Range<usize>>> =
+        windows.iter().map(|(region, _)| region.clone()).collect();
+    assert_eq!(
+        regions,
+        (0..4)
+            .flat_map(|i| [0, 2].map(|j| vec![i..i + 2, j..j + 2]))
+            .collect::<Vec<_>>()
+    );
+    for (region, data) in &windows {
+        assert_eq!(data.shape, vec![2, 2]);
+        let (i, j) = (region[0].start, region[1].start);
+        assert_eq!(
+            data.data,
+            [(i, j), (i, j + 1), (i + 1, j), (i + 1, j + 1)].map(|(i, j)| expected(i, j))
+        );
+    }
+    // Overlapping windows share decoded chunks.
+    assert_eq!(store.gets() - opened, 6);
+
+    assert!(arr.iter_windows(&[6, 1], &[1, 1]).is_err());
+    assert!(arr.iter_windows(&[1, 1], &[1, 0]).is_err());
+    assert!(arr.iter_windows(&[1, 1], &[1]).is_err());
+}
+
+#[tokio::test]
+async fn stream_into_sends_every_chunk() {
+    let arr = v2::open(grid_store().await, "grid").await.expect("open");
+    let (tx, mut rx) = mpsc::channel(2);
+    let sender = {
+        let arr = arr.clone();
+        tokio::spawn(async move { arr.stream_into(tx).await })
+    };
+    let mut received = Vec::new();
+    while let Some((key, chunk)) = rx.recv().await {
+        let want = arr.get_chunk(&key).await.unwrap().to_f64_vec().unwrap();
+        assert_eq!(chunk.to_f64_vec().unwrap(), want, "chunk {key:?}");
+        received.push(key);
+    }
+    sender.await.unwrap().expect("stream");
+    received.sort();
+    assert_eq!(received, arr.partition_chunks(1).unwrap()[0]);
+
+    let (tx, mut rx) = mpsc::channel(8);
+    arr.stream_region_into(&[0..2, 3..4], tx)
+        .await
+        .expect("stream region");
+    assert_eq!(rx.recv().await.map(|(key, _)| key), Some(vec![0, 1]));
+    assert!(rx.recv().await.is_none());
+}
+
+#[tokio::test]
+async fn stream_into_waits_for_the_consumer() {
+    let store = CountingStore::new(grid_store().await);
+    let config = SimplezarrConfig::builder().max_concurrency(1).build();
+    let arr = v2::open_with_config(store.clone(), "grid", Arc::new(config))
+        .await
+        .expect("open");
+    let opened = store.gets();
+
+    let (tx, mut rx) = mpsc::channel(1);
+    let sender = tokio::spawn(async move { arr.stream_into(tx).await });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    // One chunk in the channel and one waiting to be sent.
+    assert_eq!(store.gets() - opened, 2);
+
+    let mut count = 0;
+    while rx.recv().await.is_some() {
+        count += 1;
+    }
+    sender.await.unwrap().expect("stream");
+    assert_eq!(count, 6);
+    assert_eq!(store.gets() - opened, 6);
+}
+
+#[tokio::test]
+async fn stream_into_stops_when_the_receiver_is_dropped() {
+    let arr = v2::open(grid_store().await, "grid").await.expect("open");
+    let (tx, rx) = mpsc::channel(1);
+    drop(rx);
+    arr.stream_into(tx).await.expect("stream");
+}
+
+#[tokio::test]
+async fn failed_chunks_stop_the_read_unless_collecting_errors() {
+    let store = Arc::new(BrokenStore {
+        inner: grid_store().await,
+        broken: vec!["grid/0.1", "grid/2.0"],
+        finished: AtomicUsize::new(0),
+    });
+    let config = SimplezarrConfig::builder().max_retries(0);
+
+    let arr = v2::open_with_config(store.clone(), "grid", Arc::new(config.clone().build()))
+        .await
+        .expect("open");
+    let err = arr.load().await.unwrap_err();
+    assert!(matches!(err, ZarrError::Storage(_)), "{err:?}");
+    // The other fetches were dropped rather than left to finish.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(store.finished.swap(0, Ordering::SeqCst), 0);
+
+    let config = Arc::new(config.fail_fast(false).build());
+    let arr = v2::open_with_config(store.clone(), "grid", config)
+        .await
+        .expect("open");
+    match arr.load().await {
+        Err(ZarrError::ChunkErrors(errors)) => {
+            let mut messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+            messages.sort();
+            assert_eq!(
+                messages,
+                [
+                    "Storage error: grid/0.1 is broken",
+                    "Storage error: grid/2.0 is broken"
+                ]
+            );
+        }
+        other => panic!("expected chunk errors, got {other:?}"),
+    }
+    assert_eq!(store.finished.swap(0, Ordering::SeqCst), 4);
+
+    let (tx, mut rx) = mpsc::channel(8);
+    let err = arr.stream_into(tx).await.unwrap_err();
+    assert!(
+        matches!(err, ZarrError::ChunkErrors(ref e) if e.len() == 2),
+        "{err:?}"
+    );
+    let mut sent = 0;
+    while rx.recv().await.is_some() {
+        sent += 1;
+    }
+    assert_eq!(sent, 4);
+
+    // A single failure is returned as is.
+    let err = arr.read_region(&[0..2, 3..4]).await.unwrap_err();
+    assert!(matches!(err, ZarrError::Storage(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn group_and_aligned_reads_collect_errors_unless_failing_fast() {
+    let inner = grid_store().await;
+    for key in inner.list("grid").await.unwrap() {
+        let value = inner.get(&key).await.unwrap().unwrap();
+        inner
+            .put(&key.replacen("grid", "copy", 1), value)
+            .await
+            .unwrap();
+    }
+    let store = Arc::new(BrokenStore {
+        inner,
+        broken: vec!["grid/0.1", "copy/2.0"],
+        finished: AtomicUsize::new(0),
+    });
+    let count = |err: ZarrError| match err {
+        ZarrError::ChunkErrors(errors) => errors.len(),
+        _ => 1,
+    };
+    for (fail_fast, failures) in [(true, 1), (false, 2)] {
+        let config = SimplezarrConfig::builder()
+            .max_retries(0)
+            .fail_fast(fail_fast)
+            .build();
+        let group =
+            v2::open_group_with_config(store.clone(), "", &["grid", "copy"], Arc::new(config))
+                .await
+                .expect("open");
+        assert_eq!(count(group.load_all().await.unwrap_err()), failures);
+        let options = GroupReadOptions::default();
+        assert_eq!(
+            count(group.load_all_with_options(&options).await.unwrap_err()),
+            failures
+        );
+
+        let arrays = [
+            group.get_array("grid").unwrap(),
+            group.get_array("copy").unwrap(),
+        ];
+        let err = read_region_multi(&arrays, &[0..5, 0..4]).await.unwrap_err();
+        assert_eq!(count(err), failures);
+    }
+}
+
+#[tokio::test]
+async fn zero_length_dimensions_load_as_empty() {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let arr = ArrayBuilder::new([3, 0, 4])
+        .dtype(DataType::Int32)
+        .build(store, "empty")
+        .await
+        .unwrap();
+    assert!(arr.metadata.keys.is_empty());
+    assert_eq!(
+        arr.partition_chunks(2).unwrap(),
+        [Vec::<Vec<usize>>::new(), Vec::new()]
+    );
+
+    let loaded = arr.load().await.unwrap();
+    assert_eq!(loaded.shape, [3, 0, 4]);
+    assert!(loaded.is_empty());
+    let region = arr.read_region_value(&[1..3, 0..0, 0..2]).await.unwrap();
+    assert_eq!(region.shape, [2, 0, 2]);
+    assert!(region.is_empty());
+    assert!(arr.read_region(&[0..3, 0..1, 0..4]).await.is_err());
+    assert!(matches!(
+        arr.get_chunk(&[0, 0, 0]).await,
+        Err(ZarrError::NotFound(_))
+    ));
+}
+
+// ---------------------------------------------------------------------------
+// Decoded-size limits
+// ---------------------------------------------------------------------------
+
+/// Four `<i4` values; small enough that every compressor's output is larger
+/// than the 16 raw bytes.
+fn small_chunk() -> Vec<u8> {
+    [1i32, -2, 3, -4]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect()
+}
+
+#[tokio::test]
+async fn compressed_chunks_larger_than_raw_still_decode() {
+    let raw = small_chunk();
+    let cases = [
+        (
+            serde_json::json!({"id": "gzip", "level": 5}),
+            GzipCodec { level: 5 }.encode(&raw).unwrap(),
+        ),
+        (
+            serde_json::json!({"id": "zlib", "level": 1}),
+            ZlibCodec { level: 1 }.encode(&raw).unwrap(),
+        ),
+        (
+            serde_json::json!({"id": "zstd", "level": 3}),
+            ZstdCodec::new(3).encode(&raw).unwrap(),
+        ),
+        (
+            serde_json::json!({"id": "lz4", "acceleration": 1}),
+            Lz4Codec::default().encode(&raw).unwrap(),
+        ),
+        (
+            serde_json::json!({"id": "snappy"}),
+            SnappyCodec::default().encode(&raw).unwrap(),
+        ),
+        (
+            serde_json::json!({"id": "brotli", "level": 5}),
+            BrotliCodec::default().encode(&raw).unwrap(),
+        ),
+    ];
+    for (compressor, chunk) in cases {
+        assert!(chunk.len() > raw.len(), "{compressor}");
+        let store = single_chunk_store("small", "<i4", 4, compressor.clone(), chunk).await;
+        let arr = v2::open(store, "small").await.expect("open");
+        let data = arr.load().await.expect("load");
+        assert_eq!(data, vec![1.0, -2.0, 3.0, -4.0], "{compressor}");
+    }
+}
+
+#[tokio::test]
+async fn stream_compressors_stop_at_the_expected_size() {
+    // 1 MiB of zeros, where the array expects 16 bytes.
+    let bomb = vec![0u8; 1 << 20];
+    let cases = [
+        (
+            serde_json::json!({"id": "gzip", "level": 9}),
+            GzipCodec { level: 9 }.encode(&bomb).unwrap(),
+        ),
+        (
+            serde_json::json!({"id": "zlib", "level": 9}),
+            ZlibCodec { level: 9 }.encode(&bomb).unwrap(),
+        ),
+        (
+            serde_json::json!({"id": "zstd", "level": 3}),
+            ZstdCodec::new(3).encode(&bomb).unwrap(),
+        ),
+        (
+            serde_json::json!({"id": "brotli"}),
+            BrotliCodec::default().encode(&bomb).unwrap(),
+        ),
+    ];
+    for (compressor, chunk) in cases {
+        let store = single_chunk_store("bomb", "<i4", 4, compressor, chunk).await;
+        let arr = v2::open(store, "bomb").await.expect("open");
+        assert_limit_error(arr.get_chunk(&[0]).await);
+    }
+}
+
+#[tokio::test]
+async fn zstd_window_limits_are_configurable() {
+    // 1 MiB of int32s written with long-distance matching, so the frame
+    // needs a 2^20-byte window.
+    let raw: Vec<u8> = (0..1i32 << 18)
+        .flat_map(|i| (i % 1000).to_le_bytes())
+        .collect();
+    let long = ZstdCodec::new(3)
+        .with_long_distance_matching(true)
+        .with_window_log_max(20);
+    let chunk = long.encode(&raw).unwrap();
+    assert_eq!(long.decode(&chunk).unwrap(), raw);
+    assert_eq!(ZstdCodec::default().decode(&chunk).unwrap(), raw);
+    let err = ZstdCodec::new(3)
+        .with_window_log_max(10)
+        .decode(&chunk)
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::Decode(_)), "{err:?}");
+
+    let compressor = serde_json::json!({"id": "zstd", "level": 3});
+    let store = single_chunk_store("long", "<i4", 1 << 18, compressor, chunk).await;
+    let arr = v2::open(store, "long").await.expect("open");
+    let limited = arr.with_read_options(ArrayReadOptions::default().zstd_window_log_max(10));
+    assert!(limited.get_chunk(&[0]).await.is_err());
+    let relaxed = limited.with_read_options(ArrayReadOptions::default().zstd_window_log_max(31));
+    let values = relaxed.load().await.expect("load");
+    assert_eq!(values[999], 999.0);
+    assert_eq!(values[1000], 0.0);
+}
+
+#[tokio::test]
+async fn oversized_declared_sizes_are_rejected() {
+    // LZ4: the 4-byte prefix claims 1 MiB.
+    let mut lz4 = ((1u32 << 20).to_le_bytes()).to_vec();
+    lz4.extend_from_slice(&[0u8; 8]);
+    let store = single_chunk_store("lz4", "<i4", 4, serde_json::json!({"id": "lz4"}), lz4).await;
+    let arr = v2::open(store, "lz4").await.expect("open");
+    assert_limit_error(arr.get_chunk(&[0]).await);
+
+    // Snappy: the varint length header claims 1 MiB.
+    let snappy = vec![0x80, 0x80, 0x40, 0];
+    let compressor = serde_json::json!({"id": "snappy"});
+    let store = single_chunk_store("snappy", "<i4", 4, compressor, snappy).await;
+    let arr = v2::open(store, "snappy").await.expect("open");
+    assert_limit_error(arr.get_chunk(&[0]).await);
+
+    // Blosc: a valid 16-byte header (version, versionlz, flags, typesize,
+    // nbytes, blocksize, cbytes) declaring 1 MiB.
+    let mut blosc = vec![2u8, 1, 0x01, 4];
+    blosc.extend_from_slice(&(1u32 << 20).to_le_bytes());
+    blosc.extend_from_slice(&(1u32 << 16).to_le_bytes());
+    blosc.extend_from_slice(&32u32.to_le_bytes());
+    blosc.extend_from_slice(&[0u8; 16]);
+    let compressor = serde_json::json!({"id": "blosc", "cname": "lz4", "clevel": 5, "shuffle": 1});
+    let store = single_chunk_store("blosc", "<i4", 4, compressor, blosc).await;
+    let arr = v2::open(store, "blosc").await.expect("open");
+    assert_limit_error(arr.get_chunk(&[0]).await);
+}
+
+#[tokio::test]
+async fn overflowing_chunk_sizes_are_rejected() {
+    let options = ArrayReadOptions::default();
+    let elements = usize::MAX / 4;
+    assert!(matches!(
+        expected_chunk_bytes(DataType::Float64, elements, &options),
+        Err(ZarrError::Metadata(_))
+    ));
+    // Wrapped, the bound would be a small size the chunk could meet.
+    let codecs = [AnyCodec::Zstd(ZstdCodec::new(3))];
+    let pipeline = CodecPipeline::new(&codecs).unwrap();
+    let chunk = ZstdCodec::new(3).encode(&[0u8; 8]).unwrap();
+    let result = pipeline
+        .decode(&chunk, "0", DataType::Float64, elements, &options)
+        .await;
+    assert!(matches!(result, Err(ZarrError::Metadata(_))), "{result:?}");
+}
+
+#[tokio::test]
+async fn variable_size_chunks_use_the_configured_limit() {
+    let chunk = GzipCodec { level: 5 }.encode(&[b'x'; 1000]).unwrap();
+    let compressor = serde_json::json!({"id": "gzip", "level": 5});
+    let store = single_chunk_store("strings", "|S10", 10, compressor, chunk).await;
+    let arr = v2::open(store, "strings").await.expect("open");
+
+    let limited = arr.with_read_options(ArrayReadOptions::default().max_variable_chunk_bytes(100));
+    assert_limit_error(limited.get_chunk(&[0]).await);
+
+    // Within the default limit the chunk decompresses; it then fails only
+    // because string arrays can't be interpreted from raw bytes.
+    let unlimited = limited.with_read_options(ArrayReadOptions::default());
+    match unlimited.get_chunk(&[0]).await {
+        Err(ZarrError::Decode(msg)) => assert!(!msg.contains("limit"), "{msg}"),
+        Err(_) => {}
+        Ok(v) => panic!("object chunk unexpectedly decoded: {v:?}"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Filters
+// ---------------------------------------------------------------------------
+
+async fn filtered_store(
+    dtype: &str,
+    filters: serde_json::Value,
+    chunk: Vec<u8>,
+) -> Arc<ObjectStoreBackend> {
+    let mem = InMemory::new();
+    let zarray = serde_json::json!({
+        "zarr_format": 2,
+        "shape": [3],
+        "chunks": [3],
+        "dtype": dtype,
+        "fill_value": null,
+        "compressor": {"id": "zlib", "level": 1},
+        "filters": filters,
+    });
+    put(&mem, "scaled/.zarray", serde_json::to_vec(&zarray).unwrap()).await;
+    put(
+        &mem,
+        "scaled/0",
+        ZlibCodec { level: 1 }.encode(&chunk).unwrap(),
+    )
+    .await;
+    Arc::new(ObjectStoreBackend::new(Box::new(mem), ""))
+}
+
+#[tokio::test]
+async fn big_endian_fixedscaleoffset_filter() {
+    let stored: Vec<u8> = [-150i16, 0, 2500]
+        .iter()
+        .flat_map(|v| v.to_be_bytes())
+        .collect();
+    let filters = serde_json::json!([{
+        "id": "fixedscaleoffset",
+        "scale": 100,
+        "offset": 10,
+        "dtype": ">f8",
+        "astype": ">i2",
+    }]);
+    let store = filtered_store(">f8", filters, stored).await;
+    let arr = v2::open(store, "scaled").await.expect("open");
+    assert_eq!(arr.load().await.expect("load"), vec![8.5, 10.0, 35.0]);
+}
+
+#[tokio::test]
+async fn unsupported_filters_fail_on_decode() {
+    let filters = serde_json::json!([{"id": "delta", "dtype": "<i4"}]);
+    let store = filtered_store("<i4", filters, vec![0; 12]).await;
+    let array = v2::open(store, "scaled").await.unwrap();
+    match array.get_chunk(&[0]).await {
+        Err(ZarrError::UnsupportedFeature(FeatureId::Filter(id))) => assert_eq!(id, "delta"),
+        other => panic!("expected an unsupported filter, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn group_loads_take_per_array_read_options() {
+    let mem = InMemory::new();
+    let flags = serde_json::json!({
+        "zarr_format": 2,
+        "shape": [10],
+        "chunks": [10],
+        "dtype": "|b1",
+        "fill_value": false,
+        "compressor": null,
+    });
+    put(&mem, "g/flags/.zarray", serde_json::to_vec(&flags).unwrap()).await;
+    put(&mem, "g/flags/0", vec![0b1100_0000, 0b0100_0000]).await;
+    let ints = serde_json::json!({
+        "zarr_format": 2,
+        "shape": [2],
+        "chunks": [2],
+        "dtype": "|u1",
+        "fill_value": 0,
+        "compressor": null,
+    });
+    put(&mem, "g/ints/.zarray", serde_json::to_vec(&ints).unwrap()).await;
+    put(&mem, "g/ints/0", vec![7, 8]).await;
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(mem), ""));
+    let group = v2::open_group(store, "g", &["flags", "ints"])
+        .await
+        .expect("open_group");
+
+    // With the default byte-per-element encoding the packed flags are short.
+    assert!(matches!(
+        group.load_all().await,
+        Err(ZarrError::ChunkSizeMismatch { .. })
+    ));
+
+    let packed = ArrayReadOptions::default().bool_encoding(BoolEncoding::BitPacked);
+    let options = GroupReadOptions::default().with_override("flags", packed);
+    let loaded = group.load_all_with_options(&options).await.expect("load");
+    assert_eq!(loaded.len(), 2);
+    let mut want = vec![0.0; 10];
+    want[0] = 1.0;
+    want[1] = 1.0;
+    want[9] = 1.0;
+    assert_eq!(loaded["flags"].to_f64_vec().unwrap(), want);
+    assert_eq!(loaded["ints"].to_f64_vec().unwrap(), vec![7.0, 8.0]);
+    // The group's own arrays keep their options.
+    assert_eq!(
+        group.get_array("flags").unwrap().read_options(),
+        &ArrayReadOptions::default()
+    );
+
+    let typo = GroupReadOptions::default().with_override("flag", ArrayReadOptions::default());
+    assert!(matches!(
+        group.load_all_with_options(&typo).await,
+        Err(ZarrError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn consolidated_groups_list_arrays_in_order() {
+    let names = ["t10", "t2", "a", "t01", "t1"];
+    let zarray = serde_json::json!({
+        "zarr_format": 2,
+        "shape": [1],
+        "chunks": [1],
+        "dtype": "|u1",
+        "fill_value": 0,
+        "compressor": null,
+    });
+    let metadata: serde_json::Map<String, serde_json::Value> = names
+        .iter()
+        .map(|name| (format!("{name}/.zarray"), zarray.clone()))
+        .collect();
+    let zmetadata = serde_json::json!({"zarr_consolidated_format": 1, "metadata": metadata});
+    let mem = InMemory::new();
+    put(
+        &mem,
+        "g/.zmetadata",
+        serde_json::to_vec(&zmetadata).unwrap(),
+    )
+    .await;
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(mem), ""));
+
+    let mut group = v2::open_group(store, "g", &[]).await.expect("open_group");
+    assert_eq!(group.array_names(), ["a", "t01", "t1", "t10", "t2"]);
+    let loaded = group.load_all().await.unwrap();
+    assert!(loaded.keys().eq(group.array_names()));
+
+    group.sort_array_names(ArrayNameOrder::Natural);
+    assert_eq!(group.array_names(), ["a", "t01", "t1", "t2", "t10"]);
+    group.sort_array_names(ArrayNameOrder::Sorted);
+    assert_eq!(group.array_names(), ["a", "t01", "t1", "t10", "t2"]);
+}
+
+#[tokio::test]
+async fn gzipped_consolidated_metadata_is_read() {
+    let zarray = serde_json::json!({
+        "zarr_format": 2,
+        "shape": [2],
+        "chunks": [2],
+        "dtype": "|u1",
+        "fill_value": 0,
+        "compressor": null,
+    });
+    let zmetadata = serde_json::json!({
+        "zarr_consolidated_format": 1,
+        "metadata": {"x/.zarray": zarray, "x/.zattrs": {"units": "m"}},
+    });
+    let gzipped = GzipCodec::default()
+        .encode(&serde_json::to_vec(&zmetadata).unwrap())
+        .unwrap();
+    let mem = InMemory::new();
+    put(&mem, "g/.zmetadata", gzipped).await;
+    put(&mem, "g/x/0", vec![3, 4]).await;
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(mem), ""));
+
+    let group = v2::open_group(store, "g", &[]).await.expect("open_group");
+    assert!(group.is_consolidated());
+    let x = group.get_array("x").unwrap();
+    assert_eq!(
+        x.metadata.attributes.as_ref().unwrap().get_str("units"),
+        Some("m")
+    );
+    assert_eq!(x.load().await.unwrap().data, vec![3.0, 4.0]);
+
+    let truncated = GzipCodec::default().encode(b"{}").unwrap()[..8].to_vec();
+    assert!(ZarrConsolidatedMetadata::parse(&truncated).is_err());
+
+    let zarr_json = serde_json::json!({
+        "zarr_format": 3,
+        "node_type": "array",
+        "shape": [2],
+        "data_type": "uint8",
+        "chunk_grid": {"name": "regular", "configuration": {"chunk_shape": [2]}},
+        "chunk_key_encoding": {"name": "default"},
+        "fill_value": 0,
+        "codecs": [{"name": "bytes"}],
+    });
+    let gzipped = GzipCodec::default()
+        .encode(&serde_json::to_vec(&zarr_json).unwrap())
+        .unwrap();
+    assert_eq!(ZarrV3Metadata::parse(&gzipped).unwrap().shape, [2]);
+}
@@ -0,0 +1,128 @@
+//! Tests for the numcodecs `lzma` compressor (the `lzma` feature).
+
+use std::sync::Arc;
+
+use object_store::memory::InMemory;
+use object_store::path::Path as ObjectPath;
+use object_store::{ObjectStoreExt, PutPayload};
+use serde_json::json;
+
+use simplezarr::codecs::lzma::{FORMAT_ALONE, FORMAT_RAW, FORMAT_XZ, LzmaCodec};
+use simplezarr::codecs::{codec_to_json, parse_codec};
+use simplezarr::error::ZarrError;
+use simplezarr::store::ObjectStoreBackend;
+use simplezarr::v2;
+
+fn raw() -> Vec<u8> {
+    (0..4096i32).flat_map(|i| (i % 100).to_le_bytes()).collect()
+}
+
+async fn lzma_array(compressor: serde_json::Value, chunk: Vec<u8>) -> simplezarr::UnifiedZarrArray {
+    let mem = InMemory::new();
+    let zarray = json!({
+        "zarr_format": 2,
+        "shape": [4096],
+        "chunks": [4096],
+        "dtype": "<i4",
+        "fill_value": 0,
+        "compressor": compressor,
+        "filters": null,
+    });
+    mem.put(
+        &ObjectPath::from("a/.zarray"),
+        PutPayload::from(serde_json::to_vec(&zarray).unwrap()),
+    )
+    .await
+    .unwrap();
+    mem.put(&ObjectPath::from("a/0"), PutPayload::from(chunk))
+        .await
+        .unwrap();
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(mem), ""));
+    v2::open(store, "a").await.unwrap()
+}
+
+#[test]
+fn xz_and_alone_formats_round_trip() {
+    let raw = raw();
+    for format in [FORMAT_XZ, FORMAT_ALONE] {
+        for check in [-1, 0, 1, 10] {
+            let codec = LzmaCodec {
+                format,
+                check,
+                preset: Some(1),
+                ..LzmaCodec::default()
+            };
+            let encoded = codec.encode(&raw).unwrap();
+            assert!(encoded.len() < raw.len(), "{format}/{check}");
+            assert_eq!(codec.decode(&encoded).unwrap(), raw, "{format}/{check}");
+        }
+    }
+    // The stream header, not the configured format, decides how to decode.
+    let alone = LzmaCodec {
+        format: FORMAT_ALONE,
+        ..LzmaCodec::default()
+    };
+    let encoded = alone.encode(&raw).unwrap();
+    assert_eq!(LzmaCodec::default().decode(&encoded).unwrap(), raw);
+}
+
+#[test]
+fn decodes_stop_at_the_limit() {
+    let encoded = LzmaCodec::default().encode(&vec![0u8; 1 << 20]).unwrap();
+    match LzmaCodec::default().decode_bounded(&encoded, 16) {
+        Err(ZarrError::Decode(msg)) => assert!(msg.contains("limit"), "{msg}"),
+        other => panic!("expected a limit error, got {other:?}"),
+    }
+}
+
+#[test]
+fn raw_streams_and_filter_chains_are_rejected() {
+    let raw_format = LzmaCodec {
+        format: FORMAT_RAW,
+        ..LzmaCodec::default()
+    };
+    assert!(matches!(raw_format.decode(b"x"), Err(ZarrError::Codec(_))));
+    let filtered = LzmaCodec {
+        filters: Some(json!([{"id": 33}])),
+        ..LzmaCodec::default()
+    };
+    assert!(matches!(filtered.encode(b"x"), Err(ZarrError::Codec(_))));
+}
+
+#[test]
+fn v3_configuration_round_trips() {
+    let codec = parse_codec(&json!({
+        "name": "numcodecs.lzma",
+        "configuration": {"format": 2, "check": -1, "preset": 9, "filters": null},
+    }))
+    .unwrap();
+    assert_eq!(
+        codec_to_json(&codec),
+        json!({
+            "name": "numcodecs.lzma",
+            "configuration": {"format": 2, "check": -1, "preset": 9, "filters": null},
+        })
+    );
+}
+
+#[tokio::test]
+async fn v2_lzma_arrays_load() {
+    let raw = raw();
+    let chunk = LzmaCodec::default().encode(&raw).unwrap();
+    let array = lzma_array(
+        json!({"id": "lzma", "format": 1, "check": -1, "preset": null, "filters": null}),
+        chunk,
+    )
+    .await;
+    let values = array.load().await.unwrap();
+    assert_eq!(values.len(), 4096);
+    assert_eq!(values[199], 99.0);
+    assert_eq!(
+        array
+            .metadata
+            .describe()
+            .lines()
+            .find(|l| l.starts_with("Compressor")),
+        Some("Compressor  : LZMA(format=1, check=-1, preset=None)")
+    );
+}
@@ -0,0 +1,162 @@
+//! Tests for reading arrays through chunk reference manifests.
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use object_store::memory::InMemory;
+
+use simplezarr::error::ZarrError;
+use simplezarr::manifest::{ChunkManifest, ChunkRef, ManifestStore};
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+use simplezarr::v2;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn memory() -> ObjectStoreBackend {
+    ObjectStoreBackend::new(Box::new(InMemory::new()), "")
+}
+
+fn int32s(values: &[i32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// A 4x2 `<i4` array with 2x2 chunks: chunk `0.0` is a byte range of a
+/// shared blob, chunk `1.0` a whole object and `.zarray` inline.
+async fn referenced_array() -> ObjectStoreBackend {
+    let objects = memory();
+    let mut blob = b"header".to_vec();
+    blob.extend(int32s(&[1, 2, 3, 4]));
+    blob.extend(b"trailer");
+    objects.put("blobs/commit-1", blob.into()).await.unwrap();
+    objects
+        .put("chunks/b", int32s(&[5, 6, 7, 8]).into())
+        .await
+        .unwrap();
+
+    let zarray = serde_json::json!({
+        "zarr_format": 2,
+        "shape": [4, 2],
+        "chunks": [2, 2],
+        "dtype": "<i4",
+        "fill_value": 0,
+        "order": "C",
+        "compressor": null,
+        "filters": null,
+    });
+    let manifest = serde_json::json!({
+        "version": 1,
+        "refs": {
+            "a/.zarray": zarray.to_string(),
+            "a/0.0": ["blobs/commit-1", 6, 16],
+            "a/1.0": ["chunks/b"],
+        },
+    });
+    objects
+        .put(
+            "manifest.json",
+            serde_json::to_vec(&manifest).unwrap().into(),
+        )
+        .await
+        .unwrap();
+    objects
+}
+
+// ---------------------------------------------------------------------------
+// ChunkManifest
+// ---------------------------------------------------------------------------
+
+#[test]
+fn references_parse_in_every_form() {
+    let manifest = ChunkManifest::parse(
+        br#"{"version": 1, "refs": {
+            ".zgroup": "{\"zarr_format\": 2}",
+            "a/0": "base64:AQID",
+            "a/1": ["objects/x"],
+            "a/2": ["objects/y", 8, 24]
+        }}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        manifest.refs[".zgroup"],
+        ChunkRef::Inline(Bytes::from_static(br#"{"zarr_format": 2}"#))
+    );
+    assert_eq!(
+        manifest.refs["a/0"],
+        ChunkRef::Inline(Bytes::from_static(&[1, 2, 3]))
+    );
+    assert_eq!(manifest.refs["a/1"], ChunkRef::Object("objects/x".into()));
+    assert_eq!(
+        manifest.refs["a/2"],
+        ChunkRef::Range {
+            path: "objects/y".into(),
+            offset: 8,
+            length: 24,
+        }
+    );
+
+    let json = serde_json::to_vec(&manifest.to_json()).unwrap();
+    assert_eq!(ChunkManifest::parse(&json).unwrap(), manifest);
+    // Version 0 manifests are a bare map of references.
+    let bare = ChunkManifest::parse(br#"{"a/1": ["objects/x"]}"#).unwrap();
+    assert_eq!(bare.refs.len(), 1);
+}
+
+#[test]
+fn malformed_references_are_rejected() {
+    for json in [
+        &br#"{"version": 2, "refs": {}}"#[..],
+        br#"{"version": 1}"#,
+        br#"{"version": 1, "refs": {"a/0": ["x", 1]}}"#,
+        br#"{"version": 1, "refs": {"a/0": ["x", -1, 4]}}"#,
+        br#"{"version": 1, "refs": {"a/0": ["x", 18446744073709551615, 1]}}"#,
+        br#"{"version": 1, "refs": {"a/0": "base64:!!"}}"#,
+    ] {
+        let err = ChunkManifest::parse(json).unwrap_err();
+        assert!(matches!(err, ZarrError::Metadata(_)), "{err:?}");
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ManifestStore
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn arrays_load_through_references() {
+    let store = ManifestStore::open(referenced_array().await, "manifest.json")
+        .await
+        .unwrap();
+    assert_eq!(
+        store.list("a").await.unwrap(),
+        ["a/.zarray", "a/0.0", "a/1.0"]
+    );
+    assert_eq!(store.head("a/0.0").await.unwrap().unwrap().size, 16);
+    assert_eq!(
+        store.get_range("a/0.0", 4..8).await.unwrap().as_deref(),
+        Some(&int32s(&[2])[..])
+    );
+
+    let array = v2::open(Arc::new(store), "a").await.unwrap();
+    assert_eq!(
+        array.load().await.unwrap(),
+        vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]
+    );
+}
+
+#[tokio::test]
+async fn dangling_references_are_not_found() {
+    let objects = referenced_array().await;
+    objects.delete("chunks/b").await.unwrap();
+    let store = ManifestStore::open(objects, "manifest.json").await.unwrap();
+    assert_eq!(store.get("a/1.1").await.unwrap(), None);
+    let err = store.get("a/1.0").await.unwrap_err();
+    assert!(matches!(err, ZarrError::NotFound(_)), "{err:?}");
+    assert!(store.put("a/1.1", Bytes::from_static(b"x")).await.is_err());
+
+    let err = ManifestStore::open(memory(), "manifest.json")
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, ZarrError::NotFound(_)), "{err:?}");
+}
@@ -0,0 +1,145 @@
+//! Tests for chunk-at-a-time transforms into a new array.
+
+use std::sync::Arc;
+
+use object_store::memory::InMemory;
+
+use simplezarr::builder::ArrayBuilder;
+use simplezarr::compare::Tolerance;
+use simplezarr::error::ZarrError;
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+use simplezarr::types::{ArrayOrder, DataType, ZarrVectorValue, f64_to_zarr_vector};
+use simplezarr::{ArrayData, UnifiedZarrArray};
+
+fn memory_store() -> Arc<ObjectStoreBackend> {
+    Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""))
+}
+
+/// A 5x7 `int16` array with 2x3 chunks holding `7 * i + j`, so edge chunks
+/// carry padding.
+async fn grid(store: Arc<ObjectStoreBackend>, order: ArrayOrder) -> UnifiedZarrArray {
+    let array = ArrayBuilder::new([5, 7])
+        .chunks([2, 3])
+        .dtype(DataType::Int16)
+        .order(order)
+        .build(store, "src")
+        .await
+        .unwrap();
+    let values: Vec<i16> = match order {
+        ArrayOrder::C => (0..35).collect(),
+        ArrayOrder::F => (0..7)
+            .flat_map(|j| (0..5).map(move |i| 7 * i + j))
+            .collect(),
+    };
+    array
+        .set_region(&array.full_region(), &ZarrVectorValue::VInt16(values))
+        .await
+        .unwrap();
+    array
+}
+
+#[tokio::test]
+async fn chunks_are_transformed_into_a_matching_array() {
+    for order in [ArrayOrder::C, ArrayOrder::F] {
+        let store = memory_store();
+        let src = grid(store.clone(), order).await;
+        let dst = src
+            .map_to(
+                ArrayBuilder::new([5, 7]).dtype(DataType::Float32),
+                store,
+                "dst",
+                |_, block| {
+                    let values = block.data.to_f64_vec()?;
+                    let scaled: Vec<f64> = values.iter().map(|v| v * 0.5 + 1.0).collect();
+                    f64_to_zarr_vector(DataType::Float32, &scaled)
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(dst.metadata.chunk_shape, vec![2, 3]);
+        assert_eq!(dst.metadata.order, order);
+        assert_eq!(dst.metadata.data_type, DataType::Float32);
+
+        let expected: Vec<f64> = (0..35).map(|v| v as f64 * 0.5 + 1.0).collect();
+        let expected = ArrayData::new(
+            ZarrVectorValue::VFloat64(expected),
+            vec![5, 7],
+            ArrayOrder::C,
+        );
+        let comparison = dst
+            .load_value()
+            .await
+            .unwrap()
+            .compare(&expected, &Tolerance::exact())
+            .unwrap();
+        assert!(comparison.is_equal(), "{order:?}: {comparison}");
+    }
+}
+
+#[tokio::test]
+async fn blocks_locate_each_chunk_for_masking() {
+    let store = memory_store();
+    let src = grid(store.clone(), ArrayOrder::C).await;
+    // Keep the left three columns, zeroing the rest (the fill value).
+    let dst = src
+        .map_to(
+            ArrayBuilder::new([5, 7]).dtype(DataType::Int16),
+            store.clone(),
+            "masked",
+            |block, values| {
+                let keep = block[1].end <= 3;
+                let values = values.data.to_f64_vec()?;
+                let masked: Vec<f64> = values.iter().map(|&v| if keep { v } else { 0.0 }).collect();
+                f64_to_zarr_vector(DataType::Int16, &masked)
+            },
+        )
+        .await
+        .unwrap();
+    let data = dst.load_value().await.unwrap().data.to_f64_vec().unwrap();
+    let expected: Vec<f64> = (0..35)
+        .map(|v| if v % 7 < 3 { v as f64 } else { 0.0 })
+        .collect();
+    assert_eq!(data, expected);
+
+    // Chunks that map to the fill value are not stored.
+    assert!(store.head("masked/0.0").await.unwrap().is_some());
+    assert!(store.head("masked/0.1").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn mismatched_shapes_and_types_are_rejected() {
+    let store = memory_store();
+    let src = grid(store.clone(), ArrayOrder::C).await;
+    let err = src
+        .map_to(
+            ArrayBuilder::new([5, 8]),
+            store.clone(),
+            "wide",
+            |_, block| Ok(block.data),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::Metadata(_)), "{err}");
+    assert!(store.head("wide/.zarray").await.unwrap().is_none());
+
+    // The function must return the destination's element type.
+    let err = src
+        .map_to(
+            ArrayBuilder::new([5, 7]).dtype(DataType::Float64),
+            store.clone(),
+            "floats",
+            |_, block| Ok(block.data),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::TypeConversion(_)), "{err}");
+
+    // Errors from the function are passed through.
+    let err = src
+        .map_to(ArrayBuilder::new([5, 7]), store, "failing", |_, _| {
+            Err(ZarrError::Other("bad chunk".into()))
+        })
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("bad chunk"), "{err}");
+}
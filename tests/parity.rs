@@ -0,0 +1,86 @@
+//! Bit-exact parity with zarr-python.
+//!
+//! The fixtures under `tests/fixtures/parity` are written by
+//! `zarrgentest/parity.py` with zarr-python: every data type, compressor,
+//! memory order and byte order, each next to the bytes zarr-python reads
+//! back.  Regenerate them with `cd zarrgentest && uv run python parity.py`.
+//! V3 arrays are read through [`convert_v3_to_v2`], the crate's V3 path.
+//!
+//! The fixtures are not checked in yet, so the test is `#[ignore]`d and
+//! parity is unverified; run it with `cargo test --test parity -- --ignored`
+//! once they are generated.  It fails without them.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use object_store::memory::InMemory;
+use serde_json::Value;
+
+use simplezarr::array::UnifiedZarrArray;
+use simplezarr::convert::{ConvertOptions, convert_v3_to_v2};
+use simplezarr::error::ZarrResult;
+use simplezarr::store::{LocalBackend, ObjectStoreBackend};
+use simplezarr::types::{Endian, zarr_vector_to_bytes};
+use simplezarr::v2;
+
+const FIXTURE_DIR: &str = "tests/fixtures/parity";
+
+/// Open a fixture; V3 arrays are read through a conversion to V2.
+async fn open_case(
+    store: Arc<LocalBackend>,
+    format: u64,
+    path: &str,
+) -> ZarrResult<UnifiedZarrArray> {
+    if format == 2 {
+        return v2::open(store, path).await;
+    }
+    let converted = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    convert_v3_to_v2(
+        store,
+        path,
+        converted.clone(),
+        "a",
+        &ConvertOptions::default(),
+    )
+    .await?;
+    v2::open(converted, "a").await
+}
+
+#[tokio::test]
+#[ignore = "needs tests/fixtures/parity from zarrgentest/parity.py"]
+async fn fixtures_decode_bit_exact() {
+    let manifest = Path::new(FIXTURE_DIR).join("manifest.json");
+    let manifest = std::fs::read(&manifest).unwrap_or_else(|e| {
+        panic!(
+            "no parity fixtures at {}: {e}. Run: cd zarrgentest && uv run python parity.py",
+            manifest.display()
+        )
+    });
+    let manifest: Value = serde_json::from_slice(&manifest).unwrap();
+    let store = Arc::new(LocalBackend::new(FIXTURE_DIR));
+
+    let mut failures = Vec::new();
+    let cases = manifest["cases"].as_array().unwrap();
+    for case in cases {
+        let path = case["path"].as_str().unwrap();
+        let format = case["format"].as_u64().unwrap();
+        let expected =
+            std::fs::read(Path::new(FIXTURE_DIR).join(case["expected"].as_str().unwrap())).unwrap();
+        let decoded = match open_case(store.clone(), format, path).await {
+            Ok(array) => array.load_value().await,
+            Err(e) => Err(e),
+        };
+        match decoded.and_then(|values| zarr_vector_to_bytes(Endian::Little, &values)) {
+            Ok(bytes) if bytes == expected => {}
+            Ok(_) => failures.push(format!("{path}: decoded bytes differ")),
+            Err(e) => failures.push(format!("{path}: {e}")),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {} cases failed:\n{}",
+        failures.len(),
+        cases.len(),
+        failures.join("\n")
+    );
+}
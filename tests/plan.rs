@@ -0,0 +1,129 @@
+//! Tests for reading chunks fetched outside the crate.
+
+use std::sync::Arc;
+
+use object_store::memory::InMemory;
+
+use simplezarr::UnifiedZarrArray;
+use simplezarr::array::decode_chunk_bytes;
+use simplezarr::builder::{ArrayBuilder, gzip};
+use simplezarr::error::ZarrError;
+use simplezarr::options::ArrayReadOptions;
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+use simplezarr::types::{DataType, ZarrVectorValue};
+
+/// A 5x7 `int32` array with 2x3 chunks holding `7 * i + j`, with chunk
+/// `2.2` (the bottom-right corner) never written.
+async fn grid() -> (Arc<ObjectStoreBackend>, UnifiedZarrArray) {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let array = ArrayBuilder::new([5, 7])
+        .chunks([2, 3])
+        .dtype(DataType::Int32)
+        .codec(None)
+        .fill(-1.0)
+        .build(store.clone(), "a")
+        .await
+        .unwrap();
+    array
+        .set_region(&[0..4, 0..7], &ZarrVectorValue::VInt32((0..28).collect()))
+        .await
+        .unwrap();
+    array
+        .set_region(&[4..5, 0..6], &ZarrVectorValue::VInt32((28..34).collect()))
+        .await
+        .unwrap();
+    assert_eq!(store.get("a/2.2").await.unwrap(), None);
+    (store, array)
+}
+
+#[tokio::test]
+async fn plans_map_the_region_onto_chunks() {
+    let (_, array) = grid().await;
+    let plans = array.plan_read(&[1..4, 2..6]).unwrap();
+    let keys: Vec<&str> = plans.iter().map(|p| p.chunk_key.as_str()).collect();
+    assert_eq!(keys, ["0.0", "0.1", "1.0", "1.1"]);
+    assert_eq!(plans.iter().map(|p| p.len()).sum::<usize>(), 3 * 4);
+
+    let first = &plans[0];
+    assert_eq!(first.chunk_index, [0, 0]);
+    assert_eq!(first.path, "a/0.0");
+    assert_eq!(first.chunk_ranges, [1..2, 2..3]);
+    assert_eq!(first.output_ranges, [0..1, 0..1]);
+
+    let last = &plans[3];
+    assert_eq!(last.chunk_ranges, [0..2, 0..3]);
+    assert_eq!(last.output_ranges, [1..3, 1..4]);
+
+    assert!(array.plan_read(&[0..6, 0..7]).is_err());
+    assert!(array.plan_read(&[0..0, 0..7]).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn externally_fetched_chunks_assemble_like_a_read() {
+    let (store, array) = grid().await;
+    let region = [3..5, 1..7];
+    let mut chunks = Vec::new();
+    for plan in array.plan_read(&region).unwrap() {
+        let bytes = store.get(&plan.path).await.unwrap();
+        let chunk = array.decode_planned(&plan, bytes.as_deref()).await.unwrap();
+        chunks.push((plan.chunk_index, chunk));
+    }
+    // The unwritten corner chunk decodes to the fill value.
+    assert_eq!(chunks.len(), 6);
+    let assembled = array.assemble_region(&region, &chunks).unwrap();
+    let read = array.read_region_value(&region).await.unwrap();
+    assert_eq!(assembled.shape, [2, 6]);
+    assert_eq!(
+        assembled.data.to_f64_vec().unwrap(),
+        read.data.to_f64_vec().unwrap()
+    );
+    assert_eq!(assembled.data.to_f64_vec().unwrap()[11], -1.0);
+}
+
+#[tokio::test]
+async fn stored_bytes_decode_from_metadata_alone() {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let array = ArrayBuilder::new([5, 7])
+        .chunks([2, 3])
+        .dtype(DataType::Float32)
+        .codec(gzip(5))
+        .build(store.clone(), "g")
+        .await
+        .unwrap();
+    let values: Vec<f32> = (0..35).map(|v| v as f32 / 2.0).collect();
+    array
+        .set_region(&array.full_region(), &ZarrVectorValue::VFloat32(values))
+        .await
+        .unwrap();
+    let metadata = array.metadata.clone();
+    let options = ArrayReadOptions::default();
+
+    // An edge chunk and an interior one.
+    for key in [[2, 2], [0, 1]] {
+        let raw = store
+            .get(&format!("g/{}.{}", key[0], key[1]))
+            .await
+            .unwrap();
+        let decoded = decode_chunk_bytes(&metadata, &key, raw.as_deref(), &options)
+            .await
+            .unwrap();
+        let read = array.get_chunk(&key).await.unwrap();
+        assert_eq!(
+            decoded.to_f64_vec().unwrap(),
+            read.to_f64_vec().unwrap(),
+            "{key:?}"
+        );
+    }
+    // `None` is a chunk that was never written.
+    let missing = decode_chunk_bytes(&metadata, &[0, 1], None, &options)
+        .await
+        .unwrap();
+    assert_eq!(missing.to_f64_vec().unwrap(), vec![0.0; 6]);
+
+    for key in [&[3, 0][..], &[0]] {
+        let err = decode_chunk_bytes(&metadata, key, None, &options)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ZarrError::NotFound(_)), "{key:?}: {err:?}");
+    }
+}
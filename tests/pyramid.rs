@@ -0,0 +1,175 @@
+//! Tests for writing multi-resolution image pyramids.
+
+use std::sync::Arc;
+
+use object_store::memory::InMemory;
+use serde_json::json;
+
+use simplezarr::builder::ArrayBuilder;
+use simplezarr::error::ZarrError;
+use simplezarr::pyramid::build_pyramid;
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+use simplezarr::types::{ArrayOrder, DataType, ZarrVectorValue, f64_to_zarr_vector};
+use simplezarr::{ArrayData, Tolerance, UnifiedZarrArray};
+
+fn memory_store() -> Arc<ObjectStoreBackend> {
+    Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""))
+}
+
+fn mean(window: &[f64]) -> f64 {
+    window.iter().sum::<f64>() / window.len() as f64
+}
+
+/// A `shape` float32 image with `chunks` holding each element's C-order
+/// position.
+async fn image(
+    store: Arc<ObjectStoreBackend>,
+    shape: &[usize],
+    chunks: &[usize],
+    order: ArrayOrder,
+) -> UnifiedZarrArray {
+    let array = ArrayBuilder::new(shape)
+        .chunks(chunks)
+        .dtype(DataType::Float32)
+        .order(order)
+        .build(store, "raw")
+        .await
+        .unwrap();
+    let n: usize = shape.iter().product();
+    let values: Vec<f64> = match order {
+        ArrayOrder::C => (0..n).map(|v| v as f64).collect(),
+        // Transpose into F order.
+        ArrayOrder::F => {
+            let (rows, cols) = (shape[0], shape[1]);
+            (0..cols)
+                .flat_map(|j| (0..rows).map(move |i| (i * cols + j) as f64))
+                .collect()
+        }
+    };
+    let values = f64_to_zarr_vector(DataType::Float32, &values).unwrap();
+    array
+        .set_region(&array.full_region(), &values)
+        .await
+        .unwrap();
+    array
+}
+
+/// Mean-downsample a C-order `rows x cols` image by 2 on both axes.
+fn reference_level(values: &[f64], rows: usize, cols: usize) -> (Vec<f64>, usize, usize) {
+    let (out_rows, out_cols) = (rows.div_ceil(2), cols.div_ceil(2));
+    let mut out = Vec::new();
+    for i in 0..out_rows {
+        for j in 0..out_cols {
+            let mut window = Vec::new();
+            for y in 2 * i..(2 * i + 2).min(rows) {
+                for x in 2 * j..(2 * j + 2).min(cols) {
+                    window.push(values[y * cols + x]);
+                }
+            }
+            out.push(mean(&window));
+        }
+    }
+    (out, out_rows, out_cols)
+}
+
+#[tokio::test]
+async fn levels_halve_the_image_with_the_window_function() {
+    for order in [ArrayOrder::C, ArrayOrder::F] {
+        let store = memory_store();
+        let raw = image(store.clone(), &[9, 13], &[4, 5], order).await;
+        let group = build_pyramid(&raw, 3, mean, store, "image.ome.zarr")
+            .await
+            .unwrap();
+        assert_eq!(group.array_names(), ["0", "1", "2"]);
+
+        let (mut values, mut rows, mut cols): (Vec<f64>, _, _) =
+            ((0..9 * 13).map(|v| v as f64).collect(), 9, 13);
+        for name in ["0", "1", "2"] {
+            let level = group.get_array(name).unwrap();
+            assert_eq!(level.metadata.shape, vec![rows, cols], "level {name}");
+            assert_eq!(level.metadata.order, order);
+            assert_eq!(level.metadata.data_type, DataType::Float32);
+            let expected = ArrayData::new(
+                ZarrVectorValue::VFloat64(values.clone()),
+                vec![rows, cols],
+                ArrayOrder::C,
+            );
+            let comparison = level
+                .load_value()
+                .await
+                .unwrap()
+                .compare(&expected, &Tolerance::default())
+                .unwrap();
+            assert!(
+                comparison.is_equal(),
+                "{order:?} level {name}: {comparison}"
+            );
+            (values, rows, cols) = reference_level(&values, rows, cols);
+        }
+        // Chunks are clipped to the smaller levels.
+        assert_eq!(
+            group.get_array("2").unwrap().metadata.chunk_shape,
+            vec![3, 4]
+        );
+    }
+}
+
+#[tokio::test]
+async fn group_attributes_follow_ome_ngff() {
+    let store = memory_store();
+    let raw = image(store.clone(), &[2, 8, 8], &[1, 4, 4], ArrayOrder::C).await;
+    let group = build_pyramid(
+        &raw,
+        2,
+        |w| w.iter().copied().fold(f64::MIN, f64::max),
+        store.clone(),
+        "labels",
+    )
+    .await
+    .unwrap();
+    let attrs = group.attributes().unwrap();
+    let multiscales = attrs.get_path("multiscales/0").unwrap();
+    assert_eq!(multiscales["version"], "0.4");
+    assert_eq!(
+        multiscales["axes"],
+        json!([
+            {"name": "z", "type": "space"},
+            {"name": "y", "type": "space"},
+            {"name": "x", "type": "space"},
+        ])
+    );
+    assert_eq!(
+        multiscales["datasets"],
+        json!([
+            {"path": "0", "coordinateTransformations": [{"type": "scale", "scale": [1.0, 1.0, 1.0]}]},
+            {"path": "1", "coordinateTransformations": [{"type": "scale", "scale": [1.0, 2.0, 2.0]}]},
+        ])
+    );
+    let stored = store.get("labels/.zattrs").await.unwrap().unwrap();
+    let stored: serde_json::Value = serde_json::from_slice(&stored).unwrap();
+    assert_eq!(&stored["multiscales"][0], multiscales);
+    assert!(store.head("labels/.zgroup").await.unwrap().is_some());
+
+    // Only y and x are halved; the max keeps label values intact.
+    let level = group.get_array("1").unwrap();
+    assert_eq!(level.metadata.shape, vec![2, 4, 4]);
+    let values = level.load_value().await.unwrap().data.to_f64_vec().unwrap();
+    assert_eq!(values[0], 9.0);
+    assert_eq!(values[16], 64.0 + 9.0);
+}
+
+#[tokio::test]
+async fn unsupported_pyramids_are_rejected() {
+    let store = memory_store();
+    let line = ArrayBuilder::new([10])
+        .build(store.clone(), "line")
+        .await
+        .unwrap();
+    let err = build_pyramid(&line, 2, mean, store.clone(), "p")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::Metadata(_)), "{err}");
+
+    let raw = image(store.clone(), &[4, 4], &[2, 2], ArrayOrder::C).await;
+    assert!(build_pyramid(&raw, 0, mean, store, "p").await.is_err());
+}
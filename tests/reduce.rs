@@ -0,0 +1,132 @@
+//! Tests for chunk-at-a-time reductions.
+
+use std::sync::Arc;
+
+use object_store::memory::InMemory;
+
+use simplezarr::UnifiedZarrArray;
+use simplezarr::builder::ArrayBuilder;
+use simplezarr::error::ZarrError;
+use simplezarr::store::ObjectStoreBackend;
+use simplezarr::types::{ArrayOrder, DataType, ZarrVectorValue};
+
+/// A 5x7 `int32` array with 2x3 chunks holding `7 * i + j`, so edge chunks
+/// carry padding.
+async fn grid(order: ArrayOrder) -> UnifiedZarrArray {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let array = ArrayBuilder::new([5, 7])
+        .chunks([2, 3])
+        .dtype(DataType::Int32)
+        .codec(None)
+        .order(order)
+        .build(store, "a")
+        .await
+        .unwrap();
+    let values: Vec<i32> = match order {
+        ArrayOrder::C => (0..35).collect(),
+        ArrayOrder::F => (0..7)
+            .flat_map(|j| (0..5).map(move |i| 7 * i + j))
+            .collect(),
+    };
+    array
+        .set_region(&array.full_region(), &ZarrVectorValue::VInt32(values))
+        .await
+        .unwrap();
+    array
+}
+
+fn values(block: &simplezarr::ArrayData<ZarrVectorValue>) -> Vec<f64> {
+    block.data.to_f64_vec().unwrap()
+}
+
+#[tokio::test]
+async fn partials_cover_every_element_once() {
+    let array = grid(ArrayOrder::C).await;
+    let (sum, count, chunks) = array
+        .reduce(
+            (0.0, 0, 0),
+            |(sum, count, chunks), _, block| {
+                let v = values(&block);
+                (sum + v.iter().sum::<f64>(), count + v.len(), chunks + 1)
+            },
+            |a, b| (a.0 + b.0, a.1 + b.1, a.2 + b.2),
+        )
+        .await
+        .unwrap();
+    assert_eq!(sum, (0..35).sum::<i32>() as f64);
+    assert_eq!(count, 35);
+    assert_eq!(chunks, 9);
+
+    let (min, max) = array
+        .reduce(
+            (f64::INFINITY, f64::NEG_INFINITY),
+            |(min, max), _, block| {
+                values(&block)
+                    .into_iter()
+                    .fold((min, max), |(lo, hi), x| (lo.min(x), hi.max(x)))
+            },
+            |a, b| (a.0.min(b.0), a.1.max(b.1)),
+        )
+        .await
+        .unwrap();
+    assert_eq!((min, max), (0.0, 34.0));
+}
+
+#[tokio::test]
+async fn blocks_are_trimmed_to_the_region() {
+    for order in [ArrayOrder::C, ArrayOrder::F] {
+        let array = grid(order).await;
+        let mut blocks = array
+            .reduce_region(
+                &[1..4, 2..6],
+                Vec::new(),
+                move |mut seen, region, block| {
+                    assert_eq!(block.shape, [region[0].len(), region[1].len()]);
+                    for i in region[0].clone() {
+                        for j in region[1].clone() {
+                            let local = [i - region[0].start, j - region[1].start];
+                            let value = values(&block)[match order {
+                                ArrayOrder::C => local[0] * block.shape[1] + local[1],
+                                ArrayOrder::F => local[1] * block.shape[0] + local[0],
+                            }];
+                            assert_eq!(value, (7 * i + j) as f64);
+                        }
+                    }
+                    seen.push(region.to_vec());
+                    seen
+                },
+                |mut a, b| {
+                    a.extend(b);
+                    a
+                },
+            )
+            .await
+            .unwrap();
+        blocks.sort_by_key(|r| (r[0].start, r[1].start));
+        assert_eq!(
+            blocks,
+            [
+                vec![1..2, 2..3],
+                vec![1..2, 3..6],
+                vec![2..4, 2..3],
+                vec![2..4, 3..6],
+            ]
+        );
+    }
+}
+
+#[tokio::test]
+async fn empty_regions_give_init_and_bad_ones_fail() {
+    let array = grid(ArrayOrder::C).await;
+    let total = array
+        .reduce_region(&[2..2, 0..7], 42, |acc, _, _| acc + 1, |a, b| a + b)
+        .await
+        .unwrap();
+    assert_eq!(total, 42);
+
+    let err = array
+        .reduce_region(&[0..6, 0..7], 0, |acc, _, _| acc, |a, b| a + b)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::Other(_)), "{err:?}");
+}
@@ -0,0 +1,422 @@
+//! URL-based backends against a local HTTP server standing in for S3,
+//! MinIO or a CDN.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use simplezarr::error::ZarrError;
+use simplezarr::stac::ZarrAsset;
+use simplezarr::store::{ObjectStoreBackend, PresignedUrlBackend, StorageBackend, UrlOptions};
+use simplezarr::v2;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// One request seen by [`Server`]: the request target, whether it carried
+/// an `Authorization` header, and its headers by lowercase name.
+#[derive(Debug, Clone)]
+struct Request {
+    target: String,
+    authorized: bool,
+    headers: HashMap<String, String>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+/// Minimal HTTP/1.1 server answering `GET`s from a fixed map of request
+/// targets (path plus query); anything else is a 404.  With a token, requests
+/// whose `Authorization` header differs get a 403.
+struct Server {
+    base: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl Server {
+    fn start(files: HashMap<String, Vec<u8>>) -> Self {
+        Self::start_with_token(files, None)
+    }
+
+    fn start_with_token(files: HashMap<String, Vec<u8>>, token: Option<&str>) -> Self {
+        let token = token.map(str::to_string);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let target = line.split_whitespace().nth(1).unwrap_or("").to_string();
+                let mut headers = HashMap::new();
+                loop {
+                    let mut header = String::new();
+                    if reader.read_line(&mut header).unwrap() == 0 || header == "\r\n" {
+                        break;
+                    }
+                    if let Some((name, value)) = header.split_once(':') {
+                        headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+                    }
+                }
+                let authorized = headers.contains_key("authorization");
+                let (status, body) = match files.get(&target) {
+                    _ if token.is_some() && headers.get("authorization") != token.as_ref() => {
+                        ("403 Forbidden", Vec::new())
+                    }
+                    Some(body) => ("200 OK", body.clone()),
+                    None => ("404 Not Found", Vec::new()),
+                };
+                seen.lock().unwrap().push(Request {
+                    target,
+                    authorized,
+                    headers,
+                });
+                let head = format!(
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                let _ = stream.write_all(head.as_bytes());
+                let _ = stream.write_all(&body);
+            }
+        });
+        Self { base, requests }
+    }
+
+    fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// A 2x2 `<i4` array holding 1..=4 in one chunk, served under `prefix`
+/// with `query` appended to every target.
+fn array_files(prefix: &str, query: &str) -> HashMap<String, Vec<u8>> {
+    let zarray = serde_json::json!({
+        "zarr_format": 2,
+        "shape": [2, 2],
+        "chunks": [2, 2],
+        "dtype": "<i4",
+        "fill_value": 0,
+        "order": "C",
+        "compressor": null,
+        "filters": null,
+    });
+    let chunk: Vec<u8> = (1i32..=4).flat_map(|v| v.to_le_bytes()).collect();
+    HashMap::from([
+        (
+            format!("{prefix}/arr/.zarray{query}"),
+            serde_json::to_vec(&zarray).unwrap(),
+        ),
+        (format!("{prefix}/arr/0.0{query}"), chunk),
+    ])
+}
+
+// ---------------------------------------------------------------------------
+// ObjectStoreBackend::from_url
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn anonymous_s3_reads_through_a_custom_endpoint() {
+    let server = Server::start(array_files("/bucket/data.zarr", ""));
+    let options = UrlOptions {
+        anonymous: true,
+        endpoint: Some(server.base.clone()),
+        ..UrlOptions::default()
+    };
+    let store = ObjectStoreBackend::from_url("s3://bucket/data.zarr", &options).unwrap();
+    let arr = v2::open(Arc::new(store), "arr").await.expect("open");
+    assert_eq!(arr.load().await.unwrap(), vec![1.0, 2.0, 3.0, 4.0]);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2, "{requests:?}");
+    assert!(requests.iter().all(|r| !r.authorized), "{requests:?}");
+}
+
+#[tokio::test]
+async fn http_urls_read_relative_to_the_url() {
+    let server = Server::start(array_files("/data.zarr", ""));
+    let url = format!("{}/data.zarr", server.base);
+    let store = ObjectStoreBackend::from_url(&url, &UrlOptions::default()).unwrap();
+    let arr = v2::open(Arc::new(store), "arr").await.expect("open");
+    assert_eq!(arr.load().await.unwrap(), vec![1.0, 2.0, 3.0, 4.0]);
+}
+
+#[tokio::test]
+async fn url_options_add_headers_to_every_request() {
+    let server = Server::start(array_files("/data.zarr", ""));
+    let url = format!("{}/data.zarr", server.base);
+    let options = UrlOptions::default()
+        .user_agent("portal-client/1.0")
+        .header("X-Request-Id", "run-42");
+    let store = ObjectStoreBackend::from_url(&url, &options).unwrap();
+    let arr = v2::open(Arc::new(store), "arr").await.expect("open");
+    assert_eq!(arr.load().await.unwrap(), vec![1.0, 2.0, 3.0, 4.0]);
+
+    let server = Server::start(array_files("/bucket/data.zarr", ""));
+    let options = UrlOptions {
+        anonymous: true,
+        endpoint: Some(server.base.clone()),
+        ..options.header("User-Agent", "ignored")
+    };
+    let store = ObjectStoreBackend::from_url("s3://bucket/data.zarr", &options).unwrap();
+    let arr = v2::open(Arc::new(store), "arr").await.expect("open");
+    assert_eq!(arr.load().await.unwrap(), vec![1.0, 2.0, 3.0, 4.0]);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2, "{requests:?}");
+    for request in requests {
+        assert_eq!(request.header("user-agent"), Some("portal-client/1.0"));
+        assert_eq!(request.header("x-request-id"), Some("run-42"));
+    }
+
+    let options = UrlOptions::default().header("Bad Header", "x");
+    match ObjectStoreBackend::from_url(&url, &options) {
+        Err(ZarrError::Storage(msg)) => assert!(msg.contains("Bad Header"), "{msg}"),
+        other => panic!("expected a storage error, got {:?}", other.err()),
+    }
+}
+
+#[tokio::test]
+async fn from_url_rejects_bad_urls() {
+    for (url, options) in [
+        ("data.zarr", UrlOptions::default()),
+        ("ftp://host/data.zarr", UrlOptions::default()),
+        (
+            "https://host/data.zarr",
+            UrlOptions {
+                endpoint: Some("http://localhost:9000".into()),
+                ..UrlOptions::default()
+            },
+        ),
+    ] {
+        match ObjectStoreBackend::from_url(url, &options) {
+            Err(ZarrError::Storage(msg)) => assert!(msg.contains(url), "{msg}"),
+            other => panic!("{url}: expected a storage error, got {:?}", other.err()),
+        }
+    }
+    let memory = ObjectStoreBackend::from_url("memory://", &UrlOptions::default()).unwrap();
+    assert_eq!(memory.get("missing").await.unwrap(), None);
+}
+
+// ---------------------------------------------------------------------------
+// Credential refresh
+// ---------------------------------------------------------------------------
+
+/// An HTTP store under `url` sending `Authorization: token`.
+fn http_store(url: &str, token: &str) -> Box<dyn object_store::ObjectStore> {
+    let headers = reqwest::header::HeaderMap::from_iter([(
+        reqwest::header::AUTHORIZATION,
+        token.parse().unwrap(),
+    )]);
+    let store = object_store::http::HttpBuilder::new()
+        .with_url(url)
+        .with_client_options(
+            object_store::ClientOptions::new()
+                .with_allow_http(true)
+                .with_default_headers(headers),
+        )
+        .build()
+        .unwrap();
+    Box::new(store)
+}
+
+#[tokio::test]
+async fn rejected_requests_refresh_credentials_once() {
+    let server = Server::start_with_token(array_files("/data.zarr", ""), Some("Bearer fresh"));
+    let url = format!("{}/data.zarr", server.base);
+    let refreshes = Arc::new(AtomicUsize::new(0));
+    let store = ObjectStoreBackend::new(http_store(&url, "Bearer stale"), "")
+        .with_credential_refresh({
+            let url = url.clone();
+            let refreshes = refreshes.clone();
+            move || {
+                refreshes.fetch_add(1, Ordering::SeqCst);
+                let store = http_store(&url, "Bearer fresh");
+                async move { Ok(store) }
+            }
+        });
+    let arr = v2::open(Arc::new(store), "arr").await.expect("open");
+    assert_eq!(arr.load().await.unwrap(), vec![1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+
+    let statuses: Vec<bool> = server
+        .requests()
+        .iter()
+        .map(|r| r.header("authorization") == Some("Bearer fresh"))
+        .collect();
+    assert_eq!(statuses, [false, true, true]);
+}
+
+#[tokio::test]
+async fn failed_refreshes_fail_the_request() {
+    let server = Server::start_with_token(array_files("/data.zarr", ""), Some("Bearer fresh"));
+    let url = format!("{}/data.zarr", server.base);
+
+    let store = ObjectStoreBackend::new(http_store(&url, "Bearer stale"), "");
+    match store.get("arr/.zarray").await {
+        Err(ZarrError::Storage(msg)) => assert!(msg.contains("arr/.zarray"), "{msg}"),
+        other => panic!("expected a storage error, got {other:?}"),
+    }
+
+    let store = ObjectStoreBackend::new(http_store(&url, "Bearer stale"), "")
+        .with_credential_refresh(|| async {
+            Err(ZarrError::Storage("token endpoint unavailable".into()))
+        });
+    match store.get("arr/.zarray").await {
+        Err(ZarrError::Storage(msg)) => {
+            assert!(msg.contains("token endpoint unavailable"), "{msg}")
+        }
+        other => panic!("expected a storage error, got {other:?}"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// PresignedUrlBackend
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn presigned_templates_fetch_every_key() {
+    let server = Server::start(array_files("/signed", "?sig=abc"));
+    let template = format!("{}/signed/{{key}}?sig=abc", server.base);
+    let store = PresignedUrlBackend::new(&template).unwrap();
+    assert_eq!(
+        store.url_for("arr/0.0"),
+        format!("{}/signed/arr/0.0?sig=abc", server.base)
+    );
+    let arr = v2::open(Arc::new(store.clone()), "arr")
+        .await
+        .expect("open");
+    assert_eq!(arr.load().await.unwrap(), vec![1.0, 2.0, 3.0, 4.0]);
+
+    assert_eq!(store.get("arr/1.1").await.unwrap(), None);
+    let requests = server.requests();
+    assert!(
+        requests.iter().all(|r| r.target.ends_with("?sig=abc")),
+        "{requests:?}"
+    );
+    assert_eq!(requests.last().unwrap().target, "/signed/arr/1.1?sig=abc");
+    assert!(matches!(
+        store.list("arr").await,
+        Err(ZarrError::Storage(_))
+    ));
+}
+
+#[tokio::test]
+async fn presigned_requests_carry_custom_headers() {
+    let server = Server::start(array_files("/signed", ""));
+    let store = PresignedUrlBackend::new(format!("{}/signed/{{key}}", server.base))
+        .unwrap()
+        .user_agent("portal-client/1.0")
+        .unwrap()
+        .header("Authorization", "Bearer abc")
+        .unwrap();
+    let arr = v2::open(Arc::new(store), "arr").await.expect("open");
+    assert_eq!(arr.load().await.unwrap(), vec![1.0, 2.0, 3.0, 4.0]);
+    for request in server.requests() {
+        assert!(request.authorized, "{request:?}");
+        assert_eq!(request.header("authorization"), Some("Bearer abc"));
+        assert_eq!(request.header("user-agent"), Some("portal-client/1.0"));
+    }
+
+    let store = PresignedUrlBackend::new("https://host/{key}").unwrap();
+    assert!(matches!(
+        store.header("X-Token", "line\nbreak"),
+        Err(ZarrError::Storage(_))
+    ));
+}
+
+#[test]
+fn presigned_templates_need_a_key_placeholder() {
+    let err = PresignedUrlBackend::new("https://host/data.zarr?sig=abc").unwrap_err();
+    assert!(err.to_string().contains("{key}"), "{err}");
+}
+
+// ---------------------------------------------------------------------------
+// STAC assets
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn stac_zarr_assets_open_with_their_storage_options() {
+    let server = Server::start(array_files("/bucket/data.zarr", ""));
+    let item = serde_json::json!({
+        "type": "Feature",
+        "assets": {
+            "thumbnail": {"href": "s3://bucket/thumb.png", "type": "image/png"},
+            "zarr": {
+                "href": "s3://bucket/data.zarr/arr",
+                "type": "application/vnd+zarr",
+                "xarray:storage_options": {
+                    "anon": true,
+                    "client_kwargs": {
+                        "endpoint_url": server.base,
+                        "headers": {"X-Portal-Token": "t0k"},
+                    },
+                },
+            },
+        },
+    });
+    let asset = ZarrAsset::from_item(&item, None).unwrap();
+    assert_eq!(asset.key, "zarr");
+    assert!(asset.options.anonymous);
+    assert_eq!(
+        asset.options.endpoint.as_deref(),
+        Some(server.base.as_str())
+    );
+    let arr = asset.open_array().await.expect("open");
+    assert_eq!(arr.load().await.unwrap(), vec![1.0, 2.0, 3.0, 4.0]);
+    let requests = server.requests();
+    assert!(requests.iter().all(|r| !r.authorized), "{requests:?}");
+    assert!(
+        requests
+            .iter()
+            .all(|r| r.header("x-portal-token") == Some("t0k")),
+        "{requests:?}"
+    );
+}
+
+#[tokio::test]
+async fn stac_relative_hrefs_resolve_against_the_self_link() {
+    let server = Server::start(array_files("/items/data.zarr", ""));
+    let item = serde_json::json!({
+        "links": [{"rel": "self", "href": format!("{}/items/item.json", server.base)}],
+        "assets": {"data": {"href": "./data.zarr/arr"}},
+    });
+    let asset = ZarrAsset::from_item(&item, Some("data")).unwrap();
+    assert_eq!(asset.href, format!("{}/items/data.zarr/arr", server.base));
+    let arr = asset.open_array().await.expect("open");
+    assert_eq!(arr.load().await.unwrap(), vec![1.0, 2.0, 3.0, 4.0]);
+
+    // Without a media type, a `.zarr` href marks the asset.
+    let item = serde_json::json!({"assets": {"cube": {"href": "https://host/cube.zarr/"}}});
+    assert_eq!(ZarrAsset::from_item(&item, None).unwrap().key, "cube");
+}
+
+#[test]
+fn stac_items_without_a_zarr_asset_are_rejected() {
+    let item = serde_json::json!({"assets": {"cog": {"href": "https://host/b1.tif"}}});
+    assert!(matches!(
+        ZarrAsset::from_item(&item, None),
+        Err(ZarrError::NotFound(_))
+    ));
+    assert!(matches!(
+        ZarrAsset::from_item(&item, Some("zarr")),
+        Err(ZarrError::NotFound(_))
+    ));
+    let relative = serde_json::json!({"assets": {"z": {"href": "data.zarr"}}});
+    assert!(matches!(
+        ZarrAsset::from_item(&relative, None),
+        Err(ZarrError::Metadata(_))
+    ));
+    assert!(matches!(
+        ZarrAsset::from_item(&serde_json::json!({}), None),
+        Err(ZarrError::Metadata(_))
+    ));
+}
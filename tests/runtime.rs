@@ -0,0 +1,307 @@
+//! The crate must work without a tokio runtime.  Everything here runs under
+//! `futures::executor::block_on`, as an async-std or smol user would.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::executor::block_on;
+
+use simplezarr::codecs::zlib::ZlibCodec;
+use simplezarr::config::{RetryPolicy, SimplezarrConfig};
+use simplezarr::error::{ZarrError, ZarrResult};
+use simplezarr::runtime;
+use simplezarr::store::{LocalBackend, StorageBackend, get_with_retry};
+use simplezarr::v2;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// A fresh directory holding a 4x4 zlib-compressed `<i2` array `a` and an
+/// uncompressed `<f8` array `b`, both in 2x2 chunks, with no `.zmetadata`.
+fn group_dir(name: &str) -> PathBuf {
+    let root =
+        std::env::temp_dir().join(format!("simplezarr-runtime-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    for (array, dtype, compressor) in [
+        ("a", "<i2", serde_json::json!({"id": "zlib", "level": 1})),
+        ("b", "<f8", serde_json::Value::Null),
+    ] {
+        let dir = root.join(array);
+        std::fs::create_dir_all(&dir).unwrap();
+        let zarray = serde_json::json!({
+            "zarr_format": 2,
+            "shape": [4, 4],
+            "chunks": [2, 2],
+            "dtype": dtype,
+            "fill_value": 0,
+            "order": "C",
+            "compressor": compressor,
+            "filters": null,
+        });
+        std::fs::write(dir.join(".zarray"), serde_json::to_vec(&zarray).unwrap()).unwrap();
+        for ci in 0..2usize {
+            for cj in 0..2usize {
+                let values =
+                    (0..2).flat_map(|li| (0..2).map(move |lj| (ci * 2 + li) * 4 + cj * 2 + lj));
+                let bytes: Vec<u8> = if array == "a" {
+                    let raw: Vec<u8> = values.flat_map(|v| (v as i16).to_le_bytes()).collect();
+                    ZlibCodec { level: 1 }.encode(&raw).unwrap()
+                } else {
+                    values.flat_map(|v| (v as f64).to_le_bytes()).collect()
+                };
+                std::fs::write(dir.join(format!("{ci}.{cj}")), bytes).unwrap();
+            }
+        }
+    }
+    root
+}
+
+/// Backend whose reads never complete.
+struct HangingBackend;
+
+#[async_trait]
+impl StorageBackend for HangingBackend {
+    async fn get(&self, _path: &str) -> ZarrResult<Option<Bytes>> {
+        std::future::pending().await
+    }
+
+    async fn list(&self, _prefix: &str) -> ZarrResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        format!("{base}/{segment}")
+    }
+}
+
+/// [`LocalBackend`] that records which thread its blocking reads ran on.
+struct ThreadRecordingStore {
+    inner: LocalBackend,
+    threads: Mutex<Vec<ThreadId>>,
+}
+
+#[async_trait]
+impl StorageBackend for ThreadRecordingStore {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        let thread = runtime::spawn_blocking(|| std::thread::current().id()).await?;
+        self.threads.lock().unwrap().push(thread);
+        self.inner.get(path).await
+    }
+
+    async fn list(&self, prefix: &str) -> ZarrResult<Vec<String>> {
+        self.inner.list(prefix).await
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        self.inner.join(base, segment)
+    }
+}
+
+/// [`LocalBackend`] whose blocking read of chunk `1.0` panics, counting
+/// chunk reads.
+struct PanickingStore {
+    inner: LocalBackend,
+    gets: Mutex<usize>,
+}
+
+#[async_trait]
+impl StorageBackend for PanickingStore {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        if !path.ends_with(".zarray") {
+            *self.gets.lock().unwrap() += 1;
+        }
+        if path.ends_with("/1.0") {
+            runtime::spawn_blocking(|| panic!("corrupt chunk")).await?;
+        }
+        self.inner.get(path).await
+    }
+
+    async fn list(&self, prefix: &str) -> ZarrResult<Vec<String>> {
+        self.inner.list(prefix).await
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        self.inner.join(base, segment)
+    }
+}
+
+/// [`LocalBackend`] whose chunk reads never complete, counting how many
+/// were started.
+struct StallingStore {
+    inner: LocalBackend,
+    chunk_gets: AtomicUsize,
+}
+
+#[async_trait]
+impl StorageBackend for StallingStore {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        if path.ends_with(".zarray") {
+            return self.inner.get(path).await;
+        }
+        self.chunk_gets.fetch_add(1, Ordering::SeqCst);
+        std::future::pending().await
+    }
+
+    async fn list(&self, prefix: &str) -> ZarrResult<Vec<String>> {
+        self.inner.list(prefix).await
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        self.inner.join(base, segment)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn arrays_open_and_load_without_tokio() {
+    let root = group_dir("array");
+    let store = Arc::new(LocalBackend::new(&root));
+    let array = block_on(v2::open(store, "a")).unwrap();
+    let expected: Vec<f64> = (0..16).map(f64::from).collect();
+    assert_eq!(block_on(array.load()).unwrap(), expected);
+    assert_eq!(
+        block_on(array.read_region(&[1..3, 2..4])).unwrap(),
+        vec![6.0, 7.0, 10.0, 11.0]
+    );
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn groups_open_without_tokio() {
+    let root = group_dir("group");
+    let store = Arc::new(LocalBackend::new(&root));
+    let group = block_on(v2::open_group(store, "", &["a", "b"])).unwrap();
+    assert!(!group.metadata.consolidated);
+    assert_eq!(group.arrays.len(), 2);
+    let b = block_on(group.arrays["b"].load()).unwrap();
+    assert_eq!(b[5], 5.0);
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn request_timeouts_fire_without_tokio() {
+    let config = SimplezarrConfig {
+        retry: RetryPolicy {
+            max_retries: 1,
+            initial_backoff: Duration::from_millis(1),
+            ..RetryPolicy::default()
+        },
+        request_timeout: Some(Duration::from_millis(20)),
+        ..SimplezarrConfig::default()
+    };
+    let started = Instant::now();
+    let err = block_on(get_with_retry(&HangingBackend, "k", &config)).unwrap_err();
+    assert!(
+        matches!(err, ZarrError::Storage(ref m) if m.contains("Timed out")),
+        "{err:?}"
+    );
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn spawn_blocking_runs_outside_and_inside_tokio() {
+    let value = block_on(runtime::spawn_blocking(|| 6 * 7)).unwrap();
+    assert_eq!(value, 42);
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let value = rt.block_on(runtime::spawn_blocking(|| "pooled")).unwrap();
+    assert_eq!(value, "pooled");
+
+    // Panics resume in the caller, as if the closure had run inline.
+    let panic =
+        std::panic::catch_unwind(|| block_on(runtime::spawn_blocking::<(), _>(|| panic!("boom"))))
+            .unwrap_err();
+    assert_eq!(panic.downcast_ref::<&str>(), Some(&"boom"));
+}
+
+#[test]
+fn panicking_chunks_fail_with_their_key() {
+    let root = group_dir("panic");
+    let store = Arc::new(PanickingStore {
+        inner: LocalBackend::new(&root),
+        gets: Mutex::new(0),
+    });
+    let config = Arc::new(SimplezarrConfig::builder().max_concurrency(1).build());
+    let array = block_on(v2::open_with_config(store.clone(), "a", config)).unwrap();
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+
+    for how in ["thread", "tokio", "inline"] {
+        let result = match how {
+            "thread" => block_on(array.load()),
+            "tokio" => rt.block_on(array.load()),
+            _ => array.load_sync(),
+        };
+        match result {
+            Err(ZarrError::Decode(msg)) => {
+                assert!(
+                    msg.contains("Chunk 1.0") && msg.contains("corrupt"),
+                    "{how}: {msg}"
+                )
+            }
+            other => panic!("{how}: expected a decode error, got {other:?}"),
+        }
+        // Chunks are read in key order one at a time, so the last one is
+        // never fetched once the third has failed.
+        assert_eq!(std::mem::take(&mut *store.gets.lock().unwrap()), 3, "{how}");
+    }
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn dropped_loads_stop_fetching() {
+    let root = group_dir("dropped");
+    let store = Arc::new(StallingStore {
+        inner: LocalBackend::new(&root),
+        chunk_gets: AtomicUsize::new(0),
+    });
+    let config = Arc::new(SimplezarrConfig::builder().max_concurrency(2).build());
+    let array = block_on(v2::open_with_config(store.clone(), "a", config)).unwrap();
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+
+    for how in ["thread", "tokio"] {
+        let load = runtime::timeout(Duration::from_millis(20), array.load());
+        let loaded = match how {
+            "thread" => block_on(load),
+            _ => rt.block_on(load),
+        };
+        assert!(loaded.is_none(), "{how}");
+        // Nothing keeps fetching the other chunks once the load is dropped.
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(store.chunk_gets.swap(0, Ordering::SeqCst), 2, "{how}");
+    }
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn load_sync_runs_everything_on_the_calling_thread() {
+    let root = group_dir("sync");
+    let store = Arc::new(ThreadRecordingStore {
+        inner: LocalBackend::new(&root),
+        threads: Mutex::new(Vec::new()),
+    });
+    let array = block_on(v2::open(store.clone(), "a")).unwrap();
+    store.threads.lock().unwrap().clear();
+
+    let expected: Vec<f64> = (0..16).map(f64::from).collect();
+    assert_eq!(array.load_sync().unwrap(), expected);
+    let threads = store.threads.lock().unwrap();
+    assert_eq!(threads.len(), 4);
+    assert!(threads.iter().all(|&t| t == std::thread::current().id()));
+    std::fs::remove_dir_all(&root).unwrap();
+}
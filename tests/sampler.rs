@@ -0,0 +1,117 @@
+//! Tests for shuffled window sampling.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use object_store::memory::InMemory;
+
+use simplezarr::builder::ArrayBuilder;
+use simplezarr::store::ObjectStoreBackend;
+use simplezarr::types::{DataType, ZarrVectorValue};
+use simplezarr::{ChunkSampler, SimplezarrConfig, UnifiedZarrArray};
+
+/// A 6x8 `f8` array with 2x3 chunks whose element (i, j) is `10 * i + j`,
+/// with a decoded-chunk cache.
+async fn grid() -> Arc<UnifiedZarrArray> {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let config = SimplezarrConfig::builder()
+        .chunk_cache_bytes(1 << 20)
+        .build();
+    let array = ArrayBuilder::new([6, 8])
+        .chunks([2, 3])
+        .dtype(DataType::Float64)
+        .codec(None)
+        .build_with_config(store, "grid", Arc::new(config))
+        .await
+        .unwrap();
+    let values = (0..6)
+        .flat_map(|i| (0..8).map(move |j| (10 * i + j) as f64))
+        .collect();
+    array
+        .set_region(&array.full_region(), &ZarrVectorValue::VFloat64(values))
+        .await
+        .unwrap();
+    Arc::new(array)
+}
+
+#[tokio::test]
+async fn epochs_are_seeded_and_cover_every_chunk() {
+    let sampler = ChunkSampler::new(grid().await, &[2, 2])
+        .unwrap()
+        .seed(42)
+        .samples_per_chunk(2);
+    // Window origins span 5x7, which is 3x3 chunks.
+    assert_eq!(sampler.len(), 18);
+    let first = sampler.epoch(0);
+    assert_eq!(first.len(), 18);
+    assert_eq!(first, sampler.epoch(0));
+    assert_ne!(first, sampler.epoch(1));
+    assert_ne!(first, sampler.clone().seed(43).epoch(0));
+
+    let mut chunks = HashSet::new();
+    for pair in first.chunks(2) {
+        // Samples of one chunk come together.
+        let chunk: Vec<usize> = pair[0]
+            .iter()
+            .zip([2, 3])
+            .map(|(r, c)| r.start / c)
+            .collect();
+        for region in pair {
+            assert_eq!(region.iter().map(|r| r.len()).collect::<Vec<_>>(), [2, 2]);
+            assert!(region[0].end <= 6 && region[1].end <= 8);
+            let here: Vec<usize> = region
+                .iter()
+                .zip([2, 3])
+                .map(|(r, c)| r.start / c)
+                .collect();
+            assert_eq!(here, chunk);
+        }
+        chunks.insert(chunk);
+    }
+    assert_eq!(chunks.len(), 9);
+
+    let array = grid().await;
+    for window in [&[7, 1][..], &[0, 1], &[2]] {
+        assert!(ChunkSampler::new(array.clone(), window).is_err());
+    }
+}
+
+#[tokio::test]
+async fn batches_read_windows_in_order() {
+    let array = grid().await;
+    let sampler = ChunkSampler::new(array.clone(), &[2, 2])
+        .unwrap()
+        .samples_per_chunk(2);
+    let loader = sampler.clone().batches(4).prefetch(1);
+    assert_eq!(loader.len(), 5);
+    assert_eq!(loader.clone().drop_last(true).len(), 4);
+
+    let batches: Vec<_> = loader.epoch(3).try_collect().await.unwrap();
+    assert_eq!(batches.len(), 5);
+    assert_eq!(batches[0].shape, vec![4, 2, 2]);
+    assert_eq!(batches[4].shape, vec![2, 2, 2]);
+    let regions: Vec<_> = batches.iter().flat_map(|b| b.regions.clone()).collect();
+    assert_eq!(regions, sampler.epoch(3));
+    for batch in &batches {
+        let want: Vec<f64> = batch
+            .regions
+            .iter()
+            .flat_map(|r| {
+                let (rows, cols) = (r[0].clone(), r[1].clone());
+                rows.flat_map(move |i| cols.clone().map(move |j| (10 * i + j) as f64))
+            })
+            .collect();
+        assert_eq!(batch.values, want);
+    }
+    assert!(array.chunk_cache().unwrap().size_bytes() > 0);
+
+    let dropped: Vec<_> = loader
+        .clone()
+        .drop_last(true)
+        .epoch(3)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(dropped.len(), 4);
+}
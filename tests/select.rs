@@ -0,0 +1,217 @@
+//! Tests for `_ARRAY_DIMENSIONS` and coordinate-based selection.
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use object_store::memory::InMemory;
+use serde_json::json;
+
+use simplezarr::UnifiedZarrArray;
+use simplezarr::error::ZarrError;
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+use simplezarr::v2;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn zarray(shape: &[usize], chunks: &[usize]) -> serde_json::Value {
+    json!({
+        "zarr_format": 2,
+        "shape": shape,
+        "chunks": chunks,
+        "dtype": "|u1",
+        "fill_value": 0,
+        "order": "C",
+        "compressor": null,
+        "filters": null,
+    })
+}
+
+async fn put_json(store: &ObjectStoreBackend, key: &str, value: &serde_json::Value) {
+    store
+        .put(key, serde_json::to_vec(value).unwrap().into())
+        .await
+        .unwrap();
+}
+
+/// Group `g` with a descending `lat` (30, 20, 10, 0), an ascending `lon`
+/// (0, 90, 180, 255) and a 4x4 `temp` on (lat, lon) holding `4 * i + j`.
+async fn climate_store() -> Arc<ObjectStoreBackend> {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    for (name, values) in [("lat", [30u8, 20, 10, 0]), ("lon", [0, 90, 180, 255])] {
+        put_json(&store, &format!("g/{name}/.zarray"), &zarray(&[4], &[4])).await;
+        put_json(
+            &store,
+            &format!("g/{name}/.zattrs"),
+            &json!({"_ARRAY_DIMENSIONS": [name]}),
+        )
+        .await;
+        store
+            .put(&format!("g/{name}/0"), Bytes::copy_from_slice(&values))
+            .await
+            .unwrap();
+    }
+    put_json(&store, "g/temp/.zarray", &zarray(&[4, 4], &[2, 2])).await;
+    put_json(
+        &store,
+        "g/temp/.zattrs",
+        &json!({"_ARRAY_DIMENSIONS": ["lat", "lon"], "units": "K"}),
+    )
+    .await;
+    for ci in 0..2 {
+        for cj in 0..2 {
+            let chunk: Vec<u8> = (0..2)
+                .flat_map(|li| (0..2).map(move |lj| (4 * (2 * ci + li) + 2 * cj + lj) as u8))
+                .collect();
+            store
+                .put(&format!("g/temp/{ci}.{cj}"), chunk.into())
+                .await
+                .unwrap();
+        }
+    }
+    put_json(&store, "g/.zattrs", &json!({"title": "test"})).await;
+    store
+}
+
+async fn climate_group(store: Arc<ObjectStoreBackend>) -> simplezarr::UnifiedZarrGroup {
+    let mut group = v2::open_group(store, "g", &["lat", "lon", "temp"])
+        .await
+        .unwrap();
+    if !group.is_consolidated() {
+        group.load_attributes().await.unwrap();
+    }
+    group
+}
+
+fn dims(array: &UnifiedZarrArray) -> Vec<Option<String>> {
+    array.metadata.dimension_names.clone().unwrap()
+}
+
+// ---------------------------------------------------------------------------
+// Attributes and dimension names
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn zattrs_give_attributes_and_dimension_names() {
+    let store = climate_store().await;
+    // Opening skips .zattrs.
+    let mut temp = v2::open(store.clone(), "g/temp").await.unwrap();
+    assert!(temp.metadata.attributes.is_none());
+    assert!(temp.metadata.dimension_names.is_none());
+
+    temp.load_attributes().await.unwrap();
+    assert_eq!(temp.metadata.attributes.as_ref().unwrap()["units"], "K");
+    assert_eq!(dims(&temp), vec![Some("lat".into()), Some("lon".into())]);
+
+    // The names survive a descriptor round trip.
+    let descriptor = temp.descriptor();
+    let rebuilt = UnifiedZarrArray::from_descriptor(store.clone(), &descriptor).unwrap();
+    assert_eq!(dims(&rebuilt), dims(&temp));
+
+    // A name list of the wrong length is ignored.
+    put_json(
+        &store,
+        "g/temp/.zattrs",
+        &json!({"_ARRAY_DIMENSIONS": ["lat"]}),
+    )
+    .await;
+    temp.load_attributes().await.unwrap();
+    assert!(temp.metadata.attributes.is_some());
+    assert!(temp.metadata.dimension_names.is_none());
+}
+
+#[tokio::test]
+async fn consolidated_groups_read_zattrs() {
+    let store = climate_store().await;
+    put_json(
+        &store,
+        "g/.zmetadata",
+        &json!({
+            "zarr_consolidated_format": 1,
+            "metadata": {
+                ".zgroup": {"zarr_format": 2},
+                ".zattrs": {"title": "test"},
+                "lat/.zarray": zarray(&[4], &[4]),
+                "lat/.zattrs": {"_ARRAY_DIMENSIONS": ["lat"]},
+                "temp/.zarray": zarray(&[4, 4], &[2, 2]),
+                "temp/.zattrs": {"_ARRAY_DIMENSIONS": ["lat", "lon"]},
+            },
+        }),
+    )
+    .await;
+    let group = climate_group(store).await;
+    assert!(group.is_consolidated());
+    assert_eq!(group.attributes().unwrap()["title"], "test");
+    assert_eq!(
+        dims(group.get_array("lat").unwrap()),
+        vec![Some("lat".into())]
+    );
+    assert_eq!(dims(group.get_array("temp").unwrap()).len(), 2);
+}
+
+// ---------------------------------------------------------------------------
+// Selection
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn select_binary_searches_coordinates() {
+    let group = climate_group(climate_store().await).await;
+    assert!(group.coordinate("lat").is_some());
+    assert!(group.coordinate("temp").is_none());
+
+    // Ascending and descending coordinates, inclusive at both ends.
+    assert_eq!(group.select("lon", 90.0..=180.0).await.unwrap(), 1..3);
+    assert_eq!(group.select("lon", 100.0..=1000.0).await.unwrap(), 2..4);
+    assert_eq!(group.select("lat", 5.0..=20.0).await.unwrap(), 1..3);
+    assert_eq!(group.select("lat", -90.0..=90.0).await.unwrap(), 0..4);
+    assert!(group.select("lat", 40.0..=50.0).await.unwrap().is_empty());
+    assert!(group.select("lon", 180.0..=90.0).await.unwrap().is_empty());
+
+    let err = group.select("time", 0.0..=1.0).await.unwrap_err();
+    assert!(matches!(err, ZarrError::NotFound(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn read_selection_reads_the_selected_region() {
+    let group = climate_group(climate_store().await).await;
+    let (region, values) = group
+        .read_selection("temp", &[("lat", 5.0..=25.0), ("lon", 90.0..=180.0)])
+        .await
+        .unwrap();
+    assert_eq!(region, vec![1..3, 1..3]);
+    assert_eq!(values, vec![5.0, 6.0, 9.0, 10.0]);
+
+    // Unnamed dimensions are read whole.
+    let (region, values) = group
+        .read_selection("temp", &[("lat", 0.0..=0.0)])
+        .await
+        .unwrap();
+    assert_eq!(region, vec![3..4, 0..4]);
+    assert_eq!(values, vec![12.0, 13.0, 14.0, 15.0]);
+
+    let (region, values) = group
+        .read_selection("temp", &[("lat", 40.0..=50.0)])
+        .await
+        .unwrap();
+    assert_eq!(region, vec![0..0, 0..4]);
+    assert!(values.is_empty());
+
+    let err = group
+        .read_selection("temp", &[("depth", 0.0..=1.0)])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::NotFound(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn unsorted_coordinates_are_rejected() {
+    let store = climate_store().await;
+    store
+        .put("g/lon/0", Bytes::from_static(&[0, 180, 90, 255]))
+        .await
+        .unwrap();
+    let group = climate_group(store).await;
+    let err = group.select("lon", 0.0..=90.0).await.unwrap_err();
+    assert!(matches!(err, ZarrError::Metadata(_)), "{err:?}");
+}
@@ -0,0 +1,206 @@
+//! Tests for streaming histograms and quantiles.
+
+use std::sync::Arc;
+
+use object_store::memory::InMemory;
+
+use simplezarr::builder::ArrayBuilder;
+use simplezarr::error::ZarrError;
+use simplezarr::stats::{BitInformation, Histogram, TDigest};
+use simplezarr::store::ObjectStoreBackend;
+use simplezarr::types::{ArrayOrder, DataType, ZarrVectorValue};
+use simplezarr::{ArrayData, UnifiedZarrArray};
+
+/// A 1-D `float64` array holding `values`, in chunks of `chunk`.
+async fn array(values: Vec<f64>, chunk: usize) -> UnifiedZarrArray {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let array = ArrayBuilder::new([values.len()])
+        .chunks([chunk])
+        .dtype(DataType::Float64)
+        .codec(None)
+        .build(store, "a")
+        .await
+        .unwrap();
+    array
+        .set_region(&array.full_region(), &ZarrVectorValue::VFloat64(values))
+        .await
+        .unwrap();
+    array
+}
+
+// ---------------------------------------------------------------------------
+// Histograms
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn histograms_span_the_finite_values() {
+    let mut values: Vec<f64> = (0..100).map(f64::from).collect();
+    values[7] = f64::NAN;
+    values[8] = f64::INFINITY;
+    let array = array(values, 30).await;
+
+    assert_eq!(array.value_range().await.unwrap(), Some(0.0..=99.0));
+    let histogram = array.histogram(4).await.unwrap();
+    assert_eq!(histogram.edges, [0.0, 24.75, 49.5, 74.25, 99.0]);
+    assert_eq!(histogram.counts, [23, 25, 25, 25]);
+
+    let histogram = array.histogram_in(2, 10.0..=20.0).await.unwrap();
+    assert_eq!(histogram.counts, [5, 6]);
+    assert_eq!(histogram.total(), 11);
+}
+
+#[tokio::test]
+async fn constant_and_empty_arrays_get_unit_ranges() {
+    let constant = array(vec![3.0; 5], 2).await;
+    let histogram = constant.histogram(2).await.unwrap();
+    assert_eq!(histogram.edges, [2.5, 3.0, 3.5]);
+    assert_eq!(histogram.counts, [0, 5]);
+
+    let empty = array(vec![f64::NAN; 3], 2).await;
+    assert_eq!(empty.value_range().await.unwrap(), None);
+    assert_eq!(empty.histogram(1).await.unwrap().edges, [0.0, 1.0]);
+    assert!(empty.quantiles(&[0.5]).await.unwrap()[0].is_nan());
+}
+
+#[test]
+fn histograms_merge_counts() {
+    let mut a = Histogram::new(2, 0.0..=2.0);
+    let mut b = a.clone();
+    a.add(0.5);
+    b.add(2.0);
+    b.add(5.0);
+    a.merge(&b);
+    assert_eq!(a.counts, [1, 1]);
+}
+
+// ---------------------------------------------------------------------------
+// Quantiles
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn quantiles_are_close_and_extremes_exact() {
+    // A permutation of 0..10000, so each chunk sees a spread of values.
+    let values: Vec<f64> = (0..10_000u64).map(|i| (i * 7919 % 10_000) as f64).collect();
+    let array = array(values, 1_000).await;
+    let qs = array
+        .quantiles(&[0.0, 0.01, 0.25, 0.5, 0.99, 1.0])
+        .await
+        .unwrap();
+    assert_eq!(qs[0], 0.0);
+    assert_eq!(qs[5], 9_999.0);
+    for (q, estimate) in [0.01, 0.25, 0.5, 0.99].iter().zip(&qs[1..5]) {
+        let exact = q * 9_999.0;
+        assert!(
+            (estimate - exact).abs() < 25.0,
+            "q{q}: {estimate} vs {exact}"
+        );
+    }
+    assert_eq!(array.digest().await.unwrap().count(), 10_000);
+}
+
+#[test]
+fn digests_of_parts_merge_into_the_whole() {
+    let mut low = TDigest::default();
+    low.add((0..500).map(f64::from));
+    let mut high = TDigest::new(50.0);
+    high.add((500..1_000).map(f64::from));
+    low.merge(&high);
+    assert_eq!(low.count(), 1_000);
+    assert!((low.quantile(0.5) - 499.5).abs() < 5.0);
+    assert!(TDigest::default().quantile(0.5).is_nan());
+}
+
+// ---------------------------------------------------------------------------
+// Bit information
+// ---------------------------------------------------------------------------
+
+/// A `rows x cols` C-order `float64` signal, smooth along rows, plus noise
+/// of relative size `noise`.
+fn signal(rows: usize, cols: usize, noise: f64) -> Vec<f64> {
+    let mut state = 12345u64;
+    (0..rows * cols)
+        .map(|i| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let random = (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+            let (r, c) = (i / cols, i % cols);
+            10.0 + (c as f64 / 40_000.0 + r as f64).sin() * (1.0 + noise * random)
+        })
+        .collect()
+}
+
+#[test]
+fn keepbits_track_the_noise_level() {
+    let keepbits = |noise: f64| {
+        let mut information = BitInformation::new(DataType::Float64).unwrap();
+        let values = ZarrVectorValue::VFloat64(signal(4, 5_000, noise));
+        information.add(&ArrayData::new(values, vec![4, 5_000], ArrayOrder::C));
+        assert_eq!(information.pairs(), 4 * 4_999);
+        information.keepbits(0.99)
+    };
+    // Noise of 1e-3 on values near 10 is about 2^-14 of them; without it,
+    // how far neighbours differ sets the precision.
+    let (noisy, quiet) = (keepbits(1e-3), keepbits(1e-9));
+    assert!((12..=17).contains(&noisy), "{noisy}");
+    assert!(quiet >= noisy + 6 && quiet <= 52, "{noisy} {quiet}");
+
+    // Constant data, and no data, carry no information.
+    let mut constant = BitInformation::new(DataType::Float32).unwrap();
+    constant.add(&ArrayData::new(
+        ZarrVectorValue::VFloat32(vec![1.5; 100]),
+        vec![100],
+        ArrayOrder::C,
+    ));
+    assert!(constant.information().iter().all(|&i| i == 0.0));
+    assert_eq!(constant.keepbits(0.99), 0);
+    assert_eq!(
+        BitInformation::new(DataType::Float16)
+            .unwrap()
+            .keepbits(0.99),
+        0
+    );
+    assert!(BitInformation::new(DataType::Int32).is_err());
+}
+
+#[tokio::test]
+async fn array_bit_information_merges_chunks() {
+    let values = signal(6, 500, 1e-4);
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    // One row per chunk, so no neighbouring pair straddles two chunks.
+    let array = ArrayBuilder::new([6, 500])
+        .chunks([1, 500])
+        .dtype(DataType::Float64)
+        .build(store, "t")
+        .await
+        .unwrap();
+    array
+        .set_region(
+            &array.full_region(),
+            &ZarrVectorValue::VFloat64(values.clone()),
+        )
+        .await
+        .unwrap();
+
+    let mut whole = BitInformation::new(DataType::Float64).unwrap();
+    whole.add(&ArrayData::new(
+        ZarrVectorValue::VFloat64(values),
+        vec![6, 500],
+        ArrayOrder::C,
+    ));
+    let chunked = array.bit_information().await.unwrap();
+    assert_eq!(chunked, whole);
+    assert_eq!(array.keepbits(0.99).await.unwrap(), whole.keepbits(0.99));
+}
+
+#[tokio::test]
+async fn non_numeric_arrays_are_rejected() {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let array = ArrayBuilder::new([4])
+        .dtype(DataType::Complex64)
+        .build(store, "c")
+        .await
+        .unwrap();
+    let err = array.histogram(4).await.unwrap_err();
+    assert!(matches!(err, ZarrError::TypeConversion(_)), "{err:?}");
+}
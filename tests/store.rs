@@ -0,0 +1,552 @@
+//! Tests for writable backends and store composition.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::TryStreamExt;
+use object_store::memory::InMemory;
+
+use simplezarr::error::{ZarrError, ZarrResult};
+use simplezarr::store::{
+    ListEntry, LocalBackend, ObjectMeta, ObjectStoreBackend, StorageBackend, TieredStore,
+};
+use simplezarr::v2;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Backend wrapper counting reads that reach it.
+struct Counting<B> {
+    inner: B,
+    gets: AtomicUsize,
+}
+
+impl<B> Counting<B> {
+    fn new(inner: B) -> Self {
+        Self {
+            inner,
+            gets: AtomicUsize::new(0),
+        }
+    }
+
+    fn gets(&self) -> usize {
+        self.gets.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl<B: StorageBackend> StorageBackend for Counting<B> {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        self.gets.fetch_add(1, Ordering::SeqCst);
+        self.inner.get(path).await
+    }
+
+    async fn list(&self, prefix: &str) -> ZarrResult<Vec<String>> {
+        self.inner.list(prefix).await
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        self.inner.join(base, segment)
+    }
+
+    async fn put(&self, path: &str, data: Bytes) -> ZarrResult<()> {
+        self.inner.put(path, data).await
+    }
+
+    async fn delete(&self, path: &str) -> ZarrResult<()> {
+        self.inner.delete(path).await
+    }
+}
+
+/// Backend whose reads always fail and which cannot be written.
+struct Broken;
+
+#[async_trait]
+impl StorageBackend for Broken {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        Err(ZarrError::Storage(format!("broken: {path}")))
+    }
+
+    async fn list(&self, _prefix: &str) -> ZarrResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        format!("{base}/{segment}")
+    }
+}
+
+fn memory() -> ObjectStoreBackend {
+    ObjectStoreBackend::new(Box::new(InMemory::new()), "")
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("simplezarr-store-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// Put a 2x2 `<i4` array holding 1..=4 in one chunk at `arr` in `store`.
+async fn put_array(store: &(impl StorageBackend + ?Sized)) {
+    let zarray = serde_json::json!({
+        "zarr_format": 2,
+        "shape": [2, 2],
+        "chunks": [2, 2],
+        "dtype": "<i4",
+        "fill_value": 0,
+        "order": "C",
+        "compressor": null,
+        "filters": null,
+    });
+    let chunk: Vec<u8> = (1i32..=4).flat_map(|v| v.to_le_bytes()).collect();
+    store
+        .put("arr/.zarray", serde_json::to_vec(&zarray).unwrap().into())
+        .await
+        .unwrap();
+    store.put("arr/0.0", chunk.into()).await.unwrap();
+}
+
+// ---------------------------------------------------------------------------
+// Writes
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn writable_backends_round_trip() {
+    let dir = temp_dir("put");
+    let local = LocalBackend::new(&dir);
+    local
+        .put("a/b/c", Bytes::from_static(b"local"))
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(dir.join("a/b/c")).unwrap(), b"local");
+    assert_eq!(
+        local.get("a/b/c").await.unwrap().as_deref(),
+        Some(&b"local"[..])
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let mem = memory();
+    mem.put("x/y", Bytes::from_static(b"mem")).await.unwrap();
+    assert_eq!(mem.get("x/y").await.unwrap().as_deref(), Some(&b"mem"[..]));
+}
+
+#[tokio::test]
+async fn local_concurrent_puts_of_one_key_are_whole() {
+    let dir = temp_dir("concurrent-put");
+    let local = LocalBackend::new(&dir);
+    let values: Vec<Bytes> = (0..16u8).map(|i| Bytes::from(vec![i; 64 * 1024])).collect();
+    let puts = values.iter().map(|v| local.put("k", v.clone()));
+    for result in futures::future::join_all(puts).await {
+        result.unwrap();
+    }
+    let stored = local.get("k").await.unwrap().unwrap();
+    assert!(values.contains(&stored));
+    let names: Vec<_> = std::fs::read_dir(&dir).unwrap().flatten().collect();
+    assert_eq!(names.len(), 1, "temporary files left behind");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn writable_backends_delete() {
+    let dir = temp_dir("delete");
+    let local = LocalBackend::new(&dir);
+    local.put("a/b", Bytes::from_static(b"x")).await.unwrap();
+    local.delete("a/b").await.unwrap();
+    assert!(!dir.join("a/b").exists());
+    local.delete("a/b").await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let mem = memory();
+    mem.put("x/y", Bytes::from_static(b"mem")).await.unwrap();
+    mem.delete("x/y").await.unwrap();
+    assert_eq!(mem.get("x/y").await.unwrap(), None);
+    mem.delete("x/y").await.unwrap();
+}
+
+#[tokio::test]
+async fn backends_are_read_only_by_default() {
+    let err = Broken.put("k", Bytes::new()).await.unwrap_err();
+    assert!(err.to_string().contains("read-only"), "{err}");
+    let err = Broken.delete("k").await.unwrap_err();
+    assert!(err.to_string().contains("read-only"), "{err}");
+}
+
+// ---------------------------------------------------------------------------
+// Metadata-only requests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn head_reports_size_without_reading_the_value() {
+    let dir = temp_dir("head");
+    let local = LocalBackend::new(&dir);
+    local
+        .put("a/0.0", Bytes::from_static(b"12345"))
+        .await
+        .unwrap();
+    local.put("a/empty", Bytes::new()).await.unwrap();
+    let meta = local.head("a/0.0").await.unwrap().unwrap();
+    assert_eq!(meta.size, 5);
+    assert_eq!(meta.etag, None);
+    assert!(meta.modified.is_some());
+    assert_eq!(local.head("a/empty").await.unwrap(), None);
+    assert_eq!(local.head("a").await.unwrap(), None);
+    assert_eq!(local.head("missing").await.unwrap(), None);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let mem = Counting::new(memory());
+    mem.put("k", Bytes::from_static(b"abc")).await.unwrap();
+    let meta = mem.inner.head("k").await.unwrap().unwrap();
+    assert_eq!(meta.size, 3);
+    assert!(meta.etag.is_some());
+    assert!(meta.modified.is_some());
+    assert_eq!(mem.inner.etag("k").await.unwrap(), meta.etag);
+    assert_eq!(mem.inner.head("missing").await.unwrap(), None);
+    assert_eq!(mem.gets(), 0);
+}
+
+#[tokio::test]
+async fn byte_ranges_are_read_from_every_backend() {
+    let dir = temp_dir("range");
+    let local = LocalBackend::new(&dir);
+    let mem = memory();
+    let counting = Counting::new(memory());
+    let backends: [&dyn StorageBackend; 3] = [&local, &mem, &counting];
+    for store in backends {
+        store
+            .put("k", Bytes::from_static(b"0123456789"))
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_range("k", 2..5).await.unwrap().as_deref(),
+            Some(&b"234"[..])
+        );
+        assert_eq!(
+            store.get_range("k", 10..10).await.unwrap().as_deref(),
+            Some(&b""[..])
+        );
+        assert_eq!(store.get_range("missing", 0..1).await.unwrap(), None);
+        assert!(matches!(
+            store.get_range("k", 8..12).await,
+            Err(ZarrError::Storage(_))
+        ));
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn head_falls_back_to_reading_the_value() {
+    // `Counting` doesn't override `head`, so the default fetches the value.
+    let store = Counting::new(memory());
+    store.put("k", Bytes::from_static(b"abcd")).await.unwrap();
+    assert_eq!(
+        store.head("k").await.unwrap(),
+        Some(ObjectMeta {
+            size: 4,
+            etag: None,
+            modified: None,
+            version: None,
+        })
+    );
+    assert_eq!(store.head("missing").await.unwrap(), None);
+    assert_eq!(store.gets(), 2);
+}
+
+// ---------------------------------------------------------------------------
+// Listing
+// ---------------------------------------------------------------------------
+
+/// Keys and sizes from `list_stream`, sorted.
+async fn listed(store: &dyn StorageBackend, prefix: &str) -> Vec<(String, u64)> {
+    let entries: Vec<ListEntry> = store.list_stream(prefix).try_collect().await.unwrap();
+    let mut listed: Vec<_> = entries.into_iter().map(|e| (e.key, e.meta.size)).collect();
+    listed.sort();
+    listed
+}
+
+#[tokio::test]
+async fn list_stream_yields_keys_with_metadata() {
+    let dir = temp_dir("list");
+    let local = LocalBackend::new(&dir);
+    let mem = memory();
+    let backends: [&dyn StorageBackend; 2] = [&local, &mem];
+    for store in backends {
+        put_array(store).await;
+        store.put("arr/empty", Bytes::new()).await.unwrap();
+        store
+            .put("arr/nested/0", Bytes::from_static(b"x"))
+            .await
+            .unwrap();
+    }
+
+    // Local listings are of a directory's files, as `list` names them.
+    assert_eq!(
+        listed(&local, "arr").await,
+        [(".zarray".to_string(), 120), ("0.0".to_string(), 16)]
+    );
+    assert_eq!(
+        listed(&mem, "arr").await,
+        [
+            ("arr/.zarray".to_string(), 120),
+            ("arr/0.0".to_string(), 16),
+            ("arr/nested/0".to_string(), 1),
+        ]
+    );
+    let entries: Vec<ListEntry> = mem.list_stream("arr").try_collect().await.unwrap();
+    assert!(entries.iter().all(|e| e.meta.etag.is_some()));
+    assert!(
+        local
+            .list_stream("missing")
+            .try_collect::<Vec<_>>()
+            .await
+            .is_err()
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // The default lists, then asks each key for its metadata.
+    let counting = Counting::new(memory());
+    put_array(&counting).await;
+    assert_eq!(
+        listed(&counting, "arr").await,
+        [
+            ("arr/.zarray".to_string(), 120),
+            ("arr/0.0".to_string(), 16)
+        ]
+    );
+    assert_eq!(counting.gets(), 2);
+}
+
+/// An object store whose listings are refused, as with expired credentials;
+/// everything else goes to the wrapped store.
+#[derive(Debug)]
+struct ListingDenied(Arc<InMemory>);
+
+impl std::fmt::Display for ListingDenied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ListingDenied({})", self.0)
+    }
+}
+
+#[async_trait]
+impl object_store::ObjectStore for ListingDenied {
+    async fn put_opts(
+        &self,
+        location: &object_store::path::Path,
+        payload: object_store::PutPayload,
+        opts: object_store::PutOptions,
+    ) -> object_store::Result<object_store::PutResult> {
+        self.0.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &object_store::path::Path,
+        opts: object_store::PutMultipartOptions,
+    ) -> object_store::Result<Box<dyn object_store::MultipartUpload>> {
+        self.0.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(
+        &self,
+        location: &object_store::path::Path,
+        options: object_store::GetOptions,
+    ) -> object_store::Result<object_store::GetResult> {
+        self.0.get_opts(location, options).await
+    }
+
+    fn delete_stream(
+        &self,
+        locations: futures::stream::BoxStream<
+            'static,
+            object_store::Result<object_store::path::Path>,
+        >,
+    ) -> futures::stream::BoxStream<'static, object_store::Result<object_store::path::Path>> {
+        self.0.delete_stream(locations)
+    }
+
+    fn list(
+        &self,
+        prefix: Option<&object_store::path::Path>,
+    ) -> futures::stream::BoxStream<'static, object_store::Result<object_store::ObjectMeta>> {
+        let path = prefix.map(ToString::to_string).unwrap_or_default();
+        Box::pin(futures::stream::iter([Err(
+            object_store::Error::PermissionDenied {
+                path,
+                source: "token expired".into(),
+            },
+        )]))
+    }
+
+    async fn list_with_delimiter(
+        &self,
+        prefix: Option<&object_store::path::Path>,
+    ) -> object_store::Result<object_store::ListResult> {
+        self.0.list_with_delimiter(prefix).await
+    }
+
+    async fn copy_opts(
+        &self,
+        from: &object_store::path::Path,
+        to: &object_store::path::Path,
+        options: object_store::CopyOptions,
+    ) -> object_store::Result<()> {
+        self.0.copy_opts(from, to, options).await
+    }
+}
+
+#[tokio::test]
+async fn list_stream_refreshes_rejected_credentials() {
+    let inner = Arc::new(InMemory::new());
+    let refreshes = Arc::new(AtomicUsize::new(0));
+    let store = ObjectStoreBackend::new(Box::new(ListingDenied(inner.clone())), "")
+        .with_credential_refresh({
+            let refreshes = refreshes.clone();
+            move || {
+                refreshes.fetch_add(1, Ordering::SeqCst);
+                let store: Box<dyn object_store::ObjectStore> = Box::new(inner.clone());
+                async move { Ok(store) }
+            }
+        });
+    put_array(&store).await;
+    assert_eq!(
+        listed(&store, "arr").await,
+        [
+            ("arr/.zarray".to_string(), 120),
+            ("arr/0.0".to_string(), 16)
+        ]
+    );
+    assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+
+    // Without a refresh hook the rejection is the stream's error.
+    let store = ObjectStoreBackend::new(Box::new(ListingDenied(Arc::default())), "");
+    match store.list_stream("arr").try_collect::<Vec<_>>().await {
+        Err(ZarrError::Storage(msg)) => assert!(msg.contains("token expired"), "{msg}"),
+        other => panic!("expected a storage error, got {other:?}"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// TieredStore
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn tiered_reads_prefer_the_primary() {
+    let primary = memory();
+    let fallback = Counting::new(memory());
+    primary
+        .put("k", Bytes::from_static(b"local"))
+        .await
+        .unwrap();
+    fallback
+        .put("k", Bytes::from_static(b"remote"))
+        .await
+        .unwrap();
+    fallback
+        .put("only-remote", Bytes::from_static(b"remote"))
+        .await
+        .unwrap();
+
+    let store = TieredStore::new(primary, fallback);
+    assert_eq!(
+        store.get("k").await.unwrap().as_deref(),
+        Some(&b"local"[..])
+    );
+    assert_eq!(store.fallback().gets(), 0);
+    assert_eq!(
+        store.get("only-remote").await.unwrap().as_deref(),
+        Some(&b"remote"[..])
+    );
+    assert_eq!(store.get("missing").await.unwrap(), None);
+    // Without populate, misses leave the primary alone.
+    assert_eq!(store.primary().get("only-remote").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn tiered_misses_populate_a_local_cache_directory() {
+    let dir = temp_dir("tiered");
+    let fallback = Counting::new(memory());
+    put_array(&fallback).await;
+    let store = Arc::new(TieredStore::new(LocalBackend::new(&dir), fallback).populate(true));
+
+    let arr = v2::open(store.clone(), "arr").await.expect("open");
+    assert_eq!(arr.load().await.unwrap(), vec![1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(store.fallback().gets(), 2);
+    assert!(dir.join("arr/.zarray").exists());
+    assert!(dir.join("arr/0.0").exists());
+
+    // A second session is served entirely from disk.
+    let arr = v2::open(store.clone(), "arr").await.expect("reopen");
+    assert_eq!(arr.load().await.unwrap(), vec![1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(store.fallback().gets(), 2);
+
+    // Missing keys are not cached.
+    assert_eq!(store.get("arr/1.1").await.unwrap(), None);
+    assert!(!dir.join("arr/1.1").exists());
+
+    // Deletes reach both tiers.
+    store.delete("arr/0.0").await.unwrap();
+    assert!(!dir.join("arr/0.0").exists());
+    assert_eq!(store.fallback().get("arr/0.0").await.unwrap(), None);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn tiered_writes_replace_the_primary_copy() {
+    for populate in [false, true] {
+        let primary = memory();
+        primary
+            .put("k", Bytes::from_static(b"stale"))
+            .await
+            .unwrap();
+        let store = TieredStore::new(primary, memory()).populate(populate);
+        store.put("k", Bytes::from_static(b"fresh")).await.unwrap();
+        assert_eq!(
+            store.get("k").await.unwrap().as_deref(),
+            Some(&b"fresh"[..]),
+            "populate: {populate}"
+        );
+        assert_eq!(
+            store.fallback().get("k").await.unwrap().as_deref(),
+            Some(&b"fresh"[..])
+        );
+    }
+}
+
+#[tokio::test]
+async fn tiered_primary_failures_are_misses() {
+    let fallback = memory();
+    fallback
+        .put("k", Bytes::from_static(b"remote"))
+        .await
+        .unwrap();
+    let store = TieredStore::new(Broken, fallback).populate(true);
+    assert_eq!(
+        store.get("k").await.unwrap().as_deref(),
+        Some(&b"remote"[..])
+    );
+
+    let store = TieredStore::new(memory(), Broken);
+    assert!(matches!(store.get("k").await, Err(ZarrError::Storage(_))));
+}
+
+#[tokio::test]
+async fn tiered_head_asks_the_fallback() {
+    let primary = memory();
+    primary
+        .put("local-only", Bytes::from_static(b"x"))
+        .await
+        .unwrap();
+    let fallback = memory();
+    fallback
+        .put("k", Bytes::from_static(b"remote"))
+        .await
+        .unwrap();
+    let store = TieredStore::new(primary, fallback);
+    let meta = store.head("k").await.unwrap().unwrap();
+    assert_eq!(meta.size, 6);
+    assert_eq!(store.etag("k").await.unwrap(), meta.etag);
+    assert_eq!(store.head("local-only").await.unwrap(), None);
+}
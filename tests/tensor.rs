@@ -0,0 +1,96 @@
+//! Tests for reading regions into `candle` tensors.
+
+use std::sync::Arc;
+
+use candle_core::{DType, Device};
+use object_store::memory::InMemory;
+
+use simplezarr::UnifiedZarrArray;
+use simplezarr::builder::ArrayBuilder;
+use simplezarr::error::ZarrError;
+use simplezarr::store::ObjectStoreBackend;
+use simplezarr::tensor::zarr_vector_to_tensor;
+use simplezarr::types::{ArrayOrder, DataType, ZarrVectorValue};
+
+/// A 3x4 array of `dtype` with 2x3 chunks holding `values` in memory order.
+async fn array(dtype: DataType, order: ArrayOrder, values: ZarrVectorValue) -> UnifiedZarrArray {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let array = ArrayBuilder::new([3, 4])
+        .chunks([2, 3])
+        .dtype(dtype)
+        .codec(None)
+        .order(order)
+        .build(store, "a")
+        .await
+        .unwrap();
+    array
+        .set_region(&array.full_region(), &values)
+        .await
+        .unwrap();
+    array
+}
+
+#[tokio::test]
+async fn regions_become_tensors_of_their_shape() {
+    let values: Vec<f32> = (0..12).map(|i| i as f32).collect();
+    let arr = array(
+        DataType::Float32,
+        ArrayOrder::C,
+        ZarrVectorValue::VFloat32(values),
+    )
+    .await;
+    let tensor = arr
+        .read_region_tensor(&[1..3, 1..3], &Device::Cpu)
+        .await
+        .unwrap();
+    assert_eq!(tensor.dims(), &[2, 2]);
+    assert_eq!(tensor.dtype(), DType::F32);
+    assert_eq!(
+        tensor.to_vec2::<f32>().unwrap(),
+        vec![vec![5.0, 6.0], vec![9.0, 10.0]]
+    );
+
+    // Column-major arrays come out in the same logical layout.
+    let values: Vec<i32> = (0..4)
+        .flat_map(|j| (0..3).map(move |i| 10 * i + j))
+        .collect();
+    let arr = array(
+        DataType::Int32,
+        ArrayOrder::F,
+        ZarrVectorValue::VInt32(values),
+    )
+    .await;
+    let tensor = arr.load_tensor(&Device::Cpu).await.unwrap();
+    assert_eq!(tensor.dtype(), DType::I32);
+    assert_eq!(tensor.to_vec2::<i32>().unwrap()[2], vec![20, 21, 22, 23]);
+}
+
+#[test]
+fn element_types_widen_to_tensor_dtypes() {
+    let cpu = &Device::Cpu;
+    let c = ArrayOrder::C;
+    let cases = [
+        (ZarrVectorValue::VBool(vec![true, false]), DType::U8),
+        (ZarrVectorValue::VInt8(vec![-1, 1]), DType::I16),
+        (ZarrVectorValue::VUInt16(vec![1, 2]), DType::U32),
+        (ZarrVectorValue::VUInt64(vec![1, 2]), DType::I64),
+        (ZarrVectorValue::VFloat64(vec![1.0, 2.0]), DType::F64),
+    ];
+    for (values, dtype) in cases {
+        let tensor = zarr_vector_to_tensor(values, &[2], c, cpu).unwrap();
+        assert_eq!(tensor.dtype(), dtype);
+    }
+    let tensor = zarr_vector_to_tensor(ZarrVectorValue::VInt8(vec![-1, 1]), &[2], c, cpu).unwrap();
+    assert_eq!(tensor.to_vec1::<i16>().unwrap(), vec![-1, 1]);
+
+    for values in [
+        ZarrVectorValue::VUInt64(vec![u64::MAX]),
+        ZarrVectorValue::VString(vec!["a".into()]),
+        ZarrVectorValue::VWithNulls(DataType::Float32, vec![None]),
+    ] {
+        let err = zarr_vector_to_tensor(values, &[1], c, cpu).unwrap_err();
+        assert!(matches!(err, ZarrError::TypeConversion(_)), "{err:?}");
+    }
+    // A shape that doesn't match the values.
+    assert!(zarr_vector_to_tensor(ZarrVectorValue::VUInt8(vec![1]), &[2], c, cpu).is_err());
+}
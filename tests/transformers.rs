@@ -0,0 +1,173 @@
+//! Tests for V3 storage transformers.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use object_store::memory::InMemory;
+use serde_json::json;
+
+use simplezarr::capabilities::{FeatureId, capabilities};
+use simplezarr::convert::{ConvertOptions, convert_v3_to_v2};
+use simplezarr::error::{ZarrError, ZarrResult};
+use simplezarr::metadata::v3::ZarrV3Metadata;
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+use simplezarr::transformers::{
+    StorageTransformer, StorageTransformerSpec, apply_storage_transformers,
+    parse_storage_transformers, register_storage_transformer,
+};
+use simplezarr::v2;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Stores every key under a fixed sub-directory.
+struct Prefixed {
+    inner: Arc<dyn StorageBackend>,
+    prefix: String,
+}
+
+#[async_trait]
+impl StorageBackend for Prefixed {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        self.inner.get(path).await
+    }
+
+    async fn list(&self, prefix: &str) -> ZarrResult<Vec<String>> {
+        self.inner.list(prefix).await
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        self.inner.join(base, &format!("{}/{segment}", self.prefix))
+    }
+}
+
+struct PrefixTransformer(String);
+
+impl StorageTransformer for PrefixTransformer {
+    fn apply(&self, store: Arc<dyn StorageBackend>) -> ZarrResult<Arc<dyn StorageBackend>> {
+        Ok(Arc::new(Prefixed {
+            inner: store,
+            prefix: self.0.clone(),
+        }))
+    }
+}
+
+fn prefix_factory(spec: &StorageTransformerSpec) -> ZarrResult<Arc<dyn StorageTransformer>> {
+    let prefix = spec.configuration["prefix"]
+        .as_str()
+        .ok_or_else(|| ZarrError::Metadata("prefix transformer needs a prefix".into()))?;
+    Ok(Arc::new(PrefixTransformer(prefix.to_string())))
+}
+
+fn zarr_json(storage_transformers: serde_json::Value) -> serde_json::Value {
+    json!({
+        "zarr_format": 3,
+        "node_type": "array",
+        "shape": [4],
+        "data_type": "uint8",
+        "chunk_grid": {"name": "regular", "configuration": {"chunk_shape": [2]}},
+        "chunk_key_encoding": {"name": "default"},
+        "fill_value": 0,
+        "codecs": [{"name": "bytes"}],
+        "storage_transformers": storage_transformers,
+    })
+}
+
+fn unsupported(err: ZarrError) -> FeatureId {
+    match err {
+        ZarrError::UnsupportedFeature(feature) => feature,
+        other => panic!("expected UnsupportedFeature, got {other:?}"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn specs_parse_in_both_forms() {
+    let specs = parse_storage_transformers(Some(&json!([
+        {"name": "prefix", "configuration": {"prefix": "data"}},
+        {"name": "stats", "must_understand": false},
+        {
+            "type": "indexed",
+            "extension": "https://purl.org/zarr/spec/storage_transformers/sharding/1.0",
+            "configuration": {"chunks_per_shard": [2]},
+        },
+    ])))
+    .unwrap();
+    assert_eq!(specs.len(), 3);
+    assert_eq!(specs[0].name, "prefix");
+    assert!(specs[0].must_understand);
+    assert!(!specs[1].must_understand);
+    assert_eq!(specs[1].configuration, json!({}));
+    assert_eq!(specs[2].name, "sharding_indexed");
+    assert_eq!(specs[2].configuration, json!({"chunks_per_shard": [2]}));
+    assert_eq!(
+        specs[1].to_json(),
+        json!({"name": "stats", "configuration": {}, "must_understand": false})
+    );
+
+    assert!(parse_storage_transformers(None).unwrap().is_empty());
+    assert!(parse_storage_transformers(Some(&json!([{"configuration": {}}]))).is_err());
+    assert!(parse_storage_transformers(Some(&json!({"name": "x"}))).is_err());
+}
+
+#[test]
+fn unknown_transformers_parse_but_fail_on_access() {
+    let document =
+        zarr_json(json!([{"name": "mystery"}, {"name": "stats", "must_understand": false}]));
+    let md = ZarrV3Metadata::parse(&serde_json::to_vec(&document).unwrap()).unwrap();
+    assert_eq!(md.storage_transformers.len(), 2);
+
+    let store: Arc<dyn StorageBackend> =
+        Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let err = apply_storage_transformers(store.clone(), &md.storage_transformers)
+        .err()
+        .unwrap();
+    assert_eq!(
+        unsupported(err),
+        FeatureId::StorageTransformer("mystery".into())
+    );
+    // Optional ones are skipped.
+    apply_storage_transformers(store, &md.storage_transformers[1..]).unwrap();
+}
+
+#[tokio::test]
+async fn registered_transformers_are_applied() {
+    register_storage_transformer("prefix", prefix_factory);
+    assert!(capabilities().supports(&FeatureId::StorageTransformer("prefix".into())));
+
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let document = zarr_json(json!([{"name": "prefix", "configuration": {"prefix": "data"}}]));
+    store
+        .put(
+            "src/zarr.json",
+            serde_json::to_vec(&document).unwrap().into(),
+        )
+        .await
+        .unwrap();
+    store
+        .put("src/data/c/0", Bytes::from_static(&[1, 2]))
+        .await
+        .unwrap();
+    store
+        .put("src/data/c/1", Bytes::from_static(&[3, 4]))
+        .await
+        .unwrap();
+
+    let copied = convert_v3_to_v2(
+        store.clone(),
+        "src",
+        store.clone(),
+        "dst",
+        &ConvertOptions::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(copied, 2);
+    let array = v2::open(store, "dst").await.unwrap();
+    assert_eq!(array.load().await.unwrap(), vec![1.0, 2.0, 3.0, 4.0]);
+}
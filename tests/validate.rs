@@ -0,0 +1,278 @@
+//! Tests for strict store validation.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use object_store::memory::InMemory;
+use serde_json::json;
+
+use simplezarr::config;
+use simplezarr::error::ZarrResult;
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+use simplezarr::validate::{
+    IssueKind, Severity, ValidateOptions, validate_store, validate_store_with_options,
+};
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn zarray(shape: &[usize], chunks: &[usize]) -> serde_json::Value {
+    json!({
+        "zarr_format": 2,
+        "shape": shape,
+        "chunks": chunks,
+        "dtype": "|u1",
+        "fill_value": 0,
+        "order": "C",
+        "compressor": null,
+        "filters": null,
+    })
+}
+
+async fn put_json(store: &ObjectStoreBackend, key: &str, value: &serde_json::Value) {
+    store
+        .put(key, serde_json::to_vec(value).unwrap().into())
+        .await
+        .unwrap();
+}
+
+/// Consolidated group `g` with a 4x4 `temp` in 2x2 chunks, all stored.
+async fn valid_store() -> Arc<ObjectStoreBackend> {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let attrs = json!({"_ARRAY_DIMENSIONS": ["y", "x"]});
+    put_json(&store, "g/.zgroup", &json!({"zarr_format": 2})).await;
+    put_json(&store, "g/temp/.zarray", &zarray(&[4, 4], &[2, 2])).await;
+    put_json(&store, "g/temp/.zattrs", &attrs).await;
+    for key in ["0.0", "0.1", "1.0", "1.1"] {
+        store
+            .put(&format!("g/temp/{key}"), Bytes::from_static(&[1, 2, 3, 4]))
+            .await
+            .unwrap();
+    }
+    put_json(
+        &store,
+        "g/.zmetadata",
+        &json!({
+            "zarr_consolidated_format": 1,
+            "metadata": {
+                ".zgroup": {"zarr_format": 2},
+                "temp/.zarray": zarray(&[4, 4], &[2, 2]),
+                "temp/.zattrs": attrs,
+            },
+        }),
+    )
+    .await;
+    store
+}
+
+fn kinds(report: &simplezarr::validate::ValidationReport) -> Vec<IssueKind> {
+    report.issues.iter().map(|issue| issue.kind).collect()
+}
+
+/// Backend reporting the MD5 of `[1, 2, 3, 4]` as every key's ETag.
+struct Md5Etags(ObjectStoreBackend);
+
+#[async_trait]
+impl StorageBackend for Md5Etags {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        self.0.get(path).await
+    }
+
+    async fn list(&self, prefix: &str) -> ZarrResult<Vec<String>> {
+        self.0.list(prefix).await
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        self.0.join(base, segment)
+    }
+
+    async fn get_with_etag(&self, path: &str) -> ZarrResult<Option<(Bytes, Option<String>)>> {
+        let etag = "\"08d6c05a21512a79a1dfeb9d2a8f262f\"".to_string();
+        Ok(self.0.get(path).await?.map(|data| (data, Some(etag))))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Validation
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn valid_groups_pass() {
+    let report = validate_store(valid_store().await, "g").await.unwrap();
+    assert!(report.is_valid(), "{report}");
+    assert!(report.issues.is_empty(), "{report}");
+    assert_eq!(report.arrays_checked, 1);
+    assert_eq!(report.chunks_read, 4);
+    assert_eq!(report.chunks_missing, 0);
+
+    let report = validate_store(valid_store().await, "g/temp").await.unwrap();
+    assert!(report.issues.is_empty(), "{report}");
+    assert_eq!(report.arrays_checked, 1);
+}
+
+#[tokio::test]
+async fn metadata_is_checked_against_the_spec() {
+    let store = valid_store().await;
+    let mut document = zarray(&[4, 4], &[2, 2]);
+    document.as_object_mut().unwrap().remove("filters");
+    document["extra"] = json!(1);
+    put_json(&store, "g/temp/.zarray", &document).await;
+    put_json(&store, "g/temp/.zattrs", &json!([1])).await;
+
+    let report = validate_store(store, "g/temp").await.unwrap();
+    assert!(!report.is_valid());
+    let found = kinds(&report);
+    assert!(found.contains(&IssueKind::MissingField), "{report}");
+    assert!(found.contains(&IssueKind::UnknownField), "{report}");
+    assert!(found.contains(&IssueKind::InvalidMetadata), "{report}");
+
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let report = validate_store(store, "nothing").await.unwrap();
+    assert_eq!(kinds(&report), [IssueKind::MissingMetadata]);
+}
+
+#[tokio::test]
+async fn stale_consolidated_metadata_is_reported() {
+    let store = valid_store().await;
+    put_json(&store, "g/temp/.zattrs", &json!({"units": "K"})).await;
+    let report = validate_store(store, "g").await.unwrap();
+    assert_eq!(kinds(&report), [IssueKind::StaleConsolidated], "{report}");
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["issues"][0]["kind"], "stale_consolidated");
+    assert_eq!(json["issues"][0]["severity"], "error");
+    assert_eq!(json["issues"][0]["key"], "g/.zmetadata");
+}
+
+#[tokio::test]
+async fn sampled_chunks_are_decoded() {
+    let store = valid_store().await;
+    store
+        .put("g/temp/1.1", Bytes::from_static(&[1, 2, 3]))
+        .await
+        .unwrap();
+    let report = validate_store(store.clone(), "g").await.unwrap();
+    assert_eq!(kinds(&report), [IssueKind::ChunkDecode], "{report}");
+    assert_eq!(report.issues[0].key, "g/temp/1.1");
+
+    let options = ValidateOptions::default().sample_chunks(0);
+    let report = validate_store_with_options(store, "g", &options, config::global())
+        .await
+        .unwrap();
+    assert!(report.is_valid(), "{report}");
+    assert_eq!(report.chunks_read, 0);
+}
+
+#[tokio::test]
+async fn missing_chunks_warn_when_none_are_stored() {
+    let store = valid_store().await;
+    let mut document = zarray(&[4, 4], &[2, 2]);
+    document["dimension_separator"] = json!("/");
+    put_json(&store, "g/temp/.zarray", &document).await;
+    let report = validate_store(store, "g/temp").await.unwrap();
+    assert!(report.is_valid(), "{report}");
+    assert_eq!(kinds(&report), [IssueKind::NoChunks]);
+    assert_eq!(report.issues[0].severity, Severity::Warning);
+    assert_eq!(report.chunks_missing, 4);
+}
+
+#[tokio::test]
+async fn md5_etags_are_verified() {
+    let inner = valid_store().await;
+    inner
+        .put("g/temp/0.1", Bytes::from_static(&[4, 3, 2, 1]))
+        .await
+        .unwrap();
+    let store = Arc::new(Md5Etags(ObjectStoreBackend::new(
+        Box::new(InMemory::new()),
+        "",
+    )));
+    for key in [
+        "g/.zgroup",
+        "g/.zmetadata",
+        "g/temp/.zarray",
+        "g/temp/.zattrs",
+        "g/temp/0.0",
+        "g/temp/0.1",
+        "g/temp/1.0",
+        "g/temp/1.1",
+    ] {
+        let value = inner.get(key).await.unwrap().unwrap();
+        store.0.put(key, value).await.unwrap();
+    }
+
+    let report = validate_store(store.clone(), "g").await.unwrap();
+    assert_eq!(report.checksums_verified, 4);
+    assert_eq!(kinds(&report), [IssueKind::ChecksumMismatch], "{report}");
+    assert_eq!(report.issues[0].key, "g/temp/0.1");
+
+    let options = ValidateOptions::default().verify_etags(false);
+    let report = validate_store_with_options(store, "g", &options, config::global())
+        .await
+        .unwrap();
+    assert!(report.issues.is_empty(), "{report}");
+    assert_eq!(report.checksums_verified, 0);
+}
+
+#[tokio::test]
+async fn v3_arrays_are_checked() {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let zarr_json = json!({
+        "zarr_format": 3,
+        "node_type": "array",
+        "shape": [4],
+        "data_type": "uint8",
+        "chunk_grid": {"name": "regular", "configuration": {"chunk_shape": [2]}},
+        "chunk_key_encoding": {"name": "default"},
+        "fill_value": 0,
+        "codecs": [{"name": "bytes"}],
+    });
+    put_json(&store, "a/zarr.json", &zarr_json).await;
+    store
+        .put("a/c/0", Bytes::from_static(&[1, 2]))
+        .await
+        .unwrap();
+    store.put("a/c/1", Bytes::from_static(&[1])).await.unwrap();
+    let report = validate_store(store.clone(), "a").await.unwrap();
+    assert_eq!(report.chunks_read, 2);
+    assert_eq!(kinds(&report), [IssueKind::ChunkDecode], "{report}");
+    assert_eq!(report.issues[0].key, "a/c/1");
+
+    let mut unsupported = zarr_json.clone();
+    unsupported["codecs"] = json!([{"name": "bytes"}, {"name": "no-such-codec"}]);
+    put_json(&store, "a/zarr.json", &unsupported).await;
+    let report = validate_store(store, "a").await.unwrap();
+    assert_eq!(kinds(&report), [IssueKind::Unsupported], "{report}");
+}
+
+#[tokio::test]
+async fn huge_v3_grids_are_sampled_without_listing() {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let zarr_json = |shape: u64, chunks: u64| {
+        json!({
+            "zarr_format": 3,
+            "node_type": "array",
+            "shape": [shape],
+            "data_type": "float64",
+            "chunk_grid": {"name": "regular", "configuration": {"chunk_shape": [chunks]}},
+            "chunk_key_encoding": {"name": "default"},
+            "fill_value": 0.0,
+            "codecs": [{"name": "bytes", "configuration": {"endian": "little"}}],
+        })
+    };
+    // 10^12 single-element chunks: only the sample is ever keyed.
+    put_json(&store, "a/zarr.json", &zarr_json(1_000_000_000_000, 1)).await;
+    let options = ValidateOptions::default().sample_chunks(4);
+    let report = validate_store_with_options(store.clone(), "a", &options, config::global())
+        .await
+        .unwrap();
+    assert_eq!(report.chunks_missing, 4);
+    assert_eq!(kinds(&report), [IssueKind::NoChunks], "{report}");
+
+    // A chunk of 2^62 eight-byte elements can't be addressed.
+    put_json(&store, "a/zarr.json", &zarr_json(1, 1 << 62)).await;
+    let report = validate_store(store, "a").await.unwrap();
+    assert_eq!(kinds(&report), [IssueKind::InvalidMetadata], "{report}");
+}
@@ -0,0 +1,179 @@
+//! Tests for pinning reads to a snapshot with `VersionedStore`.
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use object_store::memory::InMemory;
+
+use simplezarr::builder::ArrayBuilder;
+use simplezarr::error::ZarrError;
+use simplezarr::store::{ObjectStoreBackend, ObjectVersion, StorageBackend};
+use simplezarr::types::{DataType, ZarrVectorValue};
+use simplezarr::v2;
+use simplezarr::versioned::{VersionManifest, VersionedStore};
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn memory_store() -> Arc<ObjectStoreBackend> {
+    Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""))
+}
+
+/// A writable store and a second backend over the same objects, for a
+/// `VersionedStore` to read while the first is updated.
+fn shared_stores() -> (Arc<ObjectStoreBackend>, ObjectStoreBackend) {
+    let objects = Arc::new(InMemory::new());
+    (
+        Arc::new(ObjectStoreBackend::new(Box::new(objects.clone()), "")),
+        ObjectStoreBackend::new(Box::new(objects), ""),
+    )
+}
+
+/// A 4-element `int32` array at `a` with chunks of 2; only chunk `0` is
+/// written.
+async fn half_written(store: &Arc<ObjectStoreBackend>) {
+    let array = ArrayBuilder::new([4])
+        .chunks([2])
+        .dtype(DataType::Int32)
+        .build(store.clone(), "a")
+        .await
+        .unwrap();
+    array
+        .write_chunk(&[0], &ZarrVectorValue::VInt32(vec![1, 2]))
+        .await
+        .unwrap();
+}
+
+async fn overwrite(store: &Arc<ObjectStoreBackend>, chunk: usize, values: Vec<i32>) {
+    let array = v2::open(store.clone(), "a").await.unwrap();
+    array
+        .write_chunk(&[chunk], &ZarrVectorValue::VInt32(values))
+        .await
+        .unwrap();
+}
+
+// ---------------------------------------------------------------------------
+// Manifests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn arrays_are_captured_with_every_chunk_key() {
+    let store = memory_store();
+    half_written(&store).await;
+    let manifest = VersionManifest::capture_array(store.as_ref(), "a")
+        .await
+        .unwrap();
+
+    let keys: Vec<&str> = manifest.entries.keys().map(String::as_str).collect();
+    assert_eq!(keys, ["a/.zarray", "a/.zattrs", "a/0", "a/1"]);
+    assert!(manifest.entries["a/.zarray"].is_some());
+    assert_eq!(
+        manifest.entries["a/0"]
+            .as_ref()
+            .and_then(|v| v.etag.clone()),
+        store.etag("a/0").await.unwrap()
+    );
+    assert_eq!(manifest.entries["a/1"], None);
+
+    let json = serde_json::to_string(&manifest).unwrap();
+    assert_eq!(
+        serde_json::from_str::<VersionManifest>(&json).unwrap(),
+        manifest
+    );
+}
+
+#[tokio::test]
+async fn consolidated_groups_pin_their_arrays_chunks() {
+    let store = memory_store();
+    half_written(&store).await;
+    let zarray: serde_json::Value =
+        serde_json::from_slice(&store.get("a/.zarray").await.unwrap().unwrap()).unwrap();
+    let zmetadata = serde_json::json!({
+        "zarr_consolidated_format": 1,
+        "metadata": {".zgroup": {"zarr_format": 2}, "a/.zarray": zarray},
+    });
+    store
+        .put(".zmetadata", serde_json::to_vec(&zmetadata).unwrap().into())
+        .await
+        .unwrap();
+
+    let manifest = VersionManifest::capture_group(store.as_ref(), "")
+        .await
+        .unwrap();
+    let keys: Vec<&str> = manifest.entries.keys().map(String::as_str).collect();
+    assert_eq!(keys, [".zmetadata", "a/0", "a/1"]);
+
+    let err = VersionManifest::capture_group(store.as_ref(), "a")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::NotFound(_)), "{err:?}");
+}
+
+// ---------------------------------------------------------------------------
+// VersionedStore
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn captured_snapshots_hide_later_writes() {
+    let (store, reader) = shared_stores();
+    half_written(&store).await;
+    let manifest = VersionManifest::capture_array(&reader, "a").await.unwrap();
+    let snapshot = Arc::new(VersionedStore::with_manifest(reader, manifest));
+    let array = v2::open(snapshot.clone(), "a").await.unwrap();
+
+    // Chunk 1 was missing when the snapshot was taken.
+    overwrite(&store, 1, vec![3, 4]).await;
+    assert_eq!(array.load().await.unwrap(), vec![1.0, 2.0, 0.0, 0.0]);
+
+    // In-memory stores keep no old versions, so a changed chunk fails the
+    // read instead of mixing versions.
+    overwrite(&store, 0, vec![5, 6]).await;
+    let err = array.load().await.unwrap_err();
+    assert!(matches!(err, ZarrError::Other(_)), "{err:?}");
+    assert!(snapshot.put("a/0", Bytes::from_static(b"x")).await.is_err());
+}
+
+#[tokio::test]
+async fn keys_are_pinned_when_first_read() {
+    let (store, reader) = shared_stores();
+    half_written(&store).await;
+    let snapshot = VersionedStore::new(reader);
+    assert!(snapshot.manifest().is_empty());
+
+    let first = snapshot.get("a/0").await.unwrap();
+    assert!(first.is_some());
+    assert_eq!(snapshot.get("a/1").await.unwrap(), None);
+    assert_eq!(snapshot.manifest().len(), 2);
+    assert_eq!(
+        snapshot.head("a/0").await.unwrap().map(|m| m.size),
+        first.as_ref().map(|b| b.len() as u64)
+    );
+
+    overwrite(&store, 1, vec![3, 4]).await;
+    assert_eq!(snapshot.get("a/1").await.unwrap(), None);
+    overwrite(&store, 0, vec![5, 6]).await;
+    assert!(matches!(
+        snapshot.get("a/0").await,
+        Err(ZarrError::Other(_))
+    ));
+}
+
+#[tokio::test]
+async fn pins_without_an_etag_accept_any_value() {
+    let store = memory_store();
+    store.put("k", Bytes::from_static(b"v1")).await.unwrap();
+    let pin = ObjectVersion::default();
+    assert_eq!(
+        store.get_pinned("k", &pin).await.unwrap().as_deref(),
+        Some(&b"v1"[..])
+    );
+    let pin = ObjectVersion {
+        etag: Some("\"stale\"".into()),
+        version: None,
+    };
+    assert!(matches!(
+        store.get_pinned("k", &pin).await,
+        Err(ZarrError::Other(_))
+    ));
+}
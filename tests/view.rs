@@ -0,0 +1,133 @@
+//! Tests for lazy array views.
+
+use std::sync::Arc;
+
+use object_store::memory::InMemory;
+
+use simplezarr::builder::ArrayBuilder;
+use simplezarr::store::ObjectStoreBackend;
+use simplezarr::types::{ArrayOrder, DataType, ZarrVectorValue};
+use simplezarr::{ArrayView, UnifiedZarrArray};
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// A 3x4 `f8` array with 2x3 chunks whose element (i, j) is `10 * i + j`.
+async fn grid(order: ArrayOrder) -> UnifiedZarrArray {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    let array = ArrayBuilder::new([3, 4])
+        .chunks([2, 3])
+        .dtype(DataType::Float64)
+        .codec(None)
+        .order(order)
+        .build(store, "grid")
+        .await
+        .unwrap();
+    let values: Vec<f64> = match order {
+        ArrayOrder::C => (0..3)
+            .flat_map(|i| (0..4).map(move |j| (10 * i + j) as f64))
+            .collect(),
+        ArrayOrder::F => (0..4)
+            .flat_map(|j| (0..3).map(move |i| (10 * i + j) as f64))
+            .collect(),
+    };
+    array
+        .set_region(&array.full_region(), &ZarrVectorValue::VFloat64(values))
+        .await
+        .unwrap();
+    array
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn slices_and_indexes_compose() {
+    let view = grid(ArrayOrder::C).await.view();
+    assert_eq!(view.shape(), vec![3, 4]);
+    assert_eq!(view.read().await.unwrap().len(), 12);
+
+    let view = view.slice(0, 1..3).unwrap().slice(1, 1..4).unwrap();
+    assert_eq!(view.shape(), vec![2, 3]);
+    assert_eq!(
+        view.read().await.unwrap(),
+        vec![11.0, 12.0, 13.0, 21.0, 22.0, 23.0]
+    );
+    // Slices are relative to the view.
+    let row = view.clone().index(0, 1).unwrap().slice(0, 1..3).unwrap();
+    assert_eq!(row.shape(), vec![2]);
+    assert_eq!(row.read().await.unwrap(), vec![22.0, 23.0]);
+    assert_eq!(view.read_region(&[0..1, 2..3]).await.unwrap(), vec![13.0]);
+
+    assert!(view.clone().slice(1, 2..4).is_err());
+    assert!(view.clone().slice(2, 0..1).is_err());
+    assert!(view.clone().squeeze(0).is_err());
+    assert!(view.read_region(&[0..3, 0..1]).await.is_err());
+}
+
+#[tokio::test]
+async fn transposes_read_in_view_order() {
+    for order in [ArrayOrder::C, ArrayOrder::F] {
+        let view = grid(order).await.view();
+        let t = view.clone().transpose(&[1, 0]).unwrap();
+        assert_eq!(t.shape(), vec![4, 3]);
+        let want: Vec<f64> = (0..4)
+            .flat_map(|j| (0..3).map(move |i| (10 * i + j) as f64))
+            .collect();
+        assert_eq!(t.read().await.unwrap(), want, "{order:?}");
+
+        // Plain reads are row-major whatever the array's order.
+        let want: Vec<f64> = (0..3)
+            .flat_map(|i| (0..4).map(move |j| (10 * i + j) as f64))
+            .collect();
+        assert_eq!(view.read().await.unwrap(), want, "{order:?}");
+    }
+
+    let view = grid(ArrayOrder::C).await.view();
+    assert!(view.clone().transpose(&[0, 0]).is_err());
+    assert!(view.transpose(&[0]).is_err());
+}
+
+#[tokio::test]
+async fn inserted_and_broadcast_axes_repeat() {
+    let column = grid(ArrayOrder::C)
+        .await
+        .view()
+        .index(1, 2)
+        .unwrap()
+        .expand_dims(1)
+        .unwrap();
+    assert_eq!(column.shape(), vec![3, 1]);
+    assert_eq!(column.read().await.unwrap(), vec![2.0, 12.0, 22.0]);
+
+    let wide = column.clone().broadcast_to(&[2, 3, 2]).unwrap();
+    assert_eq!(wide.shape(), vec![2, 3, 2]);
+    let plane = [2.0, 2.0, 12.0, 12.0, 22.0, 22.0];
+    assert_eq!(wide.read().await.unwrap(), [plane, plane].concat());
+    assert_eq!(
+        wide.clone()
+            .slice(2, 0..1)
+            .unwrap()
+            .squeeze(2)
+            .unwrap()
+            .shape(),
+        vec![2, 3]
+    );
+
+    assert!(column.clone().broadcast_to(&[2, 2]).is_err());
+    assert!(column.clone().broadcast_to(&[3]).is_err());
+    assert!(column.expand_dims(3).is_err());
+
+    let view = ArrayView::new(Arc::new(grid(ArrayOrder::C).await));
+    assert_eq!(view.ndim(), 2);
+    assert!(
+        view.slice(0, 1..1)
+            .unwrap()
+            .read()
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
@@ -0,0 +1,567 @@
+//! Tests for chunk writing.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use object_store::memory::InMemory;
+
+use simplezarr::array::decode_chunk_bytes;
+use simplezarr::builder::{ArrayBuilder, gzip};
+use simplezarr::config::SimplezarrConfig;
+use simplezarr::error::{ZarrError, ZarrResult};
+use simplezarr::options::{ArrayReadOptions, ArrayWriteOptions};
+use simplezarr::store::{LocalBackend, ObjectMeta, ObjectStoreBackend, StorageBackend};
+use simplezarr::types::{DataType, ZarrVectorValue};
+use simplezarr::v2;
+use simplezarr::write::{WriteOutcome, encode_chunk_bytes};
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// MD5 of the bytes `[1, 2, 3, 4]`, chunk `0` of [`put_array`] below.
+const MD5_1234: &str = "08d6c05a21512a79a1dfeb9d2a8f262f";
+
+/// Backend wrapper counting gets and puts, optionally reporting `etag` for
+/// every key it holds.
+struct Recording<B> {
+    inner: B,
+    etag: Option<String>,
+    gets: AtomicUsize,
+    puts: AtomicUsize,
+}
+
+impl<B> Recording<B> {
+    fn new(inner: B, etag: Option<&str>) -> Self {
+        Self {
+            inner,
+            etag: etag.map(str::to_string),
+            gets: AtomicUsize::new(0),
+            puts: AtomicUsize::new(0),
+        }
+    }
+
+    fn gets(&self) -> usize {
+        self.gets.load(Ordering::SeqCst)
+    }
+
+    fn puts(&self) -> usize {
+        self.puts.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl<B: StorageBackend> StorageBackend for Recording<B> {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        self.gets.fetch_add(1, Ordering::SeqCst);
+        self.inner.get(path).await
+    }
+
+    async fn list(&self, prefix: &str) -> ZarrResult<Vec<String>> {
+        self.inner.list(prefix).await
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        self.inner.join(base, segment)
+    }
+
+    async fn head(&self, path: &str) -> ZarrResult<Option<ObjectMeta>> {
+        let meta = self.inner.head(path).await?;
+        Ok(match &self.etag {
+            Some(etag) => meta.map(|m| ObjectMeta {
+                etag: Some(format!("\"{etag}\"")),
+                ..m
+            }),
+            None => meta,
+        })
+    }
+
+    async fn put(&self, path: &str, data: Bytes) -> ZarrResult<()> {
+        self.puts.fetch_add(1, Ordering::SeqCst);
+        self.inner.put(path, data).await
+    }
+
+    async fn delete(&self, path: &str) -> ZarrResult<()> {
+        self.inner.delete(path).await
+    }
+}
+
+/// Backend that can be read but not written.
+struct ReadOnly(ObjectStoreBackend);
+
+#[async_trait]
+impl StorageBackend for ReadOnly {
+    async fn get(&self, path: &str) -> ZarrResult<Option<Bytes>> {
+        self.0.get(path).await
+    }
+
+    async fn list(&self, prefix: &str) -> ZarrResult<Vec<String>> {
+        self.0.list(prefix).await
+    }
+
+    fn join(&self, base: &str, segment: &str) -> String {
+        self.0.join(base, segment)
+    }
+}
+
+fn memory() -> ObjectStoreBackend {
+    ObjectStoreBackend::new(Box::new(InMemory::new()), "")
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("simplezarr-write-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// Put the metadata of an empty 8-element uncompressed `|u1` array with
+/// 4-element chunks and fill value 0 at `arr`.
+async fn put_array(store: &impl StorageBackend) {
+    put_array_with(store, "|u1", serde_json::json!(0)).await;
+}
+
+/// [`put_array`] with another dtype and fill value.
+async fn put_array_with(store: &impl StorageBackend, dtype: &str, fill: serde_json::Value) {
+    let zarray = serde_json::json!({
+        "zarr_format": 2,
+        "shape": [8],
+        "chunks": [4],
+        "dtype": dtype,
+        "fill_value": fill,
+        "order": "C",
+        "compressor": null,
+        "filters": null,
+    });
+    store
+        .put("arr/.zarray", serde_json::to_vec(&zarray).unwrap().into())
+        .await
+        .unwrap();
+}
+
+fn u8s(values: &[u8]) -> ZarrVectorValue {
+    ZarrVectorValue::VUInt8(values.to_vec())
+}
+
+// ---------------------------------------------------------------------------
+// write_chunk
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn written_chunks_read_back() {
+    let store = Arc::new(memory());
+    put_array(store.as_ref()).await;
+    let config = SimplezarrConfig {
+        chunk_cache_bytes: 1 << 20,
+        ..SimplezarrConfig::default()
+    };
+    let arr = v2::open_with_config(store.clone(), "arr", Arc::new(config))
+        .await
+        .unwrap();
+    assert_eq!(arr.load().await.unwrap(), vec![0.0; 8]);
+
+    let outcome = arr.write_chunk(&[1], &u8s(&[5, 6, 7, 8])).await.unwrap();
+    assert_eq!(outcome, WriteOutcome::Written);
+    assert_eq!(
+        store.get("arr/1").await.unwrap().as_deref(),
+        Some(&[5, 6, 7, 8][..])
+    );
+    // The cached fill chunk was dropped.
+    assert_eq!(
+        arr.load().await.unwrap(),
+        vec![0.0, 0.0, 0.0, 0.0, 5.0, 6.0, 7.0, 8.0]
+    );
+}
+
+#[tokio::test]
+async fn bad_writes_are_rejected() {
+    let store = Arc::new(memory());
+    put_array(store.as_ref()).await;
+    let arr = v2::open(store.clone(), "arr").await.unwrap();
+    let err = arr.write_chunk(&[0], &u8s(&[1, 2, 3])).await.unwrap_err();
+    assert!(matches!(err, ZarrError::Encode(_)), "{err:?}");
+    let err = arr
+        .write_chunk(&[2], &u8s(&[1, 2, 3, 4]))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::NotFound(_)), "{err:?}");
+
+    let read_only = Arc::new(ReadOnly(memory()));
+    put_array(&read_only.0).await;
+    let arr = v2::open(read_only, "arr").await.unwrap();
+    let err = arr
+        .write_chunk(&[0], &u8s(&[1, 2, 3, 4]))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("read-only"), "{err}");
+}
+
+#[tokio::test]
+async fn encoded_chunks_match_written_ones() {
+    let store = Arc::new(memory());
+    let arr = ArrayBuilder::new([8])
+        .chunks([4])
+        .dtype(DataType::Int16)
+        .codec(gzip(5))
+        .build(store.clone(), "gz")
+        .await
+        .unwrap();
+    let values = ZarrVectorValue::VInt16(vec![-1, 2, -3, 4]);
+    let encoded = encode_chunk_bytes(&arr.metadata, &values).await.unwrap();
+    arr.write_chunk(&[1], &values).await.unwrap();
+    assert_eq!(
+        store.get("gz/1").await.unwrap().as_deref(),
+        Some(&encoded[..])
+    );
+    let decoded = decode_chunk_bytes(
+        &arr.metadata,
+        &[1],
+        Some(&encoded),
+        &ArrayReadOptions::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(decoded.to_f64_vec().unwrap(), vec![-1.0, 2.0, -3.0, 4.0]);
+
+    // A fill-only chunk is encoded all the same.
+    let zeros = ZarrVectorValue::VInt16(vec![0; 4]);
+    assert!(
+        !encode_chunk_bytes(&arr.metadata, &zeros)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let short = ZarrVectorValue::VInt16(vec![1, 2, 3]);
+    let err = encode_chunk_bytes(&arr.metadata, &short).await.unwrap_err();
+    assert!(matches!(err, ZarrError::Encode(_)), "{err:?}");
+}
+
+// ---------------------------------------------------------------------------
+// Deduplication
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn writes_are_always_sent_without_dedup() {
+    let store = Arc::new(Recording::new(memory(), Some(MD5_1234)));
+    put_array(store.as_ref()).await;
+    let arr = v2::open(store.clone(), "arr").await.unwrap();
+    for _ in 0..2 {
+        let outcome = arr.write_chunk(&[0], &u8s(&[1, 2, 3, 4])).await.unwrap();
+        assert_eq!(outcome, WriteOutcome::Written);
+    }
+    assert_eq!(store.puts(), 3);
+}
+
+#[tokio::test]
+async fn dedup_trusts_an_md5_etag() {
+    let store = Arc::new(Recording::new(memory(), Some(MD5_1234)));
+    put_array(store.as_ref()).await;
+    let arr = v2::open(store.clone(), "arr")
+        .await
+        .unwrap()
+        .with_write_options(ArrayWriteOptions::default().dedup(true));
+    let gets = store.gets();
+
+    let chunk = u8s(&[1, 2, 3, 4]);
+    assert_eq!(
+        arr.write_chunk(&[0], &chunk).await.unwrap(),
+        WriteOutcome::Written
+    );
+    assert_eq!(
+        arr.write_chunk(&[0], &chunk).await.unwrap(),
+        WriteOutcome::Unchanged
+    );
+    assert_eq!(store.puts(), 2);
+    assert_eq!(store.gets(), gets);
+}
+
+/// Write the same chunk twice, then a same-sized different one, to an array
+/// in `store` whose ETags (if any) are not MD5s.
+async fn check_content_dedup<B: StorageBackend + 'static>(store: Arc<Recording<B>>) {
+    put_array(store.as_ref()).await;
+    let arr = v2::open(store.clone(), "arr")
+        .await
+        .unwrap()
+        .with_write_options(ArrayWriteOptions::default().dedup(true));
+    let gets = store.gets();
+
+    let chunk = u8s(&[1, 2, 3, 4]);
+    assert_eq!(
+        arr.write_chunk(&[0], &chunk).await.unwrap(),
+        WriteOutcome::Written
+    );
+    assert_eq!(
+        arr.write_chunk(&[0], &chunk).await.unwrap(),
+        WriteOutcome::Unchanged
+    );
+    assert_eq!(store.gets(), gets + 1);
+    assert_eq!(
+        arr.write_chunk(&[0], &u8s(&[4, 3, 2, 1])).await.unwrap(),
+        WriteOutcome::Written
+    );
+    assert_eq!(store.puts(), 3);
+}
+
+#[tokio::test]
+async fn dedup_compares_content_when_the_etag_is_not_an_md5() {
+    // Local files have no ETag; in-memory ETags are opaque counters.
+    let dir = temp_dir("dedup");
+    check_content_dedup(Arc::new(Recording::new(LocalBackend::new(&dir), None))).await;
+    check_content_dedup(Arc::new(Recording::new(memory(), None))).await;
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// ---------------------------------------------------------------------------
+// Empty chunks
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn fill_only_chunks_are_not_stored() {
+    let store = Arc::new(Recording::new(memory(), None));
+    put_array(store.as_ref()).await;
+    let arr = v2::open(store.clone(), "arr").await.unwrap();
+    assert_eq!(
+        arr.write_chunk(&[0], &u8s(&[0, 0, 0, 0])).await.unwrap(),
+        WriteOutcome::Empty
+    );
+    assert_eq!(store.puts(), 1);
+    assert_eq!(store.inner.get("arr/0").await.unwrap(), None);
+
+    // Overwriting a stored chunk with fill removes it.
+    arr.write_chunk(&[1], &u8s(&[1, 0, 0, 0])).await.unwrap();
+    assert!(store.inner.get("arr/1").await.unwrap().is_some());
+    assert_eq!(
+        arr.write_chunk(&[1], &u8s(&[0, 0, 0, 0])).await.unwrap(),
+        WriteOutcome::Empty
+    );
+    assert_eq!(store.inner.get("arr/1").await.unwrap(), None);
+    assert_eq!(arr.load().await.unwrap(), vec![0.0; 8]);
+
+    let arr = arr.with_write_options(ArrayWriteOptions::default().write_empty_chunks(true));
+    assert_eq!(
+        arr.write_chunk(&[0], &u8s(&[0, 0, 0, 0])).await.unwrap(),
+        WriteOutcome::Written
+    );
+    assert!(store.inner.get("arr/0").await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn fill_values_compare_bitwise() {
+    let store = Arc::new(memory());
+    put_array_with(store.as_ref(), "<f4", serde_json::json!("NaN")).await;
+    let arr = v2::open(store.clone(), "arr").await.unwrap();
+    let nan = ZarrVectorValue::VFloat32(vec![f32::NAN; 4]);
+    assert_eq!(
+        arr.write_chunk(&[0], &nan).await.unwrap(),
+        WriteOutcome::Empty
+    );
+    // Skipped chunks read back as the fill value.
+    assert!(arr.load().await.unwrap().iter().all(|v| v.is_nan()));
+
+    let store = Arc::new(memory());
+    put_array_with(store.as_ref(), "<f4", serde_json::json!(0.0)).await;
+    let arr = v2::open(store.clone(), "arr").await.unwrap();
+    let negative_zero = ZarrVectorValue::VFloat32(vec![-0.0; 4]);
+    assert_eq!(
+        arr.write_chunk(&[0], &negative_zero).await.unwrap(),
+        WriteOutcome::Written
+    );
+}
+
+#[tokio::test]
+async fn prune_removes_stored_fill_chunks() {
+    let dir = temp_dir("prune");
+    let store = Arc::new(LocalBackend::new(&dir));
+    put_array(store.as_ref()).await;
+    let arr = v2::open(store.clone(), "arr")
+        .await
+        .unwrap()
+        .with_write_options(ArrayWriteOptions::default().write_empty_chunks(true));
+    arr.write_chunk(&[0], &u8s(&[0, 0, 0, 0])).await.unwrap();
+    arr.write_chunk(&[1], &u8s(&[0, 7, 0, 0])).await.unwrap();
+
+    assert_eq!(arr.prune_empty_chunks().await.unwrap(), 1);
+    assert!(!dir.join("arr/0").exists());
+    assert!(dir.join("arr/1").exists());
+    assert_eq!(
+        arr.load().await.unwrap(),
+        vec![0.0, 0.0, 0.0, 0.0, 0.0, 7.0, 0.0, 0.0]
+    );
+    assert_eq!(arr.prune_empty_chunks().await.unwrap(), 0);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// ---------------------------------------------------------------------------
+// Selection writes
+// ---------------------------------------------------------------------------
+
+/// A 5x4 `<i4` array with 2x3 chunks and fill value -1 at `grid`.
+async fn grid(store: Arc<Recording<ObjectStoreBackend>>) -> simplezarr::UnifiedZarrArray {
+    let zarray = serde_json::json!({
+        "zarr_format": 2,
+        "shape": [5, 4],
+        "chunks": [2, 3],
+        "dtype": "<i4",
+        "fill_value": -1,
+        "order": "C",
+        "compressor": null,
+        "filters": null,
+    });
+    store
+        .put("grid/.zarray", serde_json::to_vec(&zarray).unwrap().into())
+        .await
+        .unwrap();
+    v2::open(store, "grid").await.unwrap()
+}
+
+#[tokio::test]
+async fn set_region_reads_only_partially_covered_chunks() {
+    let store = Arc::new(Recording::new(memory(), None));
+    let arr = grid(store.clone()).await;
+    let gets = store.gets();
+
+    // Every chunk is covered, edge chunks included, so nothing is read.
+    arr.set_region(
+        &arr.full_region(),
+        &ZarrVectorValue::VInt32((0..20).collect()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(store.gets(), gets);
+    assert_eq!(store.puts(), 1 + 6);
+
+    // Rows 1..3, columns 2..4 touch four chunks, none of them fully.
+    arr.set_region(
+        &[1..3, 2..4],
+        &ZarrVectorValue::VInt32(vec![100, 101, 102, 103]),
+    )
+    .await
+    .unwrap();
+    assert_eq!(store.gets(), gets + 4);
+    let expected: Vec<f64> = (0..20)
+        .map(|i| match i {
+            6 => 100,
+            7 => 101,
+            10 => 102,
+            11 => 103,
+            _ => i,
+        })
+        .map(f64::from)
+        .collect();
+    assert_eq!(arr.load().await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn set_points_updates_each_chunk_once() {
+    let store = Arc::new(Recording::new(memory(), None));
+    let arr = grid(store.clone()).await;
+    let puts = store.puts();
+    arr.set_points(
+        &[vec![0, 0], vec![4, 3], vec![0, 1], vec![0, 0]],
+        &ZarrVectorValue::VInt32(vec![7, 8, 9, 10]),
+    )
+    .await
+    .unwrap();
+    assert_eq!(store.puts(), puts + 2);
+
+    let mut expected = vec![-1.0; 20];
+    expected[0] = 10.0;
+    expected[1] = 9.0;
+    expected[19] = 8.0;
+    assert_eq!(arr.load().await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn bad_selection_writes_are_rejected() {
+    let arr = grid(Arc::new(Recording::new(memory(), None))).await;
+    let err = arr
+        .set_region(&[0..2, 0..2], &ZarrVectorValue::VInt32(vec![1, 2, 3]))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::Encode(_)), "{err:?}");
+    let err = arr
+        .set_region(&[0..6, 0..1], &ZarrVectorValue::VInt32(vec![0; 6]))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::Other(_)), "{err:?}");
+    let err = arr
+        .set_region(&[0..1, 0..1], &ZarrVectorValue::VFloat64(vec![1.0]))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::TypeConversion(_)), "{err:?}");
+
+    let err = arr
+        .set_points(&[vec![5, 0]], &ZarrVectorValue::VInt32(vec![1]))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::Other(_)), "{err:?}");
+    let err = arr
+        .set_points(&[vec![0, 0]], &ZarrVectorValue::VInt32(vec![]))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::Encode(_)), "{err:?}");
+}
+
+// ---------------------------------------------------------------------------
+// Truncating and deleting
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn truncate_drops_chunks_outside_the_new_shape() {
+    let store = Arc::new(Recording::new(memory(), None));
+    let mut arr = grid(store.clone()).await;
+    arr.set_region(
+        &arr.full_region(),
+        &ZarrVectorValue::VInt32((0..20).collect()),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(arr.truncate(&[3, 2]).await.unwrap(), 4);
+    assert_eq!(arr.metadata.shape, vec![3, 2]);
+    assert_eq!(
+        arr.load().await.unwrap(),
+        vec![0.0, 1.0, 4.0, 5.0, 8.0, 9.0]
+    );
+    for key in ["0.1", "1.1", "2.0", "2.1"] {
+        assert_eq!(store.get(&format!("grid/{key}")).await.unwrap(), None);
+    }
+    assert!(store.get("grid/1.0").await.unwrap().is_some());
+
+    let reopened = v2::open(store.clone(), "grid").await.unwrap();
+    assert_eq!(reopened.metadata.shape, vec![3, 2]);
+    assert_eq!(reopened.metadata.keys, vec!["0.0", "1.0"]);
+
+    for bad in [&[4, 2][..], &[3][..]] {
+        let err = arr.truncate(bad).await.unwrap_err();
+        assert!(matches!(err, ZarrError::Metadata(_)), "{err:?}");
+    }
+}
+
+#[tokio::test]
+async fn delete_array_removes_chunks_and_metadata() {
+    let store = Arc::new(Recording::new(memory(), None));
+    let arr = grid(store.clone()).await;
+    arr.set_region(&[0..2, 0..3], &ZarrVectorValue::VInt32((0..6).collect()))
+        .await
+        .unwrap();
+    store
+        .put("grid/.zattrs", Bytes::from_static(b"{}"))
+        .await
+        .unwrap();
+    store
+        .put("other/.zarray", Bytes::from_static(b"{}"))
+        .await
+        .unwrap();
+
+    v2::delete_array(store.clone(), "grid").await.unwrap();
+    for key in ["grid/.zarray", "grid/.zattrs", "grid/0.0"] {
+        assert_eq!(store.get(key).await.unwrap(), None, "{key}");
+    }
+    assert!(store.get("other/.zarray").await.unwrap().is_some());
+    let err = v2::open(store.clone(), "grid").await.unwrap_err();
+    assert!(matches!(err, ZarrError::NotFound(_)), "{err:?}");
+}
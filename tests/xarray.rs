@@ -0,0 +1,236 @@
+//! Tests for writing xarray's group conventions.
+
+use std::sync::Arc;
+
+use object_store::memory::InMemory;
+use serde_json::json;
+
+use simplezarr::error::ZarrError;
+use simplezarr::store::{ObjectStoreBackend, StorageBackend};
+use simplezarr::v2;
+use simplezarr::xarray::write_xarray_conventions;
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn zarray(shape: &[usize]) -> serde_json::Value {
+    json!({
+        "zarr_format": 2,
+        "shape": shape,
+        "chunks": shape,
+        "dtype": "<f4",
+        "fill_value": null,
+        "order": "C",
+        "compressor": null,
+        "filters": null,
+    })
+}
+
+async fn put_json(store: &ObjectStoreBackend, key: &str, value: &serde_json::Value) {
+    store
+        .put(key, serde_json::to_vec(value).unwrap().into())
+        .await
+        .unwrap();
+}
+
+async fn json(store: &ObjectStoreBackend, key: &str) -> serde_json::Value {
+    serde_json::from_slice(&store.get(key).await.unwrap().unwrap()).unwrap()
+}
+
+/// Group `g` with `time` (2), 2-D `lat` and `lon` on (y, x) and
+/// `t2m` on (time, y, x); only `t2m` names its dimensions.
+async fn forecast_store() -> Arc<ObjectStoreBackend> {
+    let store = Arc::new(ObjectStoreBackend::new(Box::new(InMemory::new()), ""));
+    put_json(&store, "g/time/.zarray", &zarray(&[2])).await;
+    for name in ["lat", "lon"] {
+        put_json(&store, &format!("g/{name}/.zarray"), &zarray(&[3, 4])).await;
+        put_json(
+            &store,
+            &format!("g/{name}/.zattrs"),
+            &json!({"_ARRAY_DIMENSIONS": ["y", "x"]}),
+        )
+        .await;
+    }
+    put_json(&store, "g/t2m/.zarray", &zarray(&[2, 3, 4])).await;
+    put_json(
+        &store,
+        "g/t2m/.zattrs",
+        &json!({"_ARRAY_DIMENSIONS": ["time", "y", "x"], "units": "K"}),
+    )
+    .await;
+    put_json(&store, "g/.zattrs", &json!({"title": "forecast"})).await;
+    store
+}
+
+// ---------------------------------------------------------------------------
+// Conventions
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn conventions_are_written_and_consolidated() {
+    let store = forecast_store().await;
+    let mut group = v2::open_group(store.clone(), "g", &["time", "lat", "lon", "t2m"])
+        .await
+        .unwrap();
+    write_xarray_conventions(&mut group, &["lat", "lon"])
+        .await
+        .unwrap();
+    assert!(group.is_consolidated());
+
+    assert_eq!(
+        json(&store, "g/time/.zattrs").await,
+        json!({"_ARRAY_DIMENSIONS": ["time"]})
+    );
+    assert_eq!(
+        json(&store, "g/t2m/.zattrs").await,
+        json!({
+            "_ARRAY_DIMENSIONS": ["time", "y", "x"],
+            "units": "K",
+            "coordinates": "lat lon",
+        })
+    );
+    assert!(
+        json(&store, "g/lat/.zattrs")
+            .await
+            .get("coordinates")
+            .is_none()
+    );
+    assert_eq!(json(&store, "g/.zgroup").await, json!({"zarr_format": 2}));
+
+    let zmetadata = json(&store, "g/.zmetadata").await;
+    assert_eq!(zmetadata["zarr_consolidated_format"], 1);
+    assert_eq!(
+        zmetadata["metadata"][".zattrs"],
+        json!({"title": "forecast"})
+    );
+    assert_eq!(zmetadata["metadata"]["t2m/.zarray"], zarray(&[2, 3, 4]));
+    assert_eq!(
+        zmetadata["metadata"]["time/.zattrs"],
+        json!({"_ARRAY_DIMENSIONS": ["time"]})
+    );
+
+    let reopened = v2::open_group(store, "g", &[]).await.unwrap();
+    assert!(reopened.is_consolidated());
+    assert_eq!(reopened.array_names(), ["lat", "lon", "t2m", "time"]);
+    assert!(reopened.coordinate("time").is_some());
+    assert_eq!(
+        reopened
+            .get_array("t2m")
+            .unwrap()
+            .metadata
+            .attributes
+            .as_ref()
+            .unwrap()
+            .get_str("coordinates"),
+        Some("lat lon")
+    );
+}
+
+#[tokio::test]
+async fn existing_coordinates_are_kept() {
+    let store = forecast_store().await;
+    put_json(
+        &store,
+        "g/t2m/.zattrs",
+        &json!({"_ARRAY_DIMENSIONS": ["time", "y", "x"], "coordinates": "lat"}),
+    )
+    .await;
+    let mut group = v2::open_group(store.clone(), "g", &["time", "lat", "lon", "t2m"])
+        .await
+        .unwrap();
+    write_xarray_conventions(&mut group, &["lat", "lon"])
+        .await
+        .unwrap();
+    assert_eq!(json(&store, "g/t2m/.zattrs").await["coordinates"], "lat");
+}
+
+#[tokio::test]
+async fn unnamed_or_conflicting_dimensions_are_rejected() {
+    let store = forecast_store().await;
+    put_json(&store, "g/t2m/.zattrs", &json!({})).await;
+    let mut group = v2::open_group(store.clone(), "g", &["time", "t2m"])
+        .await
+        .unwrap();
+    let err = write_xarray_conventions(&mut group, &[]).await.unwrap_err();
+    assert!(matches!(err, ZarrError::Metadata(_)), "{err}");
+    assert!(store.get("g/.zmetadata").await.unwrap().is_none());
+
+    put_json(
+        &store,
+        "g/t2m/.zattrs",
+        &json!({"_ARRAY_DIMENSIONS": ["time", "y", "x"]}),
+    )
+    .await;
+    put_json(&store, "g/time/.zarray", &zarray(&[5])).await;
+    let mut group = v2::open_group(store.clone(), "g", &["time", "t2m"])
+        .await
+        .unwrap();
+    let err = write_xarray_conventions(&mut group, &[]).await.unwrap_err();
+    assert!(matches!(err, ZarrError::Metadata(_)), "{err}");
+}
+
+#[tokio::test]
+async fn only_named_coordinates_are_listed() {
+    let store = forecast_store().await;
+    put_json(&store, "g/precip_mean/.zarray", &zarray(&[3, 4])).await;
+    put_json(
+        &store,
+        "g/precip_mean/.zattrs",
+        &json!({"_ARRAY_DIMENSIONS": ["y", "x"]}),
+    )
+    .await;
+    let names = ["time", "lat", "lon", "t2m", "precip_mean"];
+    let mut group = v2::open_group(store.clone(), "g", &names).await.unwrap();
+    write_xarray_conventions(&mut group, &["lat"])
+        .await
+        .unwrap();
+    assert_eq!(json(&store, "g/t2m/.zattrs").await["coordinates"], "lat");
+    assert_eq!(
+        json(&store, "g/precip_mean/.zattrs").await["coordinates"],
+        "lat"
+    );
+    assert!(
+        json(&store, "g/lat/.zattrs")
+            .await
+            .get("coordinates")
+            .is_none()
+    );
+
+    let err = write_xarray_conventions(&mut group, &["elevation"])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ZarrError::NotFound(_)), "{err}");
+}
+
+#[tokio::test]
+async fn unopened_nodes_stay_in_the_consolidated_metadata() {
+    let store = forecast_store().await;
+    put_json(&store, "g/sub/.zgroup", &json!({"zarr_format": 2})).await;
+    put_json(&store, "g/sub/extra/.zarray", &zarray(&[4])).await;
+    let mut group = v2::open_group(store.clone(), "g", &["time", "t2m"])
+        .await
+        .unwrap();
+    write_xarray_conventions(&mut group, &[]).await.unwrap();
+
+    let metadata = &json(&store, "g/.zmetadata").await["metadata"];
+    assert_eq!(metadata["lat/.zarray"], zarray(&[3, 4]));
+    assert_eq!(
+        metadata["lat/.zattrs"],
+        json!({"_ARRAY_DIMENSIONS": ["y", "x"]})
+    );
+    assert_eq!(metadata["sub/.zgroup"], json!({"zarr_format": 2}));
+    assert_eq!(metadata["sub/extra/.zarray"], zarray(&[4]));
+    assert!(
+        json(&store, "g/t2m/.zattrs")
+            .await
+            .get("coordinates")
+            .is_none()
+    );
+
+    let reopened = v2::open_group(store, "g", &[]).await.unwrap();
+    assert_eq!(
+        reopened.array_names(),
+        ["lat", "lon", "sub/extra", "t2m", "time"]
+    );
+}
@@ -0,0 +1,168 @@
+"""
+Generate the zarr-python parity fixtures read by `tests/parity.rs`.
+
+Every combination of data type, compressor, memory order and byte order is
+written as a small 5x7 array with ragged 2x3 chunks, next to the exact bytes
+zarr-python reads back (little-endian, in the array's memory order).  The
+Rust test decodes each array and compares bytes, so NaN payloads, signed
+zeros and integer extremes must survive bit for bit.
+
+    cd zarrgentest && uv run python parity.py
+
+writes `../tests/fixtures/parity`, including the `manifest.json` listing
+every case.
+"""
+
+import json
+import shutil
+from pathlib import Path
+
+import numpy as np
+import zarr
+from numcodecs import GZip, LZ4, Blosc, Zlib, Zstd
+from zarr.codecs import BloscCodec, BloscShuffle, BytesCodec, GzipCodec, ZstdCodec
+
+OUTPUT_DIR = Path(__file__).parent.parent / "tests" / "fixtures" / "parity"
+SHAPE = (5, 7)
+CHUNKS = (2, 3)
+
+DTYPES = [
+    "bool",
+    "int8",
+    "int16",
+    "int32",
+    "int64",
+    "uint8",
+    "uint16",
+    "uint32",
+    "uint64",
+    "float16",
+    "float32",
+    "float64",
+    "complex64",
+    "complex128",
+]
+
+V2_COMPRESSORS = {
+    "none": None,
+    "zlib": Zlib(level=1),
+    "gzip": GZip(level=5),
+    "zstd": Zstd(level=3),
+    "lz4": LZ4(acceleration=1),
+    "blosc_lz4": Blosc(cname="lz4", clevel=5, shuffle=Blosc.SHUFFLE),
+    "blosc_zstd_bitshuffle": Blosc(cname="zstd", clevel=3, shuffle=Blosc.BITSHUFFLE),
+    "blosc_blosclz_noshuffle": Blosc(cname="blosclz", clevel=5, shuffle=Blosc.NOSHUFFLE),
+}
+
+V3_COMPRESSORS = {
+    "none": [],
+    "gzip": [GzipCodec(level=5)],
+    "zstd": [ZstdCodec(level=3)],
+    "blosc_lz4": [BloscCodec(cname="lz4", clevel=5, shuffle=BloscShuffle.shuffle)],
+}
+
+
+def values(dtype):
+    """
+    Deterministic test values for `dtype`, including the awkward ones:
+    integer extremes, NaN, infinities and negative zero.
+    """
+    n = SHAPE[0] * SHAPE[1]
+    i = np.arange(n)
+    if dtype == "bool":
+        data = i % 3 == 0
+    elif np.issubdtype(np.dtype(dtype), np.integer):
+        info = np.iinfo(dtype)
+        data = (i * 37 - 500).astype(np.int64).astype(dtype)
+        data[0], data[1] = info.min, info.max
+    else:
+        real = (i * 0.37 - 5).astype(dtype if dtype.startswith("float") else "float64")
+        if dtype.startswith("complex"):
+            data = (real + 1j * real[::-1]).astype(dtype)
+            data[0] = complex(np.nan, -0.0)
+        else:
+            data = real.astype(dtype)
+            data[0], data[1], data[2] = np.nan, np.inf, -0.0
+    return data.reshape(SHAPE)
+
+
+def expected_bytes(data, order):
+    little = data.dtype.newbyteorder("<") if data.dtype.itemsize > 1 else data.dtype
+    return np.asarray(data, dtype=little).ravel(order=order).tobytes()
+
+
+def write_case(cases, name, fmt, dtype, order, create):
+    data = values(dtype)
+    path = OUTPUT_DIR / f"v{fmt}" / name
+    array = create(str(path))
+    array[...] = data
+    np.testing.assert_array_equal(array[...], data)
+    expected = f"v{fmt}/{name}.bin"
+    (OUTPUT_DIR / expected).write_bytes(expected_bytes(array[...], order))
+    cases.append(
+        {
+            "format": fmt,
+            "path": f"v{fmt}/{name}",
+            "expected": expected,
+            "dtype": dtype,
+            "order": order,
+        }
+    )
+
+
+def main():
+    if OUTPUT_DIR.exists():
+        shutil.rmtree(OUTPUT_DIR)
+    (OUTPUT_DIR / "v2").mkdir(parents=True)
+    (OUTPUT_DIR / "v3").mkdir(parents=True)
+
+    cases = []
+    for dtype in DTYPES:
+        byte_orders = ["<"] if np.dtype(dtype).itemsize == 1 else ["<", ">"]
+        for codec_name, compressor in V2_COMPRESSORS.items():
+            for order in ["C", "F"]:
+                for byte_order in byte_orders:
+                    endian = "little" if byte_order == "<" else "big"
+                    stored = np.dtype(dtype).newbyteorder(byte_order)
+                    write_case(
+                        cases,
+                        f"{dtype}_{codec_name}_{order}_{endian}",
+                        2,
+                        dtype,
+                        order,
+                        lambda path: zarr.create_array(
+                            store=path,
+                            shape=SHAPE,
+                            chunks=CHUNKS,
+                            dtype=stored,
+                            compressors=compressor,
+                            order=order,
+                            zarr_format=2,
+                        ),
+                    )
+        for codec_name, compressors in V3_COMPRESSORS.items():
+            for endian in ["little" if b == "<" else "big" for b in byte_orders]:
+                write_case(
+                    cases,
+                    f"{dtype}_{codec_name}_{endian}",
+                    3,
+                    dtype,
+                    "C",
+                    lambda path: zarr.create_array(
+                        store=path,
+                        shape=SHAPE,
+                        chunks=CHUNKS,
+                        dtype=dtype,
+                        serializer=BytesCodec(endian=endian),
+                        compressors=compressors,
+                        zarr_format=3,
+                    ),
+                )
+
+    manifest = {"zarr_python": zarr.__version__, "cases": cases}
+    (OUTPUT_DIR / "manifest.json").write_text(json.dumps(manifest, indent=2))
+    print(f"Wrote {len(cases)} cases to {OUTPUT_DIR}")
+
+
+if __name__ == "__main__":
+    main()